@@ -1,5 +1,5 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use audio_extractor::{Args, AudioExtractor, AudioFormat};
+use audio_extractor::{AudioExtractor, AudioFormat, ExtractionOptions};
 use tempfile::{tempdir, NamedTempFile};
 use std::fs;
 use std::path::PathBuf;
@@ -41,22 +41,19 @@ fn benchmark_audio_extraction(c: &mut Criterion) {
     let sizes = vec![1, 10, 100]; // 1KB, 10KB, 100KB
     
     for size in sizes {
-        group.bench_function(&format!("extract_{}kb", size), |b| {
+        group.bench_function(format!("extract_{}kb", size), |b| {
             b.iter(|| {
                 let temp_input = create_test_video_file(size);
                 let temp_dir = tempdir().unwrap();
                 let output_path = temp_dir.path().join("output.mp3");
                 
-                let args = Args {
-                    input: temp_input.path().to_path_buf(),
-                    output: output_path,
-                    format: Some(AudioFormat::Mp3),
-                    quality: Some(128),
-                    verify: false, // Skip verification for speed
-                };
+                let options = ExtractionOptions::new(temp_input.path(), output_path)
+                    .format(AudioFormat::Mp3)
+                    .bitrate(128)
+                    .verify(false); // Skip verification for speed
                 
-                let extractor = AudioExtractor::new(args);
-                black_box(extractor.extract().unwrap());
+                let extractor = AudioExtractor::new(options);
+                black_box(extractor.extract()).unwrap();
             });
         });
     }
@@ -71,19 +68,16 @@ fn benchmark_validation(c: &mut Criterion) {
     let temp_dir = tempdir().unwrap();
     let output_path = temp_dir.path().join("output.mp3");
     
-    let args = Args {
-        input: temp_input.path().to_path_buf(),
-        output: output_path,
-        format: Some(AudioFormat::Mp3),
-        quality: Some(128),
-        verify: false, // Skip verification for speed
-    };
+    let options = ExtractionOptions::new(temp_input.path(), output_path)
+        .format(AudioFormat::Mp3)
+        .bitrate(128)
+        .verify(false); // Skip verification for speed
     
-    let extractor = AudioExtractor::new(args);
+    let extractor = AudioExtractor::new(options);
     
     group.bench_function("validate_input", |b| {
         b.iter(|| {
-            black_box(extractor.validate_input().unwrap());
+            black_box(extractor.validate_input()).unwrap();
         });
     });
     
@@ -94,15 +88,12 @@ fn benchmark_format_detection(c: &mut Criterion) {
     let mut group = c.benchmark_group("format_detection");
     
     let temp_dir = tempdir().unwrap();
-    let args = Args {
-        input: temp_dir.path().join("test.mp4"),
-        output: temp_dir.path().join("output.mp3"),
-        format: Some(AudioFormat::Mp3),
-        quality: Some(128),
-        verify: false, // Skip verification for speed
-    };
+    let options = ExtractionOptions::new(temp_dir.path().join("test.mp4"), temp_dir.path().join("output.mp3"))
+        .format(AudioFormat::Mp3)
+        .bitrate(128)
+        .verify(false); // Skip verification for speed
     
-    let extractor = AudioExtractor::new(args);
+    let extractor = AudioExtractor::new(options);
     
     let test_files = vec![
         PathBuf::from("test.mp4"),
@@ -135,22 +126,19 @@ fn benchmark_different_formats(c: &mut Criterion) {
     ];
     
     for format in formats {
-        group.bench_function(&format!("extract_{:?}", format), |b| {
+        group.bench_function(format!("extract_{:?}", format), |b| {
             b.iter(|| {
                 let temp_input = create_test_video_file(10);
                 let temp_dir = tempdir().unwrap();
                 let output_path = temp_dir.path().join(format!("output.{}", format));
                 
-                let args = Args {
-                    input: temp_input.path().to_path_buf(),
-                    output: output_path,
-                    format: Some(format.clone()),
-                    quality: Some(128),
-                    verify: false, // Skip verification for speed
-                };
+                let options = ExtractionOptions::new(temp_input.path(), output_path)
+                    .format(format.clone())
+                    .bitrate(128)
+                    .verify(false); // Skip verification for speed
                 
-                let extractor = AudioExtractor::new(args);
-                black_box(extractor.extract().unwrap());
+                let extractor = AudioExtractor::new(options);
+                black_box(extractor.extract()).unwrap();
             });
         });
     }
@@ -164,22 +152,19 @@ fn benchmark_different_qualities(c: &mut Criterion) {
     let qualities = vec![64, 128, 192, 256, 320];
     
     for quality in qualities {
-        group.bench_function(&format!("extract_{}kbps", quality), |b| {
+        group.bench_function(format!("extract_{}kbps", quality), |b| {
             b.iter(|| {
                 let temp_input = create_test_video_file(10);
                 let temp_dir = tempdir().unwrap();
                 let output_path = temp_dir.path().join("output.mp3");
                 
-                let args = Args {
-                    input: temp_input.path().to_path_buf(),
-                    output: output_path,
-                    format: Some(AudioFormat::Mp3),
-                    quality: Some(quality),
-                    verify: false, // Skip verification for speed
-                };
+                let options = ExtractionOptions::new(temp_input.path(), output_path)
+                    .format(AudioFormat::Mp3)
+                    .bitrate(quality)
+                    .verify(false); // Skip verification for speed
                 
-                let extractor = AudioExtractor::new(args);
-                black_box(extractor.extract().unwrap());
+                let extractor = AudioExtractor::new(options);
+                black_box(extractor.extract()).unwrap();
             });
         });
     }
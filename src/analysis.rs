@@ -0,0 +1,627 @@
+use anyhow::{Context, Result};
+use crate::isolation;
+use crate::spectrum::SourceQuality;
+use crate::AudioFormat;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Length of each window whisper listens to when identifying the language
+pub const LANGUAGE_WINDOW_SECONDS: f64 = 30.0;
+
+/// Upper bound on the number of windows sampled across one file
+const MAX_LANGUAGE_WINDOWS: usize = 5;
+
+/// Sample rate whisper models expect
+pub const ANALYSIS_SAMPLE_RATE: u32 = 16_000;
+
+/// Distinguishes scratch files of analyses running in the same process
+static SCRATCH_SEQ: AtomicUsize = AtomicUsize::new(0);
+
+/// Results of the optional analysis passes, written next to the output as JSON
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct AnalysisReport {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub languages: Option<Vec<DetectedLanguage>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diarization: Option<Diarization>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub segments: Option<Vec<Segment>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub music: Option<MusicAnalysis>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_quality: Option<SourceQuality>,
+    /// Results of registered `Analyzer`s, by name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub custom: BTreeMap<String, serde_json::Value>,
+}
+
+impl AnalysisReport {
+    /// Sidecar path for an output, e.g. `talk.mp3.analysis.json`
+    pub fn sidecar_path(output: &Path) -> PathBuf {
+        let mut name = output.as_os_str().to_os_string();
+        name.push(".analysis.json");
+        PathBuf::from(name)
+    }
+
+    pub fn load(output: &Path) -> Result<Self> {
+        let path = Self::sidecar_path(output);
+        let content = std::fs::read_to_string(&path).with_context(|| format!("Failed to read analysis report {:?}", path))?;
+        serde_json::from_str(&content).with_context(|| format!("Invalid analysis report {:?}", path))
+    }
+
+    pub fn write(&self, output: &Path) -> Result<PathBuf> {
+        let path = Self::sidecar_path(output);
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(&path, json).with_context(|| format!("Failed to write analysis report {:?}", path))?;
+        Ok(path)
+    }
+
+    /// Metadata tags to embed in an output of this format
+    pub fn tags(&self, format: &AudioFormat) -> Vec<(String, String)> {
+        let mut tags = Vec::new();
+        if let Some(languages) = self.languages.as_ref().filter(|l| !l.is_empty()) {
+            let codes: Vec<_> = languages.iter().map(|l| l.language.as_str()).collect();
+            tags.push(("language".to_string(), codes.join(",")));
+        }
+        if let Some(music) = &self.music {
+            // ID3 frames for MP3, the common Vorbis comment/iTunes names elsewhere
+            let (bpm, key) = match format {
+                AudioFormat::Mp3 => ("TBPM", "TKEY"),
+                _ => ("BPM", "INITIALKEY"),
+            };
+            tags.push((bpm.to_string(), format!("{:.0}", music.bpm)));
+            tags.push((key.to_string(), music.key.tag()));
+        }
+        tags
+    }
+}
+
+/// A spoken language and how much of the sampled audio it was detected in
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DetectedLanguage {
+    /// ISO 639-1 code as reported by whisper, e.g. `en`
+    pub language: String,
+    /// Mean whisper probability over the windows detected as this language
+    pub confidence: f64,
+    /// Fraction of sampled windows detected as this language
+    pub share: f64,
+}
+
+/// Identify the spoken language(s) with whisper.cpp's language detection.
+///
+/// Up to a handful of windows spread over the file are classified separately,
+/// so recordings that switch language report every language heard, most
+/// frequent first.
+pub fn detect_languages(audio: &ScratchWav, model: &Path) -> Result<Vec<DetectedLanguage>> {
+    let mut detections = Vec::new();
+    for offset in language_windows(audio.duration()?) {
+        detections.push(detect_window(audio.path(), model, offset)?);
+    }
+    Ok(summarize_languages(&detections))
+}
+
+/// Start offsets, in seconds, of the windows sampled from audio of this length
+pub fn language_windows(duration: f64) -> Vec<f64> {
+    let count = ((duration / LANGUAGE_WINDOW_SECONDS).ceil() as usize).clamp(1, MAX_LANGUAGE_WINDOWS);
+    let span = (duration - LANGUAGE_WINDOW_SECONDS).max(0.0);
+    if count == 1 {
+        return vec![0.0];
+    }
+    (0..count).map(|i| span * i as f64 / (count - 1) as f64).collect()
+}
+
+/// Combine per-window `(language, probability)` detections, most frequent first
+pub fn summarize_languages(detections: &[(String, f64)]) -> Vec<DetectedLanguage> {
+    let mut languages: Vec<(String, usize, f64)> = Vec::new();
+    for (language, probability) in detections {
+        match languages.iter_mut().find(|(l, _, _)| l == language) {
+            Some(entry) => {
+                entry.1 += 1;
+                entry.2 += probability;
+            }
+            None => languages.push((language.clone(), 1, *probability)),
+        }
+    }
+    languages.sort_by(|a, b| b.1.cmp(&a.1).then(b.2.total_cmp(&a.2)));
+
+    languages
+        .into_iter()
+        .map(|(language, windows, total)| DetectedLanguage {
+            language,
+            confidence: total / windows as f64,
+            share: windows as f64 / detections.len() as f64,
+        })
+        .collect()
+}
+
+/// Parse whisper.cpp's `auto-detected language: en (p = 0.987654)` line
+pub fn parse_whisper_language(output: &str) -> Option<(String, f64)> {
+    let line = output.lines().find(|line| line.contains("auto-detected language:"))?;
+    let rest = line.split("auto-detected language:").nth(1)?.trim();
+    let (language, probability) = rest.split_once(' ')?;
+    let probability = probability
+        .trim()
+        .trim_start_matches('(')
+        .trim_end_matches(')')
+        .trim_start_matches("p =")
+        .trim()
+        .parse()
+        .ok()?;
+    Some((language.to_string(), probability))
+}
+
+fn detect_window(wav: &Path, model: &Path, offset: f64) -> Result<(String, f64)> {
+    let output = Command::new("whisper-cli")
+        .arg("--model").arg(model)
+        .arg("--file").arg(wav)
+        .arg("--language").arg("auto")
+        .arg("--detect-language")
+        .arg("--offset-t").arg(((offset * 1000.0) as u64).to_string())
+        .arg("--duration").arg(((LANGUAGE_WINDOW_SECONDS * 1000.0) as u64).to_string())
+        .output()
+        .context("Failed to run whisper-cli; is whisper.cpp installed?")?;
+    if !output.status.success() {
+        anyhow::bail!("whisper-cli failed: {}", String::from_utf8_lossy(&output.stderr));
+    }
+
+    // whisper.cpp logs the detection to stderr; accept stdout too for wrappers
+    let text = format!("{}{}", String::from_utf8_lossy(&output.stderr), String::from_utf8_lossy(&output.stdout));
+    parse_whisper_language(&text).context("whisper-cli did not report a detected language")
+}
+
+/// Who spoke when, summarized for indexing
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Diarization {
+    pub speakers: usize,
+    /// Per-speaker totals, most talkative first
+    pub speaking_time: Vec<SpeakerTime>,
+    pub segments: Vec<SpeakerSegment>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SpeakerTime {
+    pub speaker: String,
+    pub seconds: f64,
+    /// Share of the total speaking time, 0-100
+    pub percent: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SpeakerSegment {
+    pub speaker: String,
+    pub start: f64,
+    pub end: f64,
+}
+
+/// Run an external diarizer and summarize its output.
+///
+/// `command` is split on whitespace and invoked with the path of a 16 kHz mono
+/// WAV appended; it must print RTTM (as pyannote and most diarization tools
+/// do) to stdout.
+pub fn diarize(audio: &ScratchWav, command: &str) -> Result<Diarization> {
+    let mut parts = command.split_whitespace();
+    let program = parts.next().context("Diarization command is empty")?;
+    let output = Command::new(program)
+        .args(parts)
+        .arg(audio.path())
+        .output()
+        .with_context(|| format!("Failed to run diarizer {:?}", program))?;
+    if !output.status.success() {
+        anyhow::bail!("Diarizer failed: {}", String::from_utf8_lossy(&output.stderr));
+    }
+    Ok(summarize_speakers(parse_rttm(&String::from_utf8_lossy(&output.stdout))?))
+}
+
+/// Parse the `SPEAKER` lines of an RTTM file into segments ordered by start time
+pub fn parse_rttm(rttm: &str) -> Result<Vec<SpeakerSegment>> {
+    let mut segments = Vec::new();
+    for line in rttm.lines().map(str::trim).filter(|line| line.starts_with("SPEAKER")) {
+        // SPEAKER <file> <channel> <start> <duration> <NA> <NA> <speaker> <NA> <NA>
+        let fields: Vec<_> = line.split_whitespace().collect();
+        if fields.len() < 8 {
+            anyhow::bail!("Invalid RTTM line: {}", line);
+        }
+        let start: f64 = fields[3].parse().with_context(|| format!("Invalid RTTM start: {}", line))?;
+        let duration: f64 = fields[4].parse().with_context(|| format!("Invalid RTTM duration: {}", line))?;
+        segments.push(SpeakerSegment { speaker: fields[7].to_string(), start, end: start + duration });
+    }
+    segments.sort_by(|a, b| a.start.total_cmp(&b.start));
+    Ok(segments)
+}
+
+/// Count speakers and their speaking time
+pub fn summarize_speakers(segments: Vec<SpeakerSegment>) -> Diarization {
+    let mut totals: Vec<(String, f64)> = Vec::new();
+    for segment in &segments {
+        let seconds = segment.end - segment.start;
+        match totals.iter_mut().find(|(speaker, _)| *speaker == segment.speaker) {
+            Some(entry) => entry.1 += seconds,
+            None => totals.push((segment.speaker.clone(), seconds)),
+        }
+    }
+    totals.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let total: f64 = totals.iter().map(|(_, seconds)| seconds).sum();
+    Diarization {
+        speakers: totals.len(),
+        speaking_time: totals
+            .into_iter()
+            .map(|(speaker, seconds)| SpeakerTime {
+                speaker,
+                seconds,
+                percent: if total > 0.0 { seconds / total * 100.0 } else { 0.0 },
+            })
+            .collect(),
+        segments,
+    }
+}
+
+/// Content type of a region of audio
+#[derive(Clone, Copy, ValueEnum, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SegmentLabel {
+    Music,
+    Speech,
+    Silence,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Segment {
+    pub label: SegmentLabel,
+    pub start: f64,
+    pub end: f64,
+}
+
+/// Length of the windows that are classified individually
+const CLASSIFY_WINDOW_SECONDS: f64 = 1.0;
+
+/// Length of the frames whose energies are compared within a window
+const CLASSIFY_FRAME_SECONDS: f64 = 0.02;
+
+/// Windows quieter than this are silence
+const SILENCE_THRESHOLD_DB: f64 = -45.0;
+
+/// Windows where more of the frames than this are well below the window's mean
+/// energy are speech: speech pauses between syllables, music rarely does
+const SPEECH_LOW_ENERGY_RATIO: f64 = 0.3;
+
+/// Regions shorter than this are folded into the preceding region
+const MIN_SEGMENT_SECONDS: f64 = 2.0;
+
+/// Label the audio as music, speech or silence over time
+pub fn classify(audio: &ScratchWav) -> Result<Vec<Segment>> {
+    Ok(classify_samples(&audio.samples()?, ANALYSIS_SAMPLE_RATE))
+}
+
+/// Classify mono 16-bit samples into contiguous labelled regions.
+///
+/// Each one-second window is labelled from its loudness and the share of
+/// low-energy frames in it; neighbouring windows with the same label are then
+/// merged and regions too short to be meaningful are absorbed by their
+/// predecessor.
+pub fn classify_samples(samples: &[i16], sample_rate: u32) -> Vec<Segment> {
+    let frame_len = ((sample_rate as f64 * CLASSIFY_FRAME_SECONDS) as usize).max(1);
+    let window_len = (sample_rate as f64 * CLASSIFY_WINDOW_SECONDS) as usize;
+
+    let mut segments: Vec<Segment> = Vec::new();
+    for (i, window) in samples.chunks(window_len.max(1)).enumerate() {
+        let start = i as f64 * CLASSIFY_WINDOW_SECONDS;
+        let end = start + window.len() as f64 / sample_rate as f64;
+        push_merged(&mut segments, Segment { label: classify_window(window, frame_len), start, end });
+    }
+
+    // Fold short regions into their predecessor, then merge again
+    let mut smoothed: Vec<Segment> = Vec::new();
+    for segment in segments {
+        let label = match smoothed.last() {
+            Some(previous) if segment.end - segment.start < MIN_SEGMENT_SECONDS => previous.label,
+            _ => segment.label,
+        };
+        push_merged(&mut smoothed, Segment { label, ..segment });
+    }
+    smoothed
+}
+
+fn classify_window(window: &[i16], frame_len: usize) -> SegmentLabel {
+    let energies: Vec<f64> = window
+        .chunks(frame_len)
+        .map(|frame| {
+            let sum: f64 = frame.iter().map(|&s| (s as f64 / i16::MAX as f64).powi(2)).sum();
+            (sum / frame.len() as f64).sqrt()
+        })
+        .collect();
+    let mean = energies.iter().sum::<f64>() / energies.len() as f64;
+
+    if mean <= 0.0 || 20.0 * mean.log10() < SILENCE_THRESHOLD_DB {
+        return SegmentLabel::Silence;
+    }
+
+    let low_energy = energies.iter().filter(|&&e| e < 0.5 * mean).count();
+    if low_energy as f64 / energies.len() as f64 > SPEECH_LOW_ENERGY_RATIO {
+        SegmentLabel::Speech
+    } else {
+        SegmentLabel::Music
+    }
+}
+
+fn push_merged(segments: &mut Vec<Segment>, segment: Segment) {
+    match segments.last_mut() {
+        Some(last) if last.label == segment.label => last.end = segment.end,
+        _ => segments.push(segment),
+    }
+}
+
+/// Filters keeping only the regions with one of `labels`, joined back to back
+pub fn select_filter(segments: &[Segment], labels: &[SegmentLabel]) -> Option<String> {
+    let ranges: Vec<_> = segments
+        .iter()
+        .filter(|segment| labels.contains(&segment.label))
+        .map(|segment| format!("between(t,{:.3},{:.3})", segment.start, segment.end))
+        .collect();
+    if ranges.is_empty() {
+        return None;
+    }
+    Some(format!("aselect='{}',asetpts=N/SR/TB", ranges.join("+")))
+}
+
+/// Tempo and key of a music extraction
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MusicAnalysis {
+    pub bpm: f64,
+    pub key: MusicalKey,
+    /// Correlation of the audio's pitch profile with the key's profile, -1 to 1
+    pub key_confidence: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    Major,
+    Minor,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MusicalKey {
+    pub tonic: String,
+    pub mode: Mode,
+}
+
+impl MusicalKey {
+    /// Key in the `TKEY`/`INITIALKEY` notation, e.g. `F#m`
+    pub fn tag(&self) -> String {
+        match self.mode {
+            Mode::Major => self.tonic.clone(),
+            Mode::Minor => format!("{}m", self.tonic),
+        }
+    }
+}
+
+impl std::fmt::Display for MusicalKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mode = match self.mode {
+            Mode::Major => "major",
+            Mode::Minor => "minor",
+        };
+        write!(f, "{} {}", self.tonic, mode)
+    }
+}
+
+const PITCH_CLASSES: [&str; 12] = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
+
+/// Krumhansl-Kessler key profiles, starting at the tonic
+const MAJOR_PROFILE: [f64; 12] = [6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88];
+const MINOR_PROFILE: [f64; 12] = [6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17];
+
+/// Tempo range considered, in beats per minute
+const MIN_BPM: f64 = 60.0;
+const MAX_BPM: f64 = 200.0;
+
+/// Frame and hop of the onset envelope used for tempo estimation
+const ONSET_FRAME: usize = 1024;
+const ONSET_HOP: usize = 256;
+
+/// Frame analysed for pitch content once per `CHROMA_HOP_SECONDS`
+const CHROMA_FRAME: usize = 4096;
+const CHROMA_HOP_SECONDS: f64 = 0.5;
+
+/// Estimate the tempo and key of the audio
+pub fn analyze_music(audio: &ScratchWav) -> Result<MusicAnalysis> {
+    let samples = audio.samples()?;
+    let bpm = estimate_bpm(&samples, ANALYSIS_SAMPLE_RATE)
+        .context("Could not estimate a tempo; is the audio long enough?")?;
+    let (key, key_confidence) = estimate_key(&samples, ANALYSIS_SAMPLE_RATE)
+        .context("Could not estimate a key; the audio has no pitched content")?;
+    Ok(MusicAnalysis { bpm, key, key_confidence })
+}
+
+/// Tempo from the autocorrelation of an energy-based onset envelope
+pub fn estimate_bpm(samples: &[i16], sample_rate: u32) -> Option<f64> {
+    if samples.len() < ONSET_FRAME {
+        return None;
+    }
+
+    let energies: Vec<f64> = (0..=(samples.len() - ONSET_FRAME) / ONSET_HOP)
+        .map(|i| {
+            let frame = &samples[i * ONSET_HOP..i * ONSET_HOP + ONSET_FRAME];
+            let energy: f64 = frame.iter().map(|&s| (s as f64 / i16::MAX as f64).powi(2)).sum();
+            (1e-9 + energy).ln()
+        })
+        .collect();
+    // Rises in energy mark onsets
+    let onsets: Vec<f64> = energies.windows(2).map(|w| (w[1] - w[0]).max(0.0)).collect();
+
+    let envelope_rate = sample_rate as f64 / ONSET_HOP as f64;
+    let min_lag = (envelope_rate * 60.0 / MAX_BPM).floor() as usize;
+    let max_lag = ((envelope_rate * 60.0 / MIN_BPM).ceil() as usize).min(onsets.len().checked_sub(1)?);
+    if min_lag == 0 || min_lag >= max_lag {
+        return None;
+    }
+
+    let correlation = |lag: usize| -> f64 {
+        onsets.iter().zip(&onsets[lag..]).map(|(a, b)| a * b).sum::<f64>() / (onsets.len() - lag) as f64
+    };
+    let scores: Vec<f64> = (min_lag..=max_lag).map(correlation).collect();
+    let (best, &peak) = scores.iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1))?;
+    if peak <= 0.0 {
+        return None;
+    }
+
+    // Parabolic interpolation around the peak for sub-frame precision
+    let offset = match (best.checked_sub(1).map(|i| scores[i]), scores.get(best + 1)) {
+        (Some(left), Some(&right)) => {
+            let denominator = left - 2.0 * peak + right;
+            if denominator != 0.0 { 0.5 * (left - right) / denominator } else { 0.0 }
+        }
+        _ => 0.0,
+    };
+    let lag = (min_lag + best) as f64 + offset;
+    Some(60.0 * envelope_rate / lag)
+}
+
+/// Key from a chromagram matched against the Krumhansl-Kessler profiles,
+/// with the Pearson correlation of the best match
+pub fn estimate_key(samples: &[i16], sample_rate: u32) -> Option<(MusicalKey, f64)> {
+    let hop = (sample_rate as f64 * CHROMA_HOP_SECONDS) as usize;
+    let mut chroma = [0.0f64; 12];
+
+    let mut start = 0;
+    while start + CHROMA_FRAME <= samples.len() {
+        let frame = &samples[start..start + CHROMA_FRAME];
+        // C2 (MIDI 36) to B6 (MIDI 95)
+        for note in 36..96 {
+            let frequency = 440.0 * 2f64.powf((note as f64 - 69.0) / 12.0);
+            chroma[note % 12] += goertzel_power(frame, frequency, sample_rate);
+        }
+        start += hop;
+    }
+    if chroma.iter().all(|&c| c <= 0.0) {
+        return None;
+    }
+
+    let mut best: Option<(MusicalKey, f64)> = None;
+    for (mode, profile) in [(Mode::Major, &MAJOR_PROFILE), (Mode::Minor, &MINOR_PROFILE)] {
+        for tonic in 0..12 {
+            let rotated: Vec<f64> = (0..12).map(|i| chroma[(tonic + i) % 12]).collect();
+            let score = pearson(&rotated, profile);
+            if best.as_ref().is_none_or(|(_, s)| score > *s) {
+                best = Some((MusicalKey { tonic: PITCH_CLASSES[tonic].to_string(), mode }, score));
+            }
+        }
+    }
+    best
+}
+
+fn goertzel_power(frame: &[i16], frequency: f64, sample_rate: u32) -> f64 {
+    let coefficient = 2.0 * (2.0 * std::f64::consts::PI * frequency / sample_rate as f64).cos();
+    let (mut s1, mut s2) = (0.0, 0.0);
+    for &sample in frame {
+        let s0 = sample as f64 / i16::MAX as f64 + coefficient * s1 - s2;
+        s2 = s1;
+        s1 = s0;
+    }
+    s1 * s1 + s2 * s2 - coefficient * s1 * s2
+}
+
+fn pearson(a: &[f64], b: &[f64]) -> f64 {
+    let mean_a = a.iter().sum::<f64>() / a.len() as f64;
+    let mean_b = b.iter().sum::<f64>() / b.len() as f64;
+    let covariance: f64 = a.iter().zip(b).map(|(x, y)| (x - mean_a) * (y - mean_b)).sum();
+    let variance_a: f64 = a.iter().map(|x| (x - mean_a).powi(2)).sum();
+    let variance_b: f64 = b.iter().map(|y| (y - mean_b).powi(2)).sum();
+    covariance / (variance_a * variance_b).sqrt()
+}
+
+/// Embed metadata tags by remuxing the output without re-encoding, passing
+/// `muxer_args` (such as the ID3 version) through to the muxer
+pub fn write_tags(output: &Path, tags: &[(String, String)], muxer_args: &[&str]) -> Result<()> {
+    if tags.is_empty() {
+        return Ok(());
+    }
+
+    // Keep the extension so FFmpeg picks the same muxer
+    let mut name = OsString::from(".tagging.");
+    name.push(output.file_name().context("Output has no file name")?);
+    let temp = output.with_file_name(name);
+
+    let mut cmd = isolation::ffmpeg();
+    cmd.arg("-v").arg("error").arg("-y")
+        .arg("-i").arg(output)
+        .arg("-map").arg("0")
+        .arg("-c").arg("copy");
+    for (key, value) in tags {
+        cmd.arg("-metadata").arg(format!("{}={}", key, value));
+    }
+    cmd.args(muxer_args);
+    let result = cmd.arg(&temp).output().context("Failed to run ffmpeg")?;
+    if !result.status.success() {
+        let _ = std::fs::remove_file(&temp);
+        anyhow::bail!("Failed to tag {:?}: {}", output, String::from_utf8_lossy(&result.stderr));
+    }
+
+    std::fs::rename(&temp, output).with_context(|| format!("Failed to replace {:?}", output))
+}
+
+/// 16 kHz mono PCM copy of the audio shared by the analysis passes,
+/// removed when dropped
+pub struct ScratchWav {
+    path: PathBuf,
+}
+
+impl ScratchWav {
+    pub fn decode(audio: &Path) -> Result<Self> {
+        let path = std::env::temp_dir().join(format!(
+            "audio_extractor-analysis-{}-{}.wav",
+            std::process::id(),
+            SCRATCH_SEQ.fetch_add(1, Ordering::SeqCst)
+        ));
+        let scratch = Self { path };
+
+        let output = isolation::ffmpeg()
+            .arg("-v").arg("error").arg("-y")
+            .arg("-i").arg(audio)
+            .arg("-ac").arg("1")
+            .arg("-ar").arg(ANALYSIS_SAMPLE_RATE.to_string())
+            .arg("-c:a").arg("pcm_s16le")
+            .arg("-map_metadata").arg("-1")
+            .arg("-fflags").arg("+bitexact")
+            .arg(&scratch.path)
+            .output()
+            .context("Failed to run ffmpeg")?;
+        if !output.status.success() {
+            anyhow::bail!("Failed to decode {:?} for analysis: {}", audio, String::from_utf8_lossy(&output.stderr));
+        }
+        Ok(scratch)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The decoded samples
+    pub fn samples(&self) -> Result<Vec<i16>> {
+        let data = std::fs::read(&self.path).context("Failed to read decoded audio")?;
+        Ok(data
+            .get(44..)
+            .unwrap_or_default()
+            .chunks_exact(2)
+            .map(|pair| i16::from_le_bytes([pair[0], pair[1]]))
+            .collect())
+    }
+
+    /// Duration in seconds, from the size of the bit-exact 44-byte-header WAV
+    pub fn duration(&self) -> Result<f64> {
+        let size = std::fs::metadata(&self.path)?.len();
+        Ok(size.saturating_sub(44) as f64 / (ANALYSIS_SAMPLE_RATE as f64 * 2.0))
+    }
+}
+
+impl Drop for ScratchWav {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
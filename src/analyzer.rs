@@ -0,0 +1,36 @@
+use crate::metadata::SourceMetadata;
+use anyhow::Result;
+use std::path::Path;
+
+/// What an analyzer gets to look at for one extracted file
+pub struct AnalysisInput<'a> {
+    /// The file the audio was extracted from
+    pub source: &'a Path,
+    /// The extracted audio file
+    pub output: &'a Path,
+    /// The output decoded to mono 16-bit PCM
+    pub samples: &'a [i16],
+    pub sample_rate: u32,
+    /// Tags of the source, if ffprobe could read them
+    pub metadata: Option<&'a SourceMetadata>,
+}
+
+impl AnalysisInput<'_> {
+    /// Duration of the decoded audio in seconds
+    pub fn duration(&self) -> f64 {
+        self.samples.len() as f64 / self.sample_rate as f64
+    }
+}
+
+/// A custom analysis run on every extracted file, e.g. house loudness rules
+/// or profanity detection.
+///
+/// Register one with `AudioExtractor::with_analyzer`. Its result is stored
+/// under its name in the `custom` section of the `.analysis.json` sidecar,
+/// and so ends up in `--report` batch reports as well.
+pub trait Analyzer: Send + Sync {
+    /// Key the result is stored under; should be unique among registered analyzers
+    fn name(&self) -> &str;
+
+    fn analyze(&self, input: &AnalysisInput) -> Result<serde_json::Value>;
+}
@@ -0,0 +1,247 @@
+use crate::limits::ResourceLimits;
+use crate::progress::{ProgressEvent, ProgressParser, StallAction};
+use crate::{say, stalled, supervisor, warning, AudioExtractor, AudioFormat, Backend, CacheEntry, ExtractorError, ProgressFn};
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+use tokio::process::Child;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tokio::time::Instant;
+
+/// A progress callback an async extraction can hand between threads
+type SharedProgress = Arc<dyn Fn(&ProgressEvent) + Send + Sync>;
+
+/// The encode an async extraction awaits: the FFmpeg command, the cache
+/// entry its output goes under and the seconds of audio it will write
+type PreparedEncode = (Command, Option<CacheEntry>, Option<f64>);
+
+impl AudioExtractor {
+    /// `extract` for async code, returning the output path. It has to be
+    /// called within a tokio runtime.
+    ///
+    /// FFmpeg runs as a `tokio::process` child that is awaited, so no thread
+    /// is held for the length of the encode; the steps around it, which are
+    /// short probes and file writes, run on tokio's blocking pool. Options
+    /// that chain several processes or record the whole run (--chunked,
+    /// --two-pass, --encrypt-to, --hybrid, the native backend, --audit-log
+    /// and --forensic-report) run the whole extraction on the blocking pool.
+    pub async fn extract_async(self) -> Result<PathBuf, ExtractorError> {
+        self.run_async(Arc::new(|_: &ProgressEvent| {})).await
+    }
+
+    /// `extract_async`, sending each event `extract_with_progress` would pass
+    /// to its callback on `progress`. The extraction carries on if the
+    /// receiver is dropped.
+    pub async fn extract_async_with_progress(self, progress: UnboundedSender<ProgressEvent>) -> Result<PathBuf, ExtractorError> {
+        self.run_async(Arc::new(move |event: &ProgressEvent| {
+            let _ = progress.send(event.clone());
+        }))
+        .await
+    }
+
+    /// `extract_batch_with_progress` for async code: up to `jobs` inputs are
+    /// extracted at once, each as `extract_async` does, and `progress` gets
+    /// each input's events with the input's index. Results are in input order
+    /// however the jobs finish. Outputs are verified as they are written.
+    pub async fn extract_batch_async<P: AsRef<Path>>(
+        inputs: Vec<P>,
+        output_dir: P,
+        format: AudioFormat,
+        quality: u32,
+        verify: bool,
+        jobs: usize,
+        progress: Option<UnboundedSender<(usize, ProgressEvent)>>,
+    ) -> Result<Vec<Result<PathBuf, ExtractorError>>, ExtractorError> {
+        let inputs: Vec<PathBuf> = inputs.iter().map(|input| input.as_ref().to_path_buf()).collect();
+        let output_dir = output_dir.as_ref().to_path_buf();
+        // Rules and output templates may read config.toml and probe the inputs
+        let extractors = tokio::task::spawn_blocking(move || Self::batch_extractors(inputs, output_dir, format, quality, verify))
+            .await
+            .map_err(|e| anyhow::anyhow!("Batch preparation failed: {}", e))??;
+
+        let slots = Arc::new(Semaphore::new(jobs.max(1)));
+        let mut tasks = JoinSet::new();
+        let count = extractors.len();
+        for (index, extractor) in extractors.into_iter().enumerate() {
+            let slots = slots.clone();
+            let progress = progress.clone();
+            tasks.spawn(async move {
+                // The semaphore is never closed
+                let _slot = slots.acquire_owned().await;
+                let report = move |event: &ProgressEvent| {
+                    if let Some(progress) = &progress {
+                        let _ = progress.send((index, event.clone()));
+                    }
+                };
+                (index, match extractor {
+                    Ok(extractor) => extractor.run_async(Arc::new(report)).await,
+                    Err(e) => Err(e),
+                })
+            });
+        }
+
+        let mut results: Vec<Option<Result<PathBuf, ExtractorError>>> = (0..count).map(|_| None).collect();
+        while let Some(finished) = tasks.join_next().await {
+            let (index, result) = finished.map_err(|e| anyhow::anyhow!("Extraction task failed: {}", e))?;
+            results[index] = Some(result);
+        }
+        Ok(results.into_iter().map(|result| result.expect("every input's task finished")).collect())
+    }
+
+    async fn run_async(self, progress: SharedProgress) -> Result<PathBuf, ExtractorError> {
+        let output = self.args.output.clone();
+        let extractor = Arc::new(self);
+        let prepared = blocking(&extractor, &progress, |extractor, progress| extractor.start_async(progress)).await?;
+        let Some((cmd, cached, duration)) = prepared else {
+            return Ok(output);
+        };
+
+        let mut result = extractor.run_ffmpeg_async(&cmd, duration, &*progress).await;
+        if extractor.args.on_stall == StallAction::Retry && stalled(&result) {
+            warning!("⚠ Retrying the stalled FFmpeg command");
+            result = extractor.run_ffmpeg_async(&cmd, duration, &*progress).await;
+        }
+        let result = result.context("Failed to execute FFmpeg command")?;
+        if !result.status.success() {
+            return Err(ExtractorError::ffmpeg_failed(&result));
+        }
+
+        blocking(&extractor, &progress, move |extractor, progress| {
+            extractor.store_cached(cached);
+            extractor.finish_extraction(progress)
+        })
+        .await?;
+        Ok(output)
+    }
+
+    /// The blocking steps of an async extraction up to the encode. Returns
+    /// the encode to await, or `None` when the extraction is already done,
+    /// because the output came from the cache or the encode cannot be awaited.
+    fn start_async(&self, progress: &ProgressFn) -> Result<Option<PreparedEncode>> {
+        if !self.encode_is_awaitable()? {
+            self.extract_with_progress(progress)?;
+            return Ok(None);
+        }
+        self.start_extraction(progress)?;
+        self.prepare_output()?;
+        let Some((cmd, cached)) = self.prepare_ffmpeg(Some(progress))? else {
+            self.finish_extraction(progress)?;
+            return Ok(None);
+        };
+        say!("Running FFmpeg command...");
+        let duration = self.get_video_info().ok().map(|info| self.expected_duration(info.duration));
+        Ok(Some((cmd, cached, duration)))
+    }
+
+    /// Whether the encode is a single FFmpeg run that can be awaited
+    fn encode_is_awaitable(&self) -> Result<bool> {
+        Ok(self.backend() == Backend::Ffmpeg
+            && self.is_ffmpeg_available()
+            && self.args.chunked.is_none()
+            && !self.args.two_pass
+            && self.encryption().is_none()
+            && self.hybrid_bitrate()?.is_none()
+            && self.args.audit_log.is_none()
+            && self.args.forensic_report.is_none())
+    }
+
+    /// Run the FFmpeg command as a tokio child under the job's limits,
+    /// tracked like any other FFmpeg process
+    async fn run_ffmpeg_async(&self, cmd: &Command, duration: Option<f64>, progress: &ProgressFn<'_>) -> Result<Output> {
+        let limits = self.resource_limits();
+        let mut child = limits.spawn_async(Self::with_progress_output(cmd)).await?;
+        let pid = child.id().context("FFmpeg exited before it could be tracked")?;
+
+        let output = self.wait_async(&mut child, &limits, duration, progress).await;
+        supervisor::unregister(pid);
+        output
+    }
+
+    /// Pass FFmpeg's progress on to `progress` until it exits, killing it
+    /// past --timeout and handling stalls as --on-stall says
+    async fn wait_async(&self, child: &mut Child, limits: &ResourceLimits, duration: Option<f64>, progress: &ProgressFn<'_>) -> Result<Output> {
+        let stdout = child.stdout.take().context("FFmpeg progress output is not piped")?;
+        let mut stderr = child.stderr.take().context("FFmpeg error output is not piped")?;
+        let stderr = tokio::spawn(async move {
+            let mut buf = Vec::new();
+            stderr.read_to_end(&mut buf).await.map(|_| buf)
+        });
+
+        let deadline = limits.wall_time.map(|limit| Instant::now() + limit);
+        let stall = self.args.stall_timeout.map(Duration::from_secs);
+        let mut lines = BufReader::new(stdout).lines();
+        let mut parser = ProgressParser::new(duration);
+        let mut seconds_done = -1.0;
+        let mut last_advance = Instant::now();
+        let mut reported = false;
+        loop {
+            let stall_at = stall.filter(|_| !reported).map(|stall| last_advance + stall);
+            let line = match [deadline, stall_at].into_iter().flatten().min() {
+                Some(wake) => tokio::time::timeout_at(wake, lines.next_line()).await,
+                None => Ok(lines.next_line().await),
+            };
+            match line {
+                Ok(Ok(Some(line))) => {
+                    let Some(event) = parser.line(&line) else {
+                        continue;
+                    };
+                    if let ProgressEvent::Encoding { seconds_done: done, .. } = event {
+                        if done > seconds_done {
+                            seconds_done = done;
+                            last_advance = Instant::now();
+                            reported = false;
+                        }
+                    }
+                    progress(&event);
+                }
+                // FFmpeg closed its output
+                Ok(_) => break,
+                Err(_) if deadline.is_some_and(|deadline| Instant::now() >= deadline) => {
+                    let _ = child.kill().await;
+                    anyhow::bail!("Process exceeded the time limit of {} seconds", limits.wall_time.unwrap_or_default().as_secs());
+                }
+                Err(_) => {
+                    reported = true;
+                    let seconds = self.args.stall_timeout.unwrap_or_default();
+                    progress(&ProgressEvent::Stalled { seconds });
+                    warning!("⚠ FFmpeg has made no progress for {} seconds", seconds);
+                    if self.args.on_stall != StallAction::Warn {
+                        let _ = child.kill().await;
+                        return Err(ExtractorError::Stalled { seconds }.into());
+                    }
+                }
+            }
+        }
+
+        let status = match deadline {
+            Some(deadline) => match tokio::time::timeout_at(deadline, child.wait()).await {
+                Ok(status) => status?,
+                Err(_) => {
+                    let _ = child.kill().await;
+                    anyhow::bail!("Process exceeded the time limit of {} seconds", limits.wall_time.unwrap_or_default().as_secs());
+                }
+            },
+            None => child.wait().await?,
+        };
+        let stderr = stderr.await.context("Reader task panicked")?.context("Failed to read process stderr")?;
+        Ok(Output { status, stdout: Vec::new(), stderr })
+    }
+}
+
+/// Run a blocking step of an async extraction on tokio's blocking pool
+async fn blocking<T, F>(extractor: &Arc<AudioExtractor>, progress: &SharedProgress, run: F) -> Result<T, ExtractorError>
+where
+    T: Send + 'static,
+    F: FnOnce(&AudioExtractor, &ProgressFn) -> Result<T> + Send + 'static,
+{
+    let (extractor, progress) = (extractor.clone(), progress.clone());
+    tokio::task::spawn_blocking(move || run(&extractor, &*progress))
+        .await
+        .map_err(|e| anyhow::anyhow!("Extraction task failed: {}", e))?
+        .map_err(ExtractorError::from)
+}
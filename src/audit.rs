@@ -0,0 +1,72 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// One line of the audit log: who ran what, when, with which settings, and how it ended
+#[derive(Serialize, Debug, Clone)]
+pub struct AuditRecord {
+    pub timestamp: String,
+    pub user: String,
+    pub host: String,
+    pub operation: String,
+    pub input: PathBuf,
+    pub output: PathBuf,
+    pub settings: serde_json::Value,
+    pub success: bool,
+    pub error: Option<String>,
+    pub duration_ms: u128,
+}
+
+/// Append-only JSON-lines audit log.
+///
+/// Records are only ever appended and each one is synced to disk before
+/// `append` returns, so an interrupted run cannot lose or truncate earlier entries.
+#[derive(Debug, Clone)]
+pub struct AuditLog {
+    path: PathBuf,
+}
+
+impl AuditLog {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self { path: path.as_ref().to_path_buf() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn append(&self, record: &AuditRecord) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent).context("Failed to create audit log directory")?;
+        }
+
+        let mut line = serde_json::to_vec(record).context("Failed to serialize audit record")?;
+        line.push(b'\n');
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open audit log {:?}", self.path))?;
+        file.write_all(&line).context("Failed to write audit record")?;
+        file.sync_data().context("Failed to sync audit log")?;
+        Ok(())
+    }
+}
+
+/// Name of the user running the process
+pub fn current_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+/// Host name of the machine running the process
+pub fn hostname() -> String {
+    std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .or_else(|_| std::fs::read_to_string("/etc/hostname").map(|name| name.trim().to_string()))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
@@ -0,0 +1,144 @@
+use crate::claims::OutputClaims;
+use crate::email::BatchSummary;
+use crate::rules::{self, Rule};
+use crate::verifier::Verifier;
+use crate::webhook::{self, Webhook};
+use crate::{jobs, say, template, throttle, warning, Args, AudioExtractor, AudioFormat, ExtractorError, ProgressEvent, ProgressFn};
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+
+impl AudioExtractor {
+    /// Batch processing support
+    pub fn extract_batch<P: AsRef<std::path::Path>>(
+        inputs: Vec<P>,
+        output_dir: P,
+        format: AudioFormat,
+        quality: u32,
+        verify: bool,
+    ) -> Result<Vec<Result<PathBuf, ExtractorError>>, ExtractorError> {
+        Self::extract_batch_with_progress(inputs, output_dir, format, quality, verify, 1, |_, _| {})
+    }
+
+    /// Batch processing on `jobs` threads. `progress` gets each input's
+    /// events with the input's index, from whichever thread extracts it.
+    /// Results are in input order however the jobs finish.
+    pub fn extract_batch_with_progress<P, F>(
+        inputs: Vec<P>,
+        output_dir: P,
+        format: AudioFormat,
+        quality: u32,
+        verify: bool,
+        jobs: usize,
+        progress: F,
+    ) -> Result<Vec<Result<PathBuf, ExtractorError>>, ExtractorError>
+    where
+        P: AsRef<std::path::Path>,
+        F: Fn(usize, &ProgressEvent) + Sync,
+    {
+        let extractors = Self::batch_extractors(inputs, output_dir, format, quality, verify)?;
+        let verifier = Verifier::default();
+        let throttles = Self::load_config().map(|config| config.throttle).unwrap_or_default();
+        let allowed = || throttle::jobs_at(&throttles, chrono::Local::now().time(), jobs);
+        let mut results = jobs::run_throttled(jobs, extractors.into_iter(), allowed, |index, extractor| {
+            extractor?.extract_for_batch(index, &verifier, Some(&|event: &ProgressEvent| progress(index, event)))
+        });
+
+        // Flattened into the message, so the failure reads as the verification's
+        for (index, outcome) in verifier.finish() {
+            if let Err(e) = outcome {
+                results[index] = Err(anyhow::anyhow!("Verification failed: {:#}", e));
+            }
+        }
+
+        Ok(results.into_iter().map(|result| result.map_err(ExtractorError::from)).collect())
+    }
+
+    /// An extractor for each input of a batch, in input order, with the
+    /// config.toml rules applied and the outputs claimed
+    pub(crate) fn batch_extractors<P: AsRef<std::path::Path>>(
+        inputs: Vec<P>,
+        output_dir: P,
+        format: AudioFormat,
+        quality: u32,
+        verify: bool,
+    ) -> Result<Vec<Result<AudioExtractor, ExtractorError>>> {
+        let claims = OutputClaims::new();
+        let rules = Self::rules();
+
+        // Outputs are claimed up front, so names do not depend on which job finishes first
+        let mut extractors = Vec::with_capacity(inputs.len());
+        for input in &inputs {
+            let input_path = input.as_ref();
+            let stem = input_path.file_stem()
+                .context("Failed to get file stem")?;
+
+            let mut args = Args {
+                input: input_path.to_path_buf(),
+                format: Some(format.clone()),
+                quality: Some(quality),
+                verify,
+                ..Default::default()
+            };
+            if let Some(rule) = rules::first_match(&rules, input_path) {
+                rule.apply(&mut args);
+            }
+
+            // An output directory with fields is a template naming each output
+            args.output = if template::has_fields(output_dir.as_ref()) {
+                output_dir.as_ref().to_path_buf()
+            } else {
+                output_dir.as_ref().join(format!("{}.{}", stem.to_string_lossy(), args.format.as_ref().unwrap()))
+            };
+            let extractor = AudioExtractor::new(args).with_expanded_output().map(|mut extractor| {
+                extractor.args.output = claims.claim(input_path, extractor.args.output);
+                extractor
+            });
+            extractors.push(extractor);
+        }
+        Ok(extractors)
+    }
+
+    /// Extract one input of a batch, reporting to `progress` if given.
+    /// Verification is handed to `verifier`, so the next input can be
+    /// extracted meanwhile; its outcome comes from `Verifier::finish`.
+    pub(crate) fn extract_for_batch(mut self, index: usize, verifier: &Verifier, progress: Option<&ProgressFn>) -> Result<PathBuf> {
+        let verify = std::mem::replace(&mut self.args.verify, false);
+        match progress {
+            Some(progress) => self.extract_with_progress(progress)?,
+            None => self.extract()?,
+        }
+        let output = self.args.output.clone();
+        if verify {
+            self.args.verify = true;
+            verifier.submit(index, self);
+        }
+        Ok(output)
+    }
+
+    /// Announce a finished batch by email and on the webhooks in config.toml.
+    /// The batch is over either way, so failures are only reported.
+    pub fn report_batch(summary: &BatchSummary) {
+        let Some(config) = Self::load_config() else {
+            return;
+        };
+        webhook::batch_finished(&config.webhooks, summary);
+        let Some(email) = config.email else {
+            return;
+        };
+        match email.send(summary) {
+            Ok(true) => say!("📧 Summary mailed to {}", email.to.join(", ")),
+            Ok(false) => {}
+            Err(e) => warning!("⚠ {:#}", e),
+        }
+    }
+
+    /// Webhooks from config.toml, for announcing failures as they happen
+    pub fn webhooks() -> Vec<Webhook> {
+        Self::load_config().map(|config| config.webhooks).unwrap_or_default()
+    }
+
+    /// Pattern rules from config.toml, applied to each input of a batch
+    pub fn rules() -> Vec<Rule> {
+        Self::load_config().map(|config| config.rules).unwrap_or_default()
+    }
+}
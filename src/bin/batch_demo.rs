@@ -6,7 +6,7 @@ fn main() -> Result<()> {
     println!("🎵 Audio Extractor - Batch Processing Demo");
     println!("==========================================");
     
-    let input_files = vec![
+    let input_files = [
         "sample-15s.mp4", 
     ];
     
@@ -36,9 +36,9 @@ fn main() -> Result<()> {
     
     for (format, quality) in formats {
         println!("\n🎯 Processing format: {} ({}k)", format, quality);
-        println!("{}{}{}",
+        println!("{} {} {}",
             "=".repeat(20),
-            format!(" {} ", format),
+            format,
             "=".repeat(20)
         );
         
@@ -48,7 +48,7 @@ fn main() -> Result<()> {
         }
         
         let results = AudioExtractor::extract_batch(
-            existing_files.iter().map(|s| Path::new(s)).collect(),
+            existing_files.iter().map(Path::new).collect(),
             &format_dir,
             format,
             quality,
@@ -32,6 +32,7 @@ fn main() -> Result<()> {
         format: Some(AudioFormat::Mp3),
         quality: Some(128),
         verify: true,
+        ..Default::default()
     };
     
     let extractor = AudioExtractor::new(args);
@@ -61,6 +62,7 @@ fn main() -> Result<()> {
             format: Some(format),
             quality: Some(quality),
             verify: false, // Skip verification to speed up demo
+            ..Default::default()
         };
         
         let extractor = AudioExtractor::new(args);
@@ -90,6 +92,7 @@ fn main() -> Result<()> {
             format: Some(AudioFormat::Mp3),
             quality: Some(quality),
             verify: false,
+            ..Default::default()
         };
         
         let extractor = AudioExtractor::new(args);
@@ -115,6 +118,7 @@ fn main() -> Result<()> {
         format: Some(AudioFormat::Mp3),
         quality: Some(192),
         verify: true,
+        ..Default::default()
     };
     
     let extractor = AudioExtractor::new(args);
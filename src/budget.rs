@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// A size limit on all the outputs of a scan together, from --batch-budget.
+///
+/// Each input's share is worked out just before it is extracted, in
+/// proportion to its duration among the inputs still to come. What an
+/// output actually takes is settled afterwards, so the inputs after one
+/// that came in over or under its share get less or more.
+#[derive(Debug)]
+pub struct SizeBudget {
+    total: u64,
+    state: Mutex<BudgetState>,
+}
+
+#[derive(Debug)]
+struct BudgetState {
+    /// Bytes not yet reserved or spent
+    remaining: u64,
+    spent: u64,
+    /// Seconds of audio of each input still to be extracted
+    pending: HashMap<PathBuf, f64>,
+}
+
+impl SizeBudget {
+    /// A budget of `total` bytes for inputs with these durations in seconds
+    pub fn new(total: u64, durations: HashMap<PathBuf, f64>) -> Self {
+        Self {
+            total,
+            state: Mutex::new(BudgetState { remaining: total, spent: 0, pending: durations }),
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Bytes the settled outputs took
+    pub fn spent(&self) -> u64 {
+        self.state.lock().unwrap().spent
+    }
+
+    /// Reserve `input`'s share of what is left: its part of the pending
+    /// seconds. Returns the bytes and the input's seconds, or `None` if the
+    /// input was not planned.
+    pub fn reserve(&self, input: &Path) -> Option<(u64, f64)> {
+        let mut state = self.state.lock().unwrap();
+        let pending_seconds: f64 = state.pending.values().sum();
+        let seconds = state.pending.remove(input)?;
+        let share = if pending_seconds > 0.0 {
+            (state.remaining as f64 * seconds / pending_seconds) as u64
+        } else {
+            0
+        };
+        state.remaining -= share;
+        Some((share, seconds))
+    }
+
+    /// Leave out an input that will not be extracted, such as one a hook
+    /// skipped, so its share goes to the rest
+    pub fn forgo(&self, input: &Path) {
+        self.state.lock().unwrap().pending.remove(input);
+    }
+
+    /// Record that an output given `reserved` bytes took `actual`
+    pub fn settle(&self, reserved: u64, actual: u64) {
+        let mut state = self.state.lock().unwrap();
+        state.spent += actual;
+        state.remaining = (state.remaining + reserved).saturating_sub(actual);
+    }
+}
@@ -0,0 +1,131 @@
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::ffi::OsStr;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Content-addressed store of previous extraction outputs.
+///
+/// Entries are keyed by the SHA-256 of the input file together with every
+/// setting that influences the encoded output, so an unchanged input extracted
+/// with unchanged settings is restored from the cache instead of re-encoded.
+/// Least recently used entries are evicted once the cache exceeds `max_bytes`.
+#[derive(Debug, Clone)]
+pub struct ResultCache {
+    pub dir: PathBuf,
+    pub max_bytes: Option<u64>,
+}
+
+impl ResultCache {
+    pub fn new(dir: PathBuf, max_bytes: Option<u64>) -> Self {
+        Self { dir, max_bytes }
+    }
+
+    /// Build a cache key from the input contents and the extraction settings
+    pub fn key<I, S>(input: &Path, settings: I) -> Result<String>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let mut hasher = Sha256::new();
+        hasher.update(hash_file(input)?.as_bytes());
+        for setting in settings {
+            hasher.update(setting.as_ref().as_encoded_bytes());
+            hasher.update([0]);
+        }
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+
+    fn entry_path(&self, key: &str, extension: Option<&OsStr>) -> PathBuf {
+        let mut path = self.dir.join(key);
+        if let Some(extension) = extension {
+            path.set_extension(extension);
+        }
+        path
+    }
+
+    /// Restore a cached output to `output`, returning whether there was a hit
+    pub fn restore(&self, key: &str, output: &Path) -> Result<bool> {
+        let entry = self.entry_path(key, output.extension());
+        if !entry.is_file() {
+            return Ok(false);
+        }
+
+        // A copy rather than a hard link, so rewriting the output in place
+        // later cannot change the entry. The old output is removed first in
+        // case it is a link to the entry left by an earlier version.
+        if output.exists() {
+            fs::remove_file(output).context("Failed to replace existing output file")?;
+        }
+        fs::copy(&entry, output).context("Failed to copy cached output")?;
+
+        // Refresh the modification time so eviction treats the entry as recently used
+        File::options()
+            .write(true)
+            .open(&entry)
+            .and_then(|f| f.set_modified(SystemTime::now()))
+            .context("Failed to update cache entry timestamp")?;
+
+        Ok(true)
+    }
+
+    /// Copy a freshly extracted output into the cache and enforce the size limit
+    pub fn store(&self, key: &str, output: &Path) -> Result<()> {
+        fs::create_dir_all(&self.dir).context("Failed to create cache directory")?;
+
+        let entry = self.entry_path(key, output.extension());
+        let partial = entry.with_extension("partial");
+        fs::copy(output, &partial).context("Failed to copy output into cache")?;
+        fs::rename(&partial, &entry).context("Failed to finalize cache entry")?;
+
+        self.evict()
+    }
+
+    /// Remove least recently used entries until the cache fits in `max_bytes`
+    pub fn evict(&self) -> Result<()> {
+        let Some(max_bytes) = self.max_bytes else {
+            return Ok(());
+        };
+
+        let mut entries = Vec::new();
+        let mut total = 0;
+        for entry in fs::read_dir(&self.dir).context("Failed to read cache directory")? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if !metadata.is_file() {
+                continue;
+            }
+            total += metadata.len();
+            entries.push((metadata.modified()?, metadata.len(), entry.path()));
+        }
+
+        entries.sort();
+        for (_, len, path) in entries {
+            if total <= max_bytes {
+                break;
+            }
+            fs::remove_file(&path)
+                .with_context(|| format!("Failed to evict cache entry {:?}", path))?;
+            total -= len;
+        }
+
+        Ok(())
+    }
+}
+
+/// SHA-256 of a file's contents as a lowercase hex string
+pub fn hash_file(path: &Path) -> Result<String> {
+    let mut file = File::open(path).with_context(|| format!("Failed to open {:?} for hashing", path))?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf).context("Failed to read file for hashing")?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
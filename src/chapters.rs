@@ -0,0 +1,77 @@
+use crate::limits::ResourceLimits;
+use crate::template::{self, NameProfile};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// A chapter marker of the source, as reported by ffprobe
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Chapter {
+    /// Position among the chapters, counting from 1
+    pub index: usize,
+    /// Seconds from the start of the source
+    pub start: f64,
+    pub end: f64,
+    pub title: Option<String>,
+}
+
+impl Chapter {
+    /// List the source's chapters with ffprobe. `input_args` are the
+    /// options and `-i` selecting the source.
+    pub fn probe(input_args: &[OsString], limits: &ResourceLimits) -> Result<Vec<Self>> {
+        let mut cmd = Command::new("ffprobe");
+        cmd.arg("-v").arg("error")
+            .arg("-print_format").arg("json")
+            .arg("-show_chapters")
+            .args(input_args);
+        let output = limits.output(&mut cmd).context("Failed to run ffprobe")?;
+        if !output.status.success() {
+            anyhow::bail!("ffprobe failed to list chapters: {}", String::from_utf8_lossy(&output.stderr).trim());
+        }
+        Self::from_ffprobe_json(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    /// The chapters in ffprobe's `-show_chapters` JSON, in order. Chapters
+    /// with no length are left out.
+    pub fn from_ffprobe_json(json: &str) -> Result<Vec<Self>> {
+        let probe: serde_json::Value = serde_json::from_str(json).context("Invalid ffprobe output")?;
+        let chapters = probe["chapters"].as_array().map(Vec::as_slice).unwrap_or_default();
+        let seconds = |value: &serde_json::Value| value.as_str().and_then(|s| s.parse::<f64>().ok());
+        Ok(chapters
+            .iter()
+            .filter_map(|chapter| {
+                let start = seconds(&chapter["start_time"])?;
+                let end = seconds(&chapter["end_time"]).filter(|end| *end > start)?;
+                let title = chapter["tags"]["title"].as_str()
+                    .map(str::trim)
+                    .filter(|title| !title.is_empty())
+                    .map(str::to_string);
+                Some((start, end, title))
+            })
+            .enumerate()
+            .map(|(position, (start, end, title))| Self { index: position + 1, start, end, title })
+            .collect())
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.end - self.start
+    }
+}
+
+/// Output for one chapter with `--split-chapters`:
+/// `<stem>.<NN>[.<chapter title>].<ext>`
+pub fn chapter_output(output: &Path, chapter: &Chapter) -> PathBuf {
+    let stem = output.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let mut name = format!("{}.{:02}", stem, chapter.index);
+    if let Some(title) = &chapter.title {
+        name.push('.');
+        name.push_str(&template::sanitize(title, NameProfile::Windows));
+    }
+    if let Some(ext) = output.extension() {
+        name.push('.');
+        name.push_str(&ext.to_string_lossy());
+    }
+    output.with_file_name(name)
+}
@@ -0,0 +1,374 @@
+use crate::timecode::{self, TimeRange};
+use crate::{isolation, say, verifier, AudioExtractor};
+use anyhow::{Context, Result};
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::DecoderOptions;
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+/// Chunk length for `--chunked` without a value, in minutes
+pub const DEFAULT_CHUNK_MINUTES: u32 = 30;
+
+/// Difference between a chunk's decoded length and its planned length that
+/// `--verify` accepts, in seconds; about one AAC frame at 8 kHz
+pub const DURATION_TOLERANCE: f64 = 0.15;
+
+/// Audio on each side of a join compared against the source, in seconds
+pub const JOIN_WINDOW_SECONDS: f64 = 0.25;
+
+/// How far around where it belongs a chunk edge is searched for, in seconds
+pub const MAX_LAG_SECONDS: f64 = 0.02;
+
+/// Lowest correlation between the chunk edges and the source that counts as
+/// the same audio
+pub const MIN_CORRELATION: f64 = 0.98;
+
+/// Split `duration` seconds of audio into consecutive chunks of at most
+/// `chunk_seconds`
+pub fn plan(duration: f64, chunk_seconds: f64) -> Vec<TimeRange> {
+    let mut chunks = Vec::new();
+    let mut start = 0.0;
+    while start < duration {
+        let end = (start + chunk_seconds).min(duration);
+        chunks.push(TimeRange { start, end });
+        start = end;
+    }
+    chunks
+}
+
+/// Folder next to the output holding the chunks of one extraction, removed
+/// when dropped. It lives on the output's filesystem rather than in the
+/// temp directory, which is often a small tmpfs.
+pub struct ChunkDir {
+    path: PathBuf,
+}
+
+impl ChunkDir {
+    pub fn create(output: &Path) -> Result<Self> {
+        let name = format!(".{}.chunks", output.file_name().context("Output has no file name")?.to_string_lossy());
+        let path = output.with_file_name(name);
+        fs::create_dir_all(&path).with_context(|| format!("Failed to create chunk folder {:?}", path))?;
+        Ok(Self { path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Path of the chunk at `index`, counting from 0
+    pub fn chunk(&self, index: usize) -> PathBuf {
+        self.path.join(format!("chunk_{:04}.flac", index + 1))
+    }
+
+    /// Write the list FFmpeg's concat demuxer reads the chunks from
+    pub fn write_list(&self, chunks: &[PathBuf]) -> Result<PathBuf> {
+        let list = self.path.join("chunks.txt");
+        fs::write(&list, concat_list(chunks)).with_context(|| format!("Failed to write {:?}", list))?;
+        Ok(list)
+    }
+}
+
+impl Drop for ChunkDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// Concat demuxer list naming each chunk, with quotes in paths escaped
+pub fn concat_list(chunks: &[PathBuf]) -> String {
+    chunks
+        .iter()
+        .map(|chunk| format!("file '{}'\n", chunk.to_string_lossy().replace('\'', "'\\''")))
+        .collect()
+}
+
+/// The first and last samples of a chunk, downmixed to mono
+#[derive(Debug, Clone)]
+pub struct ChunkEdges {
+    pub sample_rate: u32,
+    pub channels: usize,
+    pub head: Vec<f32>,
+    pub tail: Vec<f32>,
+}
+
+impl ChunkEdges {
+    /// Decode the chunk at `path`, keeping `seconds` of audio from each end
+    pub fn read(path: &Path, seconds: f64) -> Result<Self> {
+        let file = File::open(path).with_context(|| format!("Failed to open chunk {:?}", path))?;
+        let mss = MediaSourceStream::new(Box::new(file), Default::default());
+        let mut hint = Hint::new();
+        hint.with_extension("flac");
+        let mut reader = symphonia::default::get_probe()
+            .format(&hint, mss, &FormatOptions::default(), &MetadataOptions::default())
+            .with_context(|| format!("Failed to read chunk {:?}", path))?
+            .format;
+        let params = reader.default_track().context("Chunk has no audio")?.codec_params.clone();
+        let mut decoder = symphonia::default::get_codecs().make(&params, &DecoderOptions::default())?;
+
+        let mut edges = Self {
+            sample_rate: params.sample_rate.context("Chunk has no sample rate")?,
+            channels: params.channels.map_or(1, |channels| channels.count()),
+            head: Vec::new(),
+            tail: Vec::new(),
+        };
+        let keep = (seconds * edges.sample_rate as f64) as usize;
+        let mut tail = VecDeque::with_capacity(keep);
+        loop {
+            let packet = match reader.next_packet() {
+                Ok(packet) => packet,
+                Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e).with_context(|| format!("Failed to read chunk {:?}", path)),
+            };
+            let decoded = decoder.decode(&packet).with_context(|| format!("Failed to decode chunk {:?}", path))?;
+            let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, *decoded.spec());
+            buffer.copy_interleaved_ref(decoded);
+            for sample in downmix(buffer.samples(), edges.channels) {
+                if edges.head.len() < keep {
+                    edges.head.push(sample);
+                }
+                if tail.len() == keep {
+                    tail.pop_front();
+                }
+                tail.push_back(sample);
+            }
+        }
+        edges.tail = tail.into();
+        Ok(edges)
+    }
+}
+
+/// Average interleaved channels into one
+pub fn downmix(interleaved: &[f32], channels: usize) -> Vec<f32> {
+    interleaved
+        .chunks_exact(channels.max(1))
+        .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+        .collect()
+}
+
+/// How two chunks line up with the source around the point where they join
+#[derive(Debug, Clone, PartialEq)]
+pub struct JoinCheck {
+    /// Where the join is in the source, in seconds
+    pub boundary: f64,
+    /// Samples the earlier chunk's end is off from where it belongs, `None`
+    /// if it is too quiet to place
+    pub tail_lag: Option<isize>,
+    /// Samples the later chunk's start is off from where it belongs
+    pub head_lag: Option<isize>,
+    /// Lower of the two edges' correlation with the source
+    pub correlation: f64,
+}
+
+impl JoinCheck {
+    /// Place the end of one chunk (`tail`) and the start of the next
+    /// (`head`) in `reference`, the source decoded in one pass from
+    /// `max_lag` samples before the tail to `max_lag` samples after the head
+    pub fn new(boundary: f64, reference: &[f32], tail: &[f32], head: &[f32], max_lag: usize) -> Self {
+        let tail_match = align(reference, tail, max_lag, max_lag);
+        let head_match = align(reference, head, max_lag + tail.len(), max_lag);
+        let correlation = [tail_match, head_match]
+            .iter()
+            .flatten()
+            .map(|(_, correlation)| *correlation)
+            .fold(1.0, f64::min);
+        Self {
+            boundary,
+            tail_lag: tail_match.map(|(lag, _)| lag),
+            head_lag: head_match.map(|(lag, _)| lag),
+            correlation,
+        }
+    }
+
+    /// Samples lost at the join (positive) or played twice (negative). Both
+    /// edges are placed relative to each other, so an imprecise start of the
+    /// reference cancels out. Zero when either side is silent.
+    pub fn gap(&self) -> isize {
+        match (self.tail_lag, self.head_lag) {
+            (Some(tail), Some(head)) => head - tail,
+            _ => 0,
+        }
+    }
+
+    /// Whether the joined chunks play exactly like the source
+    pub fn exact(&self) -> bool {
+        self.gap() == 0 && self.correlation >= MIN_CORRELATION
+    }
+}
+
+impl fmt::Display for JoinCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let at = timecode::format_timestamp(self.boundary);
+        match self.gap() {
+            0 if self.correlation < MIN_CORRELATION => {
+                write!(f, "audio at {} differs from the source (correlation {:.3})", at, self.correlation)
+            }
+            0 => write!(f, "join at {} is sample-exact", at),
+            gap if gap > 0 => write!(f, "{} sample(s) missing at {}", gap, at),
+            gap => write!(f, "{} sample(s) repeated at {}", -gap, at),
+        }
+    }
+}
+
+/// Offset from `expected` at which `segment` best matches `reference`,
+/// searched up to `max_lag` samples either way, with its normalized
+/// correlation. `None` if the segment is silent.
+pub fn align(reference: &[f32], segment: &[f32], expected: usize, max_lag: usize) -> Option<(isize, f64)> {
+    let energy = |samples: &[f32]| samples.iter().map(|&s| s as f64 * s as f64).sum::<f64>();
+    let segment_energy = energy(segment);
+    if segment_energy < 1e-9 {
+        return None;
+    }
+    let max_lag = max_lag as isize;
+    (-max_lag..=max_lag)
+        .filter_map(|lag| {
+            let start = usize::try_from(expected as isize + lag).ok()?;
+            let window = reference.get(start..start + segment.len())?;
+            let window_energy = energy(window);
+            if window_energy < 1e-9 {
+                return None;
+            }
+            let dot: f64 = window.iter().zip(segment).map(|(&a, &b)| a as f64 * b as f64).sum();
+            Some((lag, dot / (window_energy * segment_energy).sqrt()))
+        })
+        // On a tie (e.g. a pure tone) the lag closest to zero wins
+        .max_by(|a, b| a.1.total_cmp(&b.1).then(b.0.abs().cmp(&a.0.abs())))
+}
+
+impl AudioExtractor {
+    /// With --chunked, extract the audio a chunk at a time into lossless
+    /// files next to the output, so no FFmpeg process or temp file has to
+    /// hold a whole 12-hour recording, then encode the joined chunks as one
+    /// stream. Returns false if the input fits in one chunk or its audio is
+    /// copied without re-encoding; the single pass is used then.
+    pub(crate) fn extract_chunked(&self, minutes: u32) -> Result<bool> {
+        if minutes == 0 {
+            anyhow::bail!("--chunked needs a chunk length above 0 minutes");
+        }
+        let duration = self.get_video_info()?.duration;
+        let chunks = plan(duration, minutes as f64 * 60.0);
+        if chunks.len() < 2 {
+            return Ok(false);
+        }
+        if self.stream_copy(&self.audio_graph()?)? {
+            say!("The audio is copied without re-encoding, so it is not chunked");
+            return Ok(false);
+        }
+
+        let dir = ChunkDir::create(&self.args.output)?;
+        let mut paths = Vec::new();
+        for (index, range) in chunks.iter().enumerate() {
+            say!("🧩 Chunk {}/{} ({})", index + 1, chunks.len(), range);
+            let path = dir.chunk(index);
+            let mut cmd = self.chunk_command(range, &path)?;
+            let output = self.resource_limits().output(&mut cmd)
+                .context("Failed to execute FFmpeg command")?;
+            if !output.status.success() {
+                anyhow::bail!("FFmpeg failed on chunk {} ({}): {}", index + 1, range, String::from_utf8_lossy(&output.stderr));
+            }
+            if self.args.verify {
+                Self::verify_chunk(&path, range)?;
+            }
+            paths.push(path);
+        }
+        if self.args.verify {
+            self.verify_joins(&chunks, &paths)?;
+        }
+
+        say!("🧩 Joining {} chunks into {:?}", paths.len(), self.args.output);
+        let list = dir.write_list(&paths)?;
+        let mut cmd = self.build_ffmpeg_command_from(Some(&list))?;
+        let output = self.resource_limits().output(&mut cmd)
+            .context("Failed to execute FFmpeg command")?;
+        if !output.status.success() {
+            anyhow::bail!("FFmpeg failed to join the chunks: {}", String::from_utf8_lossy(&output.stderr));
+        }
+        Ok(true)
+    }
+
+    /// FFmpeg command writing one --chunked range of the source as FLAC
+    fn chunk_command(&self, range: &TimeRange, path: &Path) -> Result<Command> {
+        let mut cmd = isolation::ffmpeg();
+        cmd.arg("-v").arg("error").arg("-y");
+        cmd.args(self.timestamp_repair().input_args());
+        // Input seeking decodes from the nearest keyframe and drops samples up to the exact start
+        cmd.arg("-ss").arg(format!("{:.6}", range.start));
+        cmd.arg("-t").arg(format!("{:.6}", range.duration()));
+        cmd.args(self.source_input_args()?);
+        if self.args.audio_track.is_some() {
+            cmd.arg("-map").arg(self.audio_stream()?);
+        }
+        cmd.arg("-vn").arg("-c:a").arg("flac").arg(path);
+        Ok(cmd)
+    }
+
+    /// Compare both sides of every join between chunks with the same stretch
+    /// decoded from the source in one pass, so the joined output cannot lose
+    /// or repeat samples where single-pass extraction would not
+    fn verify_joins(&self, chunks: &[TimeRange], paths: &[PathBuf]) -> Result<()> {
+        let mut previous = ChunkEdges::read(&paths[0], JOIN_WINDOW_SECONDS)?;
+        for index in 1..paths.len() {
+            let next = ChunkEdges::read(&paths[index], JOIN_WINDOW_SECONDS)?;
+            let rate = previous.sample_rate as f64;
+            let max_lag = (rate * MAX_LAG_SECONDS) as usize;
+            let before = (previous.tail.len() + max_lag) as f64 / rate;
+            let length = before + (next.head.len() + max_lag) as f64 / rate;
+            let boundary = chunks[index].start;
+            let reference = self.source_window(boundary - before, length, previous.channels)?;
+
+            let check = JoinCheck::new(boundary, &reference, &previous.tail, &next.head, max_lag);
+            if !check.exact() {
+                anyhow::bail!("Chunks {} and {} do not join cleanly: {}", index, index + 1, check);
+            }
+            previous = next;
+        }
+        say!("✓ All {} joins between chunks are sample-exact", paths.len() - 1);
+        Ok(())
+    }
+
+    /// `length` seconds of the source from `start`, decoded in one pass and
+    /// downmixed to mono
+    fn source_window(&self, start: f64, length: f64, channels: usize) -> Result<Vec<f32>> {
+        let mut cmd = isolation::ffmpeg();
+        cmd.arg("-v").arg("error");
+        cmd.args(self.timestamp_repair().input_args());
+        cmd.arg("-ss").arg(format!("{:.6}", start.max(0.0)));
+        cmd.arg("-t").arg(format!("{:.6}", length));
+        cmd.args(self.source_input_args()?);
+        if self.args.audio_track.is_some() {
+            cmd.arg("-map").arg(self.audio_stream()?);
+        }
+        cmd.arg("-vn").arg("-f").arg("f32le").arg("-c:a").arg("pcm_f32le").arg("-");
+        let output = self.resource_limits().output(&mut cmd)
+            .context("Failed to execute FFmpeg command")?;
+        if !output.status.success() {
+            anyhow::bail!("FFmpeg failed to decode the source at {}: {}", timecode::format_timestamp(start), String::from_utf8_lossy(&output.stderr));
+        }
+        let samples: Vec<f32> = output.stdout
+            .chunks_exact(4)
+            .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .collect();
+        Ok(downmix(&samples, channels))
+    }
+
+    /// Check that a chunk decodes and holds as much audio as was asked for
+    fn verify_chunk(path: &Path, range: &TimeRange) -> Result<()> {
+        let verification = verifier::FileVerification::check(path);
+        if let Some(error) = verification.error {
+            anyhow::bail!("Chunk {} failed verification: {}", range, error);
+        }
+        if let Some(duration) = verification.duration {
+            if (duration - range.duration()).abs() > DURATION_TOLERANCE {
+                anyhow::bail!("Chunk {} holds {:.3} seconds of audio instead of {:.3}", range, duration, range.duration());
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,63 @@
+use crate::warning;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Hex digits of the input path hash appended to colliding output names
+const SUFFIX_LEN: usize = 8;
+
+/// Output paths handed out during a batch, so two inputs never write the same file.
+///
+/// The first input to claim a path keeps it; any other input asking for the same
+/// path gets `<stem>_<hash>.<ext>`, where the hash is taken from its input path.
+/// The suffix therefore does not depend on which worker finishes first, and claims
+/// made in input order always name every output the same way.
+#[derive(Debug, Default)]
+pub struct OutputClaims {
+    claimed: Mutex<HashMap<PathBuf, PathBuf>>,
+}
+
+impl OutputClaims {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Claim `desired` for `input`, or a suffixed path if another input holds it.
+    /// Claiming again for the same input returns the same path.
+    pub fn claim(&self, input: &Path, desired: PathBuf) -> PathBuf {
+        let mut claimed = self.claimed.lock().unwrap();
+        if let Some(existing) = claimed.iter().find(|(_, owner)| owner.as_path() == input) {
+            return existing.0.clone();
+        }
+
+        let mut candidate = desired.clone();
+        let mut attempt = 0;
+        while claimed.contains_key(&candidate) {
+            attempt += 1;
+            candidate = suffixed(&desired, input, attempt);
+        }
+        if candidate != desired {
+            warning!("⚠ {:?} is already used by another input in this batch; writing {:?}", desired, candidate);
+        }
+        claimed.insert(candidate.clone(), input.to_path_buf());
+        candidate
+    }
+}
+
+/// `<stem>_<hash>.<ext>`; later attempts (only needed if two hashes collide)
+/// add a counter
+fn suffixed(path: &Path, input: &Path, attempt: u32) -> PathBuf {
+    let digest = Sha256::digest(input.to_string_lossy().as_bytes());
+    let hash: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+    let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let mut name = format!("{}_{}", stem, &hash[..SUFFIX_LEN]);
+    if attempt > 1 {
+        name.push_str(&format!("_{}", attempt));
+    }
+    if let Some(ext) = path.extension() {
+        name.push('.');
+        name.push_str(&ext.to_string_lossy());
+    }
+    path.with_file_name(name)
+}
@@ -0,0 +1,81 @@
+use crate::analysis::AnalysisReport;
+use crate::report::BatchReport;
+use crate::say;
+use crate::sidecar;
+use crate::trash::Disposal;
+use anyhow::Result;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Files a previous run wrote, as recorded in its `--report`, split into those
+/// that can be removed and those that have to be kept
+#[derive(Debug, Default, PartialEq)]
+pub struct CleanPlan {
+    pub remove: Vec<PathBuf>,
+    /// Outputs left alone, with the reason
+    pub keep: Vec<(PathBuf, String)>,
+}
+
+impl CleanPlan {
+    /// Plan removing the outputs of a run and the sidecars written next to
+    /// them. Outputs whose size no longer matches the report were changed
+    /// after the run and are kept unless `force` is set.
+    pub fn new(report: &BatchReport, force: bool) -> Self {
+        let mut plan = Self::default();
+        for entry in report.entries.iter().filter(|entry| entry.success) {
+            let Some(output) = &entry.output else {
+                continue;
+            };
+            let size = match fs::metadata(output) {
+                Ok(metadata) => metadata.len(),
+                Err(_) => {
+                    plan.keep.push((output.clone(), "already gone".to_string()));
+                    continue;
+                }
+            };
+            if !force {
+                if let Some(recorded) = entry.size.filter(|&recorded| recorded != size) {
+                    plan.keep.push((output.clone(), format!("changed since the run ({} bytes, was {})", size, recorded)));
+                    continue;
+                }
+            }
+
+            plan.remove.push(output.clone());
+            plan.remove.extend(sidecars(output).into_iter().filter(|path| path.exists()));
+        }
+        plan
+    }
+
+    /// Remove the planned files, or move them to the trash or a quarantine
+    /// folder, returning how many were removed
+    pub fn execute(&self, disposal: &Disposal) -> Result<usize> {
+        for path in &self.remove {
+            if let Some(moved) = disposal.dispose(path)? {
+                say!("🗑 {:?} → {:?}", path, moved);
+            }
+        }
+        Ok(self.remove.len())
+    }
+}
+
+impl fmt::Display for CleanPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for path in &self.remove {
+            writeln!(f, "  remove {:?}", path)?;
+        }
+        for (path, reason) in &self.keep {
+            writeln!(f, "  keep   {:?}: {}", path, reason)?;
+        }
+        write!(f, "{} file(s) to remove, {} output(s) kept", self.remove.len(), self.keep.len())
+    }
+}
+
+/// Files the extraction may have written next to `output`
+fn sidecars(output: &Path) -> Vec<PathBuf> {
+    vec![
+        AnalysisReport::sidecar_path(output),
+        sidecar::thumbnail_path(output),
+        sidecar::nfo_path(output),
+    ]
+}
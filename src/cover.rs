@@ -0,0 +1,132 @@
+use crate::isolation;
+use crate::limits::ResourceLimits;
+use crate::sidecar;
+use crate::AudioFormat;
+use anyhow::{Context, Result};
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// Where the cover art is taken from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CoverSource {
+    /// Picture attached to the source, by its stream index
+    Attached(u32),
+    /// Video frame at this many seconds
+    Frame(f64),
+}
+
+impl CoverSource {
+    /// The picture attached to the source, such as the thumbnail of a
+    /// downloaded video, unless `at` asks for a frame. Without either, the
+    /// frame a thumbnail would use.
+    pub fn choose(attached: Option<u32>, at: Option<f64>, duration: Option<f64>) -> Self {
+        match (attached, at) {
+            (Some(index), None) => CoverSource::Attached(index),
+            (_, at) => CoverSource::Frame(sidecar::thumbnail_time(at, duration)),
+        }
+    }
+}
+
+/// Stream index of the source's attached picture. `input_args` are the
+/// options and `-i` selecting the source.
+pub fn probe_attached(input_args: &[OsString], limits: &ResourceLimits) -> Result<Option<u32>> {
+    let mut cmd = Command::new("ffprobe");
+    cmd.arg("-v").arg("error")
+        .arg("-print_format").arg("json")
+        .arg("-show_streams")
+        .args(input_args);
+    let output = limits.output(&mut cmd).context("Failed to run ffprobe")?;
+    if !output.status.success() {
+        anyhow::bail!("ffprobe failed to list streams: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    attached_picture(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Index of the first stream ffprobe's `-show_streams` JSON marks as an attached picture
+pub fn attached_picture(json: &str) -> Result<Option<u32>> {
+    let probe: serde_json::Value = serde_json::from_str(json).context("Invalid ffprobe output")?;
+    let streams = probe["streams"].as_array().map(Vec::as_slice).unwrap_or_default();
+    Ok(streams
+        .iter()
+        .find(|stream| stream["codec_type"] == "video" && stream["disposition"]["attached_pic"].as_u64() == Some(1))
+        .and_then(|stream| stream["index"].as_u64())
+        .map(|index| index as u32))
+}
+
+/// Save the cover art to `path`, in the image format its extension names
+pub fn write_image(input_args: &[OsString], source: CoverSource, path: &Path, limits: &ResourceLimits) -> Result<()> {
+    let index = match source {
+        CoverSource::Frame(at) => return sidecar::write_thumbnail(input_args, at, path, limits),
+        CoverSource::Attached(index) => index,
+    };
+    let mut cmd = isolation::ffmpeg();
+    cmd.arg("-v").arg("error")
+        .args(input_args)
+        .arg("-map").arg(format!("0:{}", index))
+        .arg("-frames:v").arg("1")
+        .arg("-q:v").arg("2")
+        .arg("-y")
+        .arg(path);
+    let output = limits.output(&mut cmd).context("Failed to run ffmpeg")?;
+    if !output.status.success() || !path.exists() {
+        anyhow::bail!("Failed to extract the cover art: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(())
+}
+
+/// Fail unless `output` can hold cover art: MP3, FLAC, or AAC in an MP4
+/// container. Raw AAC, WAV, AIFF and Ogg have no place for a picture FFmpeg
+/// can write.
+pub fn check_format(format: &AudioFormat, output: &Path) -> Result<()> {
+    let is_mp4 = output.extension()
+        .is_some_and(|ext| ["m4a", "m4b", "mp4"].iter().any(|mp4| ext.eq_ignore_ascii_case(mp4)));
+    match format {
+        AudioFormat::Mp3 | AudioFormat::Flac => Ok(()),
+        AudioFormat::Aac if is_mp4 => Ok(()),
+        AudioFormat::Aac => anyhow::bail!("Raw AAC (ADTS) cannot hold cover art; use an .m4a output with --embed-cover"),
+        format => anyhow::bail!("--embed-cover needs MP3, FLAC or AAC output, not {}; use --cover-out to save the image", format),
+    }
+}
+
+/// FFmpeg options that copy the audio of input 0 and add input 1 as its
+/// front cover, keeping input 0's tags
+pub fn embed_args(format: &AudioFormat) -> Vec<&'static str> {
+    let mut args = vec![
+        "-map", "0:a", "-map", "1:v",
+        "-c", "copy",
+        "-map_metadata", "0",
+        "-disposition:v:0", "attached_pic",
+    ];
+    if *format == AudioFormat::Mp3 {
+        // The APIC frame's description and picture type
+        args.extend(["-metadata:s:v", "title=Album cover", "-metadata:s:v", "comment=Cover (front)"]);
+    }
+    args
+}
+
+/// Embed `image` as the cover art of `output` by remuxing it without
+/// re-encoding, passing `muxer_args` (such as the ID3 version) through to the muxer
+pub fn embed(output: &Path, image: &Path, format: &AudioFormat, muxer_args: &[&str], limits: &ResourceLimits) -> Result<()> {
+    check_format(format, output)?;
+
+    // Keep the extension so FFmpeg picks the same muxer
+    let mut name = OsString::from(".cover.");
+    name.push(output.file_name().context("Output has no file name")?);
+    let temp = output.with_file_name(name);
+
+    let mut cmd = isolation::ffmpeg();
+    cmd.arg("-v").arg("error").arg("-y")
+        .arg("-i").arg(output)
+        .arg("-i").arg(image)
+        .args(embed_args(format).iter().chain(muxer_args).map(OsStr::new))
+        .arg(&temp);
+    let result = limits.output(&mut cmd).context("Failed to run ffmpeg")?;
+    if !result.status.success() {
+        let _ = fs::remove_file(&temp);
+        anyhow::bail!("Failed to embed cover art in {:?}: {}", output, String::from_utf8_lossy(&result.stderr).trim());
+    }
+
+    fs::rename(&temp, output).with_context(|| format!("Failed to replace {:?}", output))
+}
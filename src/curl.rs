@@ -0,0 +1,53 @@
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// A curl invocation whose options are passed as a config file on stdin, so
+/// tokens and passwords never show up in the process list
+#[derive(Debug, Clone, Default)]
+pub struct Curl {
+    config: String,
+}
+
+impl Curl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a long option, e.g. `("header", "X-Token: ...")`
+    pub fn option(mut self, name: &str, value: &str) -> Self {
+        self.config.push_str(&format!("{} = {}\n", name, quote(value)));
+        self
+    }
+
+    /// Add a long option that takes no value, e.g. `ssl-reqd`
+    pub fn flag(mut self, name: &str) -> Self {
+        self.config.push_str(name);
+        self.config.push('\n');
+        self
+    }
+
+    /// Run curl, discarding the response body; fails on HTTP errors
+    pub fn run(&self, timeout_secs: u32) -> Result<()> {
+        let mut child = Command::new("curl")
+            .arg("--silent").arg("--show-error").arg("--fail")
+            .arg("--max-time").arg(timeout_secs.to_string())
+            .arg("--config").arg("-")
+            .arg("--output").arg(if cfg!(windows) { "NUL" } else { "/dev/null" })
+            .stdin(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to run curl")?;
+        child.stdin.take().unwrap().write_all(self.config.as_bytes())?;
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            anyhow::bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+        }
+        Ok(())
+    }
+}
+
+/// Quote a value for a curl config file
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+}
@@ -0,0 +1,224 @@
+use crate::limits::ResourceLimits;
+use crate::template;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Default `--title-template`: `wedding.flac` becomes `wedding_t01.flac`, ...
+pub const DEFAULT_TITLE_TEMPLATE: &str = "{name}_t{title}.{ext}";
+
+/// DVD titles are numbered from 1 and never exceed 99
+const MAX_DVD_TITLES: u32 = 99;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DiscKind {
+    Dvd,
+    Bluray,
+}
+
+/// A DVD or Blu-ray given as a `VIDEO_TS`/`BDMV` folder or an `.iso` image
+#[derive(Debug, Clone, PartialEq)]
+pub struct Disc {
+    /// Folder holding `VIDEO_TS`/`BDMV`, or the image file
+    pub path: PathBuf,
+    pub kind: DiscKind,
+}
+
+/// A DVD title or Blu-ray playlist
+#[derive(Debug, Clone, PartialEq)]
+pub struct Title {
+    pub index: u32,
+    /// Duration in seconds
+    pub duration: f64,
+}
+
+impl Disc {
+    /// Recognize a disc structure: a folder containing (or named) `VIDEO_TS` or
+    /// `BDMV`, or an `.iso` image. Images are assumed to be DVDs unless they
+    /// contain a Blu-ray index.
+    pub fn detect(path: &Path) -> Option<Disc> {
+        if path.is_dir() {
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("").to_uppercase();
+            let root = if name == "VIDEO_TS" || name == "BDMV" {
+                path.parent()?.to_path_buf()
+            } else {
+                path.to_path_buf()
+            };
+
+            if root.join("VIDEO_TS").is_dir() {
+                return Some(Disc { path: root, kind: DiscKind::Dvd });
+            }
+            if root.join("BDMV").is_dir() {
+                return Some(Disc { path: root, kind: DiscKind::Bluray });
+            }
+            return None;
+        }
+
+        let is_iso = path.extension().and_then(|e| e.to_str()).is_some_and(|e| e.eq_ignore_ascii_case("iso"));
+        if !is_iso {
+            return None;
+        }
+        let kind = if image_contains(path, b"INDEX.BDMV").unwrap_or(false) {
+            DiscKind::Bluray
+        } else {
+            DiscKind::Dvd
+        };
+        Some(Disc { path: path.to_path_buf(), kind })
+    }
+
+    /// FFmpeg input options and `-i` selecting a title, or the disc's default
+    /// (for Blu-ray, libbluray picks the longest playlist)
+    pub fn input_args(&self, title: Option<u32>) -> Vec<OsString> {
+        let mut args: Vec<OsString> = Vec::new();
+        match self.kind {
+            DiscKind::Dvd => {
+                args.extend(["-f".into(), "dvdvideo".into()]);
+                if let Some(title) = title {
+                    args.extend(["-title".into(), title.to_string().into()]);
+                }
+                args.extend(["-i".into(), self.path.clone().into()]);
+            }
+            DiscKind::Bluray => {
+                if let Some(title) = title {
+                    args.extend(["-playlist".into(), title.to_string().into()]);
+                }
+                let mut url = OsString::from("bluray:");
+                url.push(&self.path);
+                args.extend(["-i".into(), url]);
+            }
+        }
+        args
+    }
+
+    /// Probe every title with ffprobe, in index order
+    pub fn titles(&self, limits: &ResourceLimits) -> Result<Vec<Title>> {
+        let mut titles = Vec::new();
+        match self.kind {
+            DiscKind::Dvd => {
+                // Titles are contiguous, so stop at the first one that does not open
+                for index in 1..=MAX_DVD_TITLES {
+                    match self.probe_duration(index, limits)? {
+                        Some(duration) => titles.push(Title { index, duration }),
+                        None => break,
+                    }
+                }
+            }
+            DiscKind::Bluray => {
+                for index in self.playlists()? {
+                    if let Some(duration) = self.probe_duration(index, limits)? {
+                        titles.push(Title { index, duration });
+                    }
+                }
+            }
+        }
+        Ok(titles)
+    }
+
+    /// Playlist numbers from `BDMV/PLAYLIST/*.mpls`; empty for images
+    fn playlists(&self) -> Result<Vec<u32>> {
+        let dir = self.path.join("BDMV").join("PLAYLIST");
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+        let mut playlists: Vec<u32> = fs::read_dir(&dir)
+            .with_context(|| format!("Failed to read {:?}", dir))?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let path = entry.path();
+                let is_mpls = path.extension().and_then(|e| e.to_str()).is_some_and(|e| e.eq_ignore_ascii_case("mpls"));
+                if !is_mpls {
+                    return None;
+                }
+                path.file_stem()?.to_str()?.parse().ok()
+            })
+            .collect();
+        playlists.sort();
+        Ok(playlists)
+    }
+
+    fn probe_duration(&self, title: u32, limits: &ResourceLimits) -> Result<Option<f64>> {
+        let mut cmd = Command::new("ffprobe");
+        cmd.arg("-v").arg("error")
+            .args(self.input_args(Some(title)))
+            .arg("-show_entries").arg("format=duration")
+            .arg("-of").arg("csv=p=0");
+        let output = limits.output(&mut cmd).context("Failed to run ffprobe")?;
+        if !output.status.success() {
+            return Ok(None);
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().parse().ok())
+    }
+}
+
+/// Output path of one title when extracting all of them.
+///
+/// The template is expanded in the directory of `output`: `{name}` is the
+/// output's file stem, `{title}` the title index padded to two digits and
+/// `{ext}` the output's extension.
+pub fn title_output(output: &Path, template: &str, index: u32) -> Result<PathBuf> {
+    let name = output.file_stem().and_then(|s| s.to_str()).unwrap_or("title");
+    let ext = output.extension().and_then(|e| e.to_str()).unwrap_or("");
+    let fields = BTreeMap::from([
+        ("name", name.to_string()),
+        ("title", format!("{:02}", index)),
+        ("ext", ext.to_string()),
+    ]);
+    Ok(output.with_file_name(template::expand(template, &fields)?))
+}
+
+/// Outcome of one title in an `--all-titles` extraction
+#[derive(Serialize, Debug, Clone)]
+pub struct TitleResult {
+    pub index: u32,
+    pub duration: f64,
+    pub output: PathBuf,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+/// What was found on a disc and what became of each title
+#[derive(Serialize, Debug, Clone)]
+pub struct TitleReport {
+    pub disc: PathBuf,
+    pub kind: DiscKind,
+    pub titles: Vec<TitleResult>,
+}
+
+impl TitleReport {
+    /// Report path next to the outputs, e.g. `wedding.titles.json`, or
+    /// `titles.json` in the fixed part of a templated output path
+    pub fn path_for(output: &Path) -> PathBuf {
+        if template::has_fields(output) {
+            return template::static_prefix(output).join("titles.json");
+        }
+        output.with_extension("titles.json")
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json).with_context(|| format!("Failed to write title report {:?}", path))
+    }
+}
+
+/// The longest title, which on most discs is the main feature
+pub fn longest(titles: &[Title]) -> Option<&Title> {
+    titles.iter().max_by(|a, b| a.duration.total_cmp(&b.duration))
+}
+
+/// Whether the first directory sectors of an image mention `needle`
+fn image_contains(path: &Path, needle: &[u8]) -> Result<bool> {
+    use std::io::Read;
+
+    // The file system directories sit near the start of an image
+    let mut head = Vec::new();
+    fs::File::open(path)?.take(4 * 1024 * 1024).read_to_end(&mut head)?;
+    Ok(head.windows(needle.len()).any(|window| window == needle))
+}
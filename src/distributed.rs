@@ -0,0 +1,397 @@
+use crate::audit::hostname;
+use crate::claims::OutputClaims;
+use crate::status::StatusFile;
+use crate::webhook::{self, Webhook};
+use crate::{say, warning, Args, AudioExtractor, AudioFormat};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::ffi::OsStr;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Messages exchanged between coordinator and workers.
+///
+/// Each message is a single JSON line. `Job` and `JobResult` are followed by
+/// `size` raw bytes carrying the input video or the extracted audio.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Message {
+    RequestJob { worker: String },
+    Job {
+        id: usize,
+        file_name: String,
+        format: AudioFormat,
+        quality: u32,
+        verify: bool,
+        #[serde(default)]
+        allow_lossy_transcode: bool,
+        size: u64,
+    },
+    NoMoreJobs,
+    Progress { id: usize, message: String },
+    JobResult { id: usize, error: Option<String>, size: u64 },
+}
+
+fn send(stream: &mut impl Write, message: &Message, payload: Option<&[u8]>) -> Result<()> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    stream.write_all(&line)?;
+    if let Some(payload) = payload {
+        stream.write_all(payload)?;
+    }
+    stream.flush()?;
+    Ok(())
+}
+
+/// Read the next message; `None` means the peer closed the connection
+fn receive(reader: &mut impl BufRead) -> Result<Option<Message>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    let message = serde_json::from_str(&line).context("Received malformed message")?;
+    Ok(Some(message))
+}
+
+/// Copy the `size` bytes following a message into `into` a chunk at a time,
+/// so the size a peer claims never decides how much memory is allocated
+fn receive_payload(reader: &mut impl Read, size: u64, into: &mut impl Write) -> Result<()> {
+    let received = io::copy(&mut reader.take(size), into).context("Failed to receive transfer")?;
+    if received < size {
+        anyhow::bail!("Connection closed during transfer");
+    }
+    Ok(())
+}
+
+/// Distinguishes workers running in the same process
+static WORKER_SEQ: AtomicUsize = AtomicUsize::new(0);
+
+/// Batch settings the coordinator hands out with every job
+#[derive(Debug, Clone)]
+pub struct CoordinatorConfig {
+    pub listen: String,
+    pub output_dir: PathBuf,
+    pub format: AudioFormat,
+    pub quality: u32,
+    pub verify: bool,
+    pub allow_lossy_transcode: bool,
+    /// Listen on addresses other than loopback. Anyone who can reach the
+    /// port can take jobs and upload outputs, so only allow this on a
+    /// trusted network.
+    pub allow_remote: bool,
+}
+
+struct Job {
+    id: usize,
+    input: PathBuf,
+}
+
+struct BatchState {
+    pending: VecDeque<Job>,
+    status: StatusFile,
+    /// Output path of each job, claimed up front so names do not depend on completion order
+    outputs: Vec<PathBuf>,
+    results: Vec<Option<Result<PathBuf>>>,
+}
+
+impl BatchState {
+    fn is_complete(&self) -> bool {
+        self.results.iter().all(Option::is_some)
+    }
+}
+
+/// Read a batch manifest: one input path per line, blank lines and `#` comments ignored
+pub fn read_manifest(path: &Path) -> Result<Vec<PathBuf>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read manifest {:?}", path))?;
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(PathBuf::from)
+        .collect())
+}
+
+/// Publish `inputs` to workers connecting on `config.listen` and collect their outputs.
+///
+/// Returns once every job has produced a result, in the same order as `inputs`.
+/// Jobs held by a worker that disconnects are put back at the front of the queue.
+pub fn run_coordinator(config: &CoordinatorConfig, inputs: Vec<PathBuf>) -> Result<Vec<Result<PathBuf>>> {
+    if !config.allow_remote {
+        let mut addrs = config.listen.to_socket_addrs()
+            .with_context(|| format!("Failed to resolve {}", config.listen))?;
+        if let Some(addr) = addrs.find(|addr| !addr.ip().is_loopback()) {
+            anyhow::bail!("Refusing to listen on {} without --allow-remote; workers connect without authentication", addr);
+        }
+    }
+
+    fs::create_dir_all(&config.output_dir).context("Failed to create output directory")?;
+
+    let total = inputs.len();
+    let claims = OutputClaims::new();
+    let outputs = inputs
+        .iter()
+        .map(|input| Ok(claims.claim(input, output_path(input, config)?)))
+        .collect::<Result<Vec<_>>>()?;
+    let state = Arc::new(Mutex::new(BatchState {
+        status: StatusFile::new("coordinator", total),
+        pending: inputs.into_iter().enumerate().map(|(id, input)| Job { id, input }).collect(),
+        outputs,
+        results: (0..total).map(|_| None).collect(),
+    }));
+
+    let webhooks = Arc::new(AudioExtractor::webhooks());
+    let listener = TcpListener::bind(&config.listen)
+        .with_context(|| format!("Failed to listen on {}", config.listen))?;
+    listener.set_nonblocking(true)?;
+    say!("Coordinator listening on {} with {} job(s)", listener.local_addr()?, total);
+
+    while !state.lock().unwrap().is_complete() {
+        match listener.accept() {
+            Ok((stream, addr)) => {
+                say!("Worker connected from {}", addr);
+                stream.set_nonblocking(false)?;
+                let state = Arc::clone(&state);
+                let webhooks = Arc::clone(&webhooks);
+                let config = config.clone();
+                thread::spawn(move || {
+                    if let Err(e) = serve_worker(stream, &state, &config, &webhooks) {
+                        warning!("⚠ Worker {} disconnected: {}", addr, e);
+                    }
+                });
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                thread::sleep(Duration::from_millis(100));
+            }
+            Err(e) => return Err(e).context("Failed to accept worker connection"),
+        }
+    }
+
+    let mut state = state.lock().unwrap();
+    state.status.finish();
+    Ok(state.results.iter_mut().map(|r| r.take().unwrap()).collect())
+}
+
+fn serve_worker(stream: TcpStream, state: &Mutex<BatchState>, config: &CoordinatorConfig, webhooks: &[Webhook]) -> Result<()> {
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    let mut in_flight: Option<Job> = None;
+
+    let outcome = (|| -> Result<()> {
+        let mut worker_name = String::from("worker");
+        while let Some(message) = receive(&mut reader)? {
+            match message {
+                Message::RequestJob { worker } => {
+                    worker_name = worker;
+                    let (job, file_name, data) = loop {
+                        let job = state.lock().unwrap().pending.pop_front();
+                        let Some(job) = job else {
+                            send(&mut writer, &Message::NoMoreJobs, None)?;
+                            return Ok(());
+                        };
+
+                        // Inputs the coordinator cannot read fail here instead of being handed out
+                        match load_input(&job.input) {
+                            Ok((file_name, data)) => break (job, file_name, data),
+                            Err(e) => {
+                                say!("✗ job {} failed: {}", job.id, e);
+                                webhook::input_failed(webhooks, &job.input, &e);
+                                let mut state = state.lock().unwrap();
+                                state.status.done(&job.input, false);
+                                state.results[job.id] = Some(Err(e));
+                            }
+                        }
+                    };
+                    say!("→ {} ({}) assigned to {}", file_name, job.id, worker_name);
+                    state.lock().unwrap().status.started(&job.input);
+                    send(&mut writer, &Message::Job {
+                        id: job.id,
+                        file_name,
+                        format: config.format.clone(),
+                        quality: config.quality,
+                        verify: config.verify,
+                        allow_lossy_transcode: config.allow_lossy_transcode,
+                        size: data.len() as u64,
+                    }, Some(&data))?;
+                    in_flight = Some(job);
+                }
+                Message::Progress { id, message } => {
+                    say!("  [{}] job {}: {}", worker_name, id, message);
+                }
+                Message::JobResult { id, error, size } => {
+                    // The job stays in flight until its upload is in, so a broken upload re-queues it
+                    if in_flight.as_ref().map(|job| job.id) != Some(id) {
+                        anyhow::bail!("Worker reported a result for a job it does not hold");
+                    }
+                    let result = match error {
+                        Some(error) => {
+                            receive_payload(&mut reader, size, &mut io::sink())?;
+                            Err(anyhow::anyhow!(error))
+                        }
+                        None => {
+                            let output = state.lock().unwrap().outputs[id].clone();
+                            store_output(&output, &mut reader, size)?
+                        }
+                    };
+                    let job = in_flight.take().expect("the job was checked above");
+                    match &result {
+                        Ok(path) => say!("✓ job {} → {:?}", id, path),
+                        Err(e) => {
+                            say!("✗ job {} failed: {}", id, e);
+                            webhook::input_failed(webhooks, &job.input, e);
+                        }
+                    }
+                    let mut state = state.lock().unwrap();
+                    state.status.done(&job.input, result.is_ok());
+                    state.results[id] = Some(result);
+                }
+                other => anyhow::bail!("Unexpected message from worker: {:?}", other),
+            }
+        }
+        Ok(())
+    })();
+
+    if let Some(job) = in_flight {
+        warning!("⚠ Re-queueing job {} ({:?})", job.id, job.input);
+        let mut state = state.lock().unwrap();
+        state.status.requeued(&job.input);
+        state.pending.push_front(job);
+    }
+
+    outcome
+}
+
+fn load_input(input: &Path) -> Result<(String, Vec<u8>)> {
+    let file_name = input.file_name()
+        .context("Input has no file name")?
+        .to_string_lossy()
+        .into_owned();
+    let data = fs::read(input).with_context(|| format!("Failed to read input {:?}", input))?;
+    Ok((file_name, data))
+}
+
+fn output_path(input: &Path, config: &CoordinatorConfig) -> Result<PathBuf> {
+    let stem = input.file_stem().context("Failed to get file stem")?;
+    Ok(config.output_dir.join(format!("{}.{}", stem.to_string_lossy(), config.format)))
+}
+
+/// Receive an uploaded output next to its final path and move it into place,
+/// so a half-written file never replaces a finished one.
+///
+/// The outer error means the connection is left mid-upload; the inner one
+/// that the output could not be stored, with the upload read to its end.
+fn store_output(output_path: &Path, reader: &mut impl Read, size: u64) -> Result<Result<PathBuf>> {
+    let mut partial = output_path.as_os_str().to_os_string();
+    partial.push(".part");
+    let mut file = match File::create(&partial) {
+        Ok(file) => file,
+        Err(e) => {
+            receive_payload(reader, size, &mut io::sink())?;
+            return Ok(Err(anyhow::Error::new(e).context("Failed to write uploaded output")));
+        }
+    };
+    if let Err(e) = receive_payload(reader, size, &mut file) {
+        let _ = fs::remove_file(&partial);
+        return Err(e);
+    }
+    Ok(fs::rename(&partial, output_path)
+        .context("Failed to move uploaded output into place")
+        .map(|_| output_path.to_path_buf()))
+}
+
+/// The name a job's input is written under in the worker's scratch
+/// directory. The name comes from the coordinator, so anything that could
+/// leave the directory is refused.
+fn job_file_name(file_name: &str) -> Result<&OsStr> {
+    if file_name.is_empty() || file_name == "." || file_name == ".." || file_name.contains(['/', '\\']) {
+        anyhow::bail!("Refusing job input name {:?}", file_name);
+    }
+    Path::new(file_name).file_name().with_context(|| format!("Refusing job input name {:?}", file_name))
+}
+
+/// Connect to a coordinator and process jobs until it reports there are none left.
+///
+/// Returns the number of jobs this worker completed successfully.
+pub fn run_worker<A: ToSocketAddrs>(addr: A) -> Result<usize> {
+    let stream = TcpStream::connect(addr).context("Failed to connect to coordinator")?;
+    let writer = Arc::new(Mutex::new(stream.try_clone()?));
+    let mut reader = BufReader::new(stream);
+
+    let worker = format!("{}.{}@{}", std::process::id(), WORKER_SEQ.fetch_add(1, Ordering::Relaxed), hostname());
+    let scratch = std::env::temp_dir().join(format!("audio_extractor-worker-{}", worker));
+    fs::create_dir_all(&scratch).context("Failed to create worker scratch directory")?;
+
+    let mut completed = 0;
+    loop {
+        send(&mut *writer.lock().unwrap(), &Message::RequestJob { worker: worker.clone() }, None)?;
+
+        let message = receive(&mut reader)?.context("Coordinator closed the connection")?;
+        let (id, file_name, format, quality, verify, allow_lossy_transcode, size) = match message {
+            Message::Job { id, file_name, format, quality, verify, allow_lossy_transcode, size } => {
+                (id, file_name, format, quality, verify, allow_lossy_transcode, size)
+            }
+            Message::NoMoreJobs => break,
+            other => anyhow::bail!("Unexpected message from coordinator: {:?}", other),
+        };
+
+        let input = match job_file_name(&file_name) {
+            Ok(name) => scratch.join(name),
+            Err(e) => {
+                receive_payload(&mut reader, size, &mut io::sink())?;
+                let message = Message::JobResult { id, error: Some(format!("{:#}", e)), size: 0 };
+                send(&mut *writer.lock().unwrap(), &message, None)?;
+                continue;
+            }
+        };
+        let mut file = File::create(&input).context("Failed to write job input")?;
+        receive_payload(&mut reader, size, &mut file)?;
+        drop(file);
+        let output = input.with_extension(format.to_string());
+
+        say!("Processing job {}: {}", id, file_name);
+        let extractor = AudioExtractor::new(Args {
+            input: input.clone(),
+            output: output.clone(),
+            format: Some(format),
+            quality: Some(quality),
+            verify,
+            allow_lossy_transcode,
+            ..Default::default()
+        });
+
+        let progress_writer = Arc::clone(&writer);
+        let result = extractor
+            .extract_with_progress(|msg| {
+                let progress = Message::Progress { id, message: msg.to_string() };
+                let _ = send(&mut *progress_writer.lock().unwrap(), &progress, None);
+            })
+            .map_err(anyhow::Error::from)
+            .and_then(|_| fs::read(&output).context("Failed to read extracted output"));
+
+        let mut writer = writer.lock().unwrap();
+        match result {
+            Ok(data) => {
+                let message = Message::JobResult { id, error: None, size: data.len() as u64 };
+                send(&mut *writer, &message, Some(&data))?;
+                completed += 1;
+            }
+            Err(e) => {
+                let message = Message::JobResult { id, error: Some(format!("{:#}", e)), size: 0 };
+                send(&mut *writer, &message, None)?;
+            }
+        }
+
+        let _ = fs::remove_file(&input);
+        let _ = fs::remove_file(&output);
+    }
+
+    let _ = fs::remove_dir_all(&scratch);
+    Ok(completed)
+}
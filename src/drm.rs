@@ -0,0 +1,148 @@
+use crate::limits::ResourceLimits;
+use anyhow::{Context, Result};
+use std::fmt;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Largest `moov` box read into memory when looking for protection boxes
+const MAX_MOOV_BYTES: u64 = 64 * 1024 * 1024;
+
+/// The input is encrypted or DRM-protected and cannot be decoded.
+///
+/// Returned before extraction starts, as `ExtractorError::DrmProtected` from
+/// `AudioExtractor`'s methods.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DrmProtected {
+    pub path: PathBuf,
+    /// What gave the protection away, e.g. `cenc` or `encrypted audio track (enca)`
+    pub scheme: String,
+}
+
+impl fmt::Display for DrmProtected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Input is DRM-protected ({}) and cannot be extracted: {:?}",
+            self.scheme, self.path
+        )
+    }
+}
+
+impl std::error::Error for DrmProtected {}
+
+/// Fail with `DrmProtected` if the input is encrypted
+pub fn check(path: &Path, limits: Option<&ResourceLimits>) -> Result<()> {
+    if let Some(scheme) = detect(path, limits)? {
+        return Err(DrmProtected { path: path.to_path_buf(), scheme }.into());
+    }
+    Ok(())
+}
+
+/// Look for signs of encryption: ISO BMFF protection boxes (`pssh`, `sinf`/`schm`)
+/// and, if `limits` is given, what ffprobe reports about the streams
+pub fn detect(path: &Path, limits: Option<&ResourceLimits>) -> Result<Option<String>> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+    if let Some(scheme) = detect_protection_boxes(path)? {
+        return Ok(Some(scheme));
+    }
+    match limits {
+        Some(limits) => detect_with_ffprobe(path, limits),
+        None => Ok(None),
+    }
+}
+
+/// Scan the `moov` box of an MP4/MOV/M4A file for Common Encryption boxes
+pub fn detect_protection_boxes(path: &Path) -> Result<Option<String>> {
+    let mut file = File::open(path).with_context(|| format!("Failed to open {:?}", path))?;
+    let file_len = file.metadata()?.len();
+
+    let mut offset = 0;
+    while offset + 8 <= file_len {
+        let mut header = [0u8; 8];
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(&mut header)?;
+        let mut size = u32::from_be_bytes(header[..4].try_into().unwrap()) as u64;
+        let kind = &header[4..8];
+
+        let mut header_len = 8;
+        if size == 1 {
+            let mut large = [0u8; 8];
+            file.read_exact(&mut large)?;
+            size = u64::from_be_bytes(large);
+            header_len = 16;
+        } else if size == 0 {
+            size = file_len - offset;
+        }
+        if size < header_len || !kind.iter().all(u8::is_ascii_graphic) {
+            // Not an ISO BMFF file
+            return Ok(None);
+        }
+
+        if kind == b"moov" {
+            let body_len = (size - header_len).min(MAX_MOOV_BYTES);
+            let mut body = vec![0u8; body_len as usize];
+            file.read_exact(&mut body).context("Truncated moov box")?;
+            return Ok(protection_scheme(&body));
+        }
+        offset += size;
+    }
+    Ok(None)
+}
+
+/// The protection scheme named in a `schm` box, or a generic marker if
+/// only `pssh`/`tenc` boxes are present
+fn protection_scheme(moov: &[u8]) -> Option<String> {
+    if let Some(pos) = find(moov, b"schm") {
+        // type, then version/flags, then the four-character scheme
+        if let Some(scheme) = moov.get(pos + 8..pos + 12) {
+            return Some(String::from_utf8_lossy(scheme).into_owned());
+        }
+    }
+    if find(moov, b"pssh").is_some() || find(moov, b"tenc").is_some() {
+        return Some("cenc".to_string());
+    }
+    None
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+fn detect_with_ffprobe(path: &Path, limits: &ResourceLimits) -> Result<Option<String>> {
+    let mut cmd = Command::new("ffprobe");
+    cmd.arg("-v").arg("warning")
+        .arg("-print_format").arg("json")
+        .arg("-show_streams")
+        .arg(path);
+    let output = limits.output(&mut cmd).context("Failed to run ffprobe")?;
+    Ok(ffprobe_protection(&String::from_utf8_lossy(&output.stdout), &String::from_utf8_lossy(&output.stderr)))
+}
+
+/// Protection reported by ffprobe: encrypted sample entries, encryption side
+/// data, or the ASF demuxer's DRM warning
+pub fn ffprobe_protection(json: &str, stderr: &str) -> Option<String> {
+    if stderr.contains("DRM protected") {
+        return Some("ASF DRM".to_string());
+    }
+
+    let probe: serde_json::Value = serde_json::from_str(json).ok()?;
+    for stream in probe["streams"].as_array()? {
+        let tag = stream["codec_tag_string"].as_str().unwrap_or("");
+        if matches!(tag, "enca" | "encv" | "drms" | "drmi") {
+            let kind = stream["codec_type"].as_str().unwrap_or("stream");
+            return Some(format!("encrypted {} track ({})", kind, tag));
+        }
+        let encrypted_side_data = stream["side_data_list"].as_array().is_some_and(|list| {
+            list.iter()
+                .any(|side| side["side_data_type"].as_str().is_some_and(|t| t.contains("Encryption")))
+        });
+        if encrypted_side_data {
+            return Some("encryption info in stream".to_string());
+        }
+    }
+    None
+}
@@ -0,0 +1,152 @@
+use crate::analysis::{Segment, SegmentLabel};
+use crate::effects::{Effect, FadeDirection};
+use crate::loudness::{LoudnessTarget, Measurement};
+use crate::timecode::{format_timestamp, TimeRange};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// What the processing of an input would do, worked out with --dry-run
+/// without encoding anything
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DryRun {
+    pub input: PathBuf,
+    /// Seconds of audio in the source, if it could be probed
+    pub source_seconds: Option<f64>,
+    /// The processing steps, in the order they run
+    pub steps: Vec<Step>,
+}
+
+/// One processing step and what it would change
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "step", rename_all = "snake_case")]
+pub enum Step {
+    /// --start, --end and --duration
+    Cut {
+        start: f64,
+        end: Option<f64>,
+        /// Seconds left after the cut, if the source's length is known
+        kept_seconds: Option<f64>,
+    },
+    /// --mute and --mute-file
+    Mute { ranges: usize, muted_seconds: f64 },
+    /// --keep-segments: the regions kept, cut back to back
+    KeepSegments { kept: Vec<Segment>, kept_seconds: f64, dropped_seconds: f64 },
+    /// An effect added through the library
+    Effect { description: String, filter: String },
+    /// --normalize, from the first pass's measurement
+    Normalize {
+        target: LoudnessTarget,
+        measured: Measurement,
+        /// Gain the second pass applies, in dB
+        gain_db: f64,
+        /// True peak after that gain, in dBTP
+        peak_after: f64,
+        /// Whether the peak would pass the target's, so loudnorm limits the
+        /// audio instead of only changing its gain
+        limited: bool,
+    },
+}
+
+impl Step {
+    pub fn mute(ranges: &[TimeRange]) -> Self {
+        Step::Mute {
+            ranges: ranges.len(),
+            muted_seconds: ranges.iter().map(TimeRange::duration).sum(),
+        }
+    }
+
+    /// The regions of `segments` labelled one of `labels` are kept
+    pub fn keep_segments(segments: &[Segment], labels: &[SegmentLabel]) -> Self {
+        let (kept, dropped): (Vec<Segment>, Vec<Segment>) = segments.iter().cloned().partition(|segment| labels.contains(&segment.label));
+        let seconds = |segments: &[Segment]| segments.iter().map(|segment| segment.end - segment.start).sum();
+        Step::KeepSegments {
+            kept_seconds: seconds(&kept),
+            dropped_seconds: seconds(&dropped),
+            kept,
+        }
+    }
+
+    pub fn effect(effect: &Effect) -> Self {
+        let description = match effect {
+            Effect::Normalize(target) => format!("normalize to {:.1} LUFS in one pass", target.integrated),
+            Effect::Trim { start, end: Some(end) } => format!("trim to {} - {}", format_timestamp(*start), format_timestamp(*end)),
+            Effect::Trim { start, end: None } => format!("trim from {}", format_timestamp(*start)),
+            Effect::Fade { direction, start, duration } => {
+                let kind = match direction {
+                    FadeDirection::In => "in",
+                    FadeDirection::Out => "out",
+                };
+                format!("fade {} at {} over {:.1} s", kind, format_timestamp(*start), duration)
+            }
+            Effect::Resample(rate) => format!("resample to {} Hz", rate),
+            Effect::Gain(db) => format!("gain {:+.1} dB", db),
+            Effect::Custom(filter) => format!("custom filter {}", filter),
+        };
+        Step::Effect { description, filter: effect.filter() }
+    }
+
+    /// What the second pass of --normalize does to audio measured as
+    /// `measured`: a linear gain, unless it would lift the peak past the
+    /// target's
+    pub fn normalize(target: LoudnessTarget, measured: Measurement) -> Self {
+        let gain_db = target.integrated - measured.integrated;
+        let peak_after = measured.true_peak + gain_db;
+        Step::Normalize { target, measured, gain_db, peak_after, limited: peak_after > target.true_peak }
+    }
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Step::Cut { start, end, kept_seconds } => {
+                write!(f, "Cut: from {}", format_timestamp(*start))?;
+                if let Some(end) = end {
+                    write!(f, " to {}", format_timestamp(*end))?;
+                }
+                if let Some(kept) = kept_seconds {
+                    write!(f, ", keeping {:.1} s", kept)?;
+                }
+                Ok(())
+            }
+            Step::Mute { ranges, muted_seconds } => write!(f, "Mute: {} range(s), {:.1} s silenced", ranges, muted_seconds),
+            Step::KeepSegments { kept, kept_seconds, dropped_seconds } => write!(
+                f,
+                "Keep segments: {} region(s), keeping {:.1} s and dropping {:.1} s",
+                kept.len(), kept_seconds, dropped_seconds
+            ),
+            Step::Effect { description, .. } => write!(f, "Effect: {}", description),
+            Step::Normalize { target, measured, gain_db, peak_after, limited } => write!(
+                f,
+                "Normalize: measured {:.1} LUFS and {:.1} dBTP, {:+.1} dB to reach {:.1} LUFS, peak {:.1} dBTP{}",
+                measured.integrated, measured.true_peak, gain_db, target.integrated, peak_after,
+                if *limited { format!(" (over {:.1} dBTP, so it is limited)", target.true_peak) } else { String::new() }
+            ),
+        }
+    }
+}
+
+impl DryRun {
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json).with_context(|| format!("Failed to write dry run report {:?}", path))
+    }
+}
+
+impl fmt::Display for DryRun {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Dry run of {:?}", self.input)?;
+        if let Some(seconds) = self.source_seconds {
+            write!(f, " ({})", format_timestamp(seconds))?;
+        }
+        writeln!(f)?;
+        if self.steps.is_empty() {
+            writeln!(f, "  No processing; the audio would be encoded as it is")?;
+        }
+        for step in &self.steps {
+            writeln!(f, "  {}", step)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,281 @@
+use crate::loudness::LoudnessTarget;
+use anyhow::Result;
+
+/// Which end of the audio a fade is at
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FadeDirection {
+    /// From silence up to full level; silent before the fade
+    In,
+    /// From full level down to silence; silent after the fade
+    Out,
+}
+
+/// One step of audio processing. Times are in seconds of the audio the
+/// effect receives: source time at the start of a chain, and time from the
+/// cut after a `Trim`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Effect {
+    /// Loudness normalization in one pass, adjusting the gain as it goes
+    Normalize(LoudnessTarget),
+    /// Keep the audio from `start` to `end`, or to the end
+    Trim { start: f64, end: Option<f64> },
+    Fade { direction: FadeDirection, start: f64, duration: f64 },
+    /// Change the sample rate, in Hz
+    Resample(u32),
+    /// Change the level, in dB
+    Gain(f64),
+    /// An FFmpeg filter, passed as it is
+    Custom(String),
+}
+
+impl Effect {
+    /// The effect as an FFmpeg filter
+    pub fn filter(&self) -> String {
+        match self {
+            Effect::Normalize(target) => target.single_pass_filter(),
+            Effect::Trim { start, end: Some(end) } => format!("atrim=start={}:end={},asetpts=PTS-STARTPTS", start, end),
+            Effect::Trim { start, end: None } => format!("atrim=start={},asetpts=PTS-STARTPTS", start),
+            Effect::Fade { direction, start, duration } => {
+                let kind = match direction {
+                    FadeDirection::In => "in",
+                    FadeDirection::Out => "out",
+                };
+                format!("afade=t={}:st={}:d={}", kind, start, duration)
+            }
+            Effect::Resample(rate) => format!("aresample={}", rate),
+            Effect::Gain(db) => format!("volume={}dB", db),
+            Effect::Custom(filter) => filter.clone(),
+        }
+    }
+}
+
+/// Audio processing composed from typed effects, applied in order. It
+/// becomes part of the FFmpeg filter graph, or runs in-process with the
+/// native backend, which supports every effect but `Normalize` and `Custom`.
+///
+/// ```
+/// use audio_extractor::effects::{EffectChain, FadeDirection};
+///
+/// let chain = EffectChain::new()
+///     .trim(30.0, Some(90.0))
+///     .fade(FadeDirection::In, 0.0, 2.0)
+///     .gain(-3.0);
+/// assert_eq!(chain.to_filtergraph(), "atrim=start=30:end=90,asetpts=PTS-STARTPTS,afade=t=in:st=0:d=2,volume=-3dB");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EffectChain {
+    pub effects: Vec<Effect>,
+}
+
+impl EffectChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(mut self, effect: Effect) -> Self {
+        self.effects.push(effect);
+        self
+    }
+
+    pub fn normalize(self, target: LoudnessTarget) -> Self {
+        self.push(Effect::Normalize(target))
+    }
+
+    pub fn trim(self, start: f64, end: Option<f64>) -> Self {
+        self.push(Effect::Trim { start, end })
+    }
+
+    pub fn fade(self, direction: FadeDirection, start: f64, duration: f64) -> Self {
+        self.push(Effect::Fade { direction, start, duration })
+    }
+
+    pub fn resample(self, rate: u32) -> Self {
+        self.push(Effect::Resample(rate))
+    }
+
+    pub fn gain(self, db: f64) -> Self {
+        self.push(Effect::Gain(db))
+    }
+
+    pub fn custom(self, filter: impl Into<String>) -> Self {
+        self.push(Effect::Custom(filter.into()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.effects.is_empty()
+    }
+
+    /// The FFmpeg filters, one per effect
+    pub fn filters(&self) -> Vec<String> {
+        self.effects.iter().map(Effect::filter).collect()
+    }
+
+    /// The chain as an FFmpeg filtergraph for `-af`
+    pub fn to_filtergraph(&self) -> String {
+        self.filters().join(",")
+    }
+
+    /// Check the chain can run with the native backend
+    pub fn check_native(&self) -> Result<()> {
+        for effect in &self.effects {
+            match effect {
+                Effect::Normalize(_) => anyhow::bail!("Loudness normalization needs FFmpeg"),
+                Effect::Custom(filter) => anyhow::bail!("The custom filter {:?} needs FFmpeg", filter),
+                Effect::Resample(0) => anyhow::bail!("Cannot resample to 0 Hz"),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An `EffectChain` running on interleaved 16-bit samples as they are decoded
+#[derive(Debug, Clone)]
+pub struct NativeEffects {
+    stages: Vec<Stage>,
+    channels: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Stage {
+    kind: StageKind,
+    /// Sample rate the stage receives
+    rate: u32,
+    /// Seconds of the first frame the stage receives
+    offset: f64,
+    /// Frames received so far
+    position: u64,
+}
+
+#[derive(Debug, Clone)]
+enum StageKind {
+    Gain(f32),
+    Fade { direction: FadeDirection, start: f64, duration: f64 },
+    Trim { start: f64, end: Option<f64> },
+    Resample(Resampler),
+}
+
+impl NativeEffects {
+    /// Run `chain` on audio at `rate` with `channels`, whose first frame is
+    /// at `offset` seconds of the source
+    pub fn new(chain: &EffectChain, rate: u32, channels: usize, offset: f64) -> Result<Self> {
+        chain.check_native()?;
+        let mut stages = Vec::new();
+        let (mut rate, mut offset) = (rate, offset);
+        for effect in &chain.effects {
+            let kind = match effect {
+                Effect::Gain(db) => StageKind::Gain(10f32.powf(*db as f32 / 20.0)),
+                Effect::Fade { direction, start, duration } => StageKind::Fade { direction: *direction, start: *start, duration: *duration },
+                Effect::Trim { start, end } => StageKind::Trim { start: *start, end: *end },
+                Effect::Resample(to) => StageKind::Resample(Resampler::new(rate, *to, channels)),
+                Effect::Normalize(_) | Effect::Custom(_) => unreachable!("checked by check_native"),
+            };
+            stages.push(Stage { kind, rate, offset, position: 0 });
+            match effect {
+                Effect::Trim { .. } => offset = 0.0,
+                Effect::Resample(to) => rate = *to,
+                _ => {}
+            }
+        }
+        Ok(Self { stages, channels })
+    }
+
+    /// Sample rate of the processed audio
+    pub fn output_rate(&self, input_rate: u32) -> u32 {
+        self.stages.iter().rev().find_map(|stage| match &stage.kind {
+            StageKind::Resample(resampler) => Some(resampler.to),
+            _ => None,
+        }).unwrap_or(input_rate)
+    }
+
+    /// Process the next interleaved samples
+    pub fn process(&mut self, samples: &[i16]) -> Vec<i16> {
+        let mut samples: Vec<f32> = samples.iter().map(|&s| s as f32).collect();
+        for stage in &mut self.stages {
+            samples = stage.process(samples, self.channels);
+        }
+        samples.into_iter().map(|s| s.round().clamp(i16::MIN as f32, i16::MAX as f32) as i16).collect()
+    }
+}
+
+impl Stage {
+    fn process(&mut self, mut samples: Vec<f32>, channels: usize) -> Vec<f32> {
+        let frames = samples.len() / channels;
+        let first = self.position;
+        self.position += frames as u64;
+        let time = |frame: usize| self.offset + (first + frame as u64) as f64 / self.rate as f64;
+        match &mut self.kind {
+            StageKind::Gain(factor) => {
+                samples.iter_mut().for_each(|s| *s *= *factor);
+                samples
+            }
+            StageKind::Fade { direction, start, duration } => {
+                for (frame, chunk) in samples.chunks_mut(channels).enumerate() {
+                    let progress = if *duration > 0.0 {
+                        ((time(frame) - *start) / *duration).clamp(0.0, 1.0)
+                    } else if time(frame) < *start {
+                        0.0
+                    } else {
+                        1.0
+                    };
+                    let level = match direction {
+                        FadeDirection::In => progress,
+                        FadeDirection::Out => 1.0 - progress,
+                    } as f32;
+                    chunk.iter_mut().for_each(|s| *s *= level);
+                }
+                samples
+            }
+            StageKind::Trim { start, end } => {
+                let keep = |frame: &usize| time(*frame) >= *start && end.is_none_or(|end| time(*frame) < end);
+                (0..frames).filter(keep).flat_map(|frame| samples[frame * channels..(frame + 1) * channels].to_vec()).collect()
+            }
+            StageKind::Resample(resampler) => resampler.process(&samples),
+        }
+    }
+}
+
+/// Sample rate conversion by linear interpolation, carried across chunks
+#[derive(Debug, Clone)]
+struct Resampler {
+    from: u32,
+    to: u32,
+    channels: usize,
+    /// Position of the next output frame, in input frames
+    next: f64,
+    /// Input frames received before the current chunk
+    consumed: u64,
+    /// Last frame of the previous chunk
+    previous: Vec<f32>,
+}
+
+impl Resampler {
+    fn new(from: u32, to: u32, channels: usize) -> Self {
+        Self { from, to, channels, next: 0.0, consumed: 0, previous: vec![0.0; channels] }
+    }
+
+    fn process(&mut self, samples: &[f32]) -> Vec<f32> {
+        let frames = samples.len() / self.channels;
+        if frames == 0 {
+            return Vec::new();
+        }
+        let step = self.from as f64 / self.to as f64;
+        let last = self.consumed + frames as u64 - 1;
+        let mut output = Vec::new();
+        while (self.next.floor() as u64) < last {
+            let index = self.next.floor() as u64;
+            let fraction = (self.next - index as f64) as f32;
+            for channel in 0..self.channels {
+                let at = |frame: u64| match frame.checked_sub(self.consumed) {
+                    Some(i) => samples[i as usize * self.channels + channel],
+                    None => self.previous[channel],
+                };
+                output.push(at(index) + (at(index + 1) - at(index)) * fraction);
+            }
+            self.next += step;
+        }
+        self.previous.copy_from_slice(&samples[(frames - 1) * self.channels..]);
+        self.consumed += frames as u64;
+        output
+    }
+}
@@ -0,0 +1,152 @@
+use crate::audit;
+use crate::curl::Curl;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// Seconds to wait for the mail server to take the message
+const SEND_TIMEOUT_SECS: u32 = 60;
+
+/// Failures listed in the email body before the rest are only counted
+const MAX_LISTED_FAILURES: usize = 50;
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SendWhen {
+    /// After every batch
+    #[default]
+    Always,
+    /// Only after batches with failures
+    Failures,
+}
+
+/// SMTP server to mail batch summaries to, from the `[email]` table of `config.toml`
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct EmailConfig {
+    /// `smtps://host:465`, or `smtp://host:587` to upgrade with STARTTLS
+    pub smtp_url: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub from: String,
+    pub to: Vec<String>,
+    #[serde(default)]
+    pub send: SendWhen,
+}
+
+/// Outcome of a batch, as mailed at its end
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchSummary {
+    /// What ran, e.g. `scan` or `coordinator`
+    pub kind: String,
+    pub source: PathBuf,
+    pub started_at: String,
+    pub finished_at: String,
+    pub succeeded: usize,
+    pub failures: Vec<String>,
+}
+
+impl BatchSummary {
+    pub fn new(kind: &str, source: &Path, started_at: String, succeeded: usize, failures: Vec<String>) -> Self {
+        Self {
+            kind: kind.to_string(),
+            source: source.to_path_buf(),
+            started_at,
+            finished_at: chrono::Utc::now().to_rfc3339(),
+            succeeded,
+            failures,
+        }
+    }
+
+    pub fn subject(&self) -> String {
+        let outcome = if self.failures.is_empty() { "succeeded" } else { "FAILED" };
+        format!(
+            "[audio_extractor] {} of {} {} on {}: {} ok, {} failed",
+            self.kind,
+            self.source.display(),
+            outcome,
+            audit::hostname(),
+            self.succeeded,
+            self.failures.len()
+        )
+    }
+
+    pub fn body(&self) -> String {
+        let mut body = format!(
+            "Batch: {} of {}\nHost: {}\nStarted: {}\nFinished: {}\n\nSucceeded: {}\nFailed: {}\n",
+            self.kind,
+            self.source.display(),
+            audit::hostname(),
+            self.started_at,
+            self.finished_at,
+            self.succeeded,
+            self.failures.len()
+        );
+        if !self.failures.is_empty() {
+            body.push_str("\nFailures:\n");
+            for failure in self.failures.iter().take(MAX_LISTED_FAILURES) {
+                body.push_str(&format!("- {}\n", failure));
+            }
+            if self.failures.len() > MAX_LISTED_FAILURES {
+                body.push_str(&format!("... and {} more\n", self.failures.len() - MAX_LISTED_FAILURES));
+            }
+        }
+        body
+    }
+}
+
+impl EmailConfig {
+    /// Whether `summary` should be mailed under the `send` setting
+    pub fn wants(&self, summary: &BatchSummary) -> bool {
+        match self.send {
+            SendWhen::Always => true,
+            SendWhen::Failures => !summary.failures.is_empty(),
+        }
+    }
+
+    /// RFC 5322 message carrying the summary
+    pub fn message(&self, summary: &BatchSummary) -> String {
+        let headers = [
+            format!("From: {}", self.from),
+            format!("To: {}", self.to.join(", ")),
+            format!("Subject: {}", summary.subject()),
+            format!("Date: {}", chrono::Utc::now().to_rfc2822()),
+            "MIME-Version: 1.0".to_string(),
+            "Content-Type: text/plain; charset=utf-8".to_string(),
+        ];
+        // SMTP wants CRLF line endings throughout
+        format!("{}\r\n\r\n{}", headers.join("\r\n"), summary.body().replace('\n', "\r\n"))
+    }
+
+    /// Mail the summary, unless `send` says this one is not wanted
+    pub fn send(&self, summary: &BatchSummary) -> Result<bool> {
+        if !self.wants(summary) {
+            return Ok(false);
+        }
+        if self.to.is_empty() {
+            anyhow::bail!("No recipients in [email] `to`");
+        }
+
+        // The message goes through a file; curl reads its options from stdin
+        let message_path = std::env::temp_dir().join(format!("audio_extractor-mail-{}.eml", std::process::id()));
+        std::fs::write(&message_path, self.message(summary)).context("Failed to write email message")?;
+
+        let mut curl = Curl::new()
+            .option("url", &self.smtp_url)
+            .option("mail-from", &self.from)
+            .option("upload-file", &message_path.to_string_lossy());
+        for recipient in &self.to {
+            curl = curl.option("mail-rcpt", recipient);
+        }
+        if self.smtp_url.starts_with("smtp://") {
+            curl = curl.flag("ssl-reqd");
+        }
+        if let Some(username) = &self.username {
+            let password = self.password.as_deref().unwrap_or("");
+            curl = curl.option("user", &format!("{}:{}", username, password));
+        }
+
+        let sent = curl.run(SEND_TIMEOUT_SECS).context("Failed to send summary email");
+        let _ = std::fs::remove_file(&message_path);
+        sent.map(|_| true)
+    }
+}
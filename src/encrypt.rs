@@ -0,0 +1,105 @@
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+
+/// External tool used to encrypt outputs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EncryptionTool {
+    Age,
+    Gpg,
+}
+
+/// Encrypt outputs for a recipient so plaintext audio never touches disk.
+///
+/// Recipients that look like age public keys (`age1...`, `ssh-...`) are
+/// encrypted with `age`; anything else is treated as a GnuPG key id or email.
+#[derive(Debug, Clone)]
+pub struct Encryption {
+    pub recipient: String,
+    /// age identity file used to decrypt outputs during verification
+    pub identity: Option<PathBuf>,
+}
+
+impl Encryption {
+    pub fn new(recipient: String, identity: Option<PathBuf>) -> Self {
+        Self { recipient, identity }
+    }
+
+    pub fn tool(&self) -> EncryptionTool {
+        if self.recipient.starts_with("age1") || self.recipient.starts_with("ssh-") {
+            EncryptionTool::Age
+        } else {
+            EncryptionTool::Gpg
+        }
+    }
+
+    /// Command that encrypts stdin for the recipient and writes the result to `output`
+    pub fn encrypt_command(&self, output: &Path) -> Command {
+        let mut cmd = match self.tool() {
+            EncryptionTool::Age => {
+                let mut cmd = Command::new("age");
+                cmd.arg("--encrypt").arg("--recipient").arg(&self.recipient);
+                cmd
+            }
+            EncryptionTool::Gpg => {
+                let mut cmd = Command::new("gpg");
+                cmd.arg("--batch").arg("--yes").arg("--encrypt").arg("--recipient").arg(&self.recipient);
+                cmd
+            }
+        };
+        cmd.arg("--output").arg(output);
+        cmd
+    }
+
+    /// Spawn the encryption tool reading plaintext from `input`
+    pub fn spawn_encrypter(&self, output: &Path, input: Stdio) -> Result<Child> {
+        self.encrypt_command(output)
+            .stdin(input)
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Failed to run {:?}; is it installed?", self.tool()))
+    }
+
+    /// Encrypt an in-memory buffer straight to `output`
+    pub fn encrypt_bytes(&self, data: &[u8], output: &Path) -> Result<()> {
+        let mut child = self.spawn_encrypter(output, Stdio::piped())?;
+        child.stdin.take()
+            .context("Failed to open encrypter stdin")?
+            .write_all(data)
+            .context("Failed to write data to encrypter")?;
+        let result = child.wait_with_output().context("Failed to wait for encrypter")?;
+        if !result.status.success() {
+            anyhow::bail!("Encryption failed: {}", String::from_utf8_lossy(&result.stderr));
+        }
+        Ok(())
+    }
+
+    /// Decrypt `path` into memory; the plaintext is never written to disk
+    pub fn decrypt(&self, path: &Path) -> Result<Vec<u8>> {
+        let mut cmd = match self.tool() {
+            EncryptionTool::Age => {
+                let identity = self.identity.as_ref()
+                    .context("Decrypting age output requires an identity file (--decrypt-identity)")?;
+                let mut cmd = Command::new("age");
+                cmd.arg("--decrypt").arg("--identity").arg(identity);
+                cmd
+            }
+            EncryptionTool::Gpg => {
+                let mut cmd = Command::new("gpg");
+                cmd.arg("--batch").arg("--quiet").arg("--decrypt");
+                cmd
+            }
+        };
+
+        let output = cmd.arg(path)
+            .stdin(Stdio::null())
+            .output()
+            .with_context(|| format!("Failed to run {:?}; is it installed?", self.tool()))?;
+        if !output.status.success() {
+            anyhow::bail!("Decryption failed: {}", String::from_utf8_lossy(&output.stderr));
+        }
+        Ok(output.stdout)
+    }
+}
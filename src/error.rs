@@ -0,0 +1,78 @@
+use crate::drm::DrmProtected;
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// Why a library call failed.
+///
+/// `AudioExtractor`'s public methods return this so callers can tell a
+/// missing input from a failed encode without matching on messages. Failures
+/// with no variant of their own, such as a sidecar that cannot be written,
+/// are `Other` and keep their message.
+#[derive(Debug, Error)]
+pub enum ExtractorError {
+    #[error("Input file does not exist: {0:?}")]
+    InputNotFound(PathBuf),
+
+    /// The input is neither a video file of a supported type nor a disc or
+    /// image-sequence project folder
+    #[error("Input is not a supported video format, disc or project folder: {0:?}")]
+    UnsupportedFormat(PathBuf),
+
+    /// Settings that cannot be used together or with this input or format
+    #[error("{0}")]
+    InvalidOptions(String),
+
+    #[error("FFmpeg not found; install it or use --backend native for WAV, FLAC and AIFF output")]
+    FfmpegNotInstalled,
+
+    /// ffprobe is missing and `needed_for` cannot be done without it
+    #[error("ffprobe not found; install FFmpeg, which includes it, for {needed_for}")]
+    FfprobeNotInstalled { needed_for: &'static str },
+
+    /// FFmpeg ran and exited unsuccessfully; `exit_code` is `None` when it was
+    /// killed by a signal
+    #[error("FFmpeg failed: {stderr}")]
+    FfmpegFailed { stderr: String, exit_code: Option<i32> },
+
+    /// FFmpeg made no progress for the --stall-timeout and --on-stall killed it
+    #[error("FFmpeg made no progress for {seconds} seconds and was killed")]
+    Stalled { seconds: u64 },
+
+    /// ffprobe or symphonia could not make sense of a file
+    #[error("Failed to probe {path:?}: {message}")]
+    ProbeFailed { path: PathBuf, message: String },
+
+    #[error(transparent)]
+    DrmProtected(#[from] DrmProtected),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+impl ExtractorError {
+    /// `FfmpegFailed` from the output of an FFmpeg run
+    pub fn ffmpeg_failed(output: &std::process::Output) -> Self {
+        ExtractorError::FfmpegFailed {
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+            exit_code: output.status.code(),
+        }
+    }
+}
+
+/// Internal code works with `anyhow`; a typed error raised inside it, possibly
+/// under added context, comes back out as itself.
+impl From<anyhow::Error> for ExtractorError {
+    fn from(error: anyhow::Error) -> Self {
+        let error = match error.downcast::<ExtractorError>() {
+            Ok(typed) => return typed,
+            Err(error) => error,
+        };
+        match error.downcast::<DrmProtected>() {
+            Ok(drm) => ExtractorError::DrmProtected(drm),
+            Err(error) => ExtractorError::Other(error),
+        }
+    }
+}
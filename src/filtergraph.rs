@@ -0,0 +1,64 @@
+use std::path::PathBuf;
+
+/// Audio processing for one extraction: a linear filter chain on the source
+/// audio, plus generated streams (tones, watermarks) mixed on top of it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AudioGraph {
+    /// Filters applied in order to the source audio
+    pub filters: Vec<String>,
+    /// Complete source chains whose output is mixed into the result
+    pub overlays: Vec<String>,
+    /// Additional FFmpeg inputs used by overlays, numbered from 1 after the source
+    pub inputs: Vec<PathBuf>,
+}
+
+impl AudioGraph {
+    /// Register an extra input and return the FFmpeg input index overlays refer to it by
+    pub fn add_input(&mut self, path: PathBuf) -> usize {
+        self.inputs.push(path);
+        self.inputs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty() && self.overlays.is_empty()
+    }
+
+    /// FFmpeg arguments applying the graph to the audio selected by `stream` (e.g. `0:a`).
+    ///
+    /// A plain chain is passed with `-af`; overlays need `-filter_complex` and an
+    /// explicit `-map` of the mixed output.
+    pub fn ffmpeg_args(&self, stream: &str) -> Vec<String> {
+        if self.overlays.is_empty() {
+            if self.filters.is_empty() {
+                return Vec::new();
+            }
+            return vec!["-af".to_string(), self.filters.join(",")];
+        }
+
+        let chain = if self.filters.is_empty() {
+            "anull".to_string()
+        } else {
+            self.filters.join(",")
+        };
+
+        let mut graph = format!("[{}]{}[main]", stream, chain);
+        let mut mix_inputs = String::from("[main]");
+        for (i, overlay) in self.overlays.iter().enumerate() {
+            graph.push_str(&format!(";{}[overlay{}]", overlay, i));
+            mix_inputs.push_str(&format!("[overlay{}]", i));
+        }
+        // normalize=0 keeps the source at its original level instead of dividing by the input count
+        graph.push_str(&format!(
+            ";{}amix=inputs={}:duration=first:normalize=0[out]",
+            mix_inputs,
+            self.overlays.len() + 1
+        ));
+
+        vec![
+            "-filter_complex".to_string(),
+            graph,
+            "-map".to_string(),
+            "[out]".to_string(),
+        ]
+    }
+}
@@ -0,0 +1,333 @@
+use anyhow::{Context, Result};
+use std::io::{Seek, SeekFrom, Write};
+
+/// Samples per channel in each frame, the reference encoder's default
+pub const BLOCK_SIZE: usize = 4096;
+
+/// Highest fixed predictor order FLAC defines
+const MAX_ORDER: usize = 4;
+
+/// Rice parameter 15 marks an escaped partition, so 14 is the largest usable one
+const MAX_RICE_PARAM: u32 = 14;
+
+/// Offset of STREAMINFO's contents: the `fLaC` marker and a block header
+const STREAMINFO_OFFSET: u64 = 8;
+
+/// Vendor string of the VORBIS_COMMENT block
+const VENDOR: &str = concat!("audio_extractor ", env!("CARGO_PKG_VERSION"));
+
+/// Streaming 16-bit FLAC encoder using FLAC's fixed linear predictors and
+/// Rice-coded residuals. It compresses less than libFLAC's LPC search but
+/// writes standard streams any decoder plays. STREAMINFO is rewritten with
+/// the sizes and sample count when the stream is finished; its MD5 is left
+/// zero, which the format reserves for "not computed".
+pub struct FlacWriter<W: Write + Seek> {
+    writer: W,
+    sample_rate: u32,
+    channels: usize,
+    /// Samples waiting for a full block, one buffer per channel
+    pending: Vec<Vec<i32>>,
+    frame_number: u64,
+    total_samples: u64,
+    min_frame_size: u32,
+    max_frame_size: u32,
+}
+
+impl<W: Write + Seek> FlacWriter<W> {
+    pub fn new(writer: W, sample_rate: u32, channels: usize) -> Result<Self> {
+        Self::with_tags(writer, sample_rate, channels, &[])
+    }
+
+    /// Start a stream whose VORBIS_COMMENT block holds `tags`, named as in
+    /// `native::standard_tags`
+    pub fn with_tags(mut writer: W, sample_rate: u32, channels: usize, tags: &[(&str, String)]) -> Result<Self> {
+        if !(1..=8).contains(&channels) {
+            anyhow::bail!("FLAC holds 1 to 8 channels, not {}", channels);
+        }
+        if sample_rate == 0 || sample_rate >= 1 << 20 {
+            anyhow::bail!("FLAC cannot store a sample rate of {} Hz", sample_rate);
+        }
+        writer.write_all(b"fLaC")?;
+        // Type 0 (STREAMINFO), 34 bytes long, and the last block unless tags follow
+        writer.write_all(&[if tags.is_empty() { 0x80 } else { 0 }, 0, 0, 34])?;
+        writer.write_all(&[0; 34])?;
+        if !tags.is_empty() {
+            let comments = vorbis_comment(tags);
+            let len = (comments.len() as u32).to_be_bytes();
+            // Last metadata block, type 4 (VORBIS_COMMENT)
+            writer.write_all(&[0x84, len[1], len[2], len[3]])?;
+            writer.write_all(&comments)?;
+        }
+        Ok(Self {
+            writer,
+            sample_rate,
+            channels,
+            pending: vec![Vec::with_capacity(BLOCK_SIZE); channels],
+            frame_number: 0,
+            total_samples: 0,
+            min_frame_size: u32::MAX,
+            max_frame_size: 0,
+        })
+    }
+
+    /// Add interleaved samples, encoding every block that fills up
+    pub fn write(&mut self, interleaved: &[i16]) -> Result<()> {
+        for frame in interleaved.chunks_exact(self.channels) {
+            for (channel, &sample) in self.pending.iter_mut().zip(frame) {
+                channel.push(sample as i32);
+            }
+            if self.pending[0].len() == BLOCK_SIZE {
+                self.flush_block()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Encode what is left and fill in STREAMINFO, returning the writer
+    pub fn finish(mut self) -> Result<W> {
+        if !self.pending[0].is_empty() {
+            self.flush_block()?;
+        }
+        let info = streaminfo(
+            self.sample_rate,
+            self.channels,
+            self.total_samples,
+            if self.frame_number == 0 { 0 } else { self.min_frame_size },
+            self.max_frame_size,
+        );
+        self.writer.seek(SeekFrom::Start(STREAMINFO_OFFSET))?;
+        self.writer.write_all(&info)?;
+        self.writer.seek(SeekFrom::End(0))?;
+        self.writer.flush().context("Failed to write FLAC stream")?;
+        Ok(self.writer)
+    }
+
+    fn flush_block(&mut self) -> Result<()> {
+        let frame = encode_frame(&self.pending, self.frame_number);
+        self.writer.write_all(&frame).context("Failed to write FLAC frame")?;
+        self.frame_number += 1;
+        self.total_samples += self.pending[0].len() as u64;
+        self.min_frame_size = self.min_frame_size.min(frame.len() as u32);
+        self.max_frame_size = self.max_frame_size.max(frame.len() as u32);
+        for channel in &mut self.pending {
+            channel.clear();
+        }
+        Ok(())
+    }
+}
+
+/// VORBIS_COMMENT block contents: little-endian lengths, unlike the rest of FLAC
+fn vorbis_comment(tags: &[(&str, String)]) -> Vec<u8> {
+    let mut block = Vec::new();
+    let mut string = |text: &str| {
+        block.extend_from_slice(&(text.len() as u32).to_le_bytes());
+        block.extend_from_slice(text.as_bytes());
+    };
+    string(VENDOR);
+    let comments: Vec<_> = tags
+        .iter()
+        .map(|(name, value)| {
+            let field = match *name {
+                "album_artist" => "ALBUMARTIST".to_string(),
+                "track" => "TRACKNUMBER".to_string(),
+                name => name.to_uppercase(),
+            };
+            format!("{}={}", field, value)
+        })
+        .collect();
+    block.extend_from_slice(&(comments.len() as u32).to_le_bytes());
+    for comment in &comments {
+        block.extend_from_slice(&(comment.len() as u32).to_le_bytes());
+        block.extend_from_slice(comment.as_bytes());
+    }
+    block
+}
+
+fn streaminfo(sample_rate: u32, channels: usize, total_samples: u64, min_frame: u32, max_frame: u32) -> [u8; 34] {
+    let mut bits = BitWriter::default();
+    bits.put(BLOCK_SIZE as u64, 16);
+    bits.put(BLOCK_SIZE as u64, 16);
+    bits.put(min_frame as u64, 24);
+    bits.put(max_frame as u64, 24);
+    bits.put(sample_rate as u64, 20);
+    bits.put(channels as u64 - 1, 3);
+    bits.put(15, 5);
+    bits.put(total_samples >> 32, 4);
+    bits.put(total_samples & 0xffff_ffff, 32);
+    let mut info = [0; 34];
+    info[..18].copy_from_slice(&bits.bytes);
+    info
+}
+
+/// One frame holding a block of every channel, coded independently
+fn encode_frame(channels: &[Vec<i32>], frame_number: u64) -> Vec<u8> {
+    let mut bits = BitWriter::default();
+    // Sync code and fixed-blocksize strategy
+    bits.put(0xfff8, 16);
+    // Block size in 16 bits after the frame number; sample rate from STREAMINFO
+    bits.put(0b0111, 4);
+    bits.put(0, 4);
+    bits.put(channels.len() as u64 - 1, 4);
+    // 16 bits per sample
+    bits.put(0b100, 3);
+    bits.put(0, 1);
+    for byte in utf8_number(frame_number) {
+        bits.put(byte as u64, 8);
+    }
+    bits.put(channels[0].len() as u64 - 1, 16);
+    let header_crc = crc8(&bits.bytes);
+    bits.put(header_crc as u64, 8);
+
+    for samples in channels {
+        encode_subframe(&mut bits, samples);
+    }
+    bits.align();
+    let crc = crc16(&bits.bytes);
+    bits.put(crc as u64, 16);
+    bits.bytes
+}
+
+fn encode_subframe(bits: &mut BitWriter, samples: &[i32]) {
+    let max_order = MAX_ORDER.min(samples.len() - 1);
+    let (order, residuals, param, cost) = (0..=max_order)
+        .map(|order| {
+            let residuals = residuals(samples, order);
+            let (param, cost) = rice_param(&residuals);
+            (order, residuals, param, cost + 16 * order as u64)
+        })
+        .min_by_key(|(_, _, _, cost)| *cost)
+        .unwrap();
+
+    // Subframe header: zero padding bit, type, no wasted bits
+    bits.put(0, 1);
+    if cost >= 16 * samples.len() as u64 {
+        bits.put(0b000001, 6);
+        bits.put(0, 1);
+        for &sample in samples {
+            bits.put(sample as u64, 16);
+        }
+        return;
+    }
+    bits.put(0b001000 | order as u64, 6);
+    bits.put(0, 1);
+    for &sample in &samples[..order] {
+        bits.put(sample as u64, 16);
+    }
+    // Rice coding with 4-bit parameters, one partition
+    bits.put(0, 2);
+    bits.put(0, 4);
+    bits.put(param as u64, 4);
+    for &residual in &residuals {
+        let value = zigzag(residual);
+        bits.unary(value >> param);
+        bits.put(value as u64, param);
+    }
+}
+
+/// What FLAC's fixed predictor of `order` leaves after predicting each
+/// sample from the ones before it
+pub fn residuals(samples: &[i32], order: usize) -> Vec<i32> {
+    (order..samples.len())
+        .map(|i| {
+            let s = |back: usize| samples[i - back];
+            match order {
+                0 => s(0),
+                1 => s(0) - s(1),
+                2 => s(0) - 2 * s(1) + s(2),
+                3 => s(0) - 3 * s(1) + 3 * s(2) - s(3),
+                _ => s(0) - 4 * s(1) + 6 * s(2) - 4 * s(3) + s(4),
+            }
+        })
+        .collect()
+}
+
+/// Cheapest Rice parameter for `residuals` and the bits it needs
+fn rice_param(residuals: &[i32]) -> (u32, u64) {
+    (0..=MAX_RICE_PARAM)
+        .map(|param| {
+            let cost = residuals
+                .iter()
+                .map(|&r| (zigzag(r) >> param) as u64 + 1 + param as u64)
+                .sum::<u64>();
+            (param, cost)
+        })
+        .min_by_key(|(_, cost)| *cost)
+        .unwrap()
+}
+
+fn zigzag(value: i32) -> u32 {
+    ((value << 1) ^ (value >> 31)) as u32
+}
+
+/// Frame number in FLAC's extended UTF-8 coding
+fn utf8_number(value: u64) -> Vec<u8> {
+    if value < 0x80 {
+        return vec![value as u8];
+    }
+    let mut len = 2;
+    while value >= 1 << (5 * len + 1) {
+        len += 1;
+    }
+    let mut bytes = vec![(0xff00u16 >> len) as u8 | (value >> (6 * (len - 1))) as u8];
+    for i in (0..len - 1).rev() {
+        bytes.push(0x80 | ((value >> (6 * i)) & 0x3f) as u8);
+    }
+    bytes
+}
+
+fn crc8(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |mut crc, &byte| {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 { (crc << 1) ^ 0x07 } else { crc << 1 };
+        }
+        crc
+    })
+}
+
+fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0u16, |mut crc, &byte| {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x8005 } else { crc << 1 };
+        }
+        crc
+    })
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    /// Bits not yet making up a whole byte, fewer than 8
+    acc: u64,
+    len: u32,
+}
+
+impl BitWriter {
+    /// Append the low `count` bits of `value`, at most 32
+    fn put(&mut self, value: u64, count: u32) {
+        let mask = (1u64 << count) - 1;
+        self.acc = (self.acc << count) | (value & mask);
+        self.len += count;
+        while self.len >= 8 {
+            self.len -= 8;
+            self.bytes.push((self.acc >> self.len) as u8);
+        }
+        self.acc &= (1 << self.len) - 1;
+    }
+
+    /// `value` zeros followed by a one
+    fn unary(&mut self, mut value: u32) {
+        while value >= 32 {
+            self.put(0, 32);
+            value -= 32;
+        }
+        self.put(1, value + 1);
+    }
+
+    fn align(&mut self) {
+        if self.len > 0 {
+            self.put(0, 8 - self.len);
+        }
+    }
+}
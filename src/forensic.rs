@@ -0,0 +1,173 @@
+use crate::cache::hash_file;
+use crate::isolation;
+use anyhow::{Context, Result};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Size and hashes of a file involved in an extraction
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FileEvidence {
+    pub path: PathBuf,
+    pub size: u64,
+    pub sha256: String,
+    /// Per-stream SHA-256 of the demuxed packets, when FFmpeg is available
+    pub streams: Vec<StreamHash>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StreamHash {
+    pub index: usize,
+    /// FFmpeg media type letter: `a` for audio, `v` for video, ...
+    pub kind: String,
+    pub sha256: String,
+}
+
+/// Outcome of probing the output during the extraction
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct VerificationEvidence {
+    pub passed: bool,
+    pub format: Option<String>,
+    pub duration: Option<f64>,
+    pub channels: Option<usize>,
+    pub sample_rate: Option<u32>,
+    pub error: Option<String>,
+}
+
+/// Everything needed to reproduce and attest one extraction
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ForensicReport {
+    pub tool: String,
+    pub tool_version: String,
+    pub ffmpeg_version: Option<String>,
+    pub started_at: String,
+    pub finished_at: String,
+    pub user: String,
+    pub host: String,
+    pub input: FileEvidence,
+    pub output: Option<FileEvidence>,
+    pub command_line: Vec<String>,
+    pub settings: serde_json::Value,
+    pub success: bool,
+    pub error: Option<String>,
+    pub verification: Option<VerificationEvidence>,
+}
+
+/// A report together with an ed25519 signature over its JSON serialization
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SignedReport {
+    pub report: ForensicReport,
+    /// Hex-encoded ed25519 public key
+    pub public_key: String,
+    /// Hex-encoded signature of `serde_json::to_vec(&report)`
+    pub signature: String,
+}
+
+impl SignedReport {
+    pub fn sign(report: ForensicReport, key: &SigningKey) -> Result<Self> {
+        let message = serde_json::to_vec(&report).context("Failed to serialize forensic report")?;
+        let signature = key.sign(&message);
+        Ok(Self {
+            report,
+            public_key: to_hex(key.verifying_key().as_bytes()),
+            signature: to_hex(&signature.to_bytes()),
+        })
+    }
+
+    /// Check the signature against the embedded public key
+    pub fn verify(&self) -> Result<()> {
+        let public_key: [u8; 32] = from_hex(&self.public_key)?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("Public key must be 32 bytes"))?;
+        let signature: [u8; 64] = from_hex(&self.signature)?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("Signature must be 64 bytes"))?;
+
+        let key = VerifyingKey::from_bytes(&public_key).context("Invalid public key")?;
+        let message = serde_json::to_vec(&self.report)?;
+        key.verify(&message, &Signature::from_bytes(&signature))
+            .context("Forensic report signature does not match its contents")
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json).with_context(|| format!("Failed to write forensic report {:?}", path))
+    }
+}
+
+/// Load an ed25519 signing key stored as a 32-byte seed, raw or hex-encoded
+pub fn load_signing_key(path: &Path) -> Result<SigningKey> {
+    let data = std::fs::read(path).with_context(|| format!("Failed to read signing key {:?}", path))?;
+    let seed = match data.len() {
+        32 => data,
+        _ => from_hex(String::from_utf8_lossy(&data).trim())
+            .context("Signing key must be a 32-byte seed, raw or as 64 hex characters")?,
+    };
+    let seed: [u8; 32] = seed
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Signing key must be a 32-byte seed"))?;
+    Ok(SigningKey::from_bytes(&seed))
+}
+
+/// Hash a file and, if FFmpeg can demux it, each of its streams
+pub fn file_evidence(path: &Path, with_streams: bool) -> Result<FileEvidence> {
+    let size = std::fs::metadata(path)
+        .with_context(|| format!("Failed to read metadata of {:?}", path))?
+        .len();
+    Ok(FileEvidence {
+        path: path.to_path_buf(),
+        size,
+        sha256: hash_file(path)?,
+        streams: if with_streams { stream_hashes(path).unwrap_or_default() } else { Vec::new() },
+    })
+}
+
+/// SHA-256 of every stream's packets, using FFmpeg's streamhash muxer
+pub fn stream_hashes(path: &Path) -> Result<Vec<StreamHash>> {
+    let output = isolation::ffmpeg()
+        .arg("-v").arg("error")
+        .arg("-i").arg(path)
+        .arg("-map").arg("0")
+        .arg("-c").arg("copy")
+        .arg("-f").arg("streamhash")
+        .arg("-hash").arg("sha256")
+        .arg("-")
+        .output()
+        .context("Failed to run ffmpeg")?;
+    if !output.status.success() {
+        anyhow::bail!("ffmpeg could not hash streams of {:?}", path);
+    }
+
+    // Lines look like `0,a,SHA256=<hex>`
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, ',');
+            let index = parts.next()?.trim().parse().ok()?;
+            let kind = parts.next()?.to_string();
+            let sha256 = parts.next()?.split_once('=')?.1.to_lowercase();
+            Some(StreamHash { index, kind, sha256 })
+        })
+        .collect())
+}
+
+/// First line of `ffmpeg -version`, if FFmpeg is installed
+pub fn ffmpeg_version() -> Option<String> {
+    let output = Command::new("ffmpeg").arg("-version").output().ok()?;
+    String::from_utf8_lossy(&output.stdout).lines().next().map(str::to_string)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(hex: &str) -> Result<Vec<u8>> {
+    if !hex.is_ascii() || !hex.len().is_multiple_of(2) {
+        anyhow::bail!("Invalid hex string");
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).context("Invalid hex string"))
+        .collect()
+}
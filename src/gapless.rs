@@ -0,0 +1,149 @@
+use crate::AudioFormat;
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+
+/// Encoder delay and padding stored in an output, in samples, which players
+/// trim so consecutive tracks join without a gap
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GaplessInfo {
+    pub delay: u32,
+    /// Samples added after the audio; not recorded by MP4 edit lists
+    pub padding: Option<u32>,
+}
+
+impl std::fmt::Display for GaplessInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "encoder delay {} samples", self.delay)?;
+        if let Some(padding) = self.padding {
+            write!(f, ", padding {} samples", padding)?;
+        }
+        Ok(())
+    }
+}
+
+/// Check that `output` carries what gapless playback needs. WAV, FLAC, AIFF
+/// and WavPack have no encoder delay, and Ogg streams always record theirs as
+/// the pre-skip and final granule position, so they pass with `None`.
+pub fn check(output: &Path, format: &AudioFormat) -> Result<Option<GaplessInfo>> {
+    let is_mp4 = output.extension()
+        .is_some_and(|ext| ["m4a", "m4b", "mp4"].iter().any(|mp4| ext.eq_ignore_ascii_case(mp4)));
+    match format {
+        AudioFormat::Wav | AudioFormat::Flac | AudioFormat::Aiff | AudioFormat::WavPack => Ok(None),
+        AudioFormat::Opus | AudioFormat::Ogg | AudioFormat::Speex => Ok(None),
+        AudioFormat::AmrNb | AudioFormat::AmrWb => anyhow::bail!("AMR files cannot carry gapless information"),
+        AudioFormat::Aac if !is_mp4 => {
+            anyhow::bail!("Raw AAC (ADTS) cannot carry gapless information; use an .m4a output")
+        }
+        AudioFormat::Mp3 | AudioFormat::Aac => {
+            let data = fs::read(output).with_context(|| format!("Failed to read {:?}", output))?;
+            let info = match format {
+                AudioFormat::Mp3 => lame_header(&data).context("The MP3 has no LAME header with encoder delay and padding")?,
+                _ => mp4_edit_list(&data).context("The MP4 has no edit list trimming the encoder delay")?,
+            };
+            Ok(Some(info))
+        }
+    }
+}
+
+/// Encoder delay and padding from the LAME tag in the first MP3 frame, as
+/// written by LAME and FFmpeg's MP3 muxer
+pub fn lame_header(data: &[u8]) -> Option<GaplessInfo> {
+    let mut start = 0;
+    if data.starts_with(b"ID3") && data.len() >= 10 {
+        let size = data[6..10].iter().fold(0usize, |size, &b| (size << 7) | (b & 0x7f) as usize);
+        let footer = if data[5] & 0x10 != 0 { 10 } else { 0 };
+        start = 10 + size + footer;
+    }
+    let frame = data.get(start..)?;
+    let sync = frame.windows(2).position(|w| w[0] == 0xff && w[1] & 0xe0 == 0xe0)?;
+    let frame = &frame[sync..];
+    let header = frame.get(..4)?;
+
+    // Side information length depends on the MPEG version and channel mode
+    let mpeg1 = header[1] & 0x18 == 0x18;
+    let mono = header[3] & 0xc0 == 0xc0;
+    let side_info = match (mpeg1, mono) {
+        (true, false) => 32,
+        (true, true) | (false, false) => 17,
+        (false, true) => 9,
+    };
+    let xing = 4 + side_info;
+    let tag = frame.get(xing..xing + 8)?;
+    if &tag[..4] != b"Xing" && &tag[..4] != b"Info" {
+        return None;
+    }
+
+    let flags = u32::from_be_bytes(tag[4..8].try_into().ok()?);
+    let mut lame = xing + 8;
+    for (flag, size) in [(1, 4), (2, 4), (4, 100), (8, 4)] {
+        if flags & flag != 0 {
+            lame += size;
+        }
+    }
+    // Encoder name (9 bytes), then revision, lowpass, ReplayGain, flags and
+    // bitrate before the 12-bit delay and padding
+    let encoder = frame.get(lame..lame + 4)?;
+    if encoder != b"LAME" && encoder != b"Lavc" && encoder != b"Lavf" {
+        return None;
+    }
+    let packed = frame.get(lame + 21..lame + 24)?;
+    Some(GaplessInfo {
+        delay: ((packed[0] as u32) << 4) | ((packed[1] as u32) >> 4),
+        padding: Some((((packed[1] & 0x0f) as u32) << 8) | packed[2] as u32),
+    })
+}
+
+/// Encoder delay from the first audio edit list in an MP4 file, which
+/// players use to skip the encoder's priming samples
+pub fn mp4_edit_list(data: &[u8]) -> Option<GaplessInfo> {
+    let moov = find_box(data, b"moov")?;
+    let mut rest = moov;
+    while let Some((trak, after)) = next_box(rest, b"trak") {
+        rest = after;
+        let Some(elst) = find_box(trak, b"edts").and_then(|edts| find_box(edts, b"elst")) else {
+            continue;
+        };
+        let version = *elst.first()?;
+        let count = u32::from_be_bytes(elst.get(4..8)?.try_into().ok()?) as usize;
+        let entry_size = if version == 1 { 20 } else { 12 };
+        for entry in 0..count {
+            let offset = 8 + entry * entry_size;
+            let media_time = if version == 1 {
+                i64::from_be_bytes(elst.get(offset + 8..offset + 16)?.try_into().ok()?)
+            } else {
+                i32::from_be_bytes(elst.get(offset + 4..offset + 8)?.try_into().ok()?) as i64
+            };
+            // -1 marks an empty edit
+            if media_time >= 0 {
+                return Some(GaplessInfo { delay: media_time as u32, padding: None });
+            }
+        }
+    }
+    None
+}
+
+/// Contents of the first box named `name` among `data`'s top-level boxes
+fn find_box<'a>(data: &'a [u8], name: &[u8; 4]) -> Option<&'a [u8]> {
+    next_box(data, name).map(|(content, _)| content)
+}
+
+/// Contents of the first box named `name`, and the boxes after it
+fn next_box<'a>(mut data: &'a [u8], name: &[u8; 4]) -> Option<(&'a [u8], &'a [u8])> {
+    while data.len() >= 8 {
+        let size = u32::from_be_bytes(data[..4].try_into().ok()?) as usize;
+        let (header, size) = match size {
+            1 => (16, u64::from_be_bytes(data.get(8..16)?.try_into().ok()?) as usize),
+            0 => (8, data.len()),
+            size => (8, size),
+        };
+        if size < header || size > data.len() {
+            return None;
+        }
+        if &data[4..8] == name {
+            return Some((&data[header..size], &data[size..]));
+        }
+        data = &data[size..];
+    }
+    None
+}
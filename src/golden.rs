@@ -0,0 +1,255 @@
+use crate::analysis::{ScratchWav, ANALYSIS_SAMPLE_RATE};
+use crate::isolation;
+use crate::limits::ResourceLimits;
+use crate::{deserialize_format, say, Args, AudioExtractor, AudioFormat};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Default similarity below which a changed output fails the check
+pub const DEFAULT_MIN_SIMILARITY: f64 = 0.99;
+
+/// Length of the loudness envelope frames compared between outputs
+const ENVELOPE_FRAME_SECONDS: f64 = 0.02;
+
+/// Largest offset, in envelope frames, tolerated between outputs, e.g. from a
+/// changed encoder delay
+const MAX_ENVELOPE_LAG: usize = 5;
+
+/// Name of the stored golden results inside the golden directory
+const INDEX_FILE: &str = "golden.json";
+
+/// Reference inputs to extract, from a TOML file with `[[reference]]` entries
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct GoldenManifest {
+    pub reference: Vec<Reference>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Reference {
+    /// Input path, relative to the manifest
+    pub input: PathBuf,
+    /// Name in the results [default: the input's file stem]
+    pub name: Option<String>,
+    /// Output format as given to --format, e.g. `mp3` [default: flac]
+    #[serde(default, deserialize_with = "deserialize_format")]
+    pub format: Option<AudioFormat>,
+    pub quality: Option<u32>,
+}
+
+impl Reference {
+    pub fn name(&self) -> String {
+        self.name.clone().unwrap_or_else(|| {
+            self.input.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default()
+        })
+    }
+}
+
+impl GoldenManifest {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path).with_context(|| format!("Failed to read golden manifest {:?}", path))?;
+        let mut manifest: Self = toml::from_str(&content).with_context(|| format!("Invalid golden manifest {:?}", path))?;
+        let base = path.parent().unwrap_or(Path::new(""));
+        for reference in &mut manifest.reference {
+            reference.input = base.join(&reference.input);
+        }
+        Ok(manifest)
+    }
+}
+
+/// Stored result of extracting one reference input
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GoldenResult {
+    pub name: String,
+    /// SHA-256 of the output decoded to PCM, independent of container metadata
+    pub pcm_sha256: String,
+    /// Loudness envelope of the output in dB, one value per 20 ms
+    pub envelope: Vec<f32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GoldenIndex {
+    pub created_at: String,
+    pub tool_version: String,
+    pub ffmpeg_version: Option<String>,
+    pub results: Vec<GoldenResult>,
+}
+
+/// How a fresh output compares to its golden result
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verdict {
+    /// Decoded PCM is bit-identical
+    Identical,
+    /// PCM changed, but the output sounds the same within the threshold
+    Similar(f64),
+    Different(f64),
+    /// No golden result was recorded for this reference
+    Missing,
+    Failed(String),
+}
+
+impl Verdict {
+    pub fn passed(&self) -> bool {
+        matches!(self, Verdict::Identical | Verdict::Similar(_))
+    }
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Verdict::Identical => write!(f, "identical"),
+            Verdict::Similar(score) => write!(f, "similar (score {:.4})", score),
+            Verdict::Different(score) => write!(f, "DIFFERENT (score {:.4})", score),
+            Verdict::Missing => write!(f, "MISSING golden result; run `golden record`"),
+            Verdict::Failed(error) => write!(f, "FAILED: {}", error),
+        }
+    }
+}
+
+/// Extract every reference and store the results in `dir` as the new goldens
+pub fn record(manifest: &GoldenManifest, dir: &Path, limits: &ResourceLimits) -> Result<GoldenIndex> {
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create {:?}", dir))?;
+    let mut results = Vec::new();
+    for reference in &manifest.reference {
+        say!("Recording {}", reference.name());
+        results.push(extract_result(reference, limits)?);
+    }
+    let index = GoldenIndex {
+        created_at: chrono::Utc::now().to_rfc3339(),
+        tool_version: env!("CARGO_PKG_VERSION").to_string(),
+        ffmpeg_version: crate::forensic::ffmpeg_version(),
+        results,
+    };
+    fs::write(dir.join(INDEX_FILE), serde_json::to_string_pretty(&index)?).context("Failed to write golden results")?;
+    Ok(index)
+}
+
+/// Re-extract every reference and compare it with the goldens in `dir`
+pub fn check(manifest: &GoldenManifest, dir: &Path, min_similarity: f64, limits: &ResourceLimits) -> Result<Vec<(String, Verdict)>> {
+    let path = dir.join(INDEX_FILE);
+    let content = fs::read_to_string(&path).with_context(|| format!("Failed to read golden results {:?}", path))?;
+    let index: GoldenIndex = serde_json::from_str(&content).with_context(|| format!("Invalid golden results {:?}", path))?;
+
+    let mut verdicts = Vec::new();
+    for reference in &manifest.reference {
+        let name = reference.name();
+        let verdict = match index.results.iter().find(|golden| golden.name == name) {
+            None => Verdict::Missing,
+            Some(golden) => match extract_result(reference, limits) {
+                Ok(result) => compare(golden, &result, min_similarity),
+                Err(e) => Verdict::Failed(format!("{:#}", e)),
+            },
+        };
+        say!("{}: {}", name, verdict);
+        verdicts.push((name, verdict));
+    }
+    Ok(verdicts)
+}
+
+pub fn compare(golden: &GoldenResult, result: &GoldenResult, min_similarity: f64) -> Verdict {
+    if golden.pcm_sha256 == result.pcm_sha256 {
+        return Verdict::Identical;
+    }
+    let score = similarity(&golden.envelope, &result.envelope);
+    if score >= min_similarity {
+        Verdict::Similar(score)
+    } else {
+        Verdict::Different(score)
+    }
+}
+
+fn extract_result(reference: &Reference, limits: &ResourceLimits) -> Result<GoldenResult> {
+    let format = reference.format.clone().unwrap_or(AudioFormat::Flac);
+    let scratch = tempfile_dir()?;
+    let output = scratch.join(format!("{}.{}", reference.name(), format));
+    let result = (|| {
+        AudioExtractor::new(Args {
+            input: reference.input.clone(),
+            output: output.clone(),
+            format: Some(format),
+            quality: reference.quality,
+            ..Default::default()
+        })
+        .extract()?;
+
+        let decoded = ScratchWav::decode(&output)?;
+        Ok(GoldenResult {
+            name: reference.name(),
+            pcm_sha256: pcm_hash(&output, limits)?,
+            envelope: envelope(&decoded.samples()?, ANALYSIS_SAMPLE_RATE),
+        })
+    })();
+    let _ = fs::remove_dir_all(&scratch);
+    result
+}
+
+fn tempfile_dir() -> Result<PathBuf> {
+    let dir = std::env::temp_dir().join(format!("audio_extractor-golden-{}", std::process::id()));
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// SHA-256 of the audio decoded to raw PCM at its native rate and layout
+pub fn pcm_hash(path: &Path, limits: &ResourceLimits) -> Result<String> {
+    let mut cmd = isolation::ffmpeg();
+    cmd.arg("-v").arg("error")
+        .arg("-i").arg(path)
+        .arg("-map").arg("0:a:0")
+        .arg("-f").arg("s16le")
+        .arg("-");
+    let output = limits.output(&mut cmd).context("Failed to run ffmpeg")?;
+    if !output.status.success() {
+        anyhow::bail!("Failed to decode {:?}: {}", path, String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(Sha256::digest(&output.stdout).iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// RMS level in dB of consecutive 20 ms frames
+pub fn envelope(samples: &[i16], sample_rate: u32) -> Vec<f32> {
+    let frame = ((sample_rate as f64 * ENVELOPE_FRAME_SECONDS) as usize).max(1);
+    samples
+        .chunks(frame)
+        .map(|chunk| {
+            let power = chunk.iter().map(|&s| (s as f64 / 32768.0).powi(2)).sum::<f64>() / chunk.len() as f64;
+            (10.0 * power.max(1e-10).log10()) as f32
+        })
+        .collect()
+}
+
+/// Pearson correlation of two loudness envelopes at the best small offset,
+/// scaled down by how much their lengths differ. 1.0 means they match.
+pub fn similarity(a: &[f32], b: &[f32]) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return if a.len() == b.len() { 1.0 } else { 0.0 };
+    }
+    let length_ratio = a.len().min(b.len()) as f64 / a.len().max(b.len()) as f64;
+    let best = (0..=MAX_ENVELOPE_LAG)
+        .flat_map(|lag| [correlation(&a[lag.min(a.len())..], b), correlation(a, &b[lag.min(b.len())..])])
+        .fold(f64::MIN, f64::max);
+    best.max(0.0) * length_ratio
+}
+
+fn correlation(a: &[f32], b: &[f32]) -> f64 {
+    let n = a.len().min(b.len());
+    if n == 0 {
+        return 0.0;
+    }
+    let (a, b) = (&a[..n], &b[..n]);
+    let mean_a = a.iter().map(|&x| x as f64).sum::<f64>() / n as f64;
+    let mean_b = b.iter().map(|&x| x as f64).sum::<f64>() / n as f64;
+    let (mut cov, mut var_a, mut var_b) = (0.0, 0.0, 0.0);
+    for (&x, &y) in a.iter().zip(b) {
+        let (dx, dy) = (x as f64 - mean_a, y as f64 - mean_b);
+        cov += dx * dy;
+        var_a += dx * dx;
+        var_b += dy * dy;
+    }
+    if var_a == 0.0 || var_b == 0.0 {
+        // Flat envelopes (e.g. silence) only match each other
+        return if (mean_a - mean_b).abs() < 1.0 { 1.0 } else { 0.0 };
+    }
+    cov / (var_a * var_b).sqrt()
+}
@@ -0,0 +1,172 @@
+use crate::metadata::SourceMetadata;
+use crate::{say, AudioFormat};
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// What a hook script sees of a job, as the global `job` table
+#[derive(Debug, Clone, PartialEq)]
+pub struct Job {
+    pub input: PathBuf,
+    pub output: PathBuf,
+    pub format: AudioFormat,
+    pub quality: u32,
+    pub metadata: SourceMetadata,
+}
+
+/// Changes a hook script made to its job
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HookOutcome {
+    pub format: Option<AudioFormat>,
+    pub quality: Option<u32>,
+    pub output: Option<PathBuf>,
+    /// Set when the script skipped the input, with its reason
+    pub skip: Option<String>,
+}
+
+impl HookOutcome {
+    /// Apply the changes to a job's arguments. A new format without a new
+    /// output path changes the output's extension to match.
+    pub fn apply(&self, args: &mut crate::Args) {
+        if let Some(quality) = self.quality {
+            args.quality = Some(quality);
+        }
+        if let Some(format) = &self.format {
+            if self.output.is_none() {
+                args.output.set_extension(format.to_string());
+            }
+            args.format = Some(format.clone());
+        }
+        if let Some(output) = &self.output {
+            args.output = output.clone();
+        }
+    }
+}
+
+/// Run a Lua hook script on `job` with the system `lua` interpreter.
+///
+/// The script runs after a prelude defining the `job` table and may change
+/// `job.format`, `job.quality` and `job.output`, or set `job.skip = true`
+/// (with an optional `job.skip_reason`). The changed fields are read back
+/// from what a postlude prints.
+pub fn run(script: &Path, job: &Job) -> Result<HookOutcome> {
+    let body = std::fs::read_to_string(script).with_context(|| format!("Failed to read hook script {:?}", script))?;
+    // Wrapping the script in a function keeps a `return` in it from skipping the postlude
+    let program = format!("{}\nlocal function hook()\n{}\nend\nhook()\n{}", prelude(job), body, POSTLUDE);
+
+    let output = Command::new("lua")
+        .arg("-e").arg(&program)
+        .output()
+        .context("Failed to run lua; hook scripts need a Lua 5.x interpreter on the PATH")?;
+    if !output.status.success() {
+        anyhow::bail!("Hook script {:?} failed: {}", script, String::from_utf8_lossy(&output.stderr).trim());
+    }
+    parse_outcome(&String::from_utf8_lossy(&output.stdout), job)
+}
+
+/// Prints the job's mutable fields back, one `key<TAB>value` line each
+const POSTLUDE: &str = r#"
+for _, key in ipairs({"format", "quality", "output", "skip", "skip_reason"}) do
+  local value = job[key]
+  if value ~= nil then
+    io.write("@hook\t", key, "\t", (tostring(value):gsub("\n", " ")), "\n")
+  end
+end
+"#;
+
+/// Lua source defining the global `job` table
+pub fn prelude(job: &Job) -> String {
+    let metadata = &job.metadata;
+    let mut tags = String::new();
+    let text = [
+        ("title", &metadata.title),
+        ("artist", &metadata.artist),
+        ("album", &metadata.album),
+        ("show", &metadata.show),
+        ("genre", &metadata.genre),
+        ("recorded_date", &metadata.recorded_date),
+        ("description", &metadata.description),
+    ];
+    for (key, value) in text {
+        if let Some(value) = value {
+            let _ = write!(tags, "{} = {}, ", key, lua_string(value));
+        }
+    }
+    for (key, value) in [("season", metadata.season), ("episode", metadata.episode)] {
+        if let Some(value) = value {
+            let _ = write!(tags, "{} = {}, ", key, value);
+        }
+    }
+
+    format!(
+        "job = {{ input = {}, output = {}, format = {}, quality = {}, duration = {}, tags = {{ {}}}, skip = false }}",
+        lua_string(&job.input.to_string_lossy()),
+        lua_string(&job.output.to_string_lossy()),
+        lua_string(&job.format.to_string()),
+        job.quality,
+        metadata.duration.map_or("nil".to_string(), |d| d.to_string()),
+        tags
+    )
+}
+
+/// Read the fields the postlude printed, keeping only those that changed
+pub fn parse_outcome(stdout: &str, job: &Job) -> Result<HookOutcome> {
+    let mut outcome = HookOutcome::default();
+    let mut skip = false;
+    let mut skip_reason = None;
+    for line in stdout.lines() {
+        // Anything else the script printed is passed through
+        let Some(field) = line.strip_prefix("@hook\t") else {
+            say!("{}", line);
+            continue;
+        };
+        let (key, value) = field.split_once('\t').unwrap_or((field, ""));
+        match key {
+            "format" => {
+                let format = AudioFormat::from_str(value, true)
+                    .map_err(|e| anyhow::anyhow!("Hook set an invalid format '{}': {}", value, e))?;
+                if format != job.format {
+                    outcome.format = Some(format);
+                }
+            }
+            "quality" => {
+                // Lua 5.3+ prints numbers computed with floats as e.g. `192.0`
+                let quality = value.parse::<f64>().ok()
+                    .filter(|q| q.fract() == 0.0 && *q >= 0.0 && *q <= u32::MAX as f64)
+                    .with_context(|| format!("Hook set an invalid quality '{}'", value))? as u32;
+                if quality != job.quality {
+                    outcome.quality = Some(quality);
+                }
+            }
+            "output" if Path::new(value) != job.output => outcome.output = Some(PathBuf::from(value)),
+            "skip" => skip = value == "true",
+            "skip_reason" => skip_reason = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    if skip {
+        outcome.skip = Some(skip_reason.unwrap_or_else(|| "skipped by hook".to_string()));
+    }
+    Ok(outcome)
+}
+
+/// A double-quoted Lua string literal
+fn lua_string(value: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            c if c.is_control() => {
+                let _ = write!(quoted, "\\{}", c as u32);
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
@@ -0,0 +1,191 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use std::fs;
+use std::path::Path;
+
+#[derive(Clone, Copy, ValueEnum, Debug, PartialEq)]
+pub enum Id3Version {
+    #[value(name = "2.3")]
+    V23,
+    #[value(name = "2.4")]
+    V24,
+}
+
+impl Id3Version {
+    /// Value of FFmpeg's `-id3v2_version`
+    pub fn ffmpeg_value(&self) -> &'static str {
+        match self {
+            Id3Version::V23 => "3",
+            Id3Version::V24 => "4",
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum, Debug, PartialEq)]
+pub enum TextEncoding {
+    /// ISO-8859-1; text it cannot represent is written as UTF-16
+    Latin1,
+    /// UTF-16 with a byte order mark
+    Utf16,
+    /// UTF-8, ID3v2.4 only
+    Utf8,
+}
+
+impl TextEncoding {
+    fn byte(&self) -> u8 {
+        match self {
+            TextEncoding::Latin1 => 0,
+            TextEncoding::Utf16 => 1,
+            TextEncoding::Utf8 => 3,
+        }
+    }
+}
+
+/// Re-encode the text frames of an MP3's ID3v2 tag, keeping its version and
+/// every other frame as it is. The file is replaced atomically.
+pub fn rewrite_encoding(path: &Path, encoding: TextEncoding) -> Result<()> {
+    let data = fs::read(path).with_context(|| format!("Failed to read {:?}", path))?;
+    let Some(tag) = rewrite_tag(&data, encoding)? else {
+        return Ok(());
+    };
+
+    let mut temp = path.as_os_str().to_os_string();
+    temp.push(".id3");
+    fs::write(&temp, [tag.as_slice(), &data[tag_len(&data).unwrap_or(0)..]].concat())
+        .with_context(|| format!("Failed to write {:?}", temp))?;
+    fs::rename(&temp, path).with_context(|| format!("Failed to replace {:?}", path))
+}
+
+/// Length of the ID3v2 tag at the start of `data`, header included
+pub fn tag_len(data: &[u8]) -> Option<usize> {
+    if !data.starts_with(b"ID3") || data.len() < 10 {
+        return None;
+    }
+    let footer = if data[5] & 0x10 != 0 { 10 } else { 0 };
+    Some(10 + syncsafe(&data[6..10]) + footer)
+}
+
+/// The ID3v2 tag at the start of `data` with its text frames in `encoding`,
+/// or `None` if there is no tag
+pub fn rewrite_tag(data: &[u8], encoding: TextEncoding) -> Result<Option<Vec<u8>>> {
+    let Some(len) = tag_len(data) else {
+        return Ok(None);
+    };
+    let version = data[3];
+    if !matches!(version, 3 | 4) {
+        anyhow::bail!("Only ID3v2.3 and ID3v2.4 tags can be re-encoded, not ID3v2.{}", version);
+    }
+    if version == 3 && encoding == TextEncoding::Utf8 {
+        anyhow::bail!("ID3v2.3 tags cannot hold UTF-8 text; use --id3-version 2.4 or another --id3-encoding");
+    }
+    // Unsynchronisation and extended headers are not written by FFmpeg
+    if data[5] & 0xc0 != 0 {
+        anyhow::bail!("ID3 tags with unsynchronisation or an extended header are not supported");
+    }
+    let body = data.get(10..len.min(data.len())).context("Truncated ID3 tag")?;
+
+    let mut frames = Vec::new();
+    let mut rest = body;
+    while rest.len() >= 10 && rest[0] != 0 {
+        let id = &rest[..4];
+        // ID3v2.4 frame sizes are syncsafe, ID3v2.3 ones are plain integers
+        let size = if version == 4 {
+            syncsafe(&rest[4..8])
+        } else {
+            u32::from_be_bytes(rest[4..8].try_into()?) as usize
+        };
+        let flags = &rest[8..10];
+        let content = rest.get(10..10 + size).context("Truncated ID3 frame")?;
+        rest = &rest[10 + size..];
+
+        let content = reencode_frame(id, content, encoding)?;
+        frames.extend(id);
+        if version == 4 {
+            frames.extend(to_syncsafe(content.len()));
+        } else {
+            frames.extend((content.len() as u32).to_be_bytes());
+        }
+        frames.extend(flags);
+        frames.extend(content);
+    }
+
+    let mut tag = vec![b'I', b'D', b'3', version, 0, 0];
+    tag.extend(to_syncsafe(frames.len()));
+    tag.extend(frames);
+    Ok(Some(tag))
+}
+
+/// Frame content with its text in `encoding`. Text frames and comments or
+/// lyrics are converted; other frames are returned unchanged.
+fn reencode_frame(id: &[u8], content: &[u8], encoding: TextEncoding) -> Result<Vec<u8>> {
+    let prefix = match id {
+        [b'T', ..] => 1,
+        // Encoding byte and a three-letter language before the text
+        b"COMM" | b"USLT" => 4,
+        _ => return Ok(content.to_vec()),
+    };
+    let Some((&current, _)) = content.split_first() else {
+        return Ok(content.to_vec());
+    };
+    let text = decode_text(current, content.get(prefix..).unwrap_or_default())?;
+
+    let encoding = match encoding {
+        TextEncoding::Latin1 if text.chars().any(|c| c as u32 > 0xff) => TextEncoding::Utf16,
+        encoding => encoding,
+    };
+    let mut frame = vec![encoding.byte()];
+    frame.extend(&content[1..prefix]);
+    // Each string of a multi-string frame gets its own byte order mark
+    for (index, part) in text.split('\0').enumerate() {
+        if index > 0 {
+            frame.extend(terminator(encoding));
+        }
+        frame.extend(encode_text(part, encoding));
+    }
+    Ok(frame)
+}
+
+fn decode_text(encoding: u8, bytes: &[u8]) -> Result<String> {
+    Ok(match encoding {
+        0 => bytes.iter().map(|&b| b as char).collect(),
+        1 | 2 => {
+            // A byte order mark sets the order of the string that follows it
+            let mut big_endian = encoding == 2;
+            let mut units = Vec::new();
+            for pair in bytes.chunks_exact(2) {
+                match [pair[0], pair[1]] {
+                    [0xfe, 0xff] => big_endian = true,
+                    [0xff, 0xfe] => big_endian = false,
+                    pair if big_endian => units.push(u16::from_be_bytes(pair)),
+                    pair => units.push(u16::from_le_bytes(pair)),
+                }
+            }
+            String::from_utf16(&units).context("Invalid UTF-16 in ID3 frame")?
+        }
+        3 => String::from_utf8(bytes.to_vec()).context("Invalid UTF-8 in ID3 frame")?,
+        other => anyhow::bail!("Unknown ID3 text encoding {}", other),
+    }.trim_end_matches('\0').to_string())
+}
+
+fn encode_text(text: &str, encoding: TextEncoding) -> Vec<u8> {
+    match encoding {
+        TextEncoding::Latin1 => text.chars().map(|c| c as u8).collect(),
+        TextEncoding::Utf16 => [0xff, 0xfe].into_iter().chain(text.encode_utf16().flat_map(u16::to_le_bytes)).collect(),
+        TextEncoding::Utf8 => text.as_bytes().to_vec(),
+    }
+}
+
+fn terminator(encoding: TextEncoding) -> &'static [u8] {
+    match encoding {
+        TextEncoding::Utf16 => &[0, 0],
+        _ => &[0],
+    }
+}
+
+fn syncsafe(bytes: &[u8]) -> usize {
+    bytes.iter().fold(0, |size, &b| (size << 7) | (b & 0x7f) as usize)
+}
+
+fn to_syncsafe(size: usize) -> [u8; 4] {
+    [(size >> 21) as u8 & 0x7f, (size >> 14) as u8 & 0x7f, (size >> 7) as u8 & 0x7f, size as u8 & 0x7f]
+}
@@ -0,0 +1,95 @@
+use crate::rules::glob_match;
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Whether `input` is a glob pattern rather than a path
+pub fn is_pattern(input: &str) -> bool {
+    input.contains(['*', '?'])
+}
+
+/// Turn the inputs of `batch` into files, in the order given and each file
+/// once. Files are taken as they are. Directories give their files with one
+/// of `extensions`, and those of their subdirectories with `recursive`.
+/// Patterns such as `videos/*.mkv` or `shows/**/*.mp4` give the matching
+/// files with one of `extensions`, sorted by path.
+pub fn expand(inputs: &[String], recursive: bool, extensions: &[&str]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for input in inputs {
+        let path = Path::new(input);
+        let found = if is_pattern(input) {
+            let matches = matching(input, extensions)?;
+            if matches.is_empty() {
+                anyhow::bail!("No video files match {}", input);
+            }
+            matches
+        } else if path.is_dir() {
+            let mut found = Vec::new();
+            collect(path, recursive, &mut found)?;
+            found.retain(|file| has_extension(file, extensions));
+            found.sort();
+            found
+        } else if path.exists() {
+            vec![path.to_path_buf()]
+        } else {
+            anyhow::bail!("Input does not exist: {}", input);
+        };
+        files.extend(found);
+    }
+
+    let mut seen = HashSet::new();
+    files.retain(|file| seen.insert(file.clone()));
+    Ok(files)
+}
+
+/// Files matching `pattern`, searched from its last directory without wildcards
+fn matching(pattern: &str, extensions: &[&str]) -> Result<Vec<PathBuf>> {
+    let pattern = pattern.replace('\\', "/");
+    let components: Vec<&str> = pattern.split('/').collect();
+    let literal = components.iter().take_while(|c| !is_pattern(c)).count();
+    let base = match components[..literal].join("/") {
+        base if !base.is_empty() => PathBuf::from(base),
+        _ if pattern.starts_with('/') => PathBuf::from("/"),
+        _ => PathBuf::from("."),
+    };
+    let rest = components[literal..].join("/");
+    let recursive = rest.split('/').any(|c| c == "**") || rest.contains('/');
+
+    let mut found = Vec::new();
+    if base.is_dir() {
+        collect(&base, recursive, &mut found)?;
+    }
+    found.retain(|file| {
+        let relative = file.strip_prefix(&base).unwrap_or(file);
+        let relative: Vec<_> = relative
+            .components()
+            .filter_map(|c| match c {
+                Component::Normal(name) => Some(name.to_string_lossy()),
+                _ => None,
+            })
+            .collect();
+        glob_match(&rest, &relative.join("/")) && has_extension(file, extensions)
+    });
+    found.sort();
+    Ok(found)
+}
+
+fn collect(dir: &Path, recursive: bool, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir).with_context(|| format!("Failed to read directory {:?}", dir))? {
+        let entry = entry?;
+        let path = entry.path();
+        if !entry.file_type()?.is_dir() {
+            files.push(path);
+        } else if recursive {
+            collect(&path, recursive, files)?;
+        }
+    }
+    Ok(())
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| extensions.contains(&e.to_lowercase().as_str()))
+}
@@ -0,0 +1,72 @@
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+/// Variables FFmpeg and FFprobe get from our environment when it is
+/// scrubbed: what finding programs, libraries and temporary space needs
+pub const KEPT_VARIABLES: [&str; 10] = [
+    "PATH",
+    "HOME",
+    "TMPDIR",
+    "TMP",
+    "TEMP",
+    "LD_LIBRARY_PATH",
+    "DYLD_LIBRARY_PATH",
+    "DYLD_FALLBACK_LIBRARY_PATH",
+    "SYSTEMROOT",
+    "WINDIR",
+];
+
+/// The environment FFmpeg and FFprobe run in. By default it is scrubbed
+/// down to `KEPT_VARIABLES` with the C locale, so proxies, `LANG` and
+/// FFmpeg's own variables such as `FFREPORT` set for the tool do not change
+/// what a job does.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProcessEnvironment {
+    /// Pass our whole environment through instead
+    pub inherit: bool,
+    /// Variables passed through on top of `KEPT_VARIABLES`, e.g. `http_proxy`
+    pub keep: Vec<String>,
+    /// Variables set for the process, after the others
+    pub set: Vec<(String, String)>,
+    /// Working directory of the process, instead of ours
+    pub working_dir: Option<PathBuf>,
+}
+
+impl ProcessEnvironment {
+    /// Set up a command to be spawned in this environment
+    pub fn apply(&self, cmd: &mut Command) {
+        if !self.inherit {
+            cmd.env_clear();
+            for name in KEPT_VARIABLES.iter().copied().chain(self.keep.iter().map(String::as_str)) {
+                if let Some(value) = std::env::var_os(name) {
+                    cmd.env(name, value);
+                }
+            }
+            // FFmpeg's messages are parsed, so numbers must not follow the user's locale
+            cmd.env("LC_ALL", "C");
+        }
+        for (name, value) in &self.set {
+            cmd.env(name, value);
+        }
+        if let Some(dir) = &self.working_dir {
+            cmd.current_dir(dir);
+        }
+    }
+}
+
+/// An FFmpeg command that never waits on standard input: it is given
+/// `-nostdin` and its stdin is closed, so a prompt, such as whether to
+/// overwrite a file, cannot hang a job run without a terminal
+pub fn ffmpeg() -> Command {
+    let mut cmd = Command::new("ffmpeg");
+    cmd.arg("-nostdin").stdin(Stdio::null());
+    cmd
+}
+
+/// A `NAME=VALUE` pair for --ffmpeg-env
+pub fn parse_variable(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((name, value)) if !name.is_empty() => Ok((name.to_string(), value.to_string())),
+        _ => Err(format!("expected NAME=VALUE, got {:?}", s)),
+    }
+}
@@ -0,0 +1,227 @@
+use crate::limits::ResourceLimits;
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Seconds of audio encoded to calibrate `--jobs auto`
+pub const CALIBRATION_SECONDS: u32 = 10;
+
+/// How often a thread held back by a throttle checks whether it may start
+const THROTTLE_POLL: Duration = Duration::from_secs(1);
+
+/// Bytes read from an input to measure how fast the disk delivers it
+const READ_PROBE_BYTES: usize = 64 * 1024 * 1024;
+
+/// How many inputs of a batch are extracted at once
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Jobs {
+    /// Measured with a calibration encode before the batch starts
+    Auto,
+    Count(usize),
+}
+
+impl Default for Jobs {
+    fn default() -> Self {
+        Jobs::Count(1)
+    }
+}
+
+impl FromStr for Jobs {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if s.eq_ignore_ascii_case("auto") {
+            return Ok(Jobs::Auto);
+        }
+        match s.parse::<usize>() {
+            Ok(0) => anyhow::bail!("--jobs must be at least 1"),
+            Ok(count) => Ok(Jobs::Count(count)),
+            Err(_) => anyhow::bail!("'{}' is not a job count or 'auto'", s),
+        }
+    }
+}
+
+/// Order the inputs of a batch are started in
+#[derive(Clone, Copy, ValueEnum, Debug, Default, PartialEq)]
+pub enum Order {
+    /// Shortest first, for quick results and early failures
+    Shortest,
+    /// Longest first, so no job is left running a long input alone at the end
+    Longest,
+    /// Alphabetically by path
+    #[default]
+    Name,
+    /// Least recently modified first
+    Mtime,
+}
+
+/// Sort `inputs` into `order`. `duration` is only asked with `shortest` and
+/// `longest`. Inputs whose duration or modification time is unknown go
+/// last, and ties stay in path order.
+pub fn sort(inputs: &mut [PathBuf], order: Order, duration: impl Fn(&Path) -> Option<f64>) {
+    inputs.sort();
+    match order {
+        Order::Name => {}
+        Order::Shortest => sort_by_key(inputs, duration, false),
+        Order::Longest => sort_by_key(inputs, duration, true),
+        Order::Mtime => sort_by_key(inputs, |path| path.metadata().and_then(|m| m.modified()).ok(), false),
+    }
+}
+
+fn sort_by_key<K: PartialOrd>(inputs: &mut [PathBuf], key: impl Fn(&Path) -> Option<K>, descending: bool) {
+    let mut keyed: Vec<_> = inputs.iter().map(|path| (key(path), path.clone())).collect();
+    keyed.sort_by(|(a, _), (b, _)| match (a, b) {
+        (Some(a), Some(b)) => {
+            let ordering = a.partial_cmp(b).unwrap_or(Ordering::Equal);
+            if descending { ordering.reverse() } else { ordering }
+        }
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    });
+    for (input, (_, path)) in inputs.iter_mut().zip(keyed) {
+        *input = path;
+    }
+}
+
+/// What one extraction costs, measured on a short encode of a real input
+#[derive(Debug, Clone, PartialEq)]
+pub struct Calibration {
+    /// CPU cores one extraction keeps busy; FFmpeg decoders and encoders
+    /// may use more than one thread
+    pub cores_per_job: f64,
+    /// Bytes per second one extraction reads from its input
+    pub read_rate: f64,
+}
+
+impl Calibration {
+    /// Time `command`, which encodes `seconds` of an input whose audio takes
+    /// `input_bytes_per_second`, to see how much CPU and disk one job needs
+    pub fn measure(command: &mut Command, seconds: f64, input_bytes_per_second: f64, limits: &ResourceLimits) -> Result<Self> {
+        let cpu_before = children_cpu_seconds();
+        let started = Instant::now();
+        let output = limits.output(command).context("Failed to run the calibration encode")?;
+        let wall = started.elapsed().as_secs_f64().max(1e-3);
+        if !output.status.success() {
+            anyhow::bail!("Calibration encode failed: {}", String::from_utf8_lossy(&output.stderr));
+        }
+        let cores_per_job = match (cpu_before, children_cpu_seconds()) {
+            (Some(before), Some(after)) => ((after - before) / wall).max(0.1),
+            _ => 1.0,
+        };
+        Ok(Self {
+            cores_per_job,
+            read_rate: input_bytes_per_second * seconds / wall,
+        })
+    }
+}
+
+/// CPU time used so far by this process's finished children
+#[cfg(unix)]
+fn children_cpu_seconds() -> Option<f64> {
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    if unsafe { libc::getrusage(libc::RUSAGE_CHILDREN, &mut usage) } != 0 {
+        return None;
+    }
+    let seconds = |time: libc::timeval| time.tv_sec as f64 + time.tv_usec as f64 / 1e6;
+    Some(seconds(usage.ru_utime) + seconds(usage.ru_stime))
+}
+
+#[cfg(not(unix))]
+fn children_cpu_seconds() -> Option<f64> {
+    None
+}
+
+/// Bytes per second a sequential read of `path` gets. Files already in the
+/// page cache read faster than the disk could deliver them, so this is an
+/// upper bound.
+pub fn read_throughput(path: &Path) -> Option<f64> {
+    let mut file = File::open(path).ok()?;
+    let mut buffer = vec![0; 1024 * 1024];
+    let mut total = 0;
+    let started = Instant::now();
+    while total < READ_PROBE_BYTES {
+        match file.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(n) => total += n,
+            Err(_) => return None,
+        }
+    }
+    // Too little to time reliably
+    if total < 8 * 1024 * 1024 {
+        return None;
+    }
+    Some(total as f64 / started.elapsed().as_secs_f64().max(1e-6))
+}
+
+/// Concurrency that keeps every core busy without running more jobs than
+/// the cores or the disk can serve, and never more than there are inputs
+pub fn choose(cores: usize, calibration: Option<&Calibration>, disk_throughput: Option<f64>, inputs: usize) -> usize {
+    let mut jobs = cores.max(1);
+    if let Some(calibration) = calibration {
+        jobs = jobs.min((cores as f64 / calibration.cores_per_job).floor() as usize);
+        if let Some(throughput) = disk_throughput.filter(|_| calibration.read_rate > 0.0) {
+            jobs = jobs.min((throughput / calibration.read_rate).floor() as usize);
+        }
+    }
+    jobs.min(inputs).max(1)
+}
+
+/// Run `work` on each item on `jobs` threads. Items are taken in order, one
+/// at a time, and the results come back in the same order whichever
+/// finished first.
+pub fn run<T, R, I, F>(jobs: usize, items: I, work: F) -> Vec<R>
+where
+    T: Send,
+    R: Send,
+    I: Iterator<Item = T> + Send,
+    F: Fn(usize, T) -> R + Sync,
+{
+    run_throttled(jobs, items, || jobs, work)
+}
+
+/// Like `run`, but before taking an item a thread asks `allowed` how many
+/// jobs may run now. Threads past that number wait until it goes up again,
+/// finishing what they hold first.
+pub fn run_throttled<T, R, I, A, F>(jobs: usize, items: I, allowed: A, work: F) -> Vec<R>
+where
+    T: Send,
+    R: Send,
+    I: Iterator<Item = T> + Send,
+    A: Fn() -> usize + Sync,
+    F: Fn(usize, T) -> R + Sync,
+{
+    let items = Mutex::new(items.enumerate().peekable());
+    let results = Mutex::new(Vec::new());
+    thread::scope(|scope| {
+        for worker in 0..jobs.max(1) {
+            let (items, results, allowed, work) = (&items, &results, &allowed, &work);
+            scope.spawn(move || loop {
+                let next = {
+                    let mut items = items.lock().unwrap();
+                    if items.peek().is_none() {
+                        break;
+                    }
+                    (worker < allowed().max(1)).then(|| items.next()).flatten()
+                };
+                let Some((index, item)) = next else {
+                    thread::sleep(THROTTLE_POLL);
+                    continue;
+                };
+                let result = work(index, item);
+                results.lock().unwrap().push((index, result));
+            });
+        }
+    });
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
@@ -0,0 +1,85 @@
+use crate::dry_run::DryRun;
+use crate::progress::ProgressEvent;
+use crate::reporter;
+use crate::AudioExtractor;
+use serde::Serialize;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// One line of --json output
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum JsonEvent {
+    Started { input: PathBuf, output: PathBuf },
+    /// The source was probed; seconds of audio that will be extracted
+    Probed { duration: f64 },
+    Encoding { seconds_done: f64, percent: Option<f64> },
+    /// Measured and target integrated loudness, in LUFS
+    Loudness { measured: f64, target: f64 },
+    Step { message: String },
+    /// FFmpeg has made no progress for this many seconds
+    Stalled { seconds: u64 },
+    /// An output was written
+    Result { output: PathBuf, size: Option<u64>, duration: Option<f64> },
+    Error { message: String },
+    DryRun { report: DryRun },
+    /// End of a run with several outputs
+    Summary { succeeded: usize, failed: usize },
+}
+
+impl JsonEvent {
+    /// The event for a progress update, or `None` for `Started` and
+    /// `Finished`, which `Started` and `Result` events stand for
+    pub fn progress(event: &ProgressEvent) -> Option<Self> {
+        match event {
+            ProgressEvent::Started | ProgressEvent::Finished => None,
+            ProgressEvent::Probed { duration } => Some(JsonEvent::Probed { duration: *duration }),
+            ProgressEvent::Encoding { seconds_done, percent } => Some(JsonEvent::Encoding { seconds_done: *seconds_done, percent: *percent }),
+            ProgressEvent::Loudness { measured, target } => Some(JsonEvent::Loudness { measured: *measured, target: *target }),
+            ProgressEvent::Step(step) => Some(JsonEvent::Step { message: step.to_string() }),
+            ProgressEvent::Stalled { seconds } => Some(JsonEvent::Stalled { seconds: *seconds }),
+        }
+    }
+
+    /// The event for a finished output, with its size and duration when
+    /// they can be read
+    pub fn result(output: &Path) -> Self {
+        JsonEvent::Result {
+            output: output.to_path_buf(),
+            size: std::fs::metadata(output).ok().map(|metadata| metadata.len()),
+            duration: AudioExtractor::verify_standalone(&output.to_path_buf()).ok().and_then(|info| info.duration),
+        }
+    }
+
+    pub fn error(error: &dyn std::fmt::Display) -> Self {
+        JsonEvent::Error { message: format!("{:#}", error) }
+    }
+}
+
+/// Writes `JsonEvent`s one per line, flushing each so a reading pipeline
+/// sees it at once
+pub struct JsonLines {
+    out: Mutex<Box<dyn Write + Send>>,
+}
+
+impl JsonLines {
+    pub fn new(writer: impl Write + Send + 'static) -> Self {
+        Self { out: Mutex::new(Box::new(writer)) }
+    }
+
+    /// Write JSON lines to standard output. Messages for people are held
+    /// back from then on, so nothing else reaches it.
+    pub fn stdout() -> Self {
+        reporter::set_quiet(true);
+        Self::new(io::stdout())
+    }
+
+    pub fn emit(&self, event: &JsonEvent) {
+        let mut out = self.out.lock().unwrap();
+        if let Ok(line) = serde_json::to_string(event) {
+            let _ = writeln!(out, "{}", line);
+            let _ = out.flush();
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use clap::ValueEnum;
+
+/// How the MP3 encoder spends its bits
+#[derive(Clone, Copy, ValueEnum, Debug, Default, PartialEq)]
+pub enum Mp3Mode {
+    /// Constant bitrate: every frame at --quality
+    #[default]
+    Cbr,
+    /// Average bitrate: frames vary, averaging --quality
+    Abr,
+    /// Variable bitrate: the VBR level (V0-V9) whose typical average is closest to --quality
+    Vbr,
+}
+
+/// LAME's named presets, as FFmpeg's libmp3lame can express them
+#[derive(Clone, Copy, ValueEnum, Debug, PartialEq)]
+pub enum LamePreset {
+    /// VBR V4, about 165 kbps
+    Medium,
+    /// VBR V2, about 190 kbps, transparent for most listeners
+    Standard,
+    /// VBR V0, about 245 kbps
+    Extreme,
+    /// CBR 320 kbps
+    Insane,
+}
+
+/// Typical average bitrate of each VBR level, from V0 to V9
+pub const VBR_AVERAGES: [u32; 10] = [245, 225, 190, 175, 165, 130, 115, 100, 85, 65];
+
+/// The VBR level whose typical average bitrate is closest to `kbps`
+pub fn vbr_level(kbps: u32) -> u8 {
+    (0..VBR_AVERAGES.len())
+        .min_by_key(|&level| VBR_AVERAGES[level].abs_diff(kbps))
+        .unwrap() as u8
+}
+
+/// Rate control settings for libmp3lame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Encoding {
+    Cbr(u32),
+    Abr(u32),
+    /// VBR level, 0 (best) to 9
+    Vbr(u8),
+}
+
+impl Encoding {
+    /// Settings for `mode` at `kbps`, or for `preset`, which replaces both
+    pub fn new(mode: Mp3Mode, preset: Option<LamePreset>, kbps: u32) -> Self {
+        match preset {
+            Some(LamePreset::Medium) => Encoding::Vbr(4),
+            Some(LamePreset::Standard) => Encoding::Vbr(2),
+            Some(LamePreset::Extreme) => Encoding::Vbr(0),
+            Some(LamePreset::Insane) => Encoding::Cbr(320),
+            None => match mode {
+                Mp3Mode::Cbr => Encoding::Cbr(kbps),
+                Mp3Mode::Abr => Encoding::Abr(kbps),
+                Mp3Mode::Vbr => Encoding::Vbr(vbr_level(kbps)),
+            },
+        }
+    }
+
+    /// libmp3lame options selecting these settings
+    pub fn ffmpeg_args(&self) -> Vec<String> {
+        match self {
+            Encoding::Cbr(kbps) => vec!["-b:a".into(), format!("{}k", kbps)],
+            Encoding::Abr(kbps) => vec!["-b:a".into(), format!("{}k", kbps), "-abr".into(), "1".into()],
+            Encoding::Vbr(level) => vec!["-q:a".into(), level.to_string()],
+        }
+    }
+
+    /// Bitrate the output averages, roughly for VBR
+    pub fn average_kbps(&self) -> u32 {
+        match self {
+            Encoding::Cbr(kbps) | Encoding::Abr(kbps) => *kbps,
+            Encoding::Vbr(level) => VBR_AVERAGES[*level as usize],
+        }
+    }
+}
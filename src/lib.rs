@@ -1,26 +1,152 @@
 use clap::{Parser, ValueEnum};
 use anyhow::{Result, Context};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::fs;
-use std::process::Command;
-use symphonia::core::io::MediaSourceStream;
-use symphonia::core::probe::Hint;
-use symphonia::core::formats::FormatOptions;
-use symphonia::core::meta::MetadataOptions;
-use symphonia::default::get_probe;
-use chrono;
-use serde_json;
-use serde::Deserialize;
-use std::fs::File;
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::process::{Command, Stdio};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
 
-#[derive(Parser)]
+pub mod analysis;
+pub mod analyzer;
+pub mod asynchronous;
+pub mod audit;
+pub mod batch;
+pub mod budget;
+pub mod cache;
+pub mod chapters;
+pub mod chunked;
+pub mod claims;
+pub mod clean;
+pub mod cover;
+pub mod curl;
+pub mod disc;
+pub mod distributed;
+pub mod drm;
+pub mod dry_run;
+pub mod effects;
+pub mod email;
+pub mod encrypt;
+pub mod error;
+pub mod filtergraph;
+pub mod flac;
+pub mod forensic;
+pub mod gapless;
+pub mod golden;
+pub mod hook;
+pub mod id3;
+pub mod inputs;
+pub mod isolation;
+pub mod jobs;
+pub mod json_lines;
+pub mod lame;
+pub mod loudness;
+pub mod library;
+pub mod limits;
+pub mod metadata;
+pub mod native;
+pub mod options;
+pub mod preflight;
+pub mod preset;
+pub mod probe;
+pub mod profile;
+pub mod progress;
+pub mod project;
+pub mod quality;
+pub mod redact;
+pub mod sampling;
+pub mod repair;
+pub mod report;
+pub mod reporter;
+pub mod rules;
+pub mod scan;
+pub mod settings;
+pub mod sidecar;
+pub mod size;
+pub mod spectrum;
+pub mod split;
+pub mod staging;
+pub mod status;
+pub mod subtitles;
+pub mod supervisor;
+pub mod tags;
+pub mod template;
+pub mod throttle;
+pub mod timecode;
+pub mod timeline;
+pub mod timestamps;
+pub mod tracks;
+pub mod trash;
+pub mod verifier;
+pub mod vbr;
+pub mod voice;
+pub mod watermark;
+pub mod wavpack;
+pub mod webhook;
+
+pub use analysis::{AnalysisReport, SegmentLabel};
+pub use analyzer::{AnalysisInput, Analyzer};
+pub use audit::{AuditLog, AuditRecord};
+pub use budget::SizeBudget;
+pub use cache::ResultCache;
+pub use chapters::Chapter;
+pub use chunked::{ChunkDir, ChunkEdges, JoinCheck};
+pub use claims::OutputClaims;
+pub use cover::CoverSource;
+pub use disc::{Disc, TitleReport, TitleResult};
+pub use drm::DrmProtected;
+pub use dry_run::DryRun;
+pub use effects::{Effect, EffectChain};
+pub use email::{BatchSummary, EmailConfig};
+pub use encrypt::Encryption;
+pub use error::ExtractorError;
+pub use filtergraph::AudioGraph;
+pub use forensic::{ForensicReport, SignedReport, VerificationEvidence};
+pub use isolation::ProcessEnvironment;
+pub use jobs::{Calibration, Jobs, Order};
+pub use tags::Tags;
+pub use throttle::Throttle;
+pub use library::LibraryServer;
+pub use limits::ResourceLimits;
+pub use loudness::LoudnessTarget;
+pub use options::{ExtractionOptions, ResolvedOptions};
+pub use preflight::{PlannedJob, Preflight};
+pub use preset::Preset;
+pub use probe::MediaInfo;
+pub use profile::Profile;
+pub use progress::{ProgressEvent, ProgressParser, StallAction};
+pub use report::{BatchReport, ReportDiff, ReportEntry};
+pub use tracks::AudioTrack;
+pub use trash::Disposal;
+pub use rules::Rule;
+pub use metadata::SourceMetadata;
+pub use native::Backend;
+pub use quality::SourceAudio;
+pub use scan::{SkipReason, SkippedInput, UnsupportedLog};
+pub use settings::Settings;
+pub use spectrum::SourceQuality;
+pub use staging::StagedInput;
+pub use status::{BatchStatus, StatusFile};
+pub use supervisor::TrackedProcess;
+pub use timecode::TimeRange;
+pub use timeline::Timeline;
+pub use timestamps::TimestampRepair;
+pub use verifier::Verifier;
+pub use watermark::{Watermark, WatermarkSource};
+pub use webhook::Webhook;
+
+#[derive(Parser, Clone, Default)]
 #[command(author, version, about, long_about = None)]
+#[command(group(clap::ArgGroup::new("cover").multiple(true).args(["embed_cover", "cover_out"]).conflicts_with("encrypt_to")))]
 pub struct Args {
     /// Input video file path
     #[arg(short, long, required = true)]
     pub input: PathBuf,
     
-    /// Output audio file path
+    /// Output audio file path; may contain metadata fields such as {show}/{title}.mp3
     #[arg(short, long, required = true)]
     pub output: PathBuf,
     
@@ -28,21 +154,511 @@ pub struct Args {
     #[arg(short, long)]
     pub format: Option<AudioFormat>,
     
-    /// Audio quality (bitrate in kbps)
-    #[arg(short, long)]
+    /// Output settings for a common use, e.g. `transcription` for 16 kHz mono WAV; other options override it
+    #[arg(long, value_enum)]
+    pub preset: Option<Preset>,
+    
+    /// Use the settings of a `[profiles.<name>]` table in config.toml; other options override them
+    #[arg(long)]
+    pub profile: Option<String>,
+    
+    /// Audio quality (bitrate in kbps), or `auto` to choose it from the source's audio
+    #[arg(short, long, value_parser = quality::parse_quality)]
     pub quality: Option<u32>,
     
+    /// Fit a lossy output into this size, e.g. 25MB, choosing the bitrate from the duration
+    #[arg(long, value_parser = size::parse_size, conflicts_with_all = ["quality", "lame_preset", "mp3_mode"])]
+    pub target_size: Option<u64>,
+    
+    /// With --target-size, re-encode once at a corrected bitrate if the first encode misses the size
+    #[arg(long, requires = "target_size", conflicts_with_all = ["encrypt_to", "chunked"])]
+    pub two_pass: bool,
+    
+    /// Fit all the outputs of a directory scan into this size, e.g. 8GB, sharing it by duration
+    #[arg(long, value_parser = size::parse_size, conflicts_with_all = ["quality", "target_size", "lame_preset", "mp3_mode"])]
+    pub batch_budget: Option<u64>,
+    
+    /// Encode MP3, AAC, Opus or Vorbis at a VBR quality level instead of a bitrate (the codec's default level if none is given)
+    #[arg(long, num_args = 0..=1, allow_negative_numbers = true, conflicts_with_all = ["quality", "target_size", "batch_budget", "lame_preset", "mp3_mode"])]
+    pub vbr: Option<Option<f64>>,
+    
     /// Verify the output audio file after extraction
     #[arg(long)]
     pub verify: bool,
+    
+    /// Maximum CPU time for each ffmpeg process, in seconds
+    #[arg(long)]
+    pub max_cpu_time: Option<u64>,
+    
+    /// Maximum memory for each ffmpeg process, in megabytes
+    #[arg(long)]
+    pub max_memory: Option<u64>,
+    
+    /// Kill ffmpeg if it runs longer than this many seconds
+    #[arg(long)]
+    pub timeout: Option<u64>,
+    
+    /// Warn when ffmpeg reports no progress for this many seconds
+    #[arg(long, value_name = "SECONDS")]
+    pub stall_timeout: Option<u64>,
+    
+    /// What to do when ffmpeg stalls: warn, kill it, or kill it and retry once
+    #[arg(long, value_enum, default_value_t = StallAction::Warn)]
+    pub on_stall: StallAction,
+    
+    /// Run ffmpeg with our whole environment instead of a scrubbed one
+    #[arg(long)]
+    pub inherit_env: bool,
+    
+    /// Pass this environment variable through to ffmpeg, e.g. http_proxy (repeatable)
+    #[arg(long, value_name = "NAME")]
+    pub pass_env: Vec<String>,
+    
+    /// Set an environment variable for ffmpeg (repeatable)
+    #[arg(long, value_name = "NAME=VALUE", value_parser = isolation::parse_variable)]
+    pub ffmpeg_env: Vec<(String, String)>,
+    
+    /// Run ffmpeg in this directory, so files it leaves behind stay there
+    #[arg(long, value_name = "DIR")]
+    pub ffmpeg_workdir: Option<PathBuf>,
+    
+    /// Copy inputs on network mounts (NFS, SMB) to local scratch space before extracting
+    #[arg(long)]
+    pub stage_locally: bool,
+    
+    /// Directory --stage-locally copies inputs to (default: the system temporary directory)
+    #[arg(long, value_name = "DIR")]
+    pub scratch_dir: Option<PathBuf>,
+    
+    /// Reuse outputs of identical previous extractions stored in this directory
+    #[arg(long)]
+    pub cache_dir: Option<PathBuf>,
+    
+    /// Maximum size of the result cache in megabytes (least recently used entries are evicted)
+    #[arg(long)]
+    pub cache_max_size: Option<u64>,
+    
+    /// Append a JSON record of every extraction to this audit log
+    #[arg(long)]
+    pub audit_log: Option<PathBuf>,
+    
+    /// Write a signed forensic report (hashes, command line, tool versions) to this file
+    #[arg(long, requires = "forensic_key")]
+    pub forensic_report: Option<PathBuf>,
+    
+    /// ed25519 signing key for the forensic report: a 32-byte seed, raw or hex-encoded
+    #[arg(long)]
+    pub forensic_key: Option<PathBuf>,
+    
+    /// Encrypt the output for this age or GPG recipient; plaintext never touches disk
+    #[arg(long)]
+    pub encrypt_to: Option<String>,
+    
+    /// age identity file used to decrypt the output for verification
+    #[arg(long)]
+    pub decrypt_identity: Option<PathBuf>,
+    
+    /// Silence a time range, e.g. 00:12:05-00:12:30 (repeatable)
+    #[arg(long)]
+    pub mute: Vec<TimeRange>,
+    
+    /// JSON file listing time ranges to silence
+    #[arg(long)]
+    pub mute_file: Option<PathBuf>,
+    
+    /// Overlay a tone (e.g. 1kHz) on muted ranges instead of leaving silence
+    #[arg(long, value_parser = redact::parse_frequency)]
+    pub redact_tone: Option<f64>,
+    
+    /// Watermark the output with a periodic double pip at this frequency (e.g. 2kHz)
+    #[arg(long, value_parser = redact::parse_frequency, conflicts_with = "watermark_audio")]
+    pub watermark_tone: Option<f64>,
+    
+    /// Watermark the output with this audio snippet, repeated periodically
+    #[arg(long)]
+    pub watermark_audio: Option<PathBuf>,
+    
+    /// Seconds between watermark repetitions [default: 30]
+    #[arg(long)]
+    pub watermark_interval: Option<f64>,
+    
+    /// Watermark level in dB relative to full scale [default: -24]
+    #[arg(long, allow_negative_numbers = true)]
+    pub watermark_gain: Option<f64>,
+    
+    /// Identify the spoken language(s) with whisper.cpp and tag the output with them
+    #[arg(long, conflicts_with = "encrypt_to")]
+    pub detect_language: bool,
+    
+    /// whisper.cpp model used for language detection (e.g. ggml-base.bin)
+    #[arg(long)]
+    pub whisper_model: Option<PathBuf>,
+    
+    /// Summarize who spoke when (speaker count, speaking time, segments) into the analysis sidecar
+    #[arg(long, conflicts_with = "encrypt_to")]
+    pub diarize: bool,
+    
+    /// Diarization command, given a 16 kHz WAV path and printing RTTM to stdout
+    #[arg(long)]
+    pub diarizer: Option<String>,
+    
+    /// Label regions as music, speech or silence in the analysis sidecar
+    #[arg(long, conflicts_with = "encrypt_to")]
+    pub classify: bool,
+    
+    /// Only keep regions of the source classified as these (e.g. speech), cut back to back
+    #[arg(long, value_enum, value_delimiter = ',', conflicts_with = "redact_tone")]
+    pub keep_segments: Vec<SegmentLabel>,
+    
+    /// Estimate tempo (BPM) and musical key, and tag the output with them
+    #[arg(long, conflicts_with = "encrypt_to")]
+    pub analyze_music: bool,
+    
+    /// Measure the source's real audio bandwidth and flag sources upscaled from a lower bitrate
+    #[arg(long, conflicts_with = "encrypt_to")]
+    pub source_quality: bool,
+    
+    /// Where directory scans log skipped inputs [default: <output>/unsupported.log]
+    #[arg(long)]
+    pub unsupported_log: Option<PathBuf>,
+    
+    /// Repair broken or variable timestamps (e.g. from screen recorders) so the output duration is right
+    #[arg(long)]
+    pub fix_timestamps: bool,
+    
+    /// Insert silence at discontinuities longer than this many seconds, keeping absolute timestamps [default: 0.1]
+    #[arg(long, num_args = 0..=1, default_missing_value = "0.1")]
+    pub fill_gaps: Option<f64>,
+    
+    /// DVD title or Blu-ray playlist to extract from a disc input [default: the longest]
+    #[arg(long, conflicts_with = "all_titles")]
+    pub title: Option<u32>,
+    
+    /// Extract every title of a disc input into its own file
+    #[arg(long)]
+    pub all_titles: bool,
+    
+    /// Extract every audio stream into its own file, named <name>.track<N>.<language>.<ext>
+    #[arg(long, conflicts_with_all = ["audio_track", "all_titles"])]
+    pub all_tracks: bool,
+    
+    /// Extract each chapter of the source into its own file, named <name>.<NN>.<chapter title>.<ext>
+    #[arg(long, conflicts_with_all = ["all_titles", "all_tracks", "chunked", "start", "end", "duration"])]
+    pub split_chapters: bool,
+    
+    /// File name for each title with --all-titles; {name}, {title} and {ext} are replaced [default: {name}_t{title}.{ext}]
+    #[arg(long)]
+    pub title_template: Option<String>,
+    
+    /// Save a video frame as a .jpg sidecar next to the output, for use as artwork
+    #[arg(long)]
+    pub thumbnail: bool,
+    
+    /// Timestamp of the thumbnail frame, e.g. 00:01:30 [default: 10% into the video]
+    #[arg(long, value_parser = timecode::parse_timestamp, requires = "thumbnail")]
+    pub thumbnail_at: Option<f64>,
+    
+    /// Embed the source's attached thumbnail, or a video frame, as the cover art of an MP3, FLAC or .m4a output
+    #[arg(long)]
+    pub embed_cover: bool,
+    
+    /// Take the cover art from the video frame at this timestamp, e.g. 00:01:30 [default: the attached thumbnail]
+    #[arg(long, value_parser = timecode::parse_timestamp, requires = "cover")]
+    pub cover_at: Option<f64>,
+    
+    /// Save the cover art as an image file too, e.g. cover.jpg or cover.png
+    #[arg(long)]
+    pub cover_out: Option<PathBuf>,
+    
+    /// Write a Kodi/Jellyfin .nfo sidecar next to the output from the source's metadata
+    #[arg(long)]
+    pub nfo: bool,
+    
+    /// Write a .timeline.json sidecar mapping output time to source time after cuts, --keep-segments, trims and mutes
+    #[arg(long)]
+    pub timeline: bool,
+    
+    /// Write the same timeline as a CMX 3600 EDL next to the output
+    #[arg(long)]
+    pub edl: bool,
+    
+    /// Frame rate of the EDL's timecodes [default: 25]
+    #[arg(long, requires = "edl")]
+    pub edl_fps: Option<f64>,
+    
+    /// Write the source's subtitles as an .srt next to the output, re-timed to match cuts, --keep-segments and trims
+    #[arg(long)]
+    pub subtitles: bool,
+    
+    /// Re-time this SRT or WebVTT file instead of the source's first subtitle track
+    #[arg(long, requires = "subtitles")]
+    pub subtitle_file: Option<PathBuf>,
+    
+    /// Ask the Jellyfin/Plex server in config.toml's [library] to rescan the output folders when done
+    #[arg(long)]
+    pub notify_library: bool,
+    
+    /// Write a JSON report of a directory scan (outcome, size and loudness per input), for `report diff`
+    #[arg(long)]
+    pub report: Option<PathBuf>,
+    
+    /// Move the index of an .m4a/.m4b/.mp4 output to the front, so web players can start before the download finishes
+    #[arg(long, conflicts_with_all = ["fragmented", "encrypt_to"])]
+    pub faststart: bool,
+    
+    /// Write an .m4a/.m4b/.mp4 output as fragmented MP4, playable while it is still being written or streamed
+    #[arg(long, conflicts_with = "encrypt_to")]
+    pub fragmented: bool,
+    
+    /// Make sure the output records its encoder delay and padding, so split tracks play back without gaps
+    #[arg(long, conflicts_with = "encrypt_to")]
+    pub gapless: bool,
+    
+    /// ID3 tag version for MP3 outputs; many car stereos only read 2.3
+    #[arg(long, value_enum)]
+    pub id3_version: Option<id3::Id3Version>,
+    
+    /// Text encoding of MP3 ID3 tags (UTF-8 needs --id3-version 2.4)
+    #[arg(long, value_enum, conflicts_with = "encrypt_to")]
+    pub id3_encoding: Option<id3::TextEncoding>,
+    
+    /// Rate control of MP3 outputs: cbr (default), abr or vbr, each aiming at --quality
+    #[arg(long, value_enum)]
+    pub mp3_mode: Option<lame::Mp3Mode>,
+    
+    /// LAME preset for MP3 outputs, in place of --quality and --mp3-mode
+    #[arg(long, value_enum, conflicts_with = "mp3_mode")]
+    pub lame_preset: Option<lame::LamePreset>,
+    
+    /// Normalize loudness to EBU R128 in two passes: measure, then apply (needs FFmpeg)
+    #[arg(long)]
+    pub normalize: bool,
+    
+    /// Integrated loudness --normalize aims for, in LUFS (-70 to -5, default -16)
+    #[arg(long, requires = "normalize", allow_negative_numbers = true)]
+    pub target_lufs: Option<f64>,
+    
+    /// Highest true peak --normalize allows, in dBTP (-9 to 0, default -1.5)
+    #[arg(long, requires = "normalize", allow_negative_numbers = true)]
+    pub true_peak: Option<f64>,
+    
+    /// Bits per sample of WAV, AIFF and hybrid WavPack outputs: 16 (default) or 24
+    #[arg(long, value_enum)]
+    pub bit_depth: Option<BitDepth>,
+    
+    /// Sample rate of the output in Hz, e.g. 48000 (WAV defaults to 44100, others keep the source's)
+    #[arg(long)]
+    pub sample_rate: Option<u32>,
+    
+    /// Number of output channels, e.g. 1 for mono or 2 for stereo (default: the source's)
+    #[arg(long)]
+    pub channels: Option<u32>,
+    
+    /// Write WavPack as a lossy .wv at the --quality bitrate plus a .wvc correction file that restores it to lossless
+    #[arg(long, conflicts_with = "encrypt_to")]
+    pub hybrid: bool,
+    
+    /// Re-encode lossy source audio (e.g. AAC) to a lossy format even though it loses quality again
+    #[arg(long)]
+    pub allow_lossy_transcode: bool,
+    
+    /// Lua script run on each input before extraction; it can change the job's format, quality or output, or skip it
+    #[arg(long)]
+    pub hook: Option<PathBuf>,
+    
+    /// Clean output file names of characters the target filesystem does not allow (windows, fat32 or ascii)
+    #[arg(long, value_enum)]
+    pub sanitize_names: Option<template::NameProfile>,
+    
+    /// Only extract from this position on, e.g. 00:12:30.500 or 750.5
+    #[arg(long, value_parser = timecode::parse_timestamp)]
+    pub start: Option<f64>,
+    
+    /// Stop extracting at this position in the source
+    #[arg(long, value_parser = timecode::parse_timestamp, conflicts_with = "duration")]
+    pub end: Option<f64>,
+    
+    /// Extract at most this much audio, e.g. 00:45:00
+    #[arg(long, value_parser = timecode::parse_timestamp)]
+    pub duration: Option<f64>,
+    
+    /// Probe every input of a directory scan and check outputs, collisions and disk space before extracting anything
+    #[arg(long)]
+    pub preflight: bool,
+    
+    /// Write the preflight checks as JSON
+    #[arg(long, requires = "preflight")]
+    pub preflight_report: Option<PathBuf>,
+    
+    /// Print JSON lines for progress, results and errors on stdout; the usual messages are left out and warnings go to stderr
+    #[arg(long)]
+    pub json: bool,
+    
+    /// Report what --start/--end, --mute, --keep-segments, --normalize and library effects would change, without encoding
+    #[arg(long)]
+    pub dry_run: bool,
+    
+    /// Write the dry run's findings as JSON
+    #[arg(long, requires = "dry_run")]
+    pub dry_run_report: Option<PathBuf>,
+    
+    /// Start the batch without asking after a preflight, even in a terminal
+    #[arg(short = 'y', long)]
+    pub yes: bool,
+    
+    /// Audio stream to extract, counting from 0 among the audio streams (list them with `audio_extractor tracks`) [default: the default stream]
+    #[arg(long)]
+    pub audio_track: Option<usize>,
+    
+    /// Inputs of a directory scan to extract at once, or `auto` to measure what this machine can take [default: 1]
+    #[arg(long)]
+    pub jobs: Option<Jobs>,
+    
+    /// Name each output of a directory scan or batch from this template below the output directory, e.g. `{parent}/{stem}.{format}`
+    #[arg(long)]
+    pub output_template: Option<String>,
+    
+    /// Copy the source's title, artist, date and other tags into the output; `--copy-metadata false` drops them [default: true]
+    #[arg(long, value_name = "BOOL", num_args = 0..=1, default_missing_value = "true")]
+    pub copy_metadata: Option<bool>,
+    
+    /// Title tag of the output (`--title` picks a disc title instead)
+    #[arg(long)]
+    pub tag_title: Option<String>,
+    
+    /// Artist tag of the output
+    #[arg(long)]
+    pub artist: Option<String>,
+    
+    /// Album tag of the output
+    #[arg(long)]
+    pub album: Option<String>,
+    
+    /// Track number tag of the output, e.g. 3 or 3/12
+    #[arg(long, value_parser = tags::parse_track)]
+    pub track: Option<String>,
+    
+    /// Order the inputs of a directory scan are started in; `shortest` and `longest` probe each input's duration first
+    #[arg(long, value_enum, default_value_t = Order::Name)]
+    pub order: Order,
+    
+    /// Extraction backend; `native` decodes in-process to WAV, FLAC or AIFF without the ffmpeg binary
+    #[arg(long, value_enum, default_value_t = Backend::Auto)]
+    pub backend: Backend,
+    
+    /// Extract long inputs in chunks of this many minutes, written next to the output and joined at the end [default: 30]
+    #[arg(long, num_args = 0..=1, default_missing_value = "30", conflicts_with_all = ["encrypt_to", "start", "end", "duration"])]
+    pub chunked: Option<u32>,
+    
+    /// Move an existing output to the system trash instead of overwriting it
+    #[arg(long)]
+    pub trash: bool,
+    
+    /// Move an existing output into this directory instead of overwriting it
+    #[arg(long, conflicts_with = "trash")]
+    pub quarantine: Option<PathBuf>,
 }
 
-#[derive(Clone, ValueEnum, Debug, PartialEq, Deserialize)]
+#[derive(Clone, ValueEnum, Debug, PartialEq, Serialize, Deserialize)]
 pub enum AudioFormat {
     Mp3,
     Wav,
     Flac,
     Aac,
+    /// Opus in an Ogg container (`.opus`)
+    Opus,
+    /// Vorbis in an Ogg container (`.ogg`)
+    Ogg,
+    /// Uncompressed big-endian PCM (`.aiff`), which older macOS and pro-audio tools prefer
+    Aiff,
+    /// WavPack (`.wv`), lossless and usually a little smaller than FLAC, or hybrid with --hybrid
+    #[value(name = "wv", alias = "wavpack")]
+    WavPack,
+    /// Narrowband AMR (`.amr`), 8 kHz mono speech for telephony systems
+    #[value(name = "amr", alias = "amr-nb")]
+    AmrNb,
+    /// Wideband AMR (`.awb`), 16 kHz mono speech
+    #[value(name = "awb", alias = "amr-wb")]
+    AmrWb,
+    /// Wideband Speex in an Ogg container (`.spx`), 16 kHz mono speech
+    #[value(name = "spx", alias = "speex")]
+    Speex,
+}
+
+/// Bits per sample of uncompressed WAV and AIFF outputs
+#[derive(Clone, Copy, ValueEnum, Debug, Default, PartialEq)]
+pub enum BitDepth {
+    #[default]
+    #[value(name = "16")]
+    Sixteen,
+    #[value(name = "24")]
+    TwentyFour,
+}
+
+impl BitDepth {
+    pub fn bits(&self) -> u16 {
+        match self {
+            BitDepth::Sixteen => 16,
+            BitDepth::TwentyFour => 24,
+        }
+    }
+}
+
+impl AudioFormat {
+    /// FFmpeg muxer name, needed when writing to a pipe where there is no file extension
+    pub fn ffmpeg_muxer(&self) -> &'static str {
+        match self {
+            AudioFormat::Mp3 => "mp3",
+            AudioFormat::Wav => "wav",
+            AudioFormat::Flac => "flac",
+            AudioFormat::Aac => "adts",
+            AudioFormat::Opus => "opus",
+            AudioFormat::Ogg => "ogg",
+            AudioFormat::Aiff => "aiff",
+            AudioFormat::WavPack => "wv",
+            AudioFormat::AmrNb | AudioFormat::AmrWb => "amr",
+            AudioFormat::Speex => "ogg",
+        }
+    }
+    
+    /// Name FFmpeg gives the codec this format holds
+    pub fn codec(&self) -> &'static str {
+        match self {
+            AudioFormat::Mp3 => "mp3",
+            AudioFormat::Flac => "flac",
+            AudioFormat::Aac => "aac",
+            AudioFormat::Opus => "opus",
+            AudioFormat::Ogg => "vorbis",
+            AudioFormat::WavPack => "wavpack",
+            AudioFormat::AmrNb => "amr_nb",
+            AudioFormat::AmrWb => "amr_wb",
+            AudioFormat::Speex => "speex",
+            AudioFormat::Wav | AudioFormat::Aiff => self.pcm_codec(BitDepth::default()).unwrap(),
+        }
+    }
+    
+    /// FFmpeg's PCM codec for samples of `depth` bits, for the uncompressed formats
+    pub fn pcm_codec(&self, depth: BitDepth) -> Option<&'static str> {
+        match (self, depth) {
+            (AudioFormat::Wav, BitDepth::Sixteen) => Some("pcm_s16le"),
+            (AudioFormat::Wav, BitDepth::TwentyFour) => Some("pcm_s24le"),
+            (AudioFormat::Aiff, BitDepth::Sixteen) => Some("pcm_s16be"),
+            (AudioFormat::Aiff, BitDepth::TwentyFour) => Some("pcm_s24be"),
+            _ => None,
+        }
+    }
+    
+    pub fn is_lossy(&self) -> bool {
+        matches!(self, AudioFormat::Mp3 | AudioFormat::Aac | AudioFormat::Opus | AudioFormat::Ogg) || voice::is_voice(self)
+    }
+}
+
+/// Accept formats in config files spelled the way the command line takes them, e.g. `mp3`
+pub(crate) fn deserialize_format<'de, D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<Option<AudioFormat>, D::Error> {
+    let Some(name) = Option::<String>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+    AudioFormat::from_str(&name, true).map(Some).map_err(serde::de::Error::custom)
 }
 
 impl std::fmt::Display for AudioFormat {
@@ -52,6 +668,13 @@ impl std::fmt::Display for AudioFormat {
             AudioFormat::Wav => write!(f, "wav"),
             AudioFormat::Flac => write!(f, "flac"),
             AudioFormat::Aac => write!(f, "aac"),
+            AudioFormat::Opus => write!(f, "opus"),
+            AudioFormat::Ogg => write!(f, "ogg"),
+            AudioFormat::Aiff => write!(f, "aiff"),
+            AudioFormat::WavPack => write!(f, "wv"),
+            AudioFormat::AmrNb => write!(f, "amr"),
+            AudioFormat::AmrWb => write!(f, "awb"),
+            AudioFormat::Speex => write!(f, "spx"),
         }
     }
 }
@@ -68,15 +691,65 @@ pub struct AudioFileInfo {
 pub struct VideoInfo {
     pub duration: f64,
     pub has_audio: bool,
+    pub audio_tracks: Vec<AudioTrack>,
+}
+
+/// Callback receiving progress events while extracting
+pub type ProgressFn<'a> = dyn Fn(&ProgressEvent) + Sync + 'a;
+
+/// A result cache and the key an output is stored under in it
+type CacheEntry = (ResultCache, String);
+
+/// Whether an FFmpeg run failed because --on-stall killed it
+fn stalled(output: &Result<std::process::Output>) -> bool {
+    matches!(output.as_ref().err().and_then(|e| e.downcast_ref()), Some(ExtractorError::Stalled { .. }))
 }
 
 pub struct AudioExtractor {
     pub args: Args,
+    /// File the audio is read from, resolved once from the input
+    source: OnceLock<PathBuf>,
+    /// Local copy of a network input the audio is read from, with --stage-locally
+    staged: OnceLock<StagedInput>,
+    /// Title picked from a disc input, chosen once
+    disc_title: OnceLock<Option<u32>>,
+    /// Classification of the source, computed once when --keep-segments needs it
+    source_segments: OnceLock<Vec<analysis::Segment>>,
+    /// Custom analyses run on every output
+    analyzers: Vec<Arc<dyn Analyzer>>,
+    /// Processing added through the library, applied to every output
+    effects: EffectChain,
+    /// Bitrate to encode at, resolved once when --quality is `auto`
+    bitrate: OnceLock<u32>,
+    /// Whether a bitrate or rate control was asked for rather than taken
+    /// from config.toml's defaults, so a lossy source must be re-encoded
+    rate_given: bool,
+    /// The source's audio stream, probed once when needed
+    source_audio: OnceLock<Option<SourceAudio>>,
+    /// Loudness of the processed audio, measured once by --normalize's first pass
+    loudness: OnceLock<loudness::Measurement>,
+    /// Expanded output path, if it was too long and had to be shortened
+    pub shortened_from: Option<PathBuf>,
+    /// Time-of-day limits from config.toml
+    throttles: Vec<Throttle>,
 }
 
 #[derive(Deserialize, Debug, Default)]
 pub struct Config {
     pub defaults: Option<Defaults>,
+    pub library: Option<LibraryServer>,
+    pub email: Option<EmailConfig>,
+    #[serde(default)]
+    pub webhooks: Vec<Webhook>,
+    /// Settings for batch inputs matching a pattern, first match wins
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+    /// Limits for certain hours of the day, first match wins
+    #[serde(default)]
+    pub throttle: Vec<Throttle>,
+    /// Named settings chosen with --profile
+    #[serde(default)]
+    pub profiles: HashMap<String, Profile>,
 }
 
 #[derive(Deserialize, Debug, Default)]
@@ -84,11 +757,39 @@ pub struct Defaults {
     pub format: Option<AudioFormat>,
     pub quality: Option<u32>,
     pub verify: Option<bool>,
+    pub max_cpu_time: Option<u64>,
+    pub max_memory: Option<u64>,
+    pub timeout: Option<u64>,
+    pub stall_timeout: Option<u64>,
+    pub pass_env: Option<Vec<String>>,
+    pub ffmpeg_workdir: Option<PathBuf>,
+    pub scratch_dir: Option<PathBuf>,
+    pub cache_dir: Option<PathBuf>,
+    pub cache_max_size: Option<u64>,
+    pub audit_log: Option<PathBuf>,
+    pub whisper_model: Option<PathBuf>,
+    pub diarizer: Option<String>,
+    pub hook: Option<PathBuf>,
+    pub quarantine: Option<PathBuf>,
 }
 
 impl AudioExtractor {
-    pub fn new(mut args: Args) -> Self {
-        if let Some(config) = Self::load_config() {
+    /// An extractor for `options`, which may also be the command line's `Args`
+    pub fn new(options: impl Into<ExtractionOptions>) -> Self {
+        let mut args = options.into().args;
+        // A preset comes before a profile, and both before config.toml's
+        // defaults, which they are more specific than
+        if let Some(preset) = args.preset {
+            preset.apply(&mut args);
+        }
+        let mut throttles = Vec::new();
+        let mut rate_given = Self::rate_given(&args);
+        if let Some(mut config) = Self::load_config() {
+            throttles = config.throttle;
+            if let Some(profile) = args.profile.as_ref().and_then(|name| config.profiles.remove(name)) {
+                profile.apply(&mut args);
+                rate_given = Self::rate_given(&args);
+            }
             if let Some(defaults) = config.defaults {
                 if args.format.is_none() {
                     args.format = defaults.format;
@@ -99,6 +800,48 @@ impl AudioExtractor {
                 if !args.verify {
                     args.verify = defaults.verify.unwrap_or(false);
                 }
+                if args.max_cpu_time.is_none() {
+                    args.max_cpu_time = defaults.max_cpu_time;
+                }
+                if args.max_memory.is_none() {
+                    args.max_memory = defaults.max_memory;
+                }
+                if args.timeout.is_none() {
+                    args.timeout = defaults.timeout;
+                }
+                if args.stall_timeout.is_none() {
+                    args.stall_timeout = defaults.stall_timeout;
+                }
+                if args.pass_env.is_empty() {
+                    args.pass_env = defaults.pass_env.unwrap_or_default();
+                }
+                if args.ffmpeg_workdir.is_none() {
+                    args.ffmpeg_workdir = defaults.ffmpeg_workdir;
+                }
+                if args.scratch_dir.is_none() {
+                    args.scratch_dir = defaults.scratch_dir;
+                }
+                if args.cache_dir.is_none() {
+                    args.cache_dir = defaults.cache_dir;
+                }
+                if args.cache_max_size.is_none() {
+                    args.cache_max_size = defaults.cache_max_size;
+                }
+                if args.audit_log.is_none() {
+                    args.audit_log = defaults.audit_log;
+                }
+                if args.whisper_model.is_none() {
+                    args.whisper_model = defaults.whisper_model;
+                }
+                if args.diarizer.is_none() {
+                    args.diarizer = defaults.diarizer;
+                }
+                if args.hook.is_none() {
+                    args.hook = defaults.hook;
+                }
+                if args.quarantine.is_none() && !args.trash {
+                    args.quarantine = defaults.quarantine;
+                }
             }
         }
 
@@ -110,80 +853,498 @@ impl AudioExtractor {
             args.quality = Some(128);
         }
 
-        Self { args }
-    }
-
-    pub fn load_config() -> Option<Config> {
-        let config_path = PathBuf::from("config.toml");
-        if config_path.exists() {
-            if let Ok(content) = fs::read_to_string(config_path) {
-                if let Ok(config) = toml::from_str(&content) {
-                    return Some(config);
+        // FFmpeg is given our paths, which must not change meaning in its directory
+        if args.ffmpeg_workdir.is_some() {
+            for path in [&mut args.input, &mut args.output].into_iter()
+                .chain(args.watermark_audio.as_mut())
+                .chain(args.subtitle_file.as_mut())
+                .chain(args.cover_out.as_mut())
+                .chain(args.ffmpeg_workdir.as_mut())
+            {
+                if let Ok(absolute) = std::path::absolute(&*path) {
+                    *path = absolute;
                 }
             }
         }
-        None
+
+        Self {
+            args,
+            source: OnceLock::new(),
+            staged: OnceLock::new(),
+            disc_title: OnceLock::new(),
+            source_segments: OnceLock::new(),
+            analyzers: Vec::new(),
+            effects: EffectChain::default(),
+            bitrate: OnceLock::new(),
+            rate_given,
+            source_audio: OnceLock::new(),
+            loudness: OnceLock::new(),
+            shortened_from: None,
+            throttles,
+        }
     }
     
-    pub fn extract(&self) -> Result<()> {
-        self.validate_input()?;
-        self.create_output_directory()?;
-        self.extract_audio()?;
-        
-        if self.args.verify {
-            self.verify_audio_file()?;
+    /// Register a custom analysis to run on the output, and on every output of
+    /// a directory scan or disc
+    pub fn with_analyzer(mut self, analyzer: impl Analyzer + 'static) -> Self {
+        self.analyzers.push(Arc::new(analyzer));
+        self
+    }
+    
+    /// Write these tags into the output instead of --tag-title, --artist, --album and --track
+    pub fn with_tags(mut self, tags: Tags) -> Self {
+        self.args.tag_title = tags.title;
+        self.args.artist = tags.artist;
+        self.args.album = tags.album;
+        self.args.track = tags.track;
+        self
+    }
+    
+    /// Apply `effects` to the audio, after the processing options ask for
+    /// and before --normalize
+    pub fn with_effects(mut self, effects: EffectChain) -> Self {
+        self.effects = effects;
+        self
+    }
+    
+    pub fn effects(&self) -> &EffectChain {
+        &self.effects
+    }
+    
+    /// Tags set with --tag-title, --artist, --album and --track
+    pub fn tags(&self) -> Tags {
+        Tags {
+            title: self.args.tag_title.clone(),
+            artist: self.args.artist.clone(),
+            album: self.args.album.clone(),
+            track: self.args.track.clone(),
         }
-        
-        Ok(())
+    }
+    
+    /// Use this extraction backend instead of the one from --backend
+    pub fn with_backend(mut self, backend: Backend) -> Self {
+        self.args.backend = backend;
+        self
+    }
+    
+    /// The backend extractions run on, with `auto` resolved to FFmpeg when
+    /// it is installed and the native backend otherwise
+    pub fn backend(&self) -> Backend {
+        match self.args.backend {
+            Backend::Auto if self.is_ffmpeg_available() => Backend::Ffmpeg,
+            Backend::Auto => Backend::Native,
+            backend => backend,
+        }
+    }
+    
+    /// Duration of another input, probed with this extractor's settings;
+    /// without FFprobe, formats symphonia reads are probed natively
+    fn input_duration(&self, input: &Path) -> Option<f64> {
+        let extractor = AudioExtractor::new(Args { input: input.to_path_buf(), ..self.args.clone() });
+        extractor.get_video_info().ok()
+            .map(|info| info.duration)
+            .filter(|duration| *duration > 0.0)
+            .or_else(|| Self::verify_standalone(&input.to_path_buf()).ok()?.duration)
+    }
+    
+    /// Extractor for one input of a batch, sharing this one's analyzers
+    fn for_input(&self, args: Args) -> AudioExtractor {
+        let mut extractor = AudioExtractor::new(args);
+        extractor.analyzers = self.analyzers.clone();
+        extractor.effects = self.effects.clone();
+        // A config rule may have picked this input's bitrate
+        extractor.rate_given = self.rate_given || extractor.args.quality != self.args.quality;
+        extractor
+    }
+
+    /// config.toml in the current directory, or else in
+    /// `$XDG_CONFIG_HOME/audio_extractor` or `~/.config/audio_extractor`
+    pub fn config_path() -> Option<PathBuf> {
+        let local = PathBuf::from("config.toml");
+        if local.exists() {
+            return Some(local);
+        }
+        let config_home = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        let user = config_home.join("audio_extractor").join("config.toml");
+        user.exists().then_some(user)
+    }
+    
+    pub fn load_config() -> Option<Config> {
+        let content = fs::read_to_string(Self::config_path()?).ok()?;
+        toml::from_str(&content).ok()
+    }
+    
+    /// The settings extractions run with, once the command line, --profile,
+    /// --preset and config.toml's defaults are merged
+    pub fn resolved_options(&self) -> ResolvedOptions {
+        ResolvedOptions {
+            format: self.args.format.clone().unwrap_or(AudioFormat::Mp3),
+            quality: self.args.quality.unwrap_or(128),
+            vbr: self.vbr_level().ok().flatten(),
+            normalize: self.loudness_target().ok().flatten(),
+            sample_rate: self.args.sample_rate,
+            channels: self.args.channels,
+            output_template: self.args.output_template.clone(),
+            verify: self.args.verify,
+            profile: self.args.profile.clone(),
+        }
+    }
+    
+    pub fn extract(&self) -> Result<(), ExtractorError> {
+        Ok(self.audited("extract", || {
+            self.validate_input()?;
+            self.create_output_directory()?;
+            self.extract_audio(None)?;
+            self.analyze()?;
+            self.thumbnail()?;
+            self.cover()?;
+            self.nfo()?;
+            self.write_timeline()?;
+            self.write_subtitles()?;
+            self.check_gapless()?;
+            self.encode_id3()?;
+            self.check_target_size()?;
+            
+            if self.args.verify {
+                self.verify_audio_file()?;
+            }
+            
+            Ok(())
+        })?)
     }
     
     /// Advanced audio extraction with progress tracking
-    pub fn extract_with_progress<F>(&self, progress_callback: F) -> Result<()>
+    pub fn extract_with_progress<F>(&self, progress_callback: F) -> Result<(), ExtractorError>
     where
-        F: Fn(&str) + Send + Sync,
+        F: Fn(&ProgressEvent) + Send + Sync,
     {
-        progress_callback("Starting audio extraction...");
+        Ok(self.audited("extract", || {
+            self.start_extraction(&progress_callback)?;
+            self.extract_audio(Some(&progress_callback))?;
+            self.finish_extraction(&progress_callback)
+        })?)
+    }
+    
+    /// The steps of `extract_with_progress` before the audio is extracted
+    fn start_extraction(&self, progress_callback: &ProgressFn) -> Result<()> {
+        progress_callback(&ProgressEvent::Started);
         
         self.validate_input()?;
-        progress_callback("Input validation completed");
+        progress_callback(&ProgressEvent::Step("Input validation completed"));
         
         self.create_output_directory()?;
-        progress_callback("Output directory prepared");
+        progress_callback(&ProgressEvent::Step("Output directory prepared"));
         
         // Get input file info first
-        if let Ok(info) = self.get_video_info() {
-            progress_callback(&format!("Video duration: {:.2} seconds", info.duration));
-        }
-        
-        self.extract_audio()?;
-        progress_callback("Audio extraction completed");
-        
-        if self.args.verify {
-            progress_callback("Starting verification...");
-            self.verify_audio_file()?;
-            progress_callback("Verification completed");
+        let duration = self.get_video_info().ok().map(|info| self.expected_duration(info.duration));
+        if let Some(duration) = duration {
+            progress_callback(&ProgressEvent::Probed { duration });
         }
-        
         Ok(())
     }
     
-    /// Get video file information using ffprobe
-    fn get_video_info(&self) -> Result<VideoInfo> {
-        // Execute ffprobe command to get video info in JSON format
-        let output = Command::new("ffprobe")
-            .arg("-v")
-            .arg("quiet")
-            .arg("-print_format")
-            .arg("json")
-            .arg("-show_format")
-            .arg("-show_streams")
-            .arg(&self.args.input)
-            .output()
-            .context("Failed to run ffprobe")?;
+    /// The steps of `extract_with_progress` after the audio is extracted
+    fn finish_extraction(&self, progress_callback: &ProgressFn) -> Result<()> {
+        progress_callback(&ProgressEvent::Step("Audio extraction completed"));
         
-        // Check if ffprobe executed successfully
-        if !output.status.success() {
-            anyhow::bail!("ffprobe failed to analyze video file");
+        if self.analyze()? {
+            progress_callback(&ProgressEvent::Step("Analysis completed"));
+        }
+        
+        if self.thumbnail()?.is_some() {
+            progress_callback(&ProgressEvent::Step("Thumbnail saved"));
+        }
+        
+        if self.cover()? {
+            progress_callback(&ProgressEvent::Step("Cover art added"));
+        }
+        
+        if self.nfo()?.is_some() {
+            progress_callback(&ProgressEvent::Step("NFO written"));
+        }
+        
+        if self.write_timeline()? {
+            progress_callback(&ProgressEvent::Step("Timeline written"));
+        }
+        
+        if self.write_subtitles()? {
+            progress_callback(&ProgressEvent::Step("Subtitles re-timed"));
+        }
+        
+        if self.check_gapless()? {
+            progress_callback(&ProgressEvent::Step("Gapless metadata checked"));
+        }
+        
+        if self.encode_id3()? {
+            progress_callback(&ProgressEvent::Step("ID3 tags re-encoded"));
+        }
+        
+        if self.check_target_size()? {
+            progress_callback(&ProgressEvent::Step("Target size checked"));
+        }
+        
+        if self.args.verify {
+            progress_callback(&ProgressEvent::Step("Starting verification..."));
+            self.verify_audio_file()?;
+            progress_callback(&ProgressEvent::Step("Verification completed"));
+        }
+        
+        progress_callback(&ProgressEvent::Finished);
+        Ok(())
+    }
+    
+    /// Seconds of audio an extraction writes from a source of
+    /// `source_duration`, after --start, --end and --duration
+    pub fn expected_duration(&self, source_duration: f64) -> f64 {
+        let start = self.args.start.unwrap_or(0.0).min(source_duration);
+        let end = match (self.args.end, self.args.duration) {
+            (Some(end), _) => end.min(source_duration),
+            (None, Some(duration)) => (start + duration).min(source_duration),
+            (None, None) => source_duration,
+        };
+        (end - start).max(0.0)
+    }
+    
+    /// Run an operation and append its outcome to the audit log and forensic
+    /// report, if configured.
+    ///
+    /// A failure to write either record fails an otherwise successful operation.
+    fn audited<F>(&self, operation: &str, run: F) -> Result<()>
+    where
+        F: FnOnce() -> Result<()>,
+    {
+        if self.args.audit_log.is_none() && self.args.forensic_report.is_none() {
+            return run();
+        }
+        
+        let timestamp = chrono::Utc::now().to_rfc3339();
+        let started = Instant::now();
+        let mut result = run();
+        let error = result.as_ref().err().map(|e| format!("{:#}", e));
+        
+        if let Some(path) = &self.args.audit_log {
+            let record = AuditRecord {
+                timestamp: timestamp.clone(),
+                user: audit::current_user(),
+                host: audit::hostname(),
+                operation: operation.to_string(),
+                input: self.args.input.clone(),
+                output: self.args.output.clone(),
+                settings: self.settings_summary(),
+                success: error.is_none(),
+                error: error.clone(),
+                duration_ms: started.elapsed().as_millis(),
+            };
+            result = Self::record_outcome(result, AuditLog::new(path).append(&record), "audit log");
+        }
+        
+        if let Some(path) = &self.args.forensic_report {
+            let written = self.write_forensic_report(path, timestamp, error);
+            result = Self::record_outcome(result, written, "forensic report");
+        }
+        
+        result
+    }
+    
+    /// Combine an operation's result with the result of recording it
+    fn record_outcome(result: Result<()>, recorded: Result<()>, what: &str) -> Result<()> {
+        match (recorded, result) {
+            (Ok(()), result) => result,
+            (Err(e), Ok(())) => Err(e),
+            (Err(e), Err(err)) => {
+                warning!("⚠ Failed to write {}: {}", what, e);
+                Err(err)
+            }
+        }
+    }
+    
+    /// Hash the input and output, capture the exact command line and tool
+    /// versions, and write a signed report to `path`
+    fn write_forensic_report(&self, path: &Path, started_at: String, error: Option<String>) -> Result<()> {
+        let key_path = self.args.forensic_key.as_ref()
+            .context("--forensic-report requires --forensic-key")?;
+        let key = forensic::load_signing_key(key_path)?;
+        
+        // The native backend runs no command
+        let command_line = (self.backend() != Backend::Native).then(|| self.build_ffmpeg_command().ok()).flatten()
+            .map(|cmd| {
+                std::iter::once(cmd.get_program())
+                    .chain(cmd.get_args())
+                    .map(|arg| arg.to_string_lossy().into_owned())
+                    .collect()
+            })
+            .unwrap_or_default();
+        
+        // Encrypted outputs are only hashed as ciphertext; their streams cannot be demuxed
+        let output = if error.is_none() && self.args.output.exists() {
+            Some(forensic::file_evidence(&self.args.output, self.encryption().is_none())?)
+        } else {
+            None
+        };
+        
+        let verification = (self.args.verify && error.is_none()).then(|| match self.verify_audio_format() {
+            Ok(info) => VerificationEvidence {
+                passed: true,
+                format: Some(info.format),
+                duration: info.duration,
+                channels: info.channels,
+                sample_rate: info.sample_rate,
+                error: None,
+            },
+            Err(e) => VerificationEvidence {
+                passed: false,
+                format: None,
+                duration: None,
+                channels: None,
+                sample_rate: None,
+                error: Some(format!("{:#}", e)),
+            },
+        });
+        
+        let report = ForensicReport {
+            tool: env!("CARGO_PKG_NAME").to_string(),
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            ffmpeg_version: forensic::ffmpeg_version(),
+            started_at,
+            finished_at: chrono::Utc::now().to_rfc3339(),
+            user: audit::current_user(),
+            host: audit::hostname(),
+            input: forensic::file_evidence(self.source(), true)?,
+            output,
+            command_line,
+            settings: self.settings_summary(),
+            success: error.is_none(),
+            error,
+            verification,
+        };
+        
+        SignedReport::sign(report, &key)?.write(path)?;
+        say!("🔏 Forensic report written to {:?}", path);
+        Ok(())
+    }
+    
+    /// Run the requested analysis passes over the output, tag it with the
+    /// results and write them to the `.analysis.json` sidecar.
+    ///
+    /// Returns whether any analysis was requested.
+    fn analyze(&self) -> Result<bool> {
+        let builtin = self.args.detect_language || self.args.diarize || self.args.classify || self.args.analyze_music
+            || self.args.source_quality;
+        if !builtin && self.analyzers.is_empty() {
+            return Ok(false);
+        }
+        if !self.is_ffmpeg_available() {
+            anyhow::bail!("Audio analysis requires FFmpeg");
+        }
+        
+        let audio = analysis::ScratchWav::decode(&self.args.output)?;
+        let mut report = AnalysisReport::default();
+        
+        if self.args.detect_language {
+            let model = self.args.whisper_model.as_ref()
+                .context("--detect-language requires a whisper.cpp model (--whisper-model)")?;
+            let languages = analysis::detect_languages(&audio, model)?;
+            let codes: Vec<_> = languages.iter().map(|l| l.language.as_str()).collect();
+            say!("🗣 Detected language(s): {}", codes.join(", "));
+            report.languages = Some(languages);
+        }
+        
+        if self.args.diarize {
+            let command = self.args.diarizer.as_deref()
+                .context("--diarize requires a diarization command (--diarizer)")?;
+            let diarization = analysis::diarize(&audio, command)?;
+            say!("👥 {} speaker(s) in {} segment(s)", diarization.speakers, diarization.segments.len());
+            report.diarization = Some(diarization);
+        }
+        
+        if self.args.classify {
+            let segments = analysis::classify(&audio)?;
+            say!("🎼 Classified {} region(s)", segments.len());
+            report.segments = Some(segments);
+        }
+        
+        if self.args.analyze_music {
+            let music = analysis::analyze_music(&audio)?;
+            say!("🎵 Tempo: {:.0} BPM, key: {}", music.bpm, music.key);
+            report.music = Some(music);
+        }
+        
+        if self.args.source_quality {
+            let source = self.source_audio().context("--source-quality needs ffprobe and an audio stream in the source")?;
+            let bandwidth = spectrum::measure(&self.source_input_args()?, &self.resource_limits())?;
+            let quality = SourceQuality::assess(source, bandwidth);
+            if quality.upscaled {
+                warning!("⚠ Source looks upscaled: {}", quality);
+            } else {
+                say!("📶 Source: {}", quality);
+            }
+            report.source_quality = Some(quality);
+        }
+        
+        if !self.analyzers.is_empty() {
+            let samples = audio.samples()?;
+            let metadata = self.source_input_args().ok()
+                .and_then(|args| SourceMetadata::probe(&args, &self.resource_limits()).ok());
+            let input = AnalysisInput {
+                source: self.source(),
+                output: &self.args.output,
+                samples: &samples,
+                sample_rate: analysis::ANALYSIS_SAMPLE_RATE,
+                metadata: metadata.as_ref(),
+            };
+            for analyzer in &self.analyzers {
+                let result = analyzer.analyze(&input)
+                    .with_context(|| format!("Analyzer {} failed", analyzer.name()))?;
+                report.custom.insert(analyzer.name().to_string(), result);
+            }
+            say!("🔬 Ran {} custom analyzer(s)", self.analyzers.len());
+        }
+        
+        analysis::write_tags(&self.args.output, &report.tags(self.args.format.as_ref().unwrap()), &self.id3_args()?)?;
+        let sidecar = report.write(&self.args.output)?;
+        say!("Analysis written to {:?}", sidecar);
+        Ok(true)
+    }
+    
+    /// Resource limits applied to each spawned ffmpeg/ffprobe process
+    pub fn resource_limits(&self) -> ResourceLimits {
+        ResourceLimits {
+            cpu_seconds: self.args.max_cpu_time,
+            memory_bytes: self.args.max_memory.map(|mb| mb * 1024 * 1024),
+            wall_time: self.args.timeout.map(Duration::from_secs),
+            nice: throttle::active_now(&self.throttles).and_then(|throttle| throttle.nice),
+            environment: ProcessEnvironment {
+                inherit: self.args.inherit_env,
+                keep: self.args.pass_env.clone(),
+                set: self.args.ffmpeg_env.clone(),
+                working_dir: self.args.ffmpeg_workdir.clone(),
+            },
+        }
+    }
+    
+    /// Get video file information using ffprobe
+    fn get_video_info(&self) -> Result<VideoInfo> {
+        // Execute ffprobe command to get video info in JSON format
+        let mut cmd = Command::new("ffprobe");
+        cmd.arg("-v")
+            .arg("quiet")
+            .arg("-print_format")
+            .arg("json")
+            .arg("-show_format")
+            .arg("-show_streams")
+            .arg(self.source());
+        let output = self.resource_limits().output(&mut cmd)
+            .context("Failed to run ffprobe")?;
+        
+        // Check if ffprobe executed successfully
+        if !output.status.success() {
+            return Err(ExtractorError::ProbeFailed {
+                path: self.source().to_path_buf(),
+                message: format!("ffprobe exited with {}", output.status),
+            }.into());
         }
         
         let json_output = String::from_utf8_lossy(&output.stdout);
@@ -211,59 +1372,245 @@ impl AudioExtractor {
         
         // Check if the video has an audio stream
         let has_audio = json_output.contains("\"codec_type\":\"audio\"");
+        let audio_tracks = AudioTrack::from_ffprobe_json(&json_output).unwrap_or_default();
         
         Ok(VideoInfo {
             duration,
             has_audio,
+            audio_tracks,
         })
     }
     
-    /// Batch processing support
-    pub fn extract_batch<P: AsRef<std::path::Path>>(
-        inputs: Vec<P>,
-        output_dir: P,
-        format: AudioFormat,
-        quality: u32,
-        verify: bool,
-    ) -> Result<Vec<Result<PathBuf>>> {
-        let mut results = Vec::new();
-        
-        for input in inputs {
-            let input_path = input.as_ref();
-            let stem = input_path.file_stem()
-                .context("Failed to get file stem")?;
-            
-            let output_path = output_dir.as_ref().join(format!("{}.{}", stem.to_string_lossy(), format));
-            
-            let args = Args {
-                input: input_path.to_path_buf(),
-                output: output_path.clone(),
-                format: Some(format.clone()),
-                quality: Some(quality),
-                verify,
-            };
-            
-            let extractor = AudioExtractor::new(args);
-            let result = extractor.extract().map(|_| output_path);
-            results.push(result);
+    /// Bitrate in kbps to encode at. With `--quality auto` it is chosen from
+    /// the source's audio codec and bitrate, falling back to a default for
+    /// the format if the source cannot be probed.
+    pub fn bitrate(&self) -> u32 {
+        *self.bitrate.get_or_init(|| {
+            if let Ok(Some(kbps)) = self.target_bitrate() {
+                return kbps;
+            }
+            let quality = self.args.quality.unwrap();
+            if quality != quality::AUTO {
+                return quality;
+            }
+            let format = self.args.format.as_ref().unwrap();
+            let source = self.source_audio();
+            let bitrate = quality::auto_bitrate(source, format);
+            match source {
+                Some(SourceAudio { codec, bit_rate: Some(kbps), .. }) => {
+                    say!("🎚 Auto quality: {} kbps {} from {} kbps {}", bitrate, format, kbps, codec)
+                }
+                _ => say!("🎚 Auto quality: {} kbps {}", bitrate, format),
+            }
+            bitrate
+        })
+    }
+    
+    /// Bitrate in kbps that fills --target-size with the audio to extract
+    pub fn target_bitrate(&self) -> Result<Option<u32>, ExtractorError> {
+        let Some(bytes) = self.args.target_size else {
+            return Ok(None);
+        };
+        let format = self.args.format.as_ref().unwrap();
+        if !format.is_lossy() {
+            return Err(ExtractorError::InvalidOptions(format!("--target-size only applies to lossy formats; {} is lossless", format)));
         }
+        let duration = self.get_video_info()
+            .context("--target-size needs the duration of the source")?
+            .duration;
+        let kbps = size::bitrate_for(size::usable_bytes(bytes), self.expected_duration(duration))?;
+        if kbps < 8 {
+            return Err(ExtractorError::InvalidOptions(format!("{} is too small for {:.0} seconds of audio ({} kbps)", preflight::format_size(bytes), self.expected_duration(duration), kbps)));
+        }
+        Ok(Some(size::encoder_bitrate(format, kbps)))
+    }
+    
+    /// With --target-size, check the finished output fits it. Returns
+    /// whether a check was made.
+    fn check_target_size(&self) -> Result<bool> {
+        let Some(target) = self.args.target_size else {
+            return Ok(false);
+        };
+        let actual = fs::metadata(&self.args.output)
+            .with_context(|| format!("Failed to read the size of {:?}", self.args.output))?
+            .len();
+        if actual > target {
+            anyhow::bail!(
+                "{:?} is {}, over the {} target size; try a smaller --target-size",
+                self.args.output, preflight::format_size(actual), preflight::format_size(target)
+            );
+        }
+        say!("📦 Size: {} of {}", preflight::format_size(actual), preflight::format_size(target));
+        Ok(true)
+    }
+    
+    /// The source's first audio stream, or `None` if it cannot be probed
+    pub fn source_audio(&self) -> Option<&SourceAudio> {
+        self.source_audio.get_or_init(|| {
+            if !self.is_ffprobe_available() {
+                return None;
+            }
+            SourceAudio::probe(&self.source_input_args().ok()?, self.args.audio_track.unwrap_or(0), &self.resource_limits()).ok().flatten()
+        }).as_ref()
+    }
+    
+    /// Whether to copy the source's audio as it is rather than encode it.
+    ///
+    /// Encoding lossy audio to another lossy format loses quality a second
+    /// time, so it is refused unless --allow-lossy-transcode is set. When the
+    /// source already has the output's codec and nothing needs to be filtered,
+    /// the audio is copied instead, unless a bitrate or rate control was asked
+    /// for, which only an encode can honour.
+    fn stream_copy(&self, graph: &AudioGraph) -> Result<bool> {
+        let format = self.args.format.as_ref().unwrap();
+        if self.args.allow_lossy_transcode || !format.is_lossy() {
+            return Ok(false);
+        }
+        let Some(source) = self.source_audio().filter(|source| !source.is_lossless()) else {
+            return Ok(false);
+        };
         
-        Ok(results)
+        let resampled = self.args.sample_rate.is_some() || self.args.channels.is_some();
+        if source.codec == format.codec() && graph.is_empty() && !resampled && !self.rate_given {
+            say!("Source audio is already {}; copying it instead of re-encoding", source.codec);
+            return Ok(true);
+        }
+        anyhow::bail!(
+            "The source audio is lossy ({}) and encoding it to {} would lose quality again. \
+             Use --format flac or --format wav to keep it as it is, or pass --allow-lossy-transcode",
+            source.codec,
+            format
+        )
     }
     
-    pub fn validate_input(&self) -> Result<()> {
+    /// Whether `args` choose how the output is encoded: --quality, --vbr,
+    /// --target-size, --mp3-mode or --lame-preset
+    fn rate_given(args: &Args) -> bool {
+        args.quality.is_some()
+            || args.vbr.is_some()
+            || args.target_size.is_some()
+            || args.mp3_mode.is_some()
+            || args.lame_preset.is_some()
+    }
+    
+    /// Fill in fields such as `{stem}`, `{format}` or `{date}` in the output
+    /// path, and metadata fields such as `{artist}`, `{show}`, `{season}` or
+    /// `{recorded_date}` from the source's tags
+    pub fn with_expanded_output(mut self) -> Result<Self, ExtractorError> {
+        if !template::has_fields(&self.args.output) {
+            return Ok(self);
+        }
+        
+        let format = self.args.format.as_ref().unwrap().to_string();
+        let mut fields = template::file_fields(&self.args.input, &format, self.bitrate());
+        let output = self.args.output.to_string_lossy().into_owned();
+        // The source is only probed when the template asks for its tags
+        if template::field_names(&output).iter().any(|name| !fields.contains_key(name)) {
+            if !self.is_ffprobe_available() {
+                return Err(ExtractorError::FfprobeNotInstalled { needed_for: "metadata fields in the output path" });
+            }
+            let metadata = SourceMetadata::probe(&self.source_input_args()?, &self.resource_limits())?;
+            fields.extend(template::metadata_fields(&metadata, &self.args.input));
+        }
+        if let Some(profile) = self.args.sanitize_names {
+            template::sanitize_fields(&mut fields, profile);
+        }
+        let expanded = template::expand_path(&self.args.output, &fields)?;
+        
+        // Deep templates can run past the platform's name or path limits
+        let prefix = template::static_prefix(&self.args.output);
+        self.args.output = match template::fit_path(&expanded, &prefix, template::MAX_PATH_LEN)? {
+            Some(shortened) => {
+                say!("✂ {:?} is too long; writing {:?}", expanded, shortened);
+                self.shortened_from = Some(expanded);
+                shortened
+            }
+            None => expanded,
+        };
+        Ok(self)
+    }
+    
+    /// Run the --hook script on this input and apply the format, quality and
+    /// output it chose. Returns the script's reason if it skipped the input.
+    pub fn run_hook(&mut self) -> Result<Option<String>, ExtractorError> {
+        let Some(script) = self.args.hook.clone() else {
+            return Ok(None);
+        };
+        let metadata = if self.is_ffprobe_available() {
+            SourceMetadata::probe(&self.source_input_args()?, &self.resource_limits())?
+        } else {
+            SourceMetadata::default()
+        };
+        let job = hook::Job {
+            input: self.args.input.clone(),
+            output: self.args.output.clone(),
+            format: self.args.format.clone().unwrap(),
+            quality: self.bitrate(),
+            metadata,
+        };
+        
+        let outcome = hook::run(&script, &job)?;
+        if outcome.skip.is_some() {
+            return Ok(outcome.skip);
+        }
+        outcome.apply(&mut self.args);
+        // The script may have changed what the bitrate was chosen for
+        self.bitrate.take();
+        Ok(None)
+    }
+    
+    /// Ask the configured media server to pick up `outputs`, if --notify-library
+    /// is set. The audio is already written, so failures are only reported.
+    pub fn notify_library(&self, outputs: &[PathBuf]) {
+        if !self.args.notify_library || outputs.is_empty() {
+            return;
+        }
+        let Some(server) = Self::load_config().and_then(|config| config.library) else {
+            warning!("⚠ --notify-library needs a [library] table in config.toml");
+            return;
+        };
+        match server.notify(outputs) {
+            Ok(requests) => say!("📚 Asked {:?} to rescan {} folder(s)", server.server, requests),
+            Err(e) => warning!("⚠ Library notification failed: {:#}", e),
+        }
+    }
+    
+    /// The source's chapter markers, for --split-chapters
+    pub fn chapters(&self) -> Result<Vec<Chapter>, ExtractorError> {
+        if !self.is_ffprobe_available() {
+            return Err(ExtractorError::FfprobeNotInstalled { needed_for: "listing chapters" });
+        }
+        Ok(Chapter::probe(&self.source_input_args()?, &self.resource_limits())?)
+    }
+    
+    pub fn validate_input(&self) -> Result<(), ExtractorError> {
         if !self.args.input.exists() {
-            anyhow::bail!("Input file does not exist: {:?}", self.args.input);
+            return Err(ExtractorError::InputNotFound(self.args.input.clone()));
         }
         
-        if !self.is_video_file(&self.args.input) {
-            anyhow::bail!("Input file is not a supported video format: {:?}", self.args.input);
+        if let Some(name) = &self.args.profile {
+            if !Self::load_config().is_some_and(|config| config.profiles.contains_key(name)) {
+                return Err(ExtractorError::InvalidOptions(format!("Unknown profile {:?}; define it as [profiles.{}] in config.toml", name, name)));
+            }
         }
         
+        let is_disc = Disc::detect(&self.args.input).is_some();
+        if self.args.input.is_dir() && !is_disc {
+            if project::audio_track(&self.args.input)?.is_none() {
+                return Err(ExtractorError::UnsupportedFormat(self.args.input.clone()));
+            }
+        } else if !is_disc && !self.is_video_file(&self.args.input) {
+            return Err(ExtractorError::UnsupportedFormat(self.args.input.clone()));
+        }
+        
+        // Refuse encrypted inputs up front rather than failing deep inside FFmpeg
+        let limits = self.resource_limits();
+        drm::check(self.source(), self.is_ffprobe_available().then_some(&limits))?;
+        
         Ok(())
     }
     
-    pub fn is_video_file(&self, path: &PathBuf) -> bool {
+    pub fn is_video_file(&self, path: &Path) -> bool {
         if let Some(extension) = path.extension() {
             matches!(
                 extension.to_str().unwrap_or("").to_lowercase().as_str(),
@@ -282,17 +1629,49 @@ impl AudioExtractor {
         Ok(())
     }
     
-    fn extract_audio(&self) -> Result<()> {
-        println!("Extracting audio from {:?} to {:?}", self.args.input, self.args.output);
-        println!("Format: {}, Quality: {} kbps", self.args.format.as_ref().unwrap(), self.args.quality.unwrap());
-        
-        // Check if FFmpeg is available
+    fn extract_audio(&self, progress: Option<&ProgressFn>) -> Result<()> {
+        self.prepare_output()?;
+        if self.backend() == Backend::Native {
+            return self.extract_audio_native();
+        }
         if !self.is_ffmpeg_available() {
-            return self.extract_audio_fallback();
+            return Err(ExtractorError::FfmpegNotInstalled.into());
         }
-        
-        // Use FFmpeg for actual audio extraction
-        self.extract_audio_with_ffmpeg()
+        self.extract_audio_with_ffmpeg(progress)
+    }
+    
+    /// Check the output can be written and move aside whatever is in its place
+    fn prepare_output(&self) -> Result<()> {
+        if self.args.embed_cover {
+            cover::check_format(self.args.format.as_ref().unwrap(), &self.args.output)?;
+        }
+        self.displace_output()?;
+        say!("Extracting audio from {:?} to {:?}", self.args.input, self.args.output);
+        let quality = match (self.args.format.as_ref().unwrap(), self.vbr_level()?) {
+            (AudioFormat::Mp3, _) => format!("{} kbps", self.mp3_encoding()?.average_kbps()),
+            (_, Some(level)) => format!("VBR {}", level),
+            _ => format!("{} kbps", self.bitrate()),
+        };
+        say!("Format: {}, Quality: {}", self.args.format.as_ref().unwrap(), quality);
+        Ok(())
+    }
+    
+    /// What to do with files an extraction displaces: --quarantine, --trash or delete
+    pub fn disposal(&self) -> Disposal {
+        Disposal::from_flags(self.args.trash, self.args.quarantine.as_deref())
+    }
+    
+    /// With --trash or --quarantine, move an existing output out of the way
+    /// instead of letting the extraction overwrite it
+    fn displace_output(&self) -> Result<()> {
+        let disposal = self.disposal();
+        if disposal == Disposal::Delete || !self.args.output.is_file() {
+            return Ok(());
+        }
+        if let Some(moved) = disposal.dispose(&self.args.output)? {
+            say!("🗑 Moved the existing {:?} to {:?}", self.args.output, moved);
+        }
+        Ok(())
     }
     
     fn is_ffmpeg_available(&self) -> bool {
@@ -302,221 +1681,914 @@ impl AudioExtractor {
             .is_ok()
     }
     
-    fn extract_audio_with_ffmpeg(&self) -> Result<()> {
-        let mut cmd = Command::new("ffmpeg");
+    fn is_ffprobe_available(&self) -> bool {
+        Command::new("ffprobe")
+            .arg("-version")
+            .output()
+            .is_ok()
+    }
+    
+    /// File the audio is read from: the input itself, its local copy when
+    /// --stage-locally copied it off a network mount, or the audio track when
+    /// the input is an image-sequence project folder
+    pub fn source(&self) -> &Path {
+        self.source.get_or_init(|| {
+            if self.args.input.is_dir() {
+                if let Ok(Some(track)) = project::audio_track(&self.args.input) {
+                    return track;
+                }
+            }
+            if self.args.stage_locally && self.args.input.is_file() && staging::is_network_path(&self.args.input) {
+                let scratch = self.args.scratch_dir.clone().unwrap_or_else(std::env::temp_dir);
+                match StagedInput::copy(&self.args.input, &scratch) {
+                    Ok(staged) => return self.staged.get_or_init(|| staged).path().to_path_buf(),
+                    Err(e) => warning!("⚠ Failed to copy {:?} to local scratch space, reading it in place: {:#}", self.args.input, e),
+                }
+            }
+            self.args.input.clone()
+        })
+    }
+    
+    /// Title to extract from a disc: --title, or the longest DVD title.
+    /// Blu-ray discs are left to libbluray, which also picks the longest playlist.
+    fn disc_title(&self, disc: &Disc) -> Result<Option<u32>> {
+        if let Some(title) = self.disc_title.get() {
+            return Ok(*title);
+        }
+        
+        let title = match (self.args.title, disc.kind) {
+            (Some(title), _) => Some(title),
+            (None, disc::DiscKind::Bluray) => None,
+            (None, disc::DiscKind::Dvd) => {
+                let titles = disc.titles(&self.resource_limits())?;
+                let longest = disc::longest(&titles)
+                    .with_context(|| format!("No readable titles found on {:?}", disc.path))?;
+                say!("📀 Using title {} of {} ({})", longest.index, titles.len(), timecode::format_timestamp(longest.duration));
+                Some(longest.index)
+            }
+        };
+        Ok(*self.disc_title.get_or_init(|| title))
+    }
+    
+    /// FFmpeg options and `-i` opening the source, including the disc title
+    fn source_input_args(&self) -> Result<Vec<OsString>> {
+        match Disc::detect(self.source()) {
+            Some(disc) => Ok(disc.input_args(self.disc_title(&disc)?)),
+            None => Ok(vec!["-i".into(), self.source().into()]),
+        }
+    }
+    
+    /// Timestamp repairs requested for the source
+    pub fn timestamp_repair(&self) -> TimestampRepair {
+        TimestampRepair {
+            fix_timestamps: self.args.fix_timestamps,
+            fill_gaps: self.args.fill_gaps,
+        }
+    }
+    
+    /// Music/speech/silence regions of the source, classified on first use
+    fn source_segments(&self) -> Result<&[analysis::Segment]> {
+        if let Some(segments) = self.source_segments.get() {
+            return Ok(segments);
+        }
+        let segments = analysis::classify(&analysis::ScratchWav::decode(self.source())?)?;
+        Ok(self.source_segments.get_or_init(|| segments))
+    }
+    
+    /// Time ranges to silence, from --mute and --mute-file
+    pub fn mute_ranges(&self) -> Result<Vec<TimeRange>, ExtractorError> {
+        let mut ranges = self.args.mute.clone();
+        if let Some(path) = &self.args.mute_file {
+            ranges.extend(redact::load_ranges(path)?);
+        }
+        Ok(ranges)
+    }
+    
+    /// Watermark configured for this extraction, if any
+    pub fn watermark(&self) -> Result<Option<Watermark>, ExtractorError> {
+        let source = match (self.args.watermark_tone, &self.args.watermark_audio) {
+            (Some(_), Some(_)) => return Err(ExtractorError::InvalidOptions("Use either a watermark tone or a watermark audio file, not both".to_string())),
+            (Some(frequency), None) => WatermarkSource::Tone(frequency),
+            (None, Some(path)) => {
+                if !path.exists() {
+                    return Err(ExtractorError::InvalidOptions(format!("Watermark audio file does not exist: {:?}", path)));
+                }
+                WatermarkSource::Audio(path.clone())
+            }
+            (None, None) => return Ok(None),
+        };
+        Ok(Watermark::new(source, self.args.watermark_interval, self.args.watermark_gain).map(Some)?)
+    }
+    
+    /// Audio processing applied during extraction
+    pub fn audio_graph(&self) -> Result<AudioGraph, ExtractorError> {
+        let mut graph = self.processing_graph()?;
+        if let Some(target) = self.loudness_target()? {
+            // Until the first pass has run, loudnorm adjusts the gain as it goes
+            graph.filters.push(match self.loudness.get() {
+                Some(measured) => target.apply_filter(measured, self.output_sample_rate()),
+                None => target.single_pass_filter(),
+            });
+        }
+        Ok(graph)
+    }
+    
+    /// The audio graph before loudness normalization: the options' own
+    /// processing, then the effects added through the library
+    fn processing_graph(&self) -> Result<AudioGraph> {
+        let mut graph = self.options_graph()?;
+        graph.filters.extend(self.effects.filters());
+        Ok(graph)
+    }
+    
+    /// Processing options such as --mute and --watermark ask for
+    fn options_graph(&self) -> Result<AudioGraph> {
+        let mut graph = AudioGraph::default();
         
-        // Input file
-        cmd.arg("-i").arg(&self.args.input);
+        graph.filters.extend(self.timestamp_repair().filter());
         
-        // Overwrite output file if it exists
-        cmd.arg("-y");
+        let mute_ranges = self.mute_ranges()?;
+        graph.filters.extend(redact::mute_filter(&mute_ranges));
         
-        // Audio codec and format settings
+        if !self.args.keep_segments.is_empty() {
+            let select = analysis::select_filter(self.source_segments()?, &self.args.keep_segments)
+                .context("No regions of the source match --keep-segments")?;
+            graph.filters.push(select);
+        }
+        if let Some(frequency) = self.args.redact_tone {
+            graph.overlays.extend(redact::tone_overlay(&mute_ranges, frequency));
+        }
+        
+        if let Some(watermark) = self.watermark()? {
+            let input_index = match &watermark.source {
+                WatermarkSource::Audio(path) => graph.add_input(path.clone()),
+                WatermarkSource::Tone(_) => 0,
+            };
+            graph.overlays.push(watermark.overlay(input_index));
+        }
+        
+        Ok(graph)
+    }
+    
+    /// What each processing step would change, worked out without encoding:
+    /// the cut, the muted and kept regions, the library's effects, and the
+    /// gain --normalize applies, measured from the source
+    pub fn dry_run(&self) -> Result<DryRun, ExtractorError> {
+        self.validate_input()?;
+        let source_seconds = self.input_duration(self.source());
+        let mut steps = Vec::new();
+        if self.has_time_range() {
+            self.time_range_args(source_seconds)?;
+            let start = self.args.start.unwrap_or(0.0);
+            steps.push(dry_run::Step::Cut {
+                start,
+                end: self.args.end.or(self.args.duration.map(|duration| start + duration)),
+                kept_seconds: source_seconds.map(|seconds| self.expected_duration(seconds)),
+            });
+        }
+        let mute_ranges = self.mute_ranges()?;
+        if !mute_ranges.is_empty() {
+            steps.push(dry_run::Step::mute(&mute_ranges));
+        }
+        if !self.args.keep_segments.is_empty() {
+            steps.push(dry_run::Step::keep_segments(self.source_segments()?, &self.args.keep_segments));
+        }
+        steps.extend(self.effects.effects.iter().map(dry_run::Step::effect));
+        if let Some(target) = self.loudness_target()? {
+            steps.push(dry_run::Step::normalize(target, self.measure_loudness()?));
+        }
+        Ok(DryRun { input: self.args.input.clone(), source_seconds, steps })
+    }
+    
+    /// Loudness --normalize aims for, if set
+    pub fn loudness_target(&self) -> Result<Option<LoudnessTarget>, ExtractorError> {
+        if !self.args.normalize {
+            return Ok(None);
+        }
+        Ok(LoudnessTarget::new(self.args.target_lufs, self.args.true_peak).map(Some)?)
+    }
+    
+    /// Sample rate to resample normalized audio to: --sample-rate, the source's, or 48 kHz
+    fn output_sample_rate(&self) -> u32 {
+        self.args.sample_rate
+            .or_else(|| self.source_audio().and_then(|source| source.sample_rate))
+            .unwrap_or(48_000)
+    }
+    
+    /// First pass of --normalize: decode the audio through the rest of the
+    /// graph into loudnorm and read back its measurement. Runs once.
+    pub fn measure_loudness(&self) -> Result<loudness::Measurement, ExtractorError> {
+        if let Some(measured) = self.loudness.get() {
+            return Ok(*measured);
+        }
+        let target = self.loudness_target()?.context("--normalize is not set")?;
+        let mut graph = self.processing_graph()?;
+        graph.filters.push(target.measure_filter());
+        
+        let mut cmd = isolation::ffmpeg();
+        cmd.args(self.timestamp_repair().input_args());
+        cmd.args(self.source_input_args()?);
+        for input in &graph.inputs {
+            cmd.arg("-i").arg(input);
+        }
+        cmd.arg("-vn");
+        let source_duration = self.has_time_range().then(|| self.get_video_info().ok()).flatten();
+        cmd.args(self.time_range_args(source_duration.map(|info| info.duration))?);
+        let stream = self.audio_stream()?;
+        if self.args.audio_track.is_some() && graph.overlays.is_empty() {
+            cmd.arg("-map").arg(&stream);
+        }
+        cmd.args(graph.ffmpeg_args(&stream));
+        cmd.arg("-f").arg("null").arg("-");
+        
+        let output = self.resource_limits().output(&mut cmd).context("Failed to run FFmpeg to measure loudness")?;
+        if !output.status.success() {
+            return Err(ExtractorError::ffmpeg_failed(&output));
+        }
+        let measured = loudness::Measurement::from_ffmpeg_stderr(&String::from_utf8_lossy(&output.stderr))?;
+        Ok(*self.loudness.get_or_init(|| measured))
+    }
+    
+    /// Encoder and its settings for the output format
+    fn add_codec_args(&self, cmd: &mut Command) {
         match self.args.format.as_ref().unwrap() {
             AudioFormat::Mp3 => {
                 cmd.arg("-c:a").arg("libmp3lame");
-                cmd.arg("-b:a").arg(format!("{}k", self.args.quality.unwrap()));
+                let encoding = self.mp3_encoding().unwrap_or_else(|_| {
+                    lame::Encoding::new(self.args.mp3_mode.unwrap_or_default(), self.args.lame_preset, self.bitrate())
+                });
+                cmd.args(encoding.ffmpeg_args());
+                if self.args.gapless {
+                    // The LAME tag holds the encoder delay and padding
+                    cmd.arg("-write_xing").arg("1");
+                }
             }
             AudioFormat::Wav => {
-                cmd.arg("-c:a").arg("pcm_s16le");
-                cmd.arg("-ar").arg("44100");
+                cmd.arg("-c:a").arg(AudioFormat::Wav.pcm_codec(self.args.bit_depth.unwrap_or_default()).unwrap());
+            }
+            AudioFormat::Aiff => {
+                cmd.arg("-c:a").arg(AudioFormat::Aiff.pcm_codec(self.args.bit_depth.unwrap_or_default()).unwrap());
+            }
+            // With --hybrid, the wavpack tool encodes the WAV FFmpeg pipes to it
+            AudioFormat::WavPack if self.args.hybrid => {
+                cmd.arg("-c:a").arg(AudioFormat::Wav.pcm_codec(self.args.bit_depth.unwrap_or_default()).unwrap());
+            }
+            AudioFormat::WavPack => {
+                cmd.arg("-c:a").arg("wavpack");
+                cmd.arg("-compression_level").arg("3");
+            }
+            format @ (AudioFormat::AmrNb | AudioFormat::AmrWb | AudioFormat::Speex) => {
+                let encoder = match format {
+                    AudioFormat::AmrNb => "libopencore_amrnb",
+                    AudioFormat::AmrWb => "libvo_amrwbenc",
+                    _ => "libspeex",
+                };
+                cmd.arg("-c:a").arg(encoder);
+                cmd.arg("-b:a").arg(voice::bitrate(format, self.bitrate()).unwrap().to_string());
             }
             AudioFormat::Flac => {
                 cmd.arg("-c:a").arg("flac");
                 cmd.arg("-compression_level").arg("5");
             }
-            AudioFormat::Aac => {
-                cmd.arg("-c:a").arg("aac");
-                cmd.arg("-b:a").arg(format!("{}k", self.args.quality.unwrap()));
+            format @ (AudioFormat::Aac | AudioFormat::Opus | AudioFormat::Ogg) => {
+                let encoder = match format {
+                    AudioFormat::Aac => "aac",
+                    AudioFormat::Opus => "libopus",
+                    _ => "libvorbis",
+                };
+                cmd.arg("-c:a").arg(encoder);
+                match self.vbr_level().ok().flatten() {
+                    Some(level) => cmd.args(vbr::ffmpeg_args(format, level)),
+                    None => cmd.arg("-b:a").arg(format!("{}k", self.bitrate())),
+                };
             }
         }
-        
-        // Only extract audio, no video
-        cmd.arg("-vn");
-        
-        // Output file
-        cmd.arg(&self.args.output);
-        
-        println!("Running FFmpeg command...");
-        let output = cmd.output()
-            .context("Failed to execute FFmpeg command")?;
-        
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            anyhow::bail!("FFmpeg failed: {}", stderr);
+        if self.args.target_size.is_some() {
+            cmd.args(size::rate_control_args(self.args.format.as_ref().unwrap(), self.bitrate()));
+        }
+        let (sample_rate, channels) = self.sampling();
+        if let Some(rate) = sample_rate {
+            cmd.arg("-ar").arg(rate.to_string());
+        }
+        if let Some(channels) = channels {
+            cmd.arg("-ac").arg(channels.to_string());
         }
-        
-        println!("Audio extraction completed successfully!");
-        Ok(())
     }
     
-    fn extract_audio_fallback(&self) -> Result<()> {
-        println!("⚠ FFmpeg not found, using fallback method");
-        println!("Note: This creates a placeholder file for demonstration purposes");
-        println!("To use real audio extraction, please install FFmpeg:");
-        println!("  - macOS: brew install ffmpeg");
-        println!("  - Ubuntu/Debian: sudo apt install ffmpeg");
-        println!("  - Windows: Download from https://ffmpeg.org/download.html");
-        
-        // Create a placeholder file with some metadata
-        let placeholder_content = format!(
-            "# Audio Extraction Placeholder\n\
-             # Original video: {:?}\n\
-             # Target format: {}\n\
-             # Target quality: {} kbps\n\
-             # \n\
-             # This is a placeholder file created because FFmpeg is not available.\n\
-             # Install FFmpeg to enable real audio extraction.\n\
-             # \n\
-             # Generated by audio_extractor at: {}\n",
-            self.args.input,
-            self.args.format.as_ref().unwrap(),
-            self.args.quality.unwrap(),
-            chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC")
-        );
-        
-        std::fs::write(&self.args.output, placeholder_content)
-            .context("Failed to write placeholder file")?;
-        
+    /// Sample rate and channel count to encode at, where they are not left
+    /// to the source: --sample-rate and --channels, or what the format needs
+    pub fn sampling(&self) -> (Option<u32>, Option<u32>) {
+        let format = self.args.format.as_ref().unwrap();
+        let channels = self.args.channels.or(voice::is_voice(format).then_some(1));
+        (self.args.sample_rate.or(sampling::default_sample_rate(format)), channels)
+    }
+    
+    /// Check --sample-rate and --channels against what the output format supports
+    pub fn check_sampling(&self) -> Result<(), ExtractorError> {
+        let format = self.args.format.as_ref().unwrap();
+        if let Some(rate) = self.args.sample_rate {
+            sampling::check_sample_rate(format, rate)?;
+        }
+        if let Some(channels) = self.args.channels {
+            sampling::check_channels(format, channels)?;
+        }
         Ok(())
     }
     
-    fn verify_audio_file(&self) -> Result<()> {
-        println!("Verifying audio file: {:?}", self.args.output);
+    /// With --gapless, check that the output records its encoder delay and
+    /// padding. Returns whether a check was made.
+    fn check_gapless(&self) -> Result<bool> {
+        if !self.args.gapless || !self.is_ffmpeg_available() {
+            return Ok(false);
+        }
+        match gapless::check(&self.args.output, self.args.format.as_ref().unwrap())? {
+            Some(info) => say!("🔁 Gapless: {}", info),
+            None if self.args.format.as_ref().unwrap().is_lossy() => say!("🔁 Gapless: the Ogg stream records its encoder delay"),
+            None => say!("🔁 Gapless: {} has no encoder delay", self.args.format.as_ref().unwrap()),
+        }
+        Ok(true)
+    }
+    
+    /// Muxer options for --faststart and --fragmented, which only MP4-family outputs support
+    pub fn container_args(&self) -> Result<Vec<&'static str>, ExtractorError> {
+        let flags = match (self.args.faststart, self.args.fragmented) {
+            (false, false) => return Ok(Vec::new()),
+            (true, _) => "+faststart",
+            (_, true) => "+frag_keyframe+empty_moov+default_base_moof",
+        };
+        let is_mp4 = self.args.output.extension()
+            .is_some_and(|ext| ["m4a", "m4b", "mp4"].iter().any(|mp4| ext.eq_ignore_ascii_case(mp4)));
+        if !is_mp4 || self.args.format != Some(AudioFormat::Aac) {
+            return Err(ExtractorError::InvalidOptions(format!("--faststart and --fragmented need AAC in an .m4a, .m4b or .mp4 output, not {:?}", self.args.output)));
+        }
+        Ok(vec!["-movflags", flags])
+    }
+    
+    /// Whether the source's tags are copied to the output, which they are
+    /// unless --copy-metadata is set to false
+    pub fn copies_metadata(&self) -> bool {
+        self.args.copy_metadata.unwrap_or(true)
+    }
+    
+    /// Options copying the source's tags to the output, or dropping them
+    /// with --copy-metadata false
+    pub fn metadata_args(&self) -> Vec<String> {
+        if !self.copies_metadata() {
+            return vec!["-map_metadata".into(), "-1".into()];
+        }
+        let mut args = vec!["-map_metadata".to_string(), "0".to_string()];
+        // Ogg keeps tags per stream as Vorbis comments, while MKV and WebM
+        // sources mostly tag the file, so the file's tags go on the audio stream
+        if matches!(self.args.format.as_ref().unwrap(), AudioFormat::Opus | AudioFormat::Ogg) {
+            args.extend(["-map_metadata:s:a".to_string(), "0:g".to_string()]);
+        }
+        // AIFF's own text chunks only hold the title, author and comment;
+        // an ID3 chunk keeps the album, date and the rest
+        if self.args.format == Some(AudioFormat::Aiff) {
+            args.extend(["-write_id3v2".to_string(), "1".to_string()]);
+        }
+        // Audio muxers drop a video's creation_time, so it is kept as the date
+        let metadata = self.is_ffprobe_available()
+            .then(|| SourceMetadata::probe(&self.source_input_args().ok()?, &self.resource_limits()).ok())
+            .flatten();
+        if let Some(date) = metadata.and_then(|metadata| metadata.recorded_date) {
+            args.extend(["-metadata".to_string(), format!("date={}", date)]);
+        }
+        args
+    }
+    
+    /// Sample depth from --bit-depth, which only WAV, AIFF and hybrid WavPack outputs support
+    pub fn bit_depth(&self) -> Result<BitDepth, ExtractorError> {
+        let depth = self.args.bit_depth.unwrap_or_default();
+        let pcm = self.args.format.as_ref().unwrap().pcm_codec(depth).is_some() || self.hybrid_bitrate()?.is_some();
+        if self.args.bit_depth.is_some() && !pcm {
+            return Err(ExtractorError::InvalidOptions("--bit-depth only applies to WAV, AIFF and hybrid WavPack outputs".to_string()));
+        }
+        Ok(depth)
+    }
+    
+    /// Bitrate of the lossy part of a --hybrid WavPack output, from --quality
+    pub fn hybrid_bitrate(&self) -> Result<Option<u32>, ExtractorError> {
+        if !self.args.hybrid {
+            return Ok(None);
+        }
+        if self.args.format != Some(AudioFormat::WavPack) {
+            return Err(ExtractorError::InvalidOptions("--hybrid only applies to WavPack outputs".to_string()));
+        }
+        let kbps = self.bitrate();
+        if !(24..=9600).contains(&kbps) {
+            return Err(ExtractorError::InvalidOptions(format!("WavPack's hybrid mode takes 24 to 9600 kbps, not {}", kbps)));
+        }
+        Ok(Some(kbps))
+    }
+    
+    /// MP3 rate control from --mp3-mode and --lame-preset, which only MP3 outputs support
+    pub fn mp3_encoding(&self) -> Result<lame::Encoding, ExtractorError> {
+        if (self.args.mp3_mode.is_some() || self.args.lame_preset.is_some()) && self.args.format != Some(AudioFormat::Mp3) {
+            return Err(ExtractorError::InvalidOptions("--mp3-mode and --lame-preset only apply to MP3 outputs".to_string()));
+        }
+        if let Some(level) = self.vbr_level()?.filter(|_| self.args.format == Some(AudioFormat::Mp3)) {
+            return Ok(lame::Encoding::Vbr(level as u8));
+        }
+        Ok(lame::Encoding::new(self.args.mp3_mode.unwrap_or_default(), self.args.lame_preset, self.bitrate()))
+    }
+    
+    /// Quality level from --vbr, checked against the output codec's scale
+    pub fn vbr_level(&self) -> Result<Option<f64>, ExtractorError> {
+        let Some(level) = self.args.vbr else {
+            return Ok(None);
+        };
+        Ok(vbr::level(self.args.format.as_ref().unwrap(), level).map(Some)?)
+    }
+    
+    /// Muxer options for --id3-version, which only MP3 outputs support
+    pub fn id3_args(&self) -> Result<Vec<&'static str>, ExtractorError> {
+        if self.args.id3_version.is_none() && self.args.id3_encoding.is_none() {
+            return Ok(Vec::new());
+        }
+        if self.args.format != Some(AudioFormat::Mp3) {
+            return Err(ExtractorError::InvalidOptions("--id3-version and --id3-encoding only apply to MP3 outputs".to_string()));
+        }
+        // FFmpeg writes ID3v2.4 unless told otherwise
+        let version = self.args.id3_version.unwrap_or(id3::Id3Version::V24);
+        if version == id3::Id3Version::V23 && self.args.id3_encoding == Some(id3::TextEncoding::Utf8) {
+            return Err(ExtractorError::InvalidOptions("ID3v2.3 tags cannot hold UTF-8 text; use --id3-version 2.4 or another --id3-encoding".to_string()));
+        }
+        Ok(vec!["-id3v2_version", version.ffmpeg_value()])
+    }
+    
+    /// Container, duration and every audio and video stream of the input,
+    /// as ffprobe reports them
+    pub fn probe(&self) -> Result<MediaInfo, ExtractorError> {
+        if !self.is_ffprobe_available() {
+            return Err(ExtractorError::FfprobeNotInstalled { needed_for: "probing media" });
+        }
+        Ok(MediaInfo::probe(&self.source_input_args()?, &self.resource_limits())?)
+    }
+    
+    /// The source's audio streams, for choosing --audio-track
+    pub fn audio_tracks(&self) -> Result<Vec<AudioTrack>, ExtractorError> {
+        if !self.is_ffprobe_available() {
+            return Err(ExtractorError::FfprobeNotInstalled { needed_for: "listing audio tracks" });
+        }
+        Ok(AudioTrack::probe(&self.source_input_args()?, &self.resource_limits())?)
+    }
+    
+    /// FFmpeg stream specifier of the audio to extract: `0:a`, or `0:a:N` for
+    /// --audio-track, which must exist when the source can be probed
+    fn audio_stream(&self) -> Result<String> {
+        let Some(track) = self.args.audio_track else {
+            return Ok("0:a".to_string());
+        };
+        if self.is_ffprobe_available() {
+            let tracks = self.audio_tracks()?;
+            if track >= tracks.len() {
+                anyhow::bail!(
+                    "{:?} has no audio track {} ({} audio track(s), numbered from 0; see `audio_extractor tracks`)",
+                    self.args.input, track, tracks.len()
+                );
+            }
+        }
+        Ok(format!("0:a:{}", track))
+    }
+    
+    fn has_time_range(&self) -> bool {
+        self.args.start.is_some() || self.args.end.is_some() || self.args.duration.is_some()
+    }
+    
+    /// FFmpeg options for --start, --end and --duration, checked against the
+    /// source's duration when it is known
+    pub fn time_range_args(&self, source_duration: Option<f64>) -> Result<Vec<String>, ExtractorError> {
+        let start = self.args.start.unwrap_or(0.0);
+        if let Some(end) = self.args.end {
+            if end <= start {
+                return Err(ExtractorError::InvalidOptions(format!("--end {} must be after --start {}", timecode::format_timestamp(end), timecode::format_timestamp(start))));
+            }
+        }
+        if self.args.duration == Some(0.0) {
+            return Err(ExtractorError::InvalidOptions("--duration must be longer than zero".to_string()));
+        }
+        if let Some(total) = source_duration.filter(|&total| total > 0.0) {
+            if start >= total {
+                return Err(ExtractorError::InvalidOptions(format!("--start {} is past the end of the source ({})", timecode::format_timestamp(start), timecode::format_timestamp(total))));
+            }
+            if let Some(end) = self.args.end.filter(|&end| end > total) {
+                return Err(ExtractorError::InvalidOptions(format!("--end {} is past the end of the source ({})", timecode::format_timestamp(end), timecode::format_timestamp(total))));
+            }
+        }
         
-        // Check if the file exists
+        let mut args = Vec::new();
+        if let Some(start) = self.args.start {
+            args.extend(["-ss".to_string(), format!("{:.3}", start)]);
+        }
+        if let Some(end) = self.args.end {
+            args.extend(["-to".to_string(), format!("{:.3}", end)]);
+        }
+        if let Some(duration) = self.args.duration {
+            args.extend(["-t".to_string(), format!("{:.3}", duration)]);
+        }
+        Ok(args)
+    }
+    
+    /// With --id3-encoding, rewrite the output's tag text in that encoding.
+    /// Returns whether the tags were rewritten.
+    fn encode_id3(&self) -> Result<bool> {
+        let Some(encoding) = self.args.id3_encoding else {
+            return Ok(false);
+        };
         if !self.args.output.exists() {
-            anyhow::bail!("Output audio file does not exist: {:?}", self.args.output);
+            return Ok(false);
+        }
+        id3::rewrite_encoding(&self.args.output, encoding)?;
+        say!("🏷 ID3 tags written as {:?}", encoding);
+        Ok(true)
+    }
+    
+    fn build_ffmpeg_command(&self) -> Result<Command> {
+        self.build_ffmpeg_command_from(None)
+    }
+    
+    /// FFmpeg command for the extraction, reading the source, or with
+    /// `chunks` the concat list of its lossless chunks from --chunked
+    fn build_ffmpeg_command_from(&self, chunks: Option<&Path>) -> Result<Command> {
+        let mut cmd = isolation::ffmpeg();
+        
+        let graph = self.audio_graph()?;
+        
+        // Input file, followed by any inputs the filter graph mixes in
+        match chunks {
+            Some(list) => {
+                cmd.arg("-f").arg("concat").arg("-safe").arg("0").arg("-i").arg(list);
+            }
+            None => {
+                cmd.args(self.timestamp_repair().input_args());
+                cmd.args(self.source_input_args()?);
+            }
+        }
+        for input in &graph.inputs {
+            cmd.arg("-i").arg(input);
+        }
+        
+        // Overwrite output file if it exists
+        cmd.arg("-y");
+        
+        // Audio codec and format settings
+        if chunks.is_none() && self.stream_copy(&graph)? {
+            cmd.arg("-c:a").arg("copy");
+        } else {
+            self.add_codec_args(&mut cmd);
         }
         
-        // Check if the file is not empty
-        let metadata = std::fs::metadata(&self.args.output)
-            .context("Failed to read output file metadata")?;
+        // Only extract audio, no video
+        cmd.arg("-vn");
+        
+        cmd.args(self.container_args()?);
+        cmd.args(self.id3_args()?);
+        self.bit_depth()?;
+        self.mp3_encoding()?;
+        self.vbr_level()?;
+        self.target_bitrate()?;
+        self.check_sampling()?;
+        cmd.args(self.metadata_args());
+        cmd.args(self.tags().ffmpeg_args(self.args.format.as_ref().unwrap()));
+        
+        // Seeking on the output keeps filter times (e.g. --mute ranges) in source time
+        let source_duration = self.has_time_range().then(|| self.get_video_info().ok()).flatten();
+        cmd.args(self.time_range_args(source_duration.map(|info| info.duration))?);
         
-        if metadata.len() == 0 {
-            anyhow::bail!("Output audio file is empty: {:?}", self.args.output);
+        // Overlays map the mixed stream themselves; chunks only hold the chosen track
+        let stream = if chunks.is_some() { "0:a".to_string() } else { self.audio_stream()? };
+        if self.args.audio_track.is_some() && chunks.is_none() && graph.overlays.is_empty() {
+            cmd.arg("-map").arg(&stream);
+        }
+        cmd.args(graph.ffmpeg_args(&stream));
+        
+        // Output file, or stdout when the encrypter or wavpack writes the file
+        if self.hybrid_bitrate()?.is_some() {
+            cmd.arg("-f").arg("wav").arg("pipe:1");
+        } else if self.encryption().is_some() {
+            cmd.arg("-f").arg(self.args.format.as_ref().unwrap().ffmpeg_muxer());
+            cmd.arg("pipe:1");
+        } else {
+            cmd.arg(&self.args.output);
         }
         
-        // Basic file validation passed
-        println!("✓ Basic file validation passed!");
-        println!("  - File exists: {:?}", self.args.output);
-        println!("  - File size: {} bytes", metadata.len());
+        Ok(cmd)
+    }
+    
+    /// Output encryption configured for this extraction, if any
+    pub fn encryption(&self) -> Option<Encryption> {
+        self.args.encrypt_to.as_ref().map(|recipient| {
+            Encryption::new(recipient.clone(), self.args.decrypt_identity.clone())
+        })
+    }
+    
+    /// Result cache configured for this extraction, if any
+    pub fn result_cache(&self) -> Option<ResultCache> {
+        self.args.cache_dir.as_ref().map(|dir| {
+            ResultCache::new(dir.clone(), self.args.cache_max_size.map(|mb| mb * 1024 * 1024))
+        })
+    }
+    
+    /// Cache key covering the input contents and every FFmpeg argument except the file paths
+    fn cache_key(&self, cmd: &Command) -> Result<String> {
+        let settings = cmd.get_args()
+            .filter(|arg| *arg != self.source().as_os_str() && *arg != self.args.output.as_os_str())
+            .chain(self.args.output.extension())
+            .chain(self.args.encrypt_to.as_deref().map(OsStr::new));
+        ResultCache::key(self.source(), settings)
+    }
+    
+    fn extract_audio_with_ffmpeg(&self, progress: Option<&ProgressFn>) -> Result<()> {
+        let Some((mut cmd, cached)) = self.prepare_ffmpeg(progress)? else {
+            return Ok(());
+        };
         
-        // Try to verify the audio format using symphonia
-        match self.verify_audio_format() {
-            Ok(info) => {
-                println!("✓ Audio format validation successful!");
-                println!("  - Format: {}", info.format);
-                if let Some(duration) = info.duration {
-                    println!("  - Duration: {:.2} seconds", duration);
+        say!("Running FFmpeg command...");
+        match (self.encryption(), self.hybrid_bitrate()?) {
+            (Some(_), Some(_)) => anyhow::bail!("--hybrid cannot be combined with --encrypt-to"),
+            (Some(encryption), None) => self.run_encrypted_pipeline(cmd, &encryption)?,
+            (None, Some(kbps)) => self.run_hybrid_pipeline(cmd, kbps)?,
+            (None, None) => {
+                let mut output = self.run_ffmpeg(&mut cmd, progress);
+                if self.args.on_stall == StallAction::Retry && stalled(&output) {
+                    warning!("⚠ Retrying the stalled FFmpeg command");
+                    output = self.run_ffmpeg(&mut cmd, progress);
                 }
-                if let Some(channels) = info.channels {
-                    println!("  - Channels: {}", channels);
+                let output = output.context("Failed to execute FFmpeg command")?;
+                
+                if !output.status.success() {
+                    return Err(ExtractorError::ffmpeg_failed(&output).into());
                 }
-                if let Some(sample_rate) = info.sample_rate {
-                    println!("  - Sample rate: {} Hz", sample_rate);
+                if self.args.two_pass {
+                    self.second_pass(progress)?;
                 }
             }
-            Err(e) => {
-                println!("⚠ Audio format validation failed: {}", e);
-                println!("  Note: This is expected for the current test implementation");
-                println!("  The file exists and has content, but may not be a valid audio file");
-                println!("  In a real implementation with actual audio extraction, this would work correctly");
-            }
         }
         
+        self.store_cached(cached);
         Ok(())
     }
     
-    fn verify_audio_format(&self) -> Result<AudioFileInfo> {
-        // Open the file
-        let file = File::open(&self.args.output)
-            .context("Failed to open output audio file")?;
-        
-        let mss = MediaSourceStream::new(Box::new(file), Default::default());
-        
-        // Create a probe hint using the file extension
-        let mut hint = Hint::new();
-        if let Some(extension) = self.args.output.extension() {
-            hint.with_extension(extension.to_str().unwrap_or(""));
+    /// Everything before FFmpeg runs the encode: the loudness measurement,
+    /// --chunked, the result cache and --fill-gaps. Returns the command and
+    /// the cache entry to store its output under, or `None` when the output
+    /// was already written by --chunked or restored from the cache.
+    fn prepare_ffmpeg(&self, progress: Option<&ProgressFn>) -> Result<Option<(Command, Option<CacheEntry>)>> {
+        // A second pass reuses the first one's measurement
+        if let Some(target) = self.loudness_target()?.filter(|_| self.loudness.get().is_none()) {
+            say!("Measuring loudness...");
+            let measured = self.measure_loudness()?;
+            let event = ProgressEvent::Loudness { measured: measured.integrated, target: target.integrated };
+            match progress {
+                Some(progress) => progress(&event),
+                None => say!("{}", event),
+            }
         }
         
-        // Get the default probe
-        let probe = get_probe();
-        
-        // Probe the media source
-        let probed = probe.format(&hint, mss, &FormatOptions::default(), &MetadataOptions::default())
-            .context("Failed to probe audio file format")?;
+        if let Some(minutes) = self.args.chunked {
+            if self.extract_chunked(minutes)? {
+                say!("Audio extraction completed successfully!");
+                return Ok(None);
+            }
+        }
         
-        let format = probed.format;
-        let track = format.default_track()
-            .context("No default audio track found")?;
+        let cmd = self.build_ffmpeg_command()?;
         
-        let codec_params = &track.codec_params;
+        // Disc folders cannot be hashed as a single file, the cache would
+        // not keep a hybrid output's correction file, and a second pass
+        // changes the command after the key is taken
+        let cached = self.result_cache()
+            .filter(|_| self.source().is_file() && !self.args.hybrid && !self.args.two_pass)
+            .map(|cache| self.cache_key(&cmd).map(|key| (cache, key)))
+            .transpose()?;
         
-        // Get format name from codec
-        let format_name = codec_params.codec.to_string();
+        if let Some((cache, key)) = &cached {
+            match cache.restore(key, &self.args.output) {
+                Ok(true) => {
+                    say!("Restored cached result for {:?}", self.args.input);
+                    return Ok(None);
+                }
+                Ok(false) => {}
+                Err(e) => warning!("⚠ Failed to restore cached result: {}", e),
+            }
+        }
         
-        Ok(AudioFileInfo {
-            format: format_name,
-            duration: codec_params.time_base.map(|tb| {
-                codec_params.n_frames.map(|frames| frames as f64 / tb.denom as f64)
-            }).flatten(),
-            channels: codec_params.channels.map(|ch| ch.count()),
-            sample_rate: codec_params.sample_rate,
-        })
+        if let Some(threshold) = self.args.fill_gaps.filter(|_| self.is_ffprobe_available()) {
+            match timestamps::detect_gaps(self.source(), &self.resource_limits(), threshold) {
+                Ok(gaps) if !gaps.is_empty() => {
+                    let total: f64 = gaps.iter().map(|gap| gap.duration()).sum();
+                    say!("Filling {} gap(s) totalling {:.3} seconds with silence", gaps.len(), total);
+                    for gap in &gaps {
+                        say!("  - {}", gap);
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => warning!("⚠ Failed to list gaps: {}", e),
+            }
+        }
+        Ok(Some((cmd, cached)))
     }
     
-    /// Standalone method to verify any audio file
-    pub fn verify_standalone(file_path: &PathBuf) -> Result<AudioFileInfo> {
-        if !file_path.exists() {
-            anyhow::bail!("Audio file does not exist: {:?}", file_path);
+    /// Keep a finished output in the result cache, if it goes there
+    fn store_cached(&self, cached: Option<CacheEntry>) {
+        if let Some((cache, key)) = &cached {
+            if let Err(e) = cache.store(key, &self.args.output) {
+                warning!("⚠ Failed to store result in cache: {}", e);
+            }
         }
         
-        // Check if the file is not empty
-        let metadata = std::fs::metadata(file_path)
-            .context("Failed to read audio file metadata")?;
-        
-        if metadata.len() == 0 {
-            anyhow::bail!("Audio file is empty: {:?}", file_path);
+        say!("Audio extraction completed successfully!");
+    }
+    
+    /// Second pass of --two-pass: if the first encode missed --target-size,
+    /// encode again at the bitrate its size calls for
+    fn second_pass(&self, progress: Option<&ProgressFn>) -> Result<()> {
+        let Some(target) = self.args.target_size else {
+            return Ok(());
+        };
+        let actual = fs::metadata(&self.args.output)?.len();
+        let Some(kbps) = size::corrected_bitrate(self.bitrate(), actual, target)
+            .map(|kbps| size::encoder_bitrate(self.args.format.as_ref().unwrap(), kbps))
+            .filter(|&kbps| kbps != self.bitrate())
+        else {
+            return Ok(());
+        };
+        say!(
+            "Second pass: {} at {} kbps missed the {} target; encoding again at {} kbps",
+            preflight::format_size(actual), self.bitrate(), preflight::format_size(target), kbps
+        );
+        let second = self.for_input(Args { two_pass: false, ..self.args.clone() });
+        let _ = second.bitrate.set(kbps);
+        if let Some(measured) = self.loudness.get() {
+            let _ = second.loudness.set(*measured);
+        }
+        second.extract_audio_with_ffmpeg(progress)
+    }
+    
+    /// Run the FFmpeg command, with `-progress` output when there is a
+    /// progress callback or a --stall-timeout to watch it for
+    fn run_ffmpeg(&self, cmd: &mut Command, progress: Option<&ProgressFn>) -> Result<std::process::Output> {
+        match (progress, self.args.stall_timeout) {
+            (Some(progress), _) => self.run_with_progress(cmd, progress),
+            (None, Some(_)) => self.run_with_progress(cmd, &|_| {}),
+            (None, None) => self.resource_limits().output(cmd),
         }
+    }
+    
+    /// Run the FFmpeg command with `-progress` output on stdout, passing each
+    /// update to `progress` as it arrives. With a --stall-timeout, a stall is
+    /// reported as a `Stalled` event and handled as --on-stall says.
+    fn run_with_progress(&self, cmd: &Command, progress: &ProgressFn) -> Result<std::process::Output> {
+        let duration = self.get_video_info().ok().map(|info| self.expected_duration(info.duration));
+        let limits = self.resource_limits();
+        let mut child = limits.spawn(&mut Self::with_progress_output(cmd))?;
+        let stdout = child.stdout.take().context("FFmpeg progress output is not piped")?;
+        let pid = child.id();
         
-        // Open the file
-        let file = File::open(file_path)
-            .context("Failed to open audio file")?;
+        // When the output last advanced, for the stall watch
+        let last_advance = Mutex::new(Instant::now());
+        let exited = AtomicBool::new(false);
+        let killed = AtomicBool::new(false);
         
-        let mss = MediaSourceStream::new(Box::new(file), Default::default());
+        // Read progress on its own thread so the wall-clock limit keeps being checked
+        let output = std::thread::scope(|scope| {
+            let last_advance = &last_advance;
+            scope.spawn(move || {
+                let mut parser = ProgressParser::new(duration);
+                let mut seconds_done = -1.0;
+                for line in std::io::BufRead::lines(std::io::BufReader::new(stdout)).map_while(Result::ok) {
+                    if let Some(event) = parser.line(&line) {
+                        if let ProgressEvent::Encoding { seconds_done: done, .. } = event {
+                            if done > seconds_done {
+                                seconds_done = done;
+                                *last_advance.lock().unwrap() = Instant::now();
+                            }
+                        }
+                        progress(&event);
+                    }
+                }
+            });
+            let (exited, killed) = (&exited, &killed);
+            if let Some(seconds) = self.args.stall_timeout {
+                scope.spawn(move || self.watch_for_stall(seconds, pid, last_advance, exited, killed, progress));
+            }
+            let output = limits.wait(child);
+            exited.store(true, Ordering::Relaxed);
+            output
+        })?;
         
-        // Create a probe hint using the file extension
-        let mut hint = Hint::new();
-        if let Some(extension) = file_path.extension() {
-            hint.with_extension(extension.to_str().unwrap_or(""));
+        if killed.load(Ordering::Relaxed) {
+            return Err(ExtractorError::Stalled { seconds: self.args.stall_timeout.unwrap_or_default() }.into());
         }
+        Ok(output)
+    }
+    
+    /// The FFmpeg command writing `-progress` output to its stdout, with
+    /// stdin closed and stdout and stderr piped
+    fn with_progress_output(cmd: &Command) -> Command {
+        // -progress is a global option, so it has to come before the output
+        let mut cmd_with_progress = Command::new(cmd.get_program());
+        cmd_with_progress.arg("-progress").arg("pipe:1").arg("-nostats").args(cmd.get_args());
+        cmd_with_progress.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped());
+        cmd_with_progress
+    }
+    
+    /// Report FFmpeg once each time its output has not advanced for `seconds`,
+    /// killing it if --on-stall says to, until it exits
+    fn watch_for_stall(
+        &self,
+        seconds: u64,
+        pid: u32,
+        last_advance: &Mutex<Instant>,
+        exited: &AtomicBool,
+        killed: &AtomicBool,
+        progress: &ProgressFn,
+    ) {
+        let timeout = Duration::from_secs(seconds);
+        let mut reported = None;
+        while !exited.load(Ordering::Relaxed) {
+            std::thread::sleep(Duration::from_millis(100));
+            let last = *last_advance.lock().unwrap();
+            if last.elapsed() < timeout || reported == Some(last) {
+                continue;
+            }
+            reported = Some(last);
+            progress(&ProgressEvent::Stalled { seconds });
+            warning!("⚠ FFmpeg has made no progress for {} seconds", seconds);
+            if self.args.on_stall != StallAction::Warn && !exited.load(Ordering::Relaxed) {
+                match supervisor::kill_pid(pid, true) {
+                    Ok(()) => killed.store(true, Ordering::Relaxed),
+                    Err(e) => warning!("⚠ Failed to kill the stalled FFmpeg: {}", e),
+                }
+                return;
+            }
+        }
+    }
+    
+    /// Pipe FFmpeg's stdout straight into the encrypter so no plaintext reaches disk
+    fn run_encrypted_pipeline(&self, cmd: Command, encryption: &Encryption) -> Result<()> {
+        // Never leave a truncated ciphertext behind
+        self.run_piped(cmd, "Encryption", |audio| encryption.spawn_encrypter(&self.args.output, audio))?;
+        say!("🔒 Output encrypted for {}", encryption.recipient);
+        Ok(())
+    }
+    
+    /// Pipe FFmpeg's WAV into wavpack, which writes the hybrid output and its correction file
+    fn run_hybrid_pipeline(&self, cmd: Command, kbps: u32) -> Result<()> {
+        let correction = wavpack::correction_path(&self.args.output);
+        if let Err(e) = self.run_piped(cmd, "WavPack", |audio| wavpack::spawn_hybrid(&self.args.output, kbps, audio)) {
+            let _ = std::fs::remove_file(&correction);
+            return Err(e);
+        }
+        say!("📦 Hybrid WavPack at {} kbps, with the correction file {:?}", kbps, correction);
+        Ok(())
+    }
+    
+    /// Run FFmpeg with its output piped into the process `spawn` starts,
+    /// which writes the output file. `tool` names that process in errors.
+    /// The output is removed if either fails.
+    fn run_piped<F>(&self, mut cmd: Command, tool: &str, spawn: F) -> Result<()>
+    where
+        F: FnOnce(Stdio) -> Result<std::process::Child>,
+    {
+        let limits = self.resource_limits();
+        let mut ffmpeg = limits.spawn(cmd
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped()))
+            .context("Failed to execute FFmpeg command")?;
+        let audio = ffmpeg.stdout.take().context("Failed to capture FFmpeg output")?;
         
-        // Get the default probe
-        let probe = get_probe();
-        
-        // Probe the media source
-        let probed = probe.format(&hint, mss, &FormatOptions::default(), &MetadataOptions::default())
-            .context("Failed to probe audio file format")?;
-        
-        let format = probed.format;
-        let track = format.default_track()
-            .context("No default audio track found")?;
+        let consumer = match spawn(Stdio::from(audio)) {
+            Ok(consumer) => consumer,
+            Err(e) => {
+                let _ = ffmpeg.kill();
+                let _ = ffmpeg.wait();
+                supervisor::unregister(ffmpeg.id());
+                return Err(e);
+            }
+        };
         
-        let codec_params = &track.codec_params;
+        let ffmpeg_result = limits.wait(ffmpeg);
+        let consumer_output = consumer.wait_with_output().with_context(|| format!("Failed to wait for {}", tool))?;
         
-        // Get format name from codec
-        let format_name = codec_params.codec.to_string();
+        let failure = match ffmpeg_result {
+            Err(e) => Some(e.context("Failed to execute FFmpeg command")),
+            Ok(output) if !output.status.success() => {
+                Some(ExtractorError::ffmpeg_failed(&output).into())
+            }
+            Ok(_) if !consumer_output.status.success() => {
+                Some(anyhow::anyhow!("{} failed: {}", tool, String::from_utf8_lossy(&consumer_output.stderr)))
+            }
+            Ok(_) => None,
+        };
         
-        Ok(AudioFileInfo {
-            format: format_name,
-            duration: codec_params.time_base.map(|tb| {
-                codec_params.n_frames.map(|frames| frames as f64 / tb.denom as f64)
-            }).flatten(),
-            channels: codec_params.channels.map(|ch| ch.count()),
-            sample_rate: codec_params.sample_rate,
-        })
+        if let Some(e) = failure {
+            let _ = std::fs::remove_file(&self.args.output);
+            return Err(e);
+        }
+        Ok(())
     }
     
     pub fn get_supported_video_formats() -> Vec<&'static str> {
@@ -524,6 +2596,6 @@ impl AudioExtractor {
     }
     
     pub fn get_supported_audio_formats() -> Vec<AudioFormat> {
-        vec![AudioFormat::Mp3, AudioFormat::Wav, AudioFormat::Flac, AudioFormat::Aac]
+        vec![AudioFormat::Mp3, AudioFormat::Wav, AudioFormat::Flac, AudioFormat::Aac, AudioFormat::Opus, AudioFormat::Ogg, AudioFormat::Aiff, AudioFormat::WavPack, AudioFormat::AmrNb, AudioFormat::AmrWb, AudioFormat::Speex]
     }
 }
\ No newline at end of file
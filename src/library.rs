@@ -0,0 +1,122 @@
+use crate::curl::Curl;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+/// Seconds to wait for the media server to accept a scan request
+const REQUEST_TIMEOUT_SECS: u32 = 30;
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ServerKind {
+    Jellyfin,
+    Plex,
+}
+
+/// Media server to notify of new outputs, from the `[library]` table of `config.toml`
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct LibraryServer {
+    pub server: ServerKind,
+    /// Base URL, e.g. `http://localhost:8096`
+    pub url: String,
+    /// Jellyfin API key or Plex token
+    pub token: String,
+    /// Plex library section to scan
+    pub section: Option<u32>,
+}
+
+/// One HTTP call to the media server
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScanRequest {
+    pub method: &'static str,
+    pub url: String,
+    pub headers: Vec<String>,
+    pub body: Option<String>,
+}
+
+impl LibraryServer {
+    /// Requests asking the server to rescan just the folders holding `outputs`
+    pub fn scan_requests(&self, outputs: &[PathBuf]) -> Result<Vec<ScanRequest>> {
+        let base = self.url.trim_end_matches('/');
+        let folders: BTreeSet<&Path> = outputs.iter().filter_map(|output| output.parent()).collect();
+        if folders.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        match self.server {
+            ServerKind::Jellyfin => {
+                let updates: Vec<_> = folders
+                    .iter()
+                    .map(|folder| serde_json::json!({ "Path": folder, "UpdateType": "Created" }))
+                    .collect();
+                Ok(vec![ScanRequest {
+                    method: "POST",
+                    url: format!("{}/Library/Media/Updated", base),
+                    headers: vec![
+                        format!("X-Emby-Token: {}", self.token),
+                        "Content-Type: application/json".to_string(),
+                    ],
+                    body: Some(serde_json::json!({ "Updates": updates }).to_string()),
+                }])
+            }
+            ServerKind::Plex => {
+                let section = self.section.context("Plex notifications need `section` in [library]")?;
+                Ok(folders
+                    .iter()
+                    .map(|folder| ScanRequest {
+                        method: "GET",
+                        url: format!(
+                            "{}/library/sections/{}/refresh?path={}",
+                            base,
+                            section,
+                            percent_encode(&folder.to_string_lossy())
+                        ),
+                        headers: vec![format!("X-Plex-Token: {}", self.token)],
+                        body: None,
+                    })
+                    .collect())
+            }
+        }
+    }
+
+    /// Ask the server to rescan the folders holding `outputs`
+    pub fn notify(&self, outputs: &[PathBuf]) -> Result<usize> {
+        let requests = self.scan_requests(outputs)?;
+        for request in &requests {
+            send(request)?;
+        }
+        Ok(requests.len())
+    }
+}
+
+/// Send a request with curl
+fn send(request: &ScanRequest) -> Result<()> {
+    let mut curl = Curl::new()
+        .option("url", &request.url)
+        .option("request", request.method);
+    for header in &request.headers {
+        curl = curl.option("header", header);
+    }
+    if let Some(body) = &request.body {
+        curl = curl.option("data", body);
+    }
+    curl.run(REQUEST_TIMEOUT_SECS)
+        .with_context(|| format!("{} {} failed", request.method, redact(&request.url)))
+}
+
+/// URL without its query, for error messages
+fn redact(url: &str) -> &str {
+    url.split('?').next().unwrap_or(url)
+}
+
+/// Percent-encode a query value, leaving unreserved characters as they are
+pub fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
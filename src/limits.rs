@@ -0,0 +1,175 @@
+use crate::isolation::ProcessEnvironment;
+use crate::supervisor;
+use anyhow::{Context, Result};
+use std::io::Read;
+use std::process::{Child, Command, ExitStatus, Output, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often a running child is polled when a wall-clock limit is set.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Per-job resource limits applied to every spawned ffmpeg/ffprobe process.
+///
+/// CPU time and memory are enforced by the kernel through rlimits (Unix only);
+/// the wall-clock timeout is enforced by the parent, which kills the child once
+/// it runs past the deadline. The process also gets the job's environment.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResourceLimits {
+    /// Maximum CPU time in seconds
+    pub cpu_seconds: Option<u64>,
+    /// Maximum address space in bytes
+    pub memory_bytes: Option<u64>,
+    /// Maximum wall-clock run time
+    pub wall_time: Option<Duration>,
+    /// Scheduling priority, from -20 (highest) to 19 (lowest)
+    pub nice: Option<i32>,
+    /// Environment variables and working directory of the process
+    pub environment: ProcessEnvironment,
+}
+
+impl ResourceLimits {
+    pub fn is_unlimited(&self) -> bool {
+        self.cpu_seconds.is_none() && self.memory_bytes.is_none() && self.wall_time.is_none() && self.nice.is_none()
+    }
+
+    /// Run the command to completion under these limits, collecting its output.
+    pub fn output(&self, cmd: &mut Command) -> Result<Output> {
+        cmd.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped());
+        let child = self.spawn(cmd)?;
+        self.wait(child)
+    }
+
+    /// Spawn the command under these limits and register it with the
+    /// supervisor until `wait` reaps it
+    pub fn spawn(&self, cmd: &mut Command) -> std::io::Result<Child> {
+        self.apply(cmd);
+        let child = cmd.spawn()?;
+        supervisor::register(child.id(), cmd);
+        Ok(child)
+    }
+
+    /// `spawn` for async code: the child is a tokio child, killed if it is
+    /// dropped. It has to be called within a tokio runtime, and the caller
+    /// unregisters the child once it has been waited for.
+    pub async fn spawn_async(&self, mut cmd: Command) -> std::io::Result<tokio::process::Child> {
+        self.apply(&mut cmd);
+        let mut cmd = tokio::process::Command::from(cmd);
+        cmd.kill_on_drop(true);
+        supervisor::spawn_tokio(cmd).await
+    }
+
+    /// Wait for an already spawned child, killing it if it exceeds the wall-clock limit.
+    ///
+    /// Any piped stdout/stderr still attached to the child is collected into the output.
+    pub fn wait(&self, mut child: Child) -> Result<Output> {
+        let status = self.wait_for_exit(&mut child);
+        supervisor::unregister(child.id());
+        let (status, stdout, stderr) = status?;
+
+        Ok(Output {
+            status,
+            stdout: join_reader(stdout).context("Failed to read process stdout")?,
+            stderr: join_reader(stderr).context("Failed to read process stderr")?,
+        })
+    }
+
+    fn wait_for_exit(&self, child: &mut Child) -> Result<(ExitStatus, Option<Reader>, Option<Reader>)> {
+        // Drain both pipes on separate threads so a chatty child cannot block on a full pipe
+        let stdout = child.stdout.take().map(spawn_reader);
+        let stderr = child.stderr.take().map(spawn_reader);
+
+        let Some(limit) = self.wall_time else {
+            return Ok((child.wait()?, stdout, stderr));
+        };
+        let started = Instant::now();
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if started.elapsed() >= limit {
+                let _ = child.kill();
+                let _ = child.wait();
+                anyhow::bail!("Process exceeded the time limit of {} seconds", limit.as_secs());
+            }
+            thread::sleep(POLL_INTERVAL);
+        };
+        Ok((status, stdout, stderr))
+    }
+
+    /// Set up a command to be spawned under these limits and in the job's environment
+    pub fn apply(&self, cmd: &mut Command) {
+        self.environment.apply(cmd);
+        supervisor::apply(cmd);
+        self.apply_rlimits(cmd);
+    }
+
+    /// Set the CPU and memory rlimits and the niceness on a command before it is spawned
+    #[cfg(unix)]
+    pub fn apply_rlimits(&self, cmd: &mut Command) {
+        use std::os::unix::process::CommandExt;
+
+        let cpu_seconds = self.cpu_seconds;
+        let memory_bytes = self.memory_bytes;
+        let nice = self.nice;
+        if cpu_seconds.is_none() && memory_bytes.is_none() && nice.is_none() {
+            return;
+        }
+
+        // SAFETY: the closure only calls setrlimit and setpriority, which are async-signal-safe
+        unsafe {
+            cmd.pre_exec(move || {
+                if let Some(nice) = nice {
+                    // Raising the priority needs privileges; without them the child keeps ours
+                    libc::setpriority(libc::PRIO_PROCESS as _, 0, nice.clamp(-20, 19));
+                }
+                if let Some(seconds) = cpu_seconds {
+                    if libc::setrlimit(libc::RLIMIT_CPU, &rlimit(seconds)) != 0 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                }
+                if let Some(bytes) = memory_bytes {
+                    if libc::setrlimit(libc::RLIMIT_AS, &rlimit(bytes)) != 0 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                }
+                Ok(())
+            });
+        }
+    }
+
+    #[cfg(not(unix))]
+    pub fn apply_rlimits(&self, _cmd: &mut Command) {
+        if self.cpu_seconds.is_some() || self.memory_bytes.is_some() || self.nice.is_some() {
+            crate::warning!("⚠ CPU, memory and niceness limits are only supported on Unix; only the time limit applies");
+        }
+    }
+}
+
+#[cfg(unix)]
+fn rlimit(value: u64) -> libc::rlimit {
+    libc::rlimit {
+        rlim_cur: value as libc::rlim_t,
+        rlim_max: value as libc::rlim_t,
+    }
+}
+
+type Reader = thread::JoinHandle<std::io::Result<Vec<u8>>>;
+
+fn spawn_reader<R: Read + Send + 'static>(mut reader: R) -> Reader {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf)?;
+        Ok(buf)
+    })
+}
+
+fn join_reader(handle: Option<Reader>) -> Result<Vec<u8>> {
+    match handle {
+        Some(handle) => handle
+            .join()
+            .map_err(|_| anyhow::anyhow!("Reader thread panicked"))?
+            .map_err(Into::into),
+        None => Ok(Vec::new()),
+    }
+}
@@ -0,0 +1,112 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+/// EBU R128 loudness --normalize aims for
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct LoudnessTarget {
+    /// Integrated loudness in LUFS
+    pub integrated: f64,
+    /// Maximum true peak in dBTP
+    pub true_peak: f64,
+    /// Loudness range in LU
+    pub range: f64,
+}
+
+impl Default for LoudnessTarget {
+    /// -16 LUFS and -1.5 dBTP, what most podcast platforms ask for
+    fn default() -> Self {
+        Self { integrated: -16.0, true_peak: -1.5, range: 11.0 }
+    }
+}
+
+impl LoudnessTarget {
+    /// Target from --target-lufs and --true-peak, checked against the
+    /// ranges FFmpeg's loudnorm filter accepts
+    pub fn new(integrated: Option<f64>, true_peak: Option<f64>) -> Result<Self> {
+        let mut target = Self::default();
+        if let Some(integrated) = integrated {
+            if !(-70.0..=-5.0).contains(&integrated) {
+                anyhow::bail!("--target-lufs must be between -70 and -5, got {}", integrated);
+            }
+            target.integrated = integrated;
+        }
+        if let Some(true_peak) = true_peak {
+            if !(-9.0..=0.0).contains(&true_peak) {
+                anyhow::bail!("--true-peak must be between -9 and 0, got {}", true_peak);
+            }
+            target.true_peak = true_peak;
+        }
+        Ok(target)
+    }
+
+    fn options(&self) -> String {
+        format!("I={}:TP={}:LRA={}", self.integrated, self.true_peak, self.range)
+    }
+
+    /// First pass: analyse the audio and print the measurement as JSON
+    pub fn measure_filter(&self) -> String {
+        format!("loudnorm={}:print_format=json", self.options())
+    }
+
+    /// loudnorm on its own, adjusting the gain as it goes; used until the
+    /// first pass has measured the audio
+    pub fn single_pass_filter(&self) -> String {
+        format!("loudnorm={}", self.options())
+    }
+
+    /// Second pass: apply the gain the first pass measured. loudnorm works
+    /// at 192 kHz, so the result is resampled to `sample_rate`.
+    pub fn apply_filter(&self, measured: &Measurement, sample_rate: u32) -> String {
+        format!(
+            "loudnorm={}:measured_I={}:measured_TP={}:measured_LRA={}:measured_thresh={}:offset={}:linear=true,aresample={}",
+            self.options(),
+            measured.integrated,
+            measured.true_peak,
+            measured.range,
+            measured.threshold,
+            measured.offset,
+            sample_rate
+        )
+    }
+}
+
+/// Loudness of the audio, as loudnorm's first pass measured it
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct Measurement {
+    /// Integrated loudness in LUFS
+    pub integrated: f64,
+    /// True peak in dBTP
+    pub true_peak: f64,
+    /// Loudness range in LU
+    pub range: f64,
+    pub threshold: f64,
+    /// Gain loudnorm adds after its limiter, in dB
+    pub offset: f64,
+}
+
+impl Measurement {
+    /// The measurement in FFmpeg's stderr from a `measure_filter` pass,
+    /// printed as the last JSON object
+    pub fn from_ffmpeg_stderr(stderr: &str) -> Result<Self> {
+        let start = stderr.rfind('{').context("FFmpeg printed no loudness measurement")?;
+        let end = stderr[start..].find('}').context("FFmpeg printed an incomplete loudness measurement")?;
+        let json: serde_json::Value = serde_json::from_str(&stderr[start..start + end + 1])
+            .context("Invalid loudness measurement")?;
+        let value = |key: &str| -> Result<f64> {
+            json[key].as_str()
+                .and_then(|value| value.trim().parse().ok())
+                .with_context(|| format!("Loudness measurement has no {}", key))
+        };
+        let measurement = Self {
+            integrated: value("input_i")?,
+            true_peak: value("input_tp")?,
+            range: value("input_lra")?,
+            threshold: value("input_thresh")?,
+            offset: value("target_offset")?,
+        };
+        if !measurement.integrated.is_finite() {
+            anyhow::bail!("The audio is silent; there is no loudness to normalize");
+        }
+        Ok(measurement)
+    }
+}
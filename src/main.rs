@@ -1,32 +1,725 @@
-use audio_extractor::{Args, AudioExtractor};
-use clap::Parser;
+use audio_extractor::{Args, AudioExtractor, AudioFormat, BatchReport, BatchSummary, Disc, ReportDiff};
+use audio_extractor::distributed::{self, CoordinatorConfig};
+use audio_extractor::json_lines::{JsonEvent, JsonLines};
+use audio_extractor::golden::{self, GoldenManifest};
+use audio_extractor::clean::CleanPlan;
+use audio_extractor::{Disposal, ProgressEvent};
+use audio_extractor::{preflight, project, quality, repair, report, say, status, supervisor, verifier, ResourceLimits};
+use clap::{Parser, Subcommand};
 use anyhow::Result;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Commands>,
+
+    #[command(flatten)]
+    args: Option<Args>,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Publish a batch to remote workers and collect their outputs
+    Coordinator {
+        /// Address to listen on for worker connections
+        #[arg(long, default_value = "127.0.0.1:7878")]
+        listen: String,
+
+        /// Allow --listen on an address other than loopback. Workers are not
+        /// authenticated, so anyone who can reach it can take jobs and upload outputs.
+        #[arg(long)]
+        allow_remote: bool,
+
+        /// Directory to write the collected audio files to
+        #[arg(short, long)]
+        output_dir: PathBuf,
+
+        /// Output audio format
+        #[arg(short, long, default_value = "mp3")]
+        format: AudioFormat,
+
+        /// Audio quality (bitrate in kbps)
+        #[arg(short, long, default_value_t = 128)]
+        quality: u32,
+
+        /// Have workers verify each output before uploading it
+        #[arg(long)]
+        verify: bool,
+
+        /// Let workers re-encode lossy source audio to a lossy format
+        #[arg(long)]
+        allow_lossy_transcode: bool,
+
+        /// File listing one input path per line
+        #[arg(long)]
+        manifest: Option<PathBuf>,
+
+        /// Print JSON lines for each output, each error and the totals on standard output
+        #[arg(long)]
+        json: bool,
+
+        /// Input video files
+        inputs: Vec<PathBuf>,
+    },
+    /// Extract many inputs given as files, directories or glob patterns into one directory
+    Batch {
+        /// Input video file, directory or quoted glob pattern such as 'videos/**/*.mkv'; repeat for more
+        #[arg(short, long = "input", required = true)]
+        inputs: Vec<String>,
+
+        /// Also take the files in subdirectories of directory inputs
+        #[arg(short, long)]
+        recursive: bool,
+
+        /// Directory to write the audio files to
+        #[arg(short, long)]
+        output_dir: PathBuf,
+
+        /// Name each output from this template below the output directory, e.g. `{parent}/{stem}.{format}`
+        #[arg(long)]
+        output_template: Option<String>,
+
+        /// Output audio format
+        #[arg(short, long, default_value = "mp3")]
+        format: AudioFormat,
+
+        /// Audio quality (bitrate in kbps)
+        #[arg(short, long, default_value_t = 128)]
+        quality: u32,
+
+        /// Verify each output after extraction
+        #[arg(long)]
+        verify: bool,
+
+        /// Inputs to extract at once
+        #[arg(long, default_value_t = 1)]
+        jobs: usize,
+
+        /// Print JSON lines for each output, each error and the totals on standard output
+        #[arg(long)]
+        json: bool,
+    },
+    /// Pull jobs from a coordinator and upload the results
+    Worker {
+        /// Coordinator address (host:port)
+        #[arg(long)]
+        connect: String,
+    },
+    /// Work with batch reports written by --report
+    Report {
+        #[command(subcommand)]
+        command: ReportCommand,
+    },
+    /// Re-extract reference inputs and compare them with stored golden results
+    Golden {
+        #[command(subcommand)]
+        command: GoldenCommand,
+    },
+    /// Re-verify every audio file under a directory without re-extracting anything
+    VerifyDir {
+        /// Directory holding the audio files
+        dir: PathBuf,
+
+        /// Write the results as JSON to this file
+        #[arg(long)]
+        report: Option<PathBuf>,
+
+        /// Files to verify at once [default: one per CPU core]
+        #[arg(long)]
+        jobs: Option<usize>,
+
+        /// Print JSON lines for each file, each failure and the totals on standard output
+        #[arg(long)]
+        json: bool,
+    },
+    /// Remux audio files with broken headers or indexes in place, without re-encoding
+    Repair {
+        /// Audio files to repair
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+
+        /// Keep each original as <file>.bak
+        #[arg(long)]
+        backup: bool,
+
+        /// Print JSON lines for each repaired file, each error and the totals on standard output
+        #[arg(long)]
+        json: bool,
+    },
+    /// Remove the outputs of a previous run, as listed in its --report
+    Clean {
+        /// Report written by the run with --report
+        #[arg(long)]
+        run: PathBuf,
+
+        /// Only list what would be removed
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Also remove outputs that were changed after the run
+        #[arg(long)]
+        force: bool,
+
+        /// Move the files to the system trash instead of deleting them
+        #[arg(long)]
+        trash: bool,
+
+        /// Move the files into this directory instead of deleting them
+        #[arg(long, conflicts_with = "trash")]
+        quarantine: Option<PathBuf>,
+    },
+    /// List the audio streams of a video, for --audio-track
+    Tracks {
+        /// Video file or disc
+        input: PathBuf,
+    },
+    /// Show the container, duration and audio and video streams of a media file
+    Probe {
+        /// Video file or disc
+        input: PathBuf,
+        /// Print the information as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show completed/failed/remaining counts and current files of batches on this machine
+    Status {
+        /// Status file to read [default: every batch's status file]
+        file: Option<PathBuf>,
+    },
+    /// List the FFmpeg and ffprobe processes started by audio_extractor on this machine
+    Ps,
+    /// Stop FFmpeg and ffprobe processes started by audio_extractor
+    Kill {
+        /// Process IDs, as listed by `ps`
+        #[arg(required_unless_present_any = ["orphans", "all"])]
+        pids: Vec<u32>,
+
+        /// Stop every process whose audio_extractor has exited
+        #[arg(long)]
+        orphans: bool,
+
+        /// Stop every listed process
+        #[arg(long)]
+        all: bool,
+
+        /// Send SIGKILL instead of letting FFmpeg finish the file it is writing
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ReportCommand {
+    /// List regressions between two reports: new failures and size/loudness drift
+    Diff {
+        /// Report of the earlier run
+        old: PathBuf,
+
+        /// Report of the later run
+        new: PathBuf,
+
+        /// Output size change to report, in percent
+        #[arg(long, default_value_t = report::DEFAULT_SIZE_TOLERANCE)]
+        size_tolerance: f64,
+
+        /// Integrated loudness change to report, in LU
+        #[arg(long, default_value_t = report::DEFAULT_LOUDNESS_TOLERANCE)]
+        loudness_tolerance: f64,
+    },
+}
+
+#[derive(Subcommand)]
+enum GoldenCommand {
+    /// Extract the references and store the outputs as the new golden results
+    Record {
+        /// TOML file listing the reference inputs as [[reference]] entries
+        #[arg(long)]
+        manifest: PathBuf,
+
+        /// Directory holding the golden results
+        #[arg(long)]
+        dir: PathBuf,
+    },
+    /// Extract the references and fail if an output differs from its golden result
+    Check {
+        /// TOML file listing the reference inputs as [[reference]] entries
+        #[arg(long)]
+        manifest: PathBuf,
+
+        /// Directory holding the golden results
+        #[arg(long)]
+        dir: PathBuf,
+
+        /// Similarity (0-1) a changed output must reach to pass
+        #[arg(long, default_value_t = golden::DEFAULT_MIN_SIMILARITY)]
+        min_similarity: f64,
+    },
+}
 
 fn main() -> Result<()> {
-    let args = Args::parse();
-    
+    let cli = Cli::parse();
+
+    match (cli.command, cli.args) {
+        (Some(command), _) => run_command(command),
+        (None, Some(args)) => run_extraction(args),
+        (None, None) => unreachable!("clap requires --input and --output without a subcommand"),
+    }
+}
+
+fn run_command(command: Commands) -> Result<()> {
+    match command {
+        Commands::Coordinator { listen, allow_remote, output_dir, format, quality, verify, allow_lossy_transcode, manifest, mut inputs, json } => with_json(json, |json| {
+            if let Some(manifest) = manifest {
+                inputs.extend(distributed::read_manifest(&manifest)?);
+            }
+            if inputs.is_empty() {
+                anyhow::bail!("No inputs given; pass input files or --manifest");
+            }
+
+            let config = CoordinatorConfig { listen, output_dir, format, quality, verify, allow_lossy_transcode, allow_remote };
+            let started_at = chrono::Utc::now().to_rfc3339();
+            let results = distributed::run_coordinator(&config, inputs)?;
+            report_results("coordinator", &config.output_dir, started_at, &results);
+            if let Some(json) = json {
+                emit_results(json, &results);
+            }
+
+            let failed = results.iter().filter(|r| r.is_err()).count();
+            say!("🎉 Batch finished: {} succeeded, {} failed", results.len() - failed, failed);
+            if failed > 0 {
+                std::process::exit(1);
+            }
+            Ok(())
+        })?,
+        Commands::Batch { inputs, recursive, output_dir, output_template, format, quality, verify, jobs, json } => with_json(json, |json| {
+            let inputs = audio_extractor::inputs::expand(&inputs, recursive, &AudioExtractor::get_supported_video_formats())?;
+            if inputs.is_empty() {
+                anyhow::bail!("No video files found in the given inputs");
+            }
+            say!("Audio Extractor v{}", env!("CARGO_PKG_VERSION"));
+            say!("Extracting {} input(s) into {:?}", inputs.len(), output_dir);
+            say!();
+
+            let total = inputs.len();
+            let done = AtomicUsize::new(0);
+            let started_at = chrono::Utc::now().to_rfc3339();
+            let outputs = match &output_template {
+                Some(output_template) => output_dir.join(output_template),
+                None => output_dir.clone(),
+            };
+            let results = AudioExtractor::extract_batch_with_progress(
+                inputs.clone(), outputs, format, quality, verify, jobs,
+                |index, event| {
+                    if *event == ProgressEvent::Finished {
+                        let done = done.fetch_add(1, Ordering::Relaxed) + 1;
+                        say!("[{}/{}] {}", done, total, inputs[index].display());
+                    }
+                },
+            )?;
+            report_results("batch", &output_dir, started_at, &results);
+            if let Some(json) = json {
+                emit_results(json, &results);
+            }
+
+            say!();
+            for (input, result) in inputs.iter().zip(&results) {
+                match result {
+                    Ok(output) => say!("✅ {} → {}", input.display(), output.display()),
+                    Err(e) => say!("❌ {}: {:#}", input.display(), e),
+                }
+            }
+            let failed = results.iter().filter(|r| r.is_err()).count();
+            say!("🎉 Batch finished: {} succeeded, {} failed", results.len() - failed, failed);
+            if failed > 0 {
+                std::process::exit(1);
+            }
+            Ok(())
+        })?,
+        Commands::Worker { connect } => {
+            let completed = distributed::run_worker(connect.as_str())?;
+            say!("✅ Worker finished after {} job(s)", completed);
+        }
+        Commands::Report { command: ReportCommand::Diff { old, new, size_tolerance, loudness_tolerance } } => {
+            let diff = ReportDiff::between(&BatchReport::load(&old)?, &BatchReport::load(&new)?, size_tolerance, loudness_tolerance);
+            say!("{}", diff);
+            if diff.has_regressions() {
+                std::process::exit(1);
+            }
+        }
+        Commands::Golden { command: GoldenCommand::Record { manifest, dir } } => {
+            let index = golden::record(&GoldenManifest::load(&manifest)?, &dir, &ResourceLimits::default())?;
+            say!("✅ Recorded {} golden result(s) in {:?}", index.results.len(), dir);
+        }
+        Commands::Golden { command: GoldenCommand::Check { manifest, dir, min_similarity } } => {
+            let verdicts = golden::check(&GoldenManifest::load(&manifest)?, &dir, min_similarity, &ResourceLimits::default())?;
+            let failed: Vec<_> = verdicts.iter().filter(|(_, verdict)| !verdict.passed()).collect();
+            say!();
+            say!("{} reference(s), {} passed, {} failed", verdicts.len(), verdicts.len() - failed.len(), failed.len());
+            for (name, verdict) in &failed {
+                say!("  {}: {}", name, verdict);
+            }
+            if !failed.is_empty() {
+                std::process::exit(1);
+            }
+        }
+        Commands::VerifyDir { dir, report, jobs, json } => with_json(json, |json| {
+            let jobs = jobs.unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get()));
+            let verification = verifier::verify_dir(&dir, jobs, |file, done, total| {
+                match &file.error {
+                    None => say!("[{}/{}] ✓ {}", done, total, file.path.display()),
+                    Some(e) => say!("[{}/{}] ❌ {}: {}", done, total, file.path.display(), e),
+                }
+                if let Some(json) = json {
+                    json.emit(&match &file.error {
+                        None => JsonEvent::Result { output: file.path.clone(), size: file.size, duration: file.duration },
+                        Some(e) => JsonEvent::error(&format!("{}: {}", file.path.display(), e)),
+                    });
+                }
+            })?;
+            if let Some(path) = &report {
+                verification.write(path)?;
+                say!("Report written to {:?}", path);
+            }
+
+            let failed = verification.failed();
+            if let Some(json) = json {
+                json.emit(&JsonEvent::Summary { succeeded: verification.files.len() - failed, failed });
+            }
+            say!("🎉 Verified {} file(s): {} passed, {} failed", verification.files.len(), verification.files.len() - failed, failed);
+            if failed > 0 {
+                std::process::exit(1);
+            }
+            Ok(())
+        })?,
+        Commands::Repair { files, backup, json } => with_json(json, |json| {
+            let mut results = Vec::new();
+            for file in &files {
+                match repair::repair(file, backup, &ResourceLimits::default()) {
+                    Ok(outcome) => {
+                        say!("🔧 {}", outcome);
+                        results.push(Ok(outcome.path));
+                    }
+                    Err(e) => {
+                        eprintln!("❌ {}: {:#}", file.display(), e);
+                        results.push(Err(e.context(file.display().to_string())));
+                    }
+                }
+            }
+            if let Some(json) = json {
+                emit_results(json, &results);
+            }
+            let failed = results.iter().filter(|r| r.is_err()).count();
+            say!("🎉 Repaired {} file(s), {} failed", files.len() - failed, failed);
+            if failed > 0 {
+                std::process::exit(1);
+            }
+            Ok(())
+        })?,
+        Commands::Clean { run, dry_run, force, trash, quarantine } => {
+            let plan = CleanPlan::new(&BatchReport::load(&run)?, force);
+            say!("{}", plan);
+            if dry_run {
+                say!("Dry run; nothing was removed");
+            } else {
+                let removed = plan.execute(&Disposal::from_flags(trash, quarantine.as_deref()))?;
+                say!("🧹 Removed {} file(s)", removed);
+            }
+        }
+        Commands::Tracks { input } => {
+            let extractor = AudioExtractor::new(Args { input, ..Default::default() });
+            extractor.validate_input()?;
+            let tracks = extractor.audio_tracks()?;
+            if tracks.is_empty() {
+                say!("{:?} has no audio tracks", extractor.args.input);
+            }
+            for track in tracks {
+                say!("{}", track);
+            }
+        }
+        Commands::Probe { input, json } => {
+            let extractor = AudioExtractor::new(Args { input, ..Default::default() });
+            extractor.validate_input()?;
+            let info = extractor.probe()?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&info)?);
+            } else {
+                print!("{}", info);
+            }
+        }
+        Commands::Status { file } => {
+            let statuses = match file {
+                Some(file) => vec![status::read(&file)?],
+                None => status::read_all(&status::status_dir())?.into_iter().map(|(_, s)| s).collect(),
+            };
+            if statuses.is_empty() {
+                say!("No batches found");
+            }
+            for status in statuses {
+                say!("{}", status);
+            }
+        }
+        Commands::Ps => {
+            let processes = supervisor::list(&supervisor::process_dir())?;
+            if processes.is_empty() {
+                say!("No processes found");
+            }
+            for process in processes {
+                say!("{}", process);
+            }
+        }
+        Commands::Kill { pids, orphans, all, force } => {
+            let processes = supervisor::list(&supervisor::process_dir())?;
+            // Only processes this tool started can be stopped
+            if let Some(pid) = pids.iter().find(|pid| !processes.iter().any(|p| p.pid == **pid)) {
+                anyhow::bail!("Process {} was not started by audio_extractor or has exited", pid);
+            }
+            let mut failed = false;
+            for process in processes.iter().filter(|p| all || (orphans && p.is_orphaned()) || pids.contains(&p.pid)) {
+                match supervisor::kill(process, force) {
+                    Ok(()) => say!("Stopped {}", process.pid),
+                    Err(e) => {
+                        eprintln!("⚠ {:#}", e);
+                        failed = true;
+                    }
+                }
+            }
+            if failed {
+                std::process::exit(1);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn run_dry_run(extractor: &AudioExtractor, json: Option<&JsonLines>) -> Result<()> {
+    let dry_run = extractor.dry_run()?;
+    match json {
+        Some(json) => json.emit(&JsonEvent::DryRun { report: dry_run.clone() }),
+        None => print!("{}", dry_run),
+    }
+    if let Some(path) = &extractor.args.dry_run_report {
+        dry_run.write(path)?;
+        say!("Dry run report written to {:?}", path);
+    }
+    Ok(())
+}
+
+fn run_extraction(args: Args) -> Result<()> {
+    with_json(args.json, |json| extract_from_cli(args, json))
+}
+
+/// Run a command, printing JSON lines on standard output in place of the
+/// usual messages if `json` is set. An error then becomes an `error` event
+/// and exit status 1.
+fn with_json(json: bool, run: impl FnOnce(Option<&JsonLines>) -> Result<()>) -> Result<()> {
+    if !json {
+        return run(None);
+    }
+    let json = JsonLines::stdout();
+    if let Err(error) = run(Some(&json)) {
+        json.emit(&JsonEvent::error(&error));
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn extract_from_cli(args: Args, json: Option<&JsonLines>) -> Result<()> {
     let extractor = AudioExtractor::new(args);
+    
+    if extractor.args.all_titles {
+        return run_all_titles(&extractor, json);
+    }
+    if extractor.args.all_tracks {
+        return run_all_tracks(&extractor, json);
+    }
+    if extractor.args.split_chapters {
+        return run_chapters(&extractor, json);
+    }
+    
+    let is_scan = extractor.args.input.is_dir()
+        && Disc::detect(&extractor.args.input).is_none()
+        && project::audio_track(&extractor.args.input)?.is_none();
+    if extractor.args.dry_run {
+        if is_scan {
+            anyhow::bail!("--dry-run analyzes a single input; pass one file of the batch as a sample");
+        }
+        return run_dry_run(&extractor, json);
+    }
+    if is_scan {
+        return run_directory(&extractor, json);
+    }
+    if extractor.args.batch_budget.is_some() {
+        anyhow::bail!("--batch-budget applies to directory scans; use --target-size for a single input");
+    }
+    let mut extractor = extractor;
+    if let Some(output_template) = extractor.args.output_template.take() {
+        extractor.args.output = extractor.args.output.join(output_template);
+    }
+    let mut extractor = extractor.with_expanded_output()?;
+    if let Some(reason) = extractor.run_hook()? {
+        say!("⏭ Hook skipped {:?}: {}", extractor.args.input, reason);
+        return Ok(());
+    }
 
     // Show what we're about to do
-    println!("Audio Extractor v{}", env!("CARGO_PKG_VERSION"));
-    println!("Input: {:?}", extractor.args.input);
-    println!("Output: {:?}", extractor.args.output);
-    println!("Format: {}", extractor.args.format.as_ref().unwrap());
-    println!("Quality: {} kbps", extractor.args.quality.unwrap());
+    say!("Audio Extractor v{}", env!("CARGO_PKG_VERSION"));
+    say!("Input: {:?}", extractor.args.input);
+    say!("Output: {:?}", extractor.args.output);
+    say!("Format: {}", extractor.args.format.as_ref().unwrap());
+    match (extractor.args.target_size, extractor.args.quality.unwrap()) {
+        _ if extractor.args.vbr.is_some() => say!("Quality: VBR"),
+        (Some(bytes), _) => say!("Quality: fit to {}", preflight::format_size(bytes)),
+        (None, quality::AUTO) => say!("Quality: auto"),
+        (None, kbps) => say!("Quality: {} kbps", kbps),
+    }
     if extractor.args.verify {
-        println!("Verification: enabled");
+        say!("Verification: enabled");
+    }
+    say!();
+    if let Some(json) = json {
+        json.emit(&JsonEvent::Started { input: extractor.args.input.clone(), output: extractor.args.output.clone() });
     }
-    println!();
     
-    match extractor.extract_with_progress(|msg| println!("📄 {}", msg)) {
+    // Encoding updates overwrite each other on one line
+    let encoding = AtomicBool::new(false);
+    let progress = |event: &ProgressEvent| match (json, event) {
+        (Some(json), event) => {
+            if let Some(event) = JsonEvent::progress(event) {
+                json.emit(&event);
+            }
+        }
+        (None, ProgressEvent::Encoding { .. }) => {
+            print!("\r📄 {}", event);
+            let _ = std::io::stdout().flush();
+            encoding.store(true, Ordering::Relaxed);
+        }
+        // The extractor prints its own warning
+        (None, ProgressEvent::Stalled { .. }) => {
+            if encoding.swap(false, Ordering::Relaxed) {
+                say!();
+            }
+        }
+        (None, _) => {
+            if encoding.swap(false, Ordering::Relaxed) {
+                say!();
+            }
+            say!("📄 {}", event);
+        }
+    };
+    match extractor.extract_with_progress(progress) {
         Ok(()) => {
-            println!("✅ Audio extraction completed successfully!");
+            say!("✅ Audio extraction completed successfully!");
+            if let Some(json) = json {
+                json.emit(&JsonEvent::result(&extractor.args.output));
+            }
+            extractor.notify_library(std::slice::from_ref(&extractor.args.output));
         }
         Err(e) => {
             eprintln!("❌ Error: {}", e);
+            if let Some(json) = json {
+                json.emit(&JsonEvent::error(&e));
+            }
             std::process::exit(1);
         }
     }
     
     Ok(())
 }
+
+fn run_directory(extractor: &AudioExtractor, json: Option<&JsonLines>) -> Result<()> {
+    say!("Audio Extractor v{}", env!("CARGO_PKG_VERSION"));
+    say!("Scanning {:?} into {:?}", extractor.args.input, extractor.args.output);
+    say!();
+
+    let started_at = chrono::Utc::now().to_rfc3339();
+    let results = extractor.extract_directory()?;
+    report_results("scan", &extractor.args.input, started_at, &results);
+    finish_outputs(extractor, json, "Scan", &results)
+}
+
+fn run_all_titles(extractor: &AudioExtractor, json: Option<&JsonLines>) -> Result<()> {
+    say!("Audio Extractor v{}", env!("CARGO_PKG_VERSION"));
+    say!();
+
+    let started_at = chrono::Utc::now().to_rfc3339();
+    let report = extractor.extract_all_titles()?;
+    let failures = report.titles.iter()
+        .filter_map(|t| t.error.as_ref().map(|e| format!("title {}: {}", t.index, e)))
+        .collect::<Vec<_>>();
+    let summary = BatchSummary::new("titles", &report.disc, started_at, report.titles.len() - failures.len(), failures);
+    AudioExtractor::report_batch(&summary);
+    let results: Vec<Result<PathBuf>> = report.titles.iter()
+        .map(|t| if t.success {
+            Ok(t.output.clone())
+        } else {
+            Err(anyhow::anyhow!("title {}: {}", t.index, t.error.as_deref().unwrap_or("failed")))
+        })
+        .collect();
+    finish_outputs(extractor, json, "Disc", &results)
+}
+
+fn run_all_tracks(extractor: &AudioExtractor, json: Option<&JsonLines>) -> Result<()> {
+    say!("Audio Extractor v{}", env!("CARGO_PKG_VERSION"));
+    say!();
+
+    let started_at = chrono::Utc::now().to_rfc3339();
+    let results = extractor.extract_all_tracks()?;
+    report_results("tracks", &extractor.args.input, started_at, &results);
+    finish_outputs(extractor, json, "Tracks", &results)
+}
+
+fn run_chapters(extractor: &AudioExtractor, json: Option<&JsonLines>) -> Result<()> {
+    say!("Audio Extractor v{}", env!("CARGO_PKG_VERSION"));
+    say!();
+
+    let started_at = chrono::Utc::now().to_rfc3339();
+    let results = extractor.extract_chapters()?;
+    report_results("chapters", &extractor.args.input, started_at, &results);
+    finish_outputs(extractor, json, "Chapters", &results)
+}
+
+/// End a run with several outputs: print its errors, emit its --json events,
+/// pass the outputs to --notify-library and print the totals, exiting with
+/// status 1 if any output failed
+fn finish_outputs<E: std::fmt::Display>(extractor: &AudioExtractor, json: Option<&JsonLines>, run: &str, results: &[Result<PathBuf, E>]) -> Result<()> {
+    for error in results.iter().filter_map(|r| r.as_ref().err()) {
+        eprintln!("❌ Error: {}", error);
+    }
+    if let Some(json) = json {
+        emit_results(json, results);
+    }
+
+    let outputs: Vec<_> = results.iter().filter_map(|r| r.as_ref().ok().cloned()).collect();
+    extractor.notify_library(&outputs);
+
+    let failed = results.iter().filter(|r| r.is_err()).count();
+    say!("🎉 {} finished: {} extracted, {} failed", run, results.len() - failed, failed);
+    if failed > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// The --json events for the outputs and errors of a run with several outputs
+fn emit_results<E: std::fmt::Display>(json: &JsonLines, results: &[Result<PathBuf, E>]) {
+    for result in results {
+        json.emit(&match result {
+            Ok(output) => JsonEvent::result(output),
+            Err(error) => JsonEvent::error(error),
+        });
+    }
+    let failed = results.iter().filter(|r| r.is_err()).count();
+    json.emit(&JsonEvent::Summary { succeeded: results.len() - failed, failed });
+}
+
+fn report_results<E: std::fmt::Display>(kind: &str, source: &Path, started_at: String, results: &[Result<PathBuf, E>]) {
+    let failures: Vec<_> = results.iter().filter_map(|r| r.as_ref().err()).map(|e| format!("{:#}", e)).collect();
+    let summary = BatchSummary::new(kind, source, started_at, results.len() - failures.len(), failures);
+    AudioExtractor::report_batch(&summary);
+}
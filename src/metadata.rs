@@ -0,0 +1,87 @@
+use crate::limits::ResourceLimits;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::ffi::OsString;
+use std::process::Command;
+
+/// Descriptive tags of the source, as reported by ffprobe.
+///
+/// Container tags are looked up case-insensitively under the names common
+/// muxers and taggers use, e.g. `show`/`tvshow`, `date`/`creation_time`.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct SourceMetadata {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub show: Option<String>,
+    pub season: Option<u32>,
+    pub episode: Option<u32>,
+    pub genre: Option<String>,
+    /// Recording date as `YYYY-MM-DD`, or just the year if that is all there is
+    pub recorded_date: Option<String>,
+    pub description: Option<String>,
+    /// Duration in seconds
+    pub duration: Option<f64>,
+}
+
+impl SourceMetadata {
+    /// Read the source's format tags with ffprobe. `input_args` are the
+    /// options and `-i` selecting the source.
+    pub fn probe(input_args: &[OsString], limits: &ResourceLimits) -> Result<Self> {
+        let mut cmd = Command::new("ffprobe");
+        cmd.arg("-v").arg("error")
+            .arg("-print_format").arg("json")
+            .arg("-show_format")
+            .args(input_args);
+        let output = limits.output(&mut cmd).context("Failed to run ffprobe")?;
+        if !output.status.success() {
+            anyhow::bail!("ffprobe failed to read metadata: {}", String::from_utf8_lossy(&output.stderr).trim());
+        }
+        Self::from_ffprobe_json(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    /// Parse ffprobe's `-show_format` JSON
+    pub fn from_ffprobe_json(json: &str) -> Result<Self> {
+        let probe: serde_json::Value = serde_json::from_str(json).context("Invalid ffprobe output")?;
+        let format = &probe["format"];
+        let tag = |names: &[&str]| -> Option<String> {
+            let tags = format["tags"].as_object()?;
+            names.iter().find_map(|name| {
+                tags.iter()
+                    .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                    .and_then(|(_, value)| value.as_str())
+                    .map(str::trim)
+                    .filter(|value| !value.is_empty())
+                    .map(str::to_string)
+            })
+        };
+        // "3/12" track-style numbers keep only the first part
+        let number = |names: &[&str]| -> Option<u32> {
+            tag(names)?.split('/').next()?.trim().parse().ok()
+        };
+
+        Ok(Self {
+            title: tag(&["title"]),
+            artist: tag(&["artist", "album_artist", "author", "composer"]),
+            album: tag(&["album"]),
+            show: tag(&["show", "tvshow", "series"]),
+            season: number(&["season_number", "season"]),
+            episode: number(&["episode_sort", "episode_number", "episode_id"]),
+            genre: tag(&["genre"]),
+            recorded_date: tag(&["date", "creation_time", "year"]).and_then(|date| normalize_date(&date)),
+            description: tag(&["description", "synopsis", "comment"]),
+            duration: format["duration"].as_str().and_then(|d| d.parse().ok()),
+        })
+    }
+}
+
+/// Reduce `2023-05-14T18:22:01.000000Z`, `2023-05-14` or `2023` to the date part
+fn normalize_date(value: &str) -> Option<String> {
+    let date = value.split(['T', ' ']).next()?;
+    let valid = match date.len() {
+        4 => date.chars().all(|c| c.is_ascii_digit()),
+        10 => chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok(),
+        _ => false,
+    };
+    valid.then(|| date.to_string())
+}
@@ -0,0 +1,534 @@
+use crate::effects::{EffectChain, NativeEffects};
+use crate::flac::FlacWriter;
+use crate::tags::Tags;
+use crate::disc::Disc;
+use crate::{say, timecode, warning, AudioExtractor, AudioFormat, BitDepth};
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use std::fs::File;
+use std::io::{Cursor, Seek, SeekFrom, Write};
+use std::path::Path;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::{FormatOptions, Track};
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::{MetadataOptions, MetadataRevision, StandardTagKey};
+use symphonia::core::probe::Hint;
+
+/// How the audio gets out of the source
+#[derive(Clone, Copy, ValueEnum, Debug, Default, PartialEq)]
+pub enum Backend {
+    /// FFmpeg if it is installed, else the native backend
+    #[default]
+    Auto,
+    /// Run the `ffmpeg` binary, supporting every format and option
+    Ffmpeg,
+    /// Demultiplex and decode in-process with symphonia, writing WAV, FLAC or AIFF
+    Native,
+}
+
+/// What the native backend wrote
+#[derive(Debug, Clone, PartialEq)]
+pub struct NativeOutput {
+    pub codec: String,
+    pub sample_rate: u32,
+    pub channels: usize,
+    /// Samples per channel written
+    pub frames: u64,
+    /// Packets that failed to decode and were left out
+    pub skipped_packets: usize,
+    /// Tags copied from the source, by `TAG_NAMES` name
+    pub tags: Vec<(&'static str, String)>,
+}
+
+impl NativeOutput {
+    pub fn duration(&self) -> f64 {
+        self.frames as f64 / self.sample_rate as f64
+    }
+}
+
+/// Source tags the native backend copies, and the names it gives them
+const TAG_NAMES: [(StandardTagKey, &str); 8] = [
+    (StandardTagKey::TrackTitle, "title"),
+    (StandardTagKey::Artist, "artist"),
+    (StandardTagKey::AlbumArtist, "album_artist"),
+    (StandardTagKey::Album, "album"),
+    (StandardTagKey::Date, "date"),
+    (StandardTagKey::Genre, "genre"),
+    (StandardTagKey::Comment, "comment"),
+    (StandardTagKey::TrackNumber, "track"),
+];
+
+/// The tags of `revisions` the native backend copies, in `TAG_NAMES`
+/// order. A tag in an earlier revision wins over the same tag in a later one.
+pub fn standard_tags<'a>(revisions: impl IntoIterator<Item = &'a MetadataRevision>) -> Vec<(&'static str, String)> {
+    let revisions: Vec<_> = revisions.into_iter().collect();
+    TAG_NAMES
+        .iter()
+        .filter_map(|(key, name)| {
+            let value = revisions
+                .iter()
+                .flat_map(|revision| revision.tags())
+                .find(|tag| tag.std_key == Some(*key))?
+                .value
+                .to_string();
+            // RIFF INFO values keep their NUL terminator
+            let value = value.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+            (!value.is_empty()).then(|| (*name, value.to_string()))
+        })
+        .collect()
+}
+
+/// Whether the native backend can write `format`; it has no lossy encoders
+pub fn supports(format: &AudioFormat) -> bool {
+    matches!(format, AudioFormat::Wav | AudioFormat::Flac | AudioFormat::Aiff)
+}
+
+/// Decode audio stream `track` (counting among the audio streams, or the
+/// first one) of `input` and write it to `writer` as 16-bit WAV, FLAC or AIFF,
+/// keeping only the samples between `start` and `end` seconds. Everything
+/// before `start` is decoded and dropped rather than seeked past, so trimming
+/// is sample-accurate in every container. With `copy_tags`, the source's
+/// title, artist and similar tags are written to the output; `set` replaces
+/// or adds to them.
+#[allow(clippy::too_many_arguments)]
+pub fn extract<W: Write + Seek>(
+    input: &Path,
+    track: Option<usize>,
+    start: f64,
+    end: Option<f64>,
+    format: &AudioFormat,
+    copy_tags: bool,
+    set: &Tags,
+    writer: W,
+) -> Result<(W, NativeOutput)> {
+    extract_with_effects(input, track, start, end, format, copy_tags, set, &EffectChain::default(), writer)
+}
+
+/// Like `extract`, running the kept samples through `effects` before they
+/// are written. Effect times are in source time, as with FFmpeg.
+#[allow(clippy::too_many_arguments)]
+pub fn extract_with_effects<W: Write + Seek>(
+    input: &Path,
+    track: Option<usize>,
+    start: f64,
+    end: Option<f64>,
+    format: &AudioFormat,
+    copy_tags: bool,
+    set: &Tags,
+    effects: &EffectChain,
+    writer: W,
+) -> Result<(W, NativeOutput)> {
+    effects.check_native()?;
+    if !supports(format) {
+        anyhow::bail!("The native backend writes WAV, FLAC and AIFF only; {} output needs FFmpeg", format);
+    }
+
+    let file = File::open(input).with_context(|| format!("Failed to open {:?}", input))?;
+    let mss = MediaSourceStream::new(Box::new(file), Default::default());
+    let mut hint = Hint::new();
+    if let Some(extension) = input.extension() {
+        hint.with_extension(&extension.to_string_lossy());
+    }
+    let probed = symphonia::default::get_probe()
+        .format(&hint, mss, &FormatOptions::default(), &MetadataOptions::default())
+        .with_context(|| format!("{:?} is not in a container the native backend can read", input))?;
+    let mut probed_metadata = probed.metadata;
+    let mut reader = probed.format;
+    let tags = if copy_tags {
+        // Tags ahead of the container, such as ID3, then the container's own
+        let outer = probed_metadata.get();
+        standard_tags(outer.as_ref().and_then(|m| m.current()).into_iter().chain(reader.metadata().current()))
+    } else {
+        Vec::new()
+    };
+    let tags = set.apply(tags);
+
+    let audio: Vec<&Track> = reader
+        .tracks()
+        .iter()
+        .filter(|t| t.codec_params.codec != CODEC_TYPE_NULL && t.codec_params.sample_rate.is_some())
+        .collect();
+    let chosen = match track {
+        Some(index) => audio.get(index).with_context(|| {
+            format!("There is no audio track {}; the native backend found {} in {:?}", index, audio.len(), input)
+        })?,
+        None => audio.first().with_context(|| format!("The native backend found no audio it can decode in {:?}", input))?,
+    };
+    let track_id = chosen.id;
+    let codec = symphonia::default::get_codecs()
+        .get_codec(chosen.codec_params.codec)
+        .map(|descriptor| descriptor.short_name.to_string())
+        .unwrap_or_else(|| chosen.codec_params.codec.to_string());
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&chosen.codec_params, &DecoderOptions::default())
+        .with_context(|| format!("The native backend cannot decode {} audio", codec))?;
+
+    let mut encoder: Option<Encoder<W>> = None;
+    let mut processor: Option<NativeEffects> = None;
+    // Sample rate the source is decoded at, before any resampling
+    let mut source_rate = 0;
+    let mut writer = Some(writer);
+    let mut buffer: Option<SampleBuffer<i16>> = None;
+    let mut output = NativeOutput { codec, sample_rate: 0, channels: 0, frames: 0, skipped_packets: 0, tags };
+    // Samples per channel decoded so far, counting those trimmed off
+    let mut position = 0u64;
+
+    loop {
+        let packet = match reader.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {:?}", input)),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            Err(SymphoniaError::DecodeError(_)) => {
+                output.skipped_packets += 1;
+                continue;
+            }
+            Err(e) => return Err(e).with_context(|| format!("Failed to decode {:?}", input)),
+        };
+
+        let spec = *decoded.spec();
+        let channels = spec.channels.count();
+        if encoder.is_none() {
+            let effects = NativeEffects::new(effects, spec.rate, channels, start)?;
+            source_rate = spec.rate;
+            output.sample_rate = effects.output_rate(spec.rate);
+            output.channels = channels;
+            encoder = Some(Encoder::new(format, writer.take().unwrap(), output.sample_rate, channels, &output.tags)?);
+            processor = Some(effects);
+        } else if spec.rate != source_rate || channels != output.channels {
+            anyhow::bail!("The audio in {:?} changes format midway, which the native backend cannot follow", input);
+        }
+        if buffer.as_ref().is_none_or(|b| b.capacity() < decoded.capacity()) {
+            buffer = Some(SampleBuffer::new(decoded.capacity() as u64, spec));
+        }
+        let buffer = buffer.as_mut().unwrap();
+        buffer.copy_interleaved_ref(decoded);
+        let samples = buffer.samples();
+        let frames = (samples.len() / channels) as u64;
+
+        let first = (start * spec.rate as f64).round() as u64;
+        let last = end.map_or(u64::MAX, |end| (end * spec.rate as f64).round() as u64);
+        let from = first.saturating_sub(position).min(frames);
+        let to = last.saturating_sub(position).min(frames);
+        position += frames;
+        if from < to {
+            let processed = processor.as_mut().unwrap().process(&samples[from as usize * channels..to as usize * channels]);
+            encoder.as_mut().unwrap().write(&processed)?;
+            output.frames += (processed.len() / channels) as u64;
+        }
+        if position >= last {
+            break;
+        }
+    }
+
+    let encoder = encoder.with_context(|| format!("No audio could be decoded from {:?}", input))?;
+    Ok((encoder.finish()?, output))
+}
+
+enum Encoder<W: Write + Seek> {
+    Wav(WavWriter<W>),
+    Flac(FlacWriter<W>),
+    Aiff(AiffWriter<W>),
+}
+
+impl<W: Write + Seek> Encoder<W> {
+    fn new(format: &AudioFormat, writer: W, sample_rate: u32, channels: usize, tags: &[(&str, String)]) -> Result<Self> {
+        match format {
+            AudioFormat::Flac => Ok(Encoder::Flac(FlacWriter::with_tags(writer, sample_rate, channels, tags)?)),
+            AudioFormat::Aiff => Ok(Encoder::Aiff(AiffWriter::with_tags(writer, sample_rate, channels, tags)?)),
+            _ => Ok(Encoder::Wav(WavWriter::with_tags(writer, sample_rate, channels, tags)?)),
+        }
+    }
+
+    fn write(&mut self, interleaved: &[i16]) -> Result<()> {
+        match self {
+            Encoder::Wav(wav) => wav.write(interleaved),
+            Encoder::Flac(flac) => flac.write(interleaved),
+            Encoder::Aiff(aiff) => aiff.write(interleaved),
+        }
+    }
+
+    fn finish(self) -> Result<W> {
+        match self {
+            Encoder::Wav(wav) => wav.finish(),
+            Encoder::Flac(flac) => flac.finish(),
+            Encoder::Aiff(aiff) => aiff.finish(),
+        }
+    }
+}
+
+/// 16-bit PCM WAV writer; the RIFF and data sizes are filled in on finish
+pub struct WavWriter<W: Write + Seek> {
+    writer: W,
+    /// Bytes before the samples, ending with the data chunk's size
+    header_len: u64,
+    data_len: u64,
+}
+
+impl<W: Write + Seek> WavWriter<W> {
+    pub fn new(writer: W, sample_rate: u32, channels: usize) -> Result<Self> {
+        Self::with_tags(writer, sample_rate, channels, &[])
+    }
+
+    /// Start a WAV file whose LIST INFO chunk holds `tags`, named as in
+    /// `standard_tags`; tags RIFF INFO has no field for are left out
+    pub fn with_tags(mut writer: W, sample_rate: u32, channels: usize, tags: &[(&str, String)]) -> Result<Self> {
+        let block_align = channels as u16 * 2;
+        let mut header = Vec::with_capacity(44);
+        header.extend_from_slice(b"RIFF");
+        header.extend_from_slice(&0u32.to_le_bytes());
+        header.extend_from_slice(b"WAVEfmt ");
+        header.extend_from_slice(&16u32.to_le_bytes());
+        header.extend_from_slice(&1u16.to_le_bytes());
+        header.extend_from_slice(&(channels as u16).to_le_bytes());
+        header.extend_from_slice(&sample_rate.to_le_bytes());
+        header.extend_from_slice(&(sample_rate * block_align as u32).to_le_bytes());
+        header.extend_from_slice(&block_align.to_le_bytes());
+        header.extend_from_slice(&16u16.to_le_bytes());
+        header.extend_from_slice(&info_chunk(tags));
+        header.extend_from_slice(b"data");
+        header.extend_from_slice(&0u32.to_le_bytes());
+        writer.write_all(&header).context("Failed to write WAV header")?;
+        Ok(Self { writer, header_len: header.len() as u64, data_len: 0 })
+    }
+
+    pub fn write(&mut self, interleaved: &[i16]) -> Result<()> {
+        let bytes: Vec<u8> = interleaved.iter().flat_map(|sample| sample.to_le_bytes()).collect();
+        self.writer.write_all(&bytes).context("Failed to write WAV data")?;
+        self.data_len += bytes.len() as u64;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<W> {
+        let riff_len = u32::try_from(self.header_len - 8 + self.data_len)
+            .ok()
+            .context("The audio is over 4 GB, too long for WAV; use FLAC instead")?;
+        self.writer.seek(SeekFrom::Start(4))?;
+        self.writer.write_all(&riff_len.to_le_bytes())?;
+        self.writer.seek(SeekFrom::Start(self.header_len - 4))?;
+        self.writer.write_all(&(self.data_len as u32).to_le_bytes())?;
+        self.writer.seek(SeekFrom::End(0))?;
+        self.writer.flush().context("Failed to write WAV data")?;
+        Ok(self.writer)
+    }
+}
+
+/// RIFF INFO fields for the names `standard_tags` gives
+fn info_id(name: &str) -> Option<&'static [u8; 4]> {
+    match name {
+        "title" => Some(b"INAM"),
+        "artist" => Some(b"IART"),
+        "album" => Some(b"IPRD"),
+        "date" => Some(b"ICRD"),
+        "genre" => Some(b"IGNR"),
+        "comment" => Some(b"ICMT"),
+        "track" => Some(b"IPRT"),
+        _ => None,
+    }
+}
+
+/// A LIST INFO chunk holding `tags`, or nothing if none of them fit
+fn info_chunk(tags: &[(&str, String)]) -> Vec<u8> {
+    let mut fields = Vec::new();
+    for (name, value) in tags {
+        let Some(id) = info_id(name) else {
+            continue;
+        };
+        let mut text = value.as_bytes().to_vec();
+        text.push(0);
+        fields.extend_from_slice(id);
+        fields.extend_from_slice(&(text.len() as u32).to_le_bytes());
+        fields.extend_from_slice(&text);
+        // Chunks start on even offsets
+        if text.len() % 2 == 1 {
+            fields.push(0);
+        }
+    }
+    if fields.is_empty() {
+        return fields;
+    }
+    let mut chunk = b"LIST".to_vec();
+    chunk.extend_from_slice(&(fields.len() as u32 + 4).to_le_bytes());
+    chunk.extend_from_slice(b"INFO");
+    chunk.extend_from_slice(&fields);
+    chunk
+}
+
+/// 16-bit PCM AIFF writer; the FORM, COMM frame count and SSND sizes are
+/// filled in on finish
+pub struct AiffWriter<W: Write + Seek> {
+    writer: W,
+    channels: usize,
+    /// Bytes before the samples, ending with the SSND offset and block size
+    header_len: u64,
+    data_len: u64,
+}
+
+impl<W: Write + Seek> AiffWriter<W> {
+    pub fn new(writer: W, sample_rate: u32, channels: usize) -> Result<Self> {
+        Self::with_tags(writer, sample_rate, channels, &[])
+    }
+
+    /// Start an AIFF file with NAME, AUTH and ANNO chunks for the title,
+    /// artist and comment among `tags`, named as in `standard_tags`; AIFF
+    /// has no chunks for the others
+    pub fn with_tags(mut writer: W, sample_rate: u32, channels: usize, tags: &[(&str, String)]) -> Result<Self> {
+        let mut header = Vec::with_capacity(54);
+        header.extend_from_slice(b"FORM");
+        header.extend_from_slice(&0u32.to_be_bytes());
+        header.extend_from_slice(b"AIFFCOMM");
+        header.extend_from_slice(&18u32.to_be_bytes());
+        header.extend_from_slice(&(channels as u16).to_be_bytes());
+        header.extend_from_slice(&0u32.to_be_bytes());
+        header.extend_from_slice(&16u16.to_be_bytes());
+        header.extend_from_slice(&extended(sample_rate));
+        for (name, value) in tags {
+            if let Some(id) = text_chunk_id(name) {
+                header.extend_from_slice(&text_chunk(id, value));
+            }
+        }
+        header.extend_from_slice(b"SSND");
+        header.extend_from_slice(&0u32.to_be_bytes());
+        header.extend_from_slice(&0u32.to_be_bytes());
+        header.extend_from_slice(&0u32.to_be_bytes());
+        writer.write_all(&header).context("Failed to write AIFF header")?;
+        Ok(Self { writer, channels, header_len: header.len() as u64, data_len: 0 })
+    }
+
+    pub fn write(&mut self, interleaved: &[i16]) -> Result<()> {
+        let bytes: Vec<u8> = interleaved.iter().flat_map(|sample| sample.to_be_bytes()).collect();
+        self.writer.write_all(&bytes).context("Failed to write AIFF data")?;
+        self.data_len += bytes.len() as u64;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<W> {
+        let form_len = u32::try_from(self.header_len - 8 + self.data_len)
+            .ok()
+            .context("The audio is over 4 GB, too long for AIFF; use FLAC instead")?;
+        let frames = self.data_len / (self.channels as u64 * 2);
+        self.writer.seek(SeekFrom::Start(4))?;
+        self.writer.write_all(&form_len.to_be_bytes())?;
+        // The frame count follows COMM's id, size and channel count
+        self.writer.seek(SeekFrom::Start(22))?;
+        self.writer.write_all(&(frames as u32).to_be_bytes())?;
+        self.writer.seek(SeekFrom::Start(self.header_len - 12))?;
+        self.writer.write_all(&(self.data_len as u32 + 8).to_be_bytes())?;
+        self.writer.seek(SeekFrom::End(0))?;
+        self.writer.flush().context("Failed to write AIFF data")?;
+        Ok(self.writer)
+    }
+}
+
+/// AIFF text chunks for the names `standard_tags` gives
+fn text_chunk_id(name: &str) -> Option<&'static [u8; 4]> {
+    match name {
+        "title" => Some(b"NAME"),
+        "artist" => Some(b"AUTH"),
+        "comment" => Some(b"ANNO"),
+        _ => None,
+    }
+}
+
+fn text_chunk(id: &[u8; 4], text: &str) -> Vec<u8> {
+    let mut chunk = id.to_vec();
+    chunk.extend_from_slice(&(text.len() as u32).to_be_bytes());
+    chunk.extend_from_slice(text.as_bytes());
+    // Chunks start on even offsets
+    if text.len() % 2 == 1 {
+        chunk.push(0);
+    }
+    chunk
+}
+
+/// `rate` as the 80-bit IEEE extended float COMM holds the sample rate in
+fn extended(rate: u32) -> [u8; 10] {
+    let mut bytes = [0u8; 10];
+    if rate == 0 {
+        return bytes;
+    }
+    let shift = rate.leading_zeros();
+    let exponent = 16383 + 31 - shift as u16;
+    let mantissa = (rate as u64) << (32 + shift);
+    bytes[..2].copy_from_slice(&exponent.to_be_bytes());
+    bytes[2..].copy_from_slice(&mantissa.to_be_bytes());
+    bytes
+}
+
+impl AudioExtractor {
+    /// Extract with the native backend: decode the source in-process with
+    /// symphonia and write WAV, FLAC or AIFF, for machines without FFmpeg. Options
+    /// that rely on FFmpeg filters or inputs are refused rather than ignored.
+    pub(crate) fn extract_audio_native(&self) -> Result<()> {
+        let format = self.args.format.as_ref().unwrap();
+        if !supports(format) {
+            anyhow::bail!(
+                "{} output needs FFmpeg, which was not found. Install it (macOS: brew install ffmpeg, \
+                 Ubuntu/Debian: sudo apt install ffmpeg, Windows: https://ffmpeg.org/download.html) \
+                 or extract to WAV, FLAC or AIFF with the native backend",
+                format.to_string().to_uppercase()
+            );
+        }
+        if self.bit_depth()? != BitDepth::Sixteen {
+            anyhow::bail!("{}-bit output needs FFmpeg; the native backend writes 16-bit samples", self.bit_depth()?.bits());
+        }
+        if self.args.sample_rate.is_some() || self.args.channels.is_some() {
+            anyhow::bail!("--sample-rate and --channels need FFmpeg; the native backend keeps the source's");
+        }
+        if self.args.normalize {
+            anyhow::bail!("--normalize needs FFmpeg to measure and adjust the loudness");
+        }
+        if !self.options_graph()?.is_empty() {
+            anyhow::bail!("Filters (--mute, --watermark, --keep-segments, timestamp repair) need FFmpeg; the native backend only decodes");
+        }
+        if Disc::detect(self.source()).is_some() {
+            anyhow::bail!("Reading DVD and Blu-ray structures needs FFmpeg");
+        }
+        self.time_range_args(None)?;
+
+        let start = self.args.start.unwrap_or(0.0);
+        let end = self.args.end.or(self.args.duration.map(|duration| start + duration));
+        say!("⚙ Decoding with the native backend (no FFmpeg)");
+        let result = match self.encryption() {
+            Some(encryption) => extract_with_effects(self.source(), self.args.audio_track, start, end, format, self.copies_metadata(), &self.tags(), &self.effects, Cursor::new(Vec::new()))
+                .and_then(|(buffer, output)| {
+                    encryption.encrypt_bytes(&buffer.into_inner(), &self.args.output)?;
+                    Ok(output)
+                }),
+            None => File::create(&self.args.output)
+                .with_context(|| format!("Failed to create {:?}", self.args.output))
+                .and_then(|file| extract_with_effects(self.source(), self.args.audio_track, start, end, format, self.copies_metadata(), &self.tags(), &self.effects, std::io::BufWriter::new(file)))
+                .map(|(_, output)| output),
+        };
+        let output = match result {
+            Ok(output) => output,
+            Err(e) => {
+                let _ = std::fs::remove_file(&self.args.output);
+                return Err(e);
+            }
+        };
+
+        say!(
+            "Decoded {} audio: {} Hz, {} channel(s), {}",
+            output.codec,
+            output.sample_rate,
+            output.channels,
+            timecode::format_timestamp(output.duration())
+        );
+        if !output.tags.is_empty() {
+            let names: Vec<_> = output.tags.iter().map(|(name, _)| *name).collect();
+            say!("Copied tags: {}", names.join(", "));
+        }
+        if output.skipped_packets > 0 {
+            warning!("⚠ {} damaged packet(s) could not be decoded and were left out", output.skipped_packets);
+        }
+        Ok(())
+    }
+}
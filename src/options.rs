@@ -0,0 +1,154 @@
+use crate::loudness::LoudnessTarget;
+use crate::preset::Preset;
+use crate::{Args, AudioFormat};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// What to extract and how, for library callers, built up from the input
+/// and output:
+///
+/// ```no_run
+/// # use audio_extractor::{AudioExtractor, AudioFormat, ExtractionOptions};
+/// let options = ExtractionOptions::new("talk.mp4", "talk.flac")
+///     .format(AudioFormat::Flac)
+///     .verify(true);
+/// AudioExtractor::new(options).extract()?;
+/// # Ok::<(), audio_extractor::ExtractorError>(())
+/// ```
+///
+/// `AudioExtractor::new` takes these, and the command line's `Args` convert
+/// into them, so options without a method here can still be set through
+/// `Args`. Anything left unset is filled in from the --preset, the --profile
+/// and config.toml's defaults, as on the command line.
+#[derive(Clone, Default)]
+pub struct ExtractionOptions {
+    pub(crate) args: Args,
+}
+
+impl ExtractionOptions {
+    pub fn new(input: impl Into<PathBuf>, output: impl Into<PathBuf>) -> Self {
+        Self {
+            args: Args { input: input.into(), output: output.into(), ..Default::default() },
+        }
+    }
+
+    pub fn input(&self) -> &Path {
+        &self.args.input
+    }
+
+    pub fn output(&self) -> &Path {
+        &self.args.output
+    }
+
+    pub fn format(mut self, format: AudioFormat) -> Self {
+        self.args.format = Some(format);
+        self
+    }
+
+    /// Bitrate in kbps, or `quality::AUTO` to choose it from the source
+    pub fn bitrate(mut self, kbps: u32) -> Self {
+        self.args.quality = Some(kbps);
+        self
+    }
+
+    /// Encode at a VBR quality level instead of a bitrate; `None` is the codec's default level
+    pub fn vbr(mut self, level: Option<f64>) -> Self {
+        self.args.vbr = Some(level);
+        self.args.quality = None;
+        self
+    }
+
+    pub fn verify(mut self, verify: bool) -> Self {
+        self.args.verify = verify;
+        self
+    }
+
+    pub fn sample_rate(mut self, hz: u32) -> Self {
+        self.args.sample_rate = Some(hz);
+        self
+    }
+
+    pub fn channels(mut self, channels: u32) -> Self {
+        self.args.channels = Some(channels);
+        self
+    }
+
+    /// Only extract from this position on, in seconds
+    pub fn start(mut self, seconds: f64) -> Self {
+        self.args.start = Some(seconds);
+        self
+    }
+
+    /// Stop extracting at this position in the source, in seconds
+    pub fn end(mut self, seconds: f64) -> Self {
+        self.args.end = Some(seconds);
+        self
+    }
+
+    /// Extract at most this many seconds of audio
+    pub fn duration(mut self, seconds: f64) -> Self {
+        self.args.duration = Some(seconds);
+        self
+    }
+
+    /// Audio stream to extract, counting from 0 among the audio streams
+    pub fn audio_track(mut self, index: usize) -> Self {
+        self.args.audio_track = Some(index);
+        self
+    }
+
+    /// Normalize loudness to EBU R128, aiming for `target_lufs` or -16 LUFS
+    pub fn normalize(mut self, target_lufs: Option<f64>) -> Self {
+        self.args.normalize = true;
+        self.args.target_lufs = target_lufs;
+        self
+    }
+
+    pub fn preset(mut self, preset: Preset) -> Self {
+        self.args.preset = Some(preset);
+        self
+    }
+
+    /// Use the settings of a `[profiles.<name>]` table in config.toml
+    pub fn profile(mut self, name: impl Into<String>) -> Self {
+        self.args.profile = Some(name.into());
+        self
+    }
+
+    /// Re-encode lossy source audio to a lossy format even though it loses quality again
+    pub fn allow_lossy_transcode(mut self, allow: bool) -> Self {
+        self.args.allow_lossy_transcode = allow;
+        self
+    }
+
+    /// Kill FFmpeg if it runs longer than this many seconds
+    pub fn timeout(mut self, seconds: u64) -> Self {
+        self.args.timeout = Some(seconds);
+        self
+    }
+}
+
+impl From<Args> for ExtractionOptions {
+    fn from(args: Args) -> Self {
+        Self { args }
+    }
+}
+
+/// The settings an extraction runs with once the command line, the
+/// --profile, the --preset and config.toml's defaults are merged
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ResolvedOptions {
+    pub format: AudioFormat,
+    /// Bitrate in kbps, or `quality::AUTO`
+    pub quality: u32,
+    /// VBR level, which replaces the bitrate
+    pub vbr: Option<f64>,
+    /// Loudness --normalize aims for
+    pub normalize: Option<LoudnessTarget>,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u32>,
+    pub output_template: Option<String>,
+    pub verify: bool,
+    /// The config.toml profile the settings came from
+    pub profile: Option<String>,
+}
@@ -0,0 +1,265 @@
+use crate::scan::SkippedInput;
+use crate::voice;
+use crate::AudioFormat;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::fs;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+/// Free space kept in reserve on top of the estimated output size, in percent
+pub const SPACE_MARGIN_PERCENT: u64 = 10;
+
+/// FLAC output size relative to 16-bit PCM, a conservative average for speech and music
+const FLAC_RATIO: f64 = 0.7;
+
+/// One input of a batch as it will be extracted, and anything that would stop it
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct PlannedJob {
+    pub input: PathBuf,
+    pub output: Option<PathBuf>,
+    pub format: Option<String>,
+    /// Seconds of audio to extract, if the input could be probed
+    pub duration: Option<f64>,
+    /// Estimated output size in bytes
+    pub estimated_size: Option<u64>,
+    /// Problems that would make this input fail
+    pub problems: Vec<String>,
+    /// Things worth knowing that do not stop the input
+    pub warnings: Vec<String>,
+}
+
+impl PlannedJob {
+    pub fn new(input: &Path) -> Self {
+        Self {
+            input: input.to_path_buf(),
+            output: None,
+            format: None,
+            duration: None,
+            estimated_size: None,
+            problems: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SkippedFile {
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+/// Everything a batch will do, checked before the first encode, written with
+/// `--preflight-report`
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Preflight {
+    pub created_at: String,
+    pub jobs: Vec<PlannedJob>,
+    /// Inputs the scan found but will not extract
+    pub skipped: Vec<SkippedFile>,
+    /// Sum of the jobs' estimated output sizes, in bytes
+    pub estimated_size: u64,
+    /// Free space where the outputs go, in bytes, if it could be read
+    pub free_space: Option<u64>,
+    /// Problems with the batch as a whole
+    pub problems: Vec<String>,
+}
+
+impl Preflight {
+    /// Check the batch as a whole once its jobs are planned
+    pub fn new(jobs: Vec<PlannedJob>, skipped: &[SkippedInput], output_dir: &Path) -> Self {
+        let estimated_size = jobs.iter().filter_map(|job| job.estimated_size).sum();
+        let free_space = free_space(output_dir);
+        let mut problems = Vec::new();
+        if let Some(free) = free_space {
+            let needed = estimated_size + estimated_size * SPACE_MARGIN_PERCENT / 100;
+            if needed > free {
+                problems.push(format!(
+                    "Outputs need about {} but only {} is free at {:?}",
+                    format_size(estimated_size),
+                    format_size(free),
+                    output_dir
+                ));
+            }
+        }
+
+        Self {
+            created_at: chrono::Utc::now().to_rfc3339(),
+            jobs,
+            skipped: skipped
+                .iter()
+                .map(|s| SkippedFile { path: s.path.clone(), reason: s.reason.to_string() })
+                .collect(),
+            estimated_size,
+            free_space,
+            problems,
+        }
+    }
+
+    /// Whether the batch can start: no problems with it or any of its jobs
+    pub fn go(&self) -> bool {
+        self.problems.is_empty() && self.jobs.iter().all(|job| job.problems.is_empty())
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json).with_context(|| format!("Failed to write preflight report {:?}", path))
+    }
+}
+
+impl fmt::Display for Preflight {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for job in &self.jobs {
+            for problem in &job.problems {
+                writeln!(f, "  ✗ {:?}: {}", job.input, problem)?;
+            }
+            for warning in &job.warnings {
+                writeln!(f, "  ⚠ {:?}: {}", job.input, warning)?;
+            }
+        }
+        for problem in &self.problems {
+            writeln!(f, "  ✗ {}", problem)?;
+        }
+
+        let failing = self.jobs.iter().filter(|job| !job.problems.is_empty()).count();
+        let warned = self.jobs.iter().filter(|job| !job.warnings.is_empty()).count();
+        writeln!(
+            f,
+            "Preflight: {} input(s), {} with problems, {} with warnings, {} skipped",
+            self.jobs.len(),
+            failing,
+            warned,
+            self.skipped.len()
+        )?;
+        match self.free_space {
+            Some(free) => writeln!(f, "Estimated output: {} ({} free)", format_size(self.estimated_size), format_size(free))?,
+            None => writeln!(f, "Estimated output: {}", format_size(self.estimated_size))?,
+        }
+        write!(f, "{}", if self.go() { "✅ GO" } else { "🛑 NO-GO" })
+    }
+}
+
+/// Rough output size for `seconds` of audio
+pub fn estimate_size(format: &AudioFormat, kbps: u32, seconds: f64) -> u64 {
+    // The WAV encoder writes 16-bit stereo at 44.1 kHz
+    let pcm_bytes_per_second = 44_100.0 * 2.0 * 2.0;
+    let bytes_per_second = match format {
+        AudioFormat::Mp3 | AudioFormat::Aac | AudioFormat::Opus | AudioFormat::Ogg => kbps as f64 * 1000.0 / 8.0,
+        AudioFormat::Wav | AudioFormat::Aiff => pcm_bytes_per_second,
+        AudioFormat::Flac | AudioFormat::WavPack => pcm_bytes_per_second * FLAC_RATIO,
+        AudioFormat::AmrNb | AudioFormat::AmrWb | AudioFormat::Speex => voice::bitrate(format, kbps).unwrap() as f64 / 8.0,
+    };
+    (bytes_per_second * seconds.max(0.0)) as u64
+}
+
+/// Bytes available to this user on the filesystem holding `path`, or its
+/// nearest existing parent
+#[cfg(unix)]
+pub fn free_space(path: &Path) -> Option<u64> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let existing = path
+        .ancestors()
+        .find(|dir| !dir.as_os_str().is_empty() && dir.exists())
+        .unwrap_or(Path::new("."));
+    let path = std::ffi::CString::new(existing.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+#[cfg(not(unix))]
+pub fn free_space(_path: &Path) -> Option<u64> {
+    None
+}
+
+/// Size in bytes as e.g. `1.5 GB`
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1000.0 && unit < UNITS.len() - 1 {
+        size /= 1000.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// Show the planned jobs and let the user deselect some before the batch
+/// starts. Returns the indices of the jobs to run, or `None` if the user
+/// cancelled.
+pub fn confirm(jobs: &[PlannedJob], input: &mut impl BufRead, output: &mut impl Write) -> Result<Option<Vec<usize>>> {
+    let mut selected: BTreeSet<usize> = (0..jobs.len()).collect();
+    list(jobs, &selected, output)?;
+    loop {
+        write!(
+            output,
+            "Extract {} of {} input(s)? [y]es, [n]o, [l]ist, numbers to skip (3,7-9) or +numbers to add back: ",
+            selected.len(),
+            jobs.len()
+        )?;
+        output.flush()?;
+
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            // End of input counts as no
+            return Ok(None);
+        }
+        match line.trim().to_lowercase().as_str() {
+            "y" | "yes" => return Ok(Some(selected.into_iter().collect())),
+            "n" | "no" | "q" | "quit" => return Ok(None),
+            "l" | "list" => list(jobs, &selected, output)?,
+            "" => {}
+            answer => {
+                let (add, numbers) = match answer.strip_prefix('+') {
+                    Some(numbers) => (true, numbers),
+                    None => (false, answer),
+                };
+                match parse_selection(numbers, jobs.len()) {
+                    Ok(indices) if add => selected.extend(indices),
+                    Ok(indices) => selected.retain(|index| !indices.contains(index)),
+                    Err(e) => writeln!(output, "{}", e)?,
+                }
+            }
+        }
+    }
+}
+
+fn list(jobs: &[PlannedJob], selected: &BTreeSet<usize>, output: &mut impl Write) -> Result<()> {
+    for (index, job) in jobs.iter().enumerate() {
+        let mark = if selected.contains(&index) { "x" } else { " " };
+        let target = job.output.as_deref().unwrap_or(Path::new("?"));
+        writeln!(output, "  [{}] {:>4}  {:?} → {:?}", mark, index + 1, job.input, target)?;
+    }
+    Ok(())
+}
+
+/// Zero-based indices from a list of one-based numbers and ranges such as
+/// `3,7-9`
+pub fn parse_selection(text: &str, count: usize) -> Result<Vec<usize>> {
+    let mut indices = Vec::new();
+    for part in text.split(',').map(str::trim).filter(|part| !part.is_empty()) {
+        let (first, last) = part.split_once('-').unwrap_or((part, part));
+        let number = |n: &str| -> Result<usize> {
+            let n: usize = n.trim().parse().with_context(|| format!("'{}' is not a job number or range", part))?;
+            if n == 0 || n > count {
+                anyhow::bail!("There is no job {}; jobs are numbered 1 to {}", n, count);
+            }
+            Ok(n - 1)
+        };
+        let (first, last) = (number(first)?, number(last)?);
+        if first > last {
+            anyhow::bail!("Range '{}' is backwards", part);
+        }
+        indices.extend(first..=last);
+    }
+    Ok(indices)
+}
@@ -0,0 +1,48 @@
+use crate::{Args, AudioFormat, BitDepth};
+use clap::ValueEnum;
+
+/// A named set of output settings for a common destination, from --preset
+#[derive(Clone, Copy, ValueEnum, Debug, PartialEq)]
+pub enum Preset {
+    /// 16 kHz mono 16-bit WAV, what Whisper and most speech recognizers take
+    Transcription,
+}
+
+/// The settings a preset pins; `None` leaves a setting alone
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PresetSettings {
+    pub format: Option<AudioFormat>,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u32>,
+    pub bit_depth: Option<BitDepth>,
+}
+
+impl Preset {
+    pub fn settings(&self) -> PresetSettings {
+        match self {
+            Preset::Transcription => PresetSettings {
+                format: Some(AudioFormat::Wav),
+                sample_rate: Some(16_000),
+                channels: Some(1),
+                bit_depth: Some(BitDepth::Sixteen),
+            },
+        }
+    }
+
+    /// Fill in the settings of `args` the command line left unset
+    pub fn apply(&self, args: &mut Args) {
+        let settings = self.settings();
+        if args.format.is_none() {
+            args.format = settings.format;
+        }
+        if args.sample_rate.is_none() {
+            args.sample_rate = settings.sample_rate;
+        }
+        if args.channels.is_none() {
+            args.channels = settings.channels;
+        }
+        if args.bit_depth.is_none() {
+            args.bit_depth = settings.bit_depth;
+        }
+    }
+}
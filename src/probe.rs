@@ -0,0 +1,237 @@
+use crate::limits::ResourceLimits;
+use crate::preflight::format_size;
+use crate::timecode::format_timestamp;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fmt;
+use std::process::Command;
+
+/// What ffprobe reports about a media file: its container and streams
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MediaInfo {
+    /// Short name of the container format, e.g. `matroska,webm`
+    pub container: String,
+    /// Descriptive name of the container format, e.g. `Matroska / WebM`
+    pub container_name: Option<String>,
+    /// Seconds
+    pub duration: Option<f64>,
+    /// Bytes
+    pub size: Option<u64>,
+    /// Overall bitrate in bits per second
+    pub bitrate: Option<u64>,
+    pub audio: Vec<AudioStream>,
+    pub video: Vec<VideoStream>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AudioStream {
+    /// Position among the audio streams, as used by `--audio-track`
+    pub index: usize,
+    /// Index among all streams of the container
+    pub stream_index: u32,
+    pub codec: String,
+    pub channels: Option<u32>,
+    /// e.g. `stereo` or `5.1(side)`
+    pub channel_layout: Option<String>,
+    /// Hz
+    pub sample_rate: Option<u32>,
+    /// Bits per second
+    pub bitrate: Option<u64>,
+    /// ISO 639-2 language tag, e.g. `eng`
+    pub language: Option<String>,
+    pub title: Option<String>,
+    pub default: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct VideoStream {
+    /// Position among the video streams
+    pub index: usize,
+    pub stream_index: u32,
+    pub codec: String,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    /// Average frames per second
+    pub frame_rate: Option<f64>,
+    /// Bits per second
+    pub bitrate: Option<u64>,
+    pub language: Option<String>,
+}
+
+/// ffprobe's `-show_format -show_streams` JSON, which gives most numbers as strings
+#[derive(Deserialize)]
+struct FfprobeOutput {
+    #[serde(default)]
+    format: FfprobeFormat,
+    #[serde(default)]
+    streams: Vec<FfprobeStream>,
+}
+
+#[derive(Deserialize, Default)]
+struct FfprobeFormat {
+    format_name: Option<String>,
+    format_long_name: Option<String>,
+    duration: Option<String>,
+    size: Option<String>,
+    bit_rate: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct FfprobeStream {
+    index: u32,
+    codec_type: Option<String>,
+    codec_name: Option<String>,
+    channels: Option<u32>,
+    channel_layout: Option<String>,
+    sample_rate: Option<String>,
+    bit_rate: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+    avg_frame_rate: Option<String>,
+    #[serde(default)]
+    tags: HashMap<String, String>,
+    #[serde(default)]
+    disposition: HashMap<String, i64>,
+}
+
+impl FfprobeStream {
+    /// A tag, leaving out empty ones and the `und` language
+    fn tag(&self, name: &str) -> Option<String> {
+        self.tags.get(name)
+            .map(|value| value.trim())
+            .filter(|value| !value.is_empty() && *value != "und")
+            .map(str::to_string)
+    }
+
+    fn codec(&self) -> String {
+        self.codec_name.clone().unwrap_or_else(|| "unknown".to_string())
+    }
+}
+
+fn number<T: std::str::FromStr>(value: &Option<String>) -> Option<T> {
+    value.as_deref().and_then(|value| value.trim().parse().ok())
+}
+
+/// A rate ffprobe writes as a fraction, e.g. `24000/1001`; `0/0` when unknown
+fn fraction(value: &Option<String>) -> Option<f64> {
+    let (numerator, denominator) = value.as_deref()?.split_once('/')?;
+    let (numerator, denominator): (f64, f64) = (numerator.parse().ok()?, denominator.parse().ok()?);
+    (numerator > 0.0 && denominator > 0.0).then_some(numerator / denominator)
+}
+
+impl MediaInfo {
+    /// Probe the source with ffprobe. `input_args` are the options and `-i`
+    /// selecting the source.
+    pub fn probe(input_args: &[OsString], limits: &ResourceLimits) -> Result<Self> {
+        let mut cmd = Command::new("ffprobe");
+        cmd.arg("-v").arg("error")
+            .arg("-print_format").arg("json")
+            .arg("-show_format")
+            .arg("-show_streams")
+            .args(input_args);
+        let output = limits.output(&mut cmd).context("Failed to run ffprobe")?;
+        if !output.status.success() {
+            anyhow::bail!("ffprobe failed to read the file: {}", String::from_utf8_lossy(&output.stderr).trim());
+        }
+        Self::from_ffprobe_json(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    /// The container and streams in ffprobe's `-show_format -show_streams` JSON
+    pub fn from_ffprobe_json(json: &str) -> Result<Self> {
+        let probe: FfprobeOutput = serde_json::from_str(json).context("Invalid ffprobe output")?;
+        let of_type = |kind: &'static str| probe.streams.iter().filter(move |stream| stream.codec_type.as_deref() == Some(kind));
+        let audio = of_type("audio").enumerate().map(|(index, stream)| AudioStream {
+            index,
+            stream_index: stream.index,
+            codec: stream.codec(),
+            channels: stream.channels,
+            channel_layout: stream.channel_layout.clone(),
+            sample_rate: number(&stream.sample_rate),
+            bitrate: number(&stream.bit_rate),
+            language: stream.tag("language"),
+            title: stream.tag("title"),
+            default: stream.disposition.get("default") == Some(&1),
+        }).collect();
+        // Cover art is stored as a one-frame video stream
+        let video = of_type("video")
+            .filter(|stream| stream.disposition.get("attached_pic") != Some(&1))
+            .enumerate()
+            .map(|(index, stream)| VideoStream {
+                index,
+                stream_index: stream.index,
+                codec: stream.codec(),
+                width: stream.width,
+                height: stream.height,
+                frame_rate: fraction(&stream.avg_frame_rate),
+                bitrate: number(&stream.bit_rate),
+                language: stream.tag("language"),
+            })
+            .collect();
+        Ok(Self {
+            container: probe.format.format_name.clone().unwrap_or_else(|| "unknown".to_string()),
+            container_name: probe.format.format_long_name.clone(),
+            duration: number(&probe.format.duration),
+            size: number(&probe.format.size),
+            bitrate: number(&probe.format.bit_rate),
+            audio,
+            video,
+        })
+    }
+}
+
+impl fmt::Display for MediaInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Container: {}", self.container)?;
+        if let Some(name) = &self.container_name {
+            write!(f, " ({})", name)?;
+        }
+        writeln!(f)?;
+        if let Some(duration) = self.duration {
+            writeln!(f, "Duration: {}", format_timestamp(duration))?;
+        }
+        match (self.size, self.bitrate) {
+            (Some(size), Some(bitrate)) => writeln!(f, "Size: {}, {} kbps", format_size(size), bitrate / 1000)?,
+            (Some(size), None) => writeln!(f, "Size: {}", format_size(size))?,
+            _ => {}
+        }
+        for stream in &self.video {
+            write!(f, "Video {}: {}", stream.index, stream.codec)?;
+            if let (Some(width), Some(height)) = (stream.width, stream.height) {
+                write!(f, ", {}x{}", width, height)?;
+            }
+            if let Some(rate) = stream.frame_rate {
+                write!(f, ", {:.3} fps", rate)?;
+            }
+            if let Some(bitrate) = stream.bitrate {
+                write!(f, ", {} kbps", bitrate / 1000)?;
+            }
+            writeln!(f)?;
+        }
+        for stream in &self.audio {
+            write!(f, "Audio {}: {}", stream.index, stream.codec)?;
+            if let Some(channels) = stream.channels {
+                write!(f, ", {} ch", channels)?;
+            }
+            if let Some(rate) = stream.sample_rate {
+                write!(f, ", {} Hz", rate)?;
+            }
+            if let Some(bitrate) = stream.bitrate {
+                write!(f, ", {} kbps", bitrate / 1000)?;
+            }
+            write!(f, ", {}", stream.language.as_deref().unwrap_or("unknown language"))?;
+            if let Some(title) = &stream.title {
+                write!(f, ", \"{}\"", title)?;
+            }
+            if stream.default {
+                write!(f, " (default)")?;
+            }
+            writeln!(f)?;
+        }
+        if self.audio.is_empty() {
+            writeln!(f, "No audio streams")?;
+        }
+        Ok(())
+    }
+}
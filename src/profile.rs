@@ -0,0 +1,56 @@
+use crate::{deserialize_format, Args, AudioFormat};
+use serde::Deserialize;
+
+/// Named settings from a `[profiles.<name>]` table of config.toml, chosen
+/// with --profile
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Profile {
+    #[serde(default, deserialize_with = "deserialize_format")]
+    pub format: Option<AudioFormat>,
+    /// Bitrate in kbps
+    pub quality: Option<u32>,
+    pub vbr: Option<f64>,
+    pub normalize: Option<bool>,
+    pub target_lufs: Option<f64>,
+    pub true_peak: Option<f64>,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u32>,
+    pub output_template: Option<String>,
+    pub verify: Option<bool>,
+}
+
+impl Profile {
+    /// Fill in the settings of `args` the command line left unset
+    pub fn apply(&self, args: &mut Args) {
+        if args.format.is_none() {
+            args.format = self.format.clone();
+        }
+        // A VBR level or bitrate given on the command line replaces the profile's
+        let rate_set = args.quality.is_some() || args.vbr.is_some() || args.target_size.is_some();
+        if !rate_set {
+            args.quality = self.quality;
+            args.vbr = self.vbr.map(Some);
+        }
+        if !args.normalize {
+            args.normalize = self.normalize.unwrap_or(false);
+        }
+        if args.target_lufs.is_none() {
+            args.target_lufs = self.target_lufs;
+        }
+        if args.true_peak.is_none() {
+            args.true_peak = self.true_peak;
+        }
+        if args.sample_rate.is_none() {
+            args.sample_rate = self.sample_rate;
+        }
+        if args.channels.is_none() {
+            args.channels = self.channels;
+        }
+        if args.output_template.is_none() {
+            args.output_template = self.output_template.clone();
+        }
+        if !args.verify {
+            args.verify = self.verify.unwrap_or(false);
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use crate::timecode;
+use clap::ValueEnum;
+use std::fmt;
+
+/// What `AudioExtractor::extract_with_progress` reports as it goes
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProgressEvent {
+    Started,
+    /// The source was probed; `duration` is the audio that will be
+    /// extracted, in seconds, after any --start/--end/--duration
+    Probed { duration: f64 },
+    /// FFmpeg has written `seconds_done` of the output; `percent` is known
+    /// when the duration is
+    Encoding { seconds_done: f64, percent: Option<f64> },
+    /// --normalize measured the audio's integrated loudness; both are in LUFS
+    Loudness { measured: f64, target: f64 },
+    /// A step around the encode completed, such as validation or verification
+    Step(&'static str),
+    /// FFmpeg's output has not advanced for `seconds`, the --stall-timeout
+    Stalled { seconds: u64 },
+    Finished,
+}
+
+impl fmt::Display for ProgressEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProgressEvent::Started => write!(f, "Starting audio extraction..."),
+            ProgressEvent::Probed { duration } => write!(f, "Duration: {}", timecode::format_timestamp(*duration)),
+            ProgressEvent::Encoding { seconds_done, percent: Some(percent) } => {
+                write!(f, "Encoding: {} ({:.0}%)", timecode::format_timestamp(*seconds_done), percent)
+            }
+            ProgressEvent::Encoding { seconds_done, percent: None } => {
+                write!(f, "Encoding: {}", timecode::format_timestamp(*seconds_done))
+            }
+            ProgressEvent::Loudness { measured, target } => {
+                write!(f, "Loudness: {:.1} LUFS, normalizing to {:.1} LUFS", measured, target)
+            }
+            ProgressEvent::Step(step) => write!(f, "{}", step),
+            ProgressEvent::Stalled { seconds } => write!(f, "No progress from FFmpeg for {} seconds", seconds),
+            ProgressEvent::Finished => write!(f, "Finished"),
+        }
+    }
+}
+
+/// What --on-stall does once FFmpeg has made no progress for --stall-timeout
+#[derive(Clone, Copy, ValueEnum, Debug, Default, PartialEq)]
+pub enum StallAction {
+    /// Report the stall and let FFmpeg carry on
+    #[default]
+    Warn,
+    /// Kill FFmpeg and fail the job
+    Kill,
+    /// Kill FFmpeg and run it again, once
+    Retry,
+}
+
+/// Turns the `key=value` lines of FFmpeg's `-progress` output into
+/// `Encoding` events, one per block FFmpeg ends with a `progress=` line
+#[derive(Debug, Clone, Default)]
+pub struct ProgressParser {
+    /// Seconds of audio expected in the output
+    duration: Option<f64>,
+    seconds_done: Option<f64>,
+}
+
+impl ProgressParser {
+    pub fn new(duration: Option<f64>) -> Self {
+        Self { duration: duration.filter(|&d| d > 0.0), seconds_done: None }
+    }
+
+    pub fn line(&mut self, line: &str) -> Option<ProgressEvent> {
+        let (key, value) = line.trim().split_once('=')?;
+        match key {
+            // out_time_ms is in microseconds too; older FFmpeg only writes that one
+            "out_time_us" | "out_time_ms" => {
+                if let Ok(us) = value.parse::<i64>() {
+                    self.seconds_done = Some(us.max(0) as f64 / 1e6);
+                }
+                None
+            }
+            "progress" => {
+                let seconds_done = match (value, self.duration) {
+                    ("end", Some(duration)) => duration,
+                    _ => self.seconds_done?,
+                };
+                let percent = self.duration.map(|duration| (seconds_done / duration * 100.0).clamp(0.0, 100.0));
+                Some(ProgressEvent::Encoding { seconds_done, percent })
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Frame formats NLEs export image sequences in
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "tif", "tiff", "dpx", "exr", "tga", "bmp"];
+
+/// Audio formats found next to exported frame sequences
+const AUDIO_EXTENSIONS: &[&str] = &["wav", "bwf", "aif", "aiff", "flac", "mp3", "m4a", "aac", "ogg", "opus"];
+
+/// Fewest numbered frames that make a folder an image sequence
+const MIN_SEQUENCE_FRAMES: usize = 2;
+
+/// The audio track of an image-sequence project folder.
+///
+/// A folder counts as a project export when it directly holds a numbered
+/// frame sequence (`shot_0001.png`, `shot_0002.png`, ...) and at least one
+/// audio file. If several audio files are present, the largest is taken as
+/// the main mix. Returns `None` for any other directory.
+pub fn audio_track(dir: &Path) -> Result<Option<PathBuf>> {
+    let mut frames = 0;
+    let mut audio: Option<(u64, PathBuf)> = None;
+
+    for entry in fs::read_dir(dir).with_context(|| format!("Failed to read directory {:?}", dir))? {
+        let entry = entry?;
+        if !entry.file_type()?.is_file() {
+            continue;
+        }
+        let path = entry.path();
+        let Some(extension) = path.extension().and_then(|e| e.to_str()).map(str::to_lowercase) else {
+            continue;
+        };
+
+        if IMAGE_EXTENSIONS.contains(&extension.as_str()) && is_numbered(&path) {
+            frames += 1;
+        } else if AUDIO_EXTENSIONS.contains(&extension.as_str()) {
+            let size = entry.metadata()?.len();
+            if audio.as_ref().is_none_or(|(largest, _)| size > *largest) {
+                audio = Some((size, path));
+            }
+        }
+    }
+
+    if frames < MIN_SEQUENCE_FRAMES {
+        return Ok(None);
+    }
+    Ok(audio.map(|(_, path)| path))
+}
+
+/// Whether the file stem ends in a frame number
+fn is_numbered(path: &Path) -> bool {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .and_then(|stem| stem.chars().last())
+        .is_some_and(|c| c.is_ascii_digit())
+}
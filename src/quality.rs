@@ -0,0 +1,116 @@
+use crate::limits::ResourceLimits;
+use crate::AudioFormat;
+use anyhow::{Context, Result};
+use std::ffi::OsString;
+use std::process::Command;
+
+/// `--quality auto`: pick the bitrate from the source audio at extraction time
+pub const AUTO: u32 = 0;
+
+/// Bitrates the lossy encoders are given, in kbps
+const STANDARD_BITRATES: [u32; 12] = [32, 48, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320];
+
+/// Parse `--quality`: a bitrate in kbps, or `auto`
+pub fn parse_quality(value: &str) -> std::result::Result<u32, String> {
+    if value.eq_ignore_ascii_case("auto") {
+        return Ok(AUTO);
+    }
+    match value.parse::<u32>() {
+        Ok(0) => Err("quality must be a bitrate above 0 kbps, or `auto`".to_string()),
+        Ok(kbps) => Ok(kbps),
+        Err(_) => Err(format!("'{}' is not a bitrate in kbps or `auto`", value)),
+    }
+}
+
+/// An audio stream of the source, as reported by ffprobe
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceAudio {
+    pub codec: String,
+    /// Bitrate in kbps, if the container reports one
+    pub bit_rate: Option<u32>,
+    pub channels: Option<u32>,
+    /// Sample rate in Hz
+    pub sample_rate: Option<u32>,
+}
+
+impl SourceAudio {
+    /// Read the `track`th audio stream with ffprobe. `input_args` are the
+    /// options and `-i` selecting the source.
+    pub fn probe(input_args: &[OsString], track: usize, limits: &ResourceLimits) -> Result<Option<Self>> {
+        let mut cmd = Command::new("ffprobe");
+        cmd.arg("-v").arg("error")
+            .arg("-print_format").arg("json")
+            .arg("-select_streams").arg(format!("a:{}", track))
+            .arg("-show_streams")
+            .args(input_args);
+        let output = limits.output(&mut cmd).context("Failed to run ffprobe")?;
+        if !output.status.success() {
+            anyhow::bail!("ffprobe failed to read the audio stream: {}", String::from_utf8_lossy(&output.stderr).trim());
+        }
+        Self::from_ffprobe_json(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    /// Parse ffprobe's `-show_streams` JSON; `None` if there is no audio stream
+    pub fn from_ffprobe_json(json: &str) -> Result<Option<Self>> {
+        let probe: serde_json::Value = serde_json::from_str(json).context("Invalid ffprobe output")?;
+        let Some(stream) = probe["streams"].as_array().and_then(|streams| streams.first()) else {
+            return Ok(None);
+        };
+        Ok(Some(Self {
+            codec: stream["codec_name"].as_str().unwrap_or("unknown").to_string(),
+            bit_rate: stream["bit_rate"].as_str()
+                .and_then(|rate| rate.parse::<u64>().ok())
+                .map(|bps| (bps / 1000) as u32),
+            channels: stream["channels"].as_u64().map(|c| c as u32),
+            sample_rate: stream["sample_rate"].as_str().and_then(|rate| rate.parse().ok()),
+        }))
+    }
+
+    pub fn is_lossless(&self) -> bool {
+        is_lossless(&self.codec)
+    }
+}
+
+/// Whether an ffprobe codec name is a lossless codec
+pub fn is_lossless(codec: &str) -> bool {
+    codec.starts_with("pcm_") || matches!(codec, "flac" | "alac" | "truehd" | "mlp" | "wavpack" | "ape" | "tta")
+}
+
+/// Rough quality per kbps of a codec relative to MP3, so a source's bitrate
+/// can be translated into the bitrate another codec needs to keep up with it
+fn efficiency(codec: &str) -> f64 {
+    match codec {
+        "aac" => 1.4,
+        "opus" => 1.7,
+        "vorbis" => 1.5,
+        "ac3" | "mp2" => 0.8,
+        _ => 1.0,
+    }
+}
+
+/// Bitrate for `format` that keeps what `source` has without spending bits it
+/// does not have: the source's bitrate converted between the codecs, rounded
+/// down to a standard bitrate and never above the source's own bitrate.
+/// Lossless or unknown sources get a transparent default for the format.
+pub fn auto_bitrate(source: Option<&SourceAudio>, format: &AudioFormat) -> u32 {
+    let (codec, transparent, ceiling) = match format {
+        AudioFormat::Mp3 => ("mp3", 256, 320),
+        AudioFormat::Aac => ("aac", 192, 256),
+        AudioFormat::Opus => ("opus", 128, 256),
+        AudioFormat::Ogg => ("vorbis", 160, 320),
+        // Lossless outputs ignore the bitrate
+        AudioFormat::Wav | AudioFormat::Flac | AudioFormat::Aiff | AudioFormat::WavPack => return 128,
+        // Voice codecs cap the bitrate at their best mode, which speech always deserves
+        AudioFormat::AmrNb | AudioFormat::AmrWb | AudioFormat::Speex => return 128,
+    };
+    // Mono needs about half the bits of stereo for the same quality
+    let mono = source.and_then(|s| s.channels) == Some(1);
+    let transparent = if mono { transparent / 2 } else { transparent };
+
+    let Some((source, kbps)) = source.filter(|s| !s.is_lossless()).and_then(|s| Some((s, s.bit_rate?))) else {
+        return transparent;
+    };
+    let equivalent = (kbps as f64 * efficiency(&source.codec) / efficiency(codec)) as u32;
+    let target = equivalent.min(kbps).min(ceiling);
+    STANDARD_BITRATES.iter().rev().copied().find(|&rate| rate <= target).unwrap_or(STANDARD_BITRATES[0])
+}
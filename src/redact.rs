@@ -0,0 +1,64 @@
+use crate::timecode::{self, TimeRange};
+use anyhow::{Context, Result};
+use std::path::Path;
+
+/// Load redaction ranges from a JSON list file
+pub fn load_ranges(path: &Path) -> Result<Vec<TimeRange>> {
+    let json = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read redaction list {:?}", path))?;
+    timecode::parse_ranges_json(&json)
+        .with_context(|| format!("Failed to parse redaction list {:?}", path))
+}
+
+/// Level of the redaction tone relative to full scale
+const TONE_VOLUME: f64 = 0.25;
+
+/// Parse a tone frequency such as `1kHz`, `440Hz` or `1000`
+pub fn parse_frequency(value: &str) -> Result<f64> {
+    let lower = value.trim().to_lowercase();
+    let (number, multiplier) = if let Some(khz) = lower.strip_suffix("khz") {
+        (khz, 1000.0)
+    } else if let Some(hz) = lower.strip_suffix("hz") {
+        (hz, 1.0)
+    } else {
+        (lower.as_str(), 1.0)
+    };
+
+    let frequency = number.trim().parse::<f64>()
+        .with_context(|| format!("Invalid frequency '{}'", value))?
+        * multiplier;
+    if !(20.0..=20_000.0).contains(&frequency) {
+        anyhow::bail!("Frequency '{}' is outside the audible range (20Hz-20kHz)", value);
+    }
+    Ok(frequency)
+}
+
+/// `enable` expression that is true inside any of the ranges
+fn enable_expression(ranges: &[TimeRange]) -> String {
+    ranges
+        .iter()
+        .map(|range| format!("between(t,{:.3},{:.3})", range.start, range.end))
+        .collect::<Vec<_>>()
+        .join("+")
+}
+
+/// Volume filter that silences every given range, or `None` when there is nothing to mute
+pub fn mute_filter(ranges: &[TimeRange]) -> Option<String> {
+    if ranges.is_empty() {
+        return None;
+    }
+    Some(format!("volume=volume=0:enable='{}'", enable_expression(ranges)))
+}
+
+/// Sine source that is audible only inside the ranges, for mixing over muted audio
+pub fn tone_overlay(ranges: &[TimeRange], frequency: f64) -> Option<String> {
+    if ranges.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "sine=frequency={}:sample_rate=48000,volume=volume={},volume=volume=0:enable='not({})'",
+        frequency,
+        TONE_VOLUME,
+        enable_expression(ranges)
+    ))
+}
@@ -0,0 +1,104 @@
+use crate::isolation;
+use crate::limits::ResourceLimits;
+use crate::AudioExtractor;
+use anyhow::{Context, Result};
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// What `repair` did to one file
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepairOutcome {
+    pub path: PathBuf,
+    /// Duration in seconds before and after, as far as the headers tell
+    pub duration_before: Option<f64>,
+    pub duration_after: Option<f64>,
+    /// Where the original was kept, with `--backup`
+    pub backup: Option<PathBuf>,
+}
+
+impl std::fmt::Display for RepairOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let duration = |d: Option<f64>| d.map_or("unknown".to_string(), |d| format!("{:.2}s", d));
+        write!(f, "{}: duration {} → {}", self.path.display(), duration(self.duration_before), duration(self.duration_after))?;
+        if let Some(backup) = &self.backup {
+            write!(f, ", original kept as {}", backup.display())?;
+        }
+        Ok(())
+    }
+}
+
+/// Scratch file the remux is written to, next to the original so the final
+/// rename stays on one filesystem. It keeps the extension so FFmpeg picks
+/// the same muxer.
+pub fn scratch_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    match path.extension() {
+        Some(extension) => path.with_file_name(format!(".{}.repair.{}", stem, extension.to_string_lossy())),
+        None => path.with_file_name(format!(".{}.repair", stem)),
+    }
+}
+
+/// FFmpeg arguments remuxing `path` into `scratch` without re-encoding.
+///
+/// Remuxing rewrites what older or interrupted extractions got wrong: WAV
+/// and FLAC headers get the real length, MP3 gets a fresh Xing/LAME header
+/// for seeking and duration, and MP4/M4A gets its index moved to the front.
+pub fn remux_args(path: &Path, scratch: &Path) -> Vec<OsString> {
+    let mut args: Vec<OsString> = ["-v", "error", "-y", "-err_detect", "ignore_err", "-i"]
+        .iter().map(OsString::from).collect();
+    args.push(path.as_os_str().to_os_string());
+    args.extend(["-map", "0:a", "-c", "copy", "-map_metadata", "0"].iter().map(OsString::from));
+
+    let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+    match extension.as_str() {
+        "mp3" => args.extend(["-write_xing", "1", "-id3v2_version", "3"].iter().map(OsString::from)),
+        "m4a" | "mp4" | "m4b" => args.extend(["-movflags", "+faststart"].iter().map(OsString::from)),
+        _ => {}
+    }
+    args.push(scratch.as_os_str().to_os_string());
+    args
+}
+
+/// Remux an audio file in place. The result is written to a scratch file and
+/// verified before it replaces the original, so a failed repair leaves the
+/// original untouched. With `backup`, the original is kept as `<file>.bak`.
+pub fn repair(path: &Path, backup: bool, limits: &ResourceLimits) -> Result<RepairOutcome> {
+    if !path.is_file() {
+        anyhow::bail!("Not a file: {:?}", path);
+    }
+    let duration_before = AudioExtractor::verify_standalone(&path.to_path_buf()).ok().and_then(|info| info.duration);
+
+    let scratch = scratch_path(path);
+    let output = limits.output(isolation::ffmpeg().args(remux_args(path, &scratch))).context("Failed to run ffmpeg")?;
+    let result = if output.status.success() {
+        AudioExtractor::verify_standalone(&scratch).context("The remuxed file does not verify")
+    } else {
+        Err(anyhow::anyhow!("FFmpeg could not remux {:?}: {}", path, String::from_utf8_lossy(&output.stderr).trim()))
+    };
+    let info = match result {
+        Ok(info) => info,
+        Err(e) => {
+            let _ = fs::remove_file(&scratch);
+            return Err(e);
+        }
+    };
+
+    let backup = if backup {
+        let mut name = path.as_os_str().to_os_string();
+        name.push(".bak");
+        let backup = PathBuf::from(name);
+        fs::copy(path, &backup).with_context(|| format!("Failed to back up {:?}", path))?;
+        Some(backup)
+    } else {
+        None
+    };
+    fs::rename(&scratch, path).with_context(|| format!("Failed to replace {:?}", path))?;
+
+    Ok(RepairOutcome {
+        path: path.to_path_buf(),
+        duration_before,
+        duration_after: info.duration,
+        backup,
+    })
+}
@@ -0,0 +1,202 @@
+use crate::analysis::AnalysisReport;
+use crate::isolation;
+use crate::limits::ResourceLimits;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Default relative output size change reported as drift, in percent
+pub const DEFAULT_SIZE_TOLERANCE: f64 = 5.0;
+
+/// Default integrated loudness change reported as drift, in LU
+pub const DEFAULT_LOUDNESS_TOLERANCE: f64 = 1.0;
+
+/// Per-input results of a batch, written with `--report` so runs can be compared
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BatchReport {
+    pub created_at: String,
+    pub tool_version: String,
+    pub ffmpeg_version: Option<String>,
+    pub entries: Vec<ReportEntry>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ReportEntry {
+    pub input: PathBuf,
+    pub output: Option<PathBuf>,
+    pub success: bool,
+    pub error: Option<String>,
+    /// Output size in bytes
+    pub size: Option<u64>,
+    /// Integrated loudness of the output in LUFS
+    pub loudness: Option<f64>,
+    /// The output's analysis sidecar, if one was written
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub analysis: Option<AnalysisReport>,
+    /// The output path the template produced, if it was too long and was shortened
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shortened_from: Option<PathBuf>,
+}
+
+impl ReportEntry {
+    /// Record the outcome for `input`, measuring the output if there is one
+    pub fn new(input: &Path, result: &Result<PathBuf>, limits: Option<&ResourceLimits>) -> Self {
+        match result {
+            Ok(output) => Self {
+                input: input.to_path_buf(),
+                output: Some(output.clone()),
+                success: true,
+                error: None,
+                size: fs::metadata(output).ok().map(|m| m.len()),
+                loudness: limits.and_then(|limits| measure_loudness(output, limits).ok()),
+                analysis: AnalysisReport::load(output).ok(),
+                shortened_from: None,
+            },
+            Err(e) => Self {
+                input: input.to_path_buf(),
+                output: None,
+                success: false,
+                error: Some(format!("{:#}", e)),
+                size: None,
+                loudness: None,
+                analysis: None,
+                shortened_from: None,
+            },
+        }
+    }
+}
+
+impl BatchReport {
+    pub fn new(entries: Vec<ReportEntry>) -> Self {
+        Self {
+            created_at: chrono::Utc::now().to_rfc3339(),
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            ffmpeg_version: crate::forensic::ffmpeg_version(),
+            entries,
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path).with_context(|| format!("Failed to read report {:?}", path))?;
+        serde_json::from_str(&content).with_context(|| format!("Invalid report {:?}", path))
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?).with_context(|| format!("Failed to write report {:?}", path))
+    }
+}
+
+/// Integrated loudness (EBU R128) of an audio file, in LUFS
+pub fn measure_loudness(path: &Path, limits: &ResourceLimits) -> Result<f64> {
+    let mut cmd = isolation::ffmpeg();
+    cmd.arg("-nostats").arg("-hide_banner")
+        .arg("-i").arg(path)
+        .arg("-af").arg("ebur128=framelog=quiet")
+        .arg("-f").arg("null").arg("-");
+    let output = limits.output(&mut cmd).context("Failed to run ffmpeg")?;
+    parse_integrated_loudness(&String::from_utf8_lossy(&output.stderr))
+        .context("ffmpeg did not report a loudness")
+}
+
+/// The `I: -23.0 LUFS` line of the ebur128 summary
+pub fn parse_integrated_loudness(stderr: &str) -> Option<f64> {
+    stderr
+        .lines()
+        .rev()
+        .find_map(|line| line.trim().strip_prefix("I:")?.trim().strip_suffix("LUFS")?.trim().parse().ok())
+}
+
+/// Differences between two reports, by input path
+#[derive(Debug, Default, PartialEq)]
+pub struct ReportDiff {
+    /// Succeeded before, failing now, with the new error
+    pub regressions: Vec<(PathBuf, String)>,
+    /// Failed before, succeeding now
+    pub fixed: Vec<PathBuf>,
+    /// Output size before and after, beyond the tolerance
+    pub size_drift: Vec<(PathBuf, u64, u64)>,
+    /// Loudness before and after, beyond the tolerance
+    pub loudness_drift: Vec<(PathBuf, f64, f64)>,
+    pub added: Vec<PathBuf>,
+    pub removed: Vec<PathBuf>,
+}
+
+impl ReportDiff {
+    /// Compare `new` against `old`. Drift is only reported for inputs that
+    /// succeeded in both runs.
+    pub fn between(old: &BatchReport, new: &BatchReport, size_tolerance: f64, loudness_tolerance: f64) -> Self {
+        let old_entries: BTreeMap<_, _> = old.entries.iter().map(|e| (&e.input, e)).collect();
+        let new_entries: BTreeMap<_, _> = new.entries.iter().map(|e| (&e.input, e)).collect();
+        let mut diff = Self::default();
+
+        for (input, new) in &new_entries {
+            let Some(old) = old_entries.get(input) else {
+                diff.added.push(input.to_path_buf());
+                continue;
+            };
+            match (old.success, new.success) {
+                (true, false) => diff.regressions.push((input.to_path_buf(), new.error.clone().unwrap_or_default())),
+                (false, true) => diff.fixed.push(input.to_path_buf()),
+                (true, true) => {
+                    if let (Some(before), Some(after)) = (old.size, new.size) {
+                        let change = (after as f64 - before as f64).abs() / (before.max(1) as f64) * 100.0;
+                        if change > size_tolerance {
+                            diff.size_drift.push((input.to_path_buf(), before, after));
+                        }
+                    }
+                    if let (Some(before), Some(after)) = (old.loudness, new.loudness) {
+                        if (after - before).abs() > loudness_tolerance {
+                            diff.loudness_drift.push((input.to_path_buf(), before, after));
+                        }
+                    }
+                }
+                (false, false) => {}
+            }
+        }
+        diff.removed = old_entries.keys().filter(|input| !new_entries.contains_key(*input)).map(|p| p.to_path_buf()).collect();
+        diff
+    }
+
+    /// Whether anything got worse: new failures or drift beyond the tolerances
+    pub fn has_regressions(&self) -> bool {
+        !self.regressions.is_empty() || !self.size_drift.is_empty() || !self.loudness_drift.is_empty()
+    }
+}
+
+impl fmt::Display for ReportDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (input, error) in &self.regressions {
+            writeln!(f, "REGRESSION {}: {}", input.display(), error)?;
+        }
+        for (input, before, after) in &self.size_drift {
+            let change = (*after as f64 - *before as f64) / (*before).max(1) as f64 * 100.0;
+            writeln!(f, "SIZE       {}: {} → {} bytes ({:+.1}%)", input.display(), before, after, change)?;
+        }
+        for (input, before, after) in &self.loudness_drift {
+            writeln!(f, "LOUDNESS   {}: {:.1} → {:.1} LUFS ({:+.1} LU)", input.display(), before, after, after - before)?;
+        }
+        for input in &self.fixed {
+            writeln!(f, "FIXED      {}", input.display())?;
+        }
+        for input in &self.added {
+            writeln!(f, "ADDED      {}", input.display())?;
+        }
+        for input in &self.removed {
+            writeln!(f, "REMOVED    {}", input.display())?;
+        }
+        write!(
+            f,
+            "{} regression(s), {} size drift(s), {} loudness drift(s), {} fixed",
+            self.regressions.len(),
+            self.size_drift.len(),
+            self.loudness_drift.len(),
+            self.fixed.len()
+        )
+    }
+}
@@ -0,0 +1,38 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether messages for people are held back, as they are under --json
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Hold back or resume the messages `say!` prints. --json sets this so that
+/// standard output carries nothing but JSON lines.
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// `println!` for messages meant for people, which are left out while the
+/// reporter is quiet
+#[macro_export]
+macro_rules! say {
+    ($($arg:tt)*) => {
+        if !$crate::reporter::is_quiet() {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// `say!` for warnings, which go to standard error instead of being left out
+/// while the reporter is quiet
+#[macro_export]
+macro_rules! warning {
+    ($($arg:tt)*) => {
+        if $crate::reporter::is_quiet() {
+            eprintln!($($arg)*);
+        } else {
+            println!($($arg)*);
+        }
+    };
+}
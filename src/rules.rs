@@ -0,0 +1,70 @@
+use crate::{deserialize_format, Args, AudioFormat};
+use serde::Deserialize;
+use std::path::Path;
+
+/// Settings applied to batch inputs whose path matches `pattern`, from a
+/// `[[rules]]` table of `config.toml`
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Rule {
+    /// Glob matched against the input's path below the scanned directory,
+    /// e.g. `lectures/**` or `**/*.mkv`
+    pub pattern: String,
+    #[serde(default, deserialize_with = "deserialize_format")]
+    pub format: Option<AudioFormat>,
+    pub quality: Option<u32>,
+    pub verify: Option<bool>,
+}
+
+impl Rule {
+    pub fn matches(&self, path: &Path) -> bool {
+        glob_match(&self.pattern, &path.to_string_lossy().replace('\\', "/"))
+    }
+
+    /// Override the batch's settings with the ones this rule sets
+    pub fn apply(&self, args: &mut Args) {
+        if let Some(format) = &self.format {
+            args.format = Some(format.clone());
+        }
+        if let Some(quality) = self.quality {
+            args.quality = Some(quality);
+        }
+        if let Some(verify) = self.verify {
+            args.verify = verify;
+        }
+    }
+}
+
+/// The first rule matching `path`, in config order
+pub fn first_match<'a>(rules: &'a [Rule], path: &Path) -> Option<&'a Rule> {
+    rules.iter().find(|rule| rule.matches(path))
+}
+
+/// Match a `/`-separated path against a glob, where `*` and `?` stay within one
+/// path component and `**` spans any number of them
+pub fn glob_match(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<&str> = pattern.trim_start_matches("./").split('/').collect();
+    let path: Vec<&str> = path.trim_start_matches("./").split('/').collect();
+    match_components(&pattern, &path)
+}
+
+fn match_components(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| match_components(rest, &path[skip..])),
+        Some((first, rest)) => match path.split_first() {
+            Some((component, path_rest)) => {
+                match_component(first.as_bytes(), component.as_bytes()) && match_components(rest, path_rest)
+            }
+            None => false,
+        },
+    }
+}
+
+fn match_component(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some((b'*', rest)) => (0..=text.len()).any(|skip| match_component(rest, &text[skip..])),
+        Some((b'?', rest)) => !text.is_empty() && match_component(rest, &text[1..]),
+        Some((c, rest)) => text.first() == Some(c) && match_component(rest, &text[1..]),
+    }
+}
@@ -0,0 +1,71 @@
+use crate::{voice, AudioFormat};
+use anyhow::Result;
+use std::ops::RangeInclusive;
+
+/// Sample rates MP3 frames can have
+pub const MP3_RATES: [u32; 9] = [8_000, 11_025, 12_000, 16_000, 22_050, 24_000, 32_000, 44_100, 48_000];
+
+/// Sample rates AAC signals with an index
+pub const AAC_RATES: [u32; 13] = [7_350, 8_000, 11_025, 12_000, 16_000, 22_050, 24_000, 32_000, 44_100, 48_000, 64_000, 88_200, 96_000];
+
+/// Sample rates libopus encodes at; players always decode Opus at 48 kHz
+pub const OPUS_RATES: [u32; 5] = [8_000, 12_000, 16_000, 24_000, 48_000];
+
+/// Sample rates libspeex encodes at: narrowband, wideband and ultra-wideband
+pub const SPEEX_RATES: [u32; 3] = [8_000, 16_000, 32_000];
+
+/// Sample rates accepted by formats that take any rate
+pub const ANY_RATE: RangeInclusive<u32> = 8_000..=384_000;
+
+/// The only sample rates the encoder of `format` accepts, or `None` if it
+/// takes any rate in `ANY_RATE`
+pub fn sample_rates(format: &AudioFormat) -> Option<&'static [u32]> {
+    match format {
+        AudioFormat::Mp3 => Some(&MP3_RATES),
+        AudioFormat::Aac => Some(&AAC_RATES),
+        AudioFormat::Opus => Some(&OPUS_RATES),
+        AudioFormat::AmrNb => Some(&[8_000]),
+        AudioFormat::AmrWb => Some(&[16_000]),
+        AudioFormat::Speex => Some(&SPEEX_RATES),
+        AudioFormat::Wav | AudioFormat::Aiff | AudioFormat::Flac | AudioFormat::WavPack | AudioFormat::Ogg => None,
+    }
+}
+
+pub fn check_sample_rate(format: &AudioFormat, rate: u32) -> Result<()> {
+    match sample_rates(format) {
+        Some(rates) if !rates.contains(&rate) => {
+            let rates: Vec<String> = rates.iter().map(u32::to_string).collect();
+            anyhow::bail!("{} output does not support {} Hz; use one of {}", format.to_string().to_uppercase(), rate, rates.join(", "))
+        }
+        None if !ANY_RATE.contains(&rate) => {
+            anyhow::bail!("--sample-rate must be between {} and {} Hz, got {}", ANY_RATE.start(), ANY_RATE.end(), rate)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Most channels the encoder of `format` takes
+pub fn max_channels(format: &AudioFormat) -> u32 {
+    match format {
+        AudioFormat::Mp3 => 2,
+        AudioFormat::AmrNb | AudioFormat::AmrWb | AudioFormat::Speex => 1,
+        _ => 8,
+    }
+}
+
+pub fn check_channels(format: &AudioFormat, channels: u32) -> Result<()> {
+    let max = max_channels(format);
+    if channels == 0 || channels > max {
+        anyhow::bail!("{} output takes 1 to {} channels, got {}", format.to_string().to_uppercase(), max, channels);
+    }
+    Ok(())
+}
+
+/// Sample rate `format` is encoded at when --sample-rate is not given, if
+/// it is not left to the source
+pub fn default_sample_rate(format: &AudioFormat) -> Option<u32> {
+    match format {
+        AudioFormat::Wav => Some(44_100),
+        _ => voice::sample_rate(format),
+    }
+}
@@ -0,0 +1,567 @@
+use crate::budget::SizeBudget;
+use crate::claims::OutputClaims;
+use crate::disc::Disc;
+use crate::drm;
+use crate::jobs::{self, Calibration, Jobs};
+use crate::limits::ResourceLimits;
+use crate::preflight::{self, PlannedJob, Preflight};
+use crate::report::{BatchReport, ReportEntry};
+use crate::rules::{self, Rule};
+use crate::status::StatusFile;
+use crate::verifier::Verifier;
+use crate::{isolation, project, reporter, say, size, template, throttle, warning, webhook, Args, AudioExtractor, ExtractorError};
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+
+/// Size at which `unsupported.log` is rotated to `unsupported.log.1`
+pub const DEFAULT_LOG_MAX_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Why an input found during a scan was not extracted
+#[derive(Debug, Clone, PartialEq)]
+pub enum SkipReason {
+    UnsupportedType,
+    Unreadable(String),
+    NoAudioStream,
+    ZeroDuration,
+    DrmProtected(String),
+    /// Skipped by the --hook script, with its reason
+    Hook(String),
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SkipReason::UnsupportedType => write!(f, "unsupported file type"),
+            SkipReason::Unreadable(detail) => write!(f, "unreadable container: {}", detail),
+            SkipReason::NoAudioStream => write!(f, "no audio stream"),
+            SkipReason::ZeroDuration => write!(f, "zero duration"),
+            SkipReason::DrmProtected(scheme) => write!(f, "DRM-protected ({})", scheme),
+            SkipReason::Hook(reason) => write!(f, "hook: {}", reason),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkippedInput {
+    pub path: PathBuf,
+    pub reason: SkipReason,
+}
+
+/// Inputs found under a directory, split into extractable and skipped
+#[derive(Debug, Default)]
+pub struct Scan {
+    pub inputs: Vec<PathBuf>,
+    pub skipped: Vec<SkippedInput>,
+}
+
+/// Recursively collect the files under `root`, in path order.
+///
+/// Image-sequence project folders are returned as inputs themselves. Files
+/// with a supported extension are probed with ffprobe when `probe` is set, so
+/// inputs that cannot yield audio are skipped before extraction.
+pub fn scan(root: &Path, extensions: &[&str], probe: Option<&ResourceLimits>) -> Result<Scan> {
+    let mut files = Vec::new();
+    let mut projects = Vec::new();
+    collect_files(root, &mut files, &mut projects)?;
+    files.sort();
+
+    let mut scan = Scan { inputs: projects, ..Default::default() };
+    for path in files {
+        let supported = Disc::detect(&path).is_some()
+            || path
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| extensions.contains(&e.to_lowercase().as_str()));
+
+        let skipped = if !supported {
+            Some(SkipReason::UnsupportedType)
+        } else {
+            match drm::detect_protection_boxes(&path) {
+                Ok(Some(scheme)) => Some(SkipReason::DrmProtected(scheme)),
+                _ => probe.and_then(|limits| probe_input(&path, limits).err()),
+            }
+        };
+
+        match skipped {
+            Some(reason) => scan.skipped.push(SkippedInput { path, reason }),
+            None => scan.inputs.push(path),
+        }
+    }
+    scan.inputs.sort();
+    Ok(scan)
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>, projects: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir).with_context(|| format!("Failed to read directory {:?}", dir))? {
+        let entry = entry?;
+        let path = entry.path();
+        if !entry.file_type()?.is_dir() {
+            files.push(path);
+        } else if Disc::detect(&path).is_some() || project::audio_track(&path)?.is_some() {
+            // Discs and image-sequence exports are extracted as a whole, not file by file
+            projects.push(path);
+        } else {
+            collect_files(&path, files, projects)?;
+        }
+    }
+    Ok(())
+}
+
+/// Check with ffprobe that the input is unencrypted and has an audio stream of
+/// non-zero duration
+pub fn probe_input(path: &Path, limits: &ResourceLimits) -> std::result::Result<(), SkipReason> {
+    let mut cmd = Command::new("ffprobe");
+    cmd.arg("-v").arg("error")
+        .arg("-print_format").arg("json")
+        .arg("-show_format")
+        .arg("-show_streams")
+        .arg(path);
+    let output = limits.output(&mut cmd).map_err(|e| SkipReason::Unreadable(format!("{:#}", e)))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let detail = stderr.lines().last().unwrap_or("ffprobe failed").trim().to_string();
+        return Err(SkipReason::Unreadable(detail));
+    }
+
+    let json = String::from_utf8_lossy(&output.stdout);
+    if let Some(scheme) = drm::ffprobe_protection(&json, &String::from_utf8_lossy(&output.stderr)) {
+        return Err(SkipReason::DrmProtected(scheme));
+    }
+
+    let probe: serde_json::Value = serde_json::from_str(&json)
+        .map_err(|e| SkipReason::Unreadable(e.to_string()))?;
+    let has_audio = probe["streams"]
+        .as_array()
+        .is_some_and(|streams| streams.iter().any(|s| s["codec_type"] == "audio"));
+    if !has_audio {
+        return Err(SkipReason::NoAudioStream);
+    }
+
+    let duration = probe["format"]["duration"].as_str().and_then(|d| d.parse::<f64>().ok());
+    if duration.is_some_and(|d| d <= 0.0) {
+        return Err(SkipReason::ZeroDuration);
+    }
+    Ok(())
+}
+
+/// Log of skipped inputs, one tab-separated `time path reason` line each.
+///
+/// Once the log grows past `max_bytes` it is moved to `<name>.1`, replacing
+/// any previous rotation, and a fresh log is started.
+#[derive(Debug, Clone)]
+pub struct UnsupportedLog {
+    path: PathBuf,
+    max_bytes: u64,
+}
+
+impl UnsupportedLog {
+    pub fn new<P: AsRef<Path>>(path: P, max_bytes: u64) -> Self {
+        Self { path: path.as_ref().to_path_buf(), max_bytes }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn append(&self, skipped: &SkippedInput) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).context("Failed to create log directory")?;
+        }
+        if fs::metadata(&self.path).is_ok_and(|m| m.len() >= self.max_bytes) {
+            let mut rotated = self.path.as_os_str().to_os_string();
+            rotated.push(".1");
+            fs::rename(&self.path, rotated).context("Failed to rotate unsupported log")?;
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open {:?}", self.path))?;
+        writeln!(
+            file,
+            "{}\t{}\t{}",
+            chrono::Utc::now().to_rfc3339(),
+            skipped.path.display(),
+            skipped.reason
+        )
+        .context("Failed to write unsupported log")
+    }
+}
+
+/// An input of a directory scan once extracted: the input, the output path it
+/// was shortened from, and the output
+type BatchOutcome = (PathBuf, Option<PathBuf>, Result<PathBuf>);
+
+/// Extractor for one input of a scan, and the hook's reason if it skipped the input
+type PreparedInput = Result<(AudioExtractor, Option<String>)>;
+
+impl AudioExtractor {
+    /// Settings and output path for one input of a directory scan, with
+    /// config rules, metadata templates and the hook applied. Returns the
+    /// hook's reason if it skipped the input.
+    fn prepare_scan_input(&self, input: &Path, rules: &[Rule]) -> PreparedInput {
+        let root = &self.args.input;
+        let output_dir = &self.args.output;
+        let relative = input.strip_prefix(root).unwrap_or(input);
+        let mirrored = match self.args.sanitize_names {
+            Some(profile) => template::sanitize_path(relative, profile),
+            None => relative.to_path_buf(),
+        };
+        let mut args = Args {
+            input: input.to_path_buf(),
+            ..self.args.clone()
+        };
+        let rule = rules::first_match(rules, relative);
+        if let Some(rule) = rule {
+            rule.apply(&mut args);
+        }
+        let format = args.format.clone().unwrap();
+
+        // A templated output names each file from its metadata instead of mirroring the tree
+        args.output = if let Some(output_template) = &self.args.output_template {
+            output_dir.join(output_template)
+        } else if !template::has_fields(output_dir) {
+            output_dir.join(&mirrored).with_extension(format.to_string())
+        } else if rule.is_some_and(|rule| rule.format.is_some()) {
+            output_dir.with_extension(format.to_string())
+        } else {
+            output_dir.clone()
+        };
+        let mut extractor = self.for_input(args).with_expanded_output()?;
+        let skip = extractor.run_hook()?;
+        Ok((extractor, skip))
+    }
+
+    /// Plan every input of a scan and stop before extracting anything if one
+    /// of them, or the batch as a whole, would fail. In a terminal, and
+    /// unless --yes is set, the user then confirms the job list and may
+    /// deselect inputs, which are returned.
+    fn preflight(&self, jobs: &[(PathBuf, PreparedInput)], skipped: &[SkippedInput], claims: &OutputClaims) -> Result<Vec<PathBuf>> {
+        say!("🛫 Preflight: checking {} input(s)...", jobs.len());
+        let planned: Vec<_> = jobs.iter()
+            .filter_map(|(input, prepared)| match prepared {
+                Ok((_, Some(_))) => None,
+                Ok((extractor, None)) => {
+                    let mut job = extractor.plan_job();
+                    // Claiming now hands out the same paths when the inputs are extracted
+                    let claimed = claims.claim(input, extractor.args.output.clone());
+                    if claimed != extractor.args.output {
+                        job.warnings.push(format!("Another input has the same output name; writing {:?}", claimed));
+                        job.output = Some(claimed);
+                    }
+                    if let Some(long) = &extractor.shortened_from {
+                        job.warnings.push(format!("Output path shortened from {:?}", long));
+                    }
+                    Some(job)
+                }
+                Err(e) => {
+                    let mut job = PlannedJob::new(input);
+                    job.problems.push(format!("{:#}", e));
+                    Some(job)
+                }
+            })
+            .collect();
+
+        let preflight = Preflight::new(planned, skipped, &template::static_prefix(&self.args.output));
+        say!("{}", preflight);
+        if let Some(path) = &self.args.preflight_report {
+            preflight.write(path)?;
+            say!("Preflight report written to {:?}", path);
+        }
+        if !preflight.go() {
+            anyhow::bail!("Preflight found problems; nothing was extracted");
+        }
+
+        // The prompt would land among --json's lines
+        if self.args.yes || !std::io::stdin().is_terminal() || reporter::is_quiet() {
+            return Ok(Vec::new());
+        }
+        let selected = preflight::confirm(&preflight.jobs, &mut std::io::stdin().lock(), &mut std::io::stdout())?
+            .context("Batch cancelled; nothing was extracted")?;
+        let deselected: Vec<_> = preflight.jobs.iter().enumerate()
+            .filter(|(index, _)| !selected.contains(index))
+            .map(|(_, job)| job.input.clone())
+            .collect();
+        if !deselected.is_empty() {
+            say!("⏭ Deselected {} input(s)", deselected.len());
+        }
+        Ok(deselected)
+    }
+
+    /// Check one prepared input without extracting it: that it has audio, that
+    /// its settings are valid, and how large its output will be
+    pub fn plan_job(&self) -> PlannedJob {
+        let mut job = PlannedJob::new(&self.args.input);
+        job.output = Some(self.args.output.clone());
+        job.format = self.args.format.as_ref().map(|f| f.to_string());
+
+        if self.args.output == self.args.input {
+            job.problems.push("Output would overwrite the input".to_string());
+        } else if self.args.output.exists() {
+            job.warnings.push("Output exists and will be overwritten".to_string());
+        }
+
+        let mut source_duration = None;
+        if self.is_ffprobe_available() {
+            match self.get_video_info() {
+                Ok(info) if !info.has_audio => job.problems.push("No audio stream".to_string()),
+                Ok(info) => source_duration = Some(info.duration),
+                Err(e) => job.problems.push(format!("Cannot probe input: {:#}", e)),
+            }
+        }
+        if let Err(e) = self.time_range_args(source_duration).map_err(anyhow::Error::from).and_then(|_| self.build_ffmpeg_command()) {
+            job.problems.push(format!("{:#}", e));
+        }
+
+        job.duration = source_duration.map(|total| {
+            let start = self.args.start.unwrap_or(0.0);
+            let end = self.args.end.or(self.args.duration.map(|d| start + d)).unwrap_or(total);
+            (end.min(total) - start).max(0.0)
+        });
+        if let (Some(format), Some(duration)) = (self.args.format.as_ref(), job.duration) {
+            job.estimated_size = Some(preflight::estimate_size(format, self.bitrate(), duration));
+        }
+        job
+    }
+
+    /// Extract every supported file under the input directory into the output
+    /// directory, mirroring the tree.
+    ///
+    /// Files that are skipped (unsupported type, unreadable, no audio, zero
+    /// duration) are appended to the unsupported log rather than reported as failures.
+    pub fn extract_directory(&self) -> Result<Vec<Result<PathBuf, ExtractorError>>, ExtractorError> {
+        let root = &self.args.input;
+        let output_dir = &self.args.output;
+
+        let limits = self.resource_limits();
+        let probe = self.is_ffprobe_available().then_some(&limits);
+        let mut scan = scan(root, &Self::get_supported_video_formats(), probe)?;
+        jobs::sort(&mut scan.inputs, self.args.order, |input| self.input_duration(input));
+
+        let log_path = self.args.unsupported_log.clone()
+            .unwrap_or_else(|| template::static_prefix(output_dir).join("unsupported.log"));
+        let log = UnsupportedLog::new(log_path, DEFAULT_LOG_MAX_BYTES);
+        if !scan.skipped.is_empty() {
+            for skipped in &scan.skipped {
+                log.append(skipped)?;
+            }
+            warning!("⚠ Skipped {} input(s), see {:?}", scan.skipped.len(), log.path());
+        }
+
+        let budget = self.batch_budget(&scan.inputs)?;
+        let claims = OutputClaims::new();
+        let rules = Self::rules();
+        let total = scan.inputs.len();
+        let prepare = |input: PathBuf| {
+            let prepared = self.prepare_scan_input(&input, &rules);
+            (input, prepared)
+        };
+        // With --preflight every input is planned and checked before the first encode
+        let jobs: Box<dyn Iterator<Item = _> + Send> = if self.args.preflight {
+            let mut jobs: Vec<_> = scan.inputs.into_iter().map(prepare).collect();
+            let deselected = self.preflight(&jobs, &scan.skipped, &claims)?;
+            jobs.retain(|(input, _)| !deselected.contains(input));
+            if let Some(budget) = &budget {
+                deselected.iter().for_each(|input| budget.forgo(input));
+            }
+            Box::new(jobs.into_iter())
+        } else {
+            Box::new(scan.inputs.into_iter().map(prepare))
+        };
+
+        let mut jobs = jobs.peekable();
+        let concurrency = match self.args.jobs.unwrap_or_default() {
+            Jobs::Count(count) => count,
+            Jobs::Auto => {
+                let sample = match jobs.peek() {
+                    Some((_, Ok((extractor, None)))) => Some(extractor),
+                    _ => None,
+                };
+                self.auto_jobs(sample, total)
+            }
+        };
+        // Outputs are claimed as inputs are taken, so names do not depend on which job finishes first
+        let jobs = jobs.map(|(input, prepared)| {
+            let prepared = prepared.map(|(mut extractor, skip)| {
+                if skip.is_none() {
+                    // Metadata templates can give two inputs the same name
+                    extractor.args.output = claims.claim(&input, extractor.args.output);
+                }
+                (extractor, skip)
+            });
+            (input, prepared)
+        });
+
+        let status = StatusFile::new("scan", total);
+        let webhooks = Self::webhooks();
+        let verifier = Verifier::default();
+        // Threads are started for the full count; a [[throttle]] holds some back during its hours
+        let allowed = || throttle::jobs_at(&self.throttles, chrono::Local::now().time(), concurrency);
+        let outcomes = jobs::run_throttled(concurrency, jobs, allowed, |index, (input, prepared)| -> Result<Option<BatchOutcome>> {
+            status.started(&input);
+            if let (Some(budget), Ok((_, Some(_))) | Err(_)) = (&budget, &prepared) {
+                budget.forgo(&input);
+            }
+            let mut shortened_from = None;
+            let result = match prepared {
+                Ok((_, Some(reason))) => {
+                    say!("⏭ Hook skipped {:?}: {}", input, reason);
+                    log.append(&SkippedInput { path: input.clone(), reason: SkipReason::Hook(reason) })?;
+                    status.done(&input, true);
+                    return Ok(None);
+                }
+                Ok((mut extractor, None)) => {
+                    shortened_from = extractor.shortened_from.take();
+                    match &budget {
+                        Some(budget) => extractor.extract_within(budget, index, &verifier),
+                        None => extractor.extract_for_batch(index, &verifier, None),
+                    }
+                }
+                Err(e) => Err(e),
+            };
+            status.done(&input, result.is_ok());
+            if let Err(e) = &result {
+                webhook::input_failed(&webhooks, &input, e);
+            }
+            Ok(Some((input, shortened_from, result)))
+        });
+        let mut outcomes = outcomes.into_iter().collect::<Result<Vec<_>>>()?;
+
+        for (index, outcome) in verifier.finish() {
+            if let (Err(e), Some((input, _, result))) = (outcome, outcomes[index].as_mut()) {
+                let e = anyhow::anyhow!("Verification failed: {:#}", e);
+                webhook::input_failed(&webhooks, input, &e);
+                *result = Err(e);
+            }
+        }
+        let mut extracted = Vec::new();
+        let mut shortened = Vec::new();
+        let mut results = Vec::new();
+        for (input, shortened_from, result) in outcomes.into_iter().flatten() {
+            extracted.push(input);
+            shortened.push(shortened_from);
+            results.push(result);
+        }
+        status.finish();
+
+        if let Some(budget) = &budget {
+            say!("📦 Budget: used {} of {}", preflight::format_size(budget.spent()), preflight::format_size(budget.total()));
+            if budget.spent() > budget.total() {
+                warning!("⚠ The outputs are {} over the budget", preflight::format_size(budget.spent() - budget.total()));
+            }
+        }
+
+        if let Some(path) = &self.args.report {
+            let measure = self.is_ffmpeg_available().then_some(&limits);
+            let entries = extracted.iter().zip(&results).zip(shortened)
+                .map(|((input, result), shortened_from)| ReportEntry {
+                    shortened_from,
+                    ..ReportEntry::new(input, result, measure)
+                })
+                .collect();
+            BatchReport::new(entries).write(path)?;
+            say!("Report written to {:?}", path);
+        }
+
+        Ok(results.into_iter().map(|result| result.map_err(ExtractorError::from)).collect())
+    }
+
+    /// With --batch-budget, plan the budget over the durations of `inputs`
+    fn batch_budget(&self, inputs: &[PathBuf]) -> Result<Option<SizeBudget>> {
+        let Some(total) = self.args.batch_budget else {
+            return Ok(None);
+        };
+        let format = self.args.format.as_ref().unwrap();
+        if !format.is_lossy() {
+            anyhow::bail!("--batch-budget only applies to lossy formats; {} is lossless", format);
+        }
+        let mut durations = HashMap::new();
+        for input in inputs {
+            let duration = self.input_duration(input)
+                .with_context(|| format!("--batch-budget needs the duration of every input; cannot probe {:?}", input))?;
+            durations.insert(input.clone(), self.expected_duration(duration));
+        }
+        let seconds: f64 = durations.values().sum();
+        say!("📦 Budget: {} for {:.0} minutes of audio", preflight::format_size(total), seconds / 60.0);
+        Ok(Some(SizeBudget::new(total, durations)))
+    }
+
+    /// Extract one input of a scan at the bitrate its share of `budget`
+    /// allows, then settle what the output took
+    fn extract_within(mut self, budget: &SizeBudget, index: usize, verifier: &Verifier) -> Result<PathBuf> {
+        let Some((share, seconds)) = budget.reserve(&self.args.input) else {
+            return self.extract_for_batch(index, verifier, None);
+        };
+        let kbps = size::bitrate_for(size::usable_bytes(share), seconds)?;
+        if kbps < 8 {
+            budget.settle(share, 0);
+            anyhow::bail!(
+                "The budget has {} left for {:?}, too little for {:.0} seconds of audio",
+                preflight::format_size(share), self.args.input, seconds
+            );
+        }
+        let kbps = size::encoder_bitrate(self.args.format.as_ref().unwrap(), kbps);
+        say!("📦 {:?}: {} of the budget, {} kbps", self.args.input, preflight::format_size(share), kbps);
+        self.bitrate = OnceLock::from(kbps);
+
+        let result = self.extract_for_batch(index, verifier, None);
+        let actual = result.as_ref().ok()
+            .and_then(|output| fs::metadata(output).ok())
+            .map_or(0, |metadata| metadata.len());
+        budget.settle(share, actual);
+        result
+    }
+
+    /// Concurrency for --jobs auto, from the cores, how fast `sample`'s disk
+    /// reads and a calibration encode of it with the batch's settings
+    fn auto_jobs(&self, sample: Option<&AudioExtractor>, inputs: usize) -> usize {
+        let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+        // Read before the calibration encode caches the start of the file
+        let disk = sample.and_then(|sample| jobs::read_throughput(sample.source()));
+        let calibration = sample.filter(|_| self.is_ffmpeg_available()).and_then(|sample| match sample.calibrate() {
+            Ok(calibration) => Some(calibration),
+            Err(e) => {
+                warning!("⚠ Could not calibrate --jobs auto: {:#}", e);
+                None
+            }
+        });
+
+        let jobs = jobs::choose(cores, calibration.as_ref(), disk, inputs);
+        match &calibration {
+            Some(calibration) => say!(
+                "⚙ --jobs auto: {} job(s) ({} cores, {:.1} per job, {}/s read per job)",
+                jobs,
+                cores,
+                calibration.cores_per_job,
+                preflight::format_size(calibration.read_rate as u64)
+            ),
+            None => say!("⚙ --jobs auto: {} job(s) for {} cores", jobs, cores),
+        }
+        jobs
+    }
+
+    /// Time a short encode of this input with its settings, for --jobs auto
+    fn calibrate(&self) -> Result<Calibration> {
+        let duration = self.get_video_info()?.duration;
+        if duration <= 0.0 {
+            anyhow::bail!("{:?} has no known duration", self.source());
+        }
+        let size = fs::metadata(self.source())?.len() as f64;
+        let seconds = (jobs::CALIBRATION_SECONDS as f64).min(duration);
+
+        let mut cmd = isolation::ffmpeg();
+        cmd.arg("-v").arg("error");
+        cmd.args(self.source_input_args()?);
+        cmd.arg("-t").arg(format!("{:.3}", seconds)).arg("-vn");
+        self.add_codec_args(&mut cmd);
+        cmd.arg("-f").arg("null").arg("-");
+        Calibration::measure(&mut cmd, seconds, size / duration, &self.resource_limits())
+    }
+}
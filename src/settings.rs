@@ -0,0 +1,148 @@
+use crate::analysis::SegmentLabel;
+use crate::loudness::LoudnessTarget;
+use crate::tags::Tags;
+use crate::AudioExtractor;
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// Settings that determine the output, as recorded in audit logs and reports.
+/// Enum settings are written as their lowercase names.
+#[derive(Serialize, Debug, Clone)]
+pub struct Settings {
+    pub format: Option<String>,
+    /// Bitrate in kbps
+    pub quality: u32,
+    pub verify: bool,
+    pub encrypt_to: Option<String>,
+    pub mute: Vec<String>,
+    pub mute_file: Option<PathBuf>,
+    pub redact_tone: Option<f64>,
+    pub watermark_tone: Option<f64>,
+    pub watermark_audio: Option<PathBuf>,
+    pub watermark_interval: Option<f64>,
+    pub watermark_gain: Option<f64>,
+    pub detect_language: bool,
+    pub diarize: bool,
+    pub classify: bool,
+    pub keep_segments: Vec<SegmentLabel>,
+    pub analyze_music: bool,
+    pub source_quality: bool,
+    pub faststart: bool,
+    pub fragmented: bool,
+    pub gapless: bool,
+    pub id3_version: Option<&'static str>,
+    pub id3_encoding: Option<String>,
+    pub mp3_mode: Option<String>,
+    pub lame_preset: Option<String>,
+    pub preset: Option<String>,
+    pub profile: Option<String>,
+    pub target_size: Option<u64>,
+    pub batch_budget: Option<u64>,
+    pub vbr: Option<f64>,
+    pub two_pass: bool,
+    pub normalize: Option<LoudnessTarget>,
+    pub bit_depth: Option<u16>,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u32>,
+    pub hybrid: bool,
+    pub fix_timestamps: bool,
+    pub fill_gaps: Option<f64>,
+    pub title: Option<u32>,
+    pub all_titles: bool,
+    pub all_tracks: bool,
+    pub split_chapters: bool,
+    pub thumbnail: bool,
+    pub thumbnail_at: Option<f64>,
+    pub embed_cover: bool,
+    pub cover_at: Option<f64>,
+    pub nfo: bool,
+    pub timeline: bool,
+    pub edl: bool,
+    pub subtitles: bool,
+    pub audio_track: Option<usize>,
+    pub chunked: Option<u32>,
+    pub backend: String,
+    pub copy_metadata: bool,
+    pub tags: Tags,
+    pub start: Option<f64>,
+    pub end: Option<f64>,
+    pub duration: Option<f64>,
+    pub sanitize_names: Option<String>,
+}
+
+/// The lowercase name of an enum setting
+fn name(value: impl std::fmt::Debug) -> String {
+    format!("{:?}", value).to_lowercase()
+}
+
+impl AudioExtractor {
+    /// Settings that determine the output, as recorded in audit logs and reports
+    pub fn settings(&self) -> Settings {
+        let args = &self.args;
+        Settings {
+            format: args.format.as_ref().map(|f| f.to_string()),
+            quality: self.bitrate(),
+            verify: args.verify,
+            encrypt_to: args.encrypt_to.clone(),
+            mute: args.mute.iter().map(|r| r.to_string()).collect(),
+            mute_file: args.mute_file.clone(),
+            redact_tone: args.redact_tone,
+            watermark_tone: args.watermark_tone,
+            watermark_audio: args.watermark_audio.clone(),
+            watermark_interval: args.watermark_interval,
+            watermark_gain: args.watermark_gain,
+            detect_language: args.detect_language,
+            diarize: args.diarize,
+            classify: args.classify,
+            keep_segments: args.keep_segments.clone(),
+            analyze_music: args.analyze_music,
+            source_quality: args.source_quality,
+            faststart: args.faststart,
+            fragmented: args.fragmented,
+            gapless: args.gapless,
+            id3_version: args.id3_version.map(|v| v.ffmpeg_value()),
+            id3_encoding: args.id3_encoding.map(name),
+            mp3_mode: args.mp3_mode.map(name),
+            lame_preset: args.lame_preset.map(name),
+            preset: args.preset.map(name),
+            profile: args.profile.clone(),
+            target_size: args.target_size,
+            batch_budget: args.batch_budget,
+            vbr: self.vbr_level().ok().flatten(),
+            two_pass: args.two_pass,
+            normalize: self.loudness_target().ok().flatten(),
+            bit_depth: args.bit_depth.map(|depth| depth.bits()),
+            sample_rate: args.sample_rate,
+            channels: args.channels,
+            hybrid: args.hybrid,
+            fix_timestamps: args.fix_timestamps,
+            fill_gaps: args.fill_gaps,
+            title: args.title,
+            all_titles: args.all_titles,
+            all_tracks: args.all_tracks,
+            split_chapters: args.split_chapters,
+            thumbnail: args.thumbnail,
+            thumbnail_at: args.thumbnail_at,
+            embed_cover: args.embed_cover,
+            cover_at: args.cover_at,
+            nfo: args.nfo,
+            timeline: args.timeline,
+            edl: args.edl,
+            subtitles: args.subtitles,
+            audio_track: args.audio_track,
+            chunked: args.chunked,
+            backend: name(self.backend()),
+            copy_metadata: self.copies_metadata(),
+            tags: self.tags(),
+            start: args.start,
+            end: args.end,
+            duration: args.duration,
+            sanitize_names: args.sanitize_names.map(name),
+        }
+    }
+
+    /// `settings` as JSON
+    pub fn settings_summary(&self) -> serde_json::Value {
+        serde_json::to_value(self.settings()).expect("settings serialize to JSON")
+    }
+}
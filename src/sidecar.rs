@@ -0,0 +1,294 @@
+use crate::cover::{self, CoverSource};
+use crate::effects::Effect;
+use crate::timeline::{self, Timeline};
+use crate::{isolation, say, subtitles, timecode, warning, AudioExtractor, ExtractorError};
+use crate::limits::ResourceLimits;
+use crate::metadata::SourceMetadata;
+use anyhow::{Context, Result};
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Position of the default thumbnail as a fraction of the duration, past
+/// fade-ins and title cards
+pub const DEFAULT_THUMBNAIL_POSITION: f64 = 0.1;
+
+/// Thumbnail sidecar of an output: `episode.mp3` gets `episode.jpg`
+pub fn thumbnail_path(output: &Path) -> PathBuf {
+    output.with_extension("jpg")
+}
+
+/// Whether `path` names a JPEG image
+pub fn is_jpeg(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("jpg") || ext.eq_ignore_ascii_case("jpeg"))
+}
+
+/// When to take the thumbnail: `at`, kept inside the video, or
+/// `DEFAULT_THUMBNAIL_POSITION` of the way in
+pub fn thumbnail_time(at: Option<f64>, duration: Option<f64>) -> f64 {
+    let duration = duration.filter(|d| *d > 0.0);
+    match (at, duration) {
+        (Some(at), Some(duration)) if at >= duration => duration / 2.0,
+        (Some(at), _) => at,
+        (None, Some(duration)) => duration * DEFAULT_THUMBNAIL_POSITION,
+        (None, None) => 0.0,
+    }
+}
+
+/// Save the video frame at `at` seconds as a JPEG.
+///
+/// `input_args` are the options and `-i` selecting the video, so disc titles
+/// work the same way as files.
+pub fn write_thumbnail(input_args: &[OsString], at: f64, path: &Path, limits: &ResourceLimits) -> Result<()> {
+    let mut cmd = isolation::ffmpeg();
+    // Seeking before the input jumps to the nearest keyframe instead of decoding up to it
+    cmd.arg("-v").arg("error")
+        .arg("-ss").arg(format!("{:.3}", at))
+        .args(input_args)
+        .arg("-map").arg("0:v:0")
+        .arg("-frames:v").arg("1")
+        .arg("-q:v").arg("2")
+        .arg("-y")
+        .arg(path);
+    let output = limits.output(&mut cmd).context("Failed to run ffmpeg")?;
+    if !output.status.success() || !path.exists() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Failed to extract thumbnail: {}", stderr.trim());
+    }
+    Ok(())
+}
+
+/// NFO sidecar of an output: `episode.mp3` gets `episode.nfo`
+pub fn nfo_path(output: &Path) -> PathBuf {
+    output.with_extension("nfo")
+}
+
+/// Kodi/Jellyfin NFO describing `output`.
+///
+/// Sources tagged with a show become `<episodedetails>`, so they are filed
+/// under the show; everything else becomes `<musicvideo>`, which both servers
+/// read for standalone media with an artist and album.
+pub fn nfo(metadata: &SourceMetadata, output: &Path) -> String {
+    let fallback_title = output.file_stem().and_then(|s| s.to_str()).unwrap_or("").to_string();
+    let title = metadata.title.clone().unwrap_or(fallback_title);
+    let year = metadata.recorded_date.as_deref().map(|date| &date[..4]);
+    let runtime = metadata.duration.map(|seconds| ((seconds / 60.0).round() as u64).to_string());
+
+    let (root, fields): (&str, Vec<(&str, Option<String>)>) = if metadata.show.is_some() {
+        ("episodedetails", vec![
+            ("title", Some(title)),
+            ("showtitle", metadata.show.clone()),
+            ("season", metadata.season.map(|n| n.to_string())),
+            ("episode", metadata.episode.map(|n| n.to_string())),
+            ("plot", metadata.description.clone()),
+            ("aired", metadata.recorded_date.clone()),
+            ("genre", metadata.genre.clone()),
+            ("runtime", runtime),
+        ])
+    } else {
+        ("musicvideo", vec![
+            ("title", Some(title)),
+            ("artist", metadata.artist.clone()),
+            ("album", metadata.album.clone()),
+            ("plot", metadata.description.clone()),
+            ("premiered", metadata.recorded_date.clone()),
+            ("year", year.map(str::to_string)),
+            ("genre", metadata.genre.clone()),
+            ("runtime", runtime),
+        ])
+    };
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n");
+    xml.push_str(&format!("<{}>\n", root));
+    for (name, value) in fields {
+        if let Some(value) = value {
+            xml.push_str(&format!("  <{name}>{}</{name}>\n", escape_xml(&value)));
+        }
+    }
+    xml.push_str(&format!("</{}>\n", root));
+    xml
+}
+
+/// Write the NFO sidecar for `output`, returning its path
+pub fn write_nfo(metadata: &SourceMetadata, output: &Path) -> Result<PathBuf> {
+    let path = nfo_path(output);
+    fs::write(&path, nfo(metadata, output)).with_context(|| format!("Failed to write {:?}", path))?;
+    Ok(path)
+}
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+impl AudioExtractor {
+    /// Save the --thumbnail sidecar, returning its path if one was written
+    pub(crate) fn thumbnail(&self) -> Result<Option<PathBuf>> {
+        if !self.args.thumbnail {
+            return Ok(None);
+        }
+        if !self.is_ffmpeg_available() {
+            anyhow::bail!("Thumbnail extraction requires FFmpeg");
+        }
+        if self.source() != self.args.input {
+            warning!("⚠ {:?} has no video to take a thumbnail from", self.args.input);
+            return Ok(None);
+        }
+
+        let duration = self.get_video_info().ok().map(|info| info.duration);
+        let at = thumbnail_time(self.args.thumbnail_at, duration);
+        let path = thumbnail_path(&self.args.output);
+        write_thumbnail(&self.source_input_args()?, at, &path, &self.resource_limits())?;
+        say!("🖼 Thumbnail at {} saved to {:?}", timecode::format_timestamp(at), path);
+        Ok(Some(path))
+    }
+
+    /// Embed the cover art with --embed-cover and save it to --cover-out,
+    /// returning whether there was any to add
+    pub(crate) fn cover(&self) -> Result<bool> {
+        if !self.args.embed_cover && self.args.cover_out.is_none() {
+            return Ok(false);
+        }
+        if !self.is_ffmpeg_available() {
+            anyhow::bail!("Cover art extraction requires FFmpeg");
+        }
+        if self.source() != self.args.input {
+            warning!("⚠ {:?} has no video to take cover art from", self.args.input);
+            return Ok(false);
+        }
+
+        let input_args = self.source_input_args()?;
+        let limits = self.resource_limits();
+        let attached = cover::probe_attached(&input_args, &limits)?;
+        let duration = self.get_video_info().ok().map(|info| info.duration);
+        let source = CoverSource::choose(attached, self.args.cover_at, duration);
+
+        if let Some(path) = &self.args.cover_out {
+            cover::write_image(&input_args, source, path, &limits)?;
+            say!("🖼 Cover art saved to {:?}", path);
+        }
+        if self.args.embed_cover {
+            // The picture is embedded from a JPEG, whatever --cover-out's format
+            let image = match &self.args.cover_out {
+                Some(path) if is_jpeg(path) => path.clone(),
+                _ => {
+                    let image = self.args.output.with_extension("cover.jpg");
+                    cover::write_image(&input_args, source, &image, &limits)?;
+                    image
+                }
+            };
+            let embedded = cover::embed(&self.args.output, &image, self.args.format.as_ref().unwrap(), &self.id3_args()?, &limits);
+            if Some(&image) != self.args.cover_out.as_ref() {
+                let _ = fs::remove_file(&image);
+            }
+            embedded?;
+            match source {
+                CoverSource::Attached(_) => say!("🖼 Embedded the attached thumbnail as cover art"),
+                CoverSource::Frame(at) => say!("🖼 Embedded the frame at {} as cover art", timecode::format_timestamp(at)),
+            }
+        }
+        Ok(true)
+    }
+
+    /// Write the --nfo sidecar, returning its path if one was written
+    pub(crate) fn nfo(&self) -> Result<Option<PathBuf>> {
+        if !self.args.nfo {
+            return Ok(None);
+        }
+        if !self.is_ffprobe_available() {
+            return Err(ExtractorError::FfprobeNotInstalled { needed_for: "--nfo" }.into());
+        }
+
+        let metadata = SourceMetadata::probe(&self.source_input_args()?, &self.resource_limits())?;
+        let path = write_nfo(&metadata, &self.args.output)?;
+        say!("NFO written to {:?}", path);
+        Ok(Some(path))
+    }
+
+    /// Where each part of the output comes from in the source, after
+    /// --start/--end, --keep-segments and `Trim` effects, with the --mute
+    /// ranges moved onto the output's time
+    pub fn timeline(&self) -> Result<Timeline, ExtractorError> {
+        let duration = self.input_duration(self.source()).context("A timeline needs the source's duration")?;
+        let mut timeline = Timeline::new(&self.args.input, duration);
+        if self.has_time_range() {
+            let start = self.args.start.unwrap_or(0.0);
+            timeline.trim(start, self.args.end.or(self.args.duration.map(|duration| start + duration)));
+        }
+        if !self.args.keep_segments.is_empty() {
+            let kept: Vec<_> = self.source_segments()?
+                .iter()
+                .filter(|segment| self.args.keep_segments.contains(&segment.label))
+                .map(|segment| (segment.start, segment.end))
+                .collect();
+            timeline.keep_source(&kept);
+        }
+        for effect in &self.effects.effects {
+            if let Effect::Trim { start, end } = effect {
+                timeline.trim(*start, *end);
+            }
+        }
+        for range in self.mute_ranges()? {
+            timeline.mute_source(range.start, range.end);
+        }
+        Ok(timeline)
+    }
+
+    /// Write the --timeline sidecar and the --edl, returning whether either was written
+    pub(crate) fn write_timeline(&self) -> Result<bool> {
+        if !self.args.timeline && !self.args.edl {
+            return Ok(false);
+        }
+        let timeline = self.timeline()?;
+        if self.args.timeline {
+            let path = Timeline::sidecar_path(&self.args.output);
+            timeline.write(&path)?;
+            say!("Timeline written to {:?}", path);
+        }
+        if self.args.edl {
+            let fps = self.args.edl_fps.unwrap_or(timeline::DEFAULT_EDL_FPS);
+            if !(fps > 0.0 && fps.is_finite()) {
+                anyhow::bail!("--edl-fps must be above 0, got {}", fps);
+            }
+            let path = Timeline::edl_path(&self.args.output);
+            let title = self.args.output.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+            fs::write(&path, timeline.to_edl(&title, fps)).with_context(|| format!("Failed to write EDL {:?}", path))?;
+            say!("EDL written to {:?}", path);
+        }
+        Ok(true)
+    }
+
+    /// Cues of --subtitle-file, or else of the source's first subtitle track
+    fn source_subtitles(&self) -> Result<Vec<subtitles::Cue>> {
+        let text = match &self.args.subtitle_file {
+            Some(path) => fs::read_to_string(path).with_context(|| format!("Failed to read subtitles {:?}", path))?,
+            None => {
+                let mut cmd = isolation::ffmpeg();
+                cmd.arg("-v").arg("error");
+                cmd.args(self.source_input_args()?);
+                cmd.args(["-map", "0:s:0", "-f", "srt", "-"]);
+                let output = self.resource_limits().output(&mut cmd).context("Failed to run FFmpeg to read the subtitles")?;
+                if !output.status.success() {
+                    anyhow::bail!("The source has no text subtitle track to re-time: {}", String::from_utf8_lossy(&output.stderr).trim());
+                }
+                String::from_utf8_lossy(&output.stdout).into_owned()
+            }
+        };
+        subtitles::parse(&text)
+    }
+
+    /// Write the --subtitles file, re-timed to the output, returning whether it was written
+    pub(crate) fn write_subtitles(&self) -> Result<bool> {
+        if !self.args.subtitles {
+            return Ok(false);
+        }
+        let cues = subtitles::retime(&self.source_subtitles()?, &self.timeline()?);
+        let path = subtitles::output_path(&self.args.output);
+        fs::write(&path, subtitles::to_srt(&cues)).with_context(|| format!("Failed to write subtitles {:?}", path))?;
+        say!("{} subtitle cue(s) written to {:?}", cues.len(), path);
+        Ok(true)
+    }
+}
@@ -0,0 +1,80 @@
+use crate::AudioFormat;
+use anyhow::{Context, Result};
+
+/// Share of --target-size kept free for container overhead and tags
+pub const SAFETY_MARGIN: f64 = 0.03;
+
+/// Bitrates an MP3 frame can have, MPEG-2 and MPEG-1 together, in kbps
+pub const MP3_BITRATES: [u32; 18] = [8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160, 192, 224, 256, 320];
+
+/// A size such as `25MB`, `700 KB`, `1.5GB`, `64MiB` or a plain byte
+/// count. KB, MB and GB are powers of 1000, like `preflight::format_size`
+/// prints; KiB, MiB and GiB are powers of 1024.
+pub fn parse_size(s: &str) -> Result<u64> {
+    let s = s.trim();
+    let split = s.find(|c: char| c.is_ascii_alphabetic()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let number: f64 = number.trim().parse().with_context(|| format!("Invalid size: {:?}", s))?;
+    let multiplier: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" => 1_000,
+        "m" | "mb" => 1_000_000,
+        "g" | "gb" => 1_000_000_000,
+        "kib" => 1 << 10,
+        "mib" => 1 << 20,
+        "gib" => 1 << 30,
+        _ => anyhow::bail!("Unknown size unit in {:?}; use B, KB, MB, GB, KiB, MiB or GiB", s),
+    };
+    let bytes = number * multiplier as f64;
+    if !bytes.is_finite() || bytes < 1.0 {
+        anyhow::bail!("Size must be at least 1 byte: {:?}", s);
+    }
+    Ok(bytes as u64)
+}
+
+/// Bitrate in kbps that fills `bytes` with `seconds` of audio
+pub fn bitrate_for(bytes: u64, seconds: f64) -> Result<u32> {
+    if seconds <= 0.0 {
+        anyhow::bail!("Cannot fit audio of unknown length into a target size");
+    }
+    Ok((bytes as f64 * 8.0 / seconds / 1000.0).floor().min(u32::MAX as f64) as u32)
+}
+
+/// Bytes of a `bytes` target left for the audio after the safety margin
+pub fn usable_bytes(bytes: u64) -> u64 {
+    (bytes as f64 * (1.0 - SAFETY_MARGIN)) as u64
+}
+
+/// The bitrate the encoder of `format` actually uses for a size target of
+/// `kbps`: MP3 is rounded down to a frame bitrate, since LAME would round
+/// to the nearest, and Opus is kept in the 6-510 kbps it supports.
+pub fn encoder_bitrate(format: &AudioFormat, kbps: u32) -> u32 {
+    match format {
+        AudioFormat::Mp3 => MP3_BITRATES.iter().rev().copied().find(|&rate| rate <= kbps).unwrap_or(MP3_BITRATES[0]),
+        AudioFormat::Opus => kbps.clamp(6, 510),
+        _ => kbps,
+    }
+}
+
+/// Rate control for a size target: MP3 and AAC are encoded at a constant
+/// bitrate already, Opus is held close to it with constrained VBR, and
+/// Vorbis is capped at it.
+pub fn rate_control_args(format: &AudioFormat, kbps: u32) -> Vec<String> {
+    match format {
+        AudioFormat::Opus => vec!["-vbr".into(), "constrained".into()],
+        AudioFormat::Ogg => vec!["-maxrate".into(), format!("{}k", kbps)],
+        _ => Vec::new(),
+    }
+}
+
+/// For the second pass of --two-pass: the bitrate that brings an output
+/// encoded at `kbps` to `actual` bytes down (or up) to the usable part of
+/// `target` bytes. `None` if the first pass is not over the target and
+/// within 5% of it.
+pub fn corrected_bitrate(kbps: u32, actual: u64, target: u64) -> Option<u32> {
+    if actual <= target && actual as f64 >= target as f64 * 0.95 {
+        return None;
+    }
+    let corrected = (kbps as f64 * usable_bytes(target) as f64 / actual.max(1) as f64).floor() as u32;
+    (corrected != kbps).then_some(corrected.max(1))
+}
@@ -0,0 +1,210 @@
+use crate::isolation;
+use crate::limits::ResourceLimits;
+use crate::quality::{self, SourceAudio};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+
+/// Rate the source is decoded at for the bandwidth measurement
+const DECODE_SAMPLE_RATE: u32 = 44_100;
+
+/// Seconds of the source that are measured
+const MEASURE_SECONDS: u32 = 180;
+
+/// FFT size; about 10.8 Hz per bin at 44.1 kHz
+const FRAME: usize = 4096;
+
+/// Frames quieter than this (dBFS) say nothing about the bandwidth
+const SILENT_FRAME_DB: f64 = -50.0;
+
+/// How far below the 1-5 kHz level the spectrum must fall to count as cut off
+const ROLLOFF_DB: f64 = 50.0;
+
+/// Shortfall against the expected bandwidth that marks a source as upscaled
+const UPSCALE_MARGIN_HZ: f64 = 1500.0;
+
+/// How much audio bandwidth the source really carries, compared with what
+/// its codec and bitrate claim
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SourceQuality {
+    pub codec: String,
+    /// Claimed bitrate in kbps
+    pub bit_rate: Option<u32>,
+    /// Frequency above which the source's spectrum is cut off, in Hz
+    pub bandwidth: f64,
+    /// Bandwidth a genuine source of this codec and bitrate would have, in Hz
+    pub expected_bandwidth: Option<f64>,
+    /// Bitrate of a typical lossy encode with the measured bandwidth, in kbps
+    pub likely_bit_rate: Option<u32>,
+    /// The source carries much less bandwidth than it claims, e.g. a 128 kbps
+    /// MP3 re-encoded at 320 kbps or converted to FLAC
+    pub upscaled: bool,
+}
+
+impl SourceQuality {
+    /// Compare a measured bandwidth with what `source` claims to be
+    pub fn assess(source: &SourceAudio, bandwidth: f64) -> Self {
+        let expected_bandwidth = expected_bandwidth(source);
+        Self {
+            codec: source.codec.clone(),
+            bit_rate: source.bit_rate,
+            bandwidth,
+            expected_bandwidth,
+            likely_bit_rate: likely_bit_rate(bandwidth),
+            upscaled: expected_bandwidth.is_some_and(|expected| bandwidth < expected - UPSCALE_MARGIN_HZ),
+        }
+    }
+}
+
+impl std::fmt::Display for SourceQuality {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let claimed = match self.bit_rate {
+            Some(kbps) if !quality::is_lossless(&self.codec) => format!("{} at {} kbps", self.codec, kbps),
+            _ => self.codec.clone(),
+        };
+        write!(f, "{}, bandwidth {:.1} kHz", claimed, self.bandwidth / 1000.0)?;
+        if self.upscaled {
+            write!(f, " (upscaled")?;
+            if let Some(kbps) = self.likely_bit_rate {
+                write!(f, ", typical of ~{} kbps lossy audio", kbps)?;
+            }
+            write!(f, ")")?;
+        }
+        Ok(())
+    }
+}
+
+/// Bandwidth a genuine source would reach: encoders low-pass lower bitrates
+/// harder, and lossless audio goes up to nearly the Nyquist frequency. `None`
+/// when too little is known, or the bitrate is too low to expect much.
+pub fn expected_bandwidth(source: &SourceAudio) -> Option<f64> {
+    let nyquist_limit = source.sample_rate.map_or(20_000.0, |rate| rate as f64 * 0.45);
+    let expected: f64 = if source.is_lossless() {
+        20_000.0
+    } else {
+        match source.bit_rate? {
+            kbps if kbps >= 256 => 19_500.0,
+            kbps if kbps >= 192 => 18_500.0,
+            kbps if kbps >= 160 => 17_000.0,
+            _ => return None,
+        }
+    };
+    Some(expected.min(nyquist_limit))
+}
+
+/// Typical lossy bitrate for a bandwidth, after the low-pass defaults of
+/// common MP3 and AAC encoders; `None` for full-bandwidth audio
+pub fn likely_bit_rate(bandwidth: f64) -> Option<u32> {
+    match bandwidth {
+        b if b < 11_500.0 => Some(64),
+        b if b < 15_500.0 => Some(96),
+        b if b < 17_000.0 => Some(128),
+        b if b < 18_500.0 => Some(160),
+        b if b < 19_500.0 => Some(192),
+        _ => None,
+    }
+}
+
+/// Decode up to the first three minutes of the source to mono and measure its bandwidth
+pub fn measure(input_args: &[OsString], limits: &ResourceLimits) -> Result<f64> {
+    let mut cmd = isolation::ffmpeg();
+    cmd.arg("-v").arg("error")
+        .args(input_args)
+        .arg("-vn")
+        .arg("-t").arg(MEASURE_SECONDS.to_string())
+        .arg("-ac").arg("1")
+        .arg("-ar").arg(DECODE_SAMPLE_RATE.to_string())
+        .arg("-f").arg("s16le")
+        .arg("pipe:1");
+    let output = limits.output(&mut cmd).context("Failed to run ffmpeg")?;
+    if !output.status.success() {
+        anyhow::bail!("Failed to decode the source for bandwidth analysis: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+
+    let samples: Vec<i16> = output.stdout
+        .chunks_exact(2)
+        .map(|pair| i16::from_le_bytes([pair[0], pair[1]]))
+        .collect();
+    estimate_bandwidth(&samples, DECODE_SAMPLE_RATE).context("The source is too short or too quiet to measure its bandwidth")
+}
+
+/// Frequency in Hz above which the averaged spectrum stays more than
+/// `ROLLOFF_DB` below its 1-5 kHz level; `None` without any audible frames
+pub fn estimate_bandwidth(samples: &[i16], sample_rate: u32) -> Option<f64> {
+    let window: Vec<f64> = (0..FRAME)
+        .map(|i| 0.5 - 0.5 * (2.0 * std::f64::consts::PI * i as f64 / FRAME as f64).cos())
+        .collect();
+    let mut power = vec![0.0; FRAME / 2];
+    let mut frames = 0;
+    for frame in samples.chunks_exact(FRAME) {
+        let mean_square = frame.iter().map(|&s| (s as f64 / 32768.0).powi(2)).sum::<f64>() / FRAME as f64;
+        if 10.0 * mean_square.log10() < SILENT_FRAME_DB {
+            continue;
+        }
+        let mut re: Vec<f64> = frame.iter().zip(&window).map(|(&s, w)| s as f64 * w).collect();
+        let mut im = vec![0.0; FRAME];
+        fft(&mut re, &mut im);
+        for (bin, total) in power.iter_mut().enumerate() {
+            *total += re[bin] * re[bin] + im[bin] * im[bin];
+        }
+        frames += 1;
+    }
+    if frames == 0 {
+        return None;
+    }
+
+    let hz_per_bin = sample_rate as f64 / FRAME as f64;
+    // Smooth over ~100 Hz so single empty bins between harmonics don't count as the cutoff
+    let smoothed: Vec<f64> = (0..power.len())
+        .map(|bin| {
+            let range = bin.saturating_sub(4)..(bin + 5).min(power.len());
+            let len = range.len() as f64;
+            power[range].iter().sum::<f64>() / len
+        })
+        .collect();
+    let band = (1000.0 / hz_per_bin) as usize..(5000.0 / hz_per_bin) as usize;
+    let reference = smoothed[band.clone()].iter().sum::<f64>() / band.len() as f64;
+    if reference <= 0.0 {
+        return None;
+    }
+    let threshold = reference * 10f64.powf(-ROLLOFF_DB / 10.0);
+
+    let top = smoothed.iter().rposition(|&p| p > threshold).unwrap_or(0);
+    Some(top as f64 * hz_per_bin)
+}
+
+/// In-place radix-2 FFT; the length must be a power of two
+fn fft(re: &mut [f64], im: &mut [f64]) {
+    let n = re.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * std::f64::consts::PI / len as f64;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (angle * k as f64).sin_cos();
+                let (a, b) = (start + k, start + k + len / 2);
+                let t_re = re[b] * cos - im[b] * sin;
+                let t_im = re[b] * sin + im[b] * cos;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+            }
+        }
+        len <<= 1;
+    }
+}
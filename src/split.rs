@@ -0,0 +1,142 @@
+use crate::disc::{self, Disc, TitleReport, TitleResult};
+use crate::status::StatusFile;
+use crate::{chapters, say, timecode, tracks, warning, webhook, Args, AudioExtractor, ExtractorError};
+use anyhow::Context;
+use std::path::PathBuf;
+
+/// A job of a split input once extracted: its position among the jobs, its
+/// output and the extraction's result
+type JobOutcome = (usize, PathBuf, Result<(), ExtractorError>);
+
+impl AudioExtractor {
+    /// Extract every audio stream of the input into its own file, named with
+    /// the track number and language
+    pub fn extract_all_tracks(&self) -> Result<Vec<Result<PathBuf, ExtractorError>>, ExtractorError> {
+        self.validate_input()?;
+        let tracks = self.audio_tracks()?;
+        if tracks.is_empty() {
+            return Err(anyhow::anyhow!("{:?} has no audio tracks", self.args.input).into());
+        }
+        say!("🎧 Found {} audio track(s) in {:?}", tracks.len(), self.args.input);
+
+        let mut jobs = Vec::new();
+        for track in tracks {
+            let args = Args {
+                output: tracks::track_output(&self.args.output, &track),
+                audio_track: Some(track.index),
+                all_tracks: false,
+                ..self.args.clone()
+            };
+            jobs.push((self.for_input(args).with_expanded_output()?, format!("Track {}", track)));
+        }
+        let outcomes = Self::run_jobs("tracks", jobs)?;
+        Ok(outcomes.into_iter().map(|(_, output, result)| result.map(|()| output)).collect())
+    }
+
+    /// Extract each chapter of the input into its own file, named with the
+    /// chapter number and title. Chapters get their title and position as
+    /// tags unless --tag-title or --track set them.
+    pub fn extract_chapters(&self) -> Result<Vec<Result<PathBuf, ExtractorError>>, ExtractorError> {
+        self.validate_input()?;
+        let chapters = self.chapters()?;
+        if chapters.is_empty() {
+            return Err(anyhow::anyhow!("{:?} has no chapters", self.args.input).into());
+        }
+        say!("📖 Found {} chapter(s) in {:?}", chapters.len(), self.args.input);
+
+        let total = chapters.len();
+        let mut jobs = Vec::new();
+        for chapter in chapters {
+            let args = Args {
+                output: chapters::chapter_output(&self.args.output, &chapter),
+                start: Some(chapter.start),
+                end: Some(chapter.end),
+                tag_title: self.args.tag_title.clone().or(chapter.title.clone()),
+                track: self.args.track.clone().or(Some(format!("{}/{}", chapter.index, total))),
+                split_chapters: false,
+                ..self.args.clone()
+            };
+            let label = format!(
+                "Chapter {} ({} - {})",
+                chapter.index,
+                timecode::format_timestamp(chapter.start),
+                timecode::format_timestamp(chapter.end)
+            );
+            jobs.push((self.for_input(args).with_expanded_output()?, label));
+        }
+        let outcomes = Self::run_jobs("chapters", jobs)?;
+        Ok(outcomes.into_iter().map(|(_, output, result)| result.map(|()| output)).collect())
+    }
+
+    /// Extract every title of a disc input into its own file, named from
+    /// --title-template, and write a report of all titles found next to them
+    pub fn extract_all_titles(&self) -> Result<TitleReport, ExtractorError> {
+        self.validate_input()?;
+        let disc = Disc::detect(&self.args.input)
+            .with_context(|| format!("--all-titles needs a disc input, not {:?}", self.args.input))?;
+
+        let titles = disc.titles(&self.resource_limits())?;
+        if titles.is_empty() {
+            return Err(anyhow::anyhow!("No readable titles found on {:?}", disc.path).into());
+        }
+        say!("📀 Found {} title(s) on {:?}", titles.len(), disc.path);
+
+        let template = self.args.title_template.as_deref().unwrap_or(disc::DEFAULT_TITLE_TEMPLATE);
+        let mut jobs = Vec::new();
+        for title in &titles {
+            let args = Args {
+                output: disc::title_output(&self.args.output, template, title.index)?,
+                title: Some(title.index),
+                all_titles: false,
+                ..self.args.clone()
+            };
+            let label = format!("Title {} ({})", title.index, timecode::format_timestamp(title.duration));
+            jobs.push((self.for_input(args).with_expanded_output()?, label));
+        }
+        let results = Self::run_jobs("titles", jobs)?
+            .into_iter()
+            .map(|(job, output, result)| TitleResult {
+                index: titles[job].index,
+                duration: titles[job].duration,
+                output,
+                success: result.is_ok(),
+                error: result.err().map(|e| format!("{:#}", e)),
+            })
+            .collect();
+
+        let report = TitleReport { disc: disc.path, kind: disc.kind, titles: results };
+        let report_path = TitleReport::path_for(&self.args.output);
+        report.write(&report_path)?;
+        say!("Title report written to {:?}", report_path);
+        Ok(report)
+    }
+
+    /// Extract the jobs of a split input one after another, as one batch of
+    /// `kind` in the status file. Each job's label names it in messages.
+    /// Jobs the hook skips are left out of the outcomes; failures are also
+    /// reported to the webhooks.
+    fn run_jobs(kind: &str, jobs: Vec<(AudioExtractor, String)>) -> Result<Vec<JobOutcome>, ExtractorError> {
+        let mut outcomes = Vec::new();
+        let status = StatusFile::new(kind, jobs.len());
+        let webhooks = Self::webhooks();
+        for (job, (mut extractor, label)) in jobs.into_iter().enumerate() {
+            if let Some(reason) = extractor.run_hook()? {
+                say!("⏭ Hook skipped {}: {}", label, reason);
+                continue;
+            }
+            let output = extractor.args.output.clone();
+            say!("{} → {:?}", label, output);
+            status.started(&output);
+            let result = extractor.extract();
+            status.done(&output, result.is_ok());
+            if let Err(e) = &result {
+                warning!("⚠ {} failed: {}", label, e);
+                webhook::input_failed(&webhooks, &output, e);
+            }
+            outcomes.push((job, output, result));
+        }
+
+        status.finish();
+        Ok(outcomes)
+    }
+}
@@ -0,0 +1,144 @@
+use crate::preflight::{format_size, free_space};
+use crate::say;
+use crate::status::process_alive;
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+/// Prefix of the directories inputs are staged in, followed by the pid of
+/// the process that staged them
+const STAGE_PREFIX: &str = "audio_extractor-staged-";
+
+/// Distinguishes the stage directories of one process
+static STAGE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Filesystem types of network mounts, as `statfs` reports them on Linux
+#[cfg(target_os = "linux")]
+const NETWORK_FILESYSTEMS: [u32; 9] = [
+    0x6969,      // NFS
+    0x517B,      // SMB
+    0xFF53_4D42, // CIFS
+    0xFE53_4D42, // SMB2
+    0x0102_1997, // 9P, e.g. Windows drives under WSL
+    0x00C3_6400, // Ceph
+    0x5346_414F, // AFS
+    0x7375_7245, // Coda
+    0x564C,      // NCP
+];
+
+/// Whether `path` is on a network filesystem (NFS, SMB/CIFS and the like).
+/// FUSE mounts such as sshfs are not told apart from local FUSE filesystems.
+#[cfg(target_os = "linux")]
+pub fn is_network_path(path: &Path) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+
+    let Ok(path) = std::ffi::CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    let mut stat: libc::statfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statfs(path.as_ptr(), &mut stat) } != 0 {
+        return false;
+    }
+    NETWORK_FILESYSTEMS.contains(&(stat.f_type as u32))
+}
+
+#[cfg(target_os = "macos")]
+pub fn is_network_path(path: &Path) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+
+    let Ok(path) = std::ffi::CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    let mut stat: libc::statfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statfs(path.as_ptr(), &mut stat) } != 0 {
+        return false;
+    }
+    let name = unsafe { std::ffi::CStr::from_ptr(stat.f_fstypename.as_ptr()) };
+    matches!(name.to_bytes(), b"nfs" | b"smbfs" | b"afpfs" | b"webdav" | b"cifs")
+}
+
+/// Only UNC paths (`\\server\share\...`) are recognized; mapped drive
+/// letters are not
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn is_network_path(path: &Path) -> bool {
+    path.to_string_lossy().starts_with(r"\\")
+}
+
+/// A copy of an input in a local scratch directory, removed when dropped
+#[derive(Debug)]
+pub struct StagedInput {
+    path: PathBuf,
+    dir: PathBuf,
+}
+
+impl StagedInput {
+    /// Copy `input` into a directory of its own under `scratch`. Fails,
+    /// leaving nothing behind, if `scratch` lacks the space or the copy
+    /// comes out a different size. Stage directories of processes that
+    /// exited without removing them are cleared out first.
+    pub fn copy(input: &Path, scratch: &Path) -> Result<Self> {
+        remove_stale(scratch);
+
+        let size = fs::metadata(input).with_context(|| format!("Failed to read {:?}", input))?.len();
+        if let Some(free) = free_space(scratch).filter(|&free| free < size) {
+            anyhow::bail!(
+                "{:?} needs {} but only {} is free in {:?}",
+                input, format_size(size), format_size(free), scratch
+            );
+        }
+
+        // Absolute, so the path stays right for FFmpeg in --ffmpeg-workdir
+        let dir = std::path::absolute(scratch)?.join(format!(
+            "{}{}-{}",
+            STAGE_PREFIX,
+            std::process::id(),
+            STAGE_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&dir).with_context(|| format!("Failed to create {:?}", dir))?;
+        // From here on, dropping the stage removes the directory
+        let staged = Self { path: dir.join(input.file_name().context("Input has no file name")?), dir };
+
+        say!("📥 Copying {:?} ({}) to {:?}...", input, format_size(size), staged.dir);
+        let started = Instant::now();
+        let copied = fs::copy(input, &staged.path).with_context(|| format!("Failed to copy {:?}", input))?;
+        if copied != size {
+            anyhow::bail!("Copy of {:?} is {} bytes but the input is {} bytes", input, copied, size);
+        }
+        say!("📥 Copied in {:.1} seconds", started.elapsed().as_secs_f64());
+        Ok(staged)
+    }
+
+    /// The local copy
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for StagedInput {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Remove stage directories in `scratch` left by processes that are gone
+fn remove_stale(scratch: &Path) {
+    let Ok(entries) = fs::read_dir(scratch) else {
+        return;
+    };
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let name = entry.file_name();
+        let Some(pid) = name
+            .to_str()
+            .and_then(|name| name.strip_prefix(STAGE_PREFIX))
+            .and_then(|rest| rest.split('-').next())
+            .and_then(|pid| pid.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        if !process_alive(pid) {
+            let _ = fs::remove_dir_all(entry.path());
+        }
+    }
+}
@@ -0,0 +1,187 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Progress of a running or finished batch, as shown by `audio_extractor status`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BatchStatus {
+    pub pid: u32,
+    /// What kind of batch this is: `scan`, `titles` or `coordinator`
+    pub kind: String,
+    pub started_at: String,
+    pub updated_at: String,
+    pub total: usize,
+    pub completed: usize,
+    pub failed: usize,
+    /// Inputs being encoded right now
+    pub current: Vec<PathBuf>,
+    pub finished: bool,
+}
+
+impl BatchStatus {
+    pub fn remaining(&self) -> usize {
+        self.total.saturating_sub(self.completed + self.failed)
+    }
+
+    /// Whether the batch is still going: not finished, and its process is alive
+    pub fn is_running(&self) -> bool {
+        !self.finished && process_alive(self.pid)
+    }
+}
+
+impl fmt::Display for BatchStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = if self.finished {
+            "finished"
+        } else if self.is_running() {
+            "running"
+        } else {
+            "interrupted"
+        };
+        writeln!(f, "{} batch (pid {}) {}, started {}", self.kind, self.pid, state, self.started_at)?;
+        writeln!(
+            f,
+            "  {} completed, {} failed, {} remaining of {}",
+            self.completed, self.failed, self.remaining(), self.total
+        )?;
+        for input in &self.current {
+            writeln!(f, "  encoding {}", input.display())?;
+        }
+        write!(f, "  last update {}", self.updated_at)
+    }
+}
+
+/// Directory batches write their status files to, one `<pid>.json` per run
+pub fn status_dir() -> PathBuf {
+    std::env::temp_dir().join("audio_extractor-status")
+}
+
+/// Status file kept up to date while a batch runs.
+///
+/// Status is advisory, so failing to write it never fails the batch.
+#[derive(Debug)]
+pub struct StatusFile {
+    path: PathBuf,
+    status: Mutex<BatchStatus>,
+}
+
+impl StatusFile {
+    /// Start tracking a batch of `total` inputs in `status_dir()`, clearing out
+    /// files left behind by batches that are no longer running
+    pub fn new(kind: &str, total: usize) -> Self {
+        let dir = status_dir();
+        if let Ok(statuses) = read_all(&dir) {
+            for (path, status) in statuses {
+                if !status.is_running() {
+                    let _ = fs::remove_file(path);
+                }
+            }
+        }
+        Self::at(dir.join(format!("{}.json", std::process::id())), kind, total)
+    }
+
+    pub fn at(path: PathBuf, kind: &str, total: usize) -> Self {
+        let now = chrono::Utc::now().to_rfc3339();
+        let file = Self {
+            path,
+            status: Mutex::new(BatchStatus {
+                pid: std::process::id(),
+                kind: kind.to_string(),
+                started_at: now.clone(),
+                updated_at: now,
+                total,
+                completed: 0,
+                failed: 0,
+                current: Vec::new(),
+                finished: false,
+            }),
+        };
+        file.update(|_| {});
+        file
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn started(&self, input: &Path) {
+        self.update(|status| status.current.push(input.to_path_buf()));
+    }
+
+    pub fn done(&self, input: &Path, success: bool) {
+        self.update(|status| {
+            status.current.retain(|current| current != input);
+            if success {
+                status.completed += 1;
+            } else {
+                status.failed += 1;
+            }
+        });
+    }
+
+    /// An input handed back unfinished, e.g. by a worker that disconnected
+    pub fn requeued(&self, input: &Path) {
+        self.update(|status| status.current.retain(|current| current != input));
+    }
+
+    pub fn finish(&self) {
+        self.update(|status| {
+            status.current.clear();
+            status.finished = true;
+        });
+    }
+
+    fn update(&self, change: impl FnOnce(&mut BatchStatus)) {
+        let mut status = self.status.lock().unwrap();
+        change(&mut status);
+        status.updated_at = chrono::Utc::now().to_rfc3339();
+        let _ = write(&self.path, &status);
+    }
+}
+
+/// Replace the file in one step, so readers never see a partial status
+fn write(path: &Path, status: &BatchStatus) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut partial = path.as_os_str().to_os_string();
+    partial.push(".part");
+    fs::write(&partial, serde_json::to_vec_pretty(status)?)?;
+    fs::rename(&partial, path)?;
+    Ok(())
+}
+
+pub fn read(path: &Path) -> Result<BatchStatus> {
+    let content = fs::read_to_string(path).with_context(|| format!("Failed to read status file {:?}", path))?;
+    serde_json::from_str(&content).with_context(|| format!("Invalid status file {:?}", path))
+}
+
+/// Every status file in `dir`, oldest batch first
+pub fn read_all(dir: &Path) -> Result<Vec<(PathBuf, BatchStatus)>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut statuses: Vec<_> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|e| e == "json"))
+        .filter_map(|path| read(&path).ok().map(|status| (path, status)))
+        .collect();
+    statuses.sort_by(|a, b| a.1.started_at.cmp(&b.1.started_at));
+    Ok(statuses)
+}
+
+#[cfg(unix)]
+pub(crate) fn process_alive(pid: u32) -> bool {
+    // Signal 0 only checks that the process exists; EPERM means it does but belongs to someone else
+    let result = unsafe { libc::kill(pid as libc::pid_t, 0) };
+    result == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(not(unix))]
+pub(crate) fn process_alive(_pid: u32) -> bool {
+    true
+}
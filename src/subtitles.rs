@@ -0,0 +1,76 @@
+use crate::timeline::Timeline;
+use anyhow::{Context, Result};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// One caption, with its times in seconds
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cue {
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+}
+
+/// Path of the re-timed subtitles for an output: `talk.mp3` gets `talk.srt`,
+/// which players pick up next to it
+pub fn output_path(output: &Path) -> PathBuf {
+    output.with_extension("srt")
+}
+
+/// Cues of SRT or WebVTT text. Numbering, the WEBVTT header, NOTE and STYLE
+/// blocks and cue settings are skipped.
+pub fn parse(text: &str) -> Result<Vec<Cue>> {
+    let mut cues = Vec::new();
+    let text = text.trim_start_matches('\u{feff}').replace("\r\n", "\n");
+    for block in text.split("\n\n") {
+        let mut lines = block.lines().skip_while(|line| !line.contains("-->"));
+        let Some(timing) = lines.next() else {
+            continue;
+        };
+        let (start, rest) = timing.split_once("-->").unwrap();
+        let end = rest.split_whitespace().next().unwrap_or_default();
+        let cue = Cue {
+            start: parse_time(start).with_context(|| format!("Invalid cue timing: {:?}", timing))?,
+            end: parse_time(end).with_context(|| format!("Invalid cue timing: {:?}", timing))?,
+            text: lines.collect::<Vec<_>>().join("\n"),
+        };
+        cues.push(cue);
+    }
+    Ok(cues)
+}
+
+/// `HH:MM:SS,mmm` as SRT writes it, or `MM:SS.mmm` as WebVTT may
+fn parse_time(time: &str) -> Option<f64> {
+    let time = time.trim().replace(',', ".");
+    let mut seconds = 0.0;
+    for part in time.split(':') {
+        seconds = seconds * 60.0 + part.parse::<f64>().ok()?;
+    }
+    Some(seconds)
+}
+
+/// `HH:MM:SS,mmm`
+pub fn format_time(seconds: f64) -> String {
+    let millis = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!("{:02}:{:02}:{:02},{:03}", millis / 3_600_000, millis / 60_000 % 60, millis / 1000 % 60, millis % 1000)
+}
+
+/// The cues as SRT, numbered from 1
+pub fn to_srt(cues: &[Cue]) -> String {
+    let mut srt = String::new();
+    for (index, cue) in cues.iter().enumerate() {
+        let _ = write!(srt, "{}\n{} --> {}\n{}\n\n", index + 1, format_time(cue.start), format_time(cue.end), cue.text);
+    }
+    srt
+}
+
+/// Move cues of the source onto an edited output's time. A cue that lost
+/// part of its time to a cut is shortened to what is left, and one that
+/// was cut entirely is dropped.
+pub fn retime(cues: &[Cue], timeline: &Timeline) -> Vec<Cue> {
+    cues.iter().filter_map(|cue| {
+        let ranges = timeline.output_ranges(cue.start, cue.end);
+        let (first, last) = (ranges.first()?, ranges.last()?);
+        Some(Cue { start: first.start, end: last.end, text: cue.text.clone() })
+    }).collect()
+}
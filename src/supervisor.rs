@@ -0,0 +1,262 @@
+use crate::status::process_alive;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{mpsc, OnceLock};
+use tokio::sync::oneshot;
+
+/// An FFmpeg or ffprobe process started by this tool, as listed by `audio_extractor ps`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TrackedProcess {
+    pub pid: u32,
+    /// The audio_extractor process that started it
+    pub parent: u32,
+    pub started_at: String,
+    pub command: String,
+    /// When the kernel started it, in clock ticks since boot (Linux), so
+    /// a later process given the same pid is not mistaken for it
+    #[serde(default)]
+    pub start_time: Option<u64>,
+}
+
+impl TrackedProcess {
+    /// Whether the process is still running and still the one that was
+    /// registered, not another that has since been given its pid
+    pub fn is_running(&self) -> bool {
+        process_alive(self.pid) && self.is_same_process()
+    }
+
+    fn is_same_process(&self) -> bool {
+        match (self.start_time, start_time(self.pid)) {
+            (Some(registered), Some(current)) => registered == current,
+            (Some(_), None) => false,
+            // Registered without a start time: compare the command line instead
+            (None, _) => cmdline(self.pid).is_none_or(|cmdline| cmdline == self.command),
+        }
+    }
+
+    /// Whether it outlived the process that started it and nothing will wait for it
+    pub fn is_orphaned(&self) -> bool {
+        !process_alive(self.parent)
+    }
+}
+
+impl fmt::Display for TrackedProcess {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = if self.is_orphaned() { "orphaned" } else { "running" };
+        write!(f, "{:>7}  parent {:>7}  {:<8}  {}  {}", self.pid, self.parent, state, self.started_at, self.command)
+    }
+}
+
+/// Directory spawned processes are registered in, one `<pid>.json` each.
+/// It belongs to the user, so other users cannot plant entries that `kill`
+/// would act on: `$XDG_RUNTIME_DIR` when set, else a directory of the
+/// user's own in the temporary directory.
+#[cfg(unix)]
+pub fn process_dir() -> PathBuf {
+    match std::env::var_os("XDG_RUNTIME_DIR").filter(|dir| !dir.is_empty()) {
+        Some(runtime) => PathBuf::from(runtime).join("audio_extractor-processes"),
+        None => std::env::temp_dir().join(format!("audio_extractor-processes-{}", unsafe { libc::geteuid() })),
+    }
+}
+
+/// The temporary directory is already the user's own on Windows
+#[cfg(not(unix))]
+pub fn process_dir() -> PathBuf {
+    std::env::temp_dir().join("audio_extractor-processes")
+}
+
+/// Create `dir` readable only by us, or check that the existing one is
+#[cfg(unix)]
+fn private_dir(dir: &Path) -> Result<()> {
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+
+    fs::DirBuilder::new().recursive(true).mode(0o700).create(dir)?;
+    let metadata = fs::symlink_metadata(dir)?;
+    if !metadata.is_dir() || metadata.uid() != unsafe { libc::geteuid() } || metadata.mode() & 0o077 != 0 {
+        anyhow::bail!("Refusing to use {:?}: it is not a directory only this user can access", dir);
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn private_dir(dir: &Path) -> Result<()> {
+    fs::create_dir_all(dir)?;
+    Ok(())
+}
+
+/// Make the process die with us. On Linux the kernel sends it SIGKILL once
+/// the thread that spawned it exits, so a crashed or killed parent leaves no
+/// encode behind; the threads that spawn FFmpeg wait for it, and async code
+/// spawns through `spawn_tokio`'s thread, which lives as long as the process,
+/// so this only fires when the parent dies. Elsewhere, leftovers are found
+/// with `audio_extractor ps` and removed with `kill --orphans`.
+#[cfg(target_os = "linux")]
+pub fn apply(cmd: &mut Command) {
+    use std::os::unix::process::CommandExt;
+
+    let parent = std::process::id() as libc::pid_t;
+    // SAFETY: the closure only calls prctl and getppid, which are async-signal-safe
+    unsafe {
+        cmd.pre_exec(move || {
+            if libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            // The parent may have died before the signal was armed
+            if libc::getppid() != parent {
+                return Err(std::io::Error::other("parent process exited"));
+            }
+            Ok(())
+        });
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn apply(_cmd: &mut Command) {}
+
+/// Record a freshly spawned child in `process_dir()`. Tracking is advisory,
+/// so failing to write the entry never fails the job.
+pub fn register(pid: u32, cmd: &Command) {
+    let process = TrackedProcess {
+        pid,
+        parent: std::process::id(),
+        started_at: chrono::Utc::now().to_rfc3339(),
+        command: command_line(cmd),
+        start_time: start_time(pid),
+    };
+    let _ = write(&process_dir(), &process);
+}
+
+/// A tokio command to spawn, the runtime it belongs to, and where to send the child
+type SpawnRequest = (tokio::process::Command, tokio::runtime::Handle, oneshot::Sender<io::Result<tokio::process::Child>>);
+
+/// Spawn a tokio child and register it. The child is spawned from a thread
+/// kept for this, not from whichever runtime thread polls the caller: the
+/// death signal `apply` arms follows the spawning thread, and tokio retires
+/// its threads while tasks live on, which would kill a running encode.
+pub(crate) async fn spawn_tokio(cmd: tokio::process::Command) -> io::Result<tokio::process::Child> {
+    static SPAWNER: OnceLock<mpsc::Sender<SpawnRequest>> = OnceLock::new();
+    let spawner = SPAWNER.get_or_init(|| {
+        let (sender, requests) = mpsc::channel::<SpawnRequest>();
+        std::thread::Builder::new()
+            .name("audio_extractor-spawner".to_string())
+            .spawn(move || {
+                for (mut cmd, runtime, reply) in requests {
+                    // The child is reaped by the caller's runtime
+                    let _runtime = runtime.enter();
+                    let child = cmd.spawn();
+                    if let Some(pid) = child.as_ref().ok().and_then(|child| child.id()) {
+                        register(pid, cmd.as_std());
+                    }
+                    let _ = reply.send(child);
+                }
+            })
+            .expect("failed to start the process spawner thread");
+        sender
+    });
+
+    let (reply, child) = oneshot::channel();
+    spawner
+        .send((cmd, tokio::runtime::Handle::current(), reply))
+        .map_err(|_| io::Error::other("process spawner thread is gone"))?;
+    child.await.map_err(|_| io::Error::other("process spawner thread is gone"))?
+}
+
+/// Forget a child once it has been waited for
+pub fn unregister(pid: u32) {
+    let _ = fs::remove_file(process_dir().join(format!("{}.json", pid)));
+}
+
+/// The program and its arguments, as one line
+pub fn command_line(cmd: &Command) -> String {
+    std::iter::once(cmd.get_program())
+        .chain(cmd.get_args())
+        .map(|arg| arg.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// When `pid` started, in clock ticks since boot: field 22 of
+/// `/proc/<pid>/stat`. `None` where there is no /proc.
+pub fn start_time(pid: u32) -> Option<u64> {
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // The command name in field 2 is in parentheses and may itself contain
+    // spaces and parentheses, so count from the last closing one, after
+    // which field 3 comes
+    let (_, rest) = stat.rsplit_once(')')?;
+    rest.split_whitespace().nth(22 - 3)?.parse().ok()
+}
+
+/// The command line of `pid` as `command_line` writes it, from
+/// `/proc/<pid>/cmdline`. `None` where there is no /proc.
+fn cmdline(pid: u32) -> Option<String> {
+    let cmdline = fs::read(format!("/proc/{}/cmdline", pid)).ok()?;
+    Some(
+        cmdline
+            .split(|&byte| byte == 0)
+            .filter(|arg| !arg.is_empty())
+            .map(String::from_utf8_lossy)
+            .collect::<Vec<_>>()
+            .join(" "),
+    )
+}
+
+fn write(dir: &Path, process: &TrackedProcess) -> Result<()> {
+    private_dir(dir)?;
+    fs::write(dir.join(format!("{}.json", process.pid)), serde_json::to_vec_pretty(process)?)?;
+    Ok(())
+}
+
+/// Every tracked process in `dir` that is still running, oldest first.
+/// Entries of processes that have exited, or whose pid now belongs to
+/// another process, are removed.
+pub fn list(dir: &Path) -> Result<Vec<TrackedProcess>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    private_dir(dir)?;
+    let mut processes = Vec::new();
+    for path in fs::read_dir(dir)?.filter_map(|entry| entry.ok()).map(|entry| entry.path()) {
+        if path.extension().is_none_or(|e| e != "json") {
+            continue;
+        }
+        let Some(process) = fs::read_to_string(&path).ok().and_then(|content| serde_json::from_str::<TrackedProcess>(&content).ok()) else {
+            continue;
+        };
+        if process.is_running() {
+            processes.push(process);
+        } else {
+            let _ = fs::remove_file(&path);
+        }
+    }
+    processes.sort_by(|a, b| a.started_at.cmp(&b.started_at));
+    Ok(processes)
+}
+
+/// Stop a tracked process: SIGTERM lets FFmpeg finish the file it is
+/// writing, `force` sends SIGKILL. The pid is checked to still be the tracked
+/// process right before it is signalled.
+pub fn kill(process: &TrackedProcess, force: bool) -> Result<()> {
+    if !process.is_running() {
+        anyhow::bail!("Process {} has exited", process.pid);
+    }
+    kill_pid(process.pid, force)
+}
+
+#[cfg(unix)]
+pub(crate) fn kill_pid(pid: u32, force: bool) -> Result<()> {
+    let signal = if force { libc::SIGKILL } else { libc::SIGTERM };
+    if unsafe { libc::kill(pid as libc::pid_t, signal) } != 0 {
+        return Err(std::io::Error::last_os_error()).with_context(|| format!("Failed to kill process {}", pid));
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub(crate) fn kill_pid(pid: u32, _force: bool) -> Result<()> {
+    anyhow::bail!("Killing process {} is only supported on Unix", pid)
+}
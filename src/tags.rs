@@ -0,0 +1,67 @@
+use crate::AudioFormat;
+use anyhow::Result;
+use serde::Serialize;
+
+/// Tags to write into the output, replacing those copied from the source
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct Tags {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    /// Track number, alone or with the total such as `3/12`
+    pub track: Option<String>,
+}
+
+impl Tags {
+    pub fn is_empty(&self) -> bool {
+        self.named().is_empty()
+    }
+
+    /// The tags set, named as `native::standard_tags` names them
+    pub fn named(&self) -> Vec<(&'static str, String)> {
+        [("title", &self.title), ("artist", &self.artist), ("album", &self.album), ("track", &self.track)]
+            .into_iter()
+            .filter_map(|(name, value)| Some((name, value.clone()?)))
+            .collect()
+    }
+
+    /// `tags` from the source with these set over them, keeping the
+    /// source's order and adding the rest at the end
+    pub fn apply(&self, mut tags: Vec<(&'static str, String)>) -> Vec<(&'static str, String)> {
+        for (name, value) in self.named() {
+            match tags.iter_mut().find(|(existing, _)| *existing == name) {
+                Some(tag) => tag.1 = value,
+                None => tags.push((name, value)),
+            }
+        }
+        tags
+    }
+
+    /// FFmpeg `-metadata` options for these tags. FFmpeg turns the generic
+    /// names into ID3v2 frames, Vorbis comments, MP4 atoms or RIFF INFO
+    /// fields for `format`'s muxer.
+    pub fn ffmpeg_args(&self, format: &AudioFormat) -> Vec<String> {
+        let mut args = Vec::new();
+        for (name, value) in self.named() {
+            args.extend(["-metadata".to_string(), format!("{}={}", name, value)]);
+            // The AIFF muxer fills its AUTH chunk from `author`
+            if *format == AudioFormat::Aiff && name == "artist" {
+                args.extend(["-metadata".to_string(), format!("author={}", value)]);
+            }
+        }
+        args
+    }
+}
+
+/// Parse a --track value: a number, or a number and the total such as `3/12`
+pub fn parse_track(s: &str) -> Result<String> {
+    let number = |part: &str| part.trim().parse::<u32>().ok().filter(|n| *n > 0);
+    let valid = match s.split_once('/') {
+        Some((track, total)) => number(track).zip(number(total)).is_some_and(|(track, total)| track <= total),
+        None => number(s).is_some(),
+    };
+    if !valid {
+        anyhow::bail!("'{}' is not a track number like 3 or 3/12", s);
+    }
+    Ok(s.replace(' ', ""))
+}
@@ -0,0 +1,296 @@
+use crate::metadata::SourceMetadata;
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use clap::ValueEnum;
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+
+/// Value used for metadata fields the source does not have
+pub const UNKNOWN: &str = "Unknown";
+
+/// Longest file or folder name common filesystems allow, in bytes
+pub const MAX_NAME_LEN: usize = 255;
+
+/// Longest path the platform allows, in bytes
+#[cfg(windows)]
+pub const MAX_PATH_LEN: usize = 259;
+#[cfg(not(windows))]
+pub const MAX_PATH_LEN: usize = 4095;
+
+/// Names are never cut shorter than this to make a path fit
+const MIN_SHORTENED_LEN: usize = 24;
+
+/// Hex digits of the name hash that keeps shortened names apart
+const HASH_LEN: usize = 8;
+
+/// Whether a path contains `{field}` placeholders
+pub fn has_fields(path: &Path) -> bool {
+    let path = path.to_string_lossy();
+    path.find('{').is_some_and(|open| path[open..].contains('}'))
+}
+
+/// Replace each `{field}` in `template` with its value, in a single pass so
+/// values are never expanded themselves. Unknown fields are an error.
+pub fn expand(template: &str, fields: &BTreeMap<&str, String>) -> Result<String> {
+    let mut expanded = String::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        let Some(close) = rest[open..].find('}').map(|i| open + i) else {
+            break;
+        };
+        let name = &rest[open + 1..close];
+        let value = fields.get(name).ok_or_else(|| {
+            let known: Vec<_> = fields.keys().map(|k| format!("{{{}}}", k)).collect();
+            anyhow::anyhow!("Unknown field {{{}}} in '{}'; use one of {}", name, template, known.join(", "))
+        })?;
+        expanded.push_str(&rest[..open]);
+        expanded.push_str(value);
+        rest = &rest[close + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// Names of the `{field}` placeholders in `template`, in order
+pub fn field_names(template: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        let Some(close) = rest[open..].find('}').map(|i| open + i) else {
+            break;
+        };
+        names.push(&rest[open + 1..close]);
+        rest = &rest[close + 1..];
+    }
+    names
+}
+
+/// Fields available to output templates without probing the source: the
+/// input's `{stem}` and `{parent}` folder, the output's `{format}` and
+/// `{bitrate}` in kbps, and the `{date}` of the extraction
+pub fn file_fields(input: &Path, format: &str, bitrate: u32) -> BTreeMap<&'static str, String> {
+    // A bare file name's parent is the working directory
+    let absolute = std::path::absolute(input).unwrap_or_else(|_| input.to_path_buf());
+    let parent = absolute.parent().and_then(|p| p.file_name()).map(|n| path_safe(&n.to_string_lossy()));
+    let fields = [
+        ("stem", input.file_stem().map(|s| path_safe(&s.to_string_lossy()))),
+        ("parent", parent),
+        ("format", Some(format.to_string())),
+        ("bitrate", Some(bitrate.to_string())),
+        ("date", Some(chrono::Local::now().format("%Y-%m-%d").to_string())),
+    ];
+    fields
+        .into_iter()
+        .map(|(name, value)| (name, value.filter(|v| !v.is_empty()).unwrap_or_else(|| UNKNOWN.to_string())))
+        .collect()
+}
+
+/// Fields available to output templates, from the source's metadata.
+///
+/// Values are made safe to use as a single path component; missing ones
+/// become `Unknown`, except `{title}` which falls back to the input's name.
+pub fn metadata_fields(metadata: &SourceMetadata, input: &Path) -> BTreeMap<&'static str, String> {
+    let stem = input.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let number = |n: Option<u32>| n.map(|n| format!("{:02}", n));
+    let year = metadata.recorded_date.as_deref().map(|date| date[..4].to_string());
+
+    let fields = [
+        ("title", metadata.title.clone().or(Some(stem.clone()))),
+        ("artist", metadata.artist.clone()),
+        ("album", metadata.album.clone()),
+        ("show", metadata.show.clone()),
+        ("season", number(metadata.season)),
+        ("episode", number(metadata.episode)),
+        ("genre", metadata.genre.clone()),
+        ("recorded_date", metadata.recorded_date.clone()),
+        ("year", year),
+        ("stem", Some(stem)),
+    ];
+    fields
+        .into_iter()
+        .map(|(name, value)| {
+            let value = value.map(|v| path_safe(&v)).filter(|v| !v.is_empty());
+            (name, value.unwrap_or_else(|| UNKNOWN.to_string()))
+        })
+        .collect()
+}
+
+/// Expand an output path template such as `/library/{show}/Season {season}/{title}.mp3`
+pub fn expand_path(template: &Path, fields: &BTreeMap<&str, String>) -> Result<PathBuf> {
+    Ok(PathBuf::from(expand(&template.to_string_lossy(), fields)?))
+}
+
+/// The leading directories of a template, before the first placeholder
+pub fn static_prefix(template: &Path) -> PathBuf {
+    template
+        .components()
+        .take_while(|component| !has_fields(Path::new(component.as_os_str())))
+        .filter(|component| !matches!(component, Component::CurDir))
+        .collect()
+}
+
+/// Replace characters that are not allowed in file names, so a value can
+/// not add directories or escape the template
+fn path_safe(value: &str) -> String {
+    let cleaned: String = value
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    cleaned.trim().trim_start_matches('.').trim().to_string()
+}
+
+/// Filesystem rules for `--sanitize-names`
+#[derive(Clone, Copy, ValueEnum, Debug, PartialEq)]
+pub enum NameProfile {
+    /// Names Windows accepts: no reserved device names, no trailing dots or spaces
+    Windows,
+    /// Windows rules, and no emoji or other characters many FAT32 devices cannot show
+    Fat32,
+    /// Only ASCII letters, digits, spaces and `-_.()`; accents are dropped
+    Ascii,
+}
+
+/// Device names Windows reserves, with or without an extension
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9", "LPT1",
+    "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Clean every field value for the given filesystem
+pub fn sanitize_fields(fields: &mut BTreeMap<&str, String>, profile: NameProfile) {
+    for value in fields.values_mut() {
+        *value = sanitize(value, profile);
+    }
+}
+
+/// Clean each component of a relative path, keeping its extension
+pub fn sanitize_path(path: &Path, profile: NameProfile) -> PathBuf {
+    path.components()
+        .map(|component| match component {
+            Component::Normal(name) => sanitize(&name.to_string_lossy(), profile),
+            other => other.as_os_str().to_string_lossy().into_owned(),
+        })
+        .collect()
+}
+
+/// Make a single path component valid on the filesystem `profile` describes
+pub fn sanitize(value: &str, profile: NameProfile) -> String {
+    let cleaned: String = match profile {
+        NameProfile::Windows => path_safe(value),
+        NameProfile::Fat32 => path_safe(value)
+            .chars()
+            .filter(|&c| !is_emoji(c))
+            .collect(),
+        NameProfile::Ascii => {
+            let folded: String = path_safe(value).chars().map(fold_to_ascii).collect();
+            // Runs of replaced characters become a single `_`
+            folded.split('_').filter(|part| !part.is_empty()).collect::<Vec<_>>().join("_")
+        }
+    };
+
+    // Windows drops trailing dots and spaces, which can make two names collide
+    let mut cleaned = cleaned.trim_end_matches(['.', ' ']).trim_start().to_string();
+    let stem_len = cleaned.find('.').unwrap_or(cleaned.len());
+    if RESERVED_NAMES.iter().any(|name| cleaned[..stem_len].eq_ignore_ascii_case(name)) {
+        cleaned.insert(stem_len, '_');
+    }
+    if cleaned.is_empty() {
+        cleaned.push('_');
+    }
+    cleaned
+}
+
+/// Emoji and pictographs, which many FAT32 devices cannot show, and the
+/// joiners and variation selectors that combine them
+fn is_emoji(c: char) -> bool {
+    matches!(c as u32, 0x2600..=0x27bf | 0x2b00..=0x2bff | 0x200d | 0xfe0e | 0xfe0f | 0x10000..)
+}
+
+/// Closest ASCII character, or `_`
+fn fold_to_ascii(c: char) -> char {
+    const FOLDS: [(&str, char); 16] = [
+        ("àáâãäåā", 'a'),
+        ("ÀÁÂÃÄÅĀ", 'A'),
+        ("çč", 'c'),
+        ("ÇČ", 'C'),
+        ("èéêëē", 'e'),
+        ("ÈÉÊËĒ", 'E'),
+        ("ìíîïī", 'i'),
+        ("ÌÍÎÏĪ", 'I'),
+        ("ñ", 'n'),
+        ("Ñ", 'N'),
+        ("òóôõöøō", 'o'),
+        ("ÒÓÔÕÖØŌ", 'O'),
+        ("šś", 's'),
+        ("ŠŚ", 'S'),
+        ("ùúûüū", 'u'),
+        ("ÙÚÛÜŪ", 'U'),
+    ];
+    match c {
+        'A'..='Z' | 'a'..='z' | '0'..='9' | ' ' | '-' | '_' | '.' | '(' | ')' => c,
+        c => FOLDS
+            .iter()
+            .find(|(accented, _)| accented.contains(c))
+            .map_or('_', |&(_, plain)| plain),
+    }
+}
+
+/// Shorten the names below `prefix` in `path` that exceed `MAX_NAME_LEN`, or
+/// that make the whole path longer than `max_len`. Each shortened name keeps
+/// its start and extension and gets `~<hash>` of the full name, so names that
+/// only differ at the end stay apart. Returns `None` if the path already fits.
+pub fn fit_path(path: &Path, prefix: &Path, max_len: usize) -> Result<Option<PathBuf>> {
+    let Ok(relative) = path.strip_prefix(prefix) else {
+        return Ok(None);
+    };
+    let original: Vec<String> = relative.iter().map(|name| name.to_string_lossy().into_owned()).collect();
+    let last = original.len().saturating_sub(1);
+    let mut names: Vec<String> = original
+        .iter()
+        .enumerate()
+        .map(|(index, name)| shorten_name(name, MAX_NAME_LEN, index == last))
+        .collect();
+
+    let joined = |names: &[String]| names.iter().fold(prefix.to_path_buf(), |path, name| path.join(name));
+    loop {
+        let excess = joined(&names).as_os_str().len().saturating_sub(max_len);
+        if excess == 0 {
+            break;
+        }
+        let longest = names.iter().map(String::len).enumerate().max_by_key(|&(_, len)| len);
+        let Some((index, len)) = longest.filter(|&(_, len)| len > MIN_SHORTENED_LEN) else {
+            anyhow::bail!("Output path {:?} is too long for this platform even with shortened names", path);
+        };
+        let target = len.saturating_sub(excess).max(MIN_SHORTENED_LEN);
+        names[index] = shorten_name(&original[index], target, index == last);
+    }
+
+    if names == original {
+        return Ok(None);
+    }
+    Ok(Some(joined(&names)))
+}
+
+/// `name` cut to at most `max_len` bytes with a `~<hash>` suffix, keeping a
+/// file name's extension; names that fit are returned as they are
+fn shorten_name(name: &str, max_len: usize, is_file: bool) -> String {
+    if name.len() <= max_len {
+        return name.to_string();
+    }
+    let (stem, ext) = match name.rfind('.').filter(|&dot| is_file && dot > 0) {
+        Some(dot) => name.split_at(dot),
+        None => (name, ""),
+    };
+    let digest = Sha256::digest(name.as_bytes());
+    let hash: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+
+    let mut keep = max_len.saturating_sub(1 + HASH_LEN + ext.len()).min(stem.len());
+    while !stem.is_char_boundary(keep) {
+        keep -= 1;
+    }
+    format!("{}~{}{}", stem[..keep].trim_end_matches(['.', ' ']), &hash[..HASH_LEN], ext)
+}
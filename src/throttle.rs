@@ -0,0 +1,78 @@
+use anyhow::{Context, Result};
+use chrono::NaiveTime;
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+
+/// Limits for the hours of a `[[throttle]]` table of `config.toml`, so a
+/// machine can keep extracting while people are using it
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Throttle {
+    /// Local time range such as `09:00-18:00`; ranges may cross midnight
+    pub between: TimeWindow,
+    /// Most inputs of a batch extracted at once
+    pub jobs: Option<usize>,
+    /// Niceness FFmpeg runs at, from -20 to 19
+    pub nice: Option<i32>,
+}
+
+/// Local time of day from `start` up to, not including, `end`
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(try_from = "String")]
+pub struct TimeWindow {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl TimeWindow {
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+impl FromStr for TimeWindow {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (start, end) = s.split_once('-').with_context(|| format!("'{}' is not a time range like 09:00-18:00", s))?;
+        let time = |t: &str| {
+            NaiveTime::parse_from_str(t.trim(), "%H:%M").with_context(|| format!("'{}' is not a time like 09:00", t.trim()))
+        };
+        Ok(Self { start: time(start)?, end: time(end)? })
+    }
+}
+
+impl TryFrom<String> for TimeWindow {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+impl fmt::Display for TimeWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.start.format("%H:%M"), self.end.format("%H:%M"))
+    }
+}
+
+/// The first throttle whose hours include `time`, in config order
+pub fn active(throttles: &[Throttle], time: NaiveTime) -> Option<&Throttle> {
+    throttles.iter().find(|throttle| throttle.between.contains(time))
+}
+
+/// The first throttle in force now, by the local clock
+pub fn active_now(throttles: &[Throttle]) -> Option<&Throttle> {
+    active(throttles, chrono::Local::now().time())
+}
+
+/// Jobs allowed at `time` for a batch started with `jobs`
+pub fn jobs_at(throttles: &[Throttle], time: NaiveTime, jobs: usize) -> usize {
+    active(throttles, time)
+        .and_then(|throttle| throttle.jobs)
+        .map_or(jobs, |limit| jobs.min(limit.max(1)))
+}
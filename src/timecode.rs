@@ -0,0 +1,114 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+
+/// Parse a timestamp given as `HH:MM:SS[.mmm]`, `MM:SS[.mmm]` or plain seconds
+pub fn parse_timestamp(value: &str) -> Result<f64> {
+    let value = value.trim();
+    let mut seconds = 0.0;
+    for (i, part) in value.split(':').enumerate() {
+        if i > 2 {
+            anyhow::bail!("Invalid timestamp '{}': too many ':' separated fields", value);
+        }
+        let part: f64 = part
+            .parse()
+            .with_context(|| format!("Invalid timestamp '{}'", value))?;
+        if part < 0.0 || !part.is_finite() {
+            anyhow::bail!("Invalid timestamp '{}'", value);
+        }
+        seconds = seconds * 60.0 + part;
+    }
+    Ok(seconds)
+}
+
+/// Format seconds as `HH:MM:SS.mmm`
+pub fn format_timestamp(seconds: f64) -> String {
+    let millis = (seconds * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+/// A time range within the source, in seconds
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeRange {
+    pub start: f64,
+    pub end: f64,
+}
+
+impl TimeRange {
+    pub fn new(start: f64, end: f64) -> Result<Self> {
+        if end <= start {
+            anyhow::bail!(
+                "Invalid time range {}-{}: end must be after start",
+                format_timestamp(start),
+                format_timestamp(end)
+            );
+        }
+        Ok(Self { start, end })
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.end - self.start
+    }
+}
+
+impl FromStr for TimeRange {
+    type Err = anyhow::Error;
+
+    /// Parse `START-END`, e.g. `00:12:05-00:12:30` or `725-750`
+    fn from_str(value: &str) -> Result<Self> {
+        let (start, end) = value
+            .split_once('-')
+            .with_context(|| format!("Invalid time range '{}': expected START-END", value))?;
+        Self::new(parse_timestamp(start)?, parse_timestamp(end)?)
+    }
+}
+
+impl fmt::Display for TimeRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", format_timestamp(self.start), format_timestamp(self.end))
+    }
+}
+
+/// A timestamp in a JSON file, either seconds or a timestamp string
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonTimestamp {
+    Seconds(f64),
+    Text(String),
+}
+
+impl JsonTimestamp {
+    fn seconds(&self) -> Result<f64> {
+        match self {
+            JsonTimestamp::Seconds(seconds) => Ok(*seconds),
+            JsonTimestamp::Text(text) => parse_timestamp(text),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonRange {
+    Text(String),
+    Object { start: JsonTimestamp, end: JsonTimestamp },
+}
+
+/// Parse a JSON list of ranges, given either as `"START-END"` strings or
+/// `{"start": ..., "end": ...}` objects
+pub fn parse_ranges_json(json: &str) -> Result<Vec<TimeRange>> {
+    let ranges: Vec<JsonRange> = serde_json::from_str(json).context("Invalid time range list")?;
+    ranges
+        .into_iter()
+        .map(|range| match range {
+            JsonRange::Text(text) => text.parse(),
+            JsonRange::Object { start, end } => TimeRange::new(start.seconds()?, end.seconds()?),
+        })
+        .collect()
+}
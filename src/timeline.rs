@@ -0,0 +1,200 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// Frame rate of --edl timecodes when --edl-fps is not given
+pub const DEFAULT_EDL_FPS: f64 = 25.0;
+
+/// A stretch of the output taken from one place in the source, in seconds
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Span {
+    pub output_start: f64,
+    pub source_start: f64,
+    pub duration: f64,
+}
+
+impl Span {
+    pub fn output_end(&self) -> f64 {
+        self.output_start + self.duration
+    }
+
+    pub fn source_end(&self) -> f64 {
+        self.source_start + self.duration
+    }
+}
+
+/// A range of output time, in seconds
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct OutputRange {
+    pub start: f64,
+    pub end: f64,
+}
+
+/// Where each part of an edited output comes from in the source, written
+/// by --timeline so transcripts and subtitles of the source can be moved
+/// onto the output. Cuts, kept segments and trims remove source time;
+/// muted ranges keep their time but lose their audio.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Timeline {
+    pub source: PathBuf,
+    /// The output's stretches, back to back
+    pub spans: Vec<Span>,
+    /// Output time that was silenced
+    #[serde(default)]
+    pub muted: Vec<OutputRange>,
+}
+
+impl Timeline {
+    /// An unedited output of a source `duration` seconds long
+    pub fn new(source: impl Into<PathBuf>, duration: f64) -> Self {
+        Self {
+            source: source.into(),
+            spans: vec![Span { output_start: 0.0, source_start: 0.0, duration }],
+            muted: Vec::new(),
+        }
+    }
+
+    /// Sidecar path for an output, e.g. `talk.mp3.timeline.json`
+    pub fn sidecar_path(output: &Path) -> PathBuf {
+        let mut name = output.as_os_str().to_os_string();
+        name.push(".timeline.json");
+        PathBuf::from(name)
+    }
+
+    /// EDL path for an output, e.g. `talk.mp3.edl`
+    pub fn edl_path(output: &Path) -> PathBuf {
+        let mut name = output.as_os_str().to_os_string();
+        name.push(".edl");
+        PathBuf::from(name)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).with_context(|| format!("Failed to read timeline {:?}", path))?;
+        serde_json::from_str(&content).with_context(|| format!("Invalid timeline {:?}", path))
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json).with_context(|| format!("Failed to write timeline {:?}", path))
+    }
+
+    /// Seconds of output
+    pub fn duration(&self) -> f64 {
+        self.spans.iter().map(|span| span.duration).sum()
+    }
+
+    /// Keep the output from `start` to `end`, or to the end, which then
+    /// starts at 0: what --start/--end do to the source, and a `Trim` effect
+    /// to the audio it receives
+    pub fn trim(&mut self, start: f64, end: Option<f64>) {
+        let end = end.unwrap_or(f64::INFINITY);
+        self.spans = self.spans.iter().filter_map(|span| {
+            let from = span.output_start.max(start);
+            let to = span.output_end().min(end);
+            (to > from).then_some(Span {
+                output_start: from - start,
+                source_start: span.source_start + (from - span.output_start),
+                duration: to - from,
+            })
+        }).collect();
+        self.muted = self.muted.iter().filter_map(|range| {
+            let (from, to) = (range.start.max(start), range.end.min(end));
+            (to > from).then_some(OutputRange { start: from - start, end: to - start })
+        }).collect();
+    }
+
+    /// Keep only the source time in `ranges`, cut back to back, as
+    /// --keep-segments does
+    pub fn keep_source(&mut self, ranges: &[(f64, f64)]) {
+        let mut ranges = ranges.to_vec();
+        ranges.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let mut spans = Vec::new();
+        let mut position = 0.0;
+        for span in &self.spans {
+            for &(start, end) in &ranges {
+                let from = span.source_start.max(start);
+                let to = span.source_end().min(end);
+                if to > from {
+                    spans.push(Span { output_start: position, source_start: from, duration: to - from });
+                    position += to - from;
+                }
+            }
+        }
+        self.spans = spans;
+        // Muted ranges are recorded last, once the output's time is settled
+        self.muted.clear();
+    }
+
+    /// Record the source range `start` to `end` as silenced in the output
+    pub fn mute_source(&mut self, start: f64, end: f64) {
+        let muted = self.output_ranges(start, end);
+        self.muted.extend(muted);
+        self.muted.sort_by(|a, b| a.start.total_cmp(&b.start));
+    }
+
+    /// Where the source range `start` to `end` ended up in the output, in
+    /// as many pieces as it was cut into
+    pub fn output_ranges(&self, start: f64, end: f64) -> Vec<OutputRange> {
+        self.spans.iter().filter_map(|span| {
+            let from = span.source_start.max(start);
+            let to = span.source_end().min(end);
+            (to > from).then_some(OutputRange {
+                start: span.output_start + (from - span.source_start),
+                end: span.output_start + (to - span.source_start),
+            })
+        }).collect()
+    }
+
+    /// Output time of source time `seconds`, or `None` if it was cut
+    pub fn to_output(&self, seconds: f64) -> Option<f64> {
+        self.spans.iter()
+            .find(|span| seconds >= span.source_start && seconds < span.source_end())
+            .map(|span| span.output_start + (seconds - span.source_start))
+    }
+
+    /// Source time of output time `seconds`
+    pub fn to_source(&self, seconds: f64) -> Option<f64> {
+        self.spans.iter()
+            .find(|span| seconds >= span.output_start && seconds < span.output_end())
+            .map(|span| span.source_start + (seconds - span.output_start))
+    }
+
+    /// The spans as a CMX 3600 edit decision list, one audio event per
+    /// span, with non-drop-frame timecodes at `fps`
+    pub fn to_edl(&self, title: &str, fps: f64) -> String {
+        let mut edl = String::new();
+        let _ = writeln!(edl, "TITLE: {}", title);
+        let _ = writeln!(edl, "FCM: NON-DROP FRAME");
+        let clip = self.source.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        for (index, span) in self.spans.iter().enumerate() {
+            let _ = writeln!(edl);
+            let _ = writeln!(
+                edl,
+                "{:03}  AX       A     C        {} {} {} {}",
+                index + 1,
+                edl_timecode(span.source_start, fps),
+                edl_timecode(span.source_end(), fps),
+                edl_timecode(span.output_start, fps),
+                edl_timecode(span.output_end(), fps)
+            );
+            let _ = writeln!(edl, "* FROM CLIP NAME: {}", clip);
+        }
+        edl
+    }
+}
+
+/// `HH:MM:SS:FF` timecode of the frame at `seconds`, counting `fps` frames
+/// a second and the nominal whole number of frames per timecode second
+pub fn edl_timecode(seconds: f64, fps: f64) -> String {
+    let frame = (seconds * fps).round() as u64;
+    let base = (fps.round() as u64).max(1);
+    let total_seconds = frame / base;
+    format!(
+        "{:02}:{:02}:{:02}:{:02}",
+        total_seconds / 3600,
+        total_seconds / 60 % 60,
+        total_seconds % 60,
+        frame % base
+    )
+}
@@ -0,0 +1,92 @@
+use crate::limits::ResourceLimits;
+use crate::timecode::TimeRange;
+use anyhow::{Context, Result};
+use std::path::Path;
+use std::process::Command;
+
+/// Largest correction, in samples per second, `aresample` may stretch or
+/// squeeze the audio by to follow its timestamps
+pub const ASYNC_SAMPLES_PER_SECOND: u32 = 1000;
+
+/// Repairs for inputs whose timestamps are broken or variable, as produced by
+/// screen recorders and some capture software.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TimestampRepair {
+    /// Regenerate missing timestamps and resample the audio to follow them
+    pub fix_timestamps: bool,
+    /// Fill discontinuities longer than this many seconds with silence
+    pub fill_gaps: Option<f64>,
+}
+
+impl TimestampRepair {
+    pub fn is_empty(&self) -> bool {
+        !self.fix_timestamps && self.fill_gaps.is_none()
+    }
+
+    /// Options placed before the source's `-i`
+    pub fn input_args(&self) -> Vec<String> {
+        if !self.fix_timestamps {
+            return Vec::new();
+        }
+        // Generate missing PTS and ignore DTS, which such recorders often get wrong
+        vec!["-fflags".to_string(), "+genpts+igndts".to_string()]
+    }
+
+    /// Filter to run first in the chain, so later filters see the corrected timeline.
+    ///
+    /// `async` stretches the audio by up to `ASYNC_SAMPLES_PER_SECOND` to match
+    /// its timestamps, and `first_pts=0` pads a late-starting stream with silence,
+    /// so the output duration matches the source. Filling gaps alone uses
+    /// `async=1`, which never stretches and only inserts silence once the
+    /// timestamps run ahead of the samples by more than `min_hard_comp`.
+    pub fn filter(&self) -> Option<String> {
+        if self.is_empty() {
+            return None;
+        }
+        let samples = if self.fix_timestamps { ASYNC_SAMPLES_PER_SECOND } else { 1 };
+        let mut filter = format!("aresample=async={}", samples);
+        if let Some(threshold) = self.fill_gaps {
+            filter.push_str(&format!(":min_hard_comp={:.3}", threshold));
+        }
+        filter.push_str(":first_pts=0");
+        Some(filter)
+    }
+}
+
+/// Discontinuities longer than `threshold` seconds in the first audio stream
+pub fn detect_gaps(path: &Path, limits: &ResourceLimits, threshold: f64) -> Result<Vec<TimeRange>> {
+    let mut cmd = Command::new("ffprobe");
+    cmd.arg("-v").arg("error")
+        .arg("-select_streams").arg("a:0")
+        .arg("-show_entries").arg("packet=pts_time,duration_time")
+        .arg("-of").arg("csv=p=0")
+        .arg(path);
+    let output = limits.output(&mut cmd).context("Failed to run ffprobe")?;
+    if !output.status.success() {
+        anyhow::bail!("ffprobe failed to list packets: {}", String::from_utf8_lossy(&output.stderr));
+    }
+    Ok(find_gaps(&String::from_utf8_lossy(&output.stdout), threshold))
+}
+
+/// Find gaps in ffprobe's `pts_time,duration_time` packet listing
+pub fn find_gaps(packets: &str, threshold: f64) -> Vec<TimeRange> {
+    let mut gaps = Vec::new();
+    let mut expected: Option<f64> = None;
+    for line in packets.lines() {
+        let mut fields = line.trim().split(',');
+        let Some(pts) = fields.next().and_then(|f| f.parse::<f64>().ok()) else {
+            continue;
+        };
+        let duration = fields.next().and_then(|f| f.parse::<f64>().ok()).unwrap_or(0.0);
+
+        if let Some(end) = expected {
+            if pts - end > threshold {
+                if let Ok(gap) = TimeRange::new(end, pts) {
+                    gaps.push(gap);
+                }
+            }
+        }
+        expected = Some(expected.map_or(pts + duration, |end: f64| end.max(pts + duration)));
+    }
+    gaps
+}
@@ -0,0 +1,100 @@
+use crate::limits::ResourceLimits;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::ffi::OsString;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// An audio stream of the source, as reported by ffprobe
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct AudioTrack {
+    /// Position among the audio streams, as used by `--audio-track` and `-map 0:a:N`
+    pub index: usize,
+    /// Index among all streams of the container
+    pub stream_index: u32,
+    pub codec: String,
+    /// ISO 639-2 language tag, e.g. `eng`
+    pub language: Option<String>,
+    pub title: Option<String>,
+    pub channels: Option<u32>,
+    /// Whether the container marks this stream as the default one
+    pub default: bool,
+}
+
+impl AudioTrack {
+    /// List the source's audio streams with ffprobe. `input_args` are the
+    /// options and `-i` selecting the source.
+    pub fn probe(input_args: &[OsString], limits: &ResourceLimits) -> Result<Vec<Self>> {
+        let mut cmd = Command::new("ffprobe");
+        cmd.arg("-v").arg("error")
+            .arg("-print_format").arg("json")
+            .arg("-show_streams")
+            .args(input_args);
+        let output = limits.output(&mut cmd).context("Failed to run ffprobe")?;
+        if !output.status.success() {
+            anyhow::bail!("ffprobe failed to list streams: {}", String::from_utf8_lossy(&output.stderr).trim());
+        }
+        Self::from_ffprobe_json(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    /// The audio streams in ffprobe's `-show_streams` JSON, in order
+    pub fn from_ffprobe_json(json: &str) -> Result<Vec<Self>> {
+        let probe: serde_json::Value = serde_json::from_str(json).context("Invalid ffprobe output")?;
+        let streams = probe["streams"].as_array().map(Vec::as_slice).unwrap_or_default();
+        Ok(streams
+            .iter()
+            .filter(|stream| stream["codec_type"] == "audio")
+            .enumerate()
+            .map(|(index, stream)| {
+                let tag = |name: &str| {
+                    stream["tags"][name].as_str()
+                        .map(str::trim)
+                        .filter(|value| !value.is_empty() && *value != "und")
+                        .map(str::to_string)
+                };
+                Self {
+                    index,
+                    stream_index: stream["index"].as_u64().unwrap_or(index as u64) as u32,
+                    codec: stream["codec_name"].as_str().unwrap_or("unknown").to_string(),
+                    language: tag("language"),
+                    title: tag("title"),
+                    channels: stream["channels"].as_u64().map(|c| c as u32),
+                    default: stream["disposition"]["default"].as_u64() == Some(1),
+                }
+            })
+            .collect())
+    }
+}
+
+impl fmt::Display for AudioTrack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.index, self.codec)?;
+        if let Some(channels) = self.channels {
+            write!(f, ", {} ch", channels)?;
+        }
+        write!(f, ", {}", self.language.as_deref().unwrap_or("unknown language"))?;
+        if let Some(title) = &self.title {
+            write!(f, ", \"{}\"", title)?;
+        }
+        if self.default {
+            write!(f, " (default)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Output for one track with `--all-tracks`: `<stem>.track<N>[.<language>].<ext>`
+pub fn track_output(output: &Path, track: &AudioTrack) -> PathBuf {
+    let stem = output.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let mut name = format!("{}.track{}", stem, track.index);
+    if let Some(language) = &track.language {
+        name.push('.');
+        name.push_str(&crate::template::sanitize(language, crate::template::NameProfile::Ascii));
+    }
+    if let Some(ext) = output.extension() {
+        name.push('.');
+        name.push_str(&ext.to_string_lossy());
+    }
+    output.with_file_name(name)
+}
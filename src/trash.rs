@@ -0,0 +1,123 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// What happens to a file that an extraction would overwrite or `clean` would remove
+#[derive(Debug, Clone, PartialEq)]
+pub enum Disposal {
+    /// Remove it for good (or let FFmpeg overwrite it)
+    Delete,
+    /// Move it to the desktop's trash, where it can be restored from
+    Trash,
+    /// Move it into this directory
+    Quarantine(PathBuf),
+}
+
+impl Disposal {
+    /// `--quarantine` if set, else the system trash with `--trash`, else delete
+    pub fn from_flags(trash: bool, quarantine: Option<&Path>) -> Self {
+        match (trash, quarantine) {
+            (_, Some(dir)) => Disposal::Quarantine(dir.to_path_buf()),
+            (true, None) => Disposal::Trash,
+            (false, None) => Disposal::Delete,
+        }
+    }
+
+    /// Get `path` out of the way, returning where it went if it was kept
+    pub fn dispose(&self, path: &Path) -> Result<Option<PathBuf>> {
+        match self {
+            Disposal::Delete => {
+                fs::remove_file(path).with_context(|| format!("Failed to remove {:?}", path))?;
+                Ok(None)
+            }
+            Disposal::Trash => trash(path).map(Some),
+            Disposal::Quarantine(dir) => quarantine(path, dir).map(Some),
+        }
+    }
+}
+
+/// Move `path` into `dir`, under a folder named after the current time so
+/// files displaced by different runs stay apart
+pub fn quarantine(path: &Path, dir: &Path) -> Result<PathBuf> {
+    let folder = dir.join(chrono::Local::now().format("%Y%m%d-%H%M%S").to_string());
+    fs::create_dir_all(&folder).with_context(|| format!("Failed to create quarantine folder {:?}", folder))?;
+    let name = path.file_name().with_context(|| format!("{:?} has no file name", path))?;
+    let target = unused_name(&folder.join(name));
+    move_file(path, &target)?;
+    Ok(target)
+}
+
+/// Move `path` to the user's trash: `~/.Trash` on macOS, and the
+/// freedesktop.org trash (`$XDG_DATA_HOME/Trash`) elsewhere, with the info
+/// file desktop environments need to restore it
+pub fn trash(path: &Path) -> Result<PathBuf> {
+    let absolute = std::path::absolute(path).with_context(|| format!("Failed to resolve {:?}", path))?;
+    let name = absolute.file_name().with_context(|| format!("{:?} has no file name", path))?;
+
+    if cfg!(target_os = "macos") {
+        let home = std::env::var_os("HOME").context("HOME is not set, so the trash cannot be found")?;
+        let target = unused_name(&PathBuf::from(home).join(".Trash").join(name));
+        move_file(&absolute, &target)?;
+        return Ok(target);
+    }
+    if cfg!(windows) {
+        anyhow::bail!("Moving files to the Windows recycle bin is not supported; use --quarantine instead");
+    }
+
+    let trash = trash_dir()?;
+    let files = trash.join("files");
+    let info = trash.join("info");
+    fs::create_dir_all(&files).and_then(|_| fs::create_dir_all(&info))
+        .with_context(|| format!("Failed to create trash folders in {:?}", trash))?;
+
+    let target = unused_name(&files.join(name));
+    let target_name = target.file_name().unwrap_or(name).to_string_lossy().into_owned();
+    let info_file = info.join(format!("{}.trashinfo", target_name));
+    fs::write(&info_file, trash_info(&absolute))
+        .with_context(|| format!("Failed to write {:?}", info_file))?;
+    if let Err(e) = move_file(&absolute, &target) {
+        let _ = fs::remove_file(&info_file);
+        return Err(e);
+    }
+    Ok(target)
+}
+
+/// The freedesktop.org home trash
+fn trash_dir() -> Result<PathBuf> {
+    if let Some(data) = std::env::var_os("XDG_DATA_HOME").filter(|dir| !dir.is_empty()) {
+        return Ok(PathBuf::from(data).join("Trash"));
+    }
+    let home = std::env::var_os("HOME").context("Neither XDG_DATA_HOME nor HOME is set, so the trash cannot be found")?;
+    Ok(PathBuf::from(home).join(".local/share/Trash"))
+}
+
+/// `.trashinfo` contents recording where a file came from and when
+pub fn trash_info(original: &Path) -> String {
+    format!(
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        crate::library::percent_encode(&original.to_string_lossy()).replace("%2F", "/"),
+        chrono::Local::now().format("%Y-%m-%dT%H:%M:%S")
+    )
+}
+
+/// `path`, or `<stem>.<n>.<ext>` with the first `n` that is not taken
+fn unused_name(path: &Path) -> PathBuf {
+    if !path.exists() {
+        return path.to_path_buf();
+    }
+    let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let ext = path.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+    (2..)
+        .map(|n| path.with_file_name(format!("{}.{}{}", stem, n, ext)))
+        .find(|candidate| !candidate.exists())
+        .unwrap()
+}
+
+/// Rename, or copy and remove when the target is on another filesystem
+fn move_file(from: &Path, to: &Path) -> Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    fs::copy(from, to).with_context(|| format!("Failed to move {:?} to {:?}", from, to))?;
+    fs::remove_file(from).with_context(|| format!("Failed to remove {:?} after copying it to {:?}", from, to))
+}
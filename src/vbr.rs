@@ -0,0 +1,63 @@
+use crate::AudioFormat;
+use anyhow::Result;
+
+/// Bitrates in kbps Opus aims at for --vbr levels 0 to 10
+pub const OPUS_BITRATES: [u32; 11] = [24, 32, 48, 64, 80, 96, 112, 128, 160, 192, 256];
+
+/// The --vbr levels an encoder takes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VbrScale {
+    pub min: f64,
+    pub max: f64,
+    /// Level used when --vbr is given without one
+    pub default: f64,
+    /// Whether only whole levels exist
+    pub whole: bool,
+    /// Whether a lower level is better, as with LAME's V0 to V9
+    pub lower_is_better: bool,
+}
+
+/// The --vbr scale of `format`: LAME's V0 (best) to V9 for MP3, FFmpeg's AAC
+/// quality 0.1 to 2, Vorbis quality -1 to 10, and levels 0 to 10 for Opus,
+/// which has no quality scale and aims at a bitrate from `OPUS_BITRATES`.
+/// `None` for formats without a VBR mode.
+pub fn scale(format: &AudioFormat) -> Option<VbrScale> {
+    match format {
+        AudioFormat::Mp3 => Some(VbrScale { min: 0.0, max: 9.0, default: 2.0, whole: true, lower_is_better: true }),
+        AudioFormat::Aac => Some(VbrScale { min: 0.1, max: 2.0, default: 1.0, whole: false, lower_is_better: false }),
+        AudioFormat::Ogg => Some(VbrScale { min: -1.0, max: 10.0, default: 5.0, whole: false, lower_is_better: false }),
+        AudioFormat::Opus => Some(VbrScale { min: 0.0, max: 10.0, default: 5.0, whole: true, lower_is_better: false }),
+        _ => None,
+    }
+}
+
+/// The level to encode `format` at for --vbr `level`, or the format's
+/// default when no level was given
+pub fn level(format: &AudioFormat, level: Option<f64>) -> Result<f64> {
+    let Some(scale) = scale(format) else {
+        anyhow::bail!("--vbr applies to MP3, AAC, Opus and Vorbis outputs, not {}", format.to_string().to_uppercase());
+    };
+    let level = level.unwrap_or(scale.default);
+    if !(scale.min..=scale.max).contains(&level) || (scale.whole && level.fract() != 0.0) {
+        let kind = if scale.whole { "a whole number" } else { "a number" };
+        anyhow::bail!(
+            "--vbr for {} must be {} from {} to {}{}, got {}",
+            format.to_string().to_uppercase(), kind, scale.min, scale.max,
+            if scale.lower_is_better { " (lower is better)" } else { "" },
+            level
+        );
+    }
+    Ok(level)
+}
+
+/// Encoder options selecting VBR at `level` for AAC, Opus and Vorbis; MP3
+/// goes through `lame::Encoding`
+pub fn ffmpeg_args(format: &AudioFormat, level: f64) -> Vec<String> {
+    match format {
+        AudioFormat::Opus => vec![
+            "-vbr".into(), "on".into(),
+            "-b:a".into(), format!("{}k", OPUS_BITRATES[level as usize]),
+        ],
+        _ => vec!["-q:a".into(), level.to_string()],
+    }
+}
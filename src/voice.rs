@@ -0,0 +1,38 @@
+use crate::AudioFormat;
+
+/// Bitrates AMR-NB encodes at, in bits per second
+pub const AMR_NB_RATES: [u32; 8] = [4_750, 5_150, 5_900, 6_700, 7_400, 7_950, 10_200, 12_200];
+
+/// Bitrates AMR-WB encodes at, in bits per second
+pub const AMR_WB_RATES: [u32; 9] = [6_600, 8_850, 12_650, 14_250, 15_850, 18_250, 19_850, 23_050, 23_850];
+
+/// Highest bitrate of wideband Speex, in bits per second
+pub const SPEEX_MAX_RATE: u32 = 42_200;
+
+/// Whether `format` is one of the telephony voice codecs
+pub fn is_voice(format: &AudioFormat) -> bool {
+    matches!(format, AudioFormat::AmrNb | AudioFormat::AmrWb | AudioFormat::Speex)
+}
+
+/// Bitrate in bits per second a voice `format` encodes a --quality of `kbps`
+/// at. AMR only has fixed modes, so the highest one not above `kbps` is
+/// used, or the lowest if all are; Speex is capped at its highest bitrate.
+pub fn bitrate(format: &AudioFormat, kbps: u32) -> Option<u32> {
+    let bps = kbps.saturating_mul(1000);
+    let modes: &[u32] = match format {
+        AudioFormat::AmrNb => &AMR_NB_RATES,
+        AudioFormat::AmrWb => &AMR_WB_RATES,
+        AudioFormat::Speex => return Some(bps.min(SPEEX_MAX_RATE)),
+        _ => return None,
+    };
+    Some(modes.iter().rev().copied().find(|&rate| rate <= bps).unwrap_or(modes[0]))
+}
+
+/// Sample rate the codec of a voice `format` runs at; all of them are mono
+pub fn sample_rate(format: &AudioFormat) -> Option<u32> {
+    match format {
+        AudioFormat::AmrNb => Some(8_000),
+        AudioFormat::AmrWb | AudioFormat::Speex => Some(16_000),
+        _ => None,
+    }
+}
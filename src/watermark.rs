@@ -0,0 +1,77 @@
+use anyhow::Result;
+use std::path::PathBuf;
+
+/// Seconds between watermark repetitions when no interval is given
+pub const DEFAULT_INTERVAL: f64 = 30.0;
+
+/// Watermark level relative to full scale when no gain is given
+pub const DEFAULT_GAIN_DB: f64 = -24.0;
+
+/// Length of the tone pattern: two short pips
+const TONE_PATTERN_LENGTH: f64 = 0.4;
+
+/// What is played as the watermark
+#[derive(Debug, Clone, PartialEq)]
+pub enum WatermarkSource {
+    /// A double pip at the given frequency in Hz
+    Tone(f64),
+    /// A user-provided audio snippet
+    Audio(PathBuf),
+}
+
+/// A low-volume marker repeated at a fixed interval over the whole output,
+/// so leaked preview copies can be identified.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Watermark {
+    pub source: WatermarkSource,
+    /// Seconds from the start of one repetition to the next
+    pub interval: f64,
+    /// Level in dB relative to full scale
+    pub gain_db: f64,
+}
+
+impl Watermark {
+    pub fn new(source: WatermarkSource, interval: Option<f64>, gain_db: Option<f64>) -> Result<Self> {
+        let interval = interval.unwrap_or(DEFAULT_INTERVAL);
+        let gain_db = gain_db.unwrap_or(DEFAULT_GAIN_DB);
+
+        if matches!(source, WatermarkSource::Tone(_)) && interval <= TONE_PATTERN_LENGTH {
+            anyhow::bail!("Watermark interval must be longer than {} seconds", TONE_PATTERN_LENGTH);
+        }
+        if interval <= 0.0 {
+            anyhow::bail!("Watermark interval must be positive");
+        }
+        if gain_db > 0.0 {
+            anyhow::bail!("Watermark gain must not be above 0 dB");
+        }
+
+        Ok(Self { source, interval, gain_db })
+    }
+
+    /// Overlay source chain for the audio graph.
+    ///
+    /// `input_index` is the FFmpeg input carrying the snippet for audio watermarks.
+    pub fn overlay(&self, input_index: usize) -> String {
+        match &self.source {
+            WatermarkSource::Tone(frequency) => {
+                let phase = format!("mod(t,{})", self.interval);
+                format!(
+                    "sine=frequency={}:sample_rate=48000,volume=volume={}dB,\
+                     volume=volume=0:enable='not(lt({phase},0.15)+between({phase},0.25,{}))'",
+                    frequency,
+                    self.gain_db,
+                    TONE_PATTERN_LENGTH,
+                    phase = phase
+                )
+            }
+            WatermarkSource::Audio(_) => {
+                // Pad the snippet to one interval, then loop that block for the whole output
+                let samples = (self.interval * 48000.0).round() as u64;
+                format!(
+                    "[{}:a]aresample=48000,apad=whole_dur={},aloop=loop=-1:size={},volume=volume={}dB",
+                    input_index, self.interval, samples, self.gain_db
+                )
+            }
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+
+/// Correction file of a hybrid WavPack output: `album.wv` gets `album.wvc`
+pub fn correction_path(output: &Path) -> PathBuf {
+    output.with_extension("wvc")
+}
+
+/// `wavpack` command that reads a WAV stream from stdin and writes a hybrid
+/// `output` of about `kbps`, plus its correction file holding what the lossy
+/// part leaves out. Together they decode to the exact original samples.
+pub fn hybrid_command(output: &Path, kbps: u32) -> Command {
+    let mut cmd = Command::new("wavpack");
+    // -i: a piped WAV header has no real length; -y: overwrite without asking
+    cmd.arg("-q").arg("-y").arg("-i").arg("-h")
+        .arg(format!("-b{}", kbps))
+        .arg("-c")
+        .arg("-")
+        .arg("-o").arg(output);
+    cmd
+}
+
+/// Spawn `wavpack` encoding the WAV stream on `input` into `output`
+pub fn spawn_hybrid(output: &Path, kbps: u32, input: Stdio) -> Result<Child> {
+    hybrid_command(output, kbps)
+        .stdin(input)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run wavpack; is it installed? Hybrid WavPack output needs the wavpack command-line tool")
+}
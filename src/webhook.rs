@@ -0,0 +1,135 @@
+use crate::audit;
+use crate::curl::Curl;
+use crate::email::BatchSummary;
+use crate::library::percent_encode;
+use crate::warning;
+use crate::template;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Seconds to wait for a webhook to accept a message
+const POST_TIMEOUT_SECS: u32 = 15;
+
+/// Discord rejects messages longer than this
+const DISCORD_MAX_CHARS: usize = 2000;
+
+pub const DEFAULT_COMPLETION_MESSAGE: &str = "{kind} of {source} on {host} finished: {succeeded} succeeded, {failed} failed";
+pub const DEFAULT_FAILURE_MESSAGE: &str = "❌ {input} failed on {host}: {error}";
+
+/// Makes Matrix transaction IDs unique within a process
+static TXN_SEQ: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookKind {
+    Slack,
+    Discord,
+    Matrix,
+}
+
+/// A chat notifier, from a `[[webhooks]]` entry of `config.toml`.
+///
+/// Messages are templates; completion messages can use `{kind}`, `{source}`,
+/// `{succeeded}`, `{failed}` and `{host}`, failure messages `{input}`,
+/// `{error}` and `{host}`.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Webhook {
+    pub kind: WebhookKind,
+    /// Incoming webhook URL, or the homeserver URL for Matrix
+    pub url: String,
+    /// Matrix room ID, e.g. `!abc:example.org`
+    pub room: Option<String>,
+    /// Matrix access token
+    pub token: Option<String>,
+    #[serde(default = "enabled")]
+    pub on_completion: bool,
+    #[serde(default = "enabled")]
+    pub on_failure: bool,
+    pub completion_message: Option<String>,
+    pub failure_message: Option<String>,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+impl Webhook {
+    pub fn completion_text(&self, summary: &BatchSummary) -> Result<String> {
+        let fields = BTreeMap::from([
+            ("kind", summary.kind.clone()),
+            ("source", summary.source.display().to_string()),
+            ("succeeded", summary.succeeded.to_string()),
+            ("failed", summary.failures.len().to_string()),
+            ("host", audit::hostname()),
+        ]);
+        template::expand(self.completion_message.as_deref().unwrap_or(DEFAULT_COMPLETION_MESSAGE), &fields)
+    }
+
+    pub fn failure_text(&self, input: &Path, error: &str) -> Result<String> {
+        let fields = BTreeMap::from([
+            ("input", input.display().to_string()),
+            ("error", error.to_string()),
+            ("host", audit::hostname()),
+        ]);
+        template::expand(self.failure_message.as_deref().unwrap_or(DEFAULT_FAILURE_MESSAGE), &fields)
+    }
+
+    /// Method, URL and JSON body posting `text`
+    pub fn request(&self, text: &str) -> Result<(&'static str, String, serde_json::Value)> {
+        let url = self.url.trim_end_matches('/');
+        match self.kind {
+            WebhookKind::Slack => Ok(("POST", url.to_string(), serde_json::json!({ "text": text }))),
+            WebhookKind::Discord => {
+                let content: String = text.chars().take(DISCORD_MAX_CHARS).collect();
+                Ok(("POST", url.to_string(), serde_json::json!({ "content": content })))
+            }
+            WebhookKind::Matrix => {
+                let room = self.room.as_deref().context("Matrix webhooks need `room`")?;
+                let txn = format!("{}-{}-{}", std::process::id(), chrono::Utc::now().timestamp_millis(), TXN_SEQ.fetch_add(1, Ordering::Relaxed));
+                let url = format!(
+                    "{}/_matrix/client/v3/rooms/{}/send/m.room.message/{}",
+                    url,
+                    percent_encode(room),
+                    txn
+                );
+                Ok(("PUT", url, serde_json::json!({ "msgtype": "m.text", "body": text })))
+            }
+        }
+    }
+
+    pub fn post(&self, text: &str) -> Result<()> {
+        let (method, url, body) = self.request(text)?;
+        let mut curl = Curl::new()
+            .option("url", &url)
+            .option("request", method)
+            .option("header", "Content-Type: application/json")
+            .option("data", &body.to_string());
+        if self.kind == WebhookKind::Matrix {
+            let token = self.token.as_deref().context("Matrix webhooks need `token`")?;
+            curl = curl.option("header", &format!("Authorization: Bearer {}", token));
+        }
+        curl.run(POST_TIMEOUT_SECS).with_context(|| format!("{:?} webhook failed", self.kind))
+    }
+}
+
+/// Announce a finished batch on every webhook that wants completions
+pub fn batch_finished(webhooks: &[Webhook], summary: &BatchSummary) {
+    for webhook in webhooks.iter().filter(|w| w.on_completion) {
+        if let Err(e) = webhook.completion_text(summary).and_then(|text| webhook.post(&text)) {
+            warning!("⚠ {:#}", e);
+        }
+    }
+}
+
+/// Announce a failed input on every webhook that wants failures
+pub fn input_failed(webhooks: &[Webhook], input: &Path, error: &dyn std::fmt::Display) {
+    let error = format!("{:#}", error);
+    for webhook in webhooks.iter().filter(|w| w.on_failure) {
+        if let Err(e) = webhook.failure_text(input, &error).and_then(|text| webhook.post(&text)) {
+            warning!("⚠ {:#}", e);
+        }
+    }
+}
@@ -0,0 +1,213 @@
+use audio_extractor::analysis::{self, AnalysisReport, DetectedLanguage, Mode, MusicAnalysis, MusicalKey, Segment, SegmentLabel, SpeakerSegment};
+use audio_extractor::AudioFormat;
+use std::path::Path;
+
+#[test]
+fn test_parse_whisper_language() {
+    let stderr = "whisper_init_from_file: loading model\n\
+                  whisper_full_with_state: auto-detected language: de (p = 0.912345)\n";
+    assert_eq!(analysis::parse_whisper_language(stderr), Some(("de".to_string(), 0.912345)));
+    assert_eq!(analysis::parse_whisper_language("no detection here"), None);
+}
+
+#[test]
+fn test_language_windows_cover_the_file() {
+    assert_eq!(analysis::language_windows(12.0), vec![0.0]);
+    assert_eq!(analysis::language_windows(60.0), vec![0.0, 30.0]);
+    
+    let windows = analysis::language_windows(3600.0);
+    assert_eq!(windows.len(), 5);
+    assert_eq!(windows[0], 0.0);
+    assert_eq!(*windows.last().unwrap(), 3600.0 - analysis::LANGUAGE_WINDOW_SECONDS);
+}
+
+#[test]
+fn test_summarize_languages_orders_by_share() {
+    let detections = vec![
+        ("en".to_string(), 0.9),
+        ("es".to_string(), 0.8),
+        ("en".to_string(), 0.7),
+    ];
+    let languages = analysis::summarize_languages(&detections);
+    
+    assert_eq!(languages.len(), 2);
+    assert_eq!(languages[0].language, "en");
+    assert!((languages[0].confidence - 0.8).abs() < 1e-9);
+    assert!((languages[0].share - 2.0 / 3.0).abs() < 1e-9);
+    assert_eq!(languages[1].language, "es");
+}
+
+#[test]
+fn test_analysis_report_sidecar_and_tags() {
+    assert_eq!(
+        AnalysisReport::sidecar_path(Path::new("/tmp/talk.mp3")),
+        Path::new("/tmp/talk.mp3.analysis.json")
+    );
+    
+    assert!(AnalysisReport::default().tags(&AudioFormat::Mp3).is_empty());
+    
+    let report = AnalysisReport {
+        languages: Some(vec![
+            DetectedLanguage { language: "en".to_string(), confidence: 0.9, share: 0.75 },
+            DetectedLanguage { language: "fr".to_string(), confidence: 0.8, share: 0.25 },
+        ]),
+        ..Default::default()
+    };
+    assert_eq!(report.tags(&AudioFormat::Mp3), vec![("language".to_string(), "en,fr".to_string())]);
+}
+
+#[test]
+fn test_parse_rttm_and_summarize_speakers() {
+    let rttm = "SPEAKER ep1 1 10.0 5.0 <NA> <NA> SPEAKER_01 <NA> <NA>\n\
+                SPEAKER ep1 1 0.0 10.0 <NA> <NA> SPEAKER_00 <NA> <NA>\n\
+                SPEAKER ep1 1 15.0 5.0 <NA> <NA> SPEAKER_00 <NA> <NA>\n";
+    let segments = analysis::parse_rttm(rttm).unwrap();
+    assert_eq!(segments[0], SpeakerSegment { speaker: "SPEAKER_00".to_string(), start: 0.0, end: 10.0 });
+    
+    let diarization = analysis::summarize_speakers(segments);
+    assert_eq!(diarization.speakers, 2);
+    assert_eq!(diarization.segments.len(), 3);
+    assert_eq!(diarization.speaking_time[0].speaker, "SPEAKER_00");
+    assert!((diarization.speaking_time[0].percent - 75.0).abs() < 1e-9);
+    assert!((diarization.speaking_time[1].seconds - 5.0).abs() < 1e-9);
+    
+    assert!(analysis::parse_rttm("SPEAKER ep1 1 abc").is_err());
+}
+
+#[test]
+fn test_classify_samples_separates_silence_music_and_speech() {
+    let rate = 16_000;
+    let tone = |i: usize| ((i as f64 * 440.0 * 2.0 * std::f64::consts::PI / rate as f64).sin() * 8000.0) as i16;
+    
+    let mut samples = vec![0i16; 3 * rate];
+    // Steady tone: music
+    samples.extend((0..3 * rate).map(tone));
+    // Tone gated on and off four times a second: speech-like syllables
+    samples.extend((0..3 * rate).map(|i| if (i / (rate / 8)) % 2 == 0 { tone(i) } else { 0 }));
+    
+    let segments = analysis::classify_samples(&samples, rate as u32);
+    let labels: Vec<_> = segments.iter().map(|s| (s.label, s.start, s.end)).collect();
+    assert_eq!(labels, vec![
+        (SegmentLabel::Silence, 0.0, 3.0),
+        (SegmentLabel::Music, 3.0, 6.0),
+        (SegmentLabel::Speech, 6.0, 9.0),
+    ]);
+}
+
+#[test]
+fn test_classify_samples_absorbs_short_regions() {
+    let rate = 16_000;
+    let mut samples = vec![0i16; 4 * rate];
+    samples.extend((0..rate).map(|i| ((i as f64 * 0.1).sin() * 8000.0) as i16));
+    samples.extend(vec![0i16; 4 * rate]);
+    
+    let segments = analysis::classify_samples(&samples, rate as u32);
+    assert_eq!(segments.len(), 1);
+    assert_eq!(segments[0].label, SegmentLabel::Silence);
+}
+
+#[test]
+fn test_select_filter_keeps_matching_regions() {
+    let segments = vec![
+        Segment { label: SegmentLabel::Speech, start: 0.0, end: 10.0 },
+        Segment { label: SegmentLabel::Music, start: 10.0, end: 20.0 },
+        Segment { label: SegmentLabel::Speech, start: 20.0, end: 25.5 },
+    ];
+    
+    assert_eq!(
+        analysis::select_filter(&segments, &[SegmentLabel::Speech]).unwrap(),
+        "aselect='between(t,0.000,10.000)+between(t,20.000,25.500)',asetpts=N/SR/TB"
+    );
+    assert_eq!(analysis::select_filter(&segments, &[SegmentLabel::Silence]), None);
+}
+
+fn sine(frequency: f64, i: usize, rate: usize) -> f64 {
+    (i as f64 * frequency * 2.0 * std::f64::consts::PI / rate as f64).sin()
+}
+
+#[test]
+fn test_estimate_bpm_of_click_track() {
+    let rate = 16_000;
+    let beat = rate / 2; // 120 BPM
+    let samples: Vec<i16> = (0..20 * rate)
+        .map(|i| if i % beat < 800 { (sine(1000.0, i, rate) * 12000.0) as i16 } else { 0 })
+        .collect();
+    
+    let bpm = analysis::estimate_bpm(&samples, rate as u32).unwrap();
+    assert!((bpm - 120.0).abs() < 2.0, "estimated {} BPM", bpm);
+    assert_eq!(analysis::estimate_bpm(&[0; 100], rate as u32), None);
+}
+
+#[test]
+fn test_estimate_key_of_triad() {
+    let rate = 16_000;
+    // A minor triad: A3, C4, E4
+    let samples: Vec<i16> = (0..4 * rate)
+        .map(|i| ((sine(220.0, i, rate) + sine(261.63, i, rate) + sine(329.63, i, rate)) * 6000.0) as i16)
+        .collect();
+    
+    let (key, confidence) = analysis::estimate_key(&samples, rate as u32).unwrap();
+    assert_eq!(key, MusicalKey { tonic: "A".to_string(), mode: Mode::Minor });
+    assert!(confidence > 0.5);
+    assert_eq!(analysis::estimate_key(&vec![0; 4 * rate], rate as u32), None);
+}
+
+#[test]
+fn test_music_tags_depend_on_format() {
+    let report = AnalysisReport {
+        music: Some(MusicAnalysis {
+            bpm: 127.6,
+            key: MusicalKey { tonic: "F#".to_string(), mode: Mode::Minor },
+            key_confidence: 0.8,
+        }),
+        ..Default::default()
+    };
+    
+    assert_eq!(report.tags(&AudioFormat::Mp3), vec![
+        ("TBPM".to_string(), "128".to_string()),
+        ("TKEY".to_string(), "F#m".to_string()),
+    ]);
+    assert_eq!(report.tags(&AudioFormat::Flac)[1], ("INITIALKEY".to_string(), "F#m".to_string()));
+}
+
+struct PeakAnalyzer;
+
+impl audio_extractor::Analyzer for PeakAnalyzer {
+    fn name(&self) -> &str {
+        "peak"
+    }
+    
+    fn analyze(&self, input: &audio_extractor::AnalysisInput) -> anyhow::Result<serde_json::Value> {
+        let peak = input.samples.iter().map(|s| s.unsigned_abs()).max().unwrap_or(0);
+        Ok(serde_json::json!({ "peak": peak, "seconds": input.duration() }))
+    }
+}
+
+#[test]
+fn test_custom_analyzer_results_in_sidecar() {
+    use audio_extractor::Analyzer;
+    
+    let samples = [0i16, -1200, 800, 300];
+    let input = audio_extractor::AnalysisInput {
+        source: Path::new("talk.mp4"),
+        output: Path::new("talk.mp3"),
+        samples: &samples,
+        sample_rate: 4,
+        metadata: None,
+    };
+    let result = PeakAnalyzer.analyze(&input).unwrap();
+    assert_eq!(result, serde_json::json!({ "peak": 1200, "seconds": 1.0 }));
+    
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join("talk.mp3");
+    let mut report = AnalysisReport::default();
+    report.custom.insert("peak".into(), result);
+    report.write(&output).unwrap();
+    
+    let json = std::fs::read_to_string(AnalysisReport::sidecar_path(&output)).unwrap();
+    assert!(json.contains("\"custom\""));
+    assert_eq!(AnalysisReport::load(&output).unwrap(), report);
+    
+    // Without custom results the section is left out
+    assert!(!serde_json::to_string(&AnalysisReport::default()).unwrap().contains("custom"));
+}
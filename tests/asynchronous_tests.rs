@@ -0,0 +1,67 @@
+mod common;
+
+use audio_extractor::{AudioExtractor, AudioFormat, ExtractionOptions, ExtractorError, ProgressEvent};
+use tempfile::TempDir;
+use tokio::sync::mpsc;
+
+#[tokio::test]
+async fn test_extract_async_streams_progress() {
+    let dir = TempDir::new().unwrap();
+    let input = common::create_test_video_file();
+    let options = ExtractionOptions::new(input.path(), dir.path().join("out.flac"))
+        .format(AudioFormat::Flac)
+        .verify(true);
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+
+    let output = AudioExtractor::new(options).extract_async_with_progress(sender).await.unwrap();
+    assert_eq!(output, dir.path().join("out.flac"));
+    assert!(output.exists());
+
+    let mut events = Vec::new();
+    while let Some(event) = receiver.recv().await {
+        events.push(event);
+    }
+    assert_eq!(events.first(), Some(&ProgressEvent::Started));
+    assert_eq!(events.last(), Some(&ProgressEvent::Finished));
+    assert!(events.contains(&ProgressEvent::Step("Verification completed")));
+}
+
+#[tokio::test]
+async fn test_extract_async_reports_typed_errors() {
+    let dir = TempDir::new().unwrap();
+    let missing = dir.path().join("missing.mp4");
+    let options = ExtractionOptions::new(&missing, dir.path().join("out.wav")).format(AudioFormat::Wav);
+    match AudioExtractor::new(options).extract_async().await {
+        Err(ExtractorError::InputNotFound(path)) => assert_eq!(path, missing),
+        other => panic!("expected InputNotFound, got {:?}", other),
+    }
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_batch_async_keeps_input_order() {
+    let dir = TempDir::new().unwrap();
+    let inputs: Vec<_> = (0..3).map(|_| common::create_test_video_file()).collect();
+    let mut paths: Vec<_> = inputs.iter().map(|f| f.path().to_path_buf()).collect();
+    paths.insert(1, dir.path().join("missing.mp4"));
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+
+    let results = AudioExtractor::extract_batch_async(
+        paths.clone(), dir.path().join("out"), AudioFormat::Wav, 128, true, 2, Some(sender),
+    ).await.unwrap();
+
+    assert_eq!(results.len(), 4);
+    assert!(results[1].is_err());
+    for (index, result) in results.iter().enumerate().filter(|(index, _)| *index != 1) {
+        let output = result.as_ref().unwrap();
+        assert_eq!(output.file_stem(), paths[index].file_stem());
+        assert!(output.exists());
+    }
+    let mut finished = Vec::new();
+    while let Some((index, event)) = receiver.recv().await {
+        if event == ProgressEvent::Finished {
+            finished.push(index);
+        }
+    }
+    finished.sort();
+    assert_eq!(finished, vec![0, 2, 3]);
+}
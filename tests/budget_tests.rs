@@ -0,0 +1,67 @@
+mod common;
+
+use audio_extractor::{Args, AudioExtractor, AudioFormat, SizeBudget};
+use clap::Parser;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+fn budget(total: u64, durations: &[(&str, f64)]) -> SizeBudget {
+    let durations: HashMap<PathBuf, f64> = durations.iter().map(|(path, seconds)| (PathBuf::from(path), *seconds)).collect();
+    SizeBudget::new(total, durations)
+}
+
+#[test]
+fn test_shares_follow_duration() {
+    let budget = budget(4_000, &[("a.mp4", 100.0), ("b.mp4", 300.0)]);
+    assert_eq!(budget.reserve(Path::new("a.mp4")), Some((1_000, 100.0)));
+    assert_eq!(budget.reserve(Path::new("b.mp4")), Some((3_000, 300.0)));
+    assert_eq!(budget.reserve(Path::new("c.mp4")), None);
+}
+
+#[test]
+fn test_overruns_are_replanned() {
+    let budget = budget(3_000, &[("a.mp4", 100.0), ("b.mp4", 100.0), ("c.mp4", 100.0)]);
+    let (share, _) = budget.reserve(Path::new("a.mp4")).unwrap();
+    assert_eq!(share, 1_000);
+    // The first output came in 400 bytes over; the rest share what is left
+    budget.settle(share, 1_400);
+    assert_eq!(budget.reserve(Path::new("b.mp4")).unwrap().0, 800);
+    budget.settle(800, 600);
+    assert_eq!(budget.reserve(Path::new("c.mp4")).unwrap().0, 1_000);
+    budget.settle(1_000, 1_000);
+    assert_eq!(budget.spent(), 3_000);
+}
+
+#[test]
+fn test_forgone_inputs_leave_their_share() {
+    let budget = budget(2_000, &[("a.mp4", 100.0), ("skipped.mp4", 100.0)]);
+    budget.forgo(Path::new("skipped.mp4"));
+    assert_eq!(budget.reserve(Path::new("a.mp4")).unwrap().0, 2_000);
+}
+
+#[test]
+fn test_budget_arguments() {
+    let parse = |extra: &[&str]| Args::try_parse_from(["audio_extractor", "-i", "shows", "-o", "out"].iter().chain(extra));
+    assert_eq!(parse(&["--batch-budget", "8GB"]).unwrap().batch_budget, Some(8_000_000_000));
+    assert!(parse(&["--batch-budget", "8GB", "--target-size", "25MB"]).is_err());
+    assert!(parse(&["--batch-budget", "8GB", "-q", "128"]).is_err());
+}
+
+#[test]
+fn test_budget_needs_a_lossy_format() {
+    let dir = TempDir::new().unwrap();
+    let input_dir = dir.path().join("shows");
+    std::fs::create_dir(&input_dir).unwrap();
+    common::write_test_tone(&input_dir.join("episode.mp4"));
+    
+    let args = Args {
+        input: input_dir,
+        output: dir.path().join("out"),
+        format: Some(AudioFormat::Flac),
+        batch_budget: Some(1_000_000),
+        ..Default::default()
+    };
+    let err = AudioExtractor::new(args).extract_directory().unwrap_err();
+    assert!(err.to_string().contains("lossless"));
+}
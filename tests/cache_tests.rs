@@ -0,0 +1,72 @@
+use audio_extractor::ResultCache;
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn test_cache_key_depends_on_content_and_settings() {
+    let temp_dir = tempdir().unwrap();
+    let input_a = temp_dir.path().join("a.mp4");
+    let input_b = temp_dir.path().join("b.mp4");
+    fs::write(&input_a, b"same content").unwrap();
+    fs::write(&input_b, b"same content").unwrap();
+    
+    let key_a = ResultCache::key(&input_a, ["-b:a", "128k"]).unwrap();
+    let key_b = ResultCache::key(&input_b, ["-b:a", "128k"]).unwrap();
+    let key_c = ResultCache::key(&input_a, ["-b:a", "192k"]).unwrap();
+    
+    assert_eq!(key_a, key_b);
+    assert_ne!(key_a, key_c);
+}
+
+#[test]
+fn test_cache_store_and_restore() {
+    let temp_dir = tempdir().unwrap();
+    let cache = ResultCache::new(temp_dir.path().join("cache"), None);
+    
+    let output = temp_dir.path().join("output.mp3");
+    fs::write(&output, b"encoded audio").unwrap();
+    
+    assert!(!cache.restore("abc", &output).unwrap());
+    cache.store("abc", &output).unwrap();
+    
+    let restored = temp_dir.path().join("restored.mp3");
+    assert!(cache.restore("abc", &restored).unwrap());
+    assert_eq!(fs::read(&restored).unwrap(), b"encoded audio");
+    
+    // Entries are stored per output extension
+    assert!(!cache.restore("abc", &temp_dir.path().join("restored.wav")).unwrap());
+}
+
+#[test]
+fn test_cache_eviction() {
+    let temp_dir = tempdir().unwrap();
+    let cache = ResultCache::new(temp_dir.path().join("cache"), Some(10));
+    
+    let output = temp_dir.path().join("output.mp3");
+    fs::write(&output, b"0123456789").unwrap();
+    cache.store("first", &output).unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    cache.store("second", &output).unwrap();
+    
+    let restored = temp_dir.path().join("restored.mp3");
+    assert!(!cache.restore("first", &restored).unwrap());
+    assert!(cache.restore("second", &restored).unwrap());
+}
+
+#[test]
+fn test_overwriting_restored_output_leaves_entry_intact() {
+    let temp_dir = tempdir().unwrap();
+    let cache = ResultCache::new(temp_dir.path().join("cache"), None);
+    
+    let output = temp_dir.path().join("output.mp3");
+    fs::write(&output, b"encoded audio").unwrap();
+    cache.store("abc", &output).unwrap();
+    
+    let restored = temp_dir.path().join("restored.mp3");
+    assert!(cache.restore("abc", &restored).unwrap());
+    // Rewritten in place, as FFmpeg's -y and the native backend do
+    fs::write(&restored, b"another encode").unwrap();
+    
+    assert!(cache.restore("abc", &restored).unwrap());
+    assert_eq!(fs::read(&restored).unwrap(), b"encoded audio");
+}
@@ -0,0 +1,42 @@
+use audio_extractor::chapters::{self, Chapter};
+use audio_extractor::Args;
+use clap::Parser;
+use std::path::{Path, PathBuf};
+
+const FFPROBE_JSON: &str = r#"{
+    "chapters": [
+        {"id": 0, "start_time": "0.000000", "end_time": "754.250000", "tags": {"title": "Chapter 1: The Crossing"}},
+        {"id": 1, "start_time": "754.250000", "end_time": "754.250000", "tags": {"title": "Empty"}},
+        {"id": 2, "start_time": "754.250000", "end_time": "1500.000000", "tags": {"title": "  "}},
+        {"id": 3, "start_time": "1500.000000", "end_time": "2210.500000"}
+    ]
+}"#;
+
+#[test]
+fn test_from_ffprobe_json() {
+    let chapters = Chapter::from_ffprobe_json(FFPROBE_JSON).unwrap();
+    assert_eq!(chapters.len(), 3);
+    assert_eq!(chapters[0], Chapter { index: 1, start: 0.0, end: 754.25, title: Some("Chapter 1: The Crossing".into()) });
+    // Zero-length chapters are dropped and the rest renumbered
+    assert_eq!((chapters[1].index, chapters[1].start, chapters[1].title.as_deref()), (2, 754.25, None));
+    assert_eq!(chapters[2].duration(), 710.5);
+
+    assert!(Chapter::from_ffprobe_json("{}").unwrap().is_empty());
+    assert!(Chapter::from_ffprobe_json("not json").is_err());
+}
+
+#[test]
+fn test_chapter_output() {
+    let chapters = Chapter::from_ffprobe_json(FFPROBE_JSON).unwrap();
+    let output = Path::new("/books/Voyage.mp3");
+    assert_eq!(chapters::chapter_output(output, &chapters[0]), PathBuf::from("/books/Voyage.01.Chapter 1_ The Crossing.mp3"));
+    assert_eq!(chapters::chapter_output(output, &chapters[2]), PathBuf::from("/books/Voyage.03.mp3"));
+}
+
+#[test]
+fn test_split_chapters_conflicts_with_time_ranges() {
+    let parse = |extra: &[&str]| Args::try_parse_from(["audio_extractor", "-i", "in.m4b", "-o", "out.mp3"].iter().chain(extra));
+    assert!(parse(&["--split-chapters"]).unwrap().split_chapters);
+    assert!(parse(&["--split-chapters", "--start", "10"]).is_err());
+    assert!(parse(&["--split-chapters", "--all-tracks"]).is_err());
+}
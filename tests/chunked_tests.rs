@@ -0,0 +1,115 @@
+use audio_extractor::chunked::{self, ChunkDir, ChunkEdges, JoinCheck};
+use audio_extractor::flac::FlacWriter;
+use std::fs;
+use std::path::PathBuf;
+use tempfile::TempDir;
+
+#[test]
+fn test_plan_covers_duration_without_gaps() {
+    let chunks = chunked::plan(4000.0, 1800.0);
+    assert_eq!(chunks.len(), 3);
+    assert_eq!(chunks[0].start, 0.0);
+    assert_eq!(chunks[2].end, 4000.0);
+    assert_eq!(chunks[2].duration(), 400.0);
+    for pair in chunks.windows(2) {
+        assert_eq!(pair[0].end, pair[1].start);
+    }
+    
+    assert_eq!(chunked::plan(1800.0, 1800.0).len(), 1);
+    assert!(chunked::plan(0.0, 1800.0).is_empty());
+}
+
+#[test]
+fn test_concat_list_escapes_quotes() {
+    let chunks = vec![PathBuf::from("/out/.a.chunks/chunk_0001.flac"), PathBuf::from("/out/it's/chunk_0002.flac")];
+    assert_eq!(
+        chunked::concat_list(&chunks),
+        "file '/out/.a.chunks/chunk_0001.flac'\nfile '/out/it'\\''s/chunk_0002.flac'\n"
+    );
+}
+
+#[test]
+fn test_chunk_dir_lives_next_to_output_and_is_removed() {
+    let dir = TempDir::new().unwrap();
+    let output = dir.path().join("stream.mp3");
+    let path;
+    {
+        let chunks = ChunkDir::create(&output).unwrap();
+        path = chunks.path().to_path_buf();
+        assert_eq!(path, dir.path().join(".stream.mp3.chunks"));
+        assert_eq!(chunks.chunk(0), path.join("chunk_0001.flac"));
+        
+        fs::write(chunks.chunk(0), b"flac").unwrap();
+        let list = chunks.write_list(&[chunks.chunk(0)]).unwrap();
+        assert!(fs::read_to_string(list).unwrap().contains("chunk_0001.flac"));
+    }
+    assert!(!path.exists());
+}
+
+/// Deterministic noise, which only lines up with itself at one offset
+fn noise(len: usize) -> Vec<f32> {
+    let mut state = 42u32;
+    (0..len)
+        .map(|_| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (state >> 16) as f32 / 32768.0 - 1.0
+        })
+        .collect()
+}
+
+#[test]
+fn test_join_check_finds_dropped_and_repeated_samples() {
+    let source = noise(2000);
+    let (lag, window) = (20, 400);
+    // The reference starts `lag` samples before the tail; the join is at 1000
+    let reference = &source[1000 - window - lag..1000 + window + lag];
+    let tail = &source[1000 - window..1000];
+    
+    let exact = JoinCheck::new(60.0, reference, tail, &source[1000..1000 + window], lag);
+    assert_eq!((exact.tail_lag, exact.head_lag, exact.gap()), (Some(0), Some(0), 0));
+    assert!(exact.exact());
+    assert!(exact.correlation > 0.999);
+    
+    let dropped = JoinCheck::new(60.0, reference, tail, &source[1003..1003 + window], lag);
+    assert_eq!(dropped.gap(), 3);
+    assert!(!dropped.exact());
+    assert_eq!(dropped.to_string(), "3 sample(s) missing at 00:01:00.000");
+    
+    let repeated = JoinCheck::new(60.0, reference, tail, &source[998..998 + window], lag);
+    assert_eq!(repeated.gap(), -2);
+    assert!(repeated.to_string().contains("2 sample(s) repeated"));
+}
+
+#[test]
+fn test_join_check_ignores_where_the_reference_starts() {
+    let source = noise(2000);
+    let (lag, window) = (20, 400);
+    // Both edges land 5 samples late: the reference seek was off, not the join
+    let reference = &source[1000 - window - lag - 5..1000 + window + lag - 5];
+    let check = JoinCheck::new(60.0, reference, &source[1000 - window..1000], &source[1000..1000 + window], lag);
+    assert_eq!(check.tail_lag, Some(5));
+    assert!(check.exact());
+    
+    // A silent side cannot be placed and does not fail the check
+    let silence = vec![0.0; window];
+    let check = JoinCheck::new(60.0, reference, &silence, &source[1000..1000 + window], lag);
+    assert_eq!(check.tail_lag, None);
+    assert!(check.exact());
+}
+
+#[test]
+fn test_chunk_edges_keep_both_ends_downmixed() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("chunk_0001.flac");
+    let samples: Vec<i16> = (0..1000i16).flat_map(|i| [i, i + 2]).collect();
+    let mut writer = FlacWriter::new(fs::File::create(&path).unwrap(), 1000, 2).unwrap();
+    writer.write(&samples).unwrap();
+    writer.finish().unwrap();
+    
+    let edges = ChunkEdges::read(&path, 0.1).unwrap();
+    assert_eq!((edges.sample_rate, edges.channels), (1000, 2));
+    assert_eq!(edges.head.len(), 100);
+    assert_eq!(edges.tail.len(), 100);
+    assert_eq!(edges.head[0], 1.0 / 32768.0);
+    assert_eq!(edges.tail[99], 1000.0 / 32768.0);
+}
@@ -0,0 +1,38 @@
+use audio_extractor::OutputClaims;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+
+#[test]
+fn test_colliding_outputs_get_deterministic_suffixes() {
+    let claims = OutputClaims::new();
+    let first = claims.claim(Path::new("/a/intro.mp4"), PathBuf::from("/out/intro.mp3"));
+    let second = claims.claim(Path::new("/b/intro.mp4"), PathBuf::from("/out/intro.mp3"));
+    assert_eq!(first, PathBuf::from("/out/intro.mp3"));
+    assert_ne!(second, first);
+    assert_eq!(second.extension().unwrap(), "mp3");
+    assert!(second.file_stem().unwrap().to_str().unwrap().starts_with("intro_"));
+    
+    // The same input always gets the same suffix, in any batch
+    let again = OutputClaims::new();
+    again.claim(Path::new("/c/intro.mp4"), PathBuf::from("/out/intro.mp3"));
+    assert_eq!(again.claim(Path::new("/b/intro.mp4"), PathBuf::from("/out/intro.mp3")), second);
+    
+    // Claiming twice for one input is idempotent
+    assert_eq!(claims.claim(Path::new("/b/intro.mp4"), PathBuf::from("/out/intro.mp3")), second);
+}
+
+#[test]
+fn test_parallel_claims_are_unique() {
+    let claims = Arc::new(OutputClaims::new());
+    let handles: Vec<_> = (0..8)
+        .map(|i| {
+            let claims = Arc::clone(&claims);
+            thread::spawn(move || claims.claim(&PathBuf::from(format!("/dir{}/clip.mp4", i)), PathBuf::from("/out/clip.wav")))
+        })
+        .collect();
+    let mut outputs: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+    outputs.sort();
+    outputs.dedup();
+    assert_eq!(outputs.len(), 8);
+}
@@ -0,0 +1,50 @@
+use audio_extractor::clean::CleanPlan;
+use audio_extractor::sidecar;
+use audio_extractor::{BatchReport, Disposal, ReportEntry};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+fn entry(output: &Path, success: bool, size: Option<u64>) -> ReportEntry {
+    ReportEntry {
+        input: output.with_extension("mp4"),
+        output: success.then(|| output.to_path_buf()),
+        success,
+        error: (!success).then(|| "ffmpeg failed".to_string()),
+        size,
+        loudness: None,
+        analysis: None,
+        shortened_from: None,
+    }
+}
+
+#[test]
+fn test_clean_removes_a_runs_outputs() {
+    let dir = TempDir::new().unwrap();
+    let path = |name: &str| dir.path().join(name);
+    fs::write(path("a.mp3"), b"aaaa").unwrap();
+    fs::write(sidecar::nfo_path(&path("a.mp3")), b"<nfo/>").unwrap();
+    fs::write(path("b.mp3"), b"bbbb, edited since").unwrap();
+    fs::write(path("d.mp3"), b"not from this run").unwrap();
+    
+    let report = BatchReport::new(vec![
+        entry(&path("a.mp3"), true, Some(4)),
+        entry(&path("b.mp3"), true, Some(4)),
+        entry(&path("c.mp3"), true, Some(4)),
+        entry(&path("d.mp3"), false, None),
+    ]);
+    let plan = CleanPlan::new(&report, false);
+    assert_eq!(plan.remove, vec![path("a.mp3"), sidecar::nfo_path(&path("a.mp3"))]);
+    let kept: Vec<PathBuf> = plan.keep.iter().map(|(path, _)| path.clone()).collect();
+    assert_eq!(kept, vec![path("b.mp3"), path("c.mp3")]);
+    assert!(plan.keep[0].1.contains("changed"));
+    assert!(plan.to_string().contains("2 file(s) to remove, 2 output(s) kept"));
+    
+    assert_eq!(plan.execute(&Disposal::Delete).unwrap(), 2);
+    assert!(!path("a.mp3").exists());
+    assert!(path("b.mp3").exists());
+    assert!(path("d.mp3").exists());
+    
+    // --force also removes changed outputs
+    assert_eq!(CleanPlan::new(&report, true).remove, vec![path("b.mp3")]);
+}
@@ -3,7 +3,8 @@ use predicates::prelude::*;
 use tempfile::{tempdir, NamedTempFile};
 use std::fs;
 use std::path::PathBuf;
-use audio_extractor::{Args, AudioFormat, AudioExtractor};
+use audio_extractor::{Args, AudioFormat, AudioExtractor, ResourceLimits};
+use std::time::Duration;
 
 mod common;
 
@@ -35,6 +36,11 @@ fn test_cli_missing_arguments() {
 
 #[test]
 fn test_cli_successful_extraction() {
+    // The default MP3 output needs FFmpeg
+    if !common::ffmpeg_available() {
+        return;
+    }
+    
     // Create a temporary video file
     let temp_input = common::create_test_video_file();
     
@@ -77,6 +83,10 @@ fn test_cli_with_format_option() {
 
 #[test]
 fn test_cli_with_quality_option() {
+    if !common::ffmpeg_available() {
+        return;
+    }
+    
     let temp_input = common::create_test_video_file();
     
     let temp_dir = tempdir().unwrap();
@@ -131,6 +141,10 @@ fn test_cli_invalid_format() {
 
 #[test]
 fn test_cli_short_flags() {
+    if !common::ffmpeg_available() {
+        return;
+    }
+    
     let temp_input = common::create_test_video_file();
     
     let temp_dir = tempdir().unwrap();
@@ -154,7 +168,8 @@ fn test_cli_short_flags() {
 fn test_cli_all_supported_formats() {
     let formats = vec!["mp3", "wav", "flac", "aac"];
     
-    for format in formats {
+    // Without FFmpeg the native backend writes the lossless formats only
+    for format in formats.into_iter().filter(|f| common::ffmpeg_available() || ["wav", "flac"].contains(f)) {
         let temp_input = common::create_test_video_file();
         
         let temp_dir = tempdir().unwrap();
@@ -175,6 +190,10 @@ fn test_cli_all_supported_formats() {
 
 #[test]
 fn test_cli_various_quality_settings() {
+    if !common::ffmpeg_available() {
+        return;
+    }
+    
     let qualities = vec!["64", "128", "192", "256", "320"];
     
     for quality in qualities {
@@ -198,6 +217,10 @@ fn test_cli_various_quality_settings() {
 
 #[test]
 fn test_cli_with_verify_option() {
+    if !common::ffmpeg_available() {
+        return;
+    }
+    
     // Create a temporary video file
     let temp_input = common::create_test_video_file();
     
@@ -231,6 +254,10 @@ fn test_cli_help_shows_verify_option() {
 
 #[test]
 fn test_full_workflow() {
+    if !common::ffmpeg_available() {
+        return;
+    }
+    
     // Create a temporary video file
     let temp_input = common::create_test_video_file();
     
@@ -245,6 +272,7 @@ fn test_full_workflow() {
         format: Some(AudioFormat::Mp3),
         quality: Some(192),
         verify: false,
+        ..Default::default()
     };
     
     // Create extractor and run full workflow
@@ -275,6 +303,9 @@ fn test_multiple_extractions() {
     ];
     
     for (format, filename, quality) in test_cases {
+        if !common::ffmpeg_available() && !audio_extractor::native::supports(&format) {
+            continue;
+        }
         let output_path = temp_dir.path().join(filename);
         let mut args = common::create_test_args(temp_input.path().to_path_buf(), output_path.clone());
         args.format = Some(format);
@@ -297,6 +328,7 @@ fn test_error_handling_chain() {
         format: Some(AudioFormat::Mp3),
         quality: Some(128),
         verify: false,
+        ..Default::default()
     };
     
     let extractor1 = AudioExtractor::new(args1);
@@ -312,8 +344,368 @@ fn test_error_handling_chain() {
         format: Some(AudioFormat::Mp3),
         quality: Some(128),
         verify: false,
+        ..Default::default()
     };
     
     let extractor2 = AudioExtractor::new(args2);
     assert!(extractor2.extract().is_err());
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_cli_help_shows_resource_limit_options() {
+    let mut cmd = Command::cargo_bin("audio_extractor").unwrap();
+    cmd.arg("--help");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("--max-cpu-time"))
+        .stdout(predicate::str::contains("--max-memory"))
+        .stdout(predicate::str::contains("--timeout"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_resource_limits_wall_time() {
+    let limits = ResourceLimits {
+        wall_time: Some(Duration::from_millis(200)),
+        ..Default::default()
+    };
+    
+    let mut cmd = std::process::Command::new("sleep");
+    cmd.arg("5");
+    let err = limits.output(&mut cmd).unwrap_err();
+    assert!(err.to_string().contains("time limit"));
+    
+    let mut cmd = std::process::Command::new("echo");
+    cmd.arg("hello");
+    let output = limits.output(&mut cmd).unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "hello");
+}
+
+#[test]
+fn test_cli_audit_log_records_success_and_failure() {
+    let temp_input = common::create_test_video_file();
+    let temp_dir = tempdir().unwrap();
+    let audit_log = temp_dir.path().join("audit.jsonl");
+    
+    let mut cmd = Command::cargo_bin("audio_extractor").unwrap();
+    cmd.arg("--input")
+        .arg(temp_input.path())
+        .arg("--output")
+        .arg(temp_dir.path().join("output.wav"))
+        .arg("--format")
+        .arg("wav")
+        .arg("--audit-log")
+        .arg(&audit_log);
+    cmd.assert().success();
+    
+    let mut cmd = Command::cargo_bin("audio_extractor").unwrap();
+    cmd.arg("--input")
+        .arg("/nonexistent/file.mp4")
+        .arg("--output")
+        .arg(temp_dir.path().join("missing.mp3"))
+        .arg("--audit-log")
+        .arg(&audit_log);
+    cmd.assert().failure();
+    
+    let content = fs::read_to_string(&audit_log).unwrap();
+    let records: Vec<serde_json::Value> = content
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0]["operation"], "extract");
+    assert_eq!(records[0]["success"], true);
+    assert_eq!(records[0]["settings"]["format"], "wav");
+    assert_eq!(records[1]["success"], false);
+    assert!(records[1]["error"].as_str().unwrap().contains("Input file does not exist"));
+}
+
+#[test]
+fn test_cli_rejects_invalid_mute_range() {
+    let temp_input = common::create_test_video_file();
+    let temp_dir = tempdir().unwrap();
+    
+    let mut cmd = Command::cargo_bin("audio_extractor").unwrap();
+    cmd.arg("--input")
+        .arg(temp_input.path())
+        .arg("--output")
+        .arg(temp_dir.path().join("output.mp3"))
+        .arg("--mute")
+        .arg("00:00:30-00:00:10");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("end must be after start"));
+}
+
+#[test]
+fn test_cli_detect_language_conflicts_with_encryption() {
+    let temp_input = common::create_test_video_file();
+    let temp_dir = tempdir().unwrap();
+    
+    let mut cmd = Command::cargo_bin("audio_extractor").unwrap();
+    cmd.arg("--input")
+        .arg(temp_input.path())
+        .arg("--output")
+        .arg(temp_dir.path().join("output.mp3"))
+        .arg("--detect-language")
+        .arg("--encrypt-to")
+        .arg("legal@example.com");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_cli_embed_cover_conflicts_with_encryption() {
+    let temp_input = common::create_test_video_file();
+    let temp_dir = tempdir().unwrap();
+    
+    let mut cmd = Command::cargo_bin("audio_extractor").unwrap();
+    cmd.arg("--input")
+        .arg(temp_input.path())
+        .arg("--output")
+        .arg(temp_dir.path().join("output.mp3"))
+        .arg("--embed-cover")
+        .arg("--encrypt-to")
+        .arg("legal@example.com");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_cli_directory_scan_logs_unsupported_inputs() {
+    let source = tempdir().unwrap();
+    let output = tempdir().unwrap();
+    fs::create_dir(source.path().join("season1")).unwrap();
+    fs::copy(common::create_test_video_file().path(), source.path().join("season1/ep1.mp4")).unwrap();
+    fs::write(source.path().join("notes.txt"), "not media").unwrap();
+    
+    let mut cmd = Command::cargo_bin("audio_extractor").unwrap();
+    cmd.arg("--input")
+        .arg(source.path())
+        .arg("--output")
+        .arg(output.path())
+        .arg("--format")
+        .arg("wav");
+    cmd.assert().success();
+    
+    assert!(output.path().join("season1/ep1.wav").exists());
+    let log = fs::read_to_string(output.path().join("unsupported.log")).unwrap();
+    assert_eq!(log.lines().count(), 1);
+    assert!(log.contains("notes.txt\tunsupported file type"));
+}
+
+#[test]
+fn test_cli_fill_gaps_optional_threshold() {
+    // Timestamp repair is an FFmpeg filter
+    if !common::ffmpeg_available() {
+        return;
+    }
+    
+    let temp_input = common::create_test_video_file();
+    let temp_dir = tempdir().unwrap();
+    
+    for extra in [vec!["--fill-gaps"], vec!["--fill-gaps", "0.5"]] {
+        let mut cmd = Command::cargo_bin("audio_extractor").unwrap();
+        cmd.arg("--input")
+            .arg(temp_input.path())
+            .arg("--output")
+            .arg(temp_dir.path().join("output.mp3"))
+            .args(extra);
+        cmd.assert().success();
+    }
+}
+
+#[test]
+fn test_cli_subcommand_without_input() {
+    let dir = tempdir().unwrap();
+    let mut cmd = Command::cargo_bin("audio_extractor").unwrap();
+    cmd.arg("status").arg(dir.path().join("missing.json"));
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to read status file"));
+}
+
+#[test]
+fn test_faststart_needs_aac_in_mp4() {
+    let args = |output: &str, format: AudioFormat| Args {
+        output: PathBuf::from(output),
+        format: Some(format),
+        faststart: true,
+        ..Default::default()
+    };
+    
+    let extractor = AudioExtractor::new(args("book.m4b", AudioFormat::Aac));
+    assert_eq!(extractor.container_args().unwrap(), vec!["-movflags", "+faststart"]);
+    assert!(AudioExtractor::new(args("book.aac", AudioFormat::Aac)).container_args().is_err());
+    assert!(AudioExtractor::new(args("book.m4a", AudioFormat::Mp3)).container_args().is_err());
+    
+    let fragmented = Args { faststart: false, fragmented: true, ..args("live.MP4", AudioFormat::Aac) };
+    assert_eq!(AudioExtractor::new(fragmented).container_args().unwrap()[1], "+frag_keyframe+empty_moov+default_base_moof");
+    assert!(AudioExtractor::new(Args { faststart: false, ..args("a.mp3", AudioFormat::Mp3) }).container_args().unwrap().is_empty());
+}
+
+#[test]
+fn test_id3_options_need_mp3() {
+    use audio_extractor::id3::{Id3Version, TextEncoding};
+    let args = |format: AudioFormat, version: Option<Id3Version>, encoding: Option<TextEncoding>| Args {
+        output: PathBuf::from("song.mp3"),
+        format: Some(format),
+        id3_version: version,
+        id3_encoding: encoding,
+        ..Default::default()
+    };
+    
+    let extractor = AudioExtractor::new(args(AudioFormat::Mp3, Some(Id3Version::V23), None));
+    assert_eq!(extractor.id3_args().unwrap(), vec!["-id3v2_version", "3"]);
+    let extractor = AudioExtractor::new(args(AudioFormat::Mp3, None, Some(TextEncoding::Utf8)));
+    assert_eq!(extractor.id3_args().unwrap(), vec!["-id3v2_version", "4"]);
+    assert!(AudioExtractor::new(args(AudioFormat::Mp3, Some(Id3Version::V23), Some(TextEncoding::Utf8))).id3_args().is_err());
+    assert!(AudioExtractor::new(args(AudioFormat::Flac, Some(Id3Version::V23), None)).id3_args().is_err());
+    assert!(AudioExtractor::new(args(AudioFormat::Flac, None, None)).id3_args().unwrap().is_empty());
+}
+
+#[test]
+fn test_time_range_args() {
+    let args = |start: Option<f64>, end: Option<f64>, duration: Option<f64>| Args {
+        start,
+        end,
+        duration,
+        ..Default::default()
+    };
+    
+    let extractor = AudioExtractor::new(args(Some(750.5), Some(3600.0), None));
+    assert_eq!(extractor.time_range_args(Some(5400.0)).unwrap(), vec!["-ss", "750.500", "-to", "3600.000"]);
+    let extractor = AudioExtractor::new(args(None, None, Some(90.0)));
+    assert_eq!(extractor.time_range_args(None).unwrap(), vec!["-t", "90.000"]);
+    assert!(AudioExtractor::new(args(None, None, None)).time_range_args(Some(10.0)).unwrap().is_empty());
+    
+    let err = AudioExtractor::new(args(Some(60.0), Some(30.0), None)).time_range_args(None).unwrap_err();
+    assert!(err.to_string().contains("must be after"));
+    let err = AudioExtractor::new(args(Some(6000.0), None, None)).time_range_args(Some(5400.0)).unwrap_err();
+    assert!(err.to_string().contains("past the end"));
+    assert!(AudioExtractor::new(args(None, Some(6000.0), None)).time_range_args(Some(5400.0)).is_err());
+}
+
+#[test]
+fn test_end_conflicts_with_duration() {
+    let mut cmd = Command::cargo_bin("audio_extractor").unwrap();
+    cmd.args(["--input", "a.mp4", "--output", "a.mp3", "--end", "10", "--duration", "5"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_chunked_defaults_to_thirty_minutes() {
+    use clap::Parser;
+    
+    let args = Args::try_parse_from(["audio_extractor", "-i", "a.mp4", "-o", "a.mp3", "--chunked"]).unwrap();
+    assert_eq!(args.chunked, Some(audio_extractor::chunked::DEFAULT_CHUNK_MINUTES));
+    let args = Args::try_parse_from(["audio_extractor", "-i", "a.mp4", "-o", "a.mp3", "--chunked", "20"]).unwrap();
+    assert_eq!(args.chunked, Some(20));
+    assert!(Args::try_parse_from(["audio_extractor", "-i", "a.mp4", "-o", "a.mp3", "--chunked", "--start", "10"]).is_err());
+}
+
+#[test]
+fn test_cli_directory_scan_runs_jobs_in_parallel() {
+    let source = tempdir().unwrap();
+    let output = tempdir().unwrap();
+    for name in ["a.mp4", "b.mp4", "c.mp4"] {
+        fs::copy(common::create_test_video_file().path(), source.path().join(name)).unwrap();
+    }
+    
+    let mut cmd = Command::cargo_bin("audio_extractor").unwrap();
+    cmd.arg("--input")
+        .arg(source.path())
+        .arg("--output")
+        .arg(output.path())
+        .arg("--format")
+        .arg("wav")
+        .arg("--jobs")
+        .arg("auto");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("--jobs auto:"));
+    
+    for name in ["a.wav", "b.wav", "c.wav"] {
+        assert!(output.path().join(name).exists());
+    }
+}
+
+#[test]
+fn test_cli_batch_subcommand() {
+    let dir = tempdir().unwrap();
+    let videos = dir.path().join("videos");
+    fs::create_dir_all(videos.join("more")).unwrap();
+    common::write_test_tone(&videos.join("one.mp4"));
+    common::write_test_tone(&videos.join("more/two.mkv"));
+    fs::write(videos.join("broken.mp4"), b"not a video").unwrap();
+    let out = dir.path().join("audio");
+    
+    let mut cmd = Command::cargo_bin("audio_extractor").unwrap();
+    cmd.current_dir(dir.path())
+        .arg("batch")
+        .arg("-i").arg(&videos)
+        .arg("--recursive")
+        .arg("--output-dir").arg(&out)
+        .arg("--format").arg("wav")
+        .arg("--jobs").arg("2");
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("1 failed"))
+        .stdout(predicate::str::contains("❌"))
+        .stdout(predicate::str::contains("broken.mp4"));
+    assert!(out.join("one.wav").exists());
+    assert!(out.join("two.wav").exists());
+    
+    let mut cmd = Command::cargo_bin("audio_extractor").unwrap();
+    cmd.current_dir(dir.path())
+        .arg("batch")
+        .arg("-i").arg("videos/**/*.mkv")
+        .arg("-o").arg(&out)
+        .arg("-f").arg("wav");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Batch finished: 1 succeeded, 0 failed"));
+}
+
+#[test]
+fn test_cli_output_template() {
+    let dir = tempdir().unwrap();
+    let videos = dir.path().join("videos");
+    fs::create_dir_all(videos.join("Season 1")).unwrap();
+    common::write_test_tone(&videos.join("Season 1/e01.mp4"));
+    common::write_test_tone(&videos.join("Season 1/e02.mp4"));
+    
+    let mut cmd = Command::cargo_bin("audio_extractor").unwrap();
+    cmd.arg("batch")
+        .arg("-i").arg(videos.join("Season 1/*.mp4"))
+        .arg("-o").arg(dir.path().join("batch"))
+        .arg("--output-template").arg("{parent}/{stem}-{bitrate}.{format}")
+        .arg("-f").arg("wav")
+        .arg("-q").arg("256");
+    cmd.assert().success();
+    assert!(dir.path().join("batch/Season 1/e01-256.wav").exists());
+    assert!(dir.path().join("batch/Season 1/e02-256.wav").exists());
+    
+    let mut cmd = Command::cargo_bin("audio_extractor").unwrap();
+    cmd.current_dir(dir.path())
+        .arg("-i").arg(&videos)
+        .arg("-o").arg(dir.path().join("scan"))
+        .arg("--output-template").arg("{format}/{parent} {stem}.{format}")
+        .arg("-f").arg("wav");
+    cmd.assert().success();
+    assert!(dir.path().join("scan/wav/Season 1 e01.wav").exists());
+    
+    let mut cmd = Command::cargo_bin("audio_extractor").unwrap();
+    cmd.arg("-i").arg(videos.join("Season 1/e01.mp4"))
+        .arg("-o").arg(dir.path().join("single"))
+        .arg("--output-template").arg("{stem}.{format}")
+        .arg("-f").arg("wav");
+    cmd.assert().success();
+    assert!(dir.path().join("single/e01.wav").exists());
+}
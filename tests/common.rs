@@ -1,9 +1,11 @@
+use audio_extractor::native::WavWriter;
 use audio_extractor::{Args, AudioFormat};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tempfile::NamedTempFile;
 use std::fs;
 use std::process::Command;
 
+#[allow(dead_code)]
 pub fn create_test_video_file() -> NamedTempFile {
     let file = NamedTempFile::with_suffix(".mp4").unwrap();
     
@@ -14,7 +16,8 @@ pub fn create_test_video_file() -> NamedTempFile {
         .arg("-f").arg("lavfi")
         .arg("-i").arg("sine=frequency=1000:duration=1")
         .arg("-c:v").arg("libx264")
-        .arg("-c:a").arg("aac")
+        // Lossless, so extracting to MP3 or AAC is not refused as a lossy transcode
+        .arg("-c:a").arg("alac")
         .arg("-t").arg("1")
         .arg("-y") // Overwrite if exists
         .arg(file.path())
@@ -26,14 +29,15 @@ pub fn create_test_video_file() -> NamedTempFile {
             file
         }
         _ => {
-            // FFmpeg failed or not available, create a placeholder
-            // This will cause tests to be skipped or use fallback methods
-            fs::write(file.path(), b"fake video data").unwrap();
+            // FFmpeg failed or not available: write the tone as WAV, which
+            // the native backend decodes whatever the extension says
+            write_test_tone(file.path());
             file
         }
     }
 }
 
+#[allow(dead_code)]
 pub fn create_test_args(input: PathBuf, output: PathBuf) -> Args {
     Args {
         input,
@@ -41,5 +45,25 @@ pub fn create_test_args(input: PathBuf, output: PathBuf) -> Args {
         format: Some(AudioFormat::Mp3),
         quality: Some(128),
         verify: false,
+        ..Default::default()
     }
-}
\ No newline at end of file
+}
+
+/// One second of a 1 kHz sine at 44.1 kHz, mono, as 16-bit WAV
+#[allow(dead_code)]
+pub fn write_test_tone(path: &Path) {
+    let samples: Vec<i16> = (0..44_100)
+        .map(|i| ((i as f64 * 1000.0 * std::f64::consts::TAU / 44_100.0).sin() * 16_000.0) as i16)
+        .collect();
+    let file = fs::File::create(path).unwrap();
+    let mut wav = WavWriter::new(file, 44_100, 1).unwrap();
+    wav.write(&samples).unwrap();
+    wav.finish().unwrap();
+}
+
+/// Whether lossy formats and filters can be tested; without FFmpeg only the
+/// native backend's WAV and FLAC output is available
+#[allow(dead_code)]
+pub fn ffmpeg_available() -> bool {
+    Command::new("ffmpeg").arg("-version").output().is_ok()
+}
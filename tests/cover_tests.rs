@@ -0,0 +1,70 @@
+mod common;
+
+use audio_extractor::cover::{self, CoverSource};
+use audio_extractor::{Args, AudioExtractor, AudioFormat};
+use clap::Parser;
+use std::path::Path;
+
+#[test]
+fn test_attached_picture() {
+    let json = r#"{"streams": [
+        {"index": 0, "codec_type": "video", "codec_name": "h264", "disposition": {"attached_pic": 0}},
+        {"index": 1, "codec_type": "audio", "codec_name": "aac"},
+        {"index": 2, "codec_type": "video", "codec_name": "mjpeg", "disposition": {"attached_pic": 1}}
+    ]}"#;
+    assert_eq!(cover::attached_picture(json).unwrap(), Some(2));
+    assert_eq!(cover::attached_picture(r#"{"streams": [{"index": 0, "codec_type": "video"}]}"#).unwrap(), None);
+    assert!(cover::attached_picture("not json").is_err());
+}
+
+#[test]
+fn test_choose_source() {
+    // The attached thumbnail wins unless a frame is asked for
+    assert_eq!(CoverSource::choose(Some(2), None, Some(600.0)), CoverSource::Attached(2));
+    assert_eq!(CoverSource::choose(Some(2), Some(90.0), Some(600.0)), CoverSource::Frame(90.0));
+    assert_eq!(CoverSource::choose(None, None, Some(600.0)), CoverSource::Frame(60.0));
+}
+
+#[test]
+fn test_check_format() {
+    assert!(cover::check_format(&AudioFormat::Mp3, Path::new("a.mp3")).is_ok());
+    assert!(cover::check_format(&AudioFormat::Flac, Path::new("a.flac")).is_ok());
+    assert!(cover::check_format(&AudioFormat::Aac, Path::new("a.m4a")).is_ok());
+    assert!(cover::check_format(&AudioFormat::Aac, Path::new("a.aac")).is_err());
+    assert!(cover::check_format(&AudioFormat::Wav, Path::new("a.wav")).is_err());
+}
+
+#[test]
+fn test_embed_args() {
+    let args = cover::embed_args(&AudioFormat::Mp3);
+    assert!(args.windows(2).any(|w| w == ["-disposition:v:0", "attached_pic"]));
+    assert!(args.contains(&"comment=Cover (front)"));
+    assert!(!cover::embed_args(&AudioFormat::Flac).contains(&"comment=Cover (front)"));
+}
+
+#[test]
+fn test_cover_at_needs_a_cover_flag() {
+    let parse = |extra: &[&str]| {
+        Args::try_parse_from(["audio_extractor", "-i", "in.mp4", "-o", "out.mp3"].iter().chain(extra))
+    };
+    assert!(parse(&["--cover-at", "00:01:30"]).is_err());
+    assert_eq!(parse(&["--embed-cover", "--cover-at", "00:01:30"]).unwrap().cover_at, Some(90.0));
+    assert!(parse(&["--embed-cover", "--cover-out", "cover.png"]).is_ok());
+}
+
+#[test]
+fn test_embed_cover_refuses_wav_before_extracting() {
+    let input = common::create_test_video_file();
+    let dir = tempfile::TempDir::new().unwrap();
+    let output = dir.path().join("out.wav");
+    let args = Args {
+        input: input.path().to_path_buf(),
+        output: output.clone(),
+        format: Some(AudioFormat::Wav),
+        embed_cover: true,
+        ..Default::default()
+    };
+    let err = AudioExtractor::new(args).extract().unwrap_err();
+    assert!(format!("{:#}", err).contains("--embed-cover needs MP3, FLAC or AAC"));
+    assert!(!output.exists());
+}
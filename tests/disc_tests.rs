@@ -0,0 +1,97 @@
+use audio_extractor::disc::{self, Disc, DiscKind, Title};
+use audio_extractor::{Args, AudioExtractor};
+use std::ffi::OsString;
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_detect_disc_structures() {
+    let root = TempDir::new().unwrap();
+    let dvd = root.path().join("wedding");
+    fs::create_dir_all(dvd.join("VIDEO_TS")).unwrap();
+    let bluray = root.path().join("holiday");
+    fs::create_dir_all(bluray.join("BDMV/PLAYLIST")).unwrap();
+    
+    assert_eq!(Disc::detect(&dvd), Some(Disc { path: dvd.clone(), kind: DiscKind::Dvd }));
+    assert_eq!(Disc::detect(&dvd.join("VIDEO_TS")), Some(Disc { path: dvd.clone(), kind: DiscKind::Dvd }));
+    assert_eq!(Disc::detect(&bluray).unwrap().kind, DiscKind::Bluray);
+    assert_eq!(Disc::detect(root.path()), None);
+    
+    let dvd_iso = root.path().join("dvd.iso");
+    fs::write(&dvd_iso, b"CD001 VIDEO_TS.IFO").unwrap();
+    assert_eq!(Disc::detect(&dvd_iso).unwrap().kind, DiscKind::Dvd);
+    
+    let bd_iso = root.path().join("bd.ISO");
+    fs::write(&bd_iso, b"CD001 BDMV INDEX.BDMV").unwrap();
+    assert_eq!(Disc::detect(&bd_iso).unwrap().kind, DiscKind::Bluray);
+    
+    let video = root.path().join("clip.mp4");
+    fs::write(&video, b"x").unwrap();
+    assert_eq!(Disc::detect(&video), None);
+}
+
+#[test]
+fn test_disc_input_args() {
+    let dvd = Disc { path: "/discs/wedding".into(), kind: DiscKind::Dvd };
+    let args: Vec<OsString> = ["-f", "dvdvideo", "-title", "3", "-i", "/discs/wedding"].iter().map(OsString::from).collect();
+    assert_eq!(dvd.input_args(Some(3)), args);
+    
+    let bluray = Disc { path: "/discs/holiday".into(), kind: DiscKind::Bluray };
+    let args: Vec<OsString> = ["-playlist", "800", "-i", "bluray:/discs/holiday"].iter().map(OsString::from).collect();
+    assert_eq!(bluray.input_args(Some(800)), args);
+    assert_eq!(bluray.input_args(None), vec![OsString::from("-i"), OsString::from("bluray:/discs/holiday")]);
+}
+
+#[test]
+fn test_longest_title() {
+    let titles = vec![
+        Title { index: 1, duration: 12.0 },
+        Title { index: 2, duration: 5400.0 },
+        Title { index: 3, duration: 300.0 },
+    ];
+    assert_eq!(disc::longest(&titles).unwrap().index, 2);
+    assert!(disc::longest(&[]).is_none());
+}
+
+#[test]
+fn test_disc_folder_is_a_valid_input() {
+    let root = TempDir::new().unwrap();
+    fs::create_dir_all(root.path().join("VIDEO_TS")).unwrap();
+    
+    let args = Args { input: root.path().to_path_buf(), ..Default::default() };
+    AudioExtractor::new(args).validate_input().unwrap();
+}
+
+#[test]
+fn test_title_output_template() {
+    let output = std::path::Path::new("/out/wedding.flac");
+    assert_eq!(
+        disc::title_output(output, disc::DEFAULT_TITLE_TEMPLATE, 3).unwrap(),
+        std::path::PathBuf::from("/out/wedding_t03.flac")
+    );
+    assert_eq!(
+        disc::title_output(output, "{title} - {name}.{ext}", 12).unwrap(),
+        std::path::PathBuf::from("/out/12 - wedding.flac")
+    );
+    // A templated --output keeps its placeholders for the extraction to fill in
+    assert_eq!(
+        disc::title_output(std::path::Path::new("/out/{show}.flac"), disc::DEFAULT_TITLE_TEMPLATE, 1).unwrap(),
+        std::path::PathBuf::from("/out/{show}_t01.flac")
+    );
+    assert!(disc::title_output(output, "{chapter}.{ext}", 1).is_err());
+    assert_eq!(
+        disc::TitleReport::path_for(output),
+        std::path::PathBuf::from("/out/wedding.titles.json")
+    );
+}
+
+#[test]
+fn test_all_titles_needs_a_disc() {
+    let root = TempDir::new().unwrap();
+    let video = root.path().join("clip.mp4");
+    fs::write(&video, b"x").unwrap();
+    
+    let args = Args { input: video, all_titles: true, ..Default::default() };
+    let err = AudioExtractor::new(args).extract_all_titles().unwrap_err();
+    assert!(err.to_string().contains("--all-titles"));
+}
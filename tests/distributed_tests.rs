@@ -0,0 +1,164 @@
+use audio_extractor::AudioFormat;
+use audio_extractor::distributed::{self, CoordinatorConfig};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::thread;
+use std::time::Duration;
+use tempfile::tempdir;
+
+mod common;
+
+#[test]
+fn test_coordinator_and_workers_process_whole_batch() {
+    let inputs: Vec<_> = (0..3).map(|_| common::create_test_video_file()).collect();
+    let output_dir = tempdir().unwrap();
+    
+    // Reserve a free port for the coordinator
+    let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let config = CoordinatorConfig {
+        listen: format!("127.0.0.1:{}", port),
+        output_dir: output_dir.path().to_path_buf(),
+        format: AudioFormat::Wav,
+        quality: 128,
+        verify: false,
+        allow_lossy_transcode: false,
+        allow_remote: false,
+    };
+    
+    let paths = inputs.iter().map(|f| f.path().to_path_buf()).collect();
+    let coordinator = thread::spawn(move || distributed::run_coordinator(&config, paths));
+    
+    let workers: Vec<_> = (0..2)
+        .map(|_| {
+            thread::spawn(move || {
+                for _ in 0..50 {
+                    match distributed::run_worker(("127.0.0.1", port)) {
+                        Ok(completed) => return completed,
+                        Err(_) => thread::sleep(Duration::from_millis(100)),
+                    }
+                }
+                panic!("worker could not connect to coordinator");
+            })
+        })
+        .collect();
+    
+    let completed: usize = workers.into_iter().map(|w| w.join().unwrap()).sum();
+    let results = coordinator.join().unwrap().unwrap();
+    
+    assert_eq!(completed, 3);
+    assert_eq!(results.len(), 3);
+    for (input, result) in inputs.iter().zip(&results) {
+        let output = result.as_ref().unwrap();
+        assert_eq!(output.file_stem(), input.path().file_stem());
+        assert!(output.exists());
+    }
+}
+
+#[test]
+fn test_read_manifest_skips_comments_and_blank_lines() {
+    let dir = tempdir().unwrap();
+    let manifest = dir.path().join("batch.txt");
+    std::fs::write(&manifest, "# nightly batch\n/videos/a.mp4\n\n  /videos/b.mkv  \n").unwrap();
+    
+    let inputs = distributed::read_manifest(&manifest).unwrap();
+    assert_eq!(inputs.len(), 2);
+    assert_eq!(inputs[1].to_str().unwrap(), "/videos/b.mkv");
+}
+
+#[test]
+fn test_worker_refuses_input_names_outside_its_scratch_directory() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let worker = thread::spawn(move || distributed::run_worker(addr));
+    
+    let (stream, _) = listener.accept().unwrap();
+    let mut writer = stream.try_clone().unwrap();
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line).unwrap();
+    assert!(line.contains("request_job"));
+    
+    let escaped = format!("escaped-{}.mp4", std::process::id());
+    let job = serde_json::json!({
+        "type": "job",
+        "id": 0,
+        "file_name": format!("../{}", escaped),
+        "format": AudioFormat::Wav,
+        "quality": 128,
+        "verify": false,
+        "size": 5,
+    });
+    writer.write_all(format!("{}\nvideo", job).as_bytes()).unwrap();
+    
+    line.clear();
+    reader.read_line(&mut line).unwrap();
+    let result: serde_json::Value = serde_json::from_str(&line).unwrap();
+    assert_eq!(result["type"], "job_result");
+    assert!(result["error"].as_str().unwrap().contains("Refusing job input name"));
+    
+    line.clear();
+    reader.read_line(&mut line).unwrap();
+    assert!(line.contains("request_job"));
+    writer.write_all(b"{\"type\":\"no_more_jobs\"}\n").unwrap();
+    
+    assert_eq!(worker.join().unwrap().unwrap(), 0);
+    assert!(!std::env::temp_dir().join(&escaped).exists());
+}
+    
+#[test]
+fn test_coordinator_requeues_job_whose_upload_breaks_off() {
+    let input = common::create_test_video_file();
+    let output_dir = tempdir().unwrap();
+    let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let config = CoordinatorConfig {
+        listen: format!("127.0.0.1:{}", port),
+        output_dir: output_dir.path().to_path_buf(),
+        format: AudioFormat::Wav,
+        quality: 128,
+        verify: false,
+        allow_lossy_transcode: false,
+        allow_remote: false,
+    };
+    let paths = vec![input.path().to_path_buf()];
+    let coordinator = thread::spawn(move || distributed::run_coordinator(&config, paths));
+    
+    // A worker that takes the job and claims an upload far larger than memory
+    let stream = (0..50)
+        .find_map(|_| std::net::TcpStream::connect(("127.0.0.1", port)).ok().or_else(|| {
+            thread::sleep(Duration::from_millis(100));
+            None
+        }))
+        .expect("could not connect to coordinator");
+    let mut writer = stream.try_clone().unwrap();
+    let mut reader = BufReader::new(stream);
+    writer.write_all(b"{\"type\":\"request_job\",\"worker\":\"greedy\"}\n").unwrap();
+    let mut line = String::new();
+    reader.read_line(&mut line).unwrap();
+    let job: serde_json::Value = serde_json::from_str(&line).unwrap();
+    let mut data = vec![0u8; job["size"].as_u64().unwrap() as usize];
+    reader.read_exact(&mut data).unwrap();
+    writer.write_all(format!("{{\"type\":\"job_result\",\"id\":0,\"error\":null,\"size\":{}}}\nshort", u64::MAX).as_bytes()).unwrap();
+    writer.shutdown(std::net::Shutdown::Write).unwrap();
+    // The coordinator hangs up once it has put the job back on the queue
+    assert_eq!(reader.read(&mut [0u8; 1]).unwrap(), 0);
+    
+    assert_eq!(distributed::run_worker(("127.0.0.1", port)).unwrap(), 1);
+    let results = coordinator.join().unwrap().unwrap();
+    assert!(results[0].as_ref().unwrap().exists());
+}
+
+#[test]
+fn test_coordinator_refuses_non_loopback_address_without_allow_remote() {
+    let output_dir = tempdir().unwrap();
+    let config = CoordinatorConfig {
+        listen: "0.0.0.0:0".to_string(),
+        output_dir: output_dir.path().to_path_buf(),
+        format: AudioFormat::Wav,
+        quality: 128,
+        verify: false,
+        allow_lossy_transcode: false,
+        allow_remote: false,
+    };
+    let error = distributed::run_coordinator(&config, vec!["talk.mp4".into()]).unwrap_err();
+    assert!(error.to_string().contains("--allow-remote"));
+}
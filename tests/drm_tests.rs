@@ -0,0 +1,75 @@
+use audio_extractor::drm;
+use audio_extractor::{Args, AudioExtractor, ExtractorError};
+use std::fs;
+use tempfile::TempDir;
+
+fn mp4_box(kind: &[u8; 4], body: &[u8]) -> Vec<u8> {
+    let mut data = ((body.len() + 8) as u32).to_be_bytes().to_vec();
+    data.extend_from_slice(kind);
+    data.extend_from_slice(body);
+    data
+}
+
+fn write_mp4(dir: &TempDir, name: &str, moov_children: &[u8]) -> std::path::PathBuf {
+    let mut data = mp4_box(b"ftyp", b"isom\0\0\0\0isomiso2");
+    data.extend(mp4_box(b"free", &[0; 16]));
+    data.extend(mp4_box(b"moov", moov_children));
+    let path = dir.path().join(name);
+    fs::write(&path, data).unwrap();
+    path
+}
+
+#[test]
+fn test_detect_protection_boxes() {
+    let dir = TempDir::new().unwrap();
+    
+    let schm = mp4_box(b"schm", b"\0\0\0\0cbcs\0\x01\0\0");
+    let sinf = mp4_box(b"sinf", &[mp4_box(b"frma", b"mp4a"), schm].concat());
+    let protected = write_mp4(&dir, "protected.mp4", &mp4_box(b"trak", &sinf));
+    assert_eq!(drm::detect_protection_boxes(&protected).unwrap(), Some("cbcs".to_string()));
+    
+    let pssh_only = write_mp4(&dir, "pssh.mp4", &mp4_box(b"pssh", &[0; 24]));
+    assert_eq!(drm::detect_protection_boxes(&pssh_only).unwrap(), Some("cenc".to_string()));
+    
+    let clear = write_mp4(&dir, "clear.mp4", &mp4_box(b"trak", &mp4_box(b"mdia", &[0; 32])));
+    assert_eq!(drm::detect_protection_boxes(&clear).unwrap(), None);
+    
+    let not_mp4 = dir.path().join("fake.mkv");
+    fs::write(&not_mp4, b"fake video data").unwrap();
+    assert_eq!(drm::detect_protection_boxes(&not_mp4).unwrap(), None);
+}
+
+#[test]
+fn test_ffprobe_protection() {
+    let json = r#"{"streams": [
+        {"codec_type": "video", "codec_tag_string": "avc1"},
+        {"codec_type": "audio", "codec_tag_string": "enca"}
+    ]}"#;
+    assert_eq!(drm::ffprobe_protection(json, ""), Some("encrypted audio track (enca)".to_string()));
+    
+    let side_data = r#"{"streams": [{"codec_type": "audio", "side_data_list": [
+        {"side_data_type": "Encryption initialization data"}
+    ]}]}"#;
+    assert!(drm::ffprobe_protection(side_data, "").is_some());
+    
+    assert_eq!(drm::ffprobe_protection("{}", "[asf] DRM protected stream detected, decoding will likely fail!"),
+        Some("ASF DRM".to_string()));
+    assert_eq!(drm::ffprobe_protection(r#"{"streams": [{"codec_type": "audio"}]}"#, ""), None);
+}
+
+#[test]
+fn test_extract_fails_with_drm_protected() {
+    let dir = TempDir::new().unwrap();
+    let input = write_mp4(&dir, "movie.mp4", &mp4_box(b"pssh", &[0; 24]));
+    let output = dir.path().join("movie.mp3");
+    
+    let args = Args { input: input.clone(), output: output.clone(), ..Default::default() };
+    let err = AudioExtractor::new(args).extract().unwrap_err();
+    
+    let ExtractorError::DrmProtected(drm) = err else {
+        panic!("expected a DrmProtected error, got {:?}", err);
+    };
+    assert_eq!(drm.path, input);
+    assert_eq!(drm.scheme, "cenc");
+    assert!(!output.exists());
+}
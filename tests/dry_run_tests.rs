@@ -0,0 +1,69 @@
+mod common;
+
+use audio_extractor::analysis::{Segment, SegmentLabel};
+use audio_extractor::dry_run::Step;
+use audio_extractor::loudness::Measurement;
+use audio_extractor::timecode::TimeRange;
+use audio_extractor::{Args, AudioExtractor, AudioFormat, EffectChain, LoudnessTarget};
+use tempfile::TempDir;
+
+fn measured(integrated: f64, true_peak: f64) -> Measurement {
+    Measurement { integrated, true_peak, range: 6.0, threshold: -37.0, offset: 0.0 }
+}
+
+#[test]
+fn test_normalize_gain() {
+    let step = Step::normalize(LoudnessTarget::default(), measured(-24.0, -12.0));
+    assert!(matches!(step, Step::Normalize { gain_db, peak_after, limited: false, .. } if gain_db == 8.0 && peak_after == -4.0));
+    assert_eq!(step.to_string(), "Normalize: measured -24.0 LUFS and -12.0 dBTP, +8.0 dB to reach -16.0 LUFS, peak -4.0 dBTP");
+}
+
+#[test]
+fn test_normalize_reports_limiting() {
+    let step = Step::normalize(LoudnessTarget::default(), measured(-27.5, -4.5));
+    assert!(matches!(step, Step::Normalize { limited: true, .. }));
+    assert!(step.to_string().ends_with("peak 7.0 dBTP (over -1.5 dBTP, so it is limited)"));
+
+    let quieter = Step::normalize(LoudnessTarget::default(), measured(-10.0, -0.5));
+    assert!(matches!(quieter, Step::Normalize { gain_db, limited: false, .. } if gain_db == -6.0));
+}
+
+#[test]
+fn test_keep_segments_totals() {
+    let segments = [
+        Segment { label: SegmentLabel::Silence, start: 0.0, end: 2.0 },
+        Segment { label: SegmentLabel::Speech, start: 2.0, end: 10.0 },
+        Segment { label: SegmentLabel::Music, start: 10.0, end: 13.0 },
+        Segment { label: SegmentLabel::Speech, start: 13.0, end: 20.0 },
+    ];
+    let step = Step::keep_segments(&segments, &[SegmentLabel::Speech]);
+    assert_eq!(step.to_string(), "Keep segments: 2 region(s), keeping 15.0 s and dropping 5.0 s");
+}
+
+#[test]
+fn test_mute_totals() {
+    let ranges = [TimeRange::new(5.0, 7.5).unwrap(), TimeRange::new(60.0, 70.0).unwrap()];
+    assert_eq!(Step::mute(&ranges).to_string(), "Mute: 2 range(s), 12.5 s silenced");
+}
+
+#[test]
+fn test_dry_run_without_encoding() {
+    let dir = TempDir::new().unwrap();
+    let input = dir.path().join("tone.mp4");
+    common::write_test_tone(&input);
+    let output = dir.path().join("out.wav");
+
+    let args = Args { input, output: output.clone(), format: Some(AudioFormat::Wav), end: Some(0.75), ..Default::default() };
+    let extractor = AudioExtractor::new(args).with_effects(EffectChain::new().gain(-3.0));
+    let dry_run = extractor.dry_run().unwrap();
+    assert_eq!(dry_run.steps.len(), 2);
+    assert!(matches!(dry_run.steps[0], Step::Cut { start: 0.0, end: Some(0.75), .. }));
+    assert_eq!(dry_run.steps[1], Step::Effect { description: "gain -3.0 dB".into(), filter: "volume=-3dB".into() });
+    assert!(!output.exists());
+
+    let report = dir.path().join("dry_run.json");
+    dry_run.write(&report).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&report).unwrap()).unwrap();
+    assert_eq!(json["steps"][0]["step"], "cut");
+    assert_eq!(json["steps"][1]["step"], "effect");
+}
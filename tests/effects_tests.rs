@@ -0,0 +1,89 @@
+mod common;
+
+use audio_extractor::effects::{Effect, EffectChain, FadeDirection, NativeEffects};
+use audio_extractor::native::{self, Backend};
+use audio_extractor::{Args, AudioExtractor, AudioFormat, LoudnessTarget, Tags};
+use std::io::Cursor;
+use tempfile::TempDir;
+
+#[test]
+fn test_effect_filters() {
+    let chain = EffectChain::new()
+        .normalize(LoudnessTarget::default())
+        .trim(1.5, None)
+        .fade(FadeDirection::Out, 58.0, 2.0)
+        .resample(16_000)
+        .gain(6.0)
+        .custom("highpass=f=80");
+    assert_eq!(chain.filters(), [
+        "loudnorm=I=-16:TP=-1.5:LRA=11",
+        "atrim=start=1.5,asetpts=PTS-STARTPTS",
+        "afade=t=out:st=58:d=2",
+        "aresample=16000",
+        "volume=6dB",
+        "highpass=f=80",
+    ]);
+    assert!(EffectChain::new().is_empty());
+}
+
+#[test]
+fn test_native_gain_and_fade() {
+    let chain = EffectChain::new().gain(-6.0206).fade(FadeDirection::In, 0.0, 1.0);
+    let mut effects = NativeEffects::new(&chain, 4, 1, 0.0).unwrap();
+    // Four frames a second: the fade is at 0, 1/4, 1/2 and 3/4 of full level
+    assert_eq!(effects.process(&[1000, 1000, 1000, 1000]), [0, 125, 250, 375]);
+    assert_eq!(effects.process(&[1000]), [500]);
+}
+
+#[test]
+fn test_native_trim_across_chunks() {
+    let chain = EffectChain::new().trim(0.5, Some(1.25));
+    // Audio starting at 0.25 s of the source, as with --start 0.25
+    let mut effects = NativeEffects::new(&chain, 4, 2, 0.25).unwrap();
+    assert_eq!(effects.process(&[1, 1, 2, 2]), [2, 2]);
+    assert_eq!(effects.process(&[3, 3, 4, 4, 5, 5]), [3, 3, 4, 4]);
+}
+
+#[test]
+fn test_native_resample() {
+    let chain = EffectChain::new().resample(2);
+    let mut effects = NativeEffects::new(&chain, 4, 1, 0.0).unwrap();
+    assert_eq!(effects.output_rate(4), 2);
+    let mut output = effects.process(&[0, 100, 200]);
+    output.extend(effects.process(&[300, 400, 500, 600]));
+    assert_eq!(output, [0, 200, 400]);
+}
+
+#[test]
+fn test_native_refuses_ffmpeg_only_effects() {
+    assert!(NativeEffects::new(&EffectChain::new().custom("aecho"), 44_100, 1, 0.0).is_err());
+    assert!(EffectChain::new().push(Effect::Normalize(LoudnessTarget::default())).check_native().is_err());
+}
+
+#[test]
+fn test_native_extract_with_effects() {
+    let dir = TempDir::new().unwrap();
+    let input = dir.path().join("tone.wav");
+    common::write_test_tone(&input);
+    
+    let chain = EffectChain::new().trim(0.25, Some(0.75)).resample(8_000);
+    let (_, output) = native::extract_with_effects(&input, None, 0.0, None, &AudioFormat::Wav, false, &Tags::default(), &chain, Cursor::new(Vec::new())).unwrap();
+    assert_eq!(output.sample_rate, 8_000);
+    assert!((output.duration() - 0.5).abs() < 0.001);
+}
+
+#[test]
+fn test_extractor_applies_effects() {
+    let dir = TempDir::new().unwrap();
+    let input = dir.path().join("tone.mp4");
+    common::write_test_tone(&input);
+    let output = dir.path().join("out.wav");
+    
+    let args = Args { input, output: output.clone(), format: Some(AudioFormat::Wav), ..Default::default() };
+    let extractor = AudioExtractor::new(args)
+        .with_backend(Backend::Native)
+        .with_effects(EffectChain::new().gain(-3.0).trim(0.0, Some(0.5)));
+    assert_eq!(extractor.audio_graph().unwrap().filters, ["volume=-3dB", "atrim=start=0:end=0.5,asetpts=PTS-STARTPTS"]);
+    extractor.extract().unwrap();
+    assert_eq!(AudioExtractor::verify_standalone(&output).unwrap().duration, Some(0.5));
+}
@@ -0,0 +1,55 @@
+use audio_extractor::email::SendWhen;
+use audio_extractor::{BatchSummary, Config};
+use std::path::Path;
+
+fn config(send: &str) -> audio_extractor::EmailConfig {
+    let config: Config = toml::from_str(&format!(
+        r#"
+        [email]
+        smtp_url = "smtps://mail.example.com:465"
+        username = "archive"
+        password = "secret"
+        from = "archive@example.com"
+        to = ["ops@example.com", "me@example.com"]
+        send = "{}"
+        "#,
+        send
+    ))
+    .unwrap();
+    config.email.unwrap()
+}
+
+fn summary(failures: Vec<String>) -> BatchSummary {
+    BatchSummary::new("scan", Path::new("/archive/videos"), "2024-03-02T01:00:00+00:00".into(), 412, failures)
+}
+
+#[test]
+fn test_send_only_on_failures() {
+    let email = config("failures");
+    assert_eq!(email.send, SendWhen::Failures);
+    assert!(!email.wants(&summary(vec![])));
+    assert!(email.wants(&summary(vec!["corrupt.mp4: no audio".into()])));
+    assert!(config("always").wants(&summary(vec![])));
+}
+
+#[test]
+fn test_summary_message() {
+    let email = config("always");
+    let message = email.message(&summary(vec!["corrupt.mp4: no audio".into()]));
+    assert!(message.contains("From: archive@example.com\r\n"));
+    assert!(message.contains("To: ops@example.com, me@example.com\r\n"));
+    assert!(message.contains("Subject: [audio_extractor] scan of /archive/videos FAILED on "));
+    assert!(message.contains(": 412 ok, 1 failed\r\n"));
+    assert!(message.contains("\r\n\r\nBatch: scan of /archive/videos\r\n"));
+    assert!(message.contains("Failures:\r\n- corrupt.mp4: no audio\r\n"));
+    assert!(!message.contains("secret"));
+}
+
+#[test]
+fn test_long_failure_lists_are_truncated() {
+    let failures = (0..60).map(|i| format!("file{}.mp4: failed", i)).collect();
+    let body = summary(failures).body();
+    assert!(body.contains("- file49.mp4"));
+    assert!(!body.contains("- file50.mp4"));
+    assert!(body.contains("... and 10 more"));
+}
@@ -0,0 +1,32 @@
+use audio_extractor::encrypt::{Encryption, EncryptionTool};
+use std::path::Path;
+
+#[test]
+fn test_encryption_tool_selection() {
+    let age = Encryption::new("age1ql3z7hjy54pw3hyww5ayyfg7zqgvc7w3j2elw8zmrj2kg5sfn9aqmcac8p".to_string(), None);
+    assert_eq!(age.tool(), EncryptionTool::Age);
+    
+    let ssh = Encryption::new("ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAI".to_string(), None);
+    assert_eq!(ssh.tool(), EncryptionTool::Age);
+    
+    let gpg = Encryption::new("legal@example.com".to_string(), None);
+    assert_eq!(gpg.tool(), EncryptionTool::Gpg);
+}
+
+#[test]
+fn test_encrypt_command_writes_to_output() {
+    let encryption = Encryption::new("legal@example.com".to_string(), None);
+    let cmd = encryption.encrypt_command(Path::new("/tmp/out.mp3"));
+    
+    assert_eq!(cmd.get_program(), "gpg");
+    let args: Vec<_> = cmd.get_args().map(|a| a.to_str().unwrap()).collect();
+    assert!(args.windows(2).any(|w| w == ["--recipient", "legal@example.com"]));
+    assert!(args.windows(2).any(|w| w == ["--output", "/tmp/out.mp3"]));
+}
+
+#[test]
+fn test_age_decrypt_requires_identity() {
+    let encryption = Encryption::new("age1ql3z7hjy54pw3hyww5ayyfg7zqgvc7w3j2elw8zmrj2kg5sfn9aqmcac8p".to_string(), None);
+    let err = encryption.decrypt(Path::new("/tmp/out.mp3.age")).unwrap_err();
+    assert!(err.to_string().contains("identity"));
+}
@@ -0,0 +1,87 @@
+use anyhow::Context;
+use audio_extractor::{Args, AudioExtractor, AudioFormat, ExtractorError};
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_validate_input_errors_are_typed() {
+    let dir = TempDir::new().unwrap();
+    let output = dir.path().join("out.mp3");
+
+    let missing = dir.path().join("missing.mp4");
+    let err = AudioExtractor::new(Args { input: missing.clone(), output: output.clone(), ..Default::default() })
+        .extract().unwrap_err();
+    assert!(matches!(&err, ExtractorError::InputNotFound(path) if *path == missing));
+    assert!(err.to_string().contains("Input file does not exist"));
+
+    let text = dir.path().join("notes.txt");
+    fs::write(&text, b"not a video").unwrap();
+    let err = AudioExtractor::new(Args { input: text.clone(), output, ..Default::default() })
+        .validate_input().unwrap_err();
+    assert!(matches!(&err, ExtractorError::UnsupportedFormat(path) if *path == text));
+}
+
+#[test]
+fn test_invalid_options() {
+    let args = Args {
+        format: Some(AudioFormat::Mp3),
+        hybrid: true,
+        ..Default::default()
+    };
+    let err = AudioExtractor::new(args).hybrid_bitrate().unwrap_err();
+    assert!(matches!(err, ExtractorError::InvalidOptions(_)));
+    assert_eq!(err.to_string(), "--hybrid only applies to WavPack outputs");
+}
+
+#[test]
+fn test_verify_standalone_probe_failed() {
+    let dir = TempDir::new().unwrap();
+    let missing = dir.path().join("missing.wav");
+    let err = AudioExtractor::verify_standalone(&missing).unwrap_err();
+    assert!(matches!(&err, ExtractorError::ProbeFailed { path, .. } if *path == missing));
+
+    let garbage = dir.path().join("garbage.wav");
+    fs::write(&garbage, b"this is not audio at all").unwrap();
+    assert!(matches!(AudioExtractor::verify_standalone(&garbage), Err(ExtractorError::ProbeFailed { .. })));
+}
+
+#[test]
+fn test_typed_error_survives_anyhow_context() {
+    let inner: anyhow::Result<()> = Err(ExtractorError::FfmpegFailed { stderr: "boom".to_string(), exit_code: Some(1) }.into());
+    let err = ExtractorError::from(inner.context("Failed on chunk 2").unwrap_err());
+    assert!(matches!(err, ExtractorError::FfmpegFailed { exit_code: Some(1), .. }));
+
+    let err = ExtractorError::from(anyhow::anyhow!("something else"));
+    assert!(matches!(err, ExtractorError::Other(_)));
+    assert_eq!(err.to_string(), "something else");
+
+    let drm = audio_extractor::DrmProtected { path: "movie.mp4".into(), scheme: "cenc".to_string() };
+    let err = ExtractorError::from(anyhow::Error::from(drm.clone()).context("Preflight"));
+    assert!(matches!(err, ExtractorError::DrmProtected(found) if found == drm));
+}
+
+#[test]
+fn test_missing_ffprobe_is_typed() {
+    if std::process::Command::new("ffprobe").arg("-version").output().is_ok() {
+        return;
+    }
+    let dir = TempDir::new().unwrap();
+    let input = dir.path().join("movie.mp4");
+    fs::write(&input, b"video").unwrap();
+    let extractor = AudioExtractor::new(Args { input, ..Default::default() });
+
+    assert!(matches!(extractor.probe(), Err(ExtractorError::FfprobeNotInstalled { needed_for: "probing media" })));
+    assert!(matches!(extractor.audio_tracks(), Err(ExtractorError::FfprobeNotInstalled { .. })));
+    assert!(matches!(extractor.chapters(), Err(ExtractorError::FfprobeNotInstalled { .. })));
+
+    let templated = AudioExtractor::new(Args {
+        input: dir.path().join("movie.mp4"),
+        output: dir.path().join("{artist}.mp3"),
+        ..Default::default()
+    });
+    match templated.with_expanded_output() {
+        Err(err @ ExtractorError::FfprobeNotInstalled { .. }) => assert!(err.to_string().contains("metadata fields in the output path")),
+        Err(err) => panic!("expected FfprobeNotInstalled, got {:?}", err),
+        Ok(_) => panic!("expected FfprobeNotInstalled"),
+    }
+}
@@ -0,0 +1,80 @@
+use audio_extractor::flac::{self, FlacWriter, BLOCK_SIZE};
+use std::io::Cursor;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::DecoderOptions;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+/// Decode a FLAC stream with symphonia into interleaved samples, with the
+/// sample count STREAMINFO declares
+fn decode(data: Vec<u8>) -> (Vec<i16>, Option<u64>) {
+    let mss = MediaSourceStream::new(Box::new(Cursor::new(data)), Default::default());
+    let mut hint = Hint::new();
+    hint.with_extension("flac");
+    let probed = symphonia::default::get_probe()
+        .format(&hint, mss, &FormatOptions::default(), &MetadataOptions::default())
+        .unwrap();
+    let mut reader = probed.format;
+    let params = reader.default_track().unwrap().codec_params.clone();
+    let mut decoder = symphonia::default::get_codecs().make(&params, &DecoderOptions::default()).unwrap();
+    let mut samples = Vec::new();
+    while let Ok(packet) = reader.next_packet() {
+        let decoded = decoder.decode(&packet).unwrap();
+        let mut buffer = SampleBuffer::<i16>::new(decoded.capacity() as u64, *decoded.spec());
+        buffer.copy_interleaved_ref(decoded);
+        samples.extend_from_slice(buffer.samples());
+    }
+    (samples, params.n_frames)
+}
+
+#[test]
+fn test_flac_round_trips_losslessly() {
+    // A partial last block, a tone, silence and full-scale noise
+    let frames = BLOCK_SIZE * 2 + 1234;
+    let mut noise = 12345u32;
+    let samples: Vec<i16> = (0..frames)
+        .flat_map(|i| {
+            noise = noise.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            let tone = ((i as f64 * 0.05).sin() * 20_000.0) as i16;
+            let right = if i < BLOCK_SIZE { 0 } else { (noise >> 16) as i16 };
+            [tone, right]
+        })
+        .collect();
+    
+    let mut writer = FlacWriter::new(Cursor::new(Vec::new()), 48_000, 2).unwrap();
+    writer.write(&samples[..1000]).unwrap();
+    writer.write(&samples[1000..]).unwrap();
+    let data = writer.finish().unwrap().into_inner();
+    assert_eq!(&data[..4], b"fLaC");
+    assert!(data.len() < samples.len() * 2, "a tone and silence should compress");
+    
+    let (decoded, declared) = decode(data);
+    assert_eq!(declared, Some(frames as u64));
+    assert_eq!(decoded, samples);
+}
+
+#[test]
+fn test_flac_handles_tiny_streams() {
+    let mut writer = FlacWriter::new(Cursor::new(Vec::new()), 44_100, 1).unwrap();
+    writer.write(&[5, -7, 9]).unwrap();
+    assert_eq!(decode(writer.finish().unwrap().into_inner()).0, vec![5, -7, 9]);
+}
+
+#[test]
+fn test_flac_rejects_unsupported_layouts() {
+    assert!(FlacWriter::new(Cursor::new(Vec::new()), 44_100, 9).is_err());
+    assert!(FlacWriter::new(Cursor::new(Vec::new()), 0, 2).is_err());
+}
+
+#[test]
+fn test_fixed_predictors_cancel_polynomials() {
+    let line: Vec<i32> = (0..10).map(|i| 3 * i + 1).collect();
+    assert_eq!(flac::residuals(&line, 1), vec![3; 9]);
+    assert_eq!(flac::residuals(&line, 2), vec![0; 8]);
+    
+    let parabola: Vec<i32> = (0..10).map(|i| i * i).collect();
+    assert_eq!(flac::residuals(&parabola, 3), vec![0; 7]);
+    assert_eq!(flac::residuals(&parabola, 0), parabola);
+}
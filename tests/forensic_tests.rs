@@ -0,0 +1,55 @@
+mod common;
+
+use audio_extractor::forensic::{self, SignedReport};
+use audio_extractor::{AudioExtractor, AudioFormat};
+use common::*;
+use tempfile::TempDir;
+
+const SEED_HEX: &str = "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60";
+
+#[test]
+fn test_load_signing_key_accepts_hex_and_raw_seeds() {
+    let temp_dir = TempDir::new().unwrap();
+    let hex_path = temp_dir.path().join("key.hex");
+    std::fs::write(&hex_path, format!("{}\n", SEED_HEX)).unwrap();
+    let raw_path = temp_dir.path().join("key.bin");
+    std::fs::write(&raw_path, [7u8; 32]).unwrap();
+    let bad_path = temp_dir.path().join("key.bad");
+    std::fs::write(&bad_path, "not a key").unwrap();
+    
+    let hex_key = forensic::load_signing_key(&hex_path).unwrap();
+    assert_eq!(hex_key.to_bytes()[0], 0x9d);
+    assert_eq!(forensic::load_signing_key(&raw_path).unwrap().to_bytes(), [7u8; 32]);
+    assert!(forensic::load_signing_key(&bad_path).is_err());
+}
+
+#[test]
+fn test_forensic_report_is_signed_and_tamper_evident() {
+    let temp_dir = TempDir::new().unwrap();
+    let input_file = create_test_video_file();
+    let output_path = temp_dir.path().join("output.flac");
+    let report_path = temp_dir.path().join("report.json");
+    let key_path = temp_dir.path().join("key.hex");
+    std::fs::write(&key_path, SEED_HEX).unwrap();
+    
+    let mut args = create_test_args(input_file.path().to_path_buf(), output_path);
+    args.format = Some(AudioFormat::Flac);
+    args.forensic_report = Some(report_path.clone());
+    args.forensic_key = Some(key_path);
+    AudioExtractor::new(args).extract().unwrap();
+    
+    let signed: SignedReport = serde_json::from_str(&std::fs::read_to_string(&report_path).unwrap()).unwrap();
+    signed.verify().unwrap();
+    assert!(signed.report.success);
+    assert_eq!(signed.report.input.sha256, audio_extractor::cache::hash_file(input_file.path()).unwrap());
+    // The native backend runs no command to record
+    match signed.report.settings["backend"].as_str() {
+        Some("native") => assert!(signed.report.command_line.is_empty()),
+        _ => assert_eq!(signed.report.command_line[0], "ffmpeg"),
+    }
+    assert!(signed.report.output.is_some());
+    
+    let mut tampered = signed.clone();
+    tampered.report.input.sha256 = "0".repeat(64);
+    assert!(tampered.verify().is_err());
+}
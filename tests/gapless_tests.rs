@@ -0,0 +1,77 @@
+use audio_extractor::gapless::{self, GaplessInfo};
+use audio_extractor::AudioFormat;
+use std::fs;
+use tempfile::TempDir;
+
+/// First frame of an MPEG-1 stereo MP3 with an Info/LAME tag
+fn mp3_with_lame_tag(delay: u32, padding: u32) -> Vec<u8> {
+    let mut frame = vec![0xff, 0xfb, 0x90, 0x44];
+    frame.extend([0; 32]);
+    frame.extend(b"Info");
+    frame.extend(0x0000_0003u32.to_be_bytes());
+    frame.extend(1000u32.to_be_bytes());
+    frame.extend(400_000u32.to_be_bytes());
+    frame.extend(b"LAME3.100");
+    frame.extend([0; 12]);
+    frame.extend([(delay >> 4) as u8, (((delay & 0x0f) << 4) | (padding >> 8)) as u8, padding as u8]);
+    frame.extend([0; 300]);
+    frame
+}
+
+fn mp4_box(name: &[u8; 4], content: &[u8]) -> Vec<u8> {
+    let mut data = ((content.len() + 8) as u32).to_be_bytes().to_vec();
+    data.extend(name);
+    data.extend(content);
+    data
+}
+
+fn mp4_with_edit_list(media_times: &[i32]) -> Vec<u8> {
+    let mut elst = vec![0, 0, 0, 0];
+    elst.extend((media_times.len() as u32).to_be_bytes());
+    for time in media_times {
+        elst.extend(44_100u32.to_be_bytes());
+        elst.extend(time.to_be_bytes());
+        elst.extend(0x0001_0000u32.to_be_bytes());
+    }
+    let trak = mp4_box(b"trak", &[mp4_box(b"tkhd", &[0; 20]), mp4_box(b"edts", &mp4_box(b"elst", &elst))].concat());
+    [mp4_box(b"ftyp", b"M4A \0\0\0\0"), mp4_box(b"moov", &trak)].concat()
+}
+
+#[test]
+fn test_lame_header() {
+    let info = gapless::lame_header(&mp3_with_lame_tag(576, 1234)).unwrap();
+    assert_eq!(info, GaplessInfo { delay: 576, padding: Some(1234) });
+    
+    // An ID3v2 tag in front is skipped
+    let mut tagged = b"ID3\x03\x00\x00\x00\x00\x00\x05".to_vec();
+    tagged.extend([0; 5]);
+    tagged.extend(mp3_with_lame_tag(1105, 0));
+    assert_eq!(gapless::lame_header(&tagged).unwrap().delay, 1105);
+    
+    let mut plain = vec![0xff, 0xfb, 0x90, 0x44];
+    plain.extend([0; 400]);
+    assert_eq!(gapless::lame_header(&plain), None);
+}
+
+#[test]
+fn test_mp4_edit_list() {
+    assert_eq!(gapless::mp4_edit_list(&mp4_with_edit_list(&[1024])), Some(GaplessInfo { delay: 1024, padding: None }));
+    assert_eq!(gapless::mp4_edit_list(&mp4_with_edit_list(&[-1, 2112])).unwrap().delay, 2112);
+    assert_eq!(gapless::mp4_edit_list(&mp4_with_edit_list(&[])), None);
+    assert_eq!(gapless::mp4_edit_list(b"not an mp4"), None);
+}
+
+#[test]
+fn test_check_by_format() {
+    let dir = TempDir::new().unwrap();
+    let mp3 = dir.path().join("track.mp3");
+    fs::write(&mp3, mp3_with_lame_tag(576, 600)).unwrap();
+    assert_eq!(gapless::check(&mp3, &AudioFormat::Mp3).unwrap().unwrap().padding, Some(600));
+    
+    let m4a = dir.path().join("track.m4a");
+    fs::write(&m4a, mp4_with_edit_list(&[])).unwrap();
+    assert!(gapless::check(&m4a, &AudioFormat::Aac).is_err());
+    
+    assert!(gapless::check(&dir.path().join("track.aac"), &AudioFormat::Aac).unwrap_err().to_string().contains("ADTS"));
+    assert_eq!(gapless::check(&dir.path().join("track.flac"), &AudioFormat::Flac).unwrap(), None);
+}
@@ -0,0 +1,69 @@
+use audio_extractor::golden::{self, GoldenManifest, GoldenResult, Verdict};
+use audio_extractor::AudioFormat;
+use std::fs;
+use tempfile::tempdir;
+
+/// A tone whose level rises and falls once a second
+fn swelling_tone(seconds: usize) -> Vec<i16> {
+    (0..seconds * 16_000)
+        .map(|i| {
+            let t = i as f64 / 16_000.0;
+            let level = 0.5 + 0.45 * (2.0 * std::f64::consts::PI * t).sin();
+            (level * 20_000.0 * (2.0 * std::f64::consts::PI * 440.0 * t).sin()) as i16
+        })
+        .collect()
+}
+
+#[test]
+fn test_manifest_resolves_inputs_relative_to_itself() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("golden.toml");
+    fs::write(&path, r#"
+        [[reference]]
+        input = "refs/speech.mp4"
+        format = "mp3"
+        quality = 192
+
+        [[reference]]
+        input = "refs/music.mkv"
+        name = "music-flac"
+    "#).unwrap();
+    
+    let manifest = GoldenManifest::load(&path).unwrap();
+    assert_eq!(manifest.reference[0].input, dir.path().join("refs/speech.mp4"));
+    assert_eq!(manifest.reference[0].name(), "speech");
+    assert_eq!(manifest.reference[0].format, Some(AudioFormat::Mp3));
+    assert_eq!(manifest.reference[1].name(), "music-flac");
+}
+
+#[test]
+fn test_envelope_similarity() {
+    let samples = swelling_tone(3);
+    let reference = golden::envelope(&samples, 16_000);
+    assert_eq!(reference.len(), 150);
+    assert!((golden::similarity(&reference, &reference) - 1.0).abs() < 1e-9);
+    
+    // A few frames of encoder delay still match
+    let delayed: Vec<i16> = std::iter::repeat_n(0, 960).chain(samples.iter().copied()).collect();
+    assert!(golden::similarity(&reference, &golden::envelope(&delayed, 16_000)) > 0.95);
+    
+    // A different signal does not
+    let steady: Vec<i16> = (0..48_000).map(|i| ((i % 50) as i16 - 25) * 400).collect();
+    assert!(golden::similarity(&reference, &golden::envelope(&steady, 16_000)) < 0.5);
+}
+
+#[test]
+fn test_compare_verdicts() {
+    let envelope = golden::envelope(&swelling_tone(2), 16_000);
+    let golden_result = GoldenResult { name: "tone".into(), pcm_sha256: "aa".into(), envelope: envelope.clone() };
+    
+    assert_eq!(golden::compare(&golden_result, &golden_result, 0.99), Verdict::Identical);
+    
+    let reencoded = GoldenResult { pcm_sha256: "bb".into(), ..golden_result.clone() };
+    assert!(matches!(golden::compare(&golden_result, &reencoded, 0.99), Verdict::Similar(_)));
+    
+    let truncated = GoldenResult { pcm_sha256: "cc".into(), envelope: envelope[..50].to_vec(), ..golden_result.clone() };
+    let verdict = golden::compare(&golden_result, &truncated, 0.99);
+    assert!(matches!(verdict, Verdict::Different(_)));
+    assert!(!verdict.passed());
+}
@@ -0,0 +1,75 @@
+use audio_extractor::hook::{self, HookOutcome, Job};
+use audio_extractor::{Args, AudioFormat, SourceMetadata};
+use std::path::PathBuf;
+
+fn job() -> Job {
+    Job {
+        input: PathBuf::from("/videos/talk \"final\".mp4"),
+        output: PathBuf::from("/audio/talk.mp3"),
+        format: AudioFormat::Mp3,
+        quality: 128,
+        metadata: SourceMetadata {
+            title: Some("Line one\nline two".to_string()),
+            season: Some(2),
+            duration: Some(5400.5),
+            ..Default::default()
+        },
+    }
+}
+
+#[test]
+fn test_prelude_defines_job_table() {
+    let prelude = hook::prelude(&job());
+    assert!(prelude.starts_with("job = {"));
+    assert!(prelude.contains(r#"input = "/videos/talk \"final\".mp4""#));
+    assert!(prelude.contains(r#"format = "mp3", quality = 128, duration = 5400.5"#));
+    assert!(prelude.contains(r#"title = "Line one\nline two""#));
+    assert!(prelude.contains("season = 2"));
+    assert!(!prelude.contains("artist"));
+    
+    let unprobed = Job { metadata: SourceMetadata::default(), ..job() };
+    assert!(hook::prelude(&unprobed).contains("duration = nil"));
+}
+
+#[test]
+fn test_parse_outcome_keeps_changed_fields() {
+    let stdout = "@hook\tformat\tflac\n@hook\tquality\t128\n@hook\toutput\t/audio/talk.mp3\n@hook\tskip\tfalse\n";
+    let outcome = hook::parse_outcome(stdout, &job()).unwrap();
+    assert_eq!(outcome, HookOutcome { format: Some(AudioFormat::Flac), ..Default::default() });
+    
+    let stdout = "debug output\n@hook\tskip\ttrue\n@hook\tskip_reason\ttoo short\n";
+    assert_eq!(hook::parse_outcome(stdout, &job()).unwrap().skip.as_deref(), Some("too short"));
+    assert_eq!(hook::parse_outcome("@hook\tskip\ttrue\n", &job()).unwrap().skip.as_deref(), Some("skipped by hook"));
+    
+    assert!(hook::parse_outcome("@hook\tformat\twma\n", &job()).is_err());
+    assert!(hook::parse_outcome("@hook\tquality\thigh\n", &job()).is_err());
+    assert_eq!(hook::parse_outcome("@hook\tquality\t192.0\n", &job()).unwrap().quality, Some(192));
+}
+
+#[test]
+fn test_apply_outcome() {
+    let base = Args {
+        output: PathBuf::from("/audio/talk.mp3"),
+        format: Some(AudioFormat::Mp3),
+        quality: Some(128),
+        ..Default::default()
+    };
+    
+    let mut args = base.clone();
+    HookOutcome { format: Some(AudioFormat::Flac), quality: Some(0), ..Default::default() }.apply(&mut args);
+    assert_eq!(args.output, PathBuf::from("/audio/talk.flac"));
+    assert_eq!(args.format, Some(AudioFormat::Flac));
+    assert_eq!(args.quality, Some(0));
+    
+    let mut args = base.clone();
+    HookOutcome {
+        format: Some(AudioFormat::Wav),
+        output: Some(PathBuf::from("/audio/talks/keynote.wav")),
+        ..Default::default()
+    }.apply(&mut args);
+    assert_eq!(args.output, PathBuf::from("/audio/talks/keynote.wav"));
+    
+    let mut args = base.clone();
+    HookOutcome::default().apply(&mut args);
+    assert_eq!(args.output, base.output);
+}
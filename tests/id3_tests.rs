@@ -0,0 +1,67 @@
+use audio_extractor::id3::{self, TextEncoding};
+use std::fs;
+use tempfile::TempDir;
+
+/// ID3v2.3 tag holding the given frames
+fn tag(frames: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+    let body: Vec<u8> = frames.iter().flat_map(|(id, content)| {
+        let mut frame = id.to_vec();
+        frame.extend((content.len() as u32).to_be_bytes());
+        frame.extend([0, 0]);
+        frame.extend(content);
+        frame
+    }).collect();
+    let size = body.len();
+    let mut tag = b"ID3\x03\x00\x00".to_vec();
+    tag.extend([(size >> 21) as u8 & 0x7f, (size >> 14) as u8 & 0x7f, (size >> 7) as u8 & 0x7f, size as u8 & 0x7f]);
+    tag.extend(body);
+    tag
+}
+
+#[test]
+fn test_latin1_text_becomes_utf16() {
+    let original = tag(&[
+        (b"TIT2", b"\x00Caf\xe9".to_vec()),
+        (b"TXXX", b"\x00mood\x00calm".to_vec()),
+        (b"APIC", b"\x00image/png\x00\x03\x00PNG".to_vec()),
+    ]);
+    let rewritten = id3::rewrite_tag(&original, TextEncoding::Utf16).unwrap().unwrap();
+    
+    let expected = tag(&[
+        (b"TIT2", b"\x01\xff\xfeC\x00a\x00f\x00\xe9\x00".to_vec()),
+        (b"TXXX", b"\x01\xff\xfem\x00o\x00o\x00d\x00\x00\x00\xff\xfec\x00a\x00l\x00m\x00".to_vec()),
+        (b"APIC", b"\x00image/png\x00\x03\x00PNG".to_vec()),
+    ]);
+    assert_eq!(rewritten, expected);
+    
+    // And back again
+    assert_eq!(id3::rewrite_tag(&rewritten, TextEncoding::Latin1).unwrap().unwrap(), original);
+}
+
+#[test]
+fn test_latin1_falls_back_to_utf16() {
+    let original = tag(&[(b"TPE1", "\x03坂本".bytes().collect())]);
+    let rewritten = id3::rewrite_tag(&original, TextEncoding::Latin1).unwrap().unwrap();
+    assert_eq!(rewritten[20], 1);
+}
+
+#[test]
+fn test_utf8_needs_id3v24() {
+    let original = tag(&[(b"TIT2", b"\x00Song".to_vec())]);
+    assert!(id3::rewrite_tag(&original, TextEncoding::Utf8).is_err());
+    assert!(id3::rewrite_tag(b"fff", TextEncoding::Utf8).unwrap().is_none());
+}
+
+#[test]
+fn test_rewrite_keeps_the_audio() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("song.mp3");
+    let mut file = tag(&[(b"TALB", b"\x00Album".to_vec())]);
+    file.extend(b"\xff\xfbaudio");
+    fs::write(&path, &file).unwrap();
+    
+    id3::rewrite_encoding(&path, TextEncoding::Utf16).unwrap();
+    let data = fs::read(&path).unwrap();
+    assert_eq!(id3::tag_len(&data), Some(10 + 10 + 13));
+    assert!(data.ends_with(b"\xff\xfbaudio"));
+}
@@ -0,0 +1,60 @@
+use audio_extractor::inputs;
+use std::fs;
+use std::path::PathBuf;
+use tempfile::TempDir;
+
+const EXTENSIONS: &[&str] = &["mp4", "mkv"];
+
+fn tree() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    for file in ["b.mp4", "a.MKV", "notes.txt", "shows/s01/e01.mkv", "shows/s01/e02.mp4", "shows/cover.jpg"] {
+        let path = dir.path().join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, b"").unwrap();
+    }
+    dir
+}
+
+fn arg(dir: &TempDir, input: &str) -> String {
+    format!("{}/{}", dir.path().display(), input)
+}
+
+#[test]
+fn test_expand_directories() {
+    let dir = tree();
+    let root = dir.path().display().to_string();
+    
+    let files = inputs::expand(std::slice::from_ref(&root), false, EXTENSIONS).unwrap();
+    assert_eq!(files, vec![dir.path().join("a.MKV"), dir.path().join("b.mp4")]);
+    
+    let files = inputs::expand(&[root], true, EXTENSIONS).unwrap();
+    assert_eq!(files.len(), 4);
+    assert!(files.contains(&dir.path().join("shows/s01/e01.mkv")));
+}
+
+#[test]
+fn test_expand_patterns() {
+    let dir = tree();
+    
+    let files = inputs::expand(&[arg(&dir, "*.mp4")], false, EXTENSIONS).unwrap();
+    assert_eq!(files, vec![dir.path().join("b.mp4")]);
+    
+    let files = inputs::expand(&[arg(&dir, "shows/**/e0?.*")], false, EXTENSIONS).unwrap();
+    assert_eq!(files, vec![dir.path().join("shows/s01/e01.mkv"), dir.path().join("shows/s01/e02.mp4")]);
+    
+    let files = inputs::expand(&[arg(&dir, "*/*/*.mkv")], false, EXTENSIONS).unwrap();
+    assert_eq!(files, vec![dir.path().join("shows/s01/e01.mkv")]);
+    
+    assert!(inputs::expand(&[arg(&dir, "*.avi")], false, EXTENSIONS).is_err());
+}
+
+#[test]
+fn test_expand_keeps_order_and_drops_duplicates() {
+    let dir = tree();
+    // Files given by name are taken whatever their extension
+    let given = vec![arg(&dir, "notes.txt"), arg(&dir, "b.mp4"), arg(&dir, "*.mp4")];
+    let files = inputs::expand(&given, false, EXTENSIONS).unwrap();
+    assert_eq!(files, vec![dir.path().join("notes.txt"), PathBuf::from(arg(&dir, "b.mp4"))]);
+    
+    assert!(inputs::expand(&[arg(&dir, "missing.mp4")], false, EXTENSIONS).is_err());
+}
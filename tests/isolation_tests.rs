@@ -0,0 +1,60 @@
+use audio_extractor::isolation::{self, ProcessEnvironment};
+use audio_extractor::limits::ResourceLimits;
+use std::process::Command;
+use tempfile::TempDir;
+
+#[test]
+fn test_parse_variable() {
+    assert_eq!(isolation::parse_variable("http_proxy=http://proxy:3128").unwrap(),
+        ("http_proxy".to_string(), "http://proxy:3128".to_string()));
+    assert_eq!(isolation::parse_variable("EMPTY=").unwrap(), ("EMPTY".to_string(), String::new()));
+    assert!(isolation::parse_variable("NO_VALUE").is_err());
+    assert!(isolation::parse_variable("=value").is_err());
+}
+
+#[test]
+fn test_ffmpeg_command_never_reads_stdin() {
+    let cmd = isolation::ffmpeg();
+    assert_eq!(cmd.get_program(), "ffmpeg");
+    assert_eq!(cmd.get_args().next().unwrap(), "-nostdin");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_scrubbed_environment() {
+    std::env::set_var("AUDIO_EXTRACTOR_TEST_PROXY", "http://proxy:3128");
+    let run = |environment: ProcessEnvironment| {
+        let limits = ResourceLimits { environment, ..Default::default() };
+        let output = limits.output(Command::new("sh").arg("-c").arg("env")).unwrap();
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    let env = run(ProcessEnvironment::default());
+    assert!(!env.contains("AUDIO_EXTRACTOR_TEST_PROXY"));
+    assert!(env.contains("LC_ALL=C"));
+    assert!(env.lines().any(|line| line.starts_with("PATH=")));
+
+    let env = run(ProcessEnvironment {
+        keep: vec!["AUDIO_EXTRACTOR_TEST_PROXY".to_string()],
+        set: vec![("FFREPORT".to_string(), "level=32".to_string())],
+        ..Default::default()
+    });
+    assert!(env.contains("AUDIO_EXTRACTOR_TEST_PROXY=http://proxy:3128"));
+    assert!(env.contains("FFREPORT=level=32"));
+
+    let env = run(ProcessEnvironment { inherit: true, ..Default::default() });
+    assert!(env.contains("AUDIO_EXTRACTOR_TEST_PROXY=http://proxy:3128"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_working_directory() {
+    let dir = TempDir::new().unwrap();
+    let limits = ResourceLimits {
+        environment: ProcessEnvironment { working_dir: Some(dir.path().to_path_buf()), ..Default::default() },
+        ..Default::default()
+    };
+    let output = limits.output(&mut Command::new("pwd")).unwrap();
+    let pwd = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    assert_eq!(std::fs::canonicalize(pwd).unwrap(), std::fs::canonicalize(dir.path()).unwrap());
+}
@@ -0,0 +1,90 @@
+use audio_extractor::jobs::{self, Calibration, Jobs, Order};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+#[test]
+fn test_jobs_parse_counts_and_auto() {
+    assert_eq!("auto".parse::<Jobs>().unwrap(), Jobs::Auto);
+    assert_eq!("AUTO".parse::<Jobs>().unwrap(), Jobs::Auto);
+    assert_eq!("4".parse::<Jobs>().unwrap(), Jobs::Count(4));
+    assert!("0".parse::<Jobs>().is_err());
+    assert!("many".parse::<Jobs>().is_err());
+    assert_eq!(Jobs::default(), Jobs::Count(1));
+}
+
+#[test]
+fn test_choose_respects_cores_disk_and_inputs() {
+    let light = Calibration { cores_per_job: 1.0, read_rate: 10e6 };
+    assert_eq!(jobs::choose(8, Some(&light), None, 100), 8);
+    // FFmpeg using two threads per job halves the jobs
+    let threaded = Calibration { cores_per_job: 2.0, read_rate: 10e6 };
+    assert_eq!(jobs::choose(8, Some(&threaded), None, 100), 4);
+    // A disk that serves 30 MB/s feeds three 10 MB/s jobs
+    assert_eq!(jobs::choose(8, Some(&light), Some(30e6), 100), 3);
+    // Never more jobs than inputs, never fewer than one
+    assert_eq!(jobs::choose(8, Some(&light), None, 2), 2);
+    assert_eq!(jobs::choose(8, Some(&light), Some(1e6), 100), 1);
+    // Without a calibration, one job per core
+    assert_eq!(jobs::choose(6, None, Some(1e6), 100), 6);
+}
+
+#[test]
+fn test_run_keeps_results_in_input_order() {
+    let items = vec![30, 0, 20, 10];
+    let results = jobs::run(4, items.into_iter(), |index, delay| {
+        thread::sleep(Duration::from_millis(delay));
+        (index, delay)
+    });
+    assert_eq!(results, vec![(0, 30), (1, 0), (2, 20), (3, 10)]);
+}
+
+#[test]
+fn test_sort_orders_inputs() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let paths: Vec<_> = ["c.mp4", "a.mp4", "b.mp4", "d.mp4"].iter().map(|name| dir.path().join(name)).collect();
+    let duration = |path: &Path| match path.file_name().unwrap().to_str().unwrap() {
+        "a.mp4" => Some(30.0),
+        "b.mp4" => Some(10.0),
+        "c.mp4" => Some(20.0),
+        _ => None,
+    };
+    let names = |inputs: &[PathBuf]| -> Vec<String> {
+        inputs.iter().map(|p| p.file_name().unwrap().to_string_lossy().into_owned()).collect()
+    };
+    
+    let mut inputs = paths.clone();
+    jobs::sort(&mut inputs, Order::Name, |_| panic!("name order does not probe"));
+    assert_eq!(names(&inputs), ["a.mp4", "b.mp4", "c.mp4", "d.mp4"]);
+    
+    // Inputs of unknown duration go last either way
+    jobs::sort(&mut inputs, Order::Shortest, duration);
+    assert_eq!(names(&inputs), ["b.mp4", "c.mp4", "a.mp4", "d.mp4"]);
+    jobs::sort(&mut inputs, Order::Longest, duration);
+    assert_eq!(names(&inputs), ["a.mp4", "c.mp4", "b.mp4", "d.mp4"]);
+    
+    // Oldest first; d.mp4 does not exist
+    for (i, name) in ["b.mp4", "c.mp4", "a.mp4"].iter().enumerate() {
+        let file = fs::File::create(dir.path().join(name)).unwrap();
+        file.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000 * (i as u64 + 1))).unwrap();
+    }
+    jobs::sort(&mut inputs, Order::Mtime, |_| None);
+    assert_eq!(names(&inputs), ["b.mp4", "c.mp4", "a.mp4", "d.mp4"]);
+}
+
+#[test]
+fn test_run_throttled_holds_threads_back() {
+    let running = AtomicUsize::new(0);
+    let most = AtomicUsize::new(0);
+    let results = jobs::run_throttled(3, 0..6, || 1, |index, item| {
+        let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+        most.fetch_max(now, Ordering::SeqCst);
+        thread::sleep(Duration::from_millis(10));
+        running.fetch_sub(1, Ordering::SeqCst);
+        (index, item)
+    });
+    assert_eq!(results, (0..6).map(|i| (i, i)).collect::<Vec<_>>());
+    assert_eq!(most.into_inner(), 1);
+}
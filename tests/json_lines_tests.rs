@@ -0,0 +1,170 @@
+mod common;
+
+use assert_cmd::Command;
+use audio_extractor::json_lines::{JsonEvent, JsonLines};
+use audio_extractor::ProgressEvent;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use tempfile::TempDir;
+
+/// A writer whose output the test can read back
+#[derive(Clone, Default)]
+struct Shared(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Shared {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn lines(output: &[u8]) -> Vec<serde_json::Value> {
+    String::from_utf8_lossy(output).lines().map(|line| serde_json::from_str(line).unwrap()).collect()
+}
+
+#[test]
+fn test_progress_events() {
+    assert_eq!(JsonEvent::progress(&ProgressEvent::Started), None);
+    assert_eq!(JsonEvent::progress(&ProgressEvent::Finished), None);
+    assert_eq!(
+        JsonEvent::progress(&ProgressEvent::Encoding { seconds_done: 12.5, percent: Some(50.0) }),
+        Some(JsonEvent::Encoding { seconds_done: 12.5, percent: Some(50.0) })
+    );
+    assert_eq!(
+        JsonEvent::progress(&ProgressEvent::Step("Verification completed")),
+        Some(JsonEvent::Step { message: "Verification completed".into() })
+    );
+    assert_eq!(
+        JsonEvent::progress(&ProgressEvent::Stalled { seconds: 60 }),
+        Some(JsonEvent::Stalled { seconds: 60 })
+    );
+}
+
+#[test]
+fn test_one_event_per_line() {
+    let buffer = Shared::default();
+    let json = JsonLines::new(buffer.clone());
+    json.emit(&JsonEvent::Probed { duration: 90.0 });
+    json.emit(&JsonEvent::error(&anyhow::anyhow!("inner").context("outer")));
+    json.emit(&JsonEvent::Summary { succeeded: 2, failed: 1 });
+
+    let events = lines(&buffer.0.lock().unwrap());
+    assert_eq!(events[0], serde_json::json!({"event": "probed", "duration": 90.0}));
+    assert_eq!(events[1], serde_json::json!({"event": "error", "message": "outer: inner"}));
+    assert_eq!(events[2], serde_json::json!({"event": "summary", "succeeded": 2, "failed": 1}));
+}
+
+#[test]
+fn test_result_event() {
+    let dir = TempDir::new().unwrap();
+    let tone = dir.path().join("tone.wav");
+    common::write_test_tone(&tone);
+    match JsonEvent::result(&tone) {
+        JsonEvent::Result { size, duration, .. } => {
+            assert_eq!(size, Some(std::fs::metadata(&tone).unwrap().len()));
+            assert!((duration.unwrap() - 1.0).abs() < 0.01);
+        }
+        event => panic!("unexpected event {:?}", event),
+    }
+}
+
+#[test]
+fn test_cli_json_output() {
+    let dir = TempDir::new().unwrap();
+    let input = dir.path().join("tone.mp4");
+    common::write_test_tone(&input);
+    let output = dir.path().join("out.wav");
+
+    let assert = Command::cargo_bin("audio_extractor").unwrap()
+        .args(["--json", "--backend", "native", "-f", "wav", "-i"])
+        .arg(&input)
+        .arg("-o")
+        .arg(&output)
+        .assert()
+        .success();
+    let events = lines(&assert.get_output().stdout);
+    assert_eq!(events.first().unwrap()["event"], "started");
+    let result = events.last().unwrap();
+    assert_eq!(result["event"], "result");
+    assert_eq!(result["output"], output.to_str().unwrap());
+    assert!(result["size"].as_u64().unwrap() > 0);
+}
+
+#[test]
+fn test_cli_json_error() {
+    let dir = TempDir::new().unwrap();
+    let assert = Command::cargo_bin("audio_extractor").unwrap()
+        .args(["--json", "-i"])
+        .arg(dir.path().join("missing.mp4"))
+        .arg("-o")
+        .arg(dir.path().join("out.mp3"))
+        .assert()
+        .failure();
+    let events = lines(&assert.get_output().stdout);
+    let errors: Vec<_> = events.iter().filter(|event| event["event"] == "error").collect();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0]["message"].as_str().unwrap().contains("does not exist"));
+}
+
+#[test]
+fn test_cli_batch_json_output() {
+    let dir = TempDir::new().unwrap();
+    let inputs = ["a.mp4", "b.mp4"].map(|name| dir.path().join(name));
+    for input in &inputs {
+        common::write_test_tone(input);
+    }
+    let out = dir.path().join("out");
+
+    let assert = Command::cargo_bin("audio_extractor").unwrap()
+        .args(["batch", "--json", "-f", "wav", "-i"])
+        .arg(&inputs[0])
+        .arg("-i")
+        .arg(&inputs[1])
+        .arg("-o")
+        .arg(&out)
+        .assert()
+        .success();
+    // Every line of standard output is JSON
+    let events = lines(&assert.get_output().stdout);
+    assert_eq!(events.len(), 3);
+    assert_eq!(events[0]["event"], "result");
+    assert_eq!(events[0]["output"], out.join("a.wav").to_str().unwrap());
+    assert_eq!(events[2], serde_json::json!({"event": "summary", "succeeded": 2, "failed": 0}));
+
+    let assert = Command::cargo_bin("audio_extractor").unwrap()
+        .args(["verify-dir", "--json"])
+        .arg(&out)
+        .assert()
+        .success();
+    let events = lines(&assert.get_output().stdout);
+    assert_eq!(events.iter().filter(|event| event["event"] == "result").count(), 2);
+    assert_eq!(events.last().unwrap()["event"], "summary");
+}
+
+#[test]
+fn test_cli_json_sends_warnings_to_stderr() {
+    let dir = TempDir::new().unwrap();
+    let inputs = ["a", "b"].map(|folder| dir.path().join(folder).join("talk.mp4"));
+    for input in &inputs {
+        std::fs::create_dir(input.parent().unwrap()).unwrap();
+        common::write_test_tone(input);
+    }
+    let out = dir.path().join("out");
+
+    let assert = Command::cargo_bin("audio_extractor").unwrap()
+        .args(["batch", "--json", "-f", "wav", "-i"])
+        .arg(&inputs[0])
+        .arg("-i")
+        .arg(&inputs[1])
+        .arg("-o")
+        .arg(&out)
+        .assert()
+        .success();
+    // The two outputs would share a name, which is warned about
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("is already used by another input in this batch"));
+    assert_eq!(lines(&assert.get_output().stdout).len(), 3);
+}
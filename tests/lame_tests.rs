@@ -0,0 +1,47 @@
+use audio_extractor::lame::{self, Encoding, LamePreset, Mp3Mode};
+use audio_extractor::{Args, AudioExtractor, AudioFormat};
+use clap::Parser;
+
+#[test]
+fn test_vbr_level_is_closest_average() {
+    assert_eq!(lame::vbr_level(320), 0);
+    assert_eq!(lame::vbr_level(192), 2);
+    assert_eq!(lame::vbr_level(128), 5);
+    assert_eq!(lame::vbr_level(32), 9);
+}
+
+#[test]
+fn test_encoding_args() {
+    assert_eq!(Encoding::new(Mp3Mode::Cbr, None, 192).ffmpeg_args(), ["-b:a", "192k"]);
+    assert_eq!(Encoding::new(Mp3Mode::Abr, None, 160).ffmpeg_args(), ["-b:a", "160k", "-abr", "1"]);
+    assert_eq!(Encoding::new(Mp3Mode::Vbr, None, 190).ffmpeg_args(), ["-q:a", "2"]);
+    // A preset replaces both the mode and the bitrate
+    assert_eq!(Encoding::new(Mp3Mode::Cbr, Some(LamePreset::Extreme), 128), Encoding::Vbr(0));
+    assert_eq!(Encoding::new(Mp3Mode::Cbr, Some(LamePreset::Insane), 128).average_kbps(), 320);
+    assert_eq!(Encoding::Vbr(4).average_kbps(), 165);
+}
+
+#[test]
+fn test_modes_only_apply_to_mp3() {
+    let args = Args {
+        format: Some(AudioFormat::Flac),
+        lame_preset: Some(LamePreset::Standard),
+        ..Default::default()
+    };
+    assert!(AudioExtractor::new(args).mp3_encoding().is_err());
+    
+    let args = Args {
+        format: Some(AudioFormat::Mp3),
+        quality: Some(128),
+        mp3_mode: Some(Mp3Mode::Abr),
+        ..Default::default()
+    };
+    assert_eq!(AudioExtractor::new(args).mp3_encoding().unwrap(), Encoding::Abr(128));
+}
+
+#[test]
+fn test_preset_conflicts_with_mode() {
+    let parse = |extra: &[&str]| Args::try_parse_from(["audio_extractor", "-i", "in.mkv", "-o", "out.mp3"].iter().chain(extra));
+    assert_eq!(parse(&["--lame-preset", "standard"]).unwrap().lame_preset, Some(LamePreset::Standard));
+    assert!(parse(&["--lame-preset", "extreme", "--mp3-mode", "vbr"]).is_err());
+}
@@ -0,0 +1,62 @@
+use audio_extractor::library::{self, LibraryServer, ServerKind};
+use audio_extractor::Config;
+use std::path::PathBuf;
+
+fn outputs() -> Vec<PathBuf> {
+    vec![
+        PathBuf::from("/media/podcasts/Kitchen Talk/s01e01.mp3"),
+        PathBuf::from("/media/podcasts/Kitchen Talk/s01e02.mp3"),
+        PathBuf::from("/media/music/live.flac"),
+    ]
+}
+
+#[test]
+fn test_library_config() {
+    let config: Config = toml::from_str(
+        r#"
+        [library]
+        server = "plex"
+        url = "http://nas:32400/"
+        token = "abc"
+        section = 4
+        "#,
+    )
+    .unwrap();
+    let library = config.library.unwrap();
+    assert_eq!(library.server, ServerKind::Plex);
+    assert_eq!(library.section, Some(4));
+}
+
+#[test]
+fn test_jellyfin_scan_request() {
+    let server = LibraryServer { server: ServerKind::Jellyfin, url: "http://nas:8096/".into(), token: "abc".into(), section: None };
+    let requests = server.scan_requests(&outputs()).unwrap();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].method, "POST");
+    assert_eq!(requests[0].url, "http://nas:8096/Library/Media/Updated");
+    assert!(requests[0].headers.contains(&"X-Emby-Token: abc".to_string()));
+    
+    let body: serde_json::Value = serde_json::from_str(requests[0].body.as_ref().unwrap()).unwrap();
+    let paths: Vec<_> = body["Updates"].as_array().unwrap().iter().map(|u| u["Path"].as_str().unwrap()).collect();
+    assert_eq!(paths, ["/media/music", "/media/podcasts/Kitchen Talk"]);
+}
+
+#[test]
+fn test_plex_scan_requests() {
+    let server = LibraryServer { server: ServerKind::Plex, url: "http://nas:32400".into(), token: "abc".into(), section: Some(4) };
+    let requests = server.scan_requests(&outputs()).unwrap();
+    let urls: Vec<_> = requests.iter().map(|r| r.url.as_str()).collect();
+    assert_eq!(urls, [
+        "http://nas:32400/library/sections/4/refresh?path=%2Fmedia%2Fmusic",
+        "http://nas:32400/library/sections/4/refresh?path=%2Fmedia%2Fpodcasts%2FKitchen%20Talk",
+    ]);
+    assert!(requests.iter().all(|r| r.headers == ["X-Plex-Token: abc"]));
+    
+    let no_section = LibraryServer { section: None, ..server };
+    assert!(no_section.scan_requests(&outputs()).is_err());
+}
+
+#[test]
+fn test_percent_encode() {
+    assert_eq!(library::percent_encode("a b/ü~"), "a%20b%2F%C3%BC~");
+}
@@ -0,0 +1,69 @@
+use audio_extractor::loudness::{LoudnessTarget, Measurement};
+use audio_extractor::{Args, AudioExtractor, ProgressEvent};
+use clap::Parser;
+
+const STDERR: &str = r#"size=N/A time=00:01:00.00 bitrate=N/A speed= 412x
+[Parsed_loudnorm_0 @ 0x5581c2a0c300] 
+{
+	"input_i" : "-27.47",
+	"input_tp" : "-4.47",
+	"input_lra" : "18.06",
+	"input_thresh" : "-39.20",
+	"output_i" : "-16.58",
+	"output_tp" : "-1.50",
+	"output_lra" : "14.78",
+	"output_thresh" : "-27.71",
+	"normalization_type" : "dynamic",
+	"target_offset" : "0.58"
+}
+"#;
+
+#[test]
+fn test_measurement_from_ffmpeg_stderr() {
+    let measured = Measurement::from_ffmpeg_stderr(STDERR).unwrap();
+    assert_eq!(measured, Measurement { integrated: -27.47, true_peak: -4.47, range: 18.06, threshold: -39.2, offset: 0.58 });
+    
+    assert!(Measurement::from_ffmpeg_stderr("no measurement here").is_err());
+    let silent = STDERR.replace("\"-27.47\"", "\"-inf\"");
+    assert!(Measurement::from_ffmpeg_stderr(&silent).unwrap_err().to_string().contains("silent"));
+}
+
+#[test]
+fn test_target_ranges() {
+    assert_eq!(LoudnessTarget::new(None, None).unwrap(), LoudnessTarget { integrated: -16.0, true_peak: -1.5, range: 11.0 });
+    assert_eq!(LoudnessTarget::new(Some(-23.0), Some(-1.0)).unwrap().integrated, -23.0);
+    assert!(LoudnessTarget::new(Some(-2.0), None).is_err());
+    assert!(LoudnessTarget::new(None, Some(1.0)).is_err());
+}
+
+#[test]
+fn test_filters() {
+    let target = LoudnessTarget::default();
+    assert_eq!(target.measure_filter(), "loudnorm=I=-16:TP=-1.5:LRA=11:print_format=json");
+    assert_eq!(target.single_pass_filter(), "loudnorm=I=-16:TP=-1.5:LRA=11");
+    let measured = Measurement::from_ffmpeg_stderr(STDERR).unwrap();
+    assert_eq!(
+        target.apply_filter(&measured, 44_100),
+        "loudnorm=I=-16:TP=-1.5:LRA=11:measured_I=-27.47:measured_TP=-4.47:measured_LRA=18.06:\
+         measured_thresh=-39.2:offset=0.58:linear=true,aresample=44100"
+    );
+}
+
+#[test]
+fn test_normalize_arguments() {
+    let parse = |extra: &[&str]| Args::try_parse_from(["audio_extractor", "-i", "talk.mp4", "-o", "talk.mp3"].iter().chain(extra));
+    let args = parse(&["--normalize", "--target-lufs", "-14", "--true-peak", "-1"]).unwrap();
+    assert_eq!((args.target_lufs, args.true_peak), (Some(-14.0), Some(-1.0)));
+    // The targets only mean something with --normalize
+    assert!(parse(&["--target-lufs", "-14"]).is_err());
+    
+    let extractor = AudioExtractor::new(args);
+    assert_eq!(extractor.loudness_target().unwrap().unwrap().integrated, -14.0);
+    assert!(extractor.audio_graph().unwrap().filters.last().unwrap().starts_with("loudnorm=I=-14:TP=-1:"));
+}
+
+#[test]
+fn test_loudness_event_display() {
+    let event = ProgressEvent::Loudness { measured: -27.47, target: -16.0 };
+    assert_eq!(event.to_string(), "Loudness: -27.5 LUFS, normalizing to -16.0 LUFS");
+}
@@ -0,0 +1,208 @@
+mod common;
+
+use audio_extractor::native::{self, Backend, WavWriter};
+use audio_extractor::{Args, AudioExtractor, AudioFormat, BitDepth, Tags};
+use std::fs::File;
+use std::io::Cursor;
+use std::path::Path;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+use tempfile::TempDir;
+
+/// The tags symphonia reads from `path`, as the native backend names them
+fn read_tags(path: &Path) -> Vec<(&'static str, String)> {
+    let mss = MediaSourceStream::new(Box::new(File::open(path).unwrap()), Default::default());
+    let mut hint = Hint::new();
+    hint.with_extension(&path.extension().unwrap().to_string_lossy());
+    let mut probed = symphonia::default::get_probe()
+        .format(&hint, mss, &FormatOptions::default(), &MetadataOptions::default())
+        .unwrap();
+    let outer = probed.metadata.get();
+    native::standard_tags(outer.as_ref().and_then(|m| m.current()).into_iter().chain(probed.format.metadata().current()))
+}
+
+#[test]
+fn test_native_extracts_wav_and_flac() {
+    let dir = TempDir::new().unwrap();
+    let input = dir.path().join("tone.wav");
+    common::write_test_tone(&input);
+    
+    for (format, name) in [(AudioFormat::Wav, "out.wav"), (AudioFormat::Flac, "out.flac")] {
+        let path = dir.path().join(name);
+        let (_, output) = native::extract(&input, None, 0.0, None, &format, true, &Tags::default(), File::create(&path).unwrap()).unwrap();
+        assert_eq!((output.sample_rate, output.channels, output.frames), (44_100, 1, 44_100));
+        
+        let info = AudioExtractor::verify_standalone(&path).unwrap();
+        assert_eq!(info.sample_rate, Some(44_100));
+        assert_eq!(info.duration, Some(1.0));
+    }
+}
+
+#[test]
+fn test_native_writes_aiff_with_text_chunks() {
+    let dir = TempDir::new().unwrap();
+    let input = dir.path().join("tagged.wav");
+    let tags = vec![
+        ("title", "Opening Night".to_string()),
+        ("artist", "Les Élèves".to_string()),
+        ("date", "2023-05-14".to_string()),
+    ];
+    let mut wav = WavWriter::with_tags(File::create(&input).unwrap(), 44_100, 2, &tags).unwrap();
+    wav.write(&vec![1000; 44_100 * 2]).unwrap();
+    wav.finish().unwrap();
+    
+    let path = dir.path().join("out.aiff");
+    let (_, output) = native::extract(&input, None, 0.0, None, &AudioFormat::Aiff, true, &Tags::default(), File::create(&path).unwrap()).unwrap();
+    assert_eq!((output.sample_rate, output.channels, output.frames), (44_100, 2, 44_100));
+    let info = AudioExtractor::verify_standalone(&path).unwrap();
+    assert_eq!((info.sample_rate, info.channels), (Some(44_100), Some(2)));
+    
+    let bytes = std::fs::read(&path).unwrap();
+    // COMM's frame count; symphonia counts SSND's offset and block size as two more frames
+    assert_eq!(bytes[22..26], 44_100u32.to_be_bytes());
+    // AIFF has text chunks for the title and artist but not the date
+    let has = |needle: &[u8]| bytes.windows(needle.len()).any(|w| w == needle);
+    assert!(has(b"NAME\0\0\0\x0dOpening Night"));
+    assert!(has(&[b"AUTH".as_slice(), &12u32.to_be_bytes(), "Les Élèves".as_bytes()].concat()));
+    assert!(!has(b"2023-05-14"));
+    // Big-endian samples
+    assert_eq!(bytes[bytes.len() - 2..], 1000i16.to_be_bytes());
+}
+
+#[test]
+fn test_bit_depth_applies_to_pcm_outputs() {
+    use clap::Parser;
+    
+    let parse = |output: &str, extra: &[&str]| {
+        let args = Args::try_parse_from(["audio_extractor", "-i", "in.mp4", "-o", output, "-f"].iter().chain(extra)).unwrap();
+        AudioExtractor::new(args)
+    };
+    assert_eq!(parse("out.aiff", &["aiff"]).bit_depth().unwrap(), BitDepth::Sixteen);
+    assert_eq!(parse("out.aiff", &["aiff", "--bit-depth", "24"]).bit_depth().unwrap(), BitDepth::TwentyFour);
+    assert_eq!(parse("out.wav", &["wav", "--bit-depth", "24"]).bit_depth().unwrap(), BitDepth::TwentyFour);
+    assert!(parse("out.mp3", &["mp3", "--bit-depth", "24"]).bit_depth().is_err());
+    assert!(parse("out.mp3", &["mp3"]).bit_depth().is_ok());
+    
+    assert_eq!(AudioFormat::Aiff.pcm_codec(BitDepth::TwentyFour), Some("pcm_s24be"));
+    assert_eq!(AudioFormat::Wav.pcm_codec(BitDepth::TwentyFour), Some("pcm_s24le"));
+    assert_eq!(AudioFormat::Aiff.codec(), "pcm_s16be");
+    assert_eq!(AudioFormat::Flac.pcm_codec(BitDepth::Sixteen), None);
+}
+
+#[test]
+fn test_native_trims_to_the_sample() {
+    let dir = TempDir::new().unwrap();
+    let input = dir.path().join("tone.wav");
+    common::write_test_tone(&input);
+    
+    let (wav, output) = native::extract(&input, None, 0.25, Some(0.75), &AudioFormat::Wav, true, &Tags::default(), Cursor::new(Vec::new())).unwrap();
+    assert_eq!(output.frames, 22_050);
+    assert_eq!(wav.into_inner().len(), 44 + 22_050 * 2);
+}
+
+#[test]
+fn test_native_refuses_lossy_output_and_missing_tracks() {
+    let dir = TempDir::new().unwrap();
+    let input = dir.path().join("tone.wav");
+    common::write_test_tone(&input);
+    
+    let err = native::extract(&input, None, 0.0, None, &AudioFormat::Mp3, true, &Tags::default(), Cursor::new(Vec::new())).unwrap_err();
+    assert!(err.to_string().contains("needs FFmpeg"));
+    let err = native::extract(&input, Some(1), 0.0, None, &AudioFormat::Wav, true, &Tags::default(), Cursor::new(Vec::new())).unwrap_err();
+    assert!(err.to_string().contains("no audio track 1"));
+    
+    let text = dir.path().join("notes.mp4");
+    std::fs::write(&text, "not media").unwrap();
+    assert!(native::extract(&text, None, 0.0, None, &AudioFormat::Wav, true, &Tags::default(), Cursor::new(Vec::new())).is_err());
+}
+
+#[test]
+fn test_backend_is_selectable_on_the_extractor() {
+    let dir = TempDir::new().unwrap();
+    let input = dir.path().join("tone.mp4");
+    common::write_test_tone(&input);
+    let output = dir.path().join("out.flac");
+    
+    let args = Args {
+        input: input.clone(),
+        output: output.clone(),
+        format: Some(AudioFormat::Flac),
+        ..Default::default()
+    };
+    let extractor = AudioExtractor::new(args).with_backend(Backend::Native);
+    assert_eq!(extractor.backend(), Backend::Native);
+    extractor.extract().unwrap();
+    assert_eq!(AudioExtractor::verify_standalone(&output).unwrap().duration, Some(1.0));
+    
+    let args = Args {
+        input,
+        output: dir.path().join("out.aac"),
+        format: Some(AudioFormat::Aac),
+        ..Default::default()
+    };
+    let err = AudioExtractor::new(args).with_backend(Backend::Native).extract().unwrap_err();
+    assert!(format!("{:#}", err).contains("AAC output needs FFmpeg"));
+}
+
+#[test]
+fn test_native_copies_tags() {
+    let dir = TempDir::new().unwrap();
+    let input = dir.path().join("tagged.wav");
+    let tags = vec![
+        ("title", "Opening Night".to_string()),
+        ("artist", "Les Élèves".to_string()),
+        ("date", "2023-05-14".to_string()),
+    ];
+    let mut wav = WavWriter::with_tags(File::create(&input).unwrap(), 8_000, 1, &tags).unwrap();
+    wav.write(&[0, 1000, -1000, 0]).unwrap();
+    wav.finish().unwrap();
+    assert_eq!(read_tags(&input), tags);
+    
+    for name in ["out.flac", "out.wav"] {
+        let format = if name.ends_with("flac") { AudioFormat::Flac } else { AudioFormat::Wav };
+        let path = dir.path().join(name);
+        let (_, output) = native::extract(&input, None, 0.0, None, &format, true, &Tags::default(), File::create(&path).unwrap()).unwrap();
+        assert_eq!(output.tags, tags);
+        assert_eq!(read_tags(&path), tags);
+        assert_eq!(AudioExtractor::verify_standalone(&path).unwrap().sample_rate, Some(8_000));
+        
+        let (_, output) = native::extract(&input, None, 0.0, None, &format, false, &Tags::default(), File::create(&path).unwrap()).unwrap();
+        assert!(output.tags.is_empty());
+        assert!(read_tags(&path).is_empty());
+    }
+}
+
+#[test]
+fn test_copy_metadata_defaults_on() {
+    use clap::Parser;
+    
+    let parse = |extra: &[&str]| {
+        let args = Args::try_parse_from(["audio_extractor", "-i", "in.mp4", "-o", "out.mp3"].iter().chain(extra)).unwrap();
+        AudioExtractor::new(args)
+    };
+    assert!(parse(&[]).copies_metadata());
+    assert!(parse(&["--copy-metadata"]).copies_metadata());
+    let extractor = parse(&["--copy-metadata", "false"]);
+    assert!(!extractor.copies_metadata());
+    assert_eq!(extractor.metadata_args(), vec!["-map_metadata", "-1"]);
+    assert_eq!(parse(&[]).metadata_args()[..2], ["-map_metadata", "0"]);
+}
+
+#[test]
+fn test_native_refuses_resampling() {
+    let dir = TempDir::new().unwrap();
+    let input = dir.path().join("tone.mp4");
+    common::write_test_tone(&input);
+    
+    let args = Args {
+        input,
+        output: dir.path().join("out.wav"),
+        format: Some(AudioFormat::Wav),
+        sample_rate: Some(48_000),
+        ..Default::default()
+    };
+    let err = AudioExtractor::new(args).with_backend(Backend::Native).extract().unwrap_err();
+    assert!(format!("{:#}", err).contains("--sample-rate and --channels need FFmpeg"));
+}
@@ -0,0 +1,56 @@
+use audio_extractor::{Args, AudioExtractor, AudioFormat, ExtractionOptions, Preset};
+use std::path::{Path, PathBuf};
+
+#[test]
+fn test_builder_sets_options() {
+    let options = ExtractionOptions::new("talk.mp4", "talk.flac")
+        .format(AudioFormat::Flac)
+        .bitrate(256)
+        .verify(true)
+        .sample_rate(48_000)
+        .channels(1)
+        .start(30.0)
+        .duration(60.0)
+        .audio_track(1);
+    assert_eq!(options.input(), Path::new("talk.mp4"));
+    assert_eq!(options.output(), Path::new("talk.flac"));
+
+    let extractor = AudioExtractor::new(options);
+    assert_eq!(extractor.args.format, Some(AudioFormat::Flac));
+    assert_eq!(extractor.args.quality, Some(256));
+    assert!(extractor.args.verify);
+    assert_eq!(extractor.sampling(), (Some(48_000), Some(1)));
+    assert_eq!(extractor.args.start, Some(30.0));
+    assert_eq!(extractor.args.duration, Some(60.0));
+    assert_eq!(extractor.args.audio_track, Some(1));
+}
+
+#[test]
+fn test_vbr_replaces_bitrate() {
+    let options = ExtractionOptions::new("talk.mp4", "talk.mp3")
+        .format(AudioFormat::Mp3)
+        .bitrate(192)
+        .vbr(Some(2.0));
+    let extractor = AudioExtractor::new(options);
+    assert_eq!(extractor.vbr_level().unwrap(), Some(2.0));
+}
+
+#[test]
+fn test_preset_fills_unset_options() {
+    let extractor = AudioExtractor::new(ExtractionOptions::new("talk.mp4", "talk.wav").preset(Preset::Transcription));
+    assert_eq!(extractor.args.format, Some(AudioFormat::Wav));
+    assert_eq!(extractor.sampling(), (Some(16_000), Some(1)));
+}
+
+#[test]
+fn test_args_convert_into_options() {
+    let args = Args {
+        input: PathBuf::from("movie.mkv"),
+        output: PathBuf::from("movie.opus"),
+        format: Some(AudioFormat::Opus),
+        ..Default::default()
+    };
+    let options = ExtractionOptions::from(args);
+    assert_eq!(options.input(), Path::new("movie.mkv"));
+    assert_eq!(AudioExtractor::new(options).args.format, Some(AudioFormat::Opus));
+}
@@ -0,0 +1,108 @@
+use audio_extractor::id3::Id3Version;
+use audio_extractor::preflight::{self, Preflight};
+use audio_extractor::{Args, AudioExtractor, AudioFormat, PlannedJob};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn job(input: &str, size: u64, problem: Option<&str>) -> PlannedJob {
+    PlannedJob {
+        estimated_size: Some(size),
+        problems: problem.map(str::to_string).into_iter().collect(),
+        ..PlannedJob::new(Path::new(input))
+    }
+}
+
+#[test]
+fn test_estimate_size() {
+    // An hour of 128 kbps MP3 is about 57.6 MB
+    assert_eq!(preflight::estimate_size(&AudioFormat::Mp3, 128, 3600.0), 57_600_000);
+    assert_eq!(preflight::estimate_size(&AudioFormat::Wav, 128, 1.0), 176_400);
+    assert!(preflight::estimate_size(&AudioFormat::Flac, 128, 1.0) < 176_400);
+    assert_eq!(preflight::estimate_size(&AudioFormat::Aac, 128, -1.0), 0);
+    
+    assert_eq!(preflight::format_size(512), "512 B");
+    assert_eq!(preflight::format_size(57_600_000), "57.6 MB");
+    assert_eq!(preflight::format_size(1_500_000_000_000), "1.5 TB");
+}
+
+#[test]
+fn test_go_no_go() {
+    let dir = TempDir::new().unwrap();
+    let preflight = Preflight::new(vec![job("a.mp4", 1000, None), job("b.mp4", 2000, None)], &[], dir.path());
+    assert_eq!(preflight.estimated_size, 3000);
+    assert!(preflight.go());
+    assert!(preflight.to_string().ends_with("GO"));
+    
+    let preflight = Preflight::new(vec![job("a.mp4", 1000, None), job("b.mp4", 0, Some("No audio stream"))], &[], dir.path());
+    assert!(!preflight.go());
+    let summary = preflight.to_string();
+    assert!(summary.contains("No audio stream"));
+    assert!(summary.contains("1 with problems"));
+    assert!(summary.ends_with("NO-GO"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_not_enough_space() {
+    let dir = TempDir::new().unwrap();
+    let free = preflight::free_space(&dir.path().join("not/yet/created")).unwrap();
+    let preflight = Preflight::new(vec![job("huge.mkv", free, None)], &[], dir.path());
+    assert!(!preflight.go());
+    assert!(preflight.problems[0].contains("free"));
+}
+
+#[test]
+fn test_preflight_stops_before_extracting() {
+    let root = TempDir::new().unwrap();
+    fs::write(root.path().join("a.mp4"), b"x").unwrap();
+    fs::write(root.path().join("b.mp4"), b"x").unwrap();
+    let out = TempDir::new().unwrap();
+    let report = out.path().join("preflight.json");
+    
+    // ID3 options are only valid for MP3, so every job has a problem
+    let args = Args {
+        input: root.path().to_path_buf(),
+        output: out.path().join("audio"),
+        format: Some(AudioFormat::Flac),
+        id3_version: Some(Id3Version::V23),
+        preflight: true,
+        preflight_report: Some(report.clone()),
+        ..Default::default()
+    };
+    let err = AudioExtractor::new(args).extract_directory().unwrap_err();
+    assert!(err.to_string().contains("Preflight"));
+    assert!(!out.path().join("audio").exists());
+    
+    let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(report).unwrap()).unwrap();
+    assert_eq!(json["jobs"].as_array().unwrap().len(), 2);
+    assert!(json["jobs"][0]["problems"][0].as_str().unwrap().contains("MP3"));
+}
+
+#[test]
+fn test_parse_selection() {
+    assert_eq!(preflight::parse_selection("3, 7-9", 10).unwrap(), vec![2, 6, 7, 8]);
+    assert_eq!(preflight::parse_selection("1", 1).unwrap(), vec![0]);
+    assert!(preflight::parse_selection("0", 10).is_err());
+    assert!(preflight::parse_selection("11", 10).is_err());
+    assert!(preflight::parse_selection("9-7", 10).is_err());
+    assert!(preflight::parse_selection("all", 10).is_err());
+}
+
+#[test]
+fn test_confirm_deselects_jobs() {
+    let jobs: Vec<_> = (1..=5).map(|n| job(&format!("{}.mp4", n), 0, None)).collect();
+    let mut shown = Vec::new();
+    
+    let mut answers = "2-4\nbogus\n+3\ny\n".as_bytes();
+    let selected = preflight::confirm(&jobs, &mut answers, &mut shown).unwrap();
+    assert_eq!(selected, Some(vec![0, 2, 4]));
+    let shown = String::from_utf8(shown).unwrap();
+    assert!(shown.contains("Extract 5 of 5"));
+    assert!(shown.contains("Extract 2 of 5"));
+    assert!(shown.contains("Extract 3 of 5"));
+    
+    assert_eq!(preflight::confirm(&jobs, &mut "n\n".as_bytes(), &mut Vec::new()).unwrap(), None);
+    // Closing the input cancels rather than starting the batch
+    assert_eq!(preflight::confirm(&jobs, &mut "".as_bytes(), &mut Vec::new()).unwrap(), None);
+}
@@ -0,0 +1,33 @@
+use audio_extractor::{Args, AudioExtractor, AudioFormat, BitDepth, Preset};
+use clap::Parser;
+
+fn extractor(extra: &[&str]) -> AudioExtractor {
+    let args = Args::try_parse_from(["audio_extractor", "-i", "meeting.mp4", "-o", "meeting.wav"].iter().chain(extra)).unwrap();
+    AudioExtractor::new(args)
+}
+
+#[test]
+fn test_transcription_preset() {
+    let extractor = extractor(&["--preset", "transcription"]);
+    assert_eq!(extractor.args.format, Some(AudioFormat::Wav));
+    assert_eq!(extractor.args.bit_depth, Some(BitDepth::Sixteen));
+    assert_eq!(extractor.sampling(), (Some(16_000), Some(1)));
+    assert!(extractor.check_sampling().is_ok());
+}
+
+#[test]
+fn test_options_override_the_preset() {
+    let extractor = extractor(&["--preset", "transcription", "--sample-rate", "8000", "-f", "flac"]);
+    assert_eq!(extractor.args.format, Some(AudioFormat::Flac));
+    assert_eq!(extractor.sampling(), (Some(8_000), Some(1)));
+}
+
+#[test]
+fn test_preset_leaves_unpinned_settings() {
+    let settings = Preset::Transcription.settings();
+    assert_eq!(settings.format, Some(AudioFormat::Wav));
+    let mut args = Args { quality: Some(64), ..Default::default() };
+    Preset::Transcription.apply(&mut args);
+    assert_eq!(args.quality, Some(64));
+    assert_eq!(args.channels, Some(1));
+}
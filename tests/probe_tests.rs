@@ -0,0 +1,91 @@
+use audio_extractor::probe::{AudioStream, MediaInfo, VideoStream};
+
+const PROBE: &str = r#"{
+    "streams": [
+        {"index": 0, "codec_type": "video", "codec_name": "h264", "width": 1920, "height": 1080, "avg_frame_rate": "24000/1001", "bit_rate": "4500000", "disposition": {"default": 1, "attached_pic": 0}},
+        {"index": 1, "codec_type": "audio", "codec_name": "ac3", "channels": 6, "channel_layout": "5.1(side)", "sample_rate": "48000", "bit_rate": "448000", "tags": {"language": "eng"}, "disposition": {"default": 1}},
+        {"index": 2, "codec_type": "audio", "codec_name": "aac", "channels": 2, "sample_rate": "48000", "tags": {"language": "und", "title": "Commentary"}, "disposition": {"default": 0}},
+        {"index": 3, "codec_type": "subtitle", "codec_name": "subrip"},
+        {"index": 4, "codec_type": "video", "codec_name": "mjpeg", "avg_frame_rate": "0/0", "disposition": {"attached_pic": 1}}
+    ],
+    "format": {"format_name": "matroska,webm", "format_long_name": "Matroska / WebM", "duration": "6727.040000", "size": "4206720000", "bit_rate": "5003000"}
+}"#;
+
+#[test]
+fn test_media_info_from_ffprobe() {
+    let info = MediaInfo::from_ffprobe_json(PROBE).unwrap();
+    assert_eq!(info.container, "matroska,webm");
+    assert_eq!(info.container_name.as_deref(), Some("Matroska / WebM"));
+    assert_eq!(info.duration, Some(6727.04));
+    assert_eq!(info.size, Some(4_206_720_000));
+    assert_eq!(info.bitrate, Some(5_003_000));
+
+    assert_eq!(info.video.len(), 1);
+    let video = &info.video[0];
+    assert_eq!((video.codec.as_str(), video.width, video.height), ("h264", Some(1920), Some(1080)));
+    assert!((video.frame_rate.unwrap() - 23.976).abs() < 0.001);
+
+    assert_eq!(info.audio, [
+        AudioStream {
+            index: 0,
+            stream_index: 1,
+            codec: "ac3".into(),
+            channels: Some(6),
+            channel_layout: Some("5.1(side)".into()),
+            sample_rate: Some(48_000),
+            bitrate: Some(448_000),
+            language: Some("eng".into()),
+            title: None,
+            default: true,
+        },
+        AudioStream {
+            index: 1,
+            stream_index: 2,
+            codec: "aac".into(),
+            channels: Some(2),
+            channel_layout: None,
+            sample_rate: Some(48_000),
+            bitrate: None,
+            language: None,
+            title: Some("Commentary".into()),
+            default: false,
+        },
+    ]);
+}
+
+#[test]
+fn test_media_info_display() {
+    let info = MediaInfo::from_ffprobe_json(PROBE).unwrap();
+    assert_eq!(info.to_string(), "Container: matroska,webm (Matroska / WebM)\n\
+        Duration: 01:52:07.040\n\
+        Size: 4.2 GB, 5003 kbps\n\
+        Video 0: h264, 1920x1080, 23.976 fps, 4500 kbps\n\
+        Audio 0: ac3, 6 ch, 48000 Hz, 448 kbps, eng (default)\n\
+        Audio 1: aac, 2 ch, 48000 Hz, unknown language, \"Commentary\"\n");
+}
+
+#[test]
+fn test_media_info_json_round_trip() {
+    let info = MediaInfo::from_ffprobe_json(PROBE).unwrap();
+    let json = serde_json::to_string(&info).unwrap();
+    assert_eq!(serde_json::from_str::<MediaInfo>(&json).unwrap(), info);
+}
+
+#[test]
+fn test_sparse_ffprobe_output() {
+    let info = MediaInfo::from_ffprobe_json(r#"{"streams": [{"index": 0, "codec_type": "video"}]}"#).unwrap();
+    assert_eq!(info.container, "unknown");
+    assert_eq!(info.duration, None);
+    assert_eq!(info.video, [VideoStream {
+        index: 0,
+        stream_index: 0,
+        codec: "unknown".into(),
+        width: None,
+        height: None,
+        frame_rate: None,
+        bitrate: None,
+        language: None,
+    }]);
+    assert!(info.to_string().ends_with("No audio streams\n"));
+    assert!(MediaInfo::from_ffprobe_json("not json").is_err());
+}
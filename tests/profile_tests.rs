@@ -0,0 +1,93 @@
+use audio_extractor::{Args, AudioExtractor, AudioFormat, Config, Profile};
+use clap::Parser;
+
+const CONFIG: &str = r#"
+[profiles.podcast]
+format = "mp3"
+quality = 96
+channels = 1
+normalize = true
+target_lufs = -16
+output_template = "{stem}.mp3"
+
+[profiles.archive]
+format = "flac"
+verify = true
+"#;
+
+fn args(extra: &[&str]) -> Args {
+    Args::try_parse_from(["audio_extractor", "-i", "episode.mp4", "-o", "episode.mp3"].iter().chain(extra)).unwrap()
+}
+
+fn podcast() -> Profile {
+    let mut config: Config = toml::from_str(CONFIG).unwrap();
+    config.profiles.remove("podcast").unwrap()
+}
+
+#[test]
+fn test_profiles_parse_from_config() {
+    let config: Config = toml::from_str(CONFIG).unwrap();
+    assert_eq!(config.profiles.len(), 2);
+
+    let podcast = &config.profiles["podcast"];
+    assert_eq!(podcast.format, Some(AudioFormat::Mp3));
+    assert_eq!(podcast.quality, Some(96));
+    assert_eq!(podcast.normalize, Some(true));
+    assert_eq!(podcast.output_template.as_deref(), Some("{stem}.mp3"));
+
+    let archive = &config.profiles["archive"];
+    assert_eq!(archive.format, Some(AudioFormat::Flac));
+    assert_eq!(archive.verify, Some(true));
+    assert_eq!(archive.quality, None);
+}
+
+#[test]
+fn test_profile_fills_unset_options() {
+    let mut args = args(&[]);
+    podcast().apply(&mut args);
+    assert_eq!(args.format, Some(AudioFormat::Mp3));
+    assert_eq!(args.quality, Some(96));
+    assert_eq!(args.channels, Some(1));
+    assert!(args.normalize);
+    assert_eq!(args.target_lufs, Some(-16.0));
+}
+
+#[test]
+fn test_command_line_overrides_profile() {
+    let mut args = args(&["-f", "opus", "--channels", "2", "--normalize", "--target-lufs", "-14"]);
+    podcast().apply(&mut args);
+    assert_eq!(args.format, Some(AudioFormat::Opus));
+    assert_eq!(args.channels, Some(2));
+    assert_eq!(args.target_lufs, Some(-14.0));
+    assert_eq!(args.quality, Some(96));
+}
+
+#[test]
+fn test_command_line_rate_control_replaces_profile_bitrate() {
+    let mut args = args(&["--vbr", "3"]);
+    podcast().apply(&mut args);
+    assert_eq!(args.quality, None);
+    assert_eq!(args.vbr, Some(Some(3.0)));
+}
+
+#[test]
+fn test_merged_options() {
+    let mut args = args(&[]);
+    podcast().apply(&mut args);
+    let options = AudioExtractor::new(args).resolved_options();
+    assert_eq!(options.format, AudioFormat::Mp3);
+    assert_eq!(options.quality, 96);
+    assert_eq!(options.channels, Some(1));
+    assert_eq!(options.normalize.map(|target| target.integrated), Some(-16.0));
+    assert_eq!(options.vbr, None);
+}
+
+#[test]
+fn test_unknown_profile_is_rejected() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("episode.mp4");
+    std::fs::write(&input, b"").unwrap();
+    let args = Args { input, profile: Some("no-such-profile".into()), ..Default::default() };
+    let error = AudioExtractor::new(args).validate_input().unwrap_err();
+    assert!(error.to_string().contains("Unknown profile"));
+}
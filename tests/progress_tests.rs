@@ -0,0 +1,97 @@
+mod common;
+
+use audio_extractor::{Args, AudioExtractor, AudioFormat, ProgressEvent, ProgressParser, StallAction};
+use clap::Parser;
+use std::sync::Mutex;
+use tempfile::TempDir;
+
+#[test]
+fn test_parser_reports_each_progress_block() {
+    let mut parser = ProgressParser::new(Some(200.0));
+    let mut events = Vec::new();
+    for line in "frame=0\nout_time_us=50000000\nout_time_ms=50000000\nout_time=00:00:50.000000\nprogress=continue\n\
+                 out_time_us=N/A\nprogress=continue\nout_time_us=199990000\nprogress=end\n".lines() {
+        events.extend(parser.line(line));
+    }
+    assert_eq!(events, vec![
+        ProgressEvent::Encoding { seconds_done: 50.0, percent: Some(25.0) },
+        // An unreadable time keeps the last one
+        ProgressEvent::Encoding { seconds_done: 50.0, percent: Some(25.0) },
+        ProgressEvent::Encoding { seconds_done: 200.0, percent: Some(100.0) },
+    ]);
+}
+
+#[test]
+fn test_parser_without_duration_has_no_percent() {
+    let mut parser = ProgressParser::new(None);
+    assert_eq!(parser.line("progress=continue"), None);
+    parser.line("out_time_us=1500000");
+    assert_eq!(parser.line("progress=continue"), Some(ProgressEvent::Encoding { seconds_done: 1.5, percent: None }));
+    assert_eq!(
+        ProgressEvent::Encoding { seconds_done: 75.0, percent: Some(42.4) }.to_string(),
+        "Encoding: 00:01:15.000 (42%)"
+    );
+}
+
+#[test]
+fn test_expected_duration_follows_time_range() {
+    let extractor = |start, end, duration| AudioExtractor::new(Args { start, end, duration, ..Default::default() });
+    assert_eq!(extractor(None, None, None).expected_duration(600.0), 600.0);
+    assert_eq!(extractor(Some(60.0), Some(120.0), None).expected_duration(600.0), 60.0);
+    assert_eq!(extractor(Some(550.0), None, Some(90.0)).expected_duration(600.0), 50.0);
+    assert_eq!(extractor(None, Some(900.0), None).expected_duration(600.0), 600.0);
+}
+
+#[test]
+fn test_extract_with_progress_sends_typed_events() {
+    let dir = TempDir::new().unwrap();
+    let input = common::create_test_video_file();
+    let args = Args {
+        input: input.path().to_path_buf(),
+        output: dir.path().join("out.flac"),
+        format: Some(AudioFormat::Flac),
+        verify: true,
+        ..Default::default()
+    };
+    let events = Mutex::new(Vec::new());
+    AudioExtractor::new(args).extract_with_progress(|event| events.lock().unwrap().push(event.clone())).unwrap();
+    
+    let events = events.into_inner().unwrap();
+    assert_eq!(events.first(), Some(&ProgressEvent::Started));
+    assert_eq!(events.last(), Some(&ProgressEvent::Finished));
+    assert!(events.contains(&ProgressEvent::Step("Verification completed")));
+    let percents: Vec<f64> = events.iter()
+        .filter_map(|event| match event {
+            ProgressEvent::Encoding { percent, .. } => *percent,
+            _ => None,
+        })
+        .collect();
+    assert!(percents.windows(2).all(|pair| pair[0] <= pair[1]));
+}
+
+#[test]
+fn test_stall_options() {
+    let parse = |extra: &[&str]| Args::try_parse_from(["audio_extractor", "-i", "in.mp4", "-o", "out.mp3"].iter().chain(extra));
+    let args = parse(&[]).unwrap();
+    assert_eq!((args.stall_timeout, args.on_stall), (None, StallAction::Warn));
+    let args = parse(&["--stall-timeout", "120", "--on-stall", "retry"]).unwrap();
+    assert_eq!((args.stall_timeout, args.on_stall), (Some(120), StallAction::Retry));
+    assert!(parse(&["--on-stall", "ignore"]).is_err());
+    assert_eq!(ProgressEvent::Stalled { seconds: 120 }.to_string(), "No progress from FFmpeg for 120 seconds");
+}
+
+#[test]
+fn test_watched_encode_that_keeps_progressing_succeeds() {
+    let dir = TempDir::new().unwrap();
+    let input = common::create_test_video_file();
+    let args = Args {
+        input: input.path().to_path_buf(),
+        output: dir.path().join("out.flac"),
+        format: Some(AudioFormat::Flac),
+        stall_timeout: Some(30),
+        on_stall: StallAction::Kill,
+        ..Default::default()
+    };
+    AudioExtractor::new(args).extract().unwrap();
+    assert!(dir.path().join("out.flac").exists());
+}
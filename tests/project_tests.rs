@@ -0,0 +1,66 @@
+use audio_extractor::{project, scan};
+use audio_extractor::{Args, AudioExtractor};
+use std::fs;
+use tempfile::TempDir;
+
+fn write_export(dir: &std::path::Path) {
+    fs::create_dir_all(dir).unwrap();
+    for frame in 1..=3 {
+        fs::write(dir.join(format!("shot_{:04}.png", frame)), b"png").unwrap();
+    }
+    fs::write(dir.join("scratch.wav"), vec![0u8; 10]).unwrap();
+    fs::write(dir.join("mix.wav"), vec![0u8; 100]).unwrap();
+}
+
+#[test]
+fn test_audio_track_of_project_folder() {
+    let root = TempDir::new().unwrap();
+    let export = root.path().join("export");
+    write_export(&export);
+    
+    // The largest audio file is the main mix
+    assert_eq!(project::audio_track(&export).unwrap(), Some(export.join("mix.wav")));
+    
+    // Frames without audio, or audio with a single still, are not projects
+    let frames_only = root.path().join("frames");
+    fs::create_dir(&frames_only).unwrap();
+    fs::write(frames_only.join("f001.png"), b"png").unwrap();
+    fs::write(frames_only.join("f002.png"), b"png").unwrap();
+    assert_eq!(project::audio_track(&frames_only).unwrap(), None);
+    
+    let poster = root.path().join("poster");
+    fs::create_dir(&poster).unwrap();
+    fs::write(poster.join("cover.jpg"), b"jpg").unwrap();
+    fs::write(poster.join("song.mp3"), b"mp3").unwrap();
+    assert_eq!(project::audio_track(&poster).unwrap(), None);
+}
+
+#[test]
+fn test_project_folder_is_a_single_input() {
+    let root = TempDir::new().unwrap();
+    write_export(&root.path().join("reel/export"));
+    fs::write(root.path().join("reel/clip.mp4"), b"x").unwrap();
+    
+    let scan = scan::scan(root.path(), &["mp4"], None).unwrap();
+    assert_eq!(scan.inputs, vec![root.path().join("reel/clip.mp4"), root.path().join("reel/export")]);
+    assert!(scan.skipped.is_empty());
+}
+
+#[test]
+fn test_extractor_reads_project_audio_track() {
+    let root = TempDir::new().unwrap();
+    write_export(root.path());
+    
+    let args = Args {
+        input: root.path().to_path_buf(),
+        output: root.path().join("out/export.mp3"),
+        ..Default::default()
+    };
+    let extractor = AudioExtractor::new(args);
+    assert_eq!(extractor.source(), root.path().join("mix.wav"));
+    extractor.validate_input().unwrap();
+    
+    let empty = TempDir::new().unwrap();
+    let args = Args { input: empty.path().to_path_buf(), ..Default::default() };
+    assert!(AudioExtractor::new(args).validate_input().is_err());
+}
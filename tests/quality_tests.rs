@@ -0,0 +1,147 @@
+mod common;
+
+use audio_extractor::quality::{self, auto_bitrate, SourceAudio};
+use audio_extractor::{Args, AudioExtractor, AudioFormat};
+use clap::Parser;
+use std::process::Command;
+use tempfile::TempDir;
+
+fn source(codec: &str, bit_rate: Option<u32>, channels: u32) -> SourceAudio {
+    SourceAudio { codec: codec.to_string(), bit_rate, channels: Some(channels), sample_rate: Some(48000) }
+}
+
+#[test]
+fn test_parse_quality() {
+    assert_eq!(quality::parse_quality("auto"), Ok(quality::AUTO));
+    assert_eq!(quality::parse_quality("AUTO"), Ok(quality::AUTO));
+    assert_eq!(quality::parse_quality("192"), Ok(192));
+    assert!(quality::parse_quality("0").is_err());
+    assert!(quality::parse_quality("high").is_err());
+    
+    let args = Args::try_parse_from(["audio_extractor", "-i", "in.mp4", "-o", "out.mp3", "--quality", "auto"]).unwrap();
+    assert_eq!(args.quality, Some(quality::AUTO));
+}
+
+#[test]
+fn test_source_audio_from_ffprobe_json() {
+    let json = r#"{"streams": [{"codec_name": "aac", "bit_rate": "96000", "channels": 2, "sample_rate": "48000"}]}"#;
+    assert_eq!(SourceAudio::from_ffprobe_json(json).unwrap(), Some(source("aac", Some(96), 2)));
+    
+    let json = r#"{"streams": [{"codec_name": "opus", "channels": 1}]}"#;
+    assert_eq!(SourceAudio::from_ffprobe_json(json).unwrap().unwrap().bit_rate, None);
+    assert_eq!(SourceAudio::from_ffprobe_json(r#"{"streams": []}"#).unwrap(), None);
+}
+
+#[test]
+fn test_auto_bitrate_never_exceeds_source() {
+    assert_eq!(auto_bitrate(Some(&source("mp3", Some(96), 2)), &AudioFormat::Mp3), 96);
+    assert_eq!(auto_bitrate(Some(&source("aac", Some(96), 2)), &AudioFormat::Mp3), 96);
+    assert_eq!(auto_bitrate(Some(&source("aac", Some(100), 2)), &AudioFormat::Aac), 96);
+    assert_eq!(auto_bitrate(Some(&source("mp3", Some(128), 2)), &AudioFormat::Aac), 80);
+    assert_eq!(auto_bitrate(Some(&source("mp3", Some(320), 2)), &AudioFormat::Mp3), 320);
+    assert_eq!(auto_bitrate(Some(&source("ac3", Some(640), 6)), &AudioFormat::Aac), 256);
+    assert_eq!(auto_bitrate(Some(&source("aac", Some(8), 1)), &AudioFormat::Mp3), 32);
+}
+
+#[test]
+fn test_auto_bitrate_defaults() {
+    assert_eq!(auto_bitrate(Some(&source("pcm_s16le", Some(1536), 2)), &AudioFormat::Mp3), 256);
+    assert_eq!(auto_bitrate(Some(&source("flac", None, 1)), &AudioFormat::Aac), 96);
+    assert_eq!(auto_bitrate(Some(&source("opus", None, 2)), &AudioFormat::Aac), 192);
+    assert_eq!(auto_bitrate(None, &AudioFormat::Mp3), 256);
+}
+
+#[test]
+fn test_lossy_formats() {
+    assert!(AudioFormat::Mp3.is_lossy());
+    assert!(AudioFormat::Aac.is_lossy());
+    assert!(!AudioFormat::Flac.is_lossy());
+    assert!(!AudioFormat::Wav.is_lossy());
+    assert!(AudioFormat::Opus.is_lossy());
+    assert!(AudioFormat::Ogg.is_lossy());
+    
+    let args = Args::try_parse_from(["audio_extractor", "-i", "in.mp4", "-o", "out.mp3", "--allow-lossy-transcode"]).unwrap();
+    assert!(args.allow_lossy_transcode);
+}
+
+#[test]
+fn test_opus_and_vorbis_formats() {
+    let args = Args::try_parse_from(["audio_extractor", "-i", "in.webm", "-o", "out.opus", "--format", "opus"]).unwrap();
+    assert_eq!(args.format, Some(AudioFormat::Opus));
+    assert_eq!(AudioFormat::Opus.to_string(), "opus");
+    assert_eq!(AudioFormat::Ogg.to_string(), "ogg");
+    // Stream copy compares these with the source's ffprobe codec name
+    assert_eq!(AudioFormat::Opus.codec(), "opus");
+    assert_eq!(AudioFormat::Ogg.codec(), "vorbis");
+    assert_eq!(AudioFormat::Ogg.ffmpeg_muxer(), "ogg");
+    
+    // Opus needs fewer bits than AAC for the same quality
+    assert_eq!(auto_bitrate(Some(&source("aac", Some(128), 2)), &AudioFormat::Opus), 96);
+    assert_eq!(auto_bitrate(Some(&source("opus", Some(96), 2)), &AudioFormat::Ogg), 96);
+    assert_eq!(auto_bitrate(None, &AudioFormat::Opus), 128);
+    
+    let extractor = AudioExtractor::new(Args { format: Some(AudioFormat::Ogg), ..Default::default() });
+    assert!(extractor.metadata_args().windows(2).any(|w| w == ["-map_metadata:s:a", "0:g"]));
+    let extractor = AudioExtractor::new(Args { format: Some(AudioFormat::Mp3), ..Default::default() });
+    assert!(!extractor.metadata_args().contains(&"-map_metadata:s:a".to_string()));
+}
+
+/// One second of a tone encoded as MP3, a lossy source
+fn mp3_source(dir: &TempDir) -> std::path::PathBuf {
+    let path = dir.path().join("source.mp3");
+    let status = Command::new("ffmpeg")
+        .args(["-v", "error", "-f", "lavfi", "-i", "sine=frequency=1000:duration=1", "-c:a", "libmp3lame", "-y"])
+        .arg(&path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    path
+}
+
+fn mp3_args(input: &std::path::Path, output: &std::path::Path, options: &[&str]) -> Args {
+    let mut argv = vec!["audio_extractor", "-i", input.to_str().unwrap(), "-o", output.to_str().unwrap(), "--format", "mp3"];
+    argv.extend_from_slice(options);
+    Args::try_parse_from(argv).unwrap()
+}
+
+#[test]
+fn test_same_codec_source_is_copied_without_rate_options() {
+    if !common::ffmpeg_available() {
+        return;
+    }
+    let dir = TempDir::new().unwrap();
+    let input = mp3_source(&dir);
+    let output = dir.path().join("out.mp3");
+
+    // Refused as a lossy transcode if the audio were encoded again
+    AudioExtractor::new(mp3_args(&input, &output, &[])).extract().unwrap();
+    assert_eq!(AudioExtractor::verify_standalone(&output).unwrap().format, "mp3");
+}
+
+#[test]
+fn test_same_codec_source_with_rate_options_is_refused() {
+    if !common::ffmpeg_available() {
+        return;
+    }
+    let dir = TempDir::new().unwrap();
+    let input = mp3_source(&dir);
+    let output = dir.path().join("out.mp3");
+
+    let options: [&[&str]; 5] = [
+        &["--quality", "192"],
+        &["--vbr"],
+        &["--target-size", "1MB"],
+        &["--mp3-mode", "abr"],
+        &["--lame-preset", "standard"],
+    ];
+    for options in options {
+        let error = AudioExtractor::new(mp3_args(&input, &output, options)).extract().unwrap_err();
+        assert!(error.to_string().contains("--allow-lossy-transcode"), "{:?}: {}", options, error);
+        assert!(!output.exists());
+    }
+
+    let mut allowed = options[0].to_vec();
+    allowed.push("--allow-lossy-transcode");
+    AudioExtractor::new(mp3_args(&input, &output, &allowed)).extract().unwrap();
+    assert!(output.exists());
+}
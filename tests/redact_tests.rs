@@ -0,0 +1,91 @@
+use audio_extractor::{redact, AudioGraph};
+use audio_extractor::timecode::{self, TimeRange};
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn test_parse_timestamp_formats() {
+    assert_eq!(timecode::parse_timestamp("725").unwrap(), 725.0);
+    assert_eq!(timecode::parse_timestamp("12:05").unwrap(), 725.0);
+    assert_eq!(timecode::parse_timestamp("00:12:05.500").unwrap(), 725.5);
+    assert!(timecode::parse_timestamp("1:2:3:4").is_err());
+    assert!(timecode::parse_timestamp("abc").is_err());
+    assert!(timecode::parse_timestamp("-5").is_err());
+}
+
+#[test]
+fn test_time_range_parsing_and_display() {
+    let range: TimeRange = "00:12:05-00:12:30".parse().unwrap();
+    assert_eq!(range, TimeRange { start: 725.0, end: 750.0 });
+    assert_eq!(range.to_string(), "00:12:05.000-00:12:30.000");
+    
+    assert!("00:12:30-00:12:05".parse::<TimeRange>().is_err());
+    assert!("00:12:30".parse::<TimeRange>().is_err());
+}
+
+#[test]
+fn test_mute_filter() {
+    assert_eq!(redact::mute_filter(&[]), None);
+    
+    let ranges = vec![
+        TimeRange::new(1.0, 2.5).unwrap(),
+        TimeRange::new(10.0, 12.0).unwrap(),
+    ];
+    assert_eq!(
+        redact::mute_filter(&ranges).unwrap(),
+        "volume=volume=0:enable='between(t,1.000,2.500)+between(t,10.000,12.000)'"
+    );
+}
+
+#[test]
+fn test_load_ranges_from_json() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("redactions.json");
+    fs::write(&path, r#"["00:00:01-00:00:02", {"start": 30, "end": "00:00:45"}]"#).unwrap();
+    
+    let ranges = redact::load_ranges(&path).unwrap();
+    assert_eq!(ranges, vec![
+        TimeRange { start: 1.0, end: 2.0 },
+        TimeRange { start: 30.0, end: 45.0 },
+    ]);
+}
+
+#[test]
+fn test_parse_frequency() {
+    assert_eq!(redact::parse_frequency("1kHz").unwrap(), 1000.0);
+    assert_eq!(redact::parse_frequency("440Hz").unwrap(), 440.0);
+    assert_eq!(redact::parse_frequency("2.5khz").unwrap(), 2500.0);
+    assert_eq!(redact::parse_frequency("800").unwrap(), 800.0);
+    assert!(redact::parse_frequency("5Hz").is_err());
+    assert!(redact::parse_frequency("loud").is_err());
+}
+
+#[test]
+fn test_tone_overlay_graph() {
+    let ranges = vec![TimeRange::new(5.0, 6.0).unwrap()];
+    let graph = AudioGraph {
+        filters: redact::mute_filter(&ranges).into_iter().collect(),
+        overlays: redact::tone_overlay(&ranges, 1000.0).into_iter().collect(),
+        ..Default::default()
+    };
+    
+    let args = graph.ffmpeg_args("0:a");
+    assert_eq!(args[0], "-filter_complex");
+    assert_eq!(
+        args[1],
+        "[0:a]volume=volume=0:enable='between(t,5.000,6.000)'[main];\
+         sine=frequency=1000:sample_rate=48000,volume=volume=0.25,volume=volume=0:enable='not(between(t,5.000,6.000))'[overlay0];\
+         [main][overlay0]amix=inputs=2:duration=first:normalize=0[out]"
+    );
+    assert_eq!(&args[2..], ["-map", "[out]"]);
+}
+
+#[test]
+fn test_plain_chain_uses_af() {
+    let graph = AudioGraph {
+        filters: vec!["volume=2".to_string(), "aresample=48000".to_string()],
+        ..Default::default()
+    };
+    assert_eq!(graph.ffmpeg_args("0:a"), ["-af", "volume=2,aresample=48000"]);
+    assert!(AudioGraph::default().ffmpeg_args("0:a").is_empty());
+}
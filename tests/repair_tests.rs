@@ -0,0 +1,44 @@
+use audio_extractor::repair;
+use audio_extractor::ResourceLimits;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+#[test]
+fn test_scratch_path_keeps_extension() {
+    assert_eq!(repair::scratch_path(Path::new("/archive/talk.mp3")), PathBuf::from("/archive/.talk.repair.mp3"));
+    assert_eq!(repair::scratch_path(Path::new("/archive/raw")), PathBuf::from("/archive/.raw.repair"));
+}
+
+#[test]
+fn test_remux_args_per_format() {
+    let has = |args: &[OsString], flag: &str, value: &str| {
+        args.windows(2).any(|pair| pair[0] == flag && pair[1] == value)
+    };
+    
+    let args = repair::remux_args(Path::new("talk.mp3"), Path::new(".talk.repair.mp3"));
+    assert!(has(&args, "-c", "copy"));
+    assert!(has(&args, "-write_xing", "1"));
+    assert_eq!(args.last().unwrap(), ".talk.repair.mp3");
+    
+    let args = repair::remux_args(Path::new("talk.M4A"), Path::new(".talk.repair.M4A"));
+    assert!(has(&args, "-movflags", "+faststart"));
+    
+    let args = repair::remux_args(Path::new("talk.wav"), Path::new(".talk.repair.wav"));
+    assert!(!args.iter().any(|arg| arg == "-movflags" || arg == "-write_xing"));
+}
+
+#[test]
+fn test_failed_repair_leaves_original() {
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join("broken.mp3");
+    fs::write(&file, b"not audio at all").unwrap();
+    
+    assert!(repair::repair(&file, true, &ResourceLimits::default()).is_err());
+    assert_eq!(fs::read(&file).unwrap(), b"not audio at all");
+    assert!(!repair::scratch_path(&file).exists());
+    assert!(!dir.path().join("broken.mp3.bak").exists());
+    
+    assert!(repair::repair(&dir.path().join("missing.mp3"), false, &ResourceLimits::default()).is_err());
+}
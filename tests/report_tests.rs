@@ -0,0 +1,89 @@
+use assert_cmd::Command;
+use audio_extractor::report;
+use audio_extractor::{BatchReport, ReportDiff, ReportEntry};
+use predicates::prelude::*;
+use std::path::PathBuf;
+use tempfile::tempdir;
+
+fn entry(input: &str, success: bool, size: Option<u64>, loudness: Option<f64>) -> ReportEntry {
+    ReportEntry {
+        input: PathBuf::from(input),
+        output: success.then(|| PathBuf::from(input).with_extension("mp3")),
+        success,
+        error: (!success).then(|| "ffmpeg failed".to_string()),
+        size,
+        loudness,
+        analysis: None,
+        shortened_from: None,
+    }
+}
+
+fn reports() -> (BatchReport, BatchReport) {
+    let old = BatchReport::new(vec![
+        entry("a.mp4", true, Some(1000), Some(-23.0)),
+        entry("b.mp4", true, Some(1000), Some(-23.0)),
+        entry("c.mp4", false, None, None),
+        entry("d.mp4", true, Some(1000), Some(-16.0)),
+        entry("gone.mp4", true, Some(1000), None),
+    ]);
+    let new = BatchReport::new(vec![
+        entry("a.mp4", true, Some(1030), Some(-23.4)),
+        entry("b.mp4", false, None, None),
+        entry("c.mp4", true, Some(1000), None),
+        entry("d.mp4", true, Some(1200), Some(-18.5)),
+        entry("new.mp4", true, Some(1000), None),
+    ]);
+    (old, new)
+}
+
+#[test]
+fn test_report_diff() {
+    let (old, new) = reports();
+    let diff = ReportDiff::between(&old, &new, report::DEFAULT_SIZE_TOLERANCE, report::DEFAULT_LOUDNESS_TOLERANCE);
+    assert_eq!(diff.regressions, [(PathBuf::from("b.mp4"), "ffmpeg failed".to_string())]);
+    assert_eq!(diff.fixed, [PathBuf::from("c.mp4")]);
+    assert_eq!(diff.size_drift, [(PathBuf::from("d.mp4"), 1000, 1200)]);
+    assert_eq!(diff.loudness_drift, [(PathBuf::from("d.mp4"), -16.0, -18.5)]);
+    assert_eq!(diff.added, [PathBuf::from("new.mp4")]);
+    assert_eq!(diff.removed, [PathBuf::from("gone.mp4")]);
+    assert!(diff.has_regressions());
+    
+    let output = diff.to_string();
+    assert!(output.contains("REGRESSION b.mp4: ffmpeg failed"));
+    assert!(output.contains("SIZE       d.mp4: 1000 → 1200 bytes (+20.0%)"));
+    assert!(output.contains("LOUDNESS   d.mp4: -16.0 → -18.5 LUFS (-2.5 LU)"));
+    
+    let same = ReportDiff::between(&old, &old, 5.0, 1.0);
+    assert!(!same.has_regressions());
+}
+
+#[test]
+fn test_parse_integrated_loudness() {
+    let stderr = "[Parsed_ebur128_0 @ 0x1] Summary:\n\n  Integrated loudness:\n    I:         -19.6 LUFS\n    Threshold: -29.8 LUFS\n";
+    assert_eq!(report::parse_integrated_loudness(stderr), Some(-19.6));
+    assert_eq!(report::parse_integrated_loudness("nothing"), None);
+}
+
+#[test]
+fn test_report_diff_command() {
+    let dir = tempdir().unwrap();
+    let (old, new) = reports();
+    let (old_path, new_path) = (dir.path().join("old.json"), dir.path().join("new.json"));
+    old.write(&old_path).unwrap();
+    new.write(&new_path).unwrap();
+    
+    Command::cargo_bin("audio_extractor").unwrap()
+        .args(["report", "diff"])
+        .arg(&old_path)
+        .arg(&new_path)
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("1 regression(s), 1 size drift(s), 1 loudness drift(s), 1 fixed"));
+    
+    Command::cargo_bin("audio_extractor").unwrap()
+        .args(["report", "diff"])
+        .arg(&old_path)
+        .arg(&old_path)
+        .assert()
+        .success();
+}
@@ -0,0 +1,46 @@
+use audio_extractor::rules::{self, glob_match, Rule};
+use audio_extractor::{Args, AudioFormat, Config};
+use std::path::Path;
+
+#[test]
+fn test_glob_match() {
+    assert!(glob_match("lectures/**", "lectures/2024/week1.mp4"));
+    assert!(glob_match("lectures/**", "lectures/intro.mp4"));
+    assert!(!glob_match("lectures/**", "concerts/lectures/intro.mp4"));
+    assert!(glob_match("**/*.mkv", "concerts/live.mkv"));
+    assert!(glob_match("**/*.mkv", "live.mkv"));
+    assert!(!glob_match("*.mkv", "concerts/live.mkv"));
+    assert!(glob_match("concerts/*/set?.mp4", "concerts/berlin/set1.mp4"));
+    assert!(!glob_match("concerts/*/set?.mp4", "concerts/berlin/set10.mp4"));
+    assert!(glob_match("./lectures/**", "lectures/a.mp4"));
+}
+
+#[test]
+fn test_rules_from_config() {
+    let config: Config = toml::from_str(r#"
+        [[rules]]
+        pattern = "lectures/**"
+        format = "mp3"
+        quality = 64
+
+        [[rules]]
+        pattern = "concerts/**"
+        format = "flac"
+        verify = true
+    "#).unwrap();
+    
+    let rule = rules::first_match(&config.rules, Path::new("concerts/2023/berlin.mkv")).unwrap();
+    assert_eq!(rule.format, Some(AudioFormat::Flac));
+    assert_eq!(rules::first_match(&config.rules, Path::new("lectures/a.mp4")).unwrap().quality, Some(64));
+    assert!(rules::first_match(&config.rules, Path::new("misc/a.mp4")).is_none());
+}
+
+#[test]
+fn test_rule_overrides_only_what_it_sets() {
+    let rule = Rule { pattern: "**".to_string(), format: None, quality: Some(320), verify: None };
+    let mut args = Args { format: Some(AudioFormat::Aac), quality: Some(128), verify: true, ..Default::default() };
+    rule.apply(&mut args);
+    assert_eq!(args.format, Some(AudioFormat::Aac));
+    assert_eq!(args.quality, Some(320));
+    assert!(args.verify);
+}
@@ -0,0 +1,44 @@
+use audio_extractor::sampling;
+use audio_extractor::{Args, AudioExtractor, AudioFormat};
+use clap::Parser;
+
+fn extractor(output: &str, extra: &[&str]) -> AudioExtractor {
+    let args = Args::try_parse_from(["audio_extractor", "-i", "in.mp4", "-o", output].iter().chain(extra)).unwrap();
+    AudioExtractor::new(args)
+}
+
+#[test]
+fn test_sample_rates_per_format() {
+    assert!(sampling::check_sample_rate(&AudioFormat::Opus, 48_000).is_ok());
+    assert!(sampling::check_sample_rate(&AudioFormat::Opus, 44_100).unwrap_err().to_string().contains("OPUS output does not support 44100 Hz"));
+    assert!(sampling::check_sample_rate(&AudioFormat::Mp3, 96_000).is_err());
+    assert!(sampling::check_sample_rate(&AudioFormat::Aac, 96_000).is_ok());
+    assert!(sampling::check_sample_rate(&AudioFormat::AmrNb, 16_000).is_err());
+    assert!(sampling::check_sample_rate(&AudioFormat::Flac, 192_000).is_ok());
+    assert!(sampling::check_sample_rate(&AudioFormat::Wav, 1_000).is_err());
+}
+
+#[test]
+fn test_channels_per_format() {
+    assert!(sampling::check_channels(&AudioFormat::Mp3, 2).is_ok());
+    assert!(sampling::check_channels(&AudioFormat::Mp3, 6).is_err());
+    assert!(sampling::check_channels(&AudioFormat::Flac, 6).is_ok());
+    assert!(sampling::check_channels(&AudioFormat::Speex, 2).is_err());
+    assert!(sampling::check_channels(&AudioFormat::Wav, 0).is_err());
+}
+
+#[test]
+fn test_sampling_defaults() {
+    // WAV is written at 44.1 kHz and voice codecs in mono unless told otherwise
+    assert_eq!(extractor("out.wav", &["-f", "wav"]).sampling(), (Some(44_100), None));
+    assert_eq!(extractor("out.wav", &["-f", "wav", "--sample-rate", "48000", "--channels", "2"]).sampling(), (Some(48_000), Some(2)));
+    assert_eq!(extractor("out.amr", &["-f", "amr"]).sampling(), (Some(8_000), Some(1)));
+    assert_eq!(extractor("out.mp3", &["-f", "mp3"]).sampling(), (None, None));
+}
+
+#[test]
+fn test_check_sampling() {
+    assert!(extractor("out.opus", &["-f", "opus", "--sample-rate", "48000", "--channels", "2"]).check_sampling().is_ok());
+    assert!(extractor("out.opus", &["-f", "opus", "--sample-rate", "44100"]).check_sampling().is_err());
+    assert!(extractor("out.mp3", &["-f", "mp3", "--channels", "6"]).check_sampling().is_err());
+}
@@ -0,0 +1,38 @@
+use audio_extractor::scan::{self, SkipReason, SkippedInput, UnsupportedLog};
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_scan_is_recursive_and_sorted() {
+    let root = TempDir::new().unwrap();
+    fs::create_dir_all(root.path().join("b/c")).unwrap();
+    fs::write(root.path().join("b/c/two.MKV"), b"x").unwrap();
+    fs::write(root.path().join("a.mp4"), b"x").unwrap();
+    fs::write(root.path().join("b/cover.jpg"), b"x").unwrap();
+    
+    let scan = scan::scan(root.path(), &["mp4", "mkv"], None).unwrap();
+    assert_eq!(scan.inputs, vec![root.path().join("a.mp4"), root.path().join("b/c/two.MKV")]);
+    assert_eq!(scan.skipped, vec![SkippedInput {
+        path: root.path().join("b/cover.jpg"),
+        reason: SkipReason::UnsupportedType,
+    }]);
+}
+
+#[test]
+fn test_unsupported_log_rotates() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("unsupported.log");
+    let log = UnsupportedLog::new(&path, 100);
+    let skipped = SkippedInput {
+        path: "/archive/broken.mkv".into(),
+        reason: SkipReason::Unreadable("Invalid data found when processing input".to_string()),
+    };
+    
+    log.append(&skipped).unwrap();
+    let first = fs::read_to_string(&path).unwrap();
+    assert!(first.contains("/archive/broken.mkv\tunreadable container: Invalid data found"));
+    
+    log.append(&skipped).unwrap();
+    assert_eq!(fs::read_to_string(dir.path().join("unsupported.log.1")).unwrap(), first);
+    assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 1);
+}
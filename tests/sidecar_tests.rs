@@ -0,0 +1,75 @@
+use audio_extractor::sidecar;
+use audio_extractor::{Args, SourceMetadata};
+use clap::Parser;
+use std::path::{Path, PathBuf};
+
+#[test]
+fn test_thumbnail_path() {
+    assert_eq!(sidecar::thumbnail_path(Path::new("/podcast/episode.mp3")), PathBuf::from("/podcast/episode.jpg"));
+}
+
+#[test]
+fn test_thumbnail_time() {
+    assert_eq!(sidecar::thumbnail_time(None, Some(600.0)), 60.0);
+    assert_eq!(sidecar::thumbnail_time(Some(90.0), Some(600.0)), 90.0);
+    // Past the end of the video, fall back to the middle
+    assert_eq!(sidecar::thumbnail_time(Some(900.0), Some(600.0)), 300.0);
+    assert_eq!(sidecar::thumbnail_time(Some(90.0), None), 90.0);
+    assert_eq!(sidecar::thumbnail_time(None, None), 0.0);
+}
+
+#[test]
+fn test_thumbnail_at_parses_timestamps() {
+    let args = Args::try_parse_from(["audio_extractor", "-i", "in.mp4", "-o", "out.mp3", "--thumbnail", "--thumbnail-at", "01:30"]).unwrap();
+    assert_eq!(args.thumbnail_at, Some(90.0));
+    
+    assert!(Args::try_parse_from(["audio_extractor", "-i", "in.mp4", "-o", "out.mp3", "--thumbnail-at", "01:30"]).is_err());
+}
+
+#[test]
+fn test_metadata_from_ffprobe() {
+    let json = r#"{"format": {"duration": "1512.4", "tags": {
+        "TITLE": "Pilot", "show": "Kitchen Talk", "season_number": "2", "episode_sort": "3/10",
+        "creation_time": "2023-05-14T18:22:01.000000Z", "comment": "Fish & chips"}}}"#;
+    let metadata = SourceMetadata::from_ffprobe_json(json).unwrap();
+    assert_eq!(metadata.title.as_deref(), Some("Pilot"));
+    assert_eq!(metadata.show.as_deref(), Some("Kitchen Talk"));
+    assert_eq!(metadata.season, Some(2));
+    assert_eq!(metadata.episode, Some(3));
+    assert_eq!(metadata.recorded_date.as_deref(), Some("2023-05-14"));
+    assert_eq!(metadata.description.as_deref(), Some("Fish & chips"));
+    assert_eq!(metadata.duration, Some(1512.4));
+    
+    let untagged = SourceMetadata::from_ffprobe_json(r#"{"format": {}}"#).unwrap();
+    assert_eq!(untagged, SourceMetadata::default());
+}
+
+#[test]
+fn test_nfo_for_episode() {
+    let metadata = SourceMetadata {
+        title: Some("Pilot".into()),
+        show: Some("Kitchen Talk".into()),
+        season: Some(2),
+        episode: Some(3),
+        recorded_date: Some("2023-05-14".into()),
+        description: Some("Fish & chips".into()),
+        duration: Some(1512.4),
+        ..Default::default()
+    };
+    let nfo = sidecar::nfo(&metadata, Path::new("s02e03.mp3"));
+    assert!(nfo.starts_with("<?xml"));
+    assert!(nfo.contains("<episodedetails>\n  <title>Pilot</title>\n  <showtitle>Kitchen Talk</showtitle>\n  <season>2</season>\n  <episode>3</episode>\n"));
+    assert!(nfo.contains("<plot>Fish &amp; chips</plot>"));
+    assert!(nfo.contains("<aired>2023-05-14</aired>"));
+    assert!(nfo.contains("<runtime>25</runtime>"));
+    assert_eq!(sidecar::nfo_path(Path::new("/a/s02e03.mp3")), PathBuf::from("/a/s02e03.nfo"));
+}
+
+#[test]
+fn test_nfo_without_show_uses_file_name() {
+    let metadata = SourceMetadata { artist: Some("The Band".into()), recorded_date: Some("1998".into()), ..Default::default() };
+    let nfo = sidecar::nfo(&metadata, Path::new("live_set.flac"));
+    assert!(nfo.contains("<musicvideo>\n  <title>live_set</title>\n  <artist>The Band</artist>\n"));
+    assert!(nfo.contains("<year>1998</year>"));
+    assert!(!nfo.contains("<album>"));
+}
@@ -0,0 +1,71 @@
+use audio_extractor::size;
+use audio_extractor::{Args, AudioExtractor, AudioFormat};
+use clap::Parser;
+
+#[test]
+fn test_parse_size() {
+    assert_eq!(size::parse_size("25MB").unwrap(), 25_000_000);
+    assert_eq!(size::parse_size("1.5 GB").unwrap(), 1_500_000_000);
+    assert_eq!(size::parse_size("700k").unwrap(), 700_000);
+    assert_eq!(size::parse_size("64MiB").unwrap(), 64 << 20);
+    assert_eq!(size::parse_size("4096").unwrap(), 4096);
+    assert!(size::parse_size("25 furlongs").is_err());
+    assert!(size::parse_size("MB").is_err());
+    assert!(size::parse_size("0").is_err());
+}
+
+#[test]
+fn test_bitrate_for_duration() {
+    // A one-hour lecture in a 25 MB attachment
+    assert_eq!(size::bitrate_for(25_000_000, 3600.0).unwrap(), 55);
+    assert_eq!(size::bitrate_for(1_440_000, 60.0).unwrap(), 192);
+    assert!(size::bitrate_for(1_000_000, 0.0).is_err());
+}
+
+#[test]
+fn test_corrected_bitrate() {
+    // Within 5% under the target: no second pass
+    assert_eq!(size::corrected_bitrate(128, 980_000, 1_000_000), None);
+    // Over the target, scaled down
+    assert_eq!(size::corrected_bitrate(128, 1_100_000, 1_000_000), Some(112));
+    // Well under, scaled up
+    assert_eq!(size::corrected_bitrate(100, 800_000, 1_000_000), Some(121));
+}
+
+#[test]
+fn test_target_size_arguments() {
+    let parse = |extra: &[&str]| Args::try_parse_from(["audio_extractor", "-i", "lecture.mp4", "-o", "lecture.mp3"].iter().chain(extra));
+    assert_eq!(parse(&["--target-size", "25MB", "--two-pass"]).unwrap().target_size, Some(25_000_000));
+    assert!(parse(&["--target-size", "25MB", "--quality", "128"]).is_err());
+    assert!(parse(&["--two-pass"]).is_err());
+    assert!(parse(&["--target-size", "25MB", "--mp3-mode", "vbr"]).is_err());
+}
+
+#[test]
+fn test_target_size_needs_a_lossy_format() {
+    let args = Args { format: Some(AudioFormat::Flac), target_size: Some(25_000_000), ..Default::default() };
+    assert!(AudioExtractor::new(args).target_bitrate().unwrap_err().to_string().contains("lossless"));
+}
+
+#[test]
+fn test_usable_bytes_keep_a_margin() {
+    assert_eq!(size::usable_bytes(25_000_000), 24_250_000);
+    assert_eq!(size::bitrate_for(size::usable_bytes(25_000_000), 3600.0).unwrap(), 53);
+}
+
+#[test]
+fn test_encoder_bitrate() {
+    // LAME would round 53 kbps up to 56 and overshoot
+    assert_eq!(size::encoder_bitrate(&AudioFormat::Mp3, 53), 48);
+    assert_eq!(size::encoder_bitrate(&AudioFormat::Mp3, 500), 320);
+    assert_eq!(size::encoder_bitrate(&AudioFormat::Mp3, 4), 8);
+    assert_eq!(size::encoder_bitrate(&AudioFormat::Opus, 900), 510);
+    assert_eq!(size::encoder_bitrate(&AudioFormat::Aac, 53), 53);
+}
+
+#[test]
+fn test_rate_control_for_size_targets() {
+    assert_eq!(size::rate_control_args(&AudioFormat::Opus, 48), ["-vbr", "constrained"]);
+    assert_eq!(size::rate_control_args(&AudioFormat::Ogg, 48), ["-maxrate", "48k"]);
+    assert!(size::rate_control_args(&AudioFormat::Mp3, 48).is_empty());
+}
@@ -0,0 +1,58 @@
+use audio_extractor::quality::SourceAudio;
+use audio_extractor::spectrum::{self, SourceQuality};
+
+/// Equal-level tones every 250 Hz from 250 Hz up to `top`
+fn tones(top: f64, sample_rate: u32, seconds: f64) -> Vec<i16> {
+    let count = (top / 250.0) as usize;
+    (0..(sample_rate as f64 * seconds) as usize)
+        .map(|i| {
+            let t = i as f64 / sample_rate as f64;
+            let sum: f64 = (1..=count).map(|k| (2.0 * std::f64::consts::PI * 250.0 * k as f64 * t + k as f64).sin()).sum();
+            (sum / count as f64 * 8000.0) as i16
+        })
+        .collect()
+}
+
+fn source(codec: &str, bit_rate: Option<u32>) -> SourceAudio {
+    SourceAudio { codec: codec.to_string(), bit_rate, channels: Some(2), sample_rate: Some(44100) }
+}
+
+#[test]
+fn test_estimate_bandwidth() {
+    let bandwidth = spectrum::estimate_bandwidth(&tones(16_000.0, 44_100, 1.0), 44_100).unwrap();
+    assert!((15_800.0..16_300.0).contains(&bandwidth), "{}", bandwidth);
+    
+    let bandwidth = spectrum::estimate_bandwidth(&tones(20_000.0, 44_100, 1.0), 44_100).unwrap();
+    assert!((19_800.0..20_300.0).contains(&bandwidth), "{}", bandwidth);
+    
+    assert_eq!(spectrum::estimate_bandwidth(&vec![0; 44_100], 44_100), None);
+    assert_eq!(spectrum::estimate_bandwidth(&[1000; 100], 44_100), None);
+}
+
+#[test]
+fn test_upscaled_sources_are_flagged() {
+    let fake = SourceQuality::assess(&source("mp3", Some(320)), 16_000.0);
+    assert!(fake.upscaled);
+    assert_eq!(fake.expected_bandwidth, Some(19_500.0));
+    assert_eq!(fake.likely_bit_rate, Some(128));
+    assert_eq!(fake.to_string(), "mp3 at 320 kbps, bandwidth 16.0 kHz (upscaled, typical of ~128 kbps lossy audio)");
+    
+    let fake_flac = SourceQuality::assess(&source("flac", None), 17_500.0);
+    assert!(fake_flac.upscaled);
+    assert_eq!(fake_flac.to_string(), "flac, bandwidth 17.5 kHz (upscaled, typical of ~160 kbps lossy audio)");
+    
+    let genuine = SourceQuality::assess(&source("aac", Some(256)), 19_800.0);
+    assert!(!genuine.upscaled);
+    assert_eq!(genuine.likely_bit_rate, None);
+    
+    // Low bitrates are expected to be band-limited
+    assert!(!SourceQuality::assess(&source("aac", Some(96)), 14_000.0).upscaled);
+}
+
+#[test]
+fn test_expected_bandwidth_respects_sample_rate() {
+    let speech = SourceAudio { sample_rate: Some(32_000), ..source("pcm_s16le", None) };
+    assert_eq!(spectrum::expected_bandwidth(&speech), Some(14_400.0));
+    assert!(!SourceQuality::assess(&speech, 14_000.0).upscaled);
+    assert_eq!(spectrum::expected_bandwidth(&source("mp3", None)), None);
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use audio_extractor::staging::{self, StagedInput};
+use audio_extractor::{Args, AudioExtractor};
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_copy_is_removed_when_dropped() {
+    let source = TempDir::new().unwrap();
+    let scratch = TempDir::new().unwrap();
+    let input = source.path().join("talk.mp4");
+    fs::write(&input, b"not really video").unwrap();
+
+    let staged = StagedInput::copy(&input, scratch.path()).unwrap();
+    assert!(staged.path().starts_with(scratch.path()));
+    assert_eq!(staged.path().file_name(), input.file_name());
+    assert_eq!(fs::read(staged.path()).unwrap(), b"not really video");
+
+    drop(staged);
+    assert_eq!(fs::read_dir(scratch.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn test_copy_clears_stages_of_exited_processes() {
+    let source = TempDir::new().unwrap();
+    let scratch = TempDir::new().unwrap();
+    let input = source.path().join("talk.mp4");
+    fs::write(&input, b"video").unwrap();
+    // Above the kernel's pid limit, so never a live process
+    let stale = scratch.path().join("audio_extractor-staged-999999999-0");
+    fs::create_dir(&stale).unwrap();
+    fs::write(stale.join("old.mp4"), b"left behind").unwrap();
+    let unrelated = scratch.path().join("other-tool");
+    fs::create_dir(&unrelated).unwrap();
+
+    let _staged = StagedInput::copy(&input, scratch.path()).unwrap();
+    assert!(!stale.exists());
+    assert!(unrelated.exists());
+}
+
+#[test]
+fn test_missing_input_leaves_nothing_behind() {
+    let scratch = TempDir::new().unwrap();
+    assert!(StagedInput::copy(&scratch.path().join("missing.mp4"), scratch.path()).is_err());
+    assert_eq!(fs::read_dir(scratch.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn test_local_inputs_are_read_in_place() {
+    let input = common::create_test_video_file();
+    assert!(!staging::is_network_path(input.path()));
+
+    let scratch = TempDir::new().unwrap();
+    let extractor = AudioExtractor::new(Args {
+        input: input.path().to_path_buf(),
+        stage_locally: true,
+        scratch_dir: Some(scratch.path().to_path_buf()),
+        ..Default::default()
+    });
+    assert_eq!(extractor.source(), input.path());
+    assert_eq!(fs::read_dir(scratch.path()).unwrap().count(), 0);
+}
@@ -0,0 +1,50 @@
+use assert_cmd::Command;
+use audio_extractor::status::{self, StatusFile};
+use predicates::prelude::*;
+use std::path::Path;
+use tempfile::tempdir;
+
+#[test]
+fn test_status_file_tracks_progress() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("batch.json");
+    let file = StatusFile::at(path.clone(), "scan", 3);
+    
+    file.started(Path::new("a.mp4"));
+    file.started(Path::new("b.mp4"));
+    file.done(Path::new("a.mp4"), true);
+    let status = status::read(&path).unwrap();
+    assert_eq!((status.completed, status.failed, status.remaining()), (1, 0, 2));
+    assert_eq!(status.current, [Path::new("b.mp4")]);
+    assert!(status.is_running());
+    
+    file.requeued(Path::new("b.mp4"));
+    file.started(Path::new("c.mp4"));
+    file.done(Path::new("c.mp4"), false);
+    file.finish();
+    let status = status::read(&path).unwrap();
+    assert_eq!((status.completed, status.failed, status.remaining()), (1, 1, 1));
+    assert!(status.current.is_empty());
+    assert!(status.finished && !status.is_running());
+    
+    let listed = status::read_all(dir.path()).unwrap();
+    assert_eq!(listed.len(), 1);
+    assert!(!dir.path().join("batch.json.part").exists());
+}
+
+#[test]
+fn test_status_command_prints_counts() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("batch.json");
+    let file = StatusFile::at(path.clone(), "scan", 4);
+    file.started(Path::new("/videos/talk.mp4"));
+    
+    Command::cargo_bin("audio_extractor").unwrap()
+        .arg("status")
+        .arg(&path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("scan batch"))
+        .stdout(predicate::str::contains("0 completed, 0 failed, 4 remaining of 4"))
+        .stdout(predicate::str::contains("encoding /videos/talk.mp4"));
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use audio_extractor::native::Backend;
+use audio_extractor::subtitles::{self, Cue};
+use audio_extractor::{Args, AudioExtractor, AudioFormat, EffectChain, Timeline};
+use tempfile::TempDir;
+
+fn cue(start: f64, end: f64, text: &str) -> Cue {
+    Cue { start, end, text: text.into() }
+}
+
+#[test]
+fn test_parse_srt() {
+    let srt = "\u{feff}1\r\n00:00:01,500 --> 00:00:04,000\r\nHello\r\nthere\r\n\r\n2\r\n01:02:03,004 --> 01:02:05,000\r\nAgain\r\n";
+    assert_eq!(subtitles::parse(srt).unwrap(), [cue(1.5, 4.0, "Hello\nthere"), cue(3723.004, 3725.0, "Again")]);
+}
+
+#[test]
+fn test_parse_vtt() {
+    let vtt = "WEBVTT\n\nNOTE a comment\n\nintro\n00:01.000 --> 00:02.250 align:start\n<v Ann>Hi\n\n00:00:03.000 --> 00:00:04.000\nBye\n";
+    assert_eq!(subtitles::parse(vtt).unwrap(), [cue(1.0, 2.25, "<v Ann>Hi"), cue(3.0, 4.0, "Bye")]);
+    assert!(subtitles::parse("1\n00:00:xx,000 --> 00:00:01,000\nBad\n").is_err());
+}
+
+#[test]
+fn test_to_srt() {
+    let srt = subtitles::to_srt(&[cue(0.0, 1.25, "One"), cue(61.5, 3600.0, "Two")]);
+    assert_eq!(srt, "1\n00:00:00,000 --> 00:00:01,250\nOne\n\n2\n00:01:01,500 --> 01:00:00,000\nTwo\n\n");
+}
+
+#[test]
+fn test_retime() {
+    let mut timeline = Timeline::new("talk.mp4", 100.0);
+    timeline.keep_source(&[(10.0, 20.0), (50.0, 70.0)]);
+    let cues = [
+        cue(2.0, 4.0, "cut"),
+        cue(12.0, 14.0, "kept"),
+        cue(18.0, 52.0, "straddles"),
+        cue(60.0, 65.0, "later"),
+    ];
+    assert_eq!(subtitles::retime(&cues, &timeline), [
+        cue(2.0, 4.0, "kept"),
+        cue(8.0, 12.0, "straddles"),
+        cue(20.0, 25.0, "later"),
+    ]);
+}
+
+#[test]
+fn test_extraction_writes_retimed_subtitles() {
+    let dir = TempDir::new().unwrap();
+    let input = dir.path().join("tone.mp4");
+    common::write_test_tone(&input);
+    let subtitle_file = dir.path().join("tone.srt");
+    std::fs::write(&subtitle_file, "1\n00:00:00,500 --> 00:00:00,900\nTone\n").unwrap();
+    let output = dir.path().join("out.wav");
+
+    let args = Args {
+        input,
+        output: output.clone(),
+        format: Some(AudioFormat::Wav),
+        subtitles: true,
+        subtitle_file: Some(subtitle_file),
+        ..Default::default()
+    };
+    let extractor = AudioExtractor::new(args)
+        .with_backend(Backend::Native)
+        .with_effects(EffectChain::new().trim(0.25, None));
+    extractor.extract().unwrap();
+
+    let srt = std::fs::read_to_string(dir.path().join("out.srt")).unwrap();
+    assert_eq!(srt, "1\n00:00:00,250 --> 00:00:00,650\nTone\n\n");
+}
@@ -0,0 +1,156 @@
+use audio_extractor::limits::ResourceLimits;
+use audio_extractor::supervisor::{self, TrackedProcess};
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+/// A registry directory only this user can access, as `list` requires
+fn registry() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(dir.path(), fs::Permissions::from_mode(0o700)).unwrap();
+    }
+    dir
+}
+
+#[test]
+fn test_list_drops_exited_processes() {
+    let dir = registry();
+    let exited = TrackedProcess {
+        // Above the kernel's pid limit, so never a live process
+        pid: 999_999_999,
+        parent: std::process::id(),
+        started_at: "2026-01-01T00:00:00+00:00".to_string(),
+        command: "ffmpeg -i in.mp4 out.mp3".to_string(),
+        start_time: None,
+    };
+    let path = dir.path().join("999999999.json");
+    fs::write(&path, serde_json::to_vec(&exited).unwrap()).unwrap();
+    fs::write(dir.path().join("notes.txt"), b"ignored").unwrap();
+
+    assert!(supervisor::list(dir.path()).unwrap().is_empty());
+    assert!(!path.exists());
+    assert!(supervisor::list(&dir.path().join("missing")).unwrap().is_empty());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_list_drops_entries_whose_pid_was_reused() {
+    let dir = registry();
+    let own = supervisor::start_time(std::process::id()).expect("start time of a live process");
+    // This test process stands in for a process that was given a tracked pid
+    let entry = |start_time, command: &str| TrackedProcess {
+        pid: std::process::id(),
+        parent: std::process::id(),
+        started_at: "2026-01-01T00:00:00+00:00".to_string(),
+        command: command.to_string(),
+        start_time,
+    };
+    let write = |process: &TrackedProcess| {
+        fs::write(dir.path().join(format!("{}.json", process.pid)), serde_json::to_vec(process).unwrap()).unwrap();
+    };
+
+    write(&entry(Some(own + 1), "ffmpeg -i in.mp4 out.mp3"));
+    assert!(supervisor::list(dir.path()).unwrap().is_empty());
+    let reused = entry(Some(own + 1), "ffmpeg -i in.mp4 out.mp3");
+    assert!(supervisor::kill(&reused, true).is_err());
+
+    // Entries without a start time fall back to the command line
+    write(&entry(None, "ffmpeg -i in.mp4 out.mp3"));
+    assert!(supervisor::list(dir.path()).unwrap().is_empty());
+
+    write(&entry(Some(own), "ffmpeg -i in.mp4 out.mp3"));
+    assert_eq!(supervisor::list(dir.path()).unwrap().len(), 1);
+}
+
+#[test]
+fn test_command_line() {
+    let mut cmd = Command::new("ffmpeg");
+    cmd.arg("-nostdin").arg("-i").arg("movie.mp4");
+    assert_eq!(supervisor::command_line(&cmd), "ffmpeg -nostdin -i movie.mp4");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_spawned_process_is_tracked_until_reaped() {
+    let limits = ResourceLimits::default();
+    let mut cmd = Command::new("sleep");
+    cmd.arg("30");
+    let child = limits.spawn(&mut cmd).unwrap();
+    let pid = child.id();
+
+    let tracked = supervisor::list(&supervisor::process_dir()).unwrap();
+    let process = tracked.iter().find(|p| p.pid == pid).expect("spawned process is listed");
+    assert_eq!(process.parent, std::process::id());
+    assert_eq!(process.command, "sleep 30");
+    #[cfg(target_os = "linux")]
+    assert_eq!(process.start_time, supervisor::start_time(pid));
+    assert!(!process.is_orphaned());
+
+    supervisor::kill(process, true).unwrap();
+    assert!(!limits.wait(child).unwrap().status.success());
+    assert!(!supervisor::process_dir().join(format!("{}.json", pid)).exists());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_process_dies_with_its_spawning_thread() {
+    use std::os::unix::process::ExitStatusExt;
+
+    // The death signal follows the thread that spawned the child
+    let mut child = std::thread::spawn(|| {
+        let mut cmd = Command::new("sleep");
+        cmd.arg("30");
+        ResourceLimits::default().spawn(&mut cmd).unwrap()
+    })
+    .join()
+    .unwrap();
+
+    let status = child.wait().unwrap();
+    assert_eq!(status.signal(), Some(9));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_registry_is_private_to_the_user() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = TempDir::new().unwrap();
+    let shared = dir.path().join("shared");
+    fs::create_dir(&shared).unwrap();
+    fs::set_permissions(&shared, fs::Permissions::from_mode(0o777)).unwrap();
+    assert!(supervisor::list(&shared).is_err());
+
+    let limits = ResourceLimits::default();
+    let child = limits.spawn(Command::new("true").arg("registry")).unwrap();
+    let mode = fs::metadata(supervisor::process_dir()).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o700);
+    limits.wait(child).unwrap();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_async_child_outlives_the_thread_that_polled_its_spawn() {
+    use std::os::unix::process::ExitStatusExt;
+
+    let runtime = tokio::runtime::Builder::new_multi_thread().worker_threads(2).enable_all().build().unwrap();
+    // Poll the spawn on a thread that exits right after, as tokio's threads may
+    let handle = runtime.handle().clone();
+    let mut child = std::thread::spawn(move || {
+        handle.block_on(async {
+            let mut cmd = Command::new("sleep");
+            cmd.arg("1");
+            ResourceLimits::default().spawn_async(cmd).await.unwrap()
+        })
+    })
+    .join()
+    .unwrap();
+    let pid = child.id().unwrap();
+
+    let status = runtime.block_on(child.wait()).unwrap();
+    supervisor::unregister(pid);
+    assert_eq!(status.signal(), None);
+    assert!(status.success());
+}
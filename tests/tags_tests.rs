@@ -0,0 +1,91 @@
+mod common;
+
+use audio_extractor::native::{self, Backend};
+use audio_extractor::tags::{self, Tags};
+use audio_extractor::{Args, AudioExtractor, AudioFormat};
+use clap::Parser;
+use std::fs::File;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+use tempfile::TempDir;
+
+fn episode() -> Tags {
+    Tags {
+        title: Some("Episode 12: Sourdough".into()),
+        artist: Some("Kitchen Talk".into()),
+        track: Some("12".into()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_parse_track() {
+    assert_eq!(tags::parse_track("3").unwrap(), "3");
+    assert_eq!(tags::parse_track("3 / 12").unwrap(), "3/12");
+    assert!(tags::parse_track("0").is_err());
+    assert!(tags::parse_track("13/12").is_err());
+    assert!(tags::parse_track("three").is_err());
+}
+
+#[test]
+fn test_set_tags_replace_source_tags() {
+    let source = vec![("title", "raw_0042".to_string()), ("date", "2023-05-14".to_string())];
+    assert_eq!(
+        episode().apply(source),
+        vec![
+            ("title", "Episode 12: Sourdough".to_string()),
+            ("date", "2023-05-14".to_string()),
+            ("artist", "Kitchen Talk".to_string()),
+            ("track", "12".to_string()),
+        ]
+    );
+    assert!(Tags::default().is_empty());
+}
+
+#[test]
+fn test_ffmpeg_args() {
+    let args = episode().ffmpeg_args(&AudioFormat::Mp3);
+    assert_eq!(args, [
+        "-metadata", "title=Episode 12: Sourdough",
+        "-metadata", "artist=Kitchen Talk",
+        "-metadata", "track=12",
+    ]);
+    // AIFF's AUTH chunk is filled from `author`
+    assert!(episode().ffmpeg_args(&AudioFormat::Aiff).contains(&"author=Kitchen Talk".to_string()));
+}
+
+#[test]
+fn test_tag_flags() {
+    let args = Args::try_parse_from([
+        "audio_extractor", "-i", "in.mp4", "-o", "out.mp3",
+        "--tag-title", "Episode 12: Sourdough", "--artist", "Kitchen Talk", "--track", "12",
+    ]).unwrap();
+    assert_eq!(AudioExtractor::new(args).tags(), episode());
+    assert!(Args::try_parse_from(["audio_extractor", "-i", "in.mp4", "-o", "out.mp3", "--track", "x"]).is_err());
+}
+
+#[test]
+fn test_native_extraction_writes_tags() {
+    let input = common::create_test_video_file();
+    let dir = TempDir::new().unwrap();
+    let output = dir.path().join("episode.flac");
+
+    let args = Args {
+        input: input.path().to_path_buf(),
+        output: output.clone(),
+        format: Some(AudioFormat::Flac),
+        ..Default::default()
+    };
+    AudioExtractor::new(args).with_backend(Backend::Native).with_tags(episode()).extract().unwrap();
+
+    let mss = MediaSourceStream::new(Box::new(File::open(&output).unwrap()), Default::default());
+    let mut hint = Hint::new();
+    hint.with_extension("flac");
+    let mut probed = symphonia::default::get_probe()
+        .format(&hint, mss, &FormatOptions::default(), &MetadataOptions::default())
+        .unwrap();
+    let written = native::standard_tags(probed.format.metadata().current());
+    assert_eq!(written, episode().named());
+}
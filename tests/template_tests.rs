@@ -0,0 +1,139 @@
+use audio_extractor::template;
+use audio_extractor::SourceMetadata;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+fn episode() -> SourceMetadata {
+    SourceMetadata {
+        title: Some("Pilot: Part 1/2".into()),
+        show: Some("Kitchen Talk".into()),
+        season: Some(2),
+        episode: Some(3),
+        recorded_date: Some("2023-05-14".into()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_expand_metadata_path() {
+    let fields = template::metadata_fields(&episode(), Path::new("/in/raw_0042.mp4"));
+    let output = template::expand_path(
+        Path::new("/library/{show}/Season {season}/{recorded_date} - {title}.mp3"),
+        &fields,
+    )
+    .unwrap();
+    // Path separators in values never create directories
+    assert_eq!(output, PathBuf::from("/library/Kitchen Talk/Season 02/2023-05-14 - Pilot_ Part 1_2.mp3"));
+}
+
+#[test]
+fn test_missing_fields() {
+    let fields = template::metadata_fields(&SourceMetadata::default(), Path::new("/in/raw_0042.mp4"));
+    assert_eq!(fields["title"], "raw_0042");
+    assert_eq!(fields["artist"], template::UNKNOWN);
+    assert_eq!(fields["year"], template::UNKNOWN);
+    assert_eq!(fields["stem"], "raw_0042");
+}
+
+#[test]
+fn test_file_fields() {
+    let fields = template::file_fields(Path::new("/shows/Season 1/e01.mkv"), "flac", 320);
+    assert_eq!(fields["stem"], "e01");
+    assert_eq!(fields["parent"], "Season 1");
+    assert_eq!(fields["format"], "flac");
+    assert_eq!(fields["bitrate"], "320");
+    assert_eq!(fields["date"], chrono::Local::now().format("%Y-%m-%d").to_string());
+    
+    let output = template::expand_path(Path::new("/out/{parent}/{stem} ({bitrate}k).{format}"), &fields).unwrap();
+    assert_eq!(output, PathBuf::from("/out/Season 1/e01 (320k).flac"));
+    
+    // A bare file name's parent is the working directory
+    let cwd = std::env::current_dir().unwrap();
+    let fields = template::file_fields(Path::new("e01.mkv"), "mp3", 128);
+    assert_eq!(fields["parent"], cwd.file_name().unwrap().to_string_lossy());
+}
+
+#[test]
+fn test_field_names() {
+    assert_eq!(template::field_names("{parent}/{stem}.{format}"), vec!["parent", "stem", "format"]);
+    assert_eq!(template::field_names("{show} - {title"), vec!["show"]);
+    assert!(template::field_names("plain.mp3").is_empty());
+}
+
+#[test]
+fn test_expand_is_single_pass_and_strict() {
+    let fields = BTreeMap::from([("name", "{title}".to_string()), ("title", "01".to_string())]);
+    assert_eq!(template::expand("{name}_t{title}", &fields).unwrap(), "{title}_t01");
+    
+    let err = template::expand("{artst}.mp3", &fields).unwrap_err();
+    assert!(err.to_string().contains("{artst}"));
+}
+
+#[test]
+fn test_template_detection() {
+    assert!(template::has_fields(Path::new("/library/{show}/{title}.mp3")));
+    assert!(!template::has_fields(Path::new("/library/show/title.mp3")));
+    assert_eq!(template::static_prefix(Path::new("/library/{show}/{title}.mp3")), PathBuf::from("/library"));
+}
+
+#[test]
+fn test_sanitize_names() {
+    use template::NameProfile;
+    
+    assert_eq!(template::sanitize("Live at CON", NameProfile::Windows), "Live at CON");
+    assert_eq!(template::sanitize("con", NameProfile::Windows), "con_");
+    assert_eq!(template::sanitize("Aux.Tracks", NameProfile::Windows), "Aux_.Tracks");
+    assert_eq!(template::sanitize("Wait... ", NameProfile::Windows), "Wait");
+    assert_eq!(template::sanitize("Party 🎉🎉 Mix", NameProfile::Windows), "Party 🎉🎉 Mix");
+    
+    assert_eq!(template::sanitize("Party 🎉🎉 Mix", NameProfile::Fat32), "Party  Mix");
+    assert_eq!(template::sanitize("👨‍👩‍👧 Family", NameProfile::Fat32), "Family");
+    assert_eq!(template::sanitize("坂本 – Café", NameProfile::Fat32), "坂本 – Café");
+    
+    assert_eq!(template::sanitize("Café Déjà Vu: Ñandú", NameProfile::Ascii), "Cafe Deja Vu_ Nandu");
+    assert_eq!(template::sanitize("坂本龍一", NameProfile::Ascii), "_");
+    assert_eq!(template::sanitize("🎉", NameProfile::Fat32), "_");
+}
+
+#[test]
+fn test_sanitize_fields_and_paths() {
+    let mut fields = template::metadata_fields(&episode(), Path::new("/in/raw_0042.mp4"));
+    fields.insert("show", "Kitchen Talk ☕".to_string());
+    template::sanitize_fields(&mut fields, template::NameProfile::Fat32);
+    assert_eq!(fields["show"], "Kitchen Talk");
+    
+    assert_eq!(
+        template::sanitize_path(Path::new("Season 1/Pilot?.mkv"), template::NameProfile::Windows),
+        PathBuf::from("Season 1/Pilot_.mkv")
+    );
+}
+
+#[test]
+fn test_fit_long_paths() {
+    let prefix = Path::new("/library");
+    let short = Path::new("/library/Show/Season 01/Pilot.mp3");
+    assert_eq!(template::fit_path(short, prefix, 4095).unwrap(), None);
+    
+    // A name over the filesystem limit is cut, keeping its extension
+    let title = "a".repeat(300);
+    let long = Path::new("/library/Show").join(format!("{}.mp3", title));
+    let fitted = template::fit_path(&long, prefix, 4095).unwrap().unwrap();
+    let name = fitted.file_name().unwrap().to_str().unwrap();
+    assert_eq!(name.len(), template::MAX_NAME_LEN);
+    assert!(name.starts_with("aaaa") && name.ends_with(".mp3"));
+    assert!(name.contains('~'));
+    assert_eq!(fitted.parent(), Some(Path::new("/library/Show")));
+    
+    // Names that only differ at the end stay apart
+    let other = Path::new("/library/Show").join(format!("{}b.mp3", title));
+    assert_ne!(template::fit_path(&other, prefix, 4095).unwrap().unwrap(), fitted);
+    
+    // The longest templated names are cut until the whole path fits
+    let deep = Path::new("/library").join("s".repeat(100)).join("e".repeat(80)).join("t.mp3");
+    let fitted = template::fit_path(&deep, prefix, 150).unwrap().unwrap();
+    assert!(fitted.as_os_str().len() <= 150);
+    assert!(fitted.starts_with("/library"));
+    assert!(fitted.ends_with("t.mp3"));
+    
+    assert!(template::fit_path(&deep, prefix, 40).is_err());
+}
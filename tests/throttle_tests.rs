@@ -0,0 +1,64 @@
+use audio_extractor::limits::ResourceLimits;
+use audio_extractor::throttle::{self, TimeWindow};
+use audio_extractor::Config;
+use chrono::NaiveTime;
+
+fn at(time: &str) -> NaiveTime {
+    NaiveTime::parse_from_str(time, "%H:%M").unwrap()
+}
+
+#[test]
+fn test_time_window_parses_and_wraps_midnight() {
+    let day: TimeWindow = "09:00-18:00".parse().unwrap();
+    assert!(day.contains(at("09:00")));
+    assert!(day.contains(at("17:59")));
+    assert!(!day.contains(at("18:00")));
+    assert!(!day.contains(at("03:00")));
+    assert_eq!(day.to_string(), "09:00-18:00");
+    
+    let night: TimeWindow = "22:00 - 06:30".parse().unwrap();
+    assert!(night.contains(at("23:15")));
+    assert!(night.contains(at("06:29")));
+    assert!(!night.contains(at("12:00")));
+    
+    assert!("09:00".parse::<TimeWindow>().is_err());
+    assert!("9am-5pm".parse::<TimeWindow>().is_err());
+}
+
+#[test]
+fn test_throttles_from_config() {
+    let config: Config = toml::from_str(r#"
+        [[throttle]]
+        between = "09:00-18:00"
+        jobs = 2
+        nice = 19
+        
+        [[throttle]]
+        between = "08:00-20:00"
+        jobs = 4
+    "#).unwrap();
+    let throttles = &config.throttle;
+    
+    // First match wins
+    assert_eq!(throttle::active(throttles, at("10:00")).unwrap().nice, Some(19));
+    assert_eq!(throttle::active(throttles, at("19:00")).unwrap().jobs, Some(4));
+    assert!(throttle::active(throttles, at("23:00")).is_none());
+    
+    assert_eq!(throttle::jobs_at(throttles, at("10:00"), 8), 2);
+    assert_eq!(throttle::jobs_at(throttles, at("10:00"), 1), 1);
+    assert_eq!(throttle::jobs_at(throttles, at("19:30"), 8), 4);
+    assert_eq!(throttle::jobs_at(throttles, at("23:00"), 8), 8);
+    
+    assert!(toml::from_str::<Config>("[[throttle]]\nbetween = \"all day\"").is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_resource_limits_nice() {
+    let limits = ResourceLimits { nice: Some(10), ..Default::default() };
+    let mut cmd = std::process::Command::new("sh");
+    cmd.arg("-c").arg("nice");
+    let output = limits.output(&mut cmd).unwrap();
+    let niceness: i32 = String::from_utf8_lossy(&output.stdout).trim().parse().unwrap();
+    assert!(niceness >= 10);
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use audio_extractor::native::Backend;
+use audio_extractor::timeline::{edl_timecode, OutputRange, Span, Timeline};
+use audio_extractor::{Args, AudioExtractor, AudioFormat, EffectChain};
+use tempfile::TempDir;
+
+#[test]
+fn test_cut_and_keep_source() {
+    let mut timeline = Timeline::new("talk.mp4", 600.0);
+    timeline.trim(60.0, Some(300.0));
+    assert_eq!(timeline.spans, [Span { output_start: 0.0, source_start: 60.0, duration: 240.0 }]);
+
+    timeline.keep_source(&[(100.0, 130.0), (0.0, 70.0), (250.0, 400.0)]);
+    assert_eq!(timeline.spans, [
+        Span { output_start: 0.0, source_start: 60.0, duration: 10.0 },
+        Span { output_start: 10.0, source_start: 100.0, duration: 30.0 },
+        Span { output_start: 40.0, source_start: 250.0, duration: 50.0 },
+    ]);
+    assert_eq!(timeline.duration(), 90.0);
+    assert_eq!(timeline.to_output(110.0), Some(20.0));
+    assert_eq!(timeline.to_output(200.0), None);
+    assert_eq!(timeline.to_source(45.0), Some(255.0));
+}
+
+#[test]
+fn test_trim_after_keep_source() {
+    let mut timeline = Timeline::new("talk.mp4", 100.0);
+    timeline.keep_source(&[(10.0, 20.0), (50.0, 70.0)]);
+    timeline.trim(5.0, None);
+    assert_eq!(timeline.spans, [
+        Span { output_start: 0.0, source_start: 15.0, duration: 5.0 },
+        Span { output_start: 5.0, source_start: 50.0, duration: 20.0 },
+    ]);
+}
+
+#[test]
+fn test_mutes_follow_the_cuts() {
+    let mut timeline = Timeline::new("talk.mp4", 100.0);
+    timeline.keep_source(&[(10.0, 20.0), (50.0, 70.0)]);
+    timeline.mute_source(15.0, 55.0);
+    assert_eq!(timeline.muted, [OutputRange { start: 5.0, end: 10.0 }, OutputRange { start: 10.0, end: 15.0 }]);
+}
+
+#[test]
+fn test_edl() {
+    let mut timeline = Timeline::new("/videos/talk.mp4", 100.0);
+    timeline.keep_source(&[(10.0, 20.48)]);
+    let edl = timeline.to_edl("talk", 25.0);
+    assert_eq!(edl, "TITLE: talk\nFCM: NON-DROP FRAME\n\n001  AX       A     C        00:00:10:00 00:00:20:12 00:00:00:00 00:00:10:12\n* FROM CLIP NAME: talk.mp4\n");
+}
+
+#[test]
+fn test_edl_timecode() {
+    assert_eq!(edl_timecode(3723.48, 25.0), "01:02:03:12");
+    // 29.97 fps counts 30 frames per timecode second
+    assert_eq!(edl_timecode(60.0, 29.97), "00:00:59:28");
+}
+
+#[test]
+fn test_extraction_writes_timeline() {
+    let dir = TempDir::new().unwrap();
+    let input = dir.path().join("tone.mp4");
+    common::write_test_tone(&input);
+    let output = dir.path().join("out.wav");
+
+    let args = Args {
+        input,
+        output: output.clone(),
+        format: Some(AudioFormat::Wav),
+        timeline: true,
+        edl: true,
+        ..Default::default()
+    };
+    let extractor = AudioExtractor::new(args)
+        .with_backend(Backend::Native)
+        .with_effects(EffectChain::new().trim(0.25, Some(0.75)));
+    extractor.extract().unwrap();
+
+    let timeline = Timeline::load(&Timeline::sidecar_path(&output)).unwrap();
+    assert_eq!(timeline.spans, [Span { output_start: 0.0, source_start: 0.25, duration: 0.5 }]);
+    let edl = std::fs::read_to_string(Timeline::edl_path(&output)).unwrap();
+    assert!(edl.contains("00:00:00:06 00:00:00:19 00:00:00:00 00:00:00:13"));
+}
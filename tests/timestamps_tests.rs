@@ -0,0 +1,54 @@
+use audio_extractor::{timestamps, Args, AudioExtractor, TimeRange, TimestampRepair};
+
+#[test]
+fn test_timestamp_repair_disabled_by_default() {
+    let repair = TimestampRepair::default();
+    assert!(repair.is_empty());
+    assert!(repair.input_args().is_empty());
+    assert_eq!(repair.filter(), None);
+}
+
+#[test]
+fn test_fix_timestamps_regenerates_pts_and_resamples_first() {
+    let args = Args {
+        fix_timestamps: true,
+        mute: vec![TimeRange::new(10.0, 20.0).unwrap()],
+        ..Default::default()
+    };
+    let extractor = AudioExtractor::new(args);
+    
+    let repair = extractor.timestamp_repair();
+    assert_eq!(repair.input_args(), vec!["-fflags", "+genpts+igndts"]);
+    
+    let graph = extractor.audio_graph().unwrap();
+    assert_eq!(graph.filters.len(), 2);
+    assert_eq!(graph.filters[0], "aresample=async=1000:first_pts=0");
+    assert!(graph.filters[1].starts_with("volume=volume=0"));
+}
+
+#[test]
+fn test_fill_gaps_filter() {
+    let fill = TimestampRepair { fill_gaps: Some(0.25), ..Default::default() };
+    assert!(fill.input_args().is_empty());
+    assert_eq!(fill.filter().unwrap(), "aresample=async=1:min_hard_comp=0.250:first_pts=0");
+    
+    let both = TimestampRepair { fix_timestamps: true, fill_gaps: Some(0.1) };
+    assert_eq!(both.filter().unwrap(), "aresample=async=1000:min_hard_comp=0.100:first_pts=0");
+}
+
+#[test]
+fn test_find_gaps_in_packet_listing() {
+    let packets = "0.000000,0.021333\n\
+                   0.021333,0.021333\n\
+                   2.500000,0.021333\n\
+                   2.521333,0.021333\n\
+                   2.560000,0.021333\n\
+                   N/A,0.021333\n";
+    
+    let gaps = timestamps::find_gaps(packets, 0.1);
+    assert_eq!(gaps.len(), 1);
+    assert!((gaps[0].start - 0.042666).abs() < 1e-6);
+    assert_eq!(gaps[0].end, 2.5);
+    
+    assert!(timestamps::find_gaps(packets, 5.0).is_empty());
+}
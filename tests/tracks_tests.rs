@@ -0,0 +1,55 @@
+use audio_extractor::AudioTrack;
+
+const PROBE: &str = r#"{
+    "streams": [
+        { "index": 0, "codec_type": "video", "codec_name": "h264" },
+        { "index": 1, "codec_type": "audio", "codec_name": "ac3", "channels": 6,
+          "disposition": { "default": 1 }, "tags": { "language": "eng" } },
+        { "index": 2, "codec_type": "subtitle", "codec_name": "subrip" },
+        { "index": 3, "codec_type": "audio", "codec_name": "aac", "channels": 2,
+          "disposition": { "default": 0 }, "tags": { "language": "eng", "title": "Director's commentary" } },
+        { "index": 4, "codec_type": "audio", "codec_name": "opus", "tags": { "language": "und" } }
+    ]
+}"#;
+
+#[test]
+fn test_audio_tracks_from_ffprobe() {
+    let tracks = AudioTrack::from_ffprobe_json(PROBE).unwrap();
+    assert_eq!(tracks.len(), 3);
+    assert_eq!(tracks[0], AudioTrack {
+        index: 0,
+        stream_index: 1,
+        codec: "ac3".to_string(),
+        language: Some("eng".to_string()),
+        title: None,
+        channels: Some(6),
+        default: true,
+    });
+    assert_eq!(tracks[1].index, 1);
+    assert_eq!(tracks[1].stream_index, 3);
+    assert_eq!(tracks[1].title.as_deref(), Some("Director's commentary"));
+    // "und" means the language is not known
+    assert_eq!(tracks[2].language, None);
+    
+    assert_eq!(tracks[0].to_string(), "0: ac3, 6 ch, eng (default)");
+    assert_eq!(tracks[1].to_string(), "1: aac, 2 ch, eng, \"Director's commentary\"");
+    assert_eq!(tracks[2].to_string(), "2: opus, unknown language");
+}
+
+#[test]
+fn test_no_audio_tracks() {
+    assert!(AudioTrack::from_ffprobe_json(r#"{"streams": []}"#).unwrap().is_empty());
+    assert!(AudioTrack::from_ffprobe_json("{}").unwrap().is_empty());
+    assert!(AudioTrack::from_ffprobe_json("not json").is_err());
+}
+
+#[test]
+fn test_track_output_names() {
+    use audio_extractor::tracks;
+    use std::path::{Path, PathBuf};
+    
+    let tracks = AudioTrack::from_ffprobe_json(PROBE).unwrap();
+    let output = Path::new("/out/movie.mp3");
+    assert_eq!(tracks::track_output(output, &tracks[1]), PathBuf::from("/out/movie.track1.eng.mp3"));
+    assert_eq!(tracks::track_output(output, &tracks[2]), PathBuf::from("/out/movie.track2.mp3"));
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use audio_extractor::trash::{self, Disposal};
+use audio_extractor::{Args, AudioExtractor, AudioFormat};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// The file in the only timestamped folder of a quarantine directory
+fn quarantined(dir: &Path, name: &str) -> std::path::PathBuf {
+    let folder = fs::read_dir(dir).unwrap().next().unwrap().unwrap().path();
+    folder.join(name)
+}
+
+#[test]
+fn test_quarantine_keeps_displaced_files_apart() {
+    let dir = TempDir::new().unwrap();
+    let quarantine = dir.path().join("quarantine");
+    let disposal = Disposal::from_flags(true, Some(&quarantine));
+    assert_eq!(disposal, Disposal::Quarantine(quarantine.clone()));
+    
+    let mut moved = Vec::new();
+    for content in ["first", "second"] {
+        let file = dir.path().join("song.mp3");
+        fs::write(&file, content).unwrap();
+        moved.push(disposal.dispose(&file).unwrap().unwrap());
+        assert!(!file.exists());
+    }
+    assert!(moved.iter().all(|path| path.starts_with(&quarantine)));
+    assert_eq!(fs::read_to_string(&moved[0]).unwrap(), "first");
+    assert_eq!(fs::read_to_string(&moved[1]).unwrap(), "second");
+}
+
+#[test]
+fn test_delete_removes_the_file() {
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join("song.mp3");
+    fs::write(&file, "x").unwrap();
+    assert_eq!(Disposal::from_flags(false, None).dispose(&file).unwrap(), None);
+    assert!(!file.exists());
+}
+
+#[test]
+fn test_trash_info() {
+    let info = trash::trash_info(Path::new("/archive/My Songs/a&b.mp3"));
+    assert!(info.starts_with("[Trash Info]\nPath=/archive/My%20Songs/a%26b.mp3\nDeletionDate="));
+}
+
+#[test]
+fn test_existing_output_is_quarantined_before_extracting() {
+    let input = common::create_test_video_file();
+    let dir = TempDir::new().unwrap();
+    let output = dir.path().join("out.wav");
+    fs::write(&output, "irreplaceable").unwrap();
+    let quarantine = dir.path().join("quarantine");
+    
+    let args = Args {
+        input: input.path().to_path_buf(),
+        output: output.clone(),
+        format: Some(AudioFormat::Wav),
+        quarantine: Some(quarantine.clone()),
+        ..Default::default()
+    };
+    AudioExtractor::new(args).extract().unwrap();
+    assert!(output.exists());
+    assert_eq!(fs::read_to_string(quarantined(&quarantine, "out.wav")).unwrap(), "irreplaceable");
+}
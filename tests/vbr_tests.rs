@@ -0,0 +1,44 @@
+use audio_extractor::lame::Encoding;
+use audio_extractor::vbr;
+use audio_extractor::{Args, AudioExtractor, AudioFormat};
+use clap::Parser;
+
+fn extractor(output: &str, extra: &[&str]) -> AudioExtractor {
+    let args = Args::try_parse_from(["audio_extractor", "-i", "in.mp4", "-o", output].iter().chain(extra)).unwrap();
+    AudioExtractor::new(args)
+}
+
+#[test]
+fn test_levels_per_codec() {
+    assert_eq!(vbr::level(&AudioFormat::Mp3, Some(0.0)).unwrap(), 0.0);
+    assert!(vbr::level(&AudioFormat::Mp3, Some(10.0)).is_err());
+    assert!(vbr::level(&AudioFormat::Mp3, Some(2.5)).unwrap_err().to_string().contains("whole number"));
+    assert_eq!(vbr::level(&AudioFormat::Aac, Some(1.5)).unwrap(), 1.5);
+    assert!(vbr::level(&AudioFormat::Aac, Some(3.0)).is_err());
+    assert_eq!(vbr::level(&AudioFormat::Ogg, Some(-1.0)).unwrap(), -1.0);
+    assert!(vbr::level(&AudioFormat::Flac, None).unwrap_err().to_string().contains("not FLAC"));
+}
+
+#[test]
+fn test_default_levels() {
+    assert_eq!(vbr::level(&AudioFormat::Mp3, None).unwrap(), 2.0);
+    assert_eq!(vbr::level(&AudioFormat::Opus, None).unwrap(), 5.0);
+}
+
+#[test]
+fn test_encoder_options() {
+    assert_eq!(vbr::ffmpeg_args(&AudioFormat::Opus, 5.0), ["-vbr", "on", "-b:a", "96k"]);
+    assert_eq!(vbr::ffmpeg_args(&AudioFormat::Ogg, 6.0), ["-q:a", "6"]);
+    assert_eq!(vbr::ffmpeg_args(&AudioFormat::Aac, 1.2), ["-q:a", "1.2"]);
+}
+
+#[test]
+fn test_vbr_argument() {
+    assert_eq!(extractor("out.mp3", &["-f", "mp3", "--vbr"]).mp3_encoding().unwrap(), Encoding::Vbr(2));
+    assert_eq!(extractor("out.mp3", &["-f", "mp3", "--vbr", "0"]).mp3_encoding().unwrap(), Encoding::Vbr(0));
+    assert_eq!(extractor("out.ogg", &["-f", "ogg", "--vbr", "-1"]).vbr_level().unwrap(), Some(-1.0));
+    assert!(extractor("out.wav", &["-f", "wav", "--vbr", "3"]).vbr_level().is_err());
+    
+    // A VBR level replaces the bitrate
+    assert!(Args::try_parse_from(["audio_extractor", "-i", "in.mp4", "-o", "out.mp3", "--vbr", "2", "-q", "192"]).is_err());
+}
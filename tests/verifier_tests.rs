@@ -0,0 +1,96 @@
+mod common;
+
+use audio_extractor::{verifier, Args, AudioExtractor, AudioFormat, ProgressEvent, Verifier};
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_verifier_reports_outcomes_by_index() {
+    let dir = TempDir::new().unwrap();
+    let present = dir.path().join("present.mp3");
+    fs::write(&present, b"audio").unwrap();
+    let empty = dir.path().join("empty.mp3");
+    fs::write(&empty, b"").unwrap();
+    
+    let verifier = Verifier::new(2);
+    for (index, output) in [present, dir.path().join("missing.mp3"), empty].into_iter().enumerate() {
+        verifier.submit(index, AudioExtractor::new(Args { output, ..Default::default() }));
+    }
+    let outcomes = verifier.finish();
+    
+    assert_eq!(outcomes.iter().map(|(index, _)| *index).collect::<Vec<_>>(), vec![0, 1, 2]);
+    assert!(outcomes[0].1.is_ok());
+    assert!(outcomes[1].1.as_ref().unwrap_err().to_string().contains("does not exist"));
+    assert!(outcomes[2].1.as_ref().unwrap_err().to_string().contains("empty"));
+}
+
+#[test]
+fn test_batch_verifies_every_output() {
+    let dir = TempDir::new().unwrap();
+    let inputs: Vec<_> = (0..3).map(|_| common::create_test_video_file()).collect();
+    let paths: Vec<_> = inputs.iter().map(|f| f.path().to_path_buf()).collect();
+    
+    let results = AudioExtractor::extract_batch(paths, dir.path().to_path_buf(), AudioFormat::Wav, 128, true).unwrap();
+    assert_eq!(results.len(), 3);
+    for result in results {
+        assert!(result.unwrap().exists());
+    }
+}
+
+#[test]
+fn test_parallel_batch_keeps_input_order_and_reports_progress() {
+    let dir = TempDir::new().unwrap();
+    let inputs: Vec<_> = (0..4).map(|_| common::create_test_video_file()).collect();
+    let mut paths: Vec<_> = inputs.iter().map(|f| f.path().to_path_buf()).collect();
+    paths.insert(2, dir.path().join("missing.mp4"));
+    
+    let finished = std::sync::Mutex::new(Vec::new());
+    let results = AudioExtractor::extract_batch_with_progress(
+        paths.clone(), dir.path().join("out"), AudioFormat::Wav, 128, true, 3,
+        |index, event| {
+            if *event == ProgressEvent::Finished {
+                finished.lock().unwrap().push(index);
+            }
+        },
+    ).unwrap();
+    
+    assert_eq!(results.len(), 5);
+    assert!(results[2].is_err());
+    for (index, result) in results.iter().enumerate().filter(|(index, _)| *index != 2) {
+        let output = result.as_ref().unwrap();
+        assert_eq!(output.file_stem(), paths[index].file_stem());
+        assert!(output.exists());
+    }
+    let mut finished = finished.into_inner().unwrap();
+    finished.sort();
+    assert_eq!(finished, vec![0, 1, 3, 4]);
+}
+
+#[test]
+fn test_verify_dir() {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir_all(dir.path().join("2019/march")).unwrap();
+    fs::write(dir.path().join("2019/march/talk.mp3"), b"not really audio").unwrap();
+    fs::write(dir.path().join("2019/empty.FLAC"), b"").unwrap();
+    fs::write(dir.path().join("notes.txt"), b"ignored").unwrap();
+    
+    let checked = std::sync::Mutex::new(Vec::new());
+    let verification = verifier::verify_dir(dir.path(), 2, |_, done, total| {
+        checked.lock().unwrap().push((done, total));
+    }).unwrap();
+    
+    assert_eq!(verification.files.len(), 2);
+    assert_eq!(verification.failed(), 2);
+    assert_eq!(verification.files[0].path, dir.path().join("2019/empty.FLAC"));
+    assert_eq!(verification.files[1].size, Some(16));
+    let mut checked = checked.into_inner().unwrap();
+    checked.sort();
+    assert_eq!(checked, vec![(1, 2), (2, 2)]);
+    
+    let report = dir.path().join("out/verify.json");
+    verification.write(&report).unwrap();
+    let loaded: verifier::DirVerification = serde_json::from_str(&fs::read_to_string(report).unwrap()).unwrap();
+    assert_eq!(loaded, verification);
+    
+    assert!(verifier::verify_dir(&dir.path().join("notes.txt"), 1, |_, _, _| {}).is_err());
+}
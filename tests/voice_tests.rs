@@ -0,0 +1,43 @@
+use audio_extractor::{preflight, voice, AudioFormat};
+use clap::ValueEnum;
+
+#[test]
+fn test_amr_snaps_to_its_modes() {
+    assert_eq!(voice::bitrate(&AudioFormat::AmrNb, 128), Some(12_200));
+    assert_eq!(voice::bitrate(&AudioFormat::AmrNb, 8), Some(7_950));
+    // Below the lowest mode, the lowest is used
+    assert_eq!(voice::bitrate(&AudioFormat::AmrNb, 4), Some(4_750));
+    assert_eq!(voice::bitrate(&AudioFormat::AmrWb, 16), Some(15_850));
+    assert_eq!(voice::bitrate(&AudioFormat::AmrWb, 320), Some(23_850));
+    assert_eq!(voice::bitrate(&AudioFormat::Speex, 24), Some(24_000));
+    assert_eq!(voice::bitrate(&AudioFormat::Speex, 128), Some(voice::SPEEX_MAX_RATE));
+    assert_eq!(voice::bitrate(&AudioFormat::Mp3, 128), None);
+}
+
+#[test]
+fn test_sample_rates() {
+    assert_eq!(voice::sample_rate(&AudioFormat::AmrNb), Some(8_000));
+    assert_eq!(voice::sample_rate(&AudioFormat::AmrWb), Some(16_000));
+    assert_eq!(voice::sample_rate(&AudioFormat::Speex), Some(16_000));
+    assert_eq!(voice::sample_rate(&AudioFormat::Flac), None);
+}
+
+#[test]
+fn test_format_names() {
+    for (name, format, extension) in [
+        ("amr-nb", AudioFormat::AmrNb, "amr"),
+        ("amr-wb", AudioFormat::AmrWb, "awb"),
+        ("speex", AudioFormat::Speex, "spx"),
+    ] {
+        assert_eq!(AudioFormat::from_str(name, true).unwrap(), format);
+        assert_eq!(AudioFormat::from_str(extension, true).unwrap(), format);
+        assert_eq!(format.to_string(), extension);
+        assert!(format.is_lossy());
+    }
+}
+
+#[test]
+fn test_size_estimate_uses_the_mode() {
+    // An hour of AMR-NB at 12.2 kbps, whatever --quality says
+    assert_eq!(preflight::estimate_size(&AudioFormat::AmrNb, 128, 3600.0), 5_490_000);
+}
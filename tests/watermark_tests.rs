@@ -0,0 +1,45 @@
+use audio_extractor::{Args, AudioExtractor, Watermark, WatermarkSource};
+use std::path::PathBuf;
+use tempfile::tempdir;
+
+#[test]
+fn test_watermark_defaults_and_validation() {
+    let watermark = Watermark::new(WatermarkSource::Tone(2000.0), None, None).unwrap();
+    assert_eq!(watermark.interval, 30.0);
+    assert_eq!(watermark.gain_db, -24.0);
+    
+    assert!(Watermark::new(WatermarkSource::Tone(2000.0), Some(0.2), None).is_err());
+    assert!(Watermark::new(WatermarkSource::Tone(2000.0), None, Some(3.0)).is_err());
+}
+
+#[test]
+fn test_tone_watermark_overlay() {
+    let watermark = Watermark::new(WatermarkSource::Tone(2000.0), Some(10.0), Some(-30.0)).unwrap();
+    assert_eq!(
+        watermark.overlay(0),
+        "sine=frequency=2000:sample_rate=48000,volume=volume=-30dB,\
+         volume=volume=0:enable='not(lt(mod(t,10),0.15)+between(mod(t,10),0.25,0.4))'"
+    );
+}
+
+#[test]
+fn test_audio_watermark_adds_graph_input() {
+    let temp_dir = tempdir().unwrap();
+    let snippet = temp_dir.path().join("marker.wav");
+    std::fs::write(&snippet, b"RIFF").unwrap();
+    
+    let extractor = AudioExtractor::new(Args {
+        input: PathBuf::from("preview.mp4"),
+        output: temp_dir.path().join("preview.mp3"),
+        watermark_audio: Some(snippet.clone()),
+        watermark_interval: Some(20.0),
+        ..Default::default()
+    });
+    
+    let graph = extractor.audio_graph().unwrap();
+    assert_eq!(graph.inputs, vec![snippet]);
+    assert_eq!(
+        graph.overlays,
+        vec!["[1:a]aresample=48000,apad=whole_dur=20,aloop=loop=-1:size=960000,volume=volume=-24dB"]
+    );
+}
@@ -0,0 +1,52 @@
+use audio_extractor::wavpack;
+use audio_extractor::{Args, AudioExtractor, AudioFormat, BitDepth};
+use clap::{Parser, ValueEnum};
+use std::path::{Path, PathBuf};
+
+fn extractor(extra: &[&str]) -> AudioExtractor {
+    let args = Args::try_parse_from(["audio_extractor", "-i", "in.mp4", "-o", "album.wv"].iter().chain(extra)).unwrap();
+    AudioExtractor::new(args)
+}
+
+#[test]
+fn test_format_names() {
+    assert_eq!(AudioFormat::from_str("wavpack", true).unwrap(), AudioFormat::WavPack);
+    assert_eq!(AudioFormat::from_str("wv", true).unwrap(), AudioFormat::WavPack);
+    // Batch outputs take their extension from the format's name
+    assert_eq!(AudioFormat::WavPack.to_string(), "wv");
+    assert!(!AudioFormat::WavPack.is_lossy());
+}
+
+#[test]
+fn test_hybrid_bitrate() {
+    assert_eq!(extractor(&["-f", "wv"]).hybrid_bitrate().unwrap(), None);
+    assert_eq!(extractor(&["-f", "wv", "--hybrid", "-q", "320"]).hybrid_bitrate().unwrap(), Some(320));
+    assert!(extractor(&["-f", "wv", "--hybrid", "-q", "16"]).hybrid_bitrate().is_err());
+    assert!(extractor(&["-f", "flac", "--hybrid"]).hybrid_bitrate().is_err());
+}
+
+#[test]
+fn test_bit_depth_needs_hybrid() {
+    assert_eq!(extractor(&["-f", "wv", "--hybrid", "--bit-depth", "24"]).bit_depth().unwrap(), BitDepth::TwentyFour);
+    assert!(extractor(&["-f", "wv", "--bit-depth", "24"]).bit_depth().is_err());
+}
+
+#[test]
+fn test_hybrid_command() {
+    let output = Path::new("/archive/album.wv");
+    assert_eq!(wavpack::correction_path(output), PathBuf::from("/archive/album.wvc"));
+
+    let cmd = wavpack::hybrid_command(output, 320);
+    let args: Vec<_> = cmd.get_args().map(|arg| arg.to_string_lossy().into_owned()).collect();
+    assert_eq!(cmd.get_program(), "wavpack");
+    assert!(args.contains(&"-b320".to_string()));
+    assert!(args.contains(&"-c".to_string()));
+    assert!(args.ends_with(&["-".to_string(), "-o".to_string(), "/archive/album.wv".to_string()]));
+}
+
+#[test]
+fn test_hybrid_conflicts_with_encryption() {
+    assert!(Args::try_parse_from([
+        "audio_extractor", "-i", "in.mp4", "-o", "album.wv", "-f", "wv", "--hybrid", "--encrypt-to", "age1abc",
+    ]).is_err());
+}
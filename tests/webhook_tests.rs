@@ -0,0 +1,68 @@
+use audio_extractor::webhook::WebhookKind;
+use audio_extractor::{BatchSummary, Config, Webhook};
+use std::path::Path;
+
+fn webhooks() -> Vec<Webhook> {
+    let config: Config = toml::from_str(
+        r#"
+        [[webhooks]]
+        kind = "slack"
+        url = "https://hooks.slack.com/services/T0/B0/xyz"
+        on_failure = false
+
+        [[webhooks]]
+        kind = "discord"
+        url = "https://discord.com/api/webhooks/1/abc"
+        completion_message = "{succeeded}/{failed} from {source}"
+
+        [[webhooks]]
+        kind = "matrix"
+        url = "https://matrix.example.org/"
+        room = "!ops:example.org"
+        token = "syt_secret"
+        failure_message = "{input}: {error}"
+        "#,
+    )
+    .unwrap();
+    config.webhooks
+}
+
+#[test]
+fn test_webhook_config() {
+    let hooks = webhooks();
+    assert_eq!(hooks.len(), 3);
+    assert_eq!(hooks[0].kind, WebhookKind::Slack);
+    assert!(hooks[0].on_completion && !hooks[0].on_failure);
+    assert!(hooks[1].on_failure);
+    
+    let empty: Config = toml::from_str("").unwrap();
+    assert!(empty.webhooks.is_empty());
+}
+
+#[test]
+fn test_message_templates() {
+    let hooks = webhooks();
+    let summary = BatchSummary::new("scan", Path::new("/archive"), String::new(), 10, vec!["boom".into()]);
+    
+    assert!(hooks[0].completion_text(&summary).unwrap().starts_with("scan of /archive on "));
+    assert_eq!(hooks[1].completion_text(&summary).unwrap(), "10/1 from /archive");
+    assert_eq!(hooks[2].failure_text(Path::new("/a/b.mp4"), "no audio").unwrap(), "/a/b.mp4: no audio");
+}
+
+#[test]
+fn test_webhook_requests() {
+    let hooks = webhooks();
+    
+    let (method, url, body) = hooks[0].request("done").unwrap();
+    assert_eq!((method, url.as_str()), ("POST", "https://hooks.slack.com/services/T0/B0/xyz"));
+    assert_eq!(body, serde_json::json!({ "text": "done" }));
+    
+    let (_, _, body) = hooks[1].request(&"x".repeat(3000)).unwrap();
+    assert_eq!(body["content"].as_str().unwrap().len(), 2000);
+    
+    let (method, url, body) = hooks[2].request("done").unwrap();
+    assert_eq!(method, "PUT");
+    assert!(url.starts_with("https://matrix.example.org/_matrix/client/v3/rooms/%21ops%3Aexample.org/send/m.room.message/"));
+    assert_eq!(body, serde_json::json!({ "msgtype": "m.text", "body": "done" }));
+    assert_ne!(hooks[2].request("again").unwrap().1, url);
+}